@@ -0,0 +1,62 @@
+// compile した式の繰り返し評価（1点ずつの eval と配列の eval_array）と、毎回解析する evaluate_with の比較
+// 実行: cargo bench --bench compile --no-default-features
+use calc_mcp::calculator::Calculator;
+use std::collections::HashMap;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const ITERATIONS: u32 = 100_000;
+const EXPRESSION: &str = "sqrt(x^2 + 1) * ln(x + 2) - abs(x - 3) / (x + 1) + round(x * 10) / 10";
+
+fn measure(name: &str, mut eval: impl FnMut(f64) -> f64) -> Duration {
+    let start = Instant::now();
+    let mut total = 0.0;
+    for i in 0..ITERATIONS {
+        total += eval(f64::from(i) / 1000.0);
+    }
+    let elapsed = start.elapsed();
+    black_box(total);
+    println!(
+        "{:<14} {:>8.1} ms（1回あたり {:>7.1} ns）",
+        name,
+        elapsed.as_secs_f64() * 1000.0,
+        elapsed.as_secs_f64() * 1e9 / f64::from(ITERATIONS)
+    );
+    elapsed
+}
+
+fn main() {
+    let calculator = Calculator::new();
+    let mut vars = HashMap::from([("x".to_string(), 0.0)]);
+    println!("{}（{} 回）", EXPRESSION, ITERATIONS);
+
+    let interpreted = measure("evaluate_with", |x| {
+        vars.insert("x".to_string(), x);
+        calculator.evaluate_with(EXPRESSION, &vars).unwrap()
+    });
+
+    let compiled_expr = calculator.compile(EXPRESSION).unwrap();
+    let mut vars = HashMap::from([("x".to_string(), 0.0)]);
+    let compiled = measure("compile + eval", |x| {
+        *vars.get_mut("x").unwrap() = x;
+        compiled_expr.eval(&vars).unwrap()
+    });
+
+    let xs: Vec<f64> = (0..ITERATIONS).map(|i| f64::from(i) / 1000.0).collect();
+    let start = Instant::now();
+    let results = compiled_expr.eval_array("x", &xs, &HashMap::new()).unwrap();
+    let array = start.elapsed();
+    black_box(results);
+    println!(
+        "{:<14} {:>8.1} ms（1回あたり {:>7.1} ns）",
+        "eval_array",
+        array.as_secs_f64() * 1000.0,
+        array.as_secs_f64() * 1e9 / f64::from(ITERATIONS)
+    );
+
+    println!(
+        "compile した式は {:.1} 倍、eval_array は {:.1} 倍速い",
+        interpreted.as_secs_f64() / compiled.as_secs_f64(),
+        interpreted.as_secs_f64() / array.as_secs_f64()
+    );
+}
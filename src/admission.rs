@@ -0,0 +1,91 @@
+use crate::config::LimitsConfig;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+// 上限に達して受け付けなかった呼び出しのエラーの接頭辞
+pub const BUSY: &str = "BUSY";
+
+// ツールの呼び出しを同時に max_concurrent 件まで実行し、それを超えた呼び出しは
+// max_queue 件まで queue_timeout だけ空きを待たせる。待ち行列もいっぱいなら待たずに断る
+#[derive(Debug)]
+pub struct Admission {
+    semaphore: Arc<Semaphore>,
+    waiting: AtomicUsize,
+    max_concurrent: usize,
+    max_queue: usize,
+    queue_timeout: Duration,
+}
+
+// 待ち行列の人数を、待ち終えたとき（タイムアウトやキャンセルを含む）に減らす
+struct Waiting<'a>(&'a AtomicUsize);
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl Admission {
+    pub fn new(limits: &LimitsConfig) -> Self {
+        Self {
+            semaphore: Arc::new(Semaphore::new(limits.max_concurrent)),
+            waiting: AtomicUsize::new(0),
+            max_concurrent: limits.max_concurrent,
+            max_queue: limits.max_queue,
+            queue_timeout: Duration::from_millis(limits.queue_timeout_ms),
+        }
+    }
+
+    // 設定の再読み込みで作り直す必要があるか
+    pub fn matches(&self, limits: &LimitsConfig) -> bool {
+        self.max_concurrent == limits.max_concurrent
+            && self.max_queue == limits.max_queue
+            && self.queue_timeout == Duration::from_millis(limits.queue_timeout_ms)
+    }
+
+    // 実行中の呼び出しの数
+    pub fn running(&self) -> usize {
+        self.max_concurrent - self.semaphore.available_permits()
+    }
+
+    // 空きを待っている呼び出しの数
+    pub fn waiting(&self) -> usize {
+        self.waiting.load(Ordering::SeqCst)
+    }
+
+    // 実行してよければ枠を返す（枠を破棄すると次の呼び出しが実行できる）。
+    // max_concurrent が 0 なら制限しない
+    pub async fn admit(&self) -> Result<Option<OwnedSemaphorePermit>, String> {
+        if self.max_concurrent == 0 {
+            return Ok(None);
+        }
+        if let Ok(permit) = self.semaphore.clone().try_acquire_owned() {
+            return Ok(Some(permit));
+        }
+        let queued = self
+            .waiting
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| {
+                (n < self.max_queue).then_some(n + 1)
+            });
+        if queued.is_err() {
+            return Err(format!(
+                "{}: 同時に実行できる計算（{} 件）と待ち行列（{} 件）がいっぱいです。しばらくしてから再試行してください",
+                BUSY, self.max_concurrent, self.max_queue
+            ));
+        }
+        let _waiting = Waiting(&self.waiting);
+        match tokio::time::timeout(self.queue_timeout, self.semaphore.clone().acquire_owned())
+            .await
+        {
+            Ok(permit) => permit.map(Some).map_err(|e| e.to_string()),
+            Err(_) => Err(format!(
+                "{}: {} ミリ秒待っても実行できませんでした（同時に実行できる計算は {} 件）。しばらくしてから再試行してください",
+                BUSY,
+                self.queue_timeout.as_millis(),
+                self.max_concurrent
+            )),
+        }
+    }
+}
@@ -0,0 +1,48 @@
+#[cfg(test)]
+mod tests {
+    use crate::admission::{Admission, BUSY};
+    use crate::config::LimitsConfig;
+
+    fn limits(max_concurrent: usize, max_queue: usize, queue_timeout_ms: u64) -> LimitsConfig {
+        LimitsConfig {
+            max_concurrent,
+            max_queue,
+            queue_timeout_ms,
+            ..LimitsConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_admission_queue_and_timeout() {
+        let admission = Admission::new(&limits(1, 1, 50));
+        let first = admission.admit().await.unwrap();
+        assert!(first.is_some());
+        assert_eq!(admission.running(), 1);
+
+        // 1件目が終わるまで待ち、タイムアウトする
+        let error = admission.admit().await.unwrap_err();
+        assert!(error.starts_with(BUSY), "{}", error);
+        assert_eq!(admission.waiting(), 0);
+
+        // 枠が空けば待っていた呼び出しが実行される
+        let (waiting, _) = tokio::join!(admission.admit(), async {
+            tokio::task::yield_now().await;
+            drop(first);
+        });
+        assert!(waiting.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_admission_rejects_when_queue_is_full() {
+        let admission = Admission::new(&limits(1, 0, 1000));
+        let _running = admission.admit().await.unwrap();
+        let error = admission.admit().await.unwrap_err();
+        assert!(error.contains("待ち行列"), "{}", error);
+
+        // 0 なら制限しない
+        let unlimited = Admission::new(&limits(0, 0, 0));
+        assert!(unlimited.admit().await.unwrap().is_none());
+        assert!(unlimited.matches(&limits(0, 0, 0)));
+        assert!(!unlimited.matches(&limits(4, 0, 0)));
+    }
+}
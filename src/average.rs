@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[cfg_attr(feature = "server", derive(schemars::JsonSchema))]
+pub struct WeightedValue {
+    pub value: f64,
+    pub weight: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WeightedAverage {
+    pub weighted_mean: f64,
+    pub count: usize,
+    pub total_weight: f64,
+    // 各値の重みの割合（合計 1）
+    pub normalized_weights: Vec<f64>,
+}
+
+// Σ(value × weight) / Σweight。
+// normalize が false の場合、重みは合計 1（または 100%）になっていなければならない
+pub fn weighted_average(
+    items: &[WeightedValue],
+    normalize: bool,
+) -> Result<WeightedAverage, String> {
+    if items.is_empty() {
+        return Err("値と重みを1つ以上指定してください".to_string());
+    }
+    for (index, item) in items.iter().enumerate() {
+        if !item.value.is_finite() || !item.weight.is_finite() {
+            return Err(format!("{}番目の値または重みが無効です", index + 1));
+        }
+        if item.weight < 0.0 {
+            return Err(format!(
+                "重みには 0 以上の値を指定してください（{}番目: {}）",
+                index + 1,
+                item.weight
+            ));
+        }
+    }
+
+    let total_weight: f64 = items.iter().map(|item| item.weight).sum();
+    if total_weight == 0.0 {
+        return Err("重みの合計が 0 です".to_string());
+    }
+    let sums_to = |expected: f64| (total_weight - expected).abs() <= 1e-9 * expected;
+    if !normalize && !sums_to(1.0) && !sums_to(100.0) {
+        return Err(format!(
+            "重みの合計が 1（100%）ではありません: {}。合計で割ってよい場合は normalize を true にしてください",
+            total_weight
+        ));
+    }
+
+    let weighted_sum: f64 = items.iter().map(|item| item.value * item.weight).sum();
+    Ok(WeightedAverage {
+        weighted_mean: weighted_sum / total_weight,
+        count: items.len(),
+        total_weight,
+        normalized_weights: items
+            .iter()
+            .map(|item| item.weight / total_weight)
+            .collect(),
+    })
+}
@@ -0,0 +1,37 @@
+#[cfg(test)]
+mod tests {
+    use crate::average::{weighted_average, WeightedValue};
+
+    fn items(pairs: &[(f64, f64)]) -> Vec<WeightedValue> {
+        pairs
+            .iter()
+            .map(|&(value, weight)| WeightedValue { value, weight })
+            .collect()
+    }
+
+    #[test]
+    fn test_weighted_average_with_percent_weights() {
+        // 課題 30%, 中間 30%, 期末 40%
+        let result =
+            weighted_average(&items(&[(80.0, 30.0), (70.0, 30.0), (90.0, 40.0)]), false).unwrap();
+
+        assert!((result.weighted_mean - 81.0).abs() < 1e-9);
+        assert_eq!(result.total_weight, 100.0);
+        assert_eq!(result.normalized_weights, vec![0.3, 0.3, 0.4]);
+    }
+
+    #[test]
+    fn test_weighted_average_normalize() {
+        let pairs = items(&[(10.0, 1.0), (20.0, 3.0)]);
+
+        assert!(weighted_average(&pairs, false).is_err());
+        assert_eq!(weighted_average(&pairs, true).unwrap().weighted_mean, 17.5);
+    }
+
+    #[test]
+    fn test_weighted_average_invalid_weights() {
+        assert!(weighted_average(&items(&[(10.0, -1.0), (20.0, 2.0)]), true).is_err());
+        assert!(weighted_average(&items(&[(10.0, 0.0)]), true).is_err());
+        assert!(weighted_average(&[], true).is_err());
+    }
+}
@@ -0,0 +1,134 @@
+use crate::cli;
+use anyhow::{Context, Result};
+use calc_mcp::calculator::Calculator;
+use calc_mcp::dataset::csv_field;
+use calc_mcp::number_format::format_number;
+use serde_json::json;
+use std::io::{self, Read, Write};
+use std::process::ExitCode;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BatchFormat {
+    Text,
+    Csv,
+    Jsonl,
+}
+
+impl BatchFormat {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "text" => Some(Self::Text),
+            "csv" => Some(Self::Csv),
+            "jsonl" => Some(Self::Jsonl),
+            _ => None,
+        }
+    }
+}
+
+// calc-mcp run [--format text|csv|jsonl] [ファイル|-]
+pub fn run(args: &[String]) -> Result<ExitCode> {
+    let mut format = BatchFormat::Text;
+    let mut path = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let format_name = if arg == "--format" {
+            Some(args.next().map(String::as_str))
+        } else {
+            arg.strip_prefix("--format=").map(Some)
+        };
+
+        if let Some(name) = format_name {
+            match name.and_then(BatchFormat::parse) {
+                Some(parsed) => format = parsed,
+                None => {
+                    return Ok(cli::usage_error(
+                        "--format には text, csv, jsonl のいずれかを指定してください",
+                    ))
+                }
+            }
+        } else if path.is_none() {
+            path = Some(arg.as_str());
+        } else {
+            return Ok(cli::usage_error(&format!("余分な引数: {}", arg)));
+        }
+    }
+
+    let input = match path {
+        None | Some("-") => {
+            let mut input = String::new();
+            io::stdin()
+                .read_to_string(&mut input)
+                .context("標準入力を読み込めません")?;
+            input
+        }
+        Some(path) => std::fs::read_to_string(path)
+            .with_context(|| format!("ファイルを読み込めません: {}", path))?,
+    };
+
+    let stdout = io::stdout();
+    let all_ok = run_batch(&input, format, &mut stdout.lock())?;
+    Ok(if all_ok {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::from(1)
+    })
+}
+
+// 1行に1つの式を評価する。空行と # で始まる行は読み飛ばす。
+// すべての式が評価できた場合に true を返す
+pub fn run_batch(input: &str, format: BatchFormat, out: &mut impl Write) -> io::Result<bool> {
+    let calculator = Calculator::new();
+    let mut all_ok = true;
+
+    if format == BatchFormat::Csv {
+        writeln!(out, "line,expression,result,error")?;
+    }
+
+    for (index, line) in input.lines().enumerate() {
+        let line_number = index + 1;
+        let expression = line.trim();
+        if expression.is_empty() || expression.starts_with('#') {
+            continue;
+        }
+
+        let result = calculator.evaluate(expression);
+        all_ok &= result.is_ok();
+
+        match (format, result) {
+            (BatchFormat::Text, Ok(value)) => writeln!(
+                out,
+                "{}: {}",
+                line_number,
+                format_number(value, Default::default())
+            )?,
+            (BatchFormat::Text, Err(e)) => writeln!(out, "{}: エラー: {}", line_number, e)?,
+            (BatchFormat::Csv, Ok(value)) => writeln!(
+                out,
+                "{},{},{},",
+                line_number,
+                csv_field(expression),
+                format_number(value, Default::default())
+            )?,
+            (BatchFormat::Csv, Err(e)) => writeln!(
+                out,
+                "{},{},,{}",
+                line_number,
+                csv_field(expression),
+                csv_field(&e)
+            )?,
+            (BatchFormat::Jsonl, Ok(value)) => writeln!(
+                out,
+                "{}",
+                json!({ "line": line_number, "expression": expression, "result": value })
+            )?,
+            (BatchFormat::Jsonl, Err(e)) => writeln!(
+                out,
+                "{}",
+                json!({ "line": line_number, "expression": expression, "error": e })
+            )?,
+        }
+    }
+
+    Ok(all_ok)
+}
@@ -0,0 +1,51 @@
+#[cfg(test)]
+mod tests {
+    use crate::batch::{run_batch, BatchFormat};
+
+    fn run(input: &str, format: BatchFormat) -> (String, bool) {
+        let mut out = Vec::new();
+        let all_ok = run_batch(input, format, &mut out).unwrap();
+        (String::from_utf8(out).unwrap(), all_ok)
+    }
+
+    #[test]
+    fn test_batch_text_output() {
+        let (out, all_ok) = run("2 + 3\n\n# コメント\nsqrt(16)\n", BatchFormat::Text);
+        assert!(all_ok);
+        assert_eq!(out, "1: 5\n4: 4\n");
+    }
+
+    #[test]
+    fn test_batch_reports_errors_with_line_numbers() {
+        let (out, all_ok) = run("1 + 1\n1 / 0\n2 * 2", BatchFormat::Text);
+        assert!(!all_ok);
+        assert_eq!(
+            out,
+            "1: 2\n2: エラー: DIVISION_BY_ZERO: ゼロ除算エラー\n3: 4\n"
+        );
+    }
+
+    #[test]
+    fn test_batch_csv_output() {
+        let (out, all_ok) = run("2 ^ 3\nfoo(1)", BatchFormat::Csv);
+        assert!(!all_ok);
+        assert_eq!(
+            out,
+            "line,expression,result,error\n1,2 ^ 3,8,\n2,foo(1),,未サポートの関数: foo\n"
+        );
+    }
+
+    #[test]
+    fn test_batch_jsonl_output() {
+        let (out, _) = run("2 + 2\n1 / 0", BatchFormat::Jsonl);
+        let lines: Vec<serde_json::Value> = out
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        assert_eq!(lines[0]["line"], 1);
+        assert_eq!(lines[0]["result"], 4.0);
+        assert_eq!(lines[1]["line"], 2);
+        assert!(lines[1]["error"].as_str().unwrap().contains("ゼロ除算"));
+    }
+}
@@ -0,0 +1,124 @@
+use std::fmt;
+
+// 1桁（リム）の基数。10進数での表示を簡単にするため 10^9 を使う
+const BASE: u64 = 1_000_000_000;
+const BASE_DIGITS: usize = 9;
+
+// 組み合わせの数え上げ用の任意精度の非負整数（加算と小さな整数との乗除算だけを持つ）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BigUint {
+    // 下位の桁から順に並べる。0 は空ではなく [0] で表す
+    limbs: Vec<u32>,
+}
+
+impl BigUint {
+    pub fn zero() -> Self {
+        Self::from_u64(0)
+    }
+
+    pub fn one() -> Self {
+        Self::from_u64(1)
+    }
+
+    pub fn from_u64(mut value: u64) -> Self {
+        let mut limbs = vec![(value % BASE) as u32];
+        value /= BASE;
+        while value > 0 {
+            limbs.push((value % BASE) as u32);
+            value /= BASE;
+        }
+        Self { limbs }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs == [0]
+    }
+
+    // 10進数での桁数
+    pub fn digits(&self) -> usize {
+        let top = self.limbs[self.limbs.len() - 1];
+        (self.limbs.len() - 1) * BASE_DIGITS + top.to_string().len()
+    }
+
+    pub fn add(&self, other: &Self) -> Self {
+        let mut limbs = Vec::with_capacity(self.limbs.len().max(other.limbs.len()) + 1);
+        let mut carry = 0;
+        for i in 0..self.limbs.len().max(other.limbs.len()) {
+            let sum = u64::from(*self.limbs.get(i).unwrap_or(&0))
+                + u64::from(*other.limbs.get(i).unwrap_or(&0))
+                + carry;
+            limbs.push((sum % BASE) as u32);
+            carry = sum / BASE;
+        }
+        if carry > 0 {
+            limbs.push(carry as u32);
+        }
+        Self { limbs }
+    }
+
+    pub fn mul_small(&self, factor: u32) -> Self {
+        if factor == 0 {
+            return Self::zero();
+        }
+        let mut limbs = Vec::with_capacity(self.limbs.len() + 2);
+        let mut carry = 0;
+        for &limb in &self.limbs {
+            let product = u64::from(limb) * u64::from(factor) + carry;
+            limbs.push((product % BASE) as u32);
+            carry = product / BASE;
+        }
+        while carry > 0 {
+            limbs.push((carry % BASE) as u32);
+            carry /= BASE;
+        }
+        let mut result = Self { limbs };
+        result.trim();
+        result
+    }
+
+    // 商と余りを返す
+    pub fn div_small(&self, divisor: u32) -> (Self, u32) {
+        assert!(divisor != 0, "0 で割ることはできません");
+        let mut limbs = vec![0; self.limbs.len()];
+        let mut remainder = 0;
+        for (i, &limb) in self.limbs.iter().enumerate().rev() {
+            let current = remainder * BASE + u64::from(limb);
+            limbs[i] = (current / u64::from(divisor)) as u32;
+            remainder = current % u64::from(divisor);
+        }
+        let mut quotient = Self { limbs };
+        quotient.trim();
+        (quotient, remainder as u32)
+    }
+
+    // 先頭の significant 桁を使った指数表記（例: 9.332621544e157）
+    pub fn to_scientific(&self, significant: usize) -> String {
+        let digits = self.to_string();
+        let exponent = digits.len() - 1;
+        let mantissa = digits[1..digits.len().min(significant.max(1))].trim_end_matches('0');
+        if mantissa.is_empty() {
+            format!("{}e{}", &digits[..1], exponent)
+        } else {
+            format!("{}.{}e{}", &digits[..1], mantissa, exponent)
+        }
+    }
+
+    fn trim(&mut self) {
+        while self.limbs.len() > 1 && self.limbs[self.limbs.len() - 1] == 0 {
+            self.limbs.pop();
+        }
+    }
+}
+
+impl fmt::Display for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut limbs = self.limbs.iter().rev();
+        if let Some(top) = limbs.next() {
+            write!(f, "{}", top)?;
+        }
+        for limb in limbs {
+            write!(f, "{:09}", limb)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,36 @@
+#[cfg(test)]
+mod tests {
+    use crate::bigint::BigUint;
+
+    #[test]
+    fn test_arithmetic() {
+        let big = BigUint::from_u64(u64::MAX);
+        assert_eq!(big.to_string(), "18446744073709551615");
+        assert_eq!(big.digits(), 20);
+        assert_eq!(big.add(&BigUint::one()).to_string(), "18446744073709551616");
+        assert_eq!(big.mul_small(1000).to_string(), "18446744073709551615000");
+        assert_eq!(big.mul_small(0), BigUint::zero());
+
+        let (quotient, remainder) = big.div_small(10);
+        assert_eq!(quotient.to_string(), "1844674407370955161");
+        assert_eq!(remainder, 5);
+        assert!(BigUint::one().div_small(2).0.is_zero());
+        // 途中の桁の 0 も埋める
+        assert_eq!(
+            BigUint::from_u64(1_000_000_007 * 1_000_000_000).to_string(),
+            "1000000007000000000"
+        );
+    }
+
+    #[test]
+    fn test_to_scientific() {
+        let mut value = BigUint::one();
+        for i in 1..=100 {
+            value = value.mul_small(i);
+        }
+        assert_eq!(value.digits(), 158);
+        assert_eq!(value.to_scientific(10), "9.332621544e157");
+        assert_eq!(BigUint::from_u64(1000).to_scientific(10), "1e3");
+        assert_eq!(BigUint::from_u64(7).to_scientific(10), "7e0");
+    }
+}
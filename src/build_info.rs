@@ -0,0 +1,55 @@
+use serde::Serialize;
+
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+pub const GIT_COMMIT: &str = env!("CALC_MCP_GIT_COMMIT");
+pub const BUILD_TIME: &str = env!("CALC_MCP_BUILD_TIME");
+// 有効な Cargo feature（カンマ区切り、build.rs で生成）
+const FEATURES: &str = env!("CALC_MCP_FEATURES");
+// get_info で返す MCP プロトコルバージョン
+pub const PROTOCOL_VERSIONS: &[&str] = &["2024-11-05"];
+
+#[derive(Debug, Serialize)]
+pub struct BuildInfo {
+    pub name: &'static str,
+    pub version: &'static str,
+    pub git_commit: &'static str,
+    pub build_time: &'static str,
+    pub features: Vec<&'static str>,
+    pub protocol_versions: &'static [&'static str],
+}
+
+pub fn build_info() -> BuildInfo {
+    BuildInfo {
+        name: env!("CARGO_PKG_NAME"),
+        version: VERSION,
+        git_commit: GIT_COMMIT,
+        build_time: BUILD_TIME,
+        features: features(),
+        protocol_versions: PROTOCOL_VERSIONS,
+    }
+}
+
+pub fn features() -> Vec<&'static str> {
+    FEATURES
+        .split(',')
+        .filter(|feature| !feature.is_empty())
+        .collect()
+}
+
+// 不具合報告にそのまま貼れる1行の要約
+pub fn summary() -> String {
+    let features = features();
+    format!(
+        "{} {} (commit {}, built {}, features: {}, protocol: {})",
+        env!("CARGO_PKG_NAME"),
+        VERSION,
+        GIT_COMMIT,
+        BUILD_TIME,
+        if features.is_empty() {
+            "none".to_string()
+        } else {
+            features.join(",")
+        },
+        PROTOCOL_VERSIONS.join(",")
+    )
+}
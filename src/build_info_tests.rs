@@ -0,0 +1,26 @@
+#[cfg(test)]
+mod tests {
+    use crate::build_info::{build_info, summary, PROTOCOL_VERSIONS};
+    use crate::server::CalculatorService;
+    use rmcp::ServerHandler;
+
+    #[test]
+    fn test_build_info_fields() {
+        let info = build_info();
+        assert_eq!(info.name, "calc-mcp");
+        assert_eq!(info.version, "0.1.0");
+        assert!(!info.git_commit.is_empty());
+        // RFC 3339（UTC）形式: YYYY-MM-DDTHH:MM:SSZ
+        assert_eq!(info.build_time.len(), 20);
+        assert!(info.build_time.ends_with('Z'));
+        assert_eq!(info.protocol_versions, PROTOCOL_VERSIONS);
+    }
+
+    #[test]
+    fn test_build_info_in_instructions() {
+        let info = CalculatorService::default().get_info();
+        let instructions = info.instructions.unwrap();
+        assert!(instructions.contains(&summary()));
+        assert!(instructions.contains("2024-11-05"));
+    }
+}
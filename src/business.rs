@@ -0,0 +1,151 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "server", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum Calculation {
+    // 損益分岐点
+    BreakEven,
+    // 原価・売価・値入率（マークアップ）・利益率（マージン）の相互変換
+    MarkupMargin,
+    // 貢献利益（限界利益）
+    ContributionMargin,
+}
+
+impl Calculation {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::BreakEven => "break_even",
+            Self::MarkupMargin => "markup_margin",
+            Self::ContributionMargin => "contribution_margin",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct BreakEven {
+    pub fixed_costs: f64,
+    pub price: f64,
+    pub variable_cost: f64,
+    pub contribution_margin_per_unit: f64,
+    pub contribution_margin_ratio_percent: f64,
+    // 利益が 0 になる販売数量（小数）と、利益を出すのに必要な整数の数量
+    pub break_even_units: f64,
+    pub break_even_units_whole: f64,
+    pub break_even_revenue: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MarkupMargin {
+    pub cost: f64,
+    pub price: f64,
+    pub profit: f64,
+    // 利益 ÷ 原価（値入率・マークアップ）
+    pub markup_percent: f64,
+    // 利益 ÷ 売価（利益率・マージン）
+    pub margin_percent: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ContributionMargin {
+    pub price: f64,
+    pub variable_cost: f64,
+    pub quantity: f64,
+    pub contribution_margin_per_unit: f64,
+    pub contribution_margin_ratio_percent: f64,
+    pub total_contribution_margin: f64,
+    // fixed_costs を指定した場合の営業利益
+    pub operating_income: Option<f64>,
+}
+
+pub fn break_even(fixed_costs: f64, price: f64, variable_cost: f64) -> Result<BreakEven, String> {
+    check_finite(&[fixed_costs, price, variable_cost])?;
+    let per_unit = price - variable_cost;
+    if per_unit <= 0.0 {
+        return Err(format!(
+            "売価 {} が変動費 {} 以下のため、損益分岐点はありません",
+            price, variable_cost
+        ));
+    }
+    let units = fixed_costs / per_unit;
+    Ok(BreakEven {
+        fixed_costs,
+        price,
+        variable_cost,
+        contribution_margin_per_unit: per_unit,
+        contribution_margin_ratio_percent: per_unit / price * 100.0,
+        break_even_units: units,
+        break_even_units_whole: units.ceil(),
+        break_even_revenue: units * price,
+    })
+}
+
+// cost, price, markup_percent, margin_percent のうち2つ（markup と margin の組を除く）から残りを求める
+pub fn markup_margin(
+    cost: Option<f64>,
+    price: Option<f64>,
+    markup_percent: Option<f64>,
+    margin_percent: Option<f64>,
+) -> Result<MarkupMargin, String> {
+    let (cost, price) = match (cost, price, markup_percent, margin_percent) {
+        (Some(cost), Some(price), None, None) => (cost, price),
+        (Some(cost), None, Some(markup), None) => (cost, cost * (1.0 + markup / 100.0)),
+        (Some(cost), None, None, Some(margin)) => {
+            if margin >= 100.0 {
+                return Err("利益率（マージン）は 100% 未満でなければなりません".to_string());
+            }
+            (cost, cost / (1.0 - margin / 100.0))
+        }
+        (None, Some(price), Some(markup), None) => (price / (1.0 + markup / 100.0), price),
+        (None, Some(price), None, Some(margin)) => (price * (1.0 - margin / 100.0), price),
+        _ => {
+            return Err(
+                "cost, price, markup_percent, margin_percent のうち、原価か売価を含む2つを指定してください"
+                    .to_string(),
+            )
+        }
+    };
+    check_finite(&[cost, price])?;
+    if cost == 0.0 || price == 0.0 {
+        return Err("原価と売価には 0 以外の値を指定してください".to_string());
+    }
+    let profit = price - cost;
+    Ok(MarkupMargin {
+        cost,
+        price,
+        profit,
+        markup_percent: profit / cost * 100.0,
+        margin_percent: profit / price * 100.0,
+    })
+}
+
+pub fn contribution_margin(
+    price: f64,
+    variable_cost: f64,
+    quantity: f64,
+    fixed_costs: Option<f64>,
+) -> Result<ContributionMargin, String> {
+    check_finite(&[price, variable_cost, quantity, fixed_costs.unwrap_or(0.0)])?;
+    if price == 0.0 {
+        return Err("売価には 0 以外の値を指定してください".to_string());
+    }
+    let per_unit = price - variable_cost;
+    let total = per_unit * quantity;
+    Ok(ContributionMargin {
+        price,
+        variable_cost,
+        quantity,
+        contribution_margin_per_unit: per_unit,
+        contribution_margin_ratio_percent: per_unit / price * 100.0,
+        total_contribution_margin: total,
+        operating_income: fixed_costs.map(|fixed_costs| total - fixed_costs),
+    })
+}
+
+fn check_finite(values: &[f64]) -> Result<(), String> {
+    if values.iter().all(|value| value.is_finite()) {
+        Ok(())
+    } else {
+        Err("計算結果が無効です（NaN または 無限大）".to_string())
+    }
+}
@@ -0,0 +1,52 @@
+#[cfg(test)]
+mod tests {
+    use crate::business::{break_even, contribution_margin, markup_margin};
+
+    fn approx(actual: f64, expected: f64) -> bool {
+        (actual - expected).abs() < 1e-9
+    }
+
+    #[test]
+    fn test_break_even() {
+        let result = break_even(100000.0, 500.0, 300.0).unwrap();
+
+        assert_eq!(result.contribution_margin_per_unit, 200.0);
+        assert_eq!(result.contribution_margin_ratio_percent, 40.0);
+        assert_eq!(result.break_even_units, 500.0);
+        assert_eq!(result.break_even_revenue, 250000.0);
+
+        assert_eq!(
+            break_even(1000.0, 5.0, 2.0).unwrap().break_even_units_whole,
+            334.0
+        );
+        assert!(break_even(1000.0, 300.0, 300.0).is_err());
+    }
+
+    #[test]
+    fn test_markup_and_margin_differ() {
+        // 原価 100 を売価 125 で売ると、マークアップ 25% / マージン 20%
+        let result = markup_margin(Some(100.0), Some(125.0), None, None).unwrap();
+        assert_eq!(result.markup_percent, 25.0);
+        assert_eq!(result.margin_percent, 20.0);
+
+        let from_margin = markup_margin(Some(100.0), None, None, Some(20.0)).unwrap();
+        assert!(approx(from_margin.price, 125.0));
+        let from_markup = markup_margin(None, Some(125.0), Some(25.0), None).unwrap();
+        assert!(approx(from_markup.cost, 100.0));
+    }
+
+    #[test]
+    fn test_markup_margin_invalid() {
+        assert!(markup_margin(Some(100.0), None, None, Some(100.0)).is_err());
+        assert!(markup_margin(None, None, Some(25.0), Some(20.0)).is_err());
+        assert!(markup_margin(Some(100.0), Some(125.0), Some(25.0), None).is_err());
+    }
+
+    #[test]
+    fn test_contribution_margin() {
+        let result = contribution_margin(500.0, 300.0, 1200.0, Some(100000.0)).unwrap();
+
+        assert_eq!(result.total_contribution_margin, 240000.0);
+        assert_eq!(result.operating_income, Some(140000.0));
+    }
+}
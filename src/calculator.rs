@@ -1,324 +1,3317 @@
-use anyhow::Result;
-use rmcp::{
-    model::{Implementation, InitializeResult, ProtocolVersion, ServerCapabilities},
-    tool, ServerHandler,
-};
-use schemars::JsonSchema;
-use serde::Deserialize;
-use std::collections::HashMap;
-
-#[derive(Clone)]
-pub struct CalculatorService;
-
-#[derive(Debug, Deserialize, JsonSchema)]
-pub struct CalculateRequest {
-    #[schemars(
-        description = "計算する数式（例: \"2 + 3 * 4\", \"sqrt(25)\", \"sin(1.57)\"）。サポート: 四則演算(+, -, *, /)、べき乗(^)、括弧、数学関数(sqrt, abs, sin, cos, tan, ln)"
-    )]
-    pub expression: String,
-}
+use crate::constraints::{self, Relation};
+use crate::functions::{self, Domain, MultiFunction, UnaryFunction};
+use crate::rounding::{round_to, RoundingMode};
+use crate::sigfig::{format_figures, Figures, Measured};
+use crate::units::{self, Unit};
+use crate::value::{self, Value};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 
-// セキュアな数式パーサー
-#[derive(Debug, Clone, PartialEq)]
-pub enum Token {
-    Number(f64),
+// セキュアな数式パーサー。名前は入力の文字列を借用し、トークンごとに確保しない
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Token<'a> {
+    Number(f64, Figures),
     Operator(char),
-    Function(String),
+    Function(&'a str),
+    // 関数の引数の区切り
+    Comma,
+    Variable(&'a str),
     LeftParen,
     RightParen,
+    // 絶対値の |x|（開きと閉じは構文解析で区別する）
+    Bar,
+    // 10% のような百分率のリテラル（値は % の前の数値）
+    Percent(f64, Figures),
+    // √x は sqrt(x) と同じ（単項のマイナスと同じく ^ より強く結合する）
+    Sqrt,
+    // [1, 2] のベクトルと [[1, 2], [3, 4]] の行列（evaluate_value だけが受け付ける）
+    LeftBracket,
+    RightBracket,
+    // 比較演算子（evaluate_value だけが受け付け、結果は真偽値）
+    Relation(Relation),
+    // 単位の換算 5 km in mi の in（evaluate_value だけが受け付ける）
+    In,
+}
+
+// 三角関数の引数の単位
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "server", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum AngleMode {
+    #[default]
+    Radians,
+    Degrees,
+}
+
+impl AngleMode {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Radians => "radians",
+            Self::Degrees => "degrees",
+        }
+    }
+}
+
+// 角度モードの影響を受ける関数
+const TRIG_FUNCTIONS: &[&str] = &["sin", "cos", "tan"];
+// 度数法のときに結果の角度を度に変換する関数
+const INVERSE_TRIG_FUNCTIONS: &[&str] = &["atan2"];
+// evaluate_value 以外で [ ] を使ったときのエラー
+const ARRAY_UNSUPPORTED: &str =
+    "この式ではベクトル・行列（[...]）は使えません（数値になる式を指定してください）";
+// compile で比較演算子を使ったときのエラー（結果が真偽値になるため）
+const COMPARISON_UNSUPPORTED: &str =
+    "この式では比較演算子は使えません（数値になる式を指定してください）";
+const CONVERSION_UNSUPPORTED: &str = "この式では単位の換算（in）は使えません";
+// 単位の換算のキーワード（同じ名前の変数・定数があればそちらを優先する）
+const CONVERSION_KEYWORD: &str = "in";
+// 数式で定義された関数の呼び出しの深さの上限（再帰定義の検出）
+const MAX_CALL_DEPTH: usize = 32;
+// eval_array で一度に評価する値の数
+const ARRAY_CHUNK: usize = 256;
+// 数値リテラルの後に付けられる SI 接頭辞（4.7k, 100m, 2.2M, 5u など）と 10 の指数
+pub const SI_PREFIXES: &[(char, i32)] = &[
+    ('f', -15),
+    ('p', -12),
+    ('n', -9),
+    ('u', -6),
+    ('µ', -6),
+    ('m', -3),
+    ('k', 3),
+    ('M', 6),
+    ('G', 9),
+    ('T', 12),
+];
+// 寛容モードで ASCII の記号として扱う Unicode の文字（strict モードではエラー）
+const UNICODE_SUBSTITUTES: &[(char, char)] = &[
+    ('×', '*'),
+    ('·', '*'),
+    ('⋅', '*'),
+    ('∗', '*'),
+    ('＊', '*'),
+    ('÷', '/'),
+    ('∕', '/'),
+    ('／', '/'),
+    ('−', '-'),
+    ('–', '-'),
+    ('－', '-'),
+    ('＋', '+'),
+    ('＾', '^'),
+    ('（', '('),
+    ('）', ')'),
+    ('，', ','),
+    ('\u{3000}', ' '),
+    ('\u{a0}', ' '),
+];
+// canonical_hash に使う FNV-1a（64ビット）のパラメータ
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+// 1つの式として受け付ける大きさの上限（DoS攻撃防止）
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct ExpressionLimits {
+    // 式の長さ（バイト数）
+    pub max_length: usize,
+    // 字句解析後のトークン数
+    pub max_tokens: usize,
+    // 1回の関数呼び出しに渡せる引数の数
+    pub max_function_args: usize,
+}
+
+impl Default for ExpressionLimits {
+    fn default() -> Self {
+        Self {
+            max_length: 1000,
+            max_tokens: 1000,
+            max_function_args: 32,
+        }
+    }
+}
+
+// 信頼できない利用者に公開するときの計算の量の上限（[limits.safe_profile]）。
+// ExpressionLimits が式の大きさを抑えるのに対し、こちらは1つの式や1回の呼び出しの計算量を抑える
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SafeProfile {
+    // ^ の指数の絶対値（行列のべき乗の掛け算の回数もこれで決まる）
+    pub max_exponent: f64,
+    // n! の n（gamma の引数と combinatorics の n）
+    pub max_factorial: u64,
+    // 反復計算（solve_system）の反復回数
+    pub max_iterations: usize,
+    // ベクトルと値の配列（statistics の values など）の要素数
+    pub max_list_length: usize,
+    // 行列の行数・列数
+    pub max_matrix_dimension: usize,
+}
+
+impl Default for SafeProfile {
+    fn default() -> Self {
+        Self {
+            max_exponent: 1024.0,
+            max_factorial: 1000,
+            max_iterations: 1000,
+            max_list_length: 1000,
+            max_matrix_dimension: 50,
+        }
+    }
+}
+
+impl SafeProfile {
+    pub fn check_exponent(&self, exponent: f64) -> Result<(), String> {
+        if exponent.abs() > self.max_exponent {
+            return Err(exceeded("^ の指数の絶対値", self.max_exponent, exponent));
+        }
+        Ok(())
+    }
+
+    pub fn check_factorial(&self, n: f64) -> Result<(), String> {
+        if n > self.max_factorial as f64 {
+            return Err(exceeded("n! の n", self.max_factorial, n));
+        }
+        Ok(())
+    }
+
+    // gamma(x) は (x - 1)! にあたる
+    pub fn check_call(&self, name: &str, args: &[f64]) -> Result<(), String> {
+        match (name, args) {
+            ("gamma", [x]) => self.check_factorial(x - 1.0),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn check_list_length(&self, length: usize) -> Result<(), String> {
+        if length > self.max_list_length {
+            return Err(exceeded("配列の要素数", self.max_list_length, length));
+        }
+        Ok(())
+    }
+
+    pub fn check_matrix(&self, rows: usize, cols: usize) -> Result<(), String> {
+        if rows.max(cols) > self.max_matrix_dimension {
+            return Err(exceeded(
+                "行列の行数・列数",
+                self.max_matrix_dimension,
+                format!("{} × {}", rows, cols),
+            ));
+        }
+        Ok(())
+    }
+
+    // ベクトル・行列の値の大きさ（それ以外の値は検査しない）
+    pub fn check_value(&self, value: &Value) -> Result<(), String> {
+        match value {
+            Value::List(list) => self.check_list_length(list.len()),
+            Value::Matrix(matrix) => {
+                self.check_matrix(matrix.len(), matrix.first().map_or(0, Vec::len))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+// 上限（safe プロファイルや多倍長の計算の桁数）を超えたときのエラーの先頭に付けるコード
+pub const LIMIT_EXCEEDED: &str = "LIMIT_EXCEEDED";
+
+fn exceeded(what: &str, max: impl std::fmt::Display, actual: impl std::fmt::Display) -> String {
+    format!(
+        "{}: {}が safe プロファイルの上限（{}）を超えます: {}",
+        LIMIT_EXCEEDED, what, max, actual
+    )
+}
+
+// 構文の誤り・定義域外の計算・ゼロ除算のエラーの先頭に付けるコード。
+// tool_error は文言でなくこのコードでエラーを分類する
+pub const SYNTAX_ERROR: &str = "SYNTAX_ERROR";
+pub const DOMAIN_ERROR: &str = "DOMAIN_ERROR";
+pub const DIVISION_BY_ZERO: &str = "DIVISION_BY_ZERO";
+
+pub fn syntax_error(message: impl std::fmt::Display) -> String {
+    format!("{}: {}", SYNTAX_ERROR, message)
+}
+
+pub fn domain_error(message: impl std::fmt::Display) -> String {
+    format!("{}: {}", DOMAIN_ERROR, message)
+}
+
+pub fn division_by_zero() -> String {
+    format!("{}: ゼロ除算エラー", DIVISION_BY_ZERO)
 }
 
 pub struct Calculator {
     // 許可された関数のホワイトリスト
-    allowed_functions: HashMap<String, Box<dyn Fn(f64) -> f64>>,
+    allowed_functions: HashMap<String, UnaryFunction>,
+    // 引数の数や定義域を検査する組み込み関数（root など）
+    multi_functions: HashMap<String, MultiFunction>,
+    // 数式で定義された関数（引数は x）
+    custom_functions: HashMap<String, String>,
+    // 式の中から参照できる変数
+    variables: HashMap<String, f64>,
+    // 設定ファイルで定義された定数（関数の式の中からも参照できる）
+    constants: HashMap<String, f64>,
+    angle_mode: AngleMode,
+    // round() の端数処理
+    rounding_mode: RoundingMode,
+    // 数式で定義された関数の呼び出しの深さ
+    depth: usize,
+    limits: ExpressionLimits,
+    // 数値リテラルの SI 接頭辞を受け付けるか（m がミリか変数かあいまいなため既定では無効）
+    si_prefixes: bool,
+    // true なら暗黙の掛け算（2x）や × などの Unicode の記号を補正せずにエラーにする
+    strict: bool,
+    // true なら sqrt などの数値の関数にベクトル・行列を渡したとき要素ごとに計算する（false ならエラー）
+    elementwise: bool,
+    safe_profile: Option<SafeProfile>,
+}
+
+// CompiledExpr::audit で記録する1回の演算。step は評価した順の通し番号（1から）
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AuditStep {
+    pub step: usize,
+    // add, subtract, multiply, divide, power, negate, variable, function, round,
+    // call（数式で定義された関数）のいずれか
+    pub operation: &'static str,
+    // 変数・関数の名前
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub operands: Vec<f64>,
+    pub result: f64,
+}
+
+// 意味があいまいな書き方（1/2x, log(100), 100m）と、考えられる解釈
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Clarification {
+    // 式のうちあいまいな部分
+    pub text: String,
+    pub question: String,
+    pub interpretations: Vec<Interpretation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Interpretation {
+    pub meaning: String,
+    // その解釈であいまいさのないように書き直した式
+    pub expression: String,
+}
+
+// Calculator::estimate の結果。式を評価せずに見積もった計算の量
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CostEstimate {
+    // 評価で行う演算（四則演算・べき乗・符号の反転・比較・関数の呼び出し）の回数。
+    // 数式で定義された関数は呼び出すたびに本体の演算を数える
+    pub operations: u64,
+    // そのうち関数を呼び出す回数
+    pub function_calls: u64,
+    // 式の木の深さ（数式で定義された関数の本体を含む）
+    pub depth: usize,
+    pub complexity: Complexity,
+    // 繰り返しを含む演算（行列のべき乗）と回数の上限
+    pub loops: Vec<LoopBound>,
+    // true ならベクトル・行列などを含むため、構文解析をせずトークンから見積もった値
+    pub approximate: bool,
+}
+
+// 入力の大きさ（ベクトルの長さ n・行列の次数 n）に対する計算量
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Complexity {
+    // 実数のみの式。演算の回数は入力によらない
+    Constant,
+    // 要素ごとの計算、内積、ノルムなど O(n)〜O(n²)
+    Linear,
+    // 行列の積・べき乗・逆行列・行列式 O(n³)
+    Cubic,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LoopBound {
+    pub operation: &'static str,
+    // 繰り返しを決める値（べき乗の指数）
+    pub bound: f64,
+    // 繰り返す回数の上限（行列のべき乗は2乗を繰り返すため、掛け算は指数の2進数の桁数の約2倍）
+    pub iterations: u64,
+}
+
+// CompiledExpr::estimate で数える1つの部分木の量
+#[derive(Debug, Clone, Copy, Default)]
+struct Cost {
+    operations: u64,
+    function_calls: u64,
+    depth: usize,
+}
+
+impl Cost {
+    // 子 children を評価したあとに演算を1回行うノード
+    fn operation(children: &[Cost], call: bool) -> Self {
+        let mut cost = Cost {
+            operations: 1,
+            function_calls: u64::from(call),
+            depth: 0,
+        };
+        for child in children {
+            cost.operations = cost.operations.saturating_add(child.operations);
+            cost.function_calls = cost.function_calls.saturating_add(child.function_calls);
+            cost.depth = cost.depth.max(child.depth);
+        }
+        cost.depth += 1;
+        cost
+    }
+}
+
+// 数式で定義された関数の本体の量（再帰呼び出しの検出のため、数えている途中も区別する）
+#[derive(Debug, Clone, Copy)]
+enum FunctionCost {
+    Unvisited,
+    Visiting,
+    Counted(Cost),
+}
+
+// compile で一度だけ解析した式。eval で変数の値を変えて繰り返し評価できる
+#[derive(Debug, Clone)]
+pub struct CompiledExpr {
+    // 式の木のノード。子は Box ではなく添字で参照し、1つの Vec にまとめて確保する
+    nodes: Vec<Node>,
+    root: NodeId,
+    // 式から呼び出す数式で定義された関数の本体（Node::Custom の index で参照）
+    functions: Vec<NodeId>,
+    // compile した Calculator の safe プロファイル
+    safe_profile: Option<SafeProfile>,
+}
+
+// CompiledExpr::nodes の添字
+type NodeId = usize;
+
+// 解析済みの式の木。関数と定数は compile の時点で解決する
+#[derive(Debug, Clone)]
+enum Node {
+    Number(f64),
+    // eval で束縛されなければ fallback（compile 時の変数・定数の値）を使う
+    Variable {
+        name: String,
+        fallback: Option<f64>,
+    },
+    // 数式で定義された関数の引数 x
+    Argument,
+    Negate(NodeId),
+    Binary(char, NodeId, NodeId),
+    Builtin {
+        name: String,
+        function: UnaryFunction,
+        // 度数法のときに引数をラジアンに変換する三角関数
+        to_radians: bool,
+        arg: NodeId,
+    },
+    Multi {
+        name: String,
+        function: MultiFunction,
+        // 度数法のときに結果を度に変換する逆三角関数
+        to_degrees: bool,
+        args: Vec<NodeId>,
+    },
+    Round(RoundingMode, NodeId),
+    Custom {
+        name: String,
+        index: usize,
+        arg: NodeId,
+    },
+}
+
+// evaluate_sig_figs の結果
+#[derive(Debug, Clone)]
+pub struct SigFigs {
+    // 丸める前の値
+    pub value: f64,
+    // 有効数字に合わせて丸めた表示
+    pub display: String,
+    // 結果の有効数字の桁数（厳密な値のみの式では None）
+    pub digits: Option<u32>,
+    pub explanation: Vec<String>,
+}
+
+impl Default for Calculator {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Calculator {
     pub fn new() -> Self {
-        let mut allowed_functions: HashMap<String, Box<dyn Fn(f64) -> f64>> = HashMap::new();
-        allowed_functions.insert("sqrt".to_string(), Box::new(|x: f64| x.sqrt()));
-        allowed_functions.insert("abs".to_string(), Box::new(|x: f64| x.abs()));
-        allowed_functions.insert("sin".to_string(), Box::new(|x: f64| x.sin()));
-        allowed_functions.insert("cos".to_string(), Box::new(|x: f64| x.cos()));
-        allowed_functions.insert("tan".to_string(), Box::new(|x: f64| x.tan()));
-        allowed_functions.insert("ln".to_string(), Box::new(|x: f64| x.ln()));
+        let mut allowed_functions: HashMap<String, UnaryFunction> = HashMap::new();
+        let mut insert = |name: &str, function: fn(f64) -> f64, domain: Domain| {
+            allowed_functions.insert(name.to_string(), UnaryFunction::new(function, domain));
+        };
+        // 実数の範囲の平方根（複素数の引数は value::overload が扱う）
+        insert("sqrt", f64::sqrt, Domain::NonNegative);
+        insert("abs", f64::abs, Domain::Real);
+        #[cfg(feature = "trig")]
+        {
+            insert("sin", f64::sin, Domain::Real);
+            insert("cos", f64::cos, Domain::Real);
+            insert("tan", f64::tan, Domain::Real);
+        }
+        insert("cbrt", f64::cbrt, Domain::Real);
+        insert("exp", f64::exp, Domain::Real);
+        // 0 に近い x で exp(x) - 1 の桁落ちを避ける
+        insert("expm1", f64::exp_m1, Domain::Real);
+        insert("erf", functions::erf, Domain::Real);
+        insert("erfc", functions::erfc, Domain::Real);
+        // 端数処理は rounding_mode に従う（evaluate_factor を参照）
+        insert("round", f64::round, Domain::Real);
+
+        let multi_functions = functions::builtins()
+            .into_iter()
+            .map(|(name, function)| (name.to_string(), function))
+            .collect();
+
+        Self {
+            allowed_functions,
+            multi_functions,
+            custom_functions: HashMap::new(),
+            variables: HashMap::new(),
+            constants: HashMap::new(),
+            angle_mode: AngleMode::default(),
+            rounding_mode: RoundingMode::default(),
+            depth: 0,
+            limits: ExpressionLimits::default(),
+            si_prefixes: false,
+            strict: false,
+            elementwise: false,
+            safe_profile: None,
+        }
+    }
+
+    pub fn angle_mode(&self) -> AngleMode {
+        self.angle_mode
+    }
+
+    pub fn set_angle_mode(&mut self, angle_mode: AngleMode) {
+        self.angle_mode = angle_mode;
+    }
+
+    pub fn rounding_mode(&self) -> RoundingMode {
+        self.rounding_mode
+    }
+
+    pub fn set_rounding_mode(&mut self, rounding_mode: RoundingMode) {
+        self.rounding_mode = rounding_mode;
+    }
+
+    pub fn si_prefixes(&self) -> bool {
+        self.si_prefixes
+    }
+
+    pub fn set_si_prefixes(&mut self, enabled: bool) {
+        self.si_prefixes = enabled;
+    }
+
+    pub fn strict(&self) -> bool {
+        self.strict
+    }
+
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    pub fn elementwise(&self) -> bool {
+        self.elementwise
+    }
+
+    pub fn set_elementwise(&mut self, elementwise: bool) {
+        self.elementwise = elementwise;
+    }
+
+    pub fn limits(&self) -> ExpressionLimits {
+        self.limits
+    }
+
+    pub fn safe_profile(&self) -> Option<SafeProfile> {
+        self.safe_profile
+    }
+
+    // None なら計算の量を制限しない
+    pub fn set_safe_profile(&mut self, safe_profile: Option<SafeProfile>) {
+        self.safe_profile = safe_profile;
+    }
+
+    pub fn set_limits(&mut self, limits: ExpressionLimits) -> Result<(), String> {
+        if limits.max_length == 0 || limits.max_tokens == 0 || limits.max_function_args == 0 {
+            return Err(
+                "式の長さ・トークン数・関数の引数の数の上限は 1 以上にしてください".to_string(),
+            );
+        }
+        self.limits = limits;
+        Ok(())
+    }
+
+    pub fn variables(&self) -> &HashMap<String, f64> {
+        &self.variables
+    }
+
+    // 組み込み関数の名前（有効な feature に応じて変わる）
+    pub fn function_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self
+            .allowed_functions
+            .keys()
+            .chain(self.multi_functions.keys())
+            .map(String::as_str)
+            .collect();
+        names.sort_unstable();
+        names
+    }
+
+    // 引数の定義域が実数全体でない組み込み関数と、その定義域
+    pub fn function_domains(&self) -> BTreeMap<&str, &'static str> {
+        let unary = self
+            .allowed_functions
+            .iter()
+            .map(|(name, function)| (name, function.domain));
+        let multi = self
+            .multi_functions
+            .iter()
+            .map(|(name, function)| (name, function.domain));
+        unary
+            .chain(multi)
+            .filter(|(_, domain)| *domain != Domain::Real)
+            .map(|(name, domain)| (name.as_str(), domain.describe()))
+            .collect()
+    }
+
+    pub fn set_variable(&mut self, name: &str, value: f64) -> Result<(), String> {
+        if !is_identifier(name) {
+            return Err(format!("不正な変数名: {}", name));
+        }
+        if self.is_function(name) {
+            return Err(format!("関数名は変数名に使えません: {}", name));
+        }
+        if self.constants.contains_key(name) {
+            return Err(format!("定数には代入できません: {}", name));
+        }
+        self.variables.insert(name.to_string(), value);
+        Ok(())
+    }
+
+    // すべての式から参照できる定数を定義する（例: tax_rate = 0.10）
+    pub fn define_constant(&mut self, name: &str, value: f64) -> Result<(), String> {
+        if !is_identifier(name) {
+            return Err(format!("不正な定数名: {}", name));
+        }
+        if self.is_function(name) {
+            return Err(format!("関数名は定数名に使えません: {}", name));
+        }
+        if !value.is_finite() {
+            return Err(format!("定数の値が無効です: {} = {}", name, value));
+        }
+        self.constants.insert(name.to_string(), value);
+        Ok(())
+    }
+
+    // 引数 x の式で関数を定義する（例: double = "2*x"）。定義した関数同士は互いに呼び出せる
+    pub fn define_function(&mut self, name: &str, formula: &str) -> Result<(), String> {
+        if !is_identifier(name) {
+            return Err(format!("不正な関数名: {}", name));
+        }
+        if self.is_builtin(name) {
+            return Err(format!("組み込み関数は再定義できません: {}", name));
+        }
+        if self.constants.contains_key(name) {
+            return Err(format!("定数名は関数名に使えません: {}", name));
+        }
+        self.custom_functions
+            .insert(name.to_string(), formula.to_string());
+        Ok(())
+    }
+
+    // 定義された関数の式を字句解析し、未知の関数や不正な文字がないか確認する
+    pub fn validate_functions(&self) -> Result<(), String> {
+        for (name, formula) in &self.custom_functions {
+            self.tokenize(formula, &mut Vec::new())
+                .map_err(|e| format!("{}: {}", name, e))?;
+        }
+        Ok(())
+    }
+
+    fn is_builtin(&self, name: &str) -> bool {
+        self.allowed_functions.contains_key(name) || self.multi_functions.contains_key(name)
+    }
+
+    fn is_function(&self, name: &str) -> bool {
+        self.is_builtin(name) || self.custom_functions.contains_key(name)
+    }
+
+    pub fn evaluate(&self, expression: &str) -> Result<f64, String> {
+        self.evaluate_with(expression, &HashMap::new())
+    }
+
+    // vars の値を変数に束縛して評価する（同じ名前の変数・定数より優先）。
+    // 数値計算のツールが同じ式を x の値を変えて繰り返し評価するときに使う
+    pub fn evaluate_with(
+        &self,
+        expression: &str,
+        vars: &HashMap<String, f64>,
+    ) -> Result<f64, String> {
+        let value = self.evaluate_value_with(expression, vars)?;
+        value.real().ok_or_else(|| {
+            domain_error(format!(
+                "計算結果が実数ではありません（{}）",
+                value.describe()
+            ))
+        })
+    }
+
+    // compile した式の canonical_hash。2+3*4 と 2 + (3 * 4) は同じ値になる
+    pub fn canonical_hash(&self, expression: &str) -> Result<String, String> {
+        self.compile(expression)
+            .map(|compiled| compiled.canonical_hash())
+    }
+
+    // 式を解析して、繰り返し評価できる形にする。結果は compile 時点の変数・定数・角度モード・
+    // 端数処理を取り込み、その後の Calculator の変更の影響を受けない
+    pub fn compile(&self, expression: &str) -> Result<CompiledExpr, String> {
+        let tokens = self.checked_tokenize(expression)?;
+        let mut compiler = Compiler {
+            calculator: self,
+            nodes: Vec::new(),
+            functions: Vec::new(),
+            indices: HashMap::new(),
+        };
+        let root = compiler.compile_tokens(expression, &tokens, false)?;
+        Ok(CompiledExpr {
+            nodes: compiler.nodes,
+            root,
+            functions: compiler
+                .functions
+                .into_iter()
+                .map(|function| function.expect("関数の本体は compile 中に設定される"))
+                .collect(),
+            safe_profile: self.safe_profile,
+        })
+    }
+
+    // 式を評価せずに計算の量を見積もる（calculate の dry_run）。構文の誤りと上限を超える式はエラーにする
+    pub fn estimate(&self, expression: &str) -> Result<CostEstimate, String> {
+        let tokens = self.checked_tokenize(expression)?;
+        match self.compile(expression) {
+            Ok(compiled) => compiled.estimate(),
+            // ベクトル・行列・比較・単位の換算を含む式はコンパイルできないため、トークンから見積もる
+            Err(e)
+                if [
+                    ARRAY_UNSUPPORTED,
+                    COMPARISON_UNSUPPORTED,
+                    CONVERSION_UNSUPPORTED,
+                ]
+                .contains(&e.as_str()) =>
+            {
+                Ok(estimate_tokens(&tokens))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    // 式の中の、意味が2通りある単位について計算に使った意味（GB は 1000^3 バイトなど）
+    pub fn unit_notes(&self, expression: &str) -> Vec<String> {
+        let Ok(tokens) = self.tokenize(expression, &mut Vec::new()) else {
+            return Vec::new();
+        };
+        let mut notes = Vec::new();
+        for token in tokens {
+            let (Token::Variable(name) | Token::Function(name)) = token else {
+                continue;
+            };
+            if self.variables.contains_key(name) || self.constants.contains_key(name) {
+                continue;
+            }
+            if let Some(note) = units::ambiguity(name) {
+                if !notes.iter().any(|existing: &String| existing == note) {
+                    notes.push(note.to_string());
+                }
+            }
+        }
+        notes
+    }
+
+    // 寛容モードで式に加えた補正（暗黙の掛け算の * や Unicode の記号の置き換え）の説明。
+    // 評価は補正した式で行われ、strict モードでは補正が必要な式はエラーになる
+    pub fn corrections(&self, expression: &str) -> Result<Vec<String>, String> {
+        let mut corrections = Vec::new();
+        self.tokenize(expression, &mut corrections)?;
+        Ok(corrections)
+    }
+
+    // 推測して計算すると意図と違う結果になりうる書き方。calculate の clarify では、
+    // 推測せずにどの解釈かをクライアントに確認する
+    pub fn clarifications(&self, expression: &str) -> Vec<Clarification> {
+        let mut offsets = Vec::new();
+        let Ok(tokens) = self.tokenize_with_offsets(expression, &mut Vec::new(), &mut offsets)
+        else {
+            return Vec::new();
+        };
+        let end_of = |index: usize| offsets.get(index).copied().unwrap_or(expression.len());
+        let mut clarifications = Vec::new();
+        for (index, &token) in tokens.iter().enumerate() {
+            match token {
+                // 1/2x は (1/2)*x と計算するが、1/(2x) の意味で書かれることもある
+                Token::Operator('*') if is_inserted_product(&tokens, &offsets, index) => {
+                    let divisor = factor_start(&tokens[..index]);
+                    if divisor < 2 || tokens[divisor - 1] != Token::Operator('/') {
+                        continue;
+                    }
+                    let numerator = factor_start(&tokens[..divisor - 1]);
+                    let end = end_of(product_end(&tokens, &offsets, index + 1));
+                    let text = |from: usize, to: usize| expression[from..to].trim().to_string();
+                    let (product, left) = (offsets[index], offsets[divisor]);
+                    let (quotient, multiplier) =
+                        (text(offsets[numerator], product), text(product, end));
+                    let (dividend, divisor_text) = (
+                        text(offsets[numerator], offsets[divisor - 1]),
+                        text(left, end),
+                    );
+                    let inner = expression[left..end].trim_end();
+                    let ambiguous = text(offsets[numerator], end);
+                    clarifications.push(Clarification {
+                        question: format!(
+                            "{} は ({}) * {} と {} / ({}) のどちらの意味ですか",
+                            ambiguous, quotient, multiplier, dividend, divisor_text
+                        ),
+                        text: ambiguous,
+                        interpretations: vec![
+                            Interpretation {
+                                meaning: format!("({}) * {}", quotient, multiplier),
+                                expression: format!(
+                                    "{} * {}",
+                                    expression[..product].trim_end(),
+                                    &expression[product..]
+                                ),
+                            },
+                            Interpretation {
+                                meaning: format!("{} / ({})", dividend, divisor_text),
+                                expression: format!(
+                                    "{}({}){}",
+                                    &expression[..left],
+                                    inner,
+                                    &expression[left + inner.len()..]
+                                ),
+                            },
+                        ],
+                    });
+                }
+                // log は log(x, b) で底を指定する。引数が1つなら常用対数と自然対数のどちらかわからない
+                Token::Function("log") => {
+                    let Some(close) = matching_paren(&tokens, index + 1) else {
+                        continue;
+                    };
+                    let arguments = &tokens[index + 2..close];
+                    let mut depth = 0;
+                    let has_base = arguments.iter().any(|&token| {
+                        match token {
+                            Token::LeftParen | Token::LeftBracket => depth += 1,
+                            Token::RightParen | Token::RightBracket => depth -= 1,
+                            _ => {}
+                        }
+                        depth == 0 && token == Token::Comma
+                    });
+                    if has_base || arguments.is_empty() {
+                        continue;
+                    }
+                    let start = offsets[index];
+                    let end = offsets[close]
+                        + expression[offsets[close]..]
+                            .chars()
+                            .next()
+                            .map_or(1, char::len_utf8);
+                    let text = expression[start..end].to_string();
+                    let name_end = start + "log".len();
+                    let rename = |name: &str| {
+                        format!(
+                            "{}{}{}",
+                            &expression[..start],
+                            name,
+                            &expression[name_end..]
+                        )
+                    };
+                    clarifications.push(Clarification {
+                        question: format!(
+                            "{} の底は 10, e, 2 のどれですか（log(x, b) は底 b の対数です）",
+                            text
+                        ),
+                        text,
+                        interpretations: vec![
+                            Interpretation {
+                                meaning: "常用対数（底 10）".to_string(),
+                                expression: rename("log10"),
+                            },
+                            Interpretation {
+                                meaning: "自然対数（底 e）".to_string(),
+                                expression: rename("ln"),
+                            },
+                            Interpretation {
+                                meaning: "2を底とする対数".to_string(),
+                                expression: rename("log2"),
+                            },
+                        ],
+                    });
+                }
+                // SI 接頭辞が有効なとき、100m は 0.1 と読むが 100 メートルの意味で書かれることもある
+                Token::Number(..) if self.si_prefixes => {
+                    let start = offsets[index];
+                    let literal = expression[start..end_of(index + 1)].trim_end();
+                    let Some(number) = literal.strip_suffix('m') else {
+                        continue;
+                    };
+                    let end = start + literal.len();
+                    let meter = if self.strict {
+                        format!("{} * m", number)
+                    } else {
+                        format!("{} m", number)
+                    };
+                    let replace = |with: &str| {
+                        format!("{}{}{}", &expression[..start], with, &expression[end..])
+                    };
+                    clarifications.push(Clarification {
+                        text: literal.to_string(),
+                        question: format!(
+                            "{} は {} ミリ（{} / 1000）と {} メートルのどちらの意味ですか",
+                            literal, number, number, number
+                        ),
+                        interpretations: vec![
+                            Interpretation {
+                                meaning: format!("{} ミリ（SI 接頭辞）", number),
+                                expression: replace(&format!("({} / 1000)", number)),
+                            },
+                            Interpretation {
+                                meaning: format!("{} メートル", number),
+                                expression: replace(&meter),
+                            },
+                        ],
+                    });
+                }
+                _ => {}
+            }
+        }
+        clarifications
+    }
+
+    // ファジング用の入口。任意のバイト列を式として、calculate が使う解析・補正・評価・
+    // コンパイル（正規化・監査）の経路にすべて通す。入力が不正ならエラーを返すだけで、
+    // どんな入力でもパニックしてはならない（パニックは不具合として扱う）
+    pub fn fuzz_eval(&self, bytes: &[u8]) -> Result<Value, String> {
+        let expression = String::from_utf8_lossy(bytes);
+        let _ = self.corrections(&expression);
+        let _ = self.unit_notes(&expression);
+        let _ = self.evaluate_sig_figs(&expression);
+        if let Ok(compiled) = self.compile(&expression) {
+            let _ = compiled.to_infix();
+            let _ = compiled.canonical_hash();
+            let _ = compiled.audit(&HashMap::new());
+        }
+        self.evaluate_value(&expression)
+    }
+
+    // 有効数字を考慮して評価する。結果は正しい桁数に丸めて、規則の説明とあわせて返す
+    pub fn evaluate_sig_figs(&self, expression: &str) -> Result<SigFigs, String> {
+        let measured = self.evaluate_measured(expression, &HashMap::new())?;
+        Ok(SigFigs {
+            value: measured.value,
+            display: match measured.figures {
+                Some(figures) => format_figures(measured.value, figures),
+                None => measured.value.to_string(),
+            },
+            digits: measured.figures.map(|figures| figures.digits),
+            explanation: measured.notes,
+        })
+    }
+
+    // 引数が1つの関数（|x| と √x を含む）。実数でない引数は、abs(複素数) のような対応する型の
+    // 計算（value::overload）か、elementwise が有効なら要素ごとの計算にする
+    fn apply_overloaded(&self, name: &str, arg: &Value) -> Result<Value, String> {
+        if let Some(x) = arg.real() {
+            return self.apply_function(name, &[x]).map(Value::Scalar);
+        }
+        if let Some(value) = value::overload(name, arg) {
+            return value;
+        }
+        let elements = arg.try_map_elements(|x| self.apply_function(name, &[x]));
+        match elements {
+            Some(value) if self.elementwise => value,
+            Some(_) => Err(format!(
+                "{} の引数には数値を指定してください（{}が指定されています。要素ごとに計算する場合は elementwise を有効にしてください）",
+                name,
+                arg.describe()
+            )),
+            None => arg.as_scalar(&format!("{} の引数", name)).map(Value::Scalar),
+        }
+    }
+
+    // 式を評価し、型つきの値（整数・分数・複素数・ベクトル・行列・単位つきの量・真偽値）を返す。
+    // 演算子の優先順位は evaluate と同じで、evaluate はこの結果を実数として取り出したもの
+    pub fn evaluate_value(&self, expression: &str) -> Result<Value, String> {
+        self.evaluate_value_with(expression, &HashMap::new())
+    }
 
-        Self { allowed_functions }
+    pub fn evaluate_value_with(
+        &self,
+        expression: &str,
+        bindings: &HashMap<String, f64>,
+    ) -> Result<Value, String> {
+        let tokens = self.checked_tokenize(expression)?;
+        if tokens.is_empty() {
+            return Err(syntax_error("空の式です"));
+        }
+        let (value, pos) = self.value_expression(&tokens, 0, bindings)?;
+        check_unmatched_bar(&tokens, pos)?;
+        if matches!(tokens.get(pos), Some(Token::RightBracket)) {
+            return Err("対応する [ がない ] です".to_string());
+        }
+        self.check_consumed(expression, &tokens, pos)?;
+        Ok(value)
+    }
+
+    // 比較（a < b など）は最も優先順位が低く、1つの式に1つだけ書ける
+    fn value_expression(
+        &self,
+        tokens: &[Token],
+        pos: usize,
+        bindings: &HashMap<String, f64>,
+    ) -> Result<(Value, usize), String> {
+        let (left, pos) = self.value_conversion(tokens, pos, bindings)?;
+        let Some(Token::Relation(relation)) = tokens.get(pos).copied() else {
+            return Ok((left, pos));
+        };
+        let (right, pos) = self.value_conversion(tokens, pos + 1, bindings)?;
+        if matches!(tokens.get(pos), Some(Token::Relation(_))) {
+            return Err(syntax_error(
+                "比較演算子は1つの式に1つにしてください（0 < x < 1 は書けません）",
+            ));
+        }
+        Ok((left.compare(relation, right)?, pos))
+    }
+
+    // 5 km in mi の単位の換算。+ - より弱く、比較より強く結合する
+    fn value_conversion(
+        &self,
+        tokens: &[Token],
+        pos: usize,
+        bindings: &HashMap<String, f64>,
+    ) -> Result<(Value, usize), String> {
+        let (mut value, mut pos) = self.value_sum(tokens, pos, bindings)?;
+        while let Some(Token::In) = tokens.get(pos) {
+            let (unit, new_pos) = self.unit_expression(tokens, pos + 1)?;
+            value = value.convert_to(unit)?;
+            pos = new_pos;
+        }
+        Ok((value, pos))
+    }
+
+    // in の右辺の単位（mi, km/h, m^2, N*m）
+    fn unit_expression(&self, tokens: &[Token], pos: usize) -> Result<(Unit, usize), String> {
+        let (mut unit, mut pos) = self.unit_power(tokens, pos)?;
+        while let Some(Token::Operator(operator @ ('*' | '/'))) = tokens.get(pos) {
+            let (right, new_pos) = self.unit_power(tokens, pos + 1)?;
+            unit = if *operator == '*' {
+                unit.multiply(&right)?
+            } else {
+                unit.divide(&right)?
+            };
+            pos = new_pos;
+        }
+        Ok((unit, pos))
+    }
+
+    fn unit_power(&self, tokens: &[Token], pos: usize) -> Result<(Unit, usize), String> {
+        let (unit, pos) = match tokens.get(pos) {
+            Some(Token::Variable(name) | Token::Function(name)) => (
+                Unit::lookup(name).ok_or_else(|| format!("未知の単位: {}", name))?,
+                pos + 1,
+            ),
+            Some(Token::LeftParen) => {
+                let (unit, pos) = self.unit_expression(tokens, pos + 1)?;
+                if !matches!(tokens.get(pos), Some(Token::RightParen)) {
+                    return Err(syntax_error("対応する右括弧がありません"));
+                }
+                (unit, pos + 1)
+            }
+            _ => return Err("in の後に単位を指定してください（5 km in mi）".to_string()),
+        };
+        match (tokens.get(pos), tokens.get(pos + 1)) {
+            (Some(Token::Operator('^')), Some(Token::Number(exponent, _)))
+                if exponent.fract() == 0.0 && *exponent <= f64::from(i8::MAX) =>
+            {
+                Ok((unit.power(*exponent as i32)?, pos + 2))
+            }
+            (Some(Token::Operator('^')), _) => {
+                Err("in の単位の ^ の指数は正の整数にしてください（m^2）".to_string())
+            }
+            _ => Ok((unit, pos)),
+        }
+    }
+
+    fn value_sum(
+        &self,
+        tokens: &[Token],
+        pos: usize,
+        bindings: &HashMap<String, f64>,
+    ) -> Result<(Value, usize), String> {
+        let (mut left, mut pos) = self.value_term(tokens, pos, bindings)?;
+        while let Some(Token::Operator(operator @ ('+' | '-'))) = tokens.get(pos) {
+            let (mut right, new_pos) = self.value_term(tokens, pos + 1, bindings)?;
+            if is_percent_operand(tokens, pos + 1, new_pos) {
+                right = left.clone().checked_mul(right)?;
+            }
+            left = if *operator == '+' {
+                left.checked_add(right)?
+            } else {
+                left.checked_sub(right)?
+            };
+            pos = new_pos;
+        }
+        Ok((left, pos))
+    }
+
+    fn value_term(
+        &self,
+        tokens: &[Token],
+        pos: usize,
+        bindings: &HashMap<String, f64>,
+    ) -> Result<(Value, usize), String> {
+        let start = pos;
+        let (mut left, mut pos) = self.value_power(tokens, pos, bindings)?;
+        while let Some(Token::Operator(operator @ ('*' | '/'))) = tokens.get(pos) {
+            // 20 degC のように数値のリテラルに温度の目盛りの単位を続けた場合はその温度。
+            // 2 * 20 degC は 40 degC でなく温度の数値倍なのでエラーにする
+            if let (Some(value), Some(unit)) = (
+                left.real().filter(|_| *operator == '*'),
+                self.temperature_unit(tokens, pos + 1, bindings),
+            ) {
+                if !is_number_literal(tokens, start, pos) {
+                    return Err(value::absolute_scaling(&unit));
+                }
+                left = Value::Quantity(value, unit);
+                pos += 2;
+                continue;
+            }
+            let (right, new_pos) = self.value_power(tokens, pos + 1, bindings)?;
+            left = if *operator == '*' {
+                left.checked_mul(right)?
+            } else {
+                left.checked_div(right)?
+            };
+            pos = new_pos;
+        }
+        Ok((left, pos))
+    }
+
+    // tokens[pos] が温度の目盛りの単位の名前（変数・定数でなく、直後に ^ や ( がない）ならその単位
+    fn temperature_unit(
+        &self,
+        tokens: &[Token],
+        pos: usize,
+        bindings: &HashMap<String, f64>,
+    ) -> Option<Unit> {
+        let (Some(Token::Variable(name) | Token::Function(name)), next) =
+            (tokens.get(pos), tokens.get(pos + 1))
+        else {
+            return None;
+        };
+        if matches!(next, Some(Token::Operator('^') | Token::LeftParen))
+            || self.bound_value(name, bindings).is_some()
+        {
+            return None;
+        }
+        Unit::lookup(name).filter(Unit::is_absolute)
+    }
+
+    // 引数・変数・定数の値（この順に優先する）
+    fn bound_value(&self, name: &str, bindings: &HashMap<String, f64>) -> Option<f64> {
+        bindings
+            .get(name)
+            .or_else(|| self.variables.get(name))
+            .or_else(|| self.constants.get(name))
+            .copied()
+    }
+
+    fn value_power(
+        &self,
+        tokens: &[Token],
+        pos: usize,
+        bindings: &HashMap<String, f64>,
+    ) -> Result<(Value, usize), String> {
+        let (mut left, mut pos) = self.value_factor(tokens, pos, bindings)?;
+        while let Some(Token::Operator('^')) = tokens.get(pos) {
+            let (right, new_pos) = self.value_factor(tokens, pos + 1, bindings)?;
+            if let (Some(profile), Some(exponent)) = (&self.safe_profile, right.real()) {
+                profile.check_exponent(exponent)?;
+            }
+            left = left.checked_pow(right)?;
+            pos = new_pos;
+        }
+        Ok((left, pos))
+    }
+
+    fn value_factor(
+        &self,
+        tokens: &[Token],
+        pos: usize,
+        bindings: &HashMap<String, f64>,
+    ) -> Result<(Value, usize), String> {
+        match tokens.get(pos).copied() {
+            None => Err(syntax_error("予期しない式の終了")),
+            Some(Token::Number(n, _)) => Ok((Value::from_literal(n), pos + 1)),
+            Some(Token::Percent(n, _)) => Ok((Value::Scalar(n / 100.0), pos + 1)),
+            // 変数・定数として定義されていない名前は、虚数単位か単位の名前なら 1i や 1 km とする
+            Some(Token::Variable(name)) => {
+                if let Some(value) = self.bound_value(name, bindings) {
+                    return Ok((Value::Scalar(value), pos + 1));
+                }
+                if name == value::IMAGINARY_UNIT {
+                    return Ok((Value::Complex(0.0, 1.0), pos + 1));
+                }
+                Unit::lookup(name)
+                    .map(|unit| (Value::Quantity(1.0, unit), pos + 1))
+                    .ok_or_else(|| format!("未定義の変数: {}", name))
+            }
+            Some(Token::Operator('-')) => {
+                let (operand, new_pos) = self.value_factor(tokens, pos + 1, bindings)?;
+                Ok((operand.negate()?, new_pos))
+            }
+            Some(Token::Operator('+')) => self.value_factor(tokens, pos + 1, bindings),
+            Some(Token::LeftParen) => {
+                check_empty_parens(tokens, pos)?;
+                let (value, pos) = self.value_expression(tokens, pos + 1, bindings)?;
+                if !matches!(tokens.get(pos), Some(Token::RightParen)) {
+                    return Err(syntax_error("対応する右括弧がありません"));
+                }
+                Ok((value, pos + 1))
+            }
+            Some(Token::LeftBracket) => {
+                if matches!(tokens.get(pos + 1), Some(Token::RightBracket)) {
+                    return Err(syntax_error("空の [] は使えません"));
+                }
+                let mut elements = Vec::new();
+                let mut pos = pos;
+                loop {
+                    let (element, new_pos) = self.value_expression(tokens, pos + 1, bindings)?;
+                    elements.push(element);
+                    pos = new_pos;
+                    if !matches!(tokens.get(pos), Some(Token::Comma)) {
+                        break;
+                    }
+                }
+                if !matches!(tokens.get(pos), Some(Token::RightBracket)) {
+                    return Err("対応する ] がありません".to_string());
+                }
+                let value = Value::from_elements(elements)?;
+                if let Some(profile) = &self.safe_profile {
+                    profile.check_value(&value)?;
+                }
+                Ok((value, pos + 1))
+            }
+            Some(Token::Bar) => {
+                let (operand, pos) = self.value_expression(tokens, pos + 1, bindings)?;
+                if !matches!(tokens.get(pos), Some(Token::Bar)) {
+                    return Err(syntax_error("絶対値の | が閉じられていません"));
+                }
+                Ok((self.apply_overloaded("abs", &operand)?, pos + 1))
+            }
+            Some(Token::Sqrt) => {
+                let (operand, pos) = self.value_factor(tokens, pos + 1, bindings)?;
+                if let Some(x) = operand.real().filter(|x| *x < 0.0) {
+                    return Err(domain_error(format!(
+                        "負の数の平方根は実数になりません: √{}",
+                        x
+                    )));
+                }
+                Ok((self.apply_overloaded("sqrt", &operand)?, pos))
+            }
+            // min（分）のように関数と同じ名前の単位は、( が続かなければ単位とする
+            Some(Token::Function(name))
+                if !matches!(tokens.get(pos + 1), Some(Token::LeftParen)) =>
+            {
+                Unit::lookup(name)
+                    .map(|unit| (Value::Quantity(1.0, unit), pos + 1))
+                    .ok_or_else(|| syntax_error("関数の後に左括弧が必要です"))
+            }
+            Some(Token::Function(name)) => {
+                let mut args = Vec::new();
+                let mut pos = pos + 1;
+                loop {
+                    if is_empty_call(name, tokens, pos + 1, args.len())? {
+                        pos += 1;
+                        break;
+                    }
+                    let (arg, new_pos) = self.value_expression(tokens, pos + 1, bindings)?;
+                    args.push(arg);
+                    self.check_argument_count(args.len())?;
+                    pos = new_pos;
+                    if !matches!(tokens.get(pos), Some(Token::Comma)) {
+                        break;
+                    }
+                }
+                if !matches!(tokens.get(pos), Some(Token::RightParen)) {
+                    return Err(syntax_error("関数の引数の後に右括弧が必要です"));
+                }
+                if !self.is_function(name) {
+                    return Ok((value::call(name, &args)?, pos + 1));
+                }
+                if let [arg] = args.as_slice() {
+                    return Ok((self.apply_overloaded(name, arg)?, pos + 1));
+                }
+                let values = args
+                    .iter()
+                    .map(|arg| arg.as_scalar(&format!("{} の引数", name)))
+                    .collect::<Result<Vec<f64>, String>>()?;
+                Ok((Value::Scalar(self.apply_function(name, &values)?), pos + 1))
+            }
+            Some(token) => Err(syntax_error(format!("予期しないトークン: {:?}", token))),
+        }
+    }
+
+    fn evaluate_measured(
+        &self,
+        expression: &str,
+        bindings: &HashMap<String, f64>,
+    ) -> Result<Measured, String> {
+        let tokens = self.checked_tokenize(expression)?;
+        self.evaluate_tokens(expression, &tokens, bindings)
+    }
+
+    fn checked_tokenize<'a>(&self, expression: &'a str) -> Result<Vec<Token<'a>>, String> {
+        // 入力長制限（DoS攻撃防止）
+        if expression.len() > self.limits.max_length {
+            return Err(format!(
+                "式が長すぎます（最大{}文字）",
+                self.limits.max_length
+            ));
+        }
+
+        // 危険な文字をチェック（| は絶対値の記号として構文解析で対応を検証する）
+        if expression.contains(';') || expression.contains('&') {
+            return Err(syntax_error("不正な文字が含まれています"));
+        }
+
+        let tokens = self.tokenize(expression, &mut Vec::new())?;
+        if tokens.len() > self.limits.max_tokens {
+            return Err(format!(
+                "式のトークンが多すぎます: {} 個（最大{}個）",
+                tokens.len(),
+                self.limits.max_tokens
+            ));
+        }
+        Ok(tokens)
+    }
+
+    // 補正を加えた場合は corrections にその説明を追加する
+    fn tokenize<'a>(
+        &self,
+        expression: &'a str,
+        corrections: &mut Vec<String>,
+    ) -> Result<Vec<Token<'a>>, String> {
+        self.tokenize_with_offsets(expression, corrections, &mut Vec::new())
+    }
+
+    // offsets には各トークンが始まるバイト位置を入れる（補った * は次のトークンの位置）
+    fn tokenize_with_offsets<'a>(
+        &self,
+        expression: &'a str,
+        corrections: &mut Vec<String>,
+        offsets: &mut Vec<usize>,
+    ) -> Result<Vec<Token<'a>>, String> {
+        let mut tokens = Vec::new();
+        let mut chars = expression.char_indices().peekable();
+
+        while let Some(&(start, ch)) = chars.peek() {
+            let ch = match UNICODE_SUBSTITUTES
+                .iter()
+                .find(|(unicode, _)| *unicode == ch)
+            {
+                Some(&(unicode, ascii)) => {
+                    if self.strict {
+                        return Err(format!(
+                            "strict モードでは使えない文字です: {}（{} を使ってください）",
+                            unicode, ascii
+                        ));
+                    }
+                    let correction = if ascii == ' ' {
+                        format!(
+                            "U+{:04X} の空白を通常の空白として扱いました",
+                            unicode as u32
+                        )
+                    } else {
+                        format!("{} を {} として扱いました", unicode, ascii)
+                    };
+                    if !corrections.contains(&correction) {
+                        corrections.push(correction);
+                    }
+                    ascii
+                }
+                None => ch,
+            };
+            match ch {
+                // 改行やタブで複数行に整形した式も受け付ける
+                ' ' | '\t' | '\n' | '\r' => {
+                    chars.next();
+                }
+                // # から行末まではコメント
+                '#' => while chars.next_if(|&(_, c)| c != '\n').is_some() {},
+                '0'..='9' | '.' => {
+                    let (mut number, mut figures) = self.parse_number(expression, &mut chars)?;
+                    if let Some(exponent) = self.si_prefix(&mut chars) {
+                        // 10^-3 などは正確に表せないため、負の指数は割り算にする
+                        number = if exponent >= 0 {
+                            number * 10f64.powi(exponent)
+                        } else {
+                            number / 10f64.powi(-exponent)
+                        };
+                        figures.place += exponent;
+                    }
+                    let end = chars.peek().map_or(expression.len(), |&(index, _)| index);
+                    check_literal_range(number, &expression[start..end])?;
+                    if chars.next_if(|&(_, c)| c == '%').is_some() {
+                        tokens.push(Token::Percent(number, figures));
+                    } else {
+                        tokens.push(Token::Number(number, figures));
+                    }
+                }
+                '+' | '-' | '*' | '/' | '^' => {
+                    chars.next();
+                    tokens.push(Token::Operator(ch));
+                }
+                '(' => {
+                    chars.next();
+                    tokens.push(Token::LeftParen);
+                }
+                ')' => {
+                    chars.next();
+                    tokens.push(Token::RightParen);
+                }
+                ',' => {
+                    chars.next();
+                    tokens.push(Token::Comma);
+                }
+                '[' => {
+                    chars.next();
+                    tokens.push(Token::LeftBracket);
+                }
+                ']' => {
+                    chars.next();
+                    tokens.push(Token::RightBracket);
+                }
+                '√' => {
+                    chars.next();
+                    tokens.push(Token::Sqrt);
+                }
+                '<' | '>' | '=' | '!' | '≤' | '≥' | '≠' => {
+                    let start = chars.peek().map_or(expression.len(), |&(index, _)| index);
+                    match constraints::relation_prefix(&expression[start..]) {
+                        Some((symbol, relation)) if symbol != "=" => {
+                            for _ in symbol.chars() {
+                                chars.next();
+                            }
+                            tokens.push(Token::Relation(relation));
+                        }
+                        _ => {
+                            return Err(syntax_error(format!(
+                                "不正な文字: {}（比較には ==, !=, <, <=, >, >= を使ってください）",
+                                ch
+                            )))
+                        }
+                    }
+                }
+                '|' => {
+                    chars.next();
+                    tokens.push(Token::Bar);
+                }
+                'a'..='z' | 'A'..='Z' => {
+                    let name = self.parse_identifier(expression, &mut chars);
+                    let is_call =
+                        chars.clone().map(|(_, c)| c).find(|c| !c.is_whitespace()) == Some('(');
+                    if name == CONVERSION_KEYWORD
+                        && !self.variables.contains_key(name)
+                        && !self.constants.contains_key(name)
+                    {
+                        tokens.push(Token::In);
+                    } else if self.is_function(name)
+                        || (is_call && value::FUNCTIONS.contains(&name))
+                    {
+                        tokens.push(Token::Function(name));
+                    } else if is_call {
+                        return Err(format!("未サポートの関数: {}", name));
+                    } else {
+                        tokens.push(Token::Variable(name));
+                    }
+                }
+                _ => {
+                    return Err(syntax_error(format!("不正な文字: {}", ch)));
+                }
+            }
+            offsets.resize(tokens.len(), start);
+        }
+
+        check_operators(&tokens)?;
+        self.insert_implicit_multiplication(tokens, corrections, offsets)
+    }
+
+    // 式の解析が tokens の途中で終わった場合（2 + 3 5 の 5 など）は、読み残した最初の
+    // トークンの位置（1から数えた文字数）を示すエラーにする
+    fn check_consumed(&self, expression: &str, tokens: &[Token], pos: usize) -> Result<(), String> {
+        let Some(&token) = tokens.get(pos) else {
+            return Ok(());
+        };
+        let mut offsets = Vec::new();
+        let _ = self.tokenize_with_offsets(expression, &mut Vec::new(), &mut offsets);
+        let offset = offsets.get(pos).copied().unwrap_or(expression.len());
+        let column = expression[..offset].chars().count() + 1;
+        if token == Token::RightParen {
+            return Err(syntax_error(format!(
+                "{} 文字目の ) に対応する左括弧がありません",
+                column
+            )));
+        }
+        Err(syntax_error(format!(
+            "{} 文字目の {} から後を解釈できません（演算子が抜けていないか確認してください）",
+            column,
+            token_text(token)
+        )))
+    }
+
+    // 2x, 2(3 + 4), (a + b)(a - b), 2sqrt(2) のように演算子を省いた掛け算に * を補う
+    fn insert_implicit_multiplication<'a>(
+        &self,
+        tokens: Vec<Token<'a>>,
+        corrections: &mut Vec<String>,
+        offsets: &mut Vec<usize>,
+    ) -> Result<Vec<Token<'a>>, String> {
+        let mut result: Vec<Token> = Vec::with_capacity(tokens.len());
+        let mut result_offsets = Vec::with_capacity(offsets.len());
+        for (token, offset) in tokens.into_iter().zip(offsets.iter().copied()) {
+            if let Some(&previous) = result.last() {
+                if is_implicit_product(previous, token) {
+                    let (left, right) = (token_text(previous), token_text(token));
+                    if self.strict {
+                        return Err(format!(
+                            "strict モードでは暗黙の掛け算は使えません: {} と {} の間に * を書いてください",
+                            left, right
+                        ));
+                    }
+                    // 1/2x は (1/2)*x と計算するが、1/(2x) の意味で書かれることもある
+                    let start = factor_start(&result);
+                    if start > 0 && result[start - 1] == Token::Operator('/') {
+                        corrections.push(format!(
+                            "/ の後の暗黙の掛け算は / と同じ優先順位で左から計算しました（1/2x は (1/2)*x）。{} * {} を先に計算する場合は括弧を付けてください",
+                            left, right
+                        ));
+                    } else {
+                        corrections.push(format!(
+                            "{} と {} の間の掛け算の * を補いました",
+                            left, right
+                        ));
+                    }
+                    result.push(Token::Operator('*'));
+                    result_offsets.push(offset);
+                }
+            }
+            result.push(token);
+            result_offsets.push(offset);
+        }
+        *offsets = result_offsets;
+        Ok(result)
+    }
+
+    // 数値の直後の SI 接頭辞を読む。4.7k は読むが、2max や 3mm のように識別子が続く場合は読まない
+    fn si_prefix(&self, chars: &mut std::iter::Peekable<std::str::CharIndices>) -> Option<i32> {
+        if !self.si_prefixes {
+            return None;
+        }
+        let mut lookahead = chars.clone();
+        let (_, prefix) = lookahead.next()?;
+        let &(_, exponent) = SI_PREFIXES.iter().find(|(c, _)| *c == prefix)?;
+        if matches!(lookahead.next(), Some((_, c)) if c.is_alphanumeric() || c == '_') {
+            return None;
+        }
+        chars.next();
+        Some(exponent)
+    }
+
+    fn parse_number(
+        &self,
+        expression: &str,
+        chars: &mut std::iter::Peekable<std::str::CharIndices>,
+    ) -> Result<(f64, Figures), String> {
+        let start = chars.peek().map_or(expression.len(), |&(index, _)| index);
+        let mut has_dot = false;
+
+        while let Some(&(_, ch)) = chars.peek() {
+            match ch {
+                '0'..='9' => {
+                    chars.next();
+                }
+                '.' if !has_dot => {
+                    has_dot = true;
+                    chars.next();
+                }
+                _ => break,
+            }
+        }
+        let mantissa_end = chars.peek().map_or(expression.len(), |&(index, _)| index);
+
+        // 1.5e3 や 1e-400 の指数。e の後に数字（符号も可）が続かなければ e は識別子として読む
+        let mut lookahead = chars.clone();
+        if matches!(lookahead.next(), Some((_, 'e' | 'E'))) {
+            lookahead.next_if(|&(_, c)| matches!(c, '+' | '-'));
+            if matches!(lookahead.peek(), Some((_, '0'..='9'))) {
+                while lookahead.next_if(|&(_, c)| c.is_ascii_digit()).is_some() {}
+                *chars = lookahead;
+            }
+        }
+
+        let end = chars.peek().map_or(expression.len(), |&(index, _)| index);
+        let number_str = &expression[start..end];
+        let number = number_str
+            .parse::<f64>()
+            .map_err(|_| format!("数値の解析に失敗: {}", number_str))?;
+        let mut figures = Figures::of_literal(&expression[start..mantissa_end]);
+        if mantissa_end < end {
+            // 範囲外の指数はリテラルの範囲の検査でエラーになる
+            let exponent = expression[mantissa_end + 1..end]
+                .parse::<i32>()
+                .unwrap_or(0);
+            figures.place = figures.place.saturating_add(exponent);
+        }
+        Ok((number, figures))
+    }
+
+    fn parse_identifier<'a>(
+        &self,
+        expression: &'a str,
+        chars: &mut std::iter::Peekable<std::str::CharIndices>,
+    ) -> &'a str {
+        let start = chars.peek().map_or(expression.len(), |&(index, _)| index);
+
+        while let Some(&(_, ch)) = chars.peek() {
+            if ch.is_alphanumeric() || ch == '_' {
+                chars.next();
+            } else {
+                break;
+            }
+        }
+
+        let end = chars.peek().map_or(expression.len(), |&(index, _)| index);
+        // L/100km のように / や数字を含む単位の名前は、まとめて1つの名前として読む
+        let compound = units::COMPOUND_NAMES.iter().find(|name| {
+            let rest = &expression[start..];
+            rest.starts_with(*name)
+                && !rest[name.len()..].starts_with(|c: char| c.is_alphanumeric() || c == '_')
+        });
+        if let Some(name) = compound {
+            let identifier = &expression[start..end];
+            if !self.variables.contains_key(identifier) && !self.constants.contains_key(identifier)
+            {
+                let end = start + name.len();
+                while chars.peek().is_some_and(|&(index, _)| index < end) {
+                    chars.next();
+                }
+                return &expression[start..end];
+            }
+        }
+        &expression[start..end]
+    }
+
+    fn evaluate_tokens(
+        &self,
+        expression: &str,
+        tokens: &[Token],
+        bindings: &HashMap<String, f64>,
+    ) -> Result<Measured, String> {
+        if tokens.is_empty() {
+            return Err(syntax_error("空の式です"));
+        }
+
+        let (result, pos) = self.evaluate_expression(tokens, 0, bindings)?;
+        check_unmatched_bar(tokens, pos)?;
+        self.check_consumed(expression, tokens, pos)?;
+        Ok(result)
+    }
+
+    fn evaluate_expression(
+        &self,
+        tokens: &[Token],
+        mut pos: usize,
+        bindings: &HashMap<String, f64>,
+    ) -> Result<(Measured, usize), String> {
+        let (mut left, new_pos) = self.evaluate_term(tokens, pos, bindings)?;
+        pos = new_pos;
+
+        while pos < tokens.len() {
+            match &tokens[pos] {
+                Token::Operator('+') => {
+                    pos += 1;
+                    let (right, new_pos) = self.evaluate_term(tokens, pos, bindings)?;
+                    let right = percent_of(&left, right, is_percent_operand(tokens, pos, new_pos));
+                    let value = left.value + right.value;
+                    left = left.combine('+', right, value);
+                    pos = new_pos;
+                }
+                Token::Operator('-') => {
+                    pos += 1;
+                    let (right, new_pos) = self.evaluate_term(tokens, pos, bindings)?;
+                    let right = percent_of(&left, right, is_percent_operand(tokens, pos, new_pos));
+                    let value = left.value - right.value;
+                    left = left.combine('-', right, value);
+                    pos = new_pos;
+                }
+                _ => break,
+            }
+        }
+
+        Ok((left, pos))
+    }
+
+    fn evaluate_term(
+        &self,
+        tokens: &[Token],
+        mut pos: usize,
+        bindings: &HashMap<String, f64>,
+    ) -> Result<(Measured, usize), String> {
+        let (mut left, new_pos) = self.evaluate_power(tokens, pos, bindings)?;
+        pos = new_pos;
+
+        while pos < tokens.len() {
+            match &tokens[pos] {
+                Token::Operator('*') => {
+                    pos += 1;
+                    let (right, new_pos) = self.evaluate_power(tokens, pos, bindings)?;
+                    let value = left.value * right.value;
+                    left = left.combine('*', right, value);
+                    pos = new_pos;
+                }
+                Token::Operator('/') => {
+                    pos += 1;
+                    let (right, new_pos) = self.evaluate_power(tokens, pos, bindings)?;
+                    if right.value == 0.0 {
+                        return Err(division_by_zero());
+                    }
+                    let value = left.value / right.value;
+                    left = left.combine('/', right, value);
+                    pos = new_pos;
+                }
+                _ => break,
+            }
+        }
+
+        Ok((left, pos))
+    }
+
+    fn evaluate_power(
+        &self,
+        tokens: &[Token],
+        mut pos: usize,
+        bindings: &HashMap<String, f64>,
+    ) -> Result<(Measured, usize), String> {
+        let (mut left, new_pos) = self.evaluate_factor(tokens, pos, bindings)?;
+        pos = new_pos;
+
+        while pos < tokens.len() {
+            match &tokens[pos] {
+                Token::Operator('^') => {
+                    pos += 1;
+                    let (right, new_pos) = self.evaluate_factor(tokens, pos, bindings)?;
+                    if let Some(profile) = &self.safe_profile {
+                        profile.check_exponent(right.value)?;
+                    }
+                    let value = left.value.powf(right.value);
+
+                    // べき乗の結果をチェック
+                    if !value.is_finite() {
+                        return Err(domain_error("べき乗の計算結果が無効です"));
+                    }
+                    left = left.combine('^', right, value);
+
+                    pos = new_pos;
+                }
+                _ => break,
+            }
+        }
+
+        Ok((left, pos))
+    }
+
+    fn evaluate_factor(
+        &self,
+        tokens: &[Token],
+        mut pos: usize,
+        bindings: &HashMap<String, f64>,
+    ) -> Result<(Measured, usize), String> {
+        if pos >= tokens.len() {
+            return Err(syntax_error("予期しない式の終了"));
+        }
+
+        match tokens[pos] {
+            Token::Number(n, figures) => Ok((Measured::literal(n, figures), pos + 1)),
+            Token::Percent(n, figures) => {
+                Ok((Measured::literal(n, figures).map(n / 100.0), pos + 1))
+            }
+            Token::Variable(name) => bindings
+                .get(name)
+                .or_else(|| self.variables.get(name))
+                .or_else(|| self.constants.get(name))
+                .map(|value| (Measured::exact(*value), pos + 1))
+                .ok_or_else(|| format!("未定義の変数: {}", name)),
+            Token::Operator('-') => {
+                pos += 1;
+                let (operand, new_pos) = self.evaluate_factor(tokens, pos, bindings)?;
+                let value = -operand.value;
+                Ok((operand.map(value), new_pos))
+            }
+            Token::Operator('+') => {
+                pos += 1;
+                self.evaluate_factor(tokens, pos, bindings)
+            }
+            Token::LeftParen => {
+                check_empty_parens(tokens, pos)?;
+                pos += 1;
+                let (result, new_pos) = self.evaluate_expression(tokens, pos, bindings)?;
+                pos = new_pos;
+                if pos >= tokens.len() || !matches!(tokens[pos], Token::RightParen) {
+                    return Err(syntax_error("対応する右括弧がありません"));
+                }
+                Ok((result, pos + 1))
+            }
+            // |x| は abs(x) と同じ。中の式は閉じの | の手前で終わる
+            Token::Bar => {
+                let (operand, new_pos) = self.evaluate_expression(tokens, pos + 1, bindings)?;
+                if !matches!(tokens.get(new_pos), Some(Token::Bar)) {
+                    return Err(syntax_error("絶対値の | が閉じられていません"));
+                }
+                let value = operand.value.abs();
+                Ok((operand.map(value), new_pos + 1))
+            }
+            Token::Sqrt => {
+                let (operand, new_pos) = self.evaluate_factor(tokens, pos + 1, bindings)?;
+                let value = operand.value.sqrt();
+                if !value.is_finite() {
+                    return Err(domain_error(format!(
+                        "負の数の平方根は実数になりません: √{}",
+                        operand.value
+                    )));
+                }
+                Ok((operand.map(value), new_pos))
+            }
+            Token::Function(name) => {
+                let (args, new_pos) = self.evaluate_arguments(name, tokens, pos + 1, bindings)?;
+                let values: Vec<f64> = args.iter().map(|arg| arg.value).collect();
+                let result = self.apply_function(name, &values)?;
+                let measured = if self.multi_functions.contains_key(name) {
+                    least_precise(args)
+                } else {
+                    single_argument(name, args)?
+                };
+                Ok((measured.map(result), new_pos))
+            }
+            Token::LeftBracket => Err(ARRAY_UNSUPPORTED.to_string()),
+            _ => Err(syntax_error(format!(
+                "予期しないトークン: {:?}",
+                tokens[pos]
+            ))),
+        }
+    }
+
+    // 組み込み関数と数式で定義された関数を数値の引数で呼び出す
+    fn apply_function(&self, name: &str, args: &[f64]) -> Result<f64, String> {
+        let result = if let Some(multi) = self.multi_functions.get(name) {
+            multi.check_arity(name, args.len())?;
+            if let Some(profile) = &self.safe_profile {
+                profile.check_call(name, args)?;
+            }
+            let result = multi.call(name, args)?;
+            if self.returns_degrees(name) {
+                result.to_degrees()
+            } else {
+                result
+            }
+        } else {
+            let x = single_argument(name, args.to_vec())?;
+            if name == "round" {
+                round_to(x, 0, self.rounding_mode)
+            } else if let Some(function) = self.allowed_functions.get(name) {
+                let x = if self.angle_mode == AngleMode::Degrees && TRIG_FUNCTIONS.contains(&name) {
+                    x.to_radians()
+                } else {
+                    x
+                };
+                function.call(name, x)?
+            } else if let Some(formula) = self.custom_functions.get(name) {
+                self.call_custom_function(name, formula, x)?
+            } else {
+                return Err(format!("未知の関数: {}", name));
+            }
+        };
+
+        // NaN や無限大のチェック
+        if !result.is_finite() {
+            return Err(domain_error("計算結果が無効です（NaN または 無限大）"));
+        }
+        Ok(result)
+    }
+
+    // 関数名の後の (a, b, ...) を評価する。返す位置は閉じ括弧の次
+    fn evaluate_arguments(
+        &self,
+        name: &str,
+        tokens: &[Token],
+        mut pos: usize,
+        bindings: &HashMap<String, f64>,
+    ) -> Result<(Vec<Measured>, usize), String> {
+        if !matches!(tokens.get(pos), Some(Token::LeftParen)) {
+            return Err(syntax_error("関数の後に左括弧が必要です"));
+        }
+        let mut args = Vec::new();
+        loop {
+            if is_empty_call(name, tokens, pos + 1, args.len())? {
+                pos += 1;
+                break;
+            }
+            let (arg, new_pos) = self.evaluate_expression(tokens, pos + 1, bindings)?;
+            args.push(arg);
+            self.check_argument_count(args.len())?;
+            pos = new_pos;
+            if !matches!(tokens.get(pos), Some(Token::Comma)) {
+                break;
+            }
+        }
+        if !matches!(tokens.get(pos), Some(Token::RightParen)) {
+            return Err(syntax_error("関数の引数の後に右括弧が必要です"));
+        }
+        Ok((args, pos + 1))
+    }
+
+    fn returns_degrees(&self, name: &str) -> bool {
+        self.angle_mode == AngleMode::Degrees && INVERSE_TRIG_FUNCTIONS.contains(&name)
+    }
+
+    fn check_argument_count(&self, count: usize) -> Result<(), String> {
+        if count > self.limits.max_function_args {
+            return Err(format!(
+                "関数の引数が多すぎます（最大{}個）",
+                self.limits.max_function_args
+            ));
+        }
+        Ok(())
+    }
+
+    // 数式で定義された関数を、x に引数を束縛した新しいスコープで評価する
+    fn call_custom_function(&self, name: &str, formula: &str, arg: f64) -> Result<f64, String> {
+        if self.depth >= MAX_CALL_DEPTH {
+            return Err(format!("関数の呼び出しが深すぎます: {}", name));
+        }
+        let scope = Self {
+            allowed_functions: self.allowed_functions.clone(),
+            multi_functions: self.multi_functions.clone(),
+            custom_functions: self.custom_functions.clone(),
+            variables: HashMap::from([("x".to_string(), arg)]),
+            constants: self.constants.clone(),
+            angle_mode: self.angle_mode,
+            rounding_mode: self.rounding_mode,
+            depth: self.depth + 1,
+            limits: self.limits,
+            si_prefixes: self.si_prefixes,
+            strict: self.strict,
+            elementwise: self.elementwise,
+            safe_profile: self.safe_profile,
+        };
+        scope.evaluate(formula)
+    }
+}
+
+impl CompiledExpr {
+    // 正規化した式の木を S 式で表した文字列。空白や冗長な括弧、数値の表記（2 と 2.0）、
+    // 可換な演算（+ と *）の項の順序によらず、同じ計算なら同じ文字列になる。
+    // 変数・定数と数式で定義された関数は名前で表す
+    pub fn canonical(&self) -> String {
+        self.canonical_node(self.root)
+    }
+
+    // canonical の FNV-1a（64ビット）ハッシュを16進数で表したもの。
+    // Rust のバージョンやプラットフォームによらず同じ値になる
+    pub fn canonical_hash(&self) -> String {
+        let hash = self
+            .canonical()
+            .bytes()
+            .fold(FNV_OFFSET_BASIS, |hash, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+            });
+        format!("{:016x}", hash)
+    }
+
+    // 式を中置記法で表したもの。% は展開し（200 + 10% は 200 + 200 * 0.1）、括弧は必要な箇所だけに付ける
+    pub fn to_infix(&self) -> String {
+        self.infix_node(self.root)
+    }
+
+    fn infix_node(&self, node: NodeId) -> String {
+        match &self.nodes[node] {
+            Node::Number(n) => n.to_string(),
+            Node::Variable { name, .. } => name.clone(),
+            Node::Argument => "x".to_string(),
+            Node::Negate(operand) => {
+                let operand_text = self.infix_node(*operand);
+                match self.nodes[*operand] {
+                    Node::Binary(..) => format!("-({})", operand_text),
+                    _ => format!("-{}", operand_text),
+                }
+            }
+            Node::Binary(operator, left, right) => {
+                let parent_precedence = precedence(*operator);
+                let wrap = |child: NodeId, text: String, right_side: bool| {
+                    let needs_parens = match self.nodes[child] {
+                        Node::Binary(child_operator, ..) => {
+                            let child_precedence = precedence(child_operator);
+                            child_precedence < parent_precedence
+                                || (right_side && child_precedence == parent_precedence)
+                        }
+                        // -2^2 は (-2)^2 と解析されるため、読み違えないよう括弧を付ける
+                        Node::Negate(_) => *operator == '^' && !right_side,
+                        _ => false,
+                    };
+                    if needs_parens {
+                        format!("({})", text)
+                    } else {
+                        text
+                    }
+                };
+                format!(
+                    "{} {} {}",
+                    wrap(*left, self.infix_node(*left), false),
+                    operator,
+                    wrap(*right, self.infix_node(*right), true)
+                )
+            }
+            Node::Builtin { name, arg, .. } | Node::Custom { name, arg, .. } => {
+                format!("{}({})", name, self.infix_node(*arg))
+            }
+            Node::Multi { name, args, .. } => {
+                let args: Vec<String> = args.iter().map(|arg| self.infix_node(*arg)).collect();
+                format!("{}({})", name, args.join(", "))
+            }
+            Node::Round(_, arg) => format!("round({})", self.infix_node(*arg)),
+        }
+    }
+
+    fn canonical_node(&self, node: NodeId) -> String {
+        match &self.nodes[node] {
+            Node::Number(n) => n.to_string(),
+            Node::Variable { name, .. } => name.clone(),
+            Node::Argument => "x".to_string(),
+            Node::Negate(operand) => format!("(neg {})", self.canonical_node(*operand)),
+            Node::Binary(operator, left, right) => {
+                let mut operands = [self.canonical_node(*left), self.canonical_node(*right)];
+                if matches!(operator, '+' | '*') {
+                    operands.sort();
+                }
+                format!("({} {} {})", operator, operands[0], operands[1])
+            }
+            Node::Builtin {
+                name,
+                to_radians,
+                arg,
+                ..
+            } => {
+                // 度数法の三角関数は別の計算として区別する
+                let suffix = if *to_radians { "°" } else { "" };
+                format!("({}{} {})", name, suffix, self.canonical_node(*arg))
+            }
+            Node::Multi {
+                name,
+                to_degrees,
+                args,
+                ..
+            } => {
+                let suffix = if *to_degrees { "°" } else { "" };
+                let args: Vec<String> = args.iter().map(|arg| self.canonical_node(*arg)).collect();
+                format!("({}{} {})", name, suffix, args.join(" "))
+            }
+            Node::Round(mode, arg) => {
+                format!("(round:{} {})", mode.name(), self.canonical_node(*arg))
+            }
+            Node::Custom { name, arg, .. } => format!("({} {})", name, self.canonical_node(*arg)),
+        }
+    }
+
+    // 「この式 = other」を symbol について解いた右辺を中置記法で返す。symbol がこの式にちょうど
+    // 1回だけ現れ、途中の演算と関数の逆がわかる場合に限る（それ以外は None）。
+    // 偶数乗の逆のように解の一部だけを返す場合は、その旨を notes に追加する
+    #[cfg(feature = "symbolic")]
+    pub fn isolate(
+        &self,
+        symbol: &str,
+        other: &CompiledExpr,
+        notes: &mut Vec<String>,
+    ) -> Option<String> {
+        if self.occurrences(self.root, symbol) != 1 || other.occurrences(other.root, symbol) != 0 {
+            return None;
+        }
+        let mut node = self.root;
+        let mut side = other.infix_text(other.root);
+        loop {
+            match &self.nodes[node] {
+                Node::Variable { name, .. } if name == symbol => return Some(side.text),
+                Node::Negate(operand) => {
+                    side = InfixText::negate(side);
+                    node = *operand;
+                }
+                Node::Binary(operator, left, right) => {
+                    let in_left = self.occurrences(*left, symbol) == 1;
+                    let (inner, known) = if in_left {
+                        (*left, self.infix_text(*right))
+                    } else {
+                        (*right, self.infix_text(*left))
+                    };
+                    side = match (operator, in_left) {
+                        ('+', _) => InfixText::binary('-', side, known),
+                        ('-', true) => InfixText::binary('+', side, known),
+                        ('-', false) => InfixText::binary('-', known, side),
+                        ('*', _) => InfixText::binary('/', side, known),
+                        ('/', true) => InfixText::binary('*', side, known),
+                        ('/', false) => InfixText::binary('/', known, side),
+                        ('^', true) => {
+                            if let Node::Number(exponent) = self.nodes[*right] {
+                                if exponent % 2.0 == 0.0 {
+                                    notes.push(format!(
+                                        "偶数乗の逆のため、正の解だけを返します（符号を反転した値も解です）: {}",
+                                        self.infix_node(*right)
+                                    ));
+                                }
+                            }
+                            let one = InfixText::atom("1".to_string());
+                            InfixText::binary('^', side, InfixText::binary('/', one, known))
+                        }
+                        ('^', false) => InfixText::binary(
+                            '/',
+                            InfixText::call("ln", &[side]),
+                            InfixText::call("ln", &[known]),
+                        ),
+                        _ => return None,
+                    };
+                    node = inner;
+                }
+                Node::Builtin { name, arg, .. } => {
+                    side = match name.as_str() {
+                        "sqrt" => InfixText::binary('^', side, InfixText::atom("2".to_string())),
+                        "cbrt" => InfixText::binary('^', side, InfixText::atom("3".to_string())),
+                        "exp" => InfixText::call("ln", &[side]),
+                        "expm1" => InfixText::call("ln1p", &[side]),
+                        _ => return None,
+                    };
+                    node = *arg;
+                }
+                Node::Multi { name, args, .. } => {
+                    let position = args
+                        .iter()
+                        .position(|arg| self.occurrences(*arg, symbol) == 1)?;
+                    let known: Vec<InfixText> =
+                        args.iter().map(|arg| self.infix_text(*arg)).collect();
+                    side = match (name.as_str(), position) {
+                        ("ln", 0) => InfixText::call("exp", &[side]),
+                        ("ln1p", 0) => InfixText::call("expm1", &[side]),
+                        ("log10", 0) => {
+                            InfixText::binary('^', InfixText::atom("10".to_string()), side)
+                        }
+                        ("log2", 0) => {
+                            InfixText::binary('^', InfixText::atom("2".to_string()), side)
+                        }
+                        // log(x, b) = R → x = b^R、log(x, b) = R → b = x^(1/R)
+                        ("log", 0) => InfixText::binary('^', known[1].clone(), side),
+                        ("log", 1) => {
+                            let one = InfixText::atom("1".to_string());
+                            InfixText::binary(
+                                '^',
+                                known[0].clone(),
+                                InfixText::binary('/', one, side),
+                            )
+                        }
+                        // root(x, n) = R → x = R^n
+                        ("root", 0) => InfixText::binary('^', side, known[1].clone()),
+                        _ => return None,
+                    };
+                    node = args[position];
+                }
+                _ => return None,
+            }
+        }
+    }
+
+    // node 以下（呼び出す数式で定義された関数の本体を含む）に symbol が現れる回数
+    #[cfg(feature = "symbolic")]
+    fn occurrences(&self, node: NodeId, symbol: &str) -> usize {
+        match &self.nodes[node] {
+            Node::Variable { name, .. } => usize::from(name == symbol),
+            Node::Number(_) | Node::Argument => 0,
+            Node::Negate(operand)
+            | Node::Round(_, operand)
+            | Node::Builtin { arg: operand, .. } => self.occurrences(*operand, symbol),
+            Node::Binary(_, left, right) => {
+                self.occurrences(*left, symbol) + self.occurrences(*right, symbol)
+            }
+            Node::Multi { args, .. } => args.iter().map(|arg| self.occurrences(*arg, symbol)).sum(),
+            Node::Custom { index, arg, .. } => {
+                self.occurrences(*arg, symbol) + self.occurrences(self.functions[*index], symbol)
+            }
+        }
+    }
+
+    #[cfg(feature = "symbolic")]
+    fn infix_text(&self, node: NodeId) -> InfixText {
+        let precedence = match &self.nodes[node] {
+            Node::Binary(operator, ..) => precedence(*operator),
+            Node::Negate(_) => NEGATE_PRECEDENCE,
+            Node::Number(n) if *n < 0.0 => NEGATE_PRECEDENCE,
+            _ => ATOM_PRECEDENCE,
+        };
+        InfixText {
+            text: self.infix_node(node),
+            precedence,
+        }
+    }
+
+    // 式（と式から呼び出す数式で定義された関数）が参照する変数・定数の名前（重複なし、辞書順）
+    pub fn variables(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self
+            .nodes
+            .iter()
+            .filter_map(|node| match node {
+                Node::Variable { name, .. } => Some(name.as_str()),
+                _ => None,
+            })
+            .collect();
+        names.sort_unstable();
+        names.dedup();
+        names
+    }
+
+    // variables のうち、compile の時点で値がわからなかった（eval で束縛が必要な）もの
+    pub fn free_variables(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self
+            .nodes
+            .iter()
+            .filter_map(|node| match node {
+                Node::Variable {
+                    name,
+                    fallback: None,
+                } => Some(name.as_str()),
+                _ => None,
+            })
+            .collect();
+        names.sort_unstable();
+        names.dedup();
+        names
+    }
+
+    // vars の値を変数に束縛して評価する（evaluate_with と同じ結果になる）
+    pub fn eval(&self, vars: &HashMap<String, f64>) -> Result<f64, String> {
+        self.eval_node(self.root, vars, 0.0, 0)
+    }
+
+    // eval と同じ順に評価し、行った演算を被演算子と途中の結果とあわせて記録する。
+    // 数値リテラルと数式で定義された関数の引数 x の参照は演算として記録しない
+    pub fn audit(&self, vars: &HashMap<String, f64>) -> Result<(f64, Vec<AuditStep>), String> {
+        let mut steps = Vec::new();
+        let value = self.audit_node(self.root, vars, 0.0, 0, &mut steps)?;
+        Ok((value, steps))
+    }
+
+    // 評価せずに、eval で行う演算の回数と式の木の深さを数える
+    pub fn estimate(&self) -> Result<CostEstimate, String> {
+        let mut functions = vec![FunctionCost::Unvisited; self.functions.len()];
+        let cost = self.node_cost(self.root, &mut functions)?;
+        Ok(CostEstimate {
+            operations: cost.operations,
+            function_calls: cost.function_calls,
+            depth: cost.depth,
+            complexity: Complexity::Constant,
+            loops: Vec::new(),
+            approximate: false,
+        })
+    }
+
+    fn node_cost(&self, node: NodeId, functions: &mut [FunctionCost]) -> Result<Cost, String> {
+        let cost = match &self.nodes[node] {
+            Node::Number(_) | Node::Variable { .. } | Node::Argument => Cost {
+                depth: 1,
+                ..Cost::default()
+            },
+            Node::Negate(operand) => {
+                Cost::operation(&[self.node_cost(*operand, functions)?], false)
+            }
+            Node::Binary(_, left, right) => Cost::operation(
+                &[
+                    self.node_cost(*left, functions)?,
+                    self.node_cost(*right, functions)?,
+                ],
+                false,
+            ),
+            Node::Builtin { arg, .. } | Node::Round(_, arg) => {
+                Cost::operation(&[self.node_cost(*arg, functions)?], true)
+            }
+            Node::Multi { args, .. } => Cost::operation(
+                &args
+                    .iter()
+                    .map(|arg| self.node_cost(*arg, functions))
+                    .collect::<Result<Vec<_>, _>>()?,
+                true,
+            ),
+            Node::Custom { name, index, arg } => {
+                let body = match functions[*index] {
+                    FunctionCost::Counted(body) => body,
+                    // 再帰呼び出しは eval でも呼び出しの深さの上限でエラーになる
+                    FunctionCost::Visiting => {
+                        return Err(format!("関数の呼び出しが深すぎます: {}", name))
+                    }
+                    FunctionCost::Unvisited => {
+                        functions[*index] = FunctionCost::Visiting;
+                        let body = self.node_cost(self.functions[*index], functions)?;
+                        functions[*index] = FunctionCost::Counted(body);
+                        body
+                    }
+                };
+                Cost::operation(&[self.node_cost(*arg, functions)?, body], true)
+            }
+        };
+        Ok(cost)
     }
 
-    pub fn evaluate(&self, expression: &str) -> Result<f64, String> {
-        // 入力長制限（DoS攻撃防止）
-        if expression.len() > 1000 {
-            return Err("式が長すぎます（最大1000文字）".to_string());
+    // variable に values の各値を束縛した結果をまとめて求める（各値で eval した結果と同じ）。
+    // 木を1回たどるごとにチャンク内の全要素を計算するため、1点ずつ eval するより速い
+    pub fn eval_array(
+        &self,
+        variable: &str,
+        values: &[f64],
+        vars: &HashMap<String, f64>,
+    ) -> Result<Vec<f64>, String> {
+        if let Some(profile) = &self.safe_profile {
+            profile.check_list_length(values.len())?;
         }
-
-        // 危険な文字をチェック
-        if expression.contains(';') || expression.contains('|') || expression.contains('&') {
-            return Err("不正な文字が含まれています".to_string());
+        let mut results = Vec::with_capacity(values.len());
+        let mut point_vars = vars.clone();
+        for chunk in values.chunks(ARRAY_CHUNK) {
+            match self.eval_chunk(self.root, variable, chunk, vars, chunk, 0) {
+                Some(column) => results.extend(column),
+                // エラーになる値を含むチャンクは1点ずつ評価し、最初のエラーを返す
+                None => {
+                    for &value in chunk {
+                        point_vars.insert(variable.to_string(), value);
+                        let result = self
+                            .eval(&point_vars)
+                            .map_err(|e| format!("{} = {}: {}", variable, value, e))?;
+                        results.push(result);
+                    }
+                }
+            }
         }
+        Ok(results)
+    }
 
-        let tokens = self.tokenize(expression)?;
-        self.evaluate_tokens(&tokens)
+    fn check_exponent(&self, exponent: f64) -> Result<(), String> {
+        match &self.safe_profile {
+            Some(profile) => profile.check_exponent(exponent),
+            None => Ok(()),
+        }
     }
 
-    fn tokenize(&self, expression: &str) -> Result<Vec<Token>, String> {
-        let mut tokens = Vec::new();
-        let mut chars = expression.chars().peekable();
+    fn check_call(&self, name: &str, args: &[f64]) -> Result<(), String> {
+        match &self.safe_profile {
+            Some(profile) => profile.check_call(name, args),
+            None => Ok(()),
+        }
+    }
 
-        while let Some(&ch) = chars.peek() {
-            match ch {
-                ' ' => {
-                    chars.next();
+    // チャンク単位の評価。eval_node がエラーにする値が1つでもあれば None を返す
+    fn eval_chunk(
+        &self,
+        node: NodeId,
+        variable: &str,
+        values: &[f64],
+        vars: &HashMap<String, f64>,
+        argument: &[f64],
+        depth: usize,
+    ) -> Option<Vec<f64>> {
+        let result = match &self.nodes[node] {
+            Node::Number(value) => return Some(vec![*value; values.len()]),
+            Node::Variable { name, .. } if name == variable => return Some(values.to_vec()),
+            Node::Variable { name, fallback } => {
+                let value = vars.get(name).copied().or(*fallback)?;
+                return Some(vec![value; values.len()]);
+            }
+            Node::Argument => return Some(argument.to_vec()),
+            Node::Negate(operand) => {
+                let mut column =
+                    self.eval_chunk(*operand, variable, values, vars, argument, depth)?;
+                column.iter_mut().for_each(|x| *x = -*x);
+                return Some(column);
+            }
+            Node::Binary(operator, left, right) => {
+                let mut left = self.eval_chunk(*left, variable, values, vars, argument, depth)?;
+                let right = self.eval_chunk(*right, variable, values, vars, argument, depth)?;
+                let pairs = left.iter_mut().zip(&right);
+                match operator {
+                    '+' => pairs.for_each(|(l, r)| *l += r),
+                    '-' => pairs.for_each(|(l, r)| *l -= r),
+                    '*' => pairs.for_each(|(l, r)| *l *= r),
+                    '/' => {
+                        if right.contains(&0.0) {
+                            return None;
+                        }
+                        pairs.for_each(|(l, r)| *l /= r);
+                    }
+                    _ => {
+                        if right.iter().any(|r| self.check_exponent(*r).is_err()) {
+                            return None;
+                        }
+                        pairs.for_each(|(l, r)| *l = l.powf(*r));
+                        if !left.iter().all(|x| x.is_finite()) {
+                            return None;
+                        }
+                    }
                 }
-                '0'..='9' | '.' => {
-                    let number = self.parse_number(&mut chars)?;
-                    tokens.push(Token::Number(number));
+                return Some(left);
+            }
+            Node::Builtin {
+                function,
+                to_radians,
+                arg,
+                ..
+            } => {
+                let mut column = self.eval_chunk(*arg, variable, values, vars, argument, depth)?;
+                // 定義域の外の値があれば eval_node でエラーの内容を求める
+                if !column.iter().all(|x| function.domain.contains(*x)) {
+                    return None;
                 }
-                '+' | '-' | '*' | '/' | '^' => {
-                    chars.next();
-                    tokens.push(Token::Operator(ch));
+                if *to_radians {
+                    column.iter_mut().for_each(|x| *x = x.to_radians());
                 }
-                '(' => {
-                    chars.next();
-                    tokens.push(Token::LeftParen);
+                column.iter_mut().for_each(|x| *x = (function.function)(*x));
+                column
+            }
+            Node::Multi {
+                name,
+                function,
+                to_degrees,
+                args,
+            } => {
+                let columns = args
+                    .iter()
+                    .map(|arg| self.eval_chunk(*arg, variable, values, vars, argument, depth))
+                    .collect::<Option<Vec<_>>>()?;
+                let mut row = vec![0.0; columns.len()];
+                let mut column = Vec::with_capacity(values.len());
+                for i in 0..values.len() {
+                    row.iter_mut()
+                        .zip(&columns)
+                        .for_each(|(x, arg)| *x = arg[i]);
+                    self.check_call(name, &row).ok()?;
+                    let result = function.call(name, &row).ok()?;
+                    column.push(if *to_degrees {
+                        result.to_degrees()
+                    } else {
+                        result
+                    });
                 }
-                ')' => {
-                    chars.next();
-                    tokens.push(Token::RightParen);
+                column
+            }
+            Node::Round(mode, arg) => {
+                let mut column = self.eval_chunk(*arg, variable, values, vars, argument, depth)?;
+                column.iter_mut().for_each(|x| *x = round_to(*x, 0, *mode));
+                column
+            }
+            Node::Custom { index, arg, .. } => {
+                let column = self.eval_chunk(*arg, variable, values, vars, argument, depth)?;
+                if depth >= MAX_CALL_DEPTH {
+                    return None;
                 }
-                'a'..='z' | 'A'..='Z' => {
-                    let function_name = self.parse_identifier(&mut chars);
-                    if self.allowed_functions.contains_key(&function_name) {
-                        tokens.push(Token::Function(function_name));
-                    } else {
-                        return Err(format!("未サポートの関数: {}", function_name));
-                    }
+                self.eval_chunk(
+                    self.functions[*index],
+                    variable,
+                    values,
+                    vars,
+                    &column,
+                    depth + 1,
+                )?
+            }
+        };
+        result.iter().all(|x| x.is_finite()).then_some(result)
+    }
+
+    fn eval_node(
+        &self,
+        node: NodeId,
+        vars: &HashMap<String, f64>,
+        argument: f64,
+        depth: usize,
+    ) -> Result<f64, String> {
+        let result = match &self.nodes[node] {
+            Node::Number(value) => return Ok(*value),
+            Node::Variable { name, fallback } => {
+                return vars
+                    .get(name)
+                    .copied()
+                    .or(*fallback)
+                    .ok_or_else(|| format!("未定義の変数: {}", name))
+            }
+            Node::Argument => return Ok(argument),
+            Node::Negate(operand) => return Ok(-self.eval_node(*operand, vars, argument, depth)?),
+            Node::Binary(operator, left, right) => {
+                let left = self.eval_node(*left, vars, argument, depth)?;
+                let right = self.eval_node(*right, vars, argument, depth)?;
+                if *operator == '^' {
+                    self.check_exponent(right)?;
                 }
-                _ => {
-                    return Err(format!("不正な文字: {}", ch));
+                return binary(*operator, left, right);
+            }
+            Node::Builtin {
+                name,
+                function,
+                to_radians,
+                arg,
+            } => {
+                let x = function
+                    .domain
+                    .check(name, self.eval_node(*arg, vars, argument, depth)?)?;
+                (function.function)(if *to_radians { x.to_radians() } else { x })
+            }
+            Node::Multi {
+                name,
+                function,
+                to_degrees,
+                args,
+            } => {
+                let args = args
+                    .iter()
+                    .map(|arg| self.eval_node(*arg, vars, argument, depth))
+                    .collect::<Result<Vec<_>, _>>()?;
+                self.check_call(name, &args)?;
+                let result = function.call(name, &args)?;
+                if *to_degrees {
+                    result.to_degrees()
+                } else {
+                    result
                 }
             }
-        }
+            Node::Round(mode, arg) => {
+                round_to(self.eval_node(*arg, vars, argument, depth)?, 0, *mode)
+            }
+            Node::Custom { name, index, arg } => {
+                let x = self.eval_node(*arg, vars, argument, depth)?;
+                if depth >= MAX_CALL_DEPTH {
+                    return Err(format!("関数の呼び出しが深すぎます: {}", name));
+                }
+                self.eval_node(self.functions[*index], vars, x, depth + 1)?
+            }
+        };
 
-        Ok(tokens)
+        // NaN や無限大のチェック
+        if !result.is_finite() {
+            return Err(domain_error("計算結果が無効です（NaN または 無限大）"));
+        }
+        Ok(result)
     }
 
-    fn parse_number(
+    fn audit_node(
         &self,
-        chars: &mut std::iter::Peekable<std::str::Chars>,
+        node: NodeId,
+        vars: &HashMap<String, f64>,
+        argument: f64,
+        depth: usize,
+        steps: &mut Vec<AuditStep>,
     ) -> Result<f64, String> {
-        let mut number_str = String::new();
-        let mut has_dot = false;
+        // eval_node と同じく、関数の結果だけ NaN や無限大をエラーにする
+        let finite = |value: f64| {
+            if value.is_finite() {
+                Ok(value)
+            } else {
+                Err(domain_error("計算結果が無効です（NaN または 無限大）"))
+            }
+        };
+        let (operation, name, operands, result) = match &self.nodes[node] {
+            Node::Number(value) => return Ok(*value),
+            Node::Argument => return Ok(argument),
+            Node::Variable { name, .. } => (
+                "variable",
+                Some(name.clone()),
+                Vec::new(),
+                self.eval_node(node, vars, argument, depth)?,
+            ),
+            Node::Negate(operand) => {
+                let x = self.audit_node(*operand, vars, argument, depth, steps)?;
+                ("negate", None, vec![x], -x)
+            }
+            Node::Binary(operator, left, right) => {
+                let left = self.audit_node(*left, vars, argument, depth, steps)?;
+                let right = self.audit_node(*right, vars, argument, depth, steps)?;
+                if *operator == '^' {
+                    self.check_exponent(right)?;
+                }
+                let operation = match operator {
+                    '+' => "add",
+                    '-' => "subtract",
+                    '*' => "multiply",
+                    '/' => "divide",
+                    _ => "power",
+                };
+                (
+                    operation,
+                    None,
+                    vec![left, right],
+                    binary(*operator, left, right)?,
+                )
+            }
+            Node::Builtin {
+                name,
+                function,
+                to_radians,
+                arg,
+            } => {
+                let x = function
+                    .domain
+                    .check(name, self.audit_node(*arg, vars, argument, depth, steps)?)?;
+                let result = finite((function.function)(if *to_radians {
+                    x.to_radians()
+                } else {
+                    x
+                }))?;
+                ("function", Some(name.clone()), vec![x], result)
+            }
+            Node::Multi {
+                name,
+                function,
+                to_degrees,
+                args,
+            } => {
+                let args = args
+                    .iter()
+                    .map(|arg| self.audit_node(*arg, vars, argument, depth, steps))
+                    .collect::<Result<Vec<_>, _>>()?;
+                self.check_call(name, &args)?;
+                let result = function.call(name, &args)?;
+                let result = finite(if *to_degrees {
+                    result.to_degrees()
+                } else {
+                    result
+                })?;
+                ("function", Some(name.clone()), args, result)
+            }
+            Node::Round(mode, arg) => {
+                let x = self.audit_node(*arg, vars, argument, depth, steps)?;
+                ("round", None, vec![x], finite(round_to(x, 0, *mode))?)
+            }
+            Node::Custom { name, index, arg } => {
+                let x = self.audit_node(*arg, vars, argument, depth, steps)?;
+                if depth >= MAX_CALL_DEPTH {
+                    return Err(format!("関数の呼び出しが深すぎます: {}", name));
+                }
+                let result = self.audit_node(self.functions[*index], vars, x, depth + 1, steps)?;
+                ("call", Some(name.clone()), vec![x], finite(result)?)
+            }
+        };
+        steps.push(AuditStep {
+            step: steps.len() + 1,
+            operation,
+            name,
+            operands,
+            result,
+        });
+        Ok(result)
+    }
+}
 
-        while let Some(&ch) = chars.peek() {
-            match ch {
-                '0'..='9' => {
-                    number_str.push(ch);
-                    chars.next();
+// compile できない式（ベクトル・行列・比較・単位の換算）の量をトークンの並びから見積もる
+fn estimate_tokens(tokens: &[Token]) -> CostEstimate {
+    let mut estimate = CostEstimate {
+        operations: 0,
+        function_calls: 0,
+        depth: 1,
+        complexity: Complexity::Constant,
+        loops: Vec::new(),
+        approximate: true,
+    };
+    let mut nesting = 0;
+    let mut bars = 0;
+    for (index, token) in tokens.iter().enumerate() {
+        match token {
+            Token::Operator(_)
+            | Token::Relation(_)
+            | Token::In
+            | Token::Sqrt
+            | Token::Percent(..) => estimate.operations += 1,
+            Token::Function(name) => {
+                estimate.operations += 1;
+                estimate.function_calls += 1;
+                if matches!(*name, "det" | "inv") {
+                    estimate.complexity = Complexity::Cubic;
                 }
-                '.' if !has_dot => {
-                    has_dot = true;
-                    number_str.push(ch);
-                    chars.next();
+            }
+            // 開きと閉じの | で1回の abs
+            Token::Bar => bars += 1,
+            Token::LeftParen | Token::LeftBracket => {
+                nesting += 1;
+                estimate.depth = estimate.depth.max(nesting + 1);
+            }
+            // 括弧の対応は評価するまで確かめない
+            Token::RightParen => nesting = nesting.saturating_sub(1),
+            Token::RightBracket => {
+                nesting = nesting.saturating_sub(1);
+                if estimate.complexity == Complexity::Constant {
+                    estimate.complexity = Complexity::Linear;
+                }
+                // [[...]] の直後の ^ n は行列のべき乗（2乗と掛け算の繰り返し）
+                let is_matrix = index > 0 && tokens[index - 1] == Token::RightBracket;
+                if let (true, Some(Token::Operator('^')), Some(Token::Number(exponent, _))) =
+                    (is_matrix, tokens.get(index + 1), tokens.get(index + 2))
+                {
+                    let bound = exponent.abs().min(u64::MAX as f64) as u64;
+                    estimate.loops.push(LoopBound {
+                        operation: "matrix_power",
+                        bound: *exponent,
+                        iterations: u64::from(bound.count_ones())
+                            + u64::from(bound.checked_ilog2().unwrap_or(0)),
+                    });
+                    estimate.complexity = Complexity::Cubic;
                 }
-                _ => break,
             }
+            Token::Number(..) | Token::Variable(_) | Token::Comma => {}
         }
-
-        number_str
-            .parse::<f64>()
-            .map_err(|_| format!("数値の解析に失敗: {}", number_str))
     }
+    estimate.operations += bars / 2;
+    // 行列どうしの積も O(n³) になる
+    let matrix_product = tokens.windows(2).any(|pair| {
+        matches!(pair, [Token::RightBracket, Token::Operator('*')])
+            || matches!(pair, [Token::Operator('*'), Token::LeftBracket])
+    }) && tokens
+        .windows(2)
+        .any(|pair| pair == [Token::LeftBracket, Token::LeftBracket]);
+    if matrix_product {
+        estimate.complexity = Complexity::Cubic;
+    }
+    estimate
+}
 
-    fn parse_identifier(&self, chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
-        let mut identifier = String::new();
-
-        while let Some(&ch) = chars.peek() {
-            if ch.is_alphabetic() {
-                identifier.push(ch);
-                chars.next();
-            } else {
-                break;
+// 二項演算子（+ - * / ^）の計算
+fn binary(operator: char, left: f64, right: f64) -> Result<f64, String> {
+    match operator {
+        '+' => Ok(left + right),
+        '-' => Ok(left - right),
+        '*' => Ok(left * right),
+        '/' if right == 0.0 => Err(division_by_zero()),
+        '/' => Ok(left / right),
+        _ => {
+            let value = left.powf(right);
+            if !value.is_finite() {
+                return Err(domain_error("べき乗の計算結果が無効です"));
             }
+            Ok(value)
         }
+    }
+}
 
-        identifier
+// Calculator::compile の構文解析（evaluate_expression 以下と同じ文法）
+struct Compiler<'a> {
+    calculator: &'a Calculator,
+    nodes: Vec<Node>,
+    // 数式で定義された関数の本体。再帰呼び出しに備えて、解析前に None で場所を確保する
+    functions: Vec<Option<NodeId>>,
+    indices: HashMap<String, usize>,
+}
+
+impl Compiler<'_> {
+    fn push(&mut self, node: Node) -> NodeId {
+        self.nodes.push(node);
+        self.nodes.len() - 1
     }
 
-    fn evaluate_tokens(&self, tokens: &[Token]) -> Result<f64, String> {
+    // in_function が true なら、数式で定義された関数の本体（変数は x と定数だけ）
+    fn compile_tokens(
+        &mut self,
+        expression: &str,
+        tokens: &[Token],
+        in_function: bool,
+    ) -> Result<NodeId, String> {
         if tokens.is_empty() {
-            return Err("空の式です".to_string());
+            return Err(syntax_error("空の式です"));
         }
-
-        self.evaluate_expression(tokens, 0)
-            .map(|(result, _)| result)
+        let (node, pos) = self.expression(tokens, 0, in_function)?;
+        check_unmatched_bar(tokens, pos)?;
+        match tokens.get(pos) {
+            Some(Token::Relation(_)) => return Err(COMPARISON_UNSUPPORTED.to_string()),
+            Some(Token::In) => return Err(CONVERSION_UNSUPPORTED.to_string()),
+            _ => {}
+        }
+        self.calculator.check_consumed(expression, tokens, pos)?;
+        Ok(node)
     }
 
-    fn evaluate_expression(
-        &self,
+    fn expression(
+        &mut self,
         tokens: &[Token],
-        mut pos: usize,
-    ) -> Result<(f64, usize), String> {
-        let (mut left, new_pos) = self.evaluate_term(tokens, pos)?;
-        pos = new_pos;
-
-        while pos < tokens.len() {
-            match &tokens[pos] {
-                Token::Operator('+') => {
-                    pos += 1;
-                    let (right, new_pos) = self.evaluate_term(tokens, pos)?;
-                    left += right;
-                    pos = new_pos;
-                }
-                Token::Operator('-') => {
-                    pos += 1;
-                    let (right, new_pos) = self.evaluate_term(tokens, pos)?;
-                    left -= right;
-                    pos = new_pos;
-                }
-                _ => break,
+        pos: usize,
+        in_function: bool,
+    ) -> Result<(NodeId, usize), String> {
+        let (mut left, mut pos) = self.term(tokens, pos, in_function)?;
+        while let Some(Token::Operator(operator @ ('+' | '-'))) = tokens.get(pos) {
+            let (mut right, new_pos) = self.term(tokens, pos + 1, in_function)?;
+            if is_percent_operand(tokens, pos + 1, new_pos) {
+                right = self.push(Node::Binary('*', left, right));
             }
+            left = self.push(Node::Binary(*operator, left, right));
+            pos = new_pos;
         }
+        Ok((left, pos))
+    }
 
+    fn term(
+        &mut self,
+        tokens: &[Token],
+        pos: usize,
+        in_function: bool,
+    ) -> Result<(NodeId, usize), String> {
+        let (mut left, mut pos) = self.power(tokens, pos, in_function)?;
+        while let Some(Token::Operator(operator @ ('*' | '/'))) = tokens.get(pos) {
+            let (right, new_pos) = self.power(tokens, pos + 1, in_function)?;
+            left = self.push(Node::Binary(*operator, left, right));
+            pos = new_pos;
+        }
         Ok((left, pos))
     }
 
-    fn evaluate_term(&self, tokens: &[Token], mut pos: usize) -> Result<(f64, usize), String> {
-        let (mut left, new_pos) = self.evaluate_power(tokens, pos)?;
-        pos = new_pos;
+    fn power(
+        &mut self,
+        tokens: &[Token],
+        pos: usize,
+        in_function: bool,
+    ) -> Result<(NodeId, usize), String> {
+        let (mut left, mut pos) = self.factor(tokens, pos, in_function)?;
+        while let Some(Token::Operator('^')) = tokens.get(pos) {
+            let (right, new_pos) = self.factor(tokens, pos + 1, in_function)?;
+            left = self.push(Node::Binary('^', left, right));
+            pos = new_pos;
+        }
+        Ok((left, pos))
+    }
 
-        while pos < tokens.len() {
-            match &tokens[pos] {
-                Token::Operator('*') => {
-                    pos += 1;
-                    let (right, new_pos) = self.evaluate_power(tokens, pos)?;
-                    left *= right;
-                    pos = new_pos;
+    fn factor(
+        &mut self,
+        tokens: &[Token],
+        pos: usize,
+        in_function: bool,
+    ) -> Result<(NodeId, usize), String> {
+        let calculator = self.calculator;
+        match tokens.get(pos).copied() {
+            None => Err(syntax_error("予期しない式の終了")),
+            Some(Token::Number(n, _)) => Ok((self.push(Node::Number(n)), pos + 1)),
+            Some(Token::Percent(n, _)) => Ok((self.push(Node::Number(n / 100.0)), pos + 1)),
+            Some(Token::Variable(name)) if in_function => {
+                let node = if name == "x" {
+                    Node::Argument
+                } else {
+                    let value = calculator
+                        .constants
+                        .get(name)
+                        .ok_or_else(|| format!("未定義の変数: {}", name))?;
+                    Node::Number(*value)
+                };
+                Ok((self.push(node), pos + 1))
+            }
+            Some(Token::Variable(name)) => {
+                let fallback = calculator
+                    .variables
+                    .get(name)
+                    .or_else(|| calculator.constants.get(name))
+                    .copied();
+                let node = Node::Variable {
+                    name: name.to_string(),
+                    fallback,
+                };
+                Ok((self.push(node), pos + 1))
+            }
+            Some(Token::Operator('-')) => {
+                let (operand, new_pos) = self.factor(tokens, pos + 1, in_function)?;
+                Ok((self.push(Node::Negate(operand)), new_pos))
+            }
+            Some(Token::Operator('+')) => self.factor(tokens, pos + 1, in_function),
+            Some(Token::LeftParen) => {
+                check_empty_parens(tokens, pos)?;
+                let (node, pos) = self.expression(tokens, pos + 1, in_function)?;
+                if !matches!(tokens.get(pos), Some(Token::RightParen)) {
+                    return Err(syntax_error("対応する右括弧がありません"));
                 }
-                Token::Operator('/') => {
-                    pos += 1;
-                    let (right, new_pos) = self.evaluate_power(tokens, pos)?;
-                    if right == 0.0 {
-                        return Err("ゼロ除算エラー".to_string());
-                    }
-                    left /= right;
-                    pos = new_pos;
+                Ok((node, pos + 1))
+            }
+            Some(Token::Bar) => {
+                let (arg, pos) = self.expression(tokens, pos + 1, in_function)?;
+                if !matches!(tokens.get(pos), Some(Token::Bar)) {
+                    return Err(syntax_error("絶対値の | が閉じられていません"));
                 }
-                _ => break,
+                let node = Node::Builtin {
+                    name: "abs".to_string(),
+                    function: calculator.allowed_functions["abs"],
+                    to_radians: false,
+                    arg,
+                };
+                Ok((self.push(node), pos + 1))
+            }
+            Some(Token::Sqrt) => {
+                let (arg, pos) = self.factor(tokens, pos + 1, in_function)?;
+                let node = Node::Builtin {
+                    name: "sqrt".to_string(),
+                    function: calculator.allowed_functions["sqrt"],
+                    to_radians: false,
+                    arg,
+                };
+                Ok((self.push(node), pos))
+            }
+            Some(Token::Function(name)) => {
+                let (args, pos) = self.arguments(name, tokens, pos + 1, in_function)?;
+                if let Some(multi) = calculator.multi_functions.get(name) {
+                    multi.check_arity(name, args.len())?;
+                    let node = Node::Multi {
+                        name: name.to_string(),
+                        function: *multi,
+                        to_degrees: calculator.returns_degrees(name),
+                        args,
+                    };
+                    return Ok((self.push(node), pos));
+                }
+                let arg = single_argument(name, args)?;
+
+                let node = if name == "round" {
+                    Node::Round(calculator.rounding_mode, arg)
+                } else if let Some(function) = calculator.allowed_functions.get(name) {
+                    Node::Builtin {
+                        name: name.to_string(),
+                        function: *function,
+                        to_radians: calculator.angle_mode == AngleMode::Degrees
+                            && TRIG_FUNCTIONS.contains(&name),
+                        arg,
+                    }
+                } else if let Some(formula) = calculator.custom_functions.get(name) {
+                    Node::Custom {
+                        name: name.to_string(),
+                        index: self.custom_function(name, formula)?,
+                        arg,
+                    }
+                } else {
+                    return Err(format!("未知の関数: {}", name));
+                };
+                Ok((self.push(node), pos))
             }
+            Some(Token::LeftBracket) => Err(ARRAY_UNSUPPORTED.to_string()),
+            Some(token) => Err(syntax_error(format!("予期しないトークン: {:?}", token))),
         }
+    }
 
-        Ok((left, pos))
+    // Calculator::evaluate_arguments と同じ
+    fn arguments(
+        &mut self,
+        name: &str,
+        tokens: &[Token],
+        mut pos: usize,
+        in_function: bool,
+    ) -> Result<(Vec<NodeId>, usize), String> {
+        if !matches!(tokens.get(pos), Some(Token::LeftParen)) {
+            return Err(syntax_error("関数の後に左括弧が必要です"));
+        }
+        let mut args = Vec::new();
+        loop {
+            if is_empty_call(name, tokens, pos + 1, args.len())? {
+                pos += 1;
+                break;
+            }
+            let (arg, new_pos) = self.expression(tokens, pos + 1, in_function)?;
+            args.push(arg);
+            self.calculator.check_argument_count(args.len())?;
+            pos = new_pos;
+            if !matches!(tokens.get(pos), Some(Token::Comma)) {
+                break;
+            }
+        }
+        if !matches!(tokens.get(pos), Some(Token::RightParen)) {
+            return Err(syntax_error("関数の引数の後に右括弧が必要です"));
+        }
+        Ok((args, pos + 1))
     }
 
-    fn evaluate_power(&self, tokens: &[Token], mut pos: usize) -> Result<(f64, usize), String> {
-        let (mut left, new_pos) = self.evaluate_factor(tokens, pos)?;
-        pos = new_pos;
+    // 関数は一度だけ解析し、呼び出しはすべて同じ本体を参照する
+    fn custom_function(&mut self, name: &str, formula: &str) -> Result<usize, String> {
+        if let Some(index) = self.indices.get(name) {
+            return Ok(*index);
+        }
+        let index = self.functions.len();
+        self.functions.push(None);
+        self.indices.insert(name.to_string(), index);
+        let tokens = self.calculator.checked_tokenize(formula)?;
+        self.functions[index] = Some(self.compile_tokens(formula, &tokens, true)?);
+        Ok(index)
+    }
+}
 
-        while pos < tokens.len() {
-            match &tokens[pos] {
-                Token::Operator('^') => {
-                    pos += 1;
-                    let (right, new_pos) = self.evaluate_factor(tokens, pos)?;
-                    left = left.powf(right);
+// a + 10% と a - 10% の右辺が百分率のリテラルだけの場合は、a の 10% として扱う（電卓と同じ）。
+// 10% * a や a * 10% では 0.1 のまま
+fn is_percent_operand(tokens: &[Token], start: usize, end: usize) -> bool {
+    end == start + 1 && matches!(tokens[start], Token::Percent(..))
+}
 
-                    // べき乗の結果をチェック
-                    if !left.is_finite() {
-                        return Err("べき乗の計算結果が無効です".to_string());
-                    }
+// tokens[start..end] が符号の付いてもよい1つの数値のリテラル（20, -40）か
+fn is_number_literal(tokens: &[Token], start: usize, end: usize) -> bool {
+    matches!(
+        &tokens[start..end],
+        [Token::Number(..)] | [Token::Operator('-' | '+'), Token::Number(..)]
+    )
+}
 
-                    pos = new_pos;
-                }
-                _ => break,
+fn percent_of(left: &Measured, right: Measured, is_percent: bool) -> Measured {
+    if !is_percent {
+        return right;
+    }
+    let base = Measured {
+        value: left.value,
+        figures: left.figures,
+        notes: Vec::new(),
+    };
+    let value = left.value * right.value;
+    base.combine('*', right, value)
+}
+
+// previous と token の間を掛け算とみなすか。値の後に値が続く場合（2 3 や |a||b|）は補わない
+fn is_implicit_product(previous: Token, token: Token) -> bool {
+    match previous {
+        Token::Number(..) | Token::Percent(..) | Token::Variable(_) => matches!(
+            token,
+            Token::Variable(_) | Token::Function(_) | Token::LeftParen | Token::Sqrt
+        ),
+        Token::RightParen => matches!(
+            token,
+            Token::Number(..)
+                | Token::Variable(_)
+                | Token::Function(_)
+                | Token::LeftParen
+                | Token::Sqrt
+        ),
+        _ => false,
+    }
+}
+
+// tokens の末尾の値（数値・変数・括弧・関数呼び出し）の開始位置
+fn factor_start(tokens: &[Token]) -> usize {
+    let mut start = tokens.len() - 1;
+    if tokens[start] == Token::RightParen {
+        let mut depth = 0;
+        while start > 0 {
+            match tokens[start] {
+                Token::RightParen => depth += 1,
+                Token::LeftParen => depth -= 1,
+                _ => {}
+            }
+            if depth == 0 {
+                break;
             }
+            start -= 1;
+        }
+        if start > 0 && matches!(tokens[start - 1], Token::Function(_)) {
+            start -= 1;
         }
-
-        Ok((left, pos))
     }
+    start
+}
 
-    fn evaluate_factor(&self, tokens: &[Token], mut pos: usize) -> Result<(f64, usize), String> {
-        if pos >= tokens.len() {
-            return Err("予期しない式の終了".to_string());
+// tokens[start] の ( に対応する ) の位置
+fn matching_paren(tokens: &[Token], start: usize) -> Option<usize> {
+    if tokens.get(start) != Some(&Token::LeftParen) {
+        return None;
+    }
+    let mut depth = 0;
+    for (index, &token) in tokens.iter().enumerate().skip(start) {
+        match token {
+            Token::LeftParen => depth += 1,
+            Token::RightParen => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(index);
+                }
+            }
+            _ => {}
         }
+    }
+    None
+}
 
-        match &tokens[pos] {
-            Token::Number(n) => Ok((*n, pos + 1)),
-            Token::Operator('-') => {
-                pos += 1;
-                let (value, new_pos) = self.evaluate_factor(tokens, pos)?;
-                Ok((-value, new_pos))
+// tokenize_with_offsets が補った暗黙の掛け算の *（次のトークンと同じ位置にある）か
+fn is_inserted_product(tokens: &[Token], offsets: &[usize], index: usize) -> bool {
+    tokens[index] == Token::Operator('*')
+        && index + 1 < tokens.len()
+        && offsets[index] == offsets[index + 1]
+}
+
+// tokens[start] から始まる、暗黙の掛け算と ^ でつながった値の終わり（次のトークンの位置）
+fn product_end(tokens: &[Token], offsets: &[usize], start: usize) -> usize {
+    let mut depth = 0;
+    for (index, &token) in tokens.iter().enumerate().skip(start) {
+        match token {
+            Token::LeftParen | Token::LeftBracket => depth += 1,
+            Token::RightParen | Token::RightBracket if depth == 0 => return index,
+            Token::RightParen | Token::RightBracket => depth -= 1,
+            // 補った * と、^ とその指数の符号は値の一部
+            Token::Operator('*') if is_inserted_product(tokens, offsets, index) => {}
+            Token::Operator('-' | '+') if tokens[index - 1] == Token::Operator('^') => {}
+            Token::Operator('^') => {}
+            Token::Operator(_) | Token::Comma | Token::Relation(_) | Token::In | Token::Bar
+                if depth == 0 =>
+            {
+                return index
             }
-            Token::Operator('+') => {
-                pos += 1;
-                self.evaluate_factor(tokens, pos)
+            _ => {}
+        }
+    }
+    tokens.len()
+}
+
+// エラーや補正の説明に使うトークンの表記
+fn token_text(token: Token) -> String {
+    match token {
+        Token::Number(n, _) => n.to_string(),
+        Token::Percent(n, _) => format!("{}%", n),
+        Token::Function(name) | Token::Variable(name) => name.to_string(),
+        Token::Operator(operator) => operator.to_string(),
+        Token::Comma => ",".to_string(),
+        Token::LeftParen => "(".to_string(),
+        Token::RightParen => ")".to_string(),
+        Token::Bar => "|".to_string(),
+        Token::Sqrt => "√".to_string(),
+        Token::LeftBracket => "[".to_string(),
+        Token::RightBracket => "]".to_string(),
+        Token::Relation(relation) => relation.symbol().to_string(),
+        Token::In => CONVERSION_KEYWORD.to_string(),
+    }
+}
+
+// 演算子の並びの規則。単項の - は値の始まる位置ならどこでも使える（3 * -2, 2 ^ -1, 2 - -3, --5）。
+// 単項の + は式・括弧・引数の先頭だけで、演算子の直後の + と * / ^（2 ++ 3, 2 * +3, 2 ** 3）は
+// 演算子の重複として、値のない位置の * / ^（* 3, (/ 2)）は左辺の欠落としてエラーにする
+fn check_operators(tokens: &[Token]) -> Result<(), String> {
+    for (index, &token) in tokens.iter().enumerate() {
+        let Token::Operator(operator) = token else {
+            continue;
+        };
+        let previous = index.checked_sub(1).map(|index| tokens[index]);
+        match (previous, operator) {
+            (_, '-') => {}
+            (Some(Token::Operator(first)), _) => {
+                let hint = match (first, operator) {
+                    ('*', '*') => "べき乗は ^ を使ってください",
+                    ('/', '/') => "/ は1つだけ書いてください",
+                    (_, '+') => "符号の + は付けずに書いてください",
+                    _ => "演算子の間に値を書いてください",
+                };
+                return Err(syntax_error(format!(
+                    "演算子が連続しています: {}{}（{}）",
+                    first, operator, hint
+                )));
             }
-            Token::LeftParen => {
-                pos += 1;
-                let (result, new_pos) = self.evaluate_expression(tokens, pos)?;
-                pos = new_pos;
-                if pos >= tokens.len() || !matches!(tokens[pos], Token::RightParen) {
-                    return Err("対応する右括弧がありません".to_string());
-                }
-                Ok((result, pos + 1))
+            (
+                None
+                | Some(
+                    Token::LeftParen
+                    | Token::Comma
+                    | Token::LeftBracket
+                    | Token::Relation(_)
+                    | Token::In,
+                ),
+                '*' | '/' | '^',
+            ) => {
+                return Err(syntax_error(format!(
+                    "演算子 {} の左辺がありません",
+                    operator
+                )))
             }
-            Token::Function(name) => {
-                pos += 1;
-                if pos >= tokens.len() || !matches!(tokens[pos], Token::LeftParen) {
-                    return Err("関数の後に左括弧が必要です".to_string());
-                }
-                pos += 1;
-                let (arg, new_pos) = self.evaluate_expression(tokens, pos)?;
-                pos = new_pos;
-                if pos >= tokens.len() || !matches!(tokens[pos], Token::RightParen) {
-                    return Err("関数の引数の後に右括弧が必要です".to_string());
-                }
+            _ => {}
+        }
+    }
+    Ok(())
+}
 
-                let function = self
-                    .allowed_functions
-                    .get(name)
-                    .ok_or_else(|| format!("未知の関数: {}", name))?;
-                let result = function(arg);
+// f64 の範囲を超えて無限大になる数値リテラルと、0 でない値が 0 に丸められる数値リテラルは
+// 誤った結果を黙って返さないようエラーにする（literal は SI 接頭辞を含む表記）
+fn check_literal_range(value: f64, literal: &str) -> Result<(), String> {
+    let chars: Vec<char> = literal.chars().collect();
+    let shown = if chars.len() > 24 {
+        format!(
+            "{}…{}（{} 文字）",
+            chars[..10].iter().collect::<String>(),
+            chars[chars.len() - 6..].iter().collect::<String>(),
+            chars.len()
+        )
+    } else {
+        literal.to_string()
+    };
+    if value.is_infinite() {
+        return Err(domain_error(format!(
+            "数値リテラルが大きすぎます: {}（扱える絶対値は約 1.8e308 までです）",
+            shown
+        )));
+    }
+    // 0e5 のように仮数が 0 のリテラルは 0 のまま
+    let mantissa = literal.split(['e', 'E']).next().unwrap_or(literal);
+    if value == 0.0 && mantissa.chars().any(|c| matches!(c, '1'..='9')) {
+        return Err(domain_error(format!(
+            "数値リテラルが小さすぎて 0 になります: {}（0 でない絶対値は約 4.9e-324 以上にしてください）",
+            shown
+        )));
+    }
+    Ok(())
+}
 
-                // NaN や無限大のチェック
-                if !result.is_finite() {
-                    return Err("計算結果が無効です（NaN または 無限大）".to_string());
-                }
+// 関数の引数の並びで、tokens[pos]（( か , の次）から index 番目の引数が始まるはずの位置に
+// 引数がない場合の扱い。sqrt() は引数 0 個の呼び出しとして true を返し（個数は呼び出し側で
+// 確かめる）、max(1, ) や max(, 1) のように区切りの間が空ならエラーにする
+fn is_empty_call(name: &str, tokens: &[Token], pos: usize, index: usize) -> Result<bool, String> {
+    match tokens.get(pos) {
+        Some(Token::RightParen) if index == 0 => Ok(true),
+        Some(Token::RightParen | Token::Comma) => Err(syntax_error(format!(
+            "{} の {} 番目の引数が空です",
+            name,
+            index + 1
+        ))),
+        _ => Ok(false),
+    }
+}
 
-                Ok((result, pos + 1))
+// 関数の呼び出しでない () は値がないためエラーにする
+fn check_empty_parens(tokens: &[Token], pos: usize) -> Result<(), String> {
+    if matches!(tokens.get(pos + 1), Some(Token::RightParen)) {
+        return Err(syntax_error(
+            "空の括弧 () は使えません（括弧の中に式を書いてください）",
+        ));
+    }
+    Ok(())
+}
+
+// 引数1つの関数（sin や数式で定義された関数など）の引数を取り出す
+fn single_argument<T>(name: &str, args: Vec<T>) -> Result<T, String> {
+    let count = args.len();
+    let mut args = args.into_iter();
+    match (args.next(), args.next()) {
+        (Some(arg), None) => Ok(arg),
+        _ => Err(format!(
+            "{} の引数は 1 個です（{} 個指定されています）",
+            name, count
+        )),
+    }
+}
+
+// 複数の引数をとる関数の結果の有効数字は、最も桁数の少ない引数に合わせる
+fn least_precise(args: Vec<Measured>) -> Measured {
+    args.into_iter()
+        .min_by_key(|arg| arg.figures.map_or(u32::MAX, |figures| figures.digits))
+        .unwrap_or_else(|| Measured::exact(0.0))
+}
+
+// 式の解析が | の手前で止まった場合（例: 2 + 3 | 4）は、開きのない閉じの | として扱う
+fn check_unmatched_bar(tokens: &[Token], pos: usize) -> Result<(), String> {
+    if matches!(tokens.get(pos), Some(Token::Bar)) {
+        return Err(syntax_error("対応する開きの | がない絶対値の記号です"));
+    }
+    Ok(())
+}
+
+// isolate で組み立てる中置記法の式と、その最も外側の演算の優先順位
+#[cfg(feature = "symbolic")]
+#[derive(Debug, Clone)]
+struct InfixText {
+    text: String,
+    precedence: u8,
+}
+
+// 単項マイナスと、数値・変数・関数呼び出しの優先順位（precedence の二項演算子より強い）
+#[cfg(feature = "symbolic")]
+const NEGATE_PRECEDENCE: u8 = 4;
+#[cfg(feature = "symbolic")]
+const ATOM_PRECEDENCE: u8 = 5;
+
+#[cfg(feature = "symbolic")]
+impl InfixText {
+    fn atom(text: String) -> Self {
+        Self {
+            text,
+            precedence: ATOM_PRECEDENCE,
+        }
+    }
+
+    fn call(name: &str, args: &[InfixText]) -> Self {
+        let args: Vec<&str> = args.iter().map(|arg| arg.text.as_str()).collect();
+        Self::atom(format!("{}({})", name, args.join(", ")))
+    }
+
+    fn negate(operand: Self) -> Self {
+        let text = if operand.precedence < ATOM_PRECEDENCE {
+            format!("-({})", operand.text)
+        } else {
+            format!("-{}", operand.text)
+        };
+        Self {
+            text,
+            precedence: NEGATE_PRECEDENCE,
+        }
+    }
+
+    // 優先順位の低い演算と、右辺（^ では両辺）の同じ優先順位の演算・単項マイナスを括弧で囲む
+    fn binary(operator: char, left: Self, right: Self) -> Self {
+        let parent = precedence(operator);
+        let wrap = |operand: Self, right_side: bool| {
+            let ambiguous = right_side || operator == '^';
+            let needs_parens = operand.precedence < parent
+                || (ambiguous && operand.precedence == parent)
+                || (ambiguous && operand.precedence == NEGATE_PRECEDENCE);
+            if needs_parens {
+                format!("({})", operand.text)
+            } else {
+                operand.text
             }
-            _ => Err(format!("予期しないトークン: {:?}", tokens[pos])),
+        };
+        Self {
+            text: format!("{} {} {}", wrap(left, false), operator, wrap(right, true)),
+            precedence: parent,
         }
     }
 }
 
-#[tool(tool_box)]
-impl CalculatorService {
-    #[tool(
-        description = "セキュアな数式計算を実行します。四則演算、べき乗、括弧、数学関数（平方根、絶対値、三角関数、自然対数）をサポートし、悪意のある入力から保護されています。"
-    )]
-    pub fn calculate(&self, #[tool(aggr)] request: CalculateRequest) -> Result<String, String> {
-        let calculator = Calculator::new();
-        match calculator.evaluate(&request.expression) {
-            Ok(result) => Ok(format!("計算結果: {}", result)),
-            Err(e) => Err(format!("計算エラー: {}", e)),
-        }
+// 中置記法で表示するときの演算子の優先順位
+fn precedence(operator: char) -> u8 {
+    match operator {
+        '+' | '-' => 1,
+        '*' | '/' => 2,
+        _ => 3,
     }
 }
 
-#[tool(tool_box)]
-impl ServerHandler for CalculatorService {
-    fn get_info(&self) -> InitializeResult {
-        InitializeResult {
-            protocol_version: ProtocolVersion::V_2024_11_05,
-            capabilities: ServerCapabilities::builder().enable_tools().build(),
-            server_info: Implementation {
-                name: "calc-mcp".into(),
-                version: "0.1.0".into(),
-            },
-            instructions: Some(
-                "計算機能を提供するMCPサーバです。数式を受け取って計算結果を返します。".into(),
-            ),
+// 変数名として使える識別子か（英字で始まり、英数字と _ のみ）
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(ch) if ch.is_ascii_alphabetic())
+        && chars.all(|ch| ch.is_alphanumeric() || ch == '_')
+}
+
+// 有効数字 digits 桁に丸めて表示する（末尾の 0 は省略）
+pub fn format_significant(value: f64, digits: usize) -> String {
+    if value == 0.0 || !value.is_finite() || digits == 0 {
+        return value.to_string();
+    }
+    let magnitude = value.abs().log10().floor() as i32;
+    let decimals = digits as i32 - 1 - magnitude;
+    if decimals >= 0 {
+        let formatted = format!("{:.*}", decimals as usize, value);
+        if formatted.contains('.') {
+            formatted
+                .trim_end_matches('0')
+                .trim_end_matches('.')
+                .to_string()
+        } else {
+            formatted
         }
+    } else {
+        let scale = 10f64.powi(-decimals);
+        ((value / scale).round() * scale).to_string()
     }
 }
-
@@ -1,15 +1,29 @@
 #[cfg(test)]
 mod tests {
-    use crate::calculator::{CalculateRequest, CalculatorService};
+    #[cfg(feature = "trig")]
+    use crate::calculator::AngleMode;
+    use crate::calculator::{Calculator, Complexity, ExpressionLimits, SafeProfile};
+    use crate::markdown::OutputFormat;
+    use crate::number_format::{Notation, NumberLocale};
+    use crate::rounding::RoundingMode;
+    #[cfg(feature = "symbolic")]
+    use crate::server::SolveSystemRequest;
+    use crate::server::{
+        CalculateBatchRequest, CalculateRequest, CalculatorService, CheckConstraintsRequest,
+        HumanizeRequest, LoadVariablesRequest, RearrangeRequest, RecalculateRequest,
+        SetOptionsRequest, UploadDataRequest,
+    };
     use rmcp::ServerHandler;
+    use std::collections::{BTreeMap, HashMap};
 
     #[test]
     fn test_calculate_basic_arithmetic() {
-        let calculator = CalculatorService;
+        let calculator = CalculatorService::default();
 
         // 足し算
         let request = CalculateRequest {
             expression: "2 + 3".to_string(),
+            ..Default::default()
         };
         let result = calculator.calculate(request).unwrap();
         assert_eq!(result, "計算結果: 5");
@@ -17,6 +31,7 @@ mod tests {
         // 掛け算
         let request = CalculateRequest {
             expression: "4 * 5".to_string(),
+            ..Default::default()
         };
         let result = calculator.calculate(request).unwrap();
         assert_eq!(result, "計算結果: 20");
@@ -24,6 +39,7 @@ mod tests {
         // 複合演算
         let request = CalculateRequest {
             expression: "2 + 3 * 4".to_string(),
+            ..Default::default()
         };
         let result = calculator.calculate(request).unwrap();
         assert_eq!(result, "計算結果: 14");
@@ -31,10 +47,11 @@ mod tests {
 
     #[test]
     fn test_calculate_with_parentheses() {
-        let calculator = CalculatorService;
+        let calculator = CalculatorService::default();
 
         let request = CalculateRequest {
             expression: "(2 + 3) * 4".to_string(),
+            ..Default::default()
         };
         let result = calculator.calculate(request).unwrap();
         assert_eq!(result, "計算結果: 20");
@@ -42,11 +59,12 @@ mod tests {
 
     #[test]
     fn test_calculate_math_functions() {
-        let calculator = CalculatorService;
+        let calculator = CalculatorService::default();
 
         // 平方根
         let request = CalculateRequest {
             expression: "sqrt(25)".to_string(),
+            ..Default::default()
         };
         let result = calculator.calculate(request).unwrap();
         assert_eq!(result, "計算結果: 5");
@@ -54,6 +72,7 @@ mod tests {
         // 絶対値
         let request = CalculateRequest {
             expression: "abs(-10)".to_string(),
+            ..Default::default()
         };
         let result = calculator.calculate(request).unwrap();
         assert_eq!(result, "計算結果: 10");
@@ -61,6 +80,7 @@ mod tests {
         // べき乗と平方根の組み合わせ
         let request = CalculateRequest {
             expression: "25^0.5".to_string(),
+            ..Default::default()
         };
         let result = calculator.calculate(request).unwrap();
         assert_eq!(result, "計算結果: 5");
@@ -68,11 +88,12 @@ mod tests {
 
     #[test]
     fn test_calculate_error_handling() {
-        let calculator = CalculatorService;
+        let calculator = CalculatorService::default();
 
         // 無効な式
         let request = CalculateRequest {
             expression: "2 +".to_string(),
+            ..Default::default()
         };
         let result = calculator.calculate(request);
         assert!(result.is_err());
@@ -81,18 +102,781 @@ mod tests {
         // 未定義の変数
         let request = CalculateRequest {
             expression: "x + 1".to_string(),
+            ..Default::default()
         };
         let result = calculator.calculate(request);
         assert!(result.is_err());
         assert!(result.unwrap_err().contains("計算エラー"));
     }
 
+    #[cfg(not(feature = "trig"))]
+    #[test]
+    fn test_calculate_without_trig_feature() {
+        let calculator = CalculatorService::default();
+
+        let request = CalculateRequest {
+            expression: "sin(1)".to_string(),
+            ..Default::default()
+        };
+        let result = calculator.calculate(request);
+        assert!(result.unwrap_err().contains("未サポートの関数"));
+    }
+
+    #[cfg(not(feature = "symbolic"))]
+    #[test]
+    fn test_rearrange_without_symbolic_feature() {
+        let calculator = CalculatorService::default();
+
+        let request = RearrangeRequest {
+            equation: "v = d / t".to_string(),
+            symbol: "t".to_string(),
+            variables: None,
+            guess: None,
+        };
+        let result = calculator.rearrange(request);
+        assert!(result.unwrap_err().contains("symbolic feature"));
+        assert!(!CalculatorService::tool_names().contains(&"rearrange".to_string()));
+    }
+
+    #[test]
+    fn test_calculate_rounding_mode() {
+        let calculator = CalculatorService::default();
+
+        let request = CalculateRequest {
+            expression: "2 / 3".to_string(),
+            decimals: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(calculator.calculate(request).unwrap(), "計算結果: 0.67");
+
+        let request = CalculateRequest {
+            expression: "round(2.5) + round(3.5)".to_string(),
+            rounding_mode: Some(RoundingMode::HalfEven),
+            ..Default::default()
+        };
+        assert_eq!(calculator.calculate(request).unwrap(), "計算結果: 6");
+
+        let request = CalculateRequest {
+            expression: "1.005 * 100".to_string(),
+            decimals: Some(0),
+            rounding_mode: Some(RoundingMode::Truncate),
+            ..Default::default()
+        };
+        assert_eq!(calculator.calculate(request).unwrap(), "計算結果: 100");
+
+        // 丸めの途中であふれる値はそのまま表示する
+        let request = CalculateRequest {
+            expression: "10^300".to_string(),
+            decimals: Some(10),
+            ..Default::default()
+        };
+        assert!(calculator
+            .calculate(request)
+            .unwrap()
+            .starts_with("計算結果: 1e300"));
+        let request = CalculateRequest {
+            expression: "2 / 3".to_string(),
+            decimals: Some(400),
+            ..Default::default()
+        };
+        assert!(calculator
+            .calculate(request)
+            .unwrap_err()
+            .contains("100 以下"));
+    }
+
+    #[test]
+    fn test_calculate_notation_and_precision() {
+        let calculator = CalculatorService::default();
+
+        let request = CalculateRequest {
+            expression: "10^25 / 4".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(calculator.calculate(request).unwrap(), "計算結果: 2.5e24");
+
+        let request = CalculateRequest {
+            expression: "1 / 8".to_string(),
+            notation: Some(Notation::Plain),
+            precision: Some(5),
+            ..Default::default()
+        };
+        assert_eq!(calculator.calculate(request).unwrap(), "計算結果: 0.12500");
+    }
+
+    #[test]
+    fn test_calculate_warnings() {
+        let calculator = CalculatorService::default();
+
+        let request = CalculateRequest {
+            expression: "0.001 * 3".to_string(),
+            decimals: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(
+            calculator.calculate(request).unwrap(),
+            "計算結果: 0\n- 警告: 0 でない結果（0.003）を小数点以下 2 桁に丸めたため 0 になりました"
+        );
+
+        let request = CalculateRequest {
+            expression: "2^60 + 1".to_string(),
+            ..Default::default()
+        };
+        let output = calculator.calculate(request).unwrap();
+        assert!(output.starts_with("計算結果: 1152921504606847000\n- 警告: 2^53"));
+
+        // 警告は有効数字モードでも返す
+        let request = CalculateRequest {
+            expression: "2.0 * 10^305".to_string(),
+            sig_figs: true,
+            ..Default::default()
+        };
+        assert!(calculator
+            .calculate(request)
+            .unwrap()
+            .contains("- 警告: 極端に大きい値です"));
+    }
+
+    #[test]
+    fn test_calculate_exact_form() {
+        let calculator = CalculatorService::default();
+        let calculate = |expression: &str| {
+            calculator
+                .calculate(CalculateRequest {
+                    expression: expression.to_string(),
+                    ..Default::default()
+                })
+                .unwrap()
+        };
+        assert_eq!(
+            calculate("sqrt(2)^2"),
+            "計算結果: 2.0000000000000004\n- 厳密な値: 2"
+        );
+        assert_eq!(
+            calculate("1/3"),
+            "計算結果: 0.3333333333333333\n- 厳密な値: 1/3"
+        );
+        #[cfg(feature = "trig")]
+        assert!(calculate("atan2(1, 0)").ends_with("- 厳密な値: pi/2"));
+        assert_eq!(calculate("2.5 * 3"), "計算結果: 7.5");
+    }
+
+    #[test]
+    fn test_calculate_group_digits() {
+        let calculator = CalculatorService::default();
+        let request = CalculateRequest {
+            expression: "1234567.89 * 2".to_string(),
+            group_digits: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(
+            calculator.calculate(request).unwrap(),
+            "計算結果: 2,469,135.78\n- 値: 2469135.78"
+        );
+        let request = CalculateRequest {
+            expression: "1234567.89 * 2".to_string(),
+            group_digits: Some(true),
+            locale: Some(NumberLocale::Fr),
+            ..Default::default()
+        };
+        assert!(calculator
+            .calculate(request)
+            .unwrap()
+            .starts_with("計算結果: 2 469 135,78\n"));
+    }
+
+    #[test]
+    fn test_set_options() {
+        let calculator = CalculatorService::default();
+        let calculate = |expression: &str| {
+            calculator
+                .calculate(CalculateRequest {
+                    expression: expression.to_string(),
+                    ..Default::default()
+                })
+                .unwrap()
+        };
+        let request = SetOptionsRequest {
+            rounding_mode: Some(RoundingMode::HalfEven),
+            precision: Some(2),
+            group_digits: Some(true),
+            locale: Some(NumberLocale::De),
+            ..Default::default()
+        };
+        let output = calculator.set_options(request).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(value["locale"], "de");
+        assert_eq!(
+            calculate("round(2.5) * 1000.5"),
+            "計算結果: 2.001,00\n- 値: 2001.00"
+        );
+
+        // リクエストの指定が優先する
+        let request = CalculateRequest {
+            expression: "1000 / 8".to_string(),
+            precision: Some(3),
+            group_digits: Some(false),
+            ..Default::default()
+        };
+        assert_eq!(calculator.calculate(request).unwrap(), "計算結果: 125.000");
+
+        // 桁数の上限を超える指定は、設定もリクエストもエラーにする
+        let request = SetOptionsRequest {
+            precision: Some(70000),
+            ..Default::default()
+        };
+        assert!(calculator
+            .set_options(request)
+            .unwrap_err()
+            .contains("100 以下"));
+        assert_eq!(calculator.options().precision, Some(2));
+        let request = CalculateRequest {
+            expression: "1 / 3".to_string(),
+            precision: Some(70000),
+            ..Default::default()
+        };
+        assert!(calculator
+            .calculate(request)
+            .unwrap_err()
+            .contains("100 以下"));
+
+        // 指定しなかった項目は変わらない
+        let request = SetOptionsRequest {
+            precision: Some(-1),
+            ..Default::default()
+        };
+        calculator.set_options(request).unwrap();
+        let options = calculator.options();
+        assert_eq!(options.precision, None);
+        assert_eq!(options.rounding_mode, RoundingMode::HalfEven);
+    }
+
+    #[test]
+    fn test_reset_session() {
+        let calculator = CalculatorService::default();
+        let request = SetOptionsRequest {
+            precision: Some(3),
+            rounding_mode: Some(RoundingMode::Floor),
+            ..Default::default()
+        };
+        calculator.set_options(request).unwrap();
+        let request = UploadDataRequest {
+            upload_id: None,
+            chunk: "1,2\n".to_string(),
+            done: None,
+        };
+        calculator.upload_data(request).unwrap();
+
+        let request = CalculateRequest {
+            expression: "1 / 0".to_string(),
+            ..Default::default()
+        };
+        assert!(calculator.calculate(request).is_err());
+
+        // パニックの後の復旧は、更新の途中だった（ポイズニングされた）状態だけを戻す
+        assert!(calculator.recover_poisoned().is_empty());
+        assert_eq!(calculator.options().precision, Some(3));
+        assert_eq!(calculator.history().len(), 1);
+
+        let output = calculator.reset_session().unwrap();
+        assert!(output.contains("計算履歴 1 件"), "{}", output);
+        assert!(output.contains("アップロード 1 件"), "{}", output);
+        assert!(calculator.history().is_empty());
+        assert_eq!(calculator.options().precision, None);
+        assert_eq!(calculator.options().rounding_mode, RoundingMode::HalfUp);
+
+        // セッションの終了時にも戻す
+        let request = SetOptionsRequest {
+            precision: Some(3),
+            ..Default::default()
+        };
+        calculator.set_options(request).unwrap();
+        calculator.end_session();
+        assert_eq!(calculator.options().precision, None);
+    }
+
+    #[tokio::test]
+    async fn test_end_session_keeps_state_for_other_sessions() {
+        let calculator = CalculatorService::default();
+        calculator.on_initialized().await;
+        calculator.on_initialized().await;
+        let request = SetOptionsRequest {
+            precision: Some(3),
+            ..Default::default()
+        };
+        calculator.set_options(request).unwrap();
+
+        // 状態はサーバで1つのため、最後のセッションが終わるまで残す
+        calculator.end_session();
+        assert_eq!(calculator.options().precision, Some(3));
+        calculator.end_session();
+        assert_eq!(calculator.options().precision, None);
+    }
+
+    #[test]
+    fn test_humanize_decimals_limit() {
+        let calculator = CalculatorService::default();
+        let request = HumanizeRequest {
+            value: Some(1234567.0),
+            text: None,
+            locale: Default::default(),
+            bytes: false,
+            decimals: Some(70000),
+        };
+        assert!(calculator
+            .humanize(request)
+            .unwrap_err()
+            .contains("15 以下"));
+    }
+
+    #[test]
+    fn test_load_variables() {
+        let calculator = CalculatorService::default();
+        let calculate = |expression: &str| {
+            calculator.calculate(CalculateRequest {
+                expression: expression.to_string(),
+                ..Default::default()
+            })
+        };
+        let request = LoadVariablesRequest {
+            csv: Some("name,value\nprincipal,1000\nrate,0.05\n".to_string()),
+            ..Default::default()
+        };
+        calculator.load_variables(request).unwrap();
+        assert_eq!(
+            calculate("principal * (1 + rate)^2").unwrap(),
+            "計算結果: 1102.5"
+        );
+
+        // 1つでも不正な組があれば何も読み込まない
+        let request = LoadVariablesRequest {
+            variables: Some(BTreeMap::from([
+                ("years".to_string(), 10.0),
+                ("sqrt".to_string(), 3.0),
+            ])),
+            ..Default::default()
+        };
+        assert!(calculator.load_variables(request).is_err());
+        assert!(calculate("years").is_err());
+
+        let request = LoadVariablesRequest {
+            variables: Some(BTreeMap::from([("years".to_string(), 10.0)])),
+            replace: true,
+            ..Default::default()
+        };
+        calculator.load_variables(request).unwrap();
+        assert_eq!(calculate("years * 2").unwrap(), "計算結果: 20");
+        assert!(calculate("principal").is_err());
+
+        let request = LoadVariablesRequest {
+            variables: Some(BTreeMap::new()),
+            csv: Some("x,1".to_string()),
+            ..Default::default()
+        };
+        assert!(calculator.load_variables(request).is_err());
+    }
+
+    #[cfg(feature = "symbolic")]
+    #[test]
+    fn test_isolate() {
+        let calculator = Calculator::new();
+        let solve = |lhs: &str, rhs: &str, symbol: &str| {
+            let lhs = calculator.compile(lhs).unwrap();
+            let rhs = calculator.compile(rhs).unwrap();
+            let mut notes = Vec::new();
+            let solved = lhs
+                .isolate(symbol, &rhs, &mut notes)
+                .or_else(|| rhs.isolate(symbol, &lhs, &mut notes));
+            (solved, notes)
+        };
+        assert_eq!(solve("v", "d / t", "t").0.as_deref(), Some("d / v"));
+        assert_eq!(solve("v", "d / t", "d").0.as_deref(), Some("v * t"));
+        assert_eq!(
+            solve("y", "m * x + b", "x").0.as_deref(),
+            Some("(y - b) / m")
+        );
+        assert_eq!(
+            solve("a - b", "c - d", "b").0.as_deref(),
+            Some("a - (c - d)")
+        );
+        assert_eq!(
+            solve("e", "sqrt(2 * g * h)", "h").0.as_deref(),
+            Some("e ^ 2 / (2 * g)")
+        );
+        assert_eq!(solve("y", "-x", "x").0.as_deref(), Some("-y"));
+        assert_eq!(
+            solve("ln(x)", "k * t", "x").0.as_deref(),
+            Some("exp(k * t)")
+        );
+        assert_eq!(solve("a", "2 ^ n", "n").0.as_deref(), Some("ln(a) / ln(2)"));
+
+        let (solved, notes) = solve("area", "s ^ 2", "s");
+        assert_eq!(solved.as_deref(), Some("area ^ (1 / 2)"));
+        assert_eq!(notes.len(), 1);
+
+        // 2回現れる変数や、逆がわからない関数は解かない
+        assert_eq!(solve("y", "x * x", "x").0, None);
+        assert_eq!(solve("y", "abs(x)", "x").0, None);
+    }
+
+    #[cfg(feature = "symbolic")]
+    #[test]
+    fn test_rearrange() {
+        let calculator = CalculatorService::default();
+        let request = RearrangeRequest {
+            equation: "v = d / t".to_string(),
+            symbol: "t".to_string(),
+            variables: Some(BTreeMap::from([
+                ("v".to_string(), 20.0),
+                ("d".to_string(), 100.0),
+            ])),
+            guess: None,
+        };
+        let output = calculator.rearrange(request).unwrap();
+        assert!(output.contains("t = d / v"), "{}", output);
+        assert!(output.contains("symbolic"), "{}", output);
+
+        // 記号的に解けない場合は数値的に解く
+        let request = RearrangeRequest {
+            equation: "x * x + x = 6".to_string(),
+            symbol: "x".to_string(),
+            variables: None,
+            guess: Some(1.0),
+        };
+        let output = calculator.rearrange(request).unwrap();
+        assert!(output.contains("numeric"), "{}", output);
+
+        let request = RearrangeRequest {
+            equation: "x * x = a".to_string(),
+            symbol: "x".to_string(),
+            ..Default::default()
+        };
+        let error = calculator.rearrange(request).unwrap_err();
+        assert!(error.contains("a の値"), "{}", error);
+
+        let request = RearrangeRequest {
+            equation: "y = 2".to_string(),
+            symbol: "x".to_string(),
+            ..Default::default()
+        };
+        assert!(calculator.rearrange(request).is_err());
+    }
+
+    #[cfg(feature = "symbolic")]
+    #[test]
+    fn test_solve_system() {
+        let calculator = CalculatorService::default();
+        let request = SolveSystemRequest {
+            equations: vec!["x + y = 10".to_string(), "x * y - k".to_string()],
+            unknowns: BTreeMap::from([("x".to_string(), 1.0), ("y".to_string(), 8.0)]),
+            variables: Some(BTreeMap::from([("k".to_string(), 21.0)])),
+            ..Default::default()
+        };
+        assert!(calculator.solve_system(request).is_ok());
+
+        let request = SolveSystemRequest {
+            equations: vec!["x + y".to_string()],
+            unknowns: BTreeMap::from([("x".to_string(), 1.0), ("y".to_string(), 1.0)]),
+            ..Default::default()
+        };
+        assert!(calculator.solve_system(request).is_err());
+
+        let request = SolveSystemRequest {
+            equations: vec!["x = 1 = 2".to_string()],
+            unknowns: BTreeMap::from([("x".to_string(), 1.0)]),
+            ..Default::default()
+        };
+        assert!(calculator.solve_system(request).is_err());
+    }
+
+    #[test]
+    fn test_calculate_matrix_literals() {
+        let calculator = CalculatorService::default();
+        let request = CalculateRequest {
+            expression: "[[1, 2], [3, 4]] * [5, 6]".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            calculator.calculate(request),
+            Ok("計算結果: [17, 39]\n- 型: 長さ 2 のベクトル".to_string())
+        );
+
+        let request = CalculateRequest {
+            expression: "[[1, 2], [3, 4]] / 3".to_string(),
+            decimals: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(
+            calculator.calculate(request),
+            Ok("計算結果: [[0.33, 0.67], [1, 1.33]]\n- 型: 2×2 の行列".to_string())
+        );
+
+        // 行列の式で数値になった場合は通常の結果と同じ
+        let request = CalculateRequest {
+            expression: "det([[1, 2], [3, 4]])".to_string(),
+            ..Default::default()
+        };
+        assert!(calculator
+            .calculate(request)
+            .unwrap()
+            .starts_with("計算結果: -2"));
+
+        let request = CalculateRequest {
+            expression: "[[1, 2]] * [[1, 2]]".to_string(),
+            ..Default::default()
+        };
+        assert!(calculator.calculate(request).is_err());
+    }
+
+    #[test]
+    fn test_check_constraints() {
+        let calculator = CalculatorService::default();
+        let request = CheckConstraintsRequest {
+            constraints: vec![
+                "x + y <= 10".to_string(),
+                "x >= 0".to_string(),
+                "x * y = 24".to_string(),
+                "z > 0".to_string(),
+            ],
+            variables: Some(BTreeMap::from([
+                ("x".to_string(), 4.0),
+                ("y".to_string(), 6.0),
+            ])),
+        };
+        assert!(calculator.check_constraints(request).is_ok());
+
+        let request = CheckConstraintsRequest {
+            constraints: vec!["x > 0".to_string(); 501],
+            variables: None,
+        };
+        assert!(calculator.check_constraints(request).is_err());
+    }
+
+    #[test]
+    fn test_recalculate() {
+        let calculator = CalculatorService::default();
+        let request = RecalculateRequest {
+            formulas: Some(BTreeMap::from([(
+                "total".to_string(),
+                "price * quantity".to_string(),
+            )])),
+            variables: Some(BTreeMap::from([
+                ("price".to_string(), 120.0),
+                ("quantity".to_string(), 3.0),
+            ])),
+        };
+        calculator.recalculate(request).unwrap();
+        // 変更した変数はそのまま calculate からも参照できる
+        let request = CalculateRequest {
+            expression: "price + 1".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(calculator.calculate(request).unwrap(), "計算結果: 121");
+
+        // 数式と同じ名前の変数は使えない
+        let request = RecalculateRequest {
+            variables: Some(BTreeMap::from([("total".to_string(), 1.0)])),
+            ..Default::default()
+        };
+        assert!(calculator.recalculate(request).is_err());
+
+        let output = calculator.reset_session().unwrap();
+        assert!(output.contains("変数 2 件、数式 1 件"), "{}", output);
+    }
+
+    #[tokio::test]
+    async fn test_calculation_history() {
+        let calculator = CalculatorService::default();
+        let request = CalculateRequest {
+            expression: "1000 * 3".to_string(),
+            group_digits: Some(true),
+            ..Default::default()
+        };
+        calculator.calculate(request).unwrap();
+        let request = CalculateBatchRequest {
+            expressions: vec!["1 / 4".to_string(), "1 +".to_string()],
+            decimals: None,
+            rounding_mode: None,
+            timeout_ms: None,
+            output: None,
+        };
+        calculator.calculate_batch(request).await.unwrap();
+
+        let entries = calculator.history().entries();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].tool, "calculate");
+        assert_eq!(entries[0].result.as_deref(), Some("3,000"));
+        assert_eq!(entries[1].tool, "calculate_batch");
+        assert_eq!(entries[1].result.as_deref(), Some("0.25"));
+        assert!(entries[2].error.is_some());
+    }
+
+    #[cfg(feature = "trig")]
+    #[test]
+    fn test_set_options_angle_mode() {
+        let calculator = CalculatorService::default();
+        let request = SetOptionsRequest {
+            angle_mode: Some(AngleMode::Degrees),
+            ..Default::default()
+        };
+        calculator.set_options(request).unwrap();
+        let request = CalculateRequest {
+            expression: "sin(90)".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(calculator.calculate(request).unwrap(), "計算結果: 1");
+    }
+
+    #[test]
+    fn test_calculate_output_bases() {
+        let calculator = CalculatorService::default();
+        let request = CalculateRequest {
+            expression: "255 + 1".to_string(),
+            output_bases: Some(vec![2, 16]),
+            ..Default::default()
+        };
+        assert_eq!(
+            calculator.calculate(request).unwrap(),
+            "計算結果: 256\n- 2進数: 0b100000000\n- 16進数: 0x100"
+        );
+
+        let request = CalculateRequest {
+            expression: "10 / 4".to_string(),
+            output_bases: Some(vec![16]),
+            ..Default::default()
+        };
+        let output = calculator.calculate(request).unwrap();
+        assert!(output.starts_with("計算結果: 2.5\n- 警告: output_bases の 16 進数は省略しました"));
+
+        let request = CalculateRequest {
+            expression: "1".to_string(),
+            output_bases: Some(vec![1]),
+            ..Default::default()
+        };
+        assert!(calculator.calculate(request).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_calculate_batch() {
+        let calculator = CalculatorService::default();
+        let request = CalculateBatchRequest {
+            expressions: vec![
+                "2 + 3".to_string(),
+                "1 / 0".to_string(),
+                "10 / 3".to_string(),
+            ],
+            decimals: Some(2),
+            rounding_mode: None,
+            timeout_ms: None,
+            output: None,
+        };
+        let output = calculator.calculate_batch(request).await.unwrap();
+        let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+
+        // 入力と同じ順序で、エラーの式があっても他の結果を返す
+        assert_eq!(value["succeeded"], 2);
+        assert_eq!(value["failed"], 1);
+        assert_eq!(value["results"][0]["result"], 5.0);
+        assert_eq!(
+            value["results"][1]["error"],
+            "DIVISION_BY_ZERO: ゼロ除算エラー"
+        );
+        assert_eq!(value["results"][2]["index"], 2);
+        assert_eq!(value["results"][2]["result"], 3.33);
+        assert_eq!(value["results"][2]["normalized_expression"], "10 / 3");
+        assert_eq!(
+            value["results"][0]["canonical_hash"]
+                .as_str()
+                .unwrap()
+                .len(),
+            16
+        );
+
+        let request = CalculateBatchRequest {
+            expressions: vec!["1 + 1".to_string(), "1 / 0".to_string()],
+            decimals: None,
+            rounding_mode: None,
+            timeout_ms: None,
+            output: Some(OutputFormat::Markdown),
+        };
+        let output = calculator.calculate_batch(request).await.unwrap();
+        let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(
+            value["markdown"],
+            "| index | expression | result |\n| --- | --- | --- |\n| 0 | 1 + 1 | 2 |\n| 1 | 1 / 0 | エラー: DIVISION_BY_ZERO: ゼロ除算エラー |"
+        );
+
+        let request = CalculateBatchRequest {
+            expressions: vec!["1".to_string(); 501],
+            decimals: None,
+            rounding_mode: None,
+            timeout_ms: None,
+            output: None,
+        };
+        assert!(calculator.calculate_batch(request).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_calculate_batch_value_types() {
+        let calculator = CalculatorService::default();
+        let request = CalculateBatchRequest {
+            expressions: vec![
+                "1 / 3".to_string(),
+                "(1 + 2i) * (3 - i)".to_string(),
+                "[[1, 2], [3, 4]]".to_string(),
+                "5 km + 300 m".to_string(),
+                "1 < 2".to_string(),
+            ],
+            decimals: None,
+            rounding_mode: None,
+            timeout_ms: None,
+            output: None,
+        };
+        let output = calculator.calculate_batch(request).await.unwrap();
+        let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+        let results = &value["results"];
+
+        assert_eq!(results[0]["type"], "rational");
+        assert_eq!(results[0]["is_exact"], true);
+        assert_eq!(results[0]["exact"], "1/3");
+        assert_eq!(results[1]["type"], "complex");
+        assert_eq!(
+            results[1]["result"],
+            serde_json::json!({ "re": 5.0, "im": 5.0 })
+        );
+        assert_eq!(results[2]["type"], "matrix");
+        assert_eq!(results[2]["shape"], serde_json::json!([2, 2]));
+        assert_eq!(results[2]["result"][1][0], 3.0);
+        // ベクトル・行列の式は compile できないため、解釈した式は返さない
+        assert!(results[2].get("normalized_expression").is_none());
+        assert_eq!(results[3]["type"], "quantity");
+        assert_eq!(results[3]["unit"], "km");
+        assert_eq!(results[3]["result"], 5.3);
+        assert_eq!(results[3]["is_exact"], false);
+        assert_eq!(results[4]["type"], "boolean");
+        assert_eq!(results[4]["result"], true);
+    }
+
+    #[test]
+    fn test_calculate_sig_figs() {
+        let calculator = CalculatorService::default();
+
+        let request = CalculateRequest {
+            expression: "12.0 * 2.5".to_string(),
+            sig_figs: true,
+            ..Default::default()
+        };
+        let result = calculator.calculate(request).unwrap();
+        assert!(result.starts_with("計算結果: 30（有効数字2桁）\n- "));
+    }
+
     #[test]
     fn test_calculate_floating_point() {
-        let calculator = CalculatorService;
+        let calculator = CalculatorService::default();
 
         let request = CalculateRequest {
             expression: "3.14 * 2".to_string(),
+            ..Default::default()
         };
         let result = calculator.calculate(request).unwrap();
         assert_eq!(result, "計算結果: 6.28");
@@ -100,18 +884,733 @@ mod tests {
 
     #[test]
     fn test_calculate_power() {
-        let calculator = CalculatorService;
+        let calculator = CalculatorService::default();
 
         let request = CalculateRequest {
             expression: "2^3".to_string(),
+            ..Default::default()
         };
         let result = calculator.calculate(request).unwrap();
         assert_eq!(result, "計算結果: 8");
     }
 
+    #[test]
+    fn test_multiline_expression_with_comments() {
+        let calculator = Calculator::new();
+        let expression = "(1200 * 3)   # 単価 × 個数\n\t+ 500 # 送料\r\n* 2";
+        assert_eq!(calculator.evaluate(expression), Ok(4600.0));
+        assert_eq!(calculator.evaluate("sqrt\n(16) # 平方根"), Ok(4.0));
+        assert!(calculator.evaluate("# 式がない").is_err());
+    }
+
+    #[test]
+    fn test_percent_literals() {
+        let calculator = Calculator::new();
+        assert_eq!(calculator.evaluate("15% * 200"), Ok(30.0));
+        assert_eq!(calculator.evaluate("200 * 15%"), Ok(30.0));
+        assert_eq!(calculator.evaluate("200 + 10%"), Ok(220.0));
+        assert_eq!(calculator.evaluate("200 - 10%"), Ok(180.0));
+        assert_eq!(calculator.evaluate("(200 + 10%) + 10%"), Ok(242.0));
+        // 右辺が百分率のリテラルだけでなければ 0.1 として計算する
+        assert_eq!(calculator.evaluate("200 + 10% * 2"), Ok(200.2));
+        assert!(calculator.evaluate("200 + %").is_err());
+
+        let compiled = calculator.compile("200 + 10%").unwrap();
+        assert_eq!(compiled.eval(&HashMap::new()), Ok(220.0));
+        assert_eq!(compiled.to_infix(), "200 + 200 * 0.1");
+        let compiled = calculator
+            .compile("(2 - (3 - 4)) * -(1 + 1) + (-2)^2")
+            .unwrap();
+        assert_eq!(compiled.to_infix(), "(2 - (3 - 4)) * -(1 + 1) + (-2) ^ 2");
+
+        let service = CalculatorService::default();
+        let request = CalculateRequest {
+            expression: "1200 - 15%".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            service.calculate(request).unwrap(),
+            "計算結果: 1020\n- 解釈: 1200 - 1200 * 0.15"
+        );
+    }
+
+    #[test]
+    fn test_normalized_expression() {
+        let service = CalculatorService::default();
+        let calculate = |expression: &str| {
+            service
+                .calculate(CalculateRequest {
+                    expression: expression.to_string(),
+                    ..Default::default()
+                })
+                .unwrap()
+        };
+        // 表記が同じなら解釈の行は付けない
+        assert_eq!(calculate("1/2*4"), "計算結果: 2");
+        assert_eq!(calculate("1 / (2 * 4)"), "計算結果: 0.125");
+        assert_eq!(
+            calculate("((1 / 2)) * 4 # 半分の4倍"),
+            "計算結果: 2\n- 解釈: 1 / 2 * 4"
+        );
+        assert_eq!(
+            calculate("√16 + |-2|"),
+            "計算結果: 6\n- 解釈: sqrt(16) + abs(-2)"
+        );
+    }
+
+    #[test]
+    fn test_implicit_multiplication_and_strict_mode() {
+        let mut calculator = Calculator::new();
+        calculator.set_variable("x", 3.0).unwrap();
+        calculator.set_variable("y", 4.0).unwrap();
+        assert_eq!(calculator.evaluate("2x"), Ok(6.0));
+        assert_eq!(calculator.evaluate("2(x + 1)"), Ok(8.0));
+        assert_eq!(calculator.evaluate("(x + y)(y - x)"), Ok(7.0));
+        assert_eq!(calculator.evaluate("2sqrt(16)"), Ok(8.0));
+        assert_eq!(calculator.evaluate("1/2x"), Ok(1.5));
+        assert_eq!(calculator.evaluate("6 ÷ 2 × 3 − 1"), Ok(8.0));
+        assert_eq!(calculator.corrections("2 * x"), Ok(vec![]));
+        assert_eq!(
+            calculator.corrections("2x").unwrap(),
+            vec!["2 と x の間の掛け算の * を補いました"]
+        );
+        assert!(calculator.corrections("1/2x").unwrap()[0].contains("(1/2)*x"));
+        assert!(calculator.corrections("1/sqrt(4)x").unwrap()[0].contains("(1/2)*x"));
+        assert_eq!(
+            calculator.corrections("3 × 4 × 5").unwrap(),
+            vec!["× を * として扱いました"]
+        );
+
+        calculator.set_strict(true);
+        assert_eq!(calculator.evaluate("2 * x"), Ok(6.0));
+        assert!(calculator.evaluate("2x").unwrap_err().contains("strict"));
+        assert!(calculator.evaluate("1/2x").unwrap_err().contains("strict"));
+        assert!(calculator.evaluate("3 × 4").unwrap_err().contains("×"));
+
+        let service = CalculatorService::default();
+        let request = CalculateRequest {
+            expression: "2(3 + 4)".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            service.calculate(request).unwrap(),
+            "計算結果: 14\n- 解釈: 2 * (3 + 4)\n- 警告: 2 と ( の間の掛け算の * を補いました"
+        );
+        let request = CalculateRequest {
+            expression: "2(3 + 4)".to_string(),
+            strict: Some(true),
+            ..Default::default()
+        };
+        assert!(service.calculate(request).is_err());
+    }
+
+    #[test]
+    fn test_clarifications() {
+        let mut calculator = Calculator::new();
+        calculator.set_variable("x", 4.0).unwrap();
+        let expressions = |calculator: &Calculator, expression: &str| -> Vec<Vec<String>> {
+            calculator
+                .clarifications(expression)
+                .into_iter()
+                .map(|clarification| {
+                    clarification
+                        .interpretations
+                        .into_iter()
+                        .map(|interpretation| interpretation.expression)
+                        .collect()
+                })
+                .collect()
+        };
+
+        assert_eq!(
+            expressions(&calculator, "3 + 1/2x^2 - 1"),
+            vec![vec!["3 + 1/2 * x^2 - 1", "3 + 1/(2x^2) - 1"]]
+        );
+        assert_eq!(
+            expressions(&calculator, "1/2sqrt(x)"),
+            vec![vec!["1/2 * sqrt(x)", "1/(2sqrt(x))"]]
+        );
+        assert_eq!(
+            expressions(&calculator, "log(100) + log(8, 2)"),
+            vec![vec![
+                "log10(100) + log(8, 2)",
+                "ln(100) + log(8, 2)",
+                "log2(100) + log(8, 2)"
+            ]]
+        );
+        assert!(expressions(&calculator, "2x + 1/2 * x + log10(x)").is_empty());
+        // 書き直した式はあいまいさがなく、それぞれの解釈で計算できる
+        assert_eq!(calculator.evaluate("1/2 * x"), Ok(2.0));
+        assert_eq!(calculator.evaluate("1/(2x)"), Ok(0.125));
+
+        let clarification = &calculator.clarifications("1/2x")[0];
+        assert_eq!(clarification.text, "1/2x");
+        assert_eq!(
+            clarification.question,
+            "1/2x は (1/2) * x と 1 / (2x) のどちらの意味ですか"
+        );
+
+        // 100m は SI 接頭辞が有効なときだけ 0.1 と読む
+        assert!(expressions(&calculator, "100m").is_empty());
+        calculator.set_si_prefixes(true);
+        assert_eq!(
+            expressions(&calculator, "2 * 100m"),
+            vec![vec!["2 * (100 / 1000)", "2 * 100 m"]]
+        );
+        assert!(expressions(&calculator, "4.7k").is_empty());
+
+        let service = CalculatorService::default();
+        let request = CalculateRequest {
+            expression: "log(100)".to_string(),
+            clarify: Some(true),
+            ..Default::default()
+        };
+        let error = service.calculate(request).unwrap_err();
+        assert!(error.starts_with("NEEDS_CLARIFICATION: "));
+        assert!(error.contains("\n- log10(100)（常用対数（底 10））"));
+        let request = CalculateRequest {
+            expression: "log(100, 10)".to_string(),
+            clarify: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(service.calculate(request).unwrap(), "計算結果: 2");
+    }
+
+    #[test]
+    fn test_si_prefixes() {
+        let mut calculator = Calculator::new();
+        // 既定では無効（m は変数として扱う）
+        assert_ne!(calculator.evaluate("100m"), Ok(0.1));
+
+        calculator.set_si_prefixes(true);
+        assert_eq!(calculator.evaluate("4.7k"), Ok(4700.0));
+        assert_eq!(calculator.evaluate("100m"), Ok(0.1));
+        assert_eq!(calculator.evaluate("2.2M / 1k"), Ok(2200.0));
+        assert_eq!(calculator.evaluate("5u"), Ok(5e-6));
+        assert_eq!(
+            calculator
+                .evaluate("1 / (2 * 3.14159 * 10k * 100n)")
+                .map(|f| f.round()),
+            Ok(159.0)
+        );
+        // 識別子が続く場合は接頭辞として読まない
+        calculator.set_variable("mm", 2.0).unwrap();
+        assert_eq!(calculator.evaluate("mm * 3"), Ok(6.0));
+        assert_ne!(calculator.evaluate("3km"), Ok(3000.0));
+
+        let sig_figs = calculator.evaluate_sig_figs("4.7k * 2.00").unwrap();
+        assert_eq!(sig_figs.digits, Some(2));
+        assert_eq!(sig_figs.display, "9.4e3");
+
+        let service = CalculatorService::default();
+        let request = CalculateRequest {
+            expression: "4.7k + 330".to_string(),
+            si_prefixes: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(
+            service.calculate(request).unwrap(),
+            "計算結果: 5030\n- 解釈: 4700 + 330"
+        );
+    }
+
+    #[test]
+    fn test_roots() {
+        let calculator = Calculator::new();
+        assert_eq!(calculator.evaluate("root(-8, 3)").unwrap(), -2.0);
+        assert_eq!(calculator.evaluate("root(64, 3)").unwrap(), 4.0);
+        assert_eq!(calculator.evaluate("root(16, 4) + 1").unwrap(), 3.0);
+        assert_eq!(calculator.evaluate("cbrt(-27)").unwrap(), -3.0);
+        assert_eq!(calculator.evaluate("√16").unwrap(), 4.0);
+        assert_eq!(calculator.evaluate("√(3^2 + 4^2)").unwrap(), 5.0);
+
+        assert!(calculator
+            .evaluate("root(-16, 4)")
+            .unwrap_err()
+            .contains("-16"));
+        assert!(calculator.evaluate("√-4").unwrap_err().contains("-4"));
+        assert!(calculator.evaluate("root(8, 0)").is_err());
+        assert!(calculator.evaluate("root(8)").unwrap_err().contains("2 個"));
+        assert!(calculator
+            .evaluate("sqrt(4, 2)")
+            .unwrap_err()
+            .contains("1 個"));
+
+        let compiled = calculator.compile("root(x, 3) + √x").unwrap();
+        assert_eq!(compiled.to_infix(), "root(x, 3) + sqrt(x)");
+        let error = compiled
+            .eval_array("x", &[8.0, -8.0], &HashMap::new())
+            .unwrap_err();
+        assert!(error.contains("x = -8"));
+        assert_eq!(
+            compiled.eval(&HashMap::from([("x".to_string(), 64.0)])),
+            Ok(12.0)
+        );
+    }
+
+    #[test]
+    fn test_logarithms() {
+        let calculator = Calculator::new();
+        assert_eq!(calculator.evaluate("log10(1000)").unwrap(), 3.0);
+        assert_eq!(calculator.evaluate("log2(1024)").unwrap(), 10.0);
+        assert_eq!(calculator.evaluate("log(1000, 10)").unwrap(), 3.0);
+        assert!((calculator.evaluate("log(81, 3)").unwrap() - 4.0).abs() < 1e-12);
+
+        assert_eq!(
+            calculator.evaluate("ln(0)").unwrap_err(),
+            "DOMAIN_ERROR: ln は x = 0 では定義されません（定義域: x > 0）"
+        );
+        assert!(calculator
+            .evaluate("log10(2 - 5)")
+            .unwrap_err()
+            .contains("x = -3"));
+        assert!(calculator.evaluate("log(8, 1)").unwrap_err().contains("底"));
+        assert!(calculator.evaluate("log(8)").is_err());
+    }
+
+    #[test]
+    fn test_exponentials() {
+        let calculator = Calculator::new();
+        assert_eq!(calculator.evaluate("exp(0)").unwrap(), 1.0);
+        assert_eq!(calculator.evaluate("ln(exp(2))").unwrap(), 2.0);
+
+        // 1 + 1e-15 の丸めで失われる桁を保つ
+        let naive = calculator.evaluate("exp(0.000000000000001) - 1").unwrap();
+        let expm1 = calculator.evaluate("expm1(0.000000000000001)").unwrap();
+        assert!((expm1 - 1e-15).abs() < 1e-30);
+        assert!((naive - 1e-15).abs() > 1e-17);
+        assert!((calculator.evaluate("ln1p(0.000000000000001)").unwrap() - 1e-15).abs() < 1e-30);
+
+        assert!(calculator
+            .evaluate("ln1p(-1)")
+            .unwrap_err()
+            .contains("定義域: x > -1"));
+        assert!(calculator.evaluate("exp(1000)").is_err());
+    }
+
+    #[test]
+    fn test_special_functions() {
+        let calculator = Calculator::new();
+        let close = |expression: &str, expected: f64| {
+            let value = calculator.evaluate(expression).unwrap();
+            assert!(
+                ((value - expected) / expected).abs() < 1e-13,
+                "{} = {}",
+                expression,
+                value
+            );
+        };
+        assert_eq!(calculator.evaluate("gamma(5)").unwrap(), 24.0);
+        close("gamma(5.5)", 52.342_777_784_553_52);
+        close("gamma(0.5)", std::f64::consts::PI.sqrt());
+        close("gamma(-0.5)", -2.0 * std::f64::consts::PI.sqrt());
+        close("lgamma(1000)", 5_905.220_423_209_181);
+        close("erf(1)", 0.842_700_792_949_714_9);
+        close("erf(-0.5)", -0.520_499_877_813_046_5);
+        close("erfc(3)", 2.209_049_699_858_544e-5);
+        close("erfc(10)", 2.088_487_583_762_545e-45);
+        close("erf(0.3) + erfc(0.3)", 1.0);
+
+        assert!(calculator
+            .evaluate("gamma(-2)")
+            .unwrap_err()
+            .contains("gamma は x = -2 では定義されません"));
+        assert!(calculator.evaluate("gamma(200)").is_err());
+    }
+
+    #[test]
+    fn test_function_domains() {
+        let calculator = Calculator::new();
+        assert_eq!(
+            calculator.evaluate("ln(-2)").unwrap_err(),
+            "DOMAIN_ERROR: ln は x = -2 では定義されません（定義域: x > 0）"
+        );
+        assert_eq!(
+            calculator.evaluate("sqrt(-4)").unwrap_err(),
+            "DOMAIN_ERROR: sqrt は x = -4 では定義されません（定義域: x ≥ 0）"
+        );
+        assert_eq!(
+            calculator.evaluate_value("lgamma(0)").unwrap_err(),
+            "DOMAIN_ERROR: lgamma は x = 0 では定義されません（定義域: 0 と負の整数を除く実数）"
+        );
+        // 定義域の境界と、検査するのは最初の引数だけであること
+        assert_eq!(calculator.evaluate("sqrt(0)").unwrap(), 0.0);
+        assert!(calculator
+            .evaluate("log(8, -2)")
+            .unwrap_err()
+            .contains("底"));
+
+        // コンパイルした式と監査も同じエラーを返す
+        let compiled = calculator.compile("ln(x) + sqrt(x)").unwrap();
+        let vars = HashMap::from([("x".to_string(), -2.0)]);
+        assert!(compiled.eval(&vars).unwrap_err().contains("ln は x = -2"));
+        assert!(compiled.audit(&vars).unwrap_err().contains("ln は x = -2"));
+        let error = compiled
+            .eval_array("x", &[1.0, 0.0], &HashMap::new())
+            .unwrap_err();
+        assert!(error.contains("定義域: x > 0"));
+
+        let domains = calculator.function_domains();
+        assert_eq!(domains["ln"], "x > 0");
+        assert_eq!(domains["sqrt"], "x ≥ 0");
+        assert!(!domains.contains_key("abs"));
+    }
+
+    #[test]
+    fn test_hypot_and_clamp() {
+        let calculator = Calculator::new();
+        assert_eq!(calculator.evaluate("hypot(3, 4)").unwrap(), 5.0);
+        assert_eq!(calculator.evaluate("hypot(-5, 12)").unwrap(), 13.0);
+        // 3e200 の 2 乗は f64 の範囲を超えるが、結果は範囲内
+        assert_eq!(
+            calculator
+                .evaluate("hypot(3 * 10^200, 4 * 10^200) / 10^200")
+                .unwrap(),
+            5.0
+        );
+        assert!(calculator.evaluate("sqrt((3 * 10^200)^2)").is_err());
+
+        assert_eq!(calculator.evaluate("clamp(15, 0, 10)").unwrap(), 10.0);
+        assert_eq!(calculator.evaluate("clamp(-3, 0, 10)").unwrap(), 0.0);
+        assert_eq!(calculator.evaluate("clamp(2 + 3, 0, 10)").unwrap(), 5.0);
+        assert!(calculator
+            .evaluate("clamp(5, 10, 0)")
+            .unwrap_err()
+            .contains("clamp(5, 10, 0)"));
+        assert!(calculator.evaluate("clamp(5, 10)").is_err());
+    }
+
+    #[cfg(feature = "trig")]
+    #[test]
+    fn test_atan2() {
+        let mut calculator = Calculator::new();
+        let quadrants = [(1.0, 1.0, 45.0), (1.0, -1.0, 135.0), (-1.0, -1.0, -135.0)];
+        for (y, x, degrees) in quadrants {
+            let radians = calculator
+                .evaluate(&format!("atan2({}, {})", y, x))
+                .unwrap();
+            assert!((radians - f64::to_radians(degrees)).abs() < 1e-15);
+        }
+        assert!(calculator.evaluate("atan2(0, 0)").is_err());
+
+        calculator.set_angle_mode(AngleMode::Degrees);
+        assert_eq!(calculator.evaluate("atan2(1, -1)").unwrap(), 135.0);
+        assert_eq!(calculator.evaluate("sin(atan2(1, 0))").unwrap(), 1.0);
+        let compiled = calculator.compile("atan2(y, 1)").unwrap();
+        assert_eq!(
+            compiled
+                .eval_array("y", &[0.0, -1.0], &HashMap::new())
+                .unwrap(),
+            vec![0.0, -45.0]
+        );
+    }
+
+    #[test]
+    fn test_variadic_functions() {
+        let mut calculator = Calculator::new();
+        assert_eq!(calculator.evaluate("min(3, -1, 2)").unwrap(), -1.0);
+        assert_eq!(calculator.evaluate("max(3, 7, 5) * 2").unwrap(), 14.0);
+        assert_eq!(calculator.evaluate("avg(1, 2, 3, 4)").unwrap(), 2.5);
+        assert_eq!(calculator.evaluate("max(min(5, 9), 2^3)").unwrap(), 8.0);
+        assert!(calculator
+            .evaluate("max(1)")
+            .unwrap_err()
+            .contains("2 個以上"));
+
+        let limits = ExpressionLimits {
+            max_function_args: 3,
+            ..ExpressionLimits::default()
+        };
+        calculator.set_limits(limits).unwrap();
+        assert_eq!(calculator.evaluate("max(1, 2, 3)").unwrap(), 3.0);
+        assert!(calculator
+            .evaluate("max(1, 2, 3, 4)")
+            .unwrap_err()
+            .contains("最大3個"));
+        assert!(calculator.compile("avg(1, 2, 3, 4)").is_err());
+    }
+
+    #[test]
+    fn test_safe_profile() {
+        let mut calculator = Calculator::new();
+        assert!(calculator.evaluate_value("[[1, 1], [0, 1]]^2000").is_ok());
+
+        calculator.set_safe_profile(Some(SafeProfile {
+            max_exponent: 100.0,
+            max_list_length: 3,
+            max_matrix_dimension: 2,
+            ..SafeProfile::default()
+        }));
+        assert_eq!(calculator.evaluate("2^100").unwrap(), 2f64.powi(100));
+        let error = calculator.evaluate("2^-101").unwrap_err();
+        assert!(error.starts_with("LIMIT_EXCEEDED:"), "{}", error);
+        assert!(error.contains("^ の指数の絶対値"), "{}", error);
+        assert!(calculator
+            .evaluate_value("[[1, 1], [0, 1]]^2000")
+            .unwrap_err()
+            .contains("上限（100）"));
+        assert!(calculator
+            .evaluate_value("[1, 2, 3, 4]")
+            .unwrap_err()
+            .contains("配列の要素数"));
+        assert!(calculator
+            .evaluate_value("[[1, 2, 3], [4, 5, 6]]")
+            .unwrap_err()
+            .contains("2 × 3"));
+        assert!(calculator
+            .evaluate("gamma(1002)")
+            .unwrap_err()
+            .contains("n! の n"));
+
+        // compile した式は compile 時の safe プロファイルに従う
+        let compiled = calculator.compile("x^y").unwrap();
+        let vars = HashMap::from([("x".to_string(), 2.0), ("y".to_string(), 200.0)]);
+        assert!(compiled
+            .eval(&vars)
+            .unwrap_err()
+            .starts_with("LIMIT_EXCEEDED:"));
+        assert!(compiled.audit(&vars).is_err());
+        assert!(compiled
+            .eval_array("y", &[1.0, 200.0], &vars)
+            .unwrap_err()
+            .contains("y = 200"));
+        assert!(compiled
+            .eval_array("y", &[1.0; 4], &vars)
+            .unwrap_err()
+            .contains("配列の要素数"));
+
+        calculator.set_safe_profile(None);
+        let compiled = calculator.compile("x^y").unwrap();
+        assert_eq!(compiled.eval(&vars).unwrap(), 2f64.powi(200));
+    }
+
+    #[test]
+    fn test_estimate_cost() {
+        let mut calculator = Calculator::new();
+        let estimate = calculator.estimate("2 + 3 * 4").unwrap();
+        assert_eq!(
+            (estimate.operations, estimate.function_calls, estimate.depth),
+            (2, 0, 3)
+        );
+        assert_eq!(estimate.complexity, Complexity::Constant);
+        assert!(!estimate.approximate);
+        assert_eq!(
+            calculator
+                .estimate("sqrt(x) + round(2.5)")
+                .unwrap()
+                .function_calls,
+            2
+        );
+
+        // 数式で定義された関数は呼び出すたびに本体を数える
+        calculator.define_function("double", "2 * x").unwrap();
+        let estimate = calculator.estimate("double(double(1))").unwrap();
+        assert_eq!(
+            (estimate.operations, estimate.function_calls, estimate.depth),
+            (4, 2, 4)
+        );
+        calculator
+            .define_function("forever", "forever(x) + 1")
+            .unwrap();
+        assert!(calculator
+            .estimate("forever(1)")
+            .unwrap_err()
+            .contains("深すぎます"));
+
+        let estimate = calculator.estimate("[[1, 1], [0, 1]]^2000").unwrap();
+        assert!(estimate.approximate);
+        assert_eq!(estimate.complexity, Complexity::Cubic);
+        assert_eq!(estimate.loops[0].operation, "matrix_power");
+        assert_eq!(estimate.loops[0].iterations, 16);
+        assert_eq!(
+            calculator.estimate("[1, 2] + [3, 4]").unwrap().complexity,
+            Complexity::Linear
+        );
+        assert_eq!(calculator.estimate("2 > 1").unwrap().operations, 1);
+
+        // 評価はしないが、構文の誤りはエラーにする
+        assert!(calculator.estimate("1 +").is_err());
+        assert!(calculator.estimate("1 / 0").is_ok());
+    }
+
+    #[test]
+    fn test_calculate_dry_run() {
+        let service = CalculatorService::default();
+        let request = CalculateRequest {
+            expression: "ln(2) * 3".to_string(),
+            dry_run: true,
+            ..Default::default()
+        };
+        let output = service.calculate(request).unwrap();
+        let response: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(response["dry_run"], true);
+        assert_eq!(response["estimate"]["operations"], 2);
+        assert_eq!(response["estimate"]["complexity"], "constant");
+
+        let request = CalculateRequest {
+            expression: "ln(2) *".to_string(),
+            dry_run: true,
+            ..Default::default()
+        };
+        assert!(service
+            .calculate(request)
+            .unwrap_err()
+            .starts_with("計算エラー:"));
+    }
+
+    #[test]
+    fn test_interpolation() {
+        let calculator = Calculator::new();
+        assert_eq!(calculator.evaluate("lerp(10, 20, 0.25)").unwrap(), 12.5);
+        assert_eq!(calculator.evaluate("lerp(0.1, 0.7, 1)").unwrap(), 0.7);
+        assert_eq!(calculator.evaluate("lerp(0, 10, 1.5)").unwrap(), 15.0);
+
+        assert_eq!(
+            calculator.evaluate("maprange(75, 0, 100, 0, 255)").unwrap(),
+            191.25
+        );
+        // 逆向きの範囲と外挿
+        assert_eq!(
+            calculator
+                .evaluate("maprange(-40, -40, 100, 1, 0)")
+                .unwrap(),
+            1.0
+        );
+        assert_eq!(
+            calculator.evaluate("maprange(20, 0, 10, 0, 1)").unwrap(),
+            2.0
+        );
+        assert!(calculator
+            .evaluate("maprange(5, 3, 3, 0, 1)")
+            .unwrap_err()
+            .contains("幅が 0"));
+    }
+
+    #[test]
+    fn test_evaluate_with_bindings() {
+        let mut calculator = Calculator::new();
+        calculator.set_variable("x", 100.0).unwrap();
+        calculator.define_constant("rate", 0.5).unwrap();
+
+        // 束縛した値は同じ名前の変数・定数より優先する
+        let vars = HashMap::from([("x".to_string(), 3.0)]);
+        assert_eq!(calculator.evaluate_with("x^2 + rate", &vars).unwrap(), 9.5);
+        let vars = HashMap::from([("rate".to_string(), 2.0)]);
+        assert_eq!(calculator.evaluate_with("x * rate", &vars).unwrap(), 200.0);
+
+        // 束縛は呼び出しごとで、セッションの変数は変わらない
+        assert_eq!(calculator.evaluate("x").unwrap(), 100.0);
+        let error = calculator
+            .evaluate_with("y + 1", &HashMap::new())
+            .unwrap_err();
+        assert!(error.contains("未定義の変数"));
+    }
+
+    #[test]
+    fn test_compiled_expression_matches_evaluate() {
+        let mut calculator = Calculator::new();
+        calculator.define_constant("rate", 0.1).unwrap();
+        calculator.define_function("double", "2*x").unwrap();
+        calculator
+            .define_function("quad", "double(double(x)) + rate")
+            .unwrap();
+        calculator.define_function("loop", "loop(x)").unwrap();
+        calculator.set_variable("offset", 5.0).unwrap();
+        calculator.set_rounding_mode(RoundingMode::HalfEven);
+
+        let expressions = [
+            "x^2 - 3*x + 2",
+            "-(x + offset) / 2 * rate",
+            "quad(x) + round(x / 2)",
+            "sqrt(x) + abs(-x) + ln(x + 1)",
+            "1 / (x - 2)",
+            "loop(x)",
+            "y + 1",
+        ];
+        for expression in expressions {
+            let compiled = calculator.compile(expression).unwrap();
+            for x in [0.0, 1.0, 2.0, 2.5, 9.0] {
+                let vars = HashMap::from([("x".to_string(), x)]);
+                assert_eq!(
+                    compiled.eval(&vars),
+                    calculator.evaluate_with(expression, &vars),
+                    "{} (x = {})",
+                    expression,
+                    x
+                );
+            }
+        }
+
+        // 構文の誤りは compile の時点で分かる
+        assert!(calculator.compile("2 +").is_err());
+        assert!(calculator.compile("foo(1)").is_err());
+        assert!(calculator.compile("1;2").is_err());
+    }
+
+    #[test]
+    fn test_canonical_hash() {
+        let calculator = Calculator::new();
+        let hash =
+            |calculator: &Calculator, expression| calculator.canonical_hash(expression).unwrap();
+
+        assert_eq!(hash(&calculator, "2+3*4"), hash(&calculator, "2 + (3 * 4)"));
+        assert_eq!(
+            hash(&calculator, "x * 2.0 + 1"),
+            hash(&calculator, "1 + 2 * x")
+        );
+        assert_ne!(hash(&calculator, "2 - 3"), hash(&calculator, "3 - 2"));
+        assert_ne!(
+            hash(&calculator, "(2 + 3) * 4"),
+            hash(&calculator, "2 + 3 * 4")
+        );
+        assert_eq!(
+            calculator.compile("-(a) + sqrt(4)").unwrap().canonical(),
+            "(+ (neg a) (sqrt 4))"
+        );
+        assert!(calculator.canonical_hash("2 +").is_err());
+    }
+
+    // 度数法の三角関数は別の計算になる
+    #[cfg(feature = "trig")]
+    #[test]
+    fn test_canonical_hash_angle_mode() {
+        let mut calculator = Calculator::new();
+        let radians = calculator.canonical_hash("sin(30)").unwrap();
+        calculator.set_angle_mode(AngleMode::Degrees);
+        assert_ne!(calculator.canonical_hash("sin(30)").unwrap(), radians);
+    }
+
+    #[test]
+    fn test_compiled_eval_array() {
+        let mut calculator = Calculator::new();
+        calculator.define_function("double", "2*x").unwrap();
+        let compiled = calculator.compile("double(x)^2 / k + round(x)").unwrap();
+        let vars = HashMap::from([("k".to_string(), 4.0)]);
+
+        // チャンクの境界をまたぐ長さ
+        let xs: Vec<f64> = (0..600).map(|i| f64::from(i) / 7.0 - 30.0).collect();
+        let results = compiled.eval_array("x", &xs, &vars).unwrap();
+        assert_eq!(results.len(), xs.len());
+        for (x, result) in xs.iter().zip(&results) {
+            let point = HashMap::from([("k".to_string(), 4.0), ("x".to_string(), *x)]);
+            assert_eq!(*result, compiled.eval(&point).unwrap());
+        }
+
+        // エラーは最初に失敗した値とあわせて返す
+        let compiled = calculator.compile("1 / (x - 3)").unwrap();
+        let error = compiled
+            .eval_array("x", &[1.0, 2.0, 3.0, 4.0], &HashMap::new())
+            .unwrap_err();
+        assert_eq!(error, "x = 3: DIVISION_BY_ZERO: ゼロ除算エラー");
+        let error = compiled
+            .eval_array("t", &[1.0], &HashMap::new())
+            .unwrap_err();
+        assert!(error.contains("未定義の変数: x"));
+    }
+
     #[test]
     fn test_server_info() {
-        let calculator = CalculatorService;
+        let calculator = CalculatorService::default();
         let info = calculator.get_info();
 
         assert_eq!(info.server_info.name, "calc-mcp");
@@ -122,12 +1621,13 @@ mod tests {
 
     #[test]
     fn test_security_input_length_limit() {
-        let calculator = CalculatorService;
+        let calculator = CalculatorService::default();
 
         // 長すぎる入力
         let long_expression = "1+".repeat(1000);
         let request = CalculateRequest {
             expression: long_expression,
+            ..Default::default()
         };
         let result = calculator.calculate(request);
         assert!(result.is_err());
@@ -136,32 +1636,61 @@ mod tests {
 
     #[test]
     fn test_security_dangerous_characters() {
-        let calculator = CalculatorService;
+        let calculator = CalculatorService::default();
 
         // 危険な文字のテスト
-        let dangerous_inputs = vec![
-            "2 + 3; rm -rf /",
-            "2 + 3 | echo hello",
-            "2 + 3 & echo world",
-        ];
+        let dangerous_inputs = vec!["2 + 3; rm -rf /", "2 + 3 & echo world"];
 
         for input in dangerous_inputs {
             let request = CalculateRequest {
                 expression: input.to_string(),
+                ..Default::default()
             };
             let result = calculator.calculate(request);
             assert!(result.is_err());
             assert!(result.unwrap_err().contains("不正な文字"));
         }
+
+        // | は絶対値の記号としてのみ受け付ける
+        let request = CalculateRequest {
+            expression: "2 + 3 | echo hello".to_string(),
+            ..Default::default()
+        };
+        assert!(calculator.calculate(request).unwrap_err().contains("|"));
+    }
+
+    #[test]
+    fn test_absolute_value_bars() {
+        let calculator = Calculator::new();
+        let vars = HashMap::from([("x".to_string(), 1.0)]);
+        assert_eq!(calculator.evaluate_with("|x - 3|", &vars), Ok(2.0));
+        assert_eq!(calculator.evaluate("|-2| * |3 - 5|"), Ok(4.0));
+        // 入れ子: 開きの | は項の先頭、閉じの | は式の終わりでだけ現れる
+        assert_eq!(calculator.evaluate("|1 - |2 - 5||"), Ok(2.0));
+        assert_eq!(calculator.evaluate("||-4| - 6|"), Ok(2.0));
+        assert_eq!(calculator.evaluate("|-|-3||"), Ok(3.0));
+        assert_eq!(calculator.evaluate("2^|1 - 3|"), Ok(4.0));
+        assert!(calculator.evaluate("|2 - 5").is_err());
+        assert!(calculator.evaluate("2 - 5|").is_err());
+        assert!(calculator.evaluate("(|2)|").is_err());
+        assert!(calculator.evaluate("||").is_err());
+
+        let compiled = calculator.compile("|x - 3|").unwrap();
+        assert_eq!(compiled.eval(&vars), Ok(2.0));
+        assert_eq!(
+            compiled.canonical_hash(),
+            calculator.canonical_hash("abs(x - 3)").unwrap()
+        );
     }
 
     #[test]
     fn test_security_function_whitelist() {
-        let calculator = CalculatorService;
+        let calculator = CalculatorService::default();
 
         // 許可されていない関数
         let request = CalculateRequest {
             expression: "exec(rm)".to_string(),
+            ..Default::default()
         };
         let result = calculator.calculate(request);
         assert!(result.is_err());
@@ -169,12 +1698,71 @@ mod tests {
         assert!(error_msg.contains("未サポートの関数") || error_msg.contains("不正な文字"));
     }
 
+    #[test]
+    fn test_audit_trace() {
+        let mut calculator = Calculator::new();
+        calculator.define_function("double", "2 * x").unwrap();
+        let compiled = calculator.compile("2 + 3 * sqrt(16) - double(r)").unwrap();
+        let vars = HashMap::from([("r".to_string(), 1.5)]);
+        let (value, steps) = compiled.audit(&vars).unwrap();
+        assert_eq!(value, compiled.eval(&vars).unwrap());
+        let trace: Vec<(&str, Option<&str>, &[f64], f64)> = steps
+            .iter()
+            .map(|step| {
+                (
+                    step.operation,
+                    step.name.as_deref(),
+                    step.operands.as_slice(),
+                    step.result,
+                )
+            })
+            .collect();
+        assert_eq!(
+            trace,
+            vec![
+                ("function", Some("sqrt"), &[16.0][..], 4.0),
+                ("multiply", None, &[3.0, 4.0][..], 12.0),
+                ("add", None, &[2.0, 12.0][..], 14.0),
+                ("variable", Some("r"), &[][..], 1.5),
+                ("multiply", None, &[2.0, 1.5][..], 3.0),
+                ("call", Some("double"), &[1.5][..], 3.0),
+                ("subtract", None, &[14.0, 3.0][..], 11.0),
+            ]
+        );
+        assert!(steps.iter().enumerate().all(|(i, step)| step.step == i + 1));
+        assert!(calculator
+            .compile("1 / (2 - 2)")
+            .unwrap()
+            .audit(&vars)
+            .is_err());
+
+        let service = CalculatorService::default();
+        let result = service
+            .calculate(CalculateRequest {
+                expression: "2 + 3 * 4".to_string(),
+                audit: true,
+                ..Default::default()
+            })
+            .unwrap();
+        assert!(result.starts_with("計算結果: 14\n- 監査: ["), "{}", result);
+        assert!(result.contains(r#""operation":"multiply""#), "{}", result);
+        let result = service
+            .calculate(CalculateRequest {
+                expression: "[1, 2] * 2".to_string(),
+                audit: true,
+                ..Default::default()
+            })
+            .unwrap();
+        assert!(result.contains("audit は実数の式だけ"), "{}", result);
+    }
+
     #[test]
     fn test_security_zero_division() {
-        let calculator = CalculatorService;
+        let calculator = CalculatorService::default();
 
         let request = CalculateRequest {
             expression: "1 / 0".to_string(),
+            ..Default::default()
         };
         let result = calculator.calculate(request);
         assert!(result.is_err());
@@ -183,16 +1771,227 @@ mod tests {
 
     #[test]
     fn test_security_nan_infinity() {
-        let calculator = CalculatorService;
+        let calculator = CalculatorService::default();
 
         // 無限大を生成する可能性のある計算
         let request = CalculateRequest {
             expression: "sqrt(-1)".to_string(),
+            ..Default::default()
         };
         let result = calculator.calculate(request);
-        // NaNの場合はエラーになるはず
-        if result.is_err() {
-            assert!(result.unwrap_err().contains("無効"));
+        // NaN になる前に定義域の外としてエラーになる
+        assert!(result.unwrap_err().contains("定義されません"));
+    }
+
+    #[test]
+    fn test_unary_and_doubled_operators() {
+        let calculator = Calculator::new();
+        let vars = HashMap::new();
+        // 単項の - は値の始まる位置ならどこでも使える
+        let valid = [
+            ("-5", -5.0),
+            ("--5", 5.0),
+            ("+5", 5.0),
+            ("3 * -2", -6.0),
+            ("3 / -2", -1.5),
+            ("2 ^ -1", 0.5),
+            ("2 - -3", 5.0),
+            ("2 + -3", -1.0),
+            ("-(2 + 3)", -5.0),
+            ("(+2)", 2.0),
+            ("max(-1, +2)", 2.0),
+            ("2 * --3", 6.0),
+        ];
+        for (expression, expected) in valid {
+            assert_eq!(
+                calculator.evaluate(expression),
+                Ok(expected),
+                "{}",
+                expression
+            );
+            let compiled = calculator.compile(expression).unwrap();
+            assert_eq!(compiled.eval(&vars), Ok(expected), "{}", expression);
+            let sig_figs = calculator.evaluate_sig_figs(expression).unwrap();
+            assert_eq!(sig_figs.value, expected, "{}", expression);
+        }
+
+        // 演算子の重複と左辺のない演算子は、どの経路でも同じエラーになる
+        let invalid = [
+            ("2 ++ 3", "演算子が連続しています: ++"),
+            ("2 -+ 3", "演算子が連続しています: -+"),
+            ("2 * +3", "演算子が連続しています: *+"),
+            ("2 ** 3", "べき乗は ^ を使ってください"),
+            ("6 // 3", "/ は1つだけ書いてください"),
+            ("2 */ 3", "演算子が連続しています: */"),
+            ("2 - * 3", "演算子が連続しています: -*"),
+            ("* 3", "演算子 * の左辺がありません"),
+            ("(/ 2)", "演算子 / の左辺がありません"),
+            ("max(1, ^2)", "演算子 ^ の左辺がありません"),
+        ];
+        for (expression, message) in invalid {
+            let errors = [
+                calculator.evaluate(expression).unwrap_err(),
+                calculator.compile(expression).unwrap_err(),
+                calculator.evaluate_sig_figs(expression).unwrap_err(),
+                calculator.evaluate_value(expression).unwrap_err(),
+            ];
+            for error in errors {
+                assert!(error.contains(message), "{}: {}", expression, error);
+            }
+        }
+    }
+
+    #[test]
+    fn test_trailing_tokens_are_rejected() {
+        let calculator = Calculator::new();
+        // 読み残したトークンの位置は文字数で数える（× は2バイト）
+        let cases = [
+            ("2 + 3 5", "7 文字目の 5 から後を解釈できません"),
+            ("2 3", "3 文字目の 3 から後"),
+            ("2 × 3 4", "7 文字目の 4 から後"),
+            ("1, 2", "2 文字目の , から後"),
+            ("(1 + 2))", "8 文字目の ) に対応する左括弧がありません"),
+        ];
+        for (expression, message) in cases {
+            let errors = [
+                calculator.evaluate(expression).unwrap_err(),
+                calculator.compile(expression).unwrap_err(),
+                calculator.evaluate_sig_figs(expression).unwrap_err(),
+                calculator.evaluate_value(expression).unwrap_err(),
+            ];
+            for error in errors {
+                assert!(error.contains(message), "{}: {}", expression, error);
+            }
+        }
+        // 暗黙の掛け算とコメントは読み残しではない
+        assert_eq!(calculator.evaluate("2(3) # コメント"), Ok(6.0));
+    }
+
+    #[test]
+    fn test_empty_arguments_and_parentheses() {
+        let calculator = Calculator::new();
+        let cases = [
+            ("sqrt()", "sqrt の引数は 1 個です（0 個指定されています）"),
+            ("max()", "max の引数は 2 個以上です（0 個指定されています）"),
+            (
+                "hypot(1, 2, 3)",
+                "hypot の引数は 2 個です（3 個指定されています）",
+            ),
+            ("max(1, )", "max の 2 番目の引数が空です"),
+            ("max(, 1)", "max の 1 番目の引数が空です"),
+            ("hypot(1,, 2)", "hypot の 2 番目の引数が空です"),
+            ("()", "空の括弧 () は使えません"),
+            ("2 * ()", "空の括弧 () は使えません"),
+        ];
+        for (expression, message) in cases {
+            let errors = [
+                calculator.evaluate(expression).unwrap_err(),
+                calculator.compile(expression).unwrap_err(),
+                calculator.evaluate_sig_figs(expression).unwrap_err(),
+                calculator.evaluate_value(expression).unwrap_err(),
+            ];
+            for error in errors {
+                assert!(error.contains(message), "{}: {}", expression, error);
+            }
+        }
+        assert_eq!(
+            calculator.evaluate_value("det()").unwrap_err(),
+            "det の引数は 1 個です（0 個指定されています）"
+        );
+    }
+
+    #[test]
+    fn test_literal_range() {
+        let mut calculator = Calculator::new();
+        let huge = format!("1{}", "0".repeat(400));
+        let tiny = format!("0.{}1", "0".repeat(400));
+        let cases = [
+            (
+                huge.clone(),
+                "数値リテラルが大きすぎます: 1000000000…000000（401 文字）",
+            ),
+            (
+                format!("2 * {}", tiny),
+                "数値リテラルが小さすぎて 0 になります",
+            ),
+            ("1e999".to_string(), "数値リテラルが大きすぎます: 1e999"),
+            (
+                "2 * 1e-400".to_string(),
+                "数値リテラルが小さすぎて 0 になります: 1e-400",
+            ),
+        ];
+        for (expression, message) in &cases {
+            let errors = [
+                calculator.evaluate(expression).unwrap_err(),
+                calculator.compile(expression).unwrap_err(),
+                calculator.evaluate_sig_figs(expression).unwrap_err(),
+                calculator.evaluate_value(expression).unwrap_err(),
+            ];
+            for error in errors {
+                assert!(error.contains(message), "{}", error);
+            }
+        }
+        // 範囲内の大きな値・小さな値と 0 はそのまま
+        let large = format!("1{}", "0".repeat(300));
+        assert_eq!(calculator.evaluate(&large), Ok(1e300));
+        assert_eq!(calculator.evaluate("0.000"), Ok(0.0));
+        assert_eq!(calculator.evaluate("1.5e3 + 2E-3"), Ok(1500.002));
+        assert_eq!(calculator.evaluate("0e5"), Ok(0.0));
+        assert_eq!(calculator.evaluate("1e308"), Ok(1e308));
+
+        // SI 接頭辞で範囲を超える場合も同じ
+        calculator.set_si_prefixes(true);
+        let error = calculator
+            .evaluate(&format!("1{}T", "0".repeat(300)))
+            .unwrap_err();
+        assert!(error.contains("数値リテラルが大きすぎます"), "{}", error);
+    }
+
+    #[test]
+    fn test_fuzz_eval() {
+        let mut calculator = Calculator::new();
+        calculator.set_si_prefixes(true);
+        calculator.define_function("twice", "x * 2").unwrap();
+        let crafted: [&[u8]; 14] = [
+            b"",
+            b"\xff\xfe(1 +",
+            b"9^9^9^9^9",
+            b"fact(1e9)",
+            b"1e309 - 1e309",
+            b"((((((((((((((((((((1))))))))))))))))))))",
+            b"-(-(-(-(-(-(-(-1)))))))",
+            b"round(1, 1e18)",
+            b"max(,)",
+            b"2x\xc3\x97\xe2\x88\x9a",
+            b"[[1, 2], [3]] * [1",
+            b"4k7 + 0x",
+            b"twice(twice(twice(",
+            b"1 < 2 < 3 = 4",
+        ];
+        for bytes in crafted {
+            let _ = calculator.fuzz_eval(bytes);
+        }
+        // 式に現れる記号を組み合わせた、再現できる疑似乱数の入力
+        let mut alphabet: Vec<&[u8]> =
+            "1 0 .5 e pi x + - * / ^ % ! ( ) [ ] , sqrt sin log max twice round k | = < √"
+                .split(' ')
+                .map(str::as_bytes)
+                .collect();
+        alphabet.extend([b" ".as_slice(), b"\xff"]);
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        for _ in 0..2000 {
+            let mut bytes = Vec::new();
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            for _ in 0..state % 24 {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                bytes.extend_from_slice(alphabet[(state % alphabet.len() as u64) as usize]);
+            }
+            let _ = calculator.fuzz_eval(&bytes);
         }
+        assert_eq!(calculator.fuzz_eval(b"1 + 2").unwrap().real(), Some(3.0));
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,101 @@
+use calc_mcp::calculator::Calculator;
+use calc_mcp::number_format::format_number;
+use calc_mcp::stress;
+use serde_json::json;
+use std::process::ExitCode;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const USAGE: &str = "使い方:
+  calc-mcp            MCPサーバとして起動（stdio）
+  calc-mcp serve      同上
+  calc-mcp eval <式>  式を1回だけ評価して結果を表示
+  calc-mcp repl       対話モード
+  calc-mcp run [--format text|csv|jsonl] [ファイル|-]
+                      1行に1つの式を評価（ファイル省略時は標準入力）
+  calc-mcp stress-test [--count N] [--seed N] [--depth N]
+                      乱数で生成した式で計算エンジンを自己検証
+  calc-mcp --version  ビルド情報を表示";
+
+// 終了コード: 0 = 成功, 1 = 計算エラー, 2 = 使い方の誤り
+const EXIT_EVAL_ERROR: u8 = 1;
+const EXIT_USAGE: u8 = 2;
+
+pub fn usage_error(message: &str) -> ExitCode {
+    eprintln!("{}\n\n{}", message, USAGE);
+    ExitCode::from(EXIT_USAGE)
+}
+
+// MCPのハンドシェイクを経ずに式を評価する（引数はスペースで連結する）
+pub fn eval(args: &[String]) -> ExitCode {
+    if args.is_empty() {
+        return usage_error("評価する式を指定してください");
+    }
+    let expression = args.join(" ");
+
+    match evaluate(&expression) {
+        Ok(result) => {
+            println!("{}", result);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("{}", json!({ "error": e, "expression": expression }));
+            ExitCode::from(EXIT_EVAL_ERROR)
+        }
+    }
+}
+
+pub fn evaluate(expression: &str) -> Result<String, String> {
+    Calculator::new()
+        .evaluate(expression)
+        .map(|result| format_number(result, Default::default()))
+}
+
+// stress-test の式の入れ子の深さの上限（深いと式の大きさの上限を超えやすい）
+const MAX_STRESS_DEPTH: usize = 8;
+
+// calc-mcp stress-test [--count N] [--seed N] [--depth N]
+// 不一致があれば終了コード 1。seed 省略時は現在時刻から決め、再現できるよう結果に出力する
+pub fn stress_test(args: &[String]) -> ExitCode {
+    let mut count = 1000;
+    let mut seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos() as u64);
+    let mut depth = 4;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let (name, value) = match arg.split_once('=') {
+            Some((name, value)) => (name, Some(value)),
+            None => (arg.as_str(), args.next().map(String::as_str)),
+        };
+        let value = value.and_then(|value| value.parse::<u64>().ok());
+        match (name, value) {
+            ("--count", Some(value)) => count = value as usize,
+            ("--seed", Some(value)) => seed = value,
+            ("--depth", Some(value)) if (1..=MAX_STRESS_DEPTH as u64).contains(&value) => {
+                depth = value as usize
+            }
+            ("--count" | "--seed", None) => {
+                return usage_error(&format!("{} には 0 以上の整数を指定してください", name))
+            }
+            ("--depth", _) => {
+                return usage_error(&format!(
+                    "--depth には 1 から {} の整数を指定してください",
+                    MAX_STRESS_DEPTH
+                ))
+            }
+            _ => return usage_error(&format!("余分な引数: {}", arg)),
+        }
+    }
+
+    let report = stress::run(&Calculator::new(), count, seed, depth);
+    match serde_json::to_string_pretty(&report) {
+        Ok(json) => println!("{}", json),
+        Err(e) => eprintln!("{}", e),
+    }
+    if report.is_ok() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::from(EXIT_EVAL_ERROR)
+    }
+}
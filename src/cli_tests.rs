@@ -0,0 +1,16 @@
+#[cfg(test)]
+mod tests {
+    use crate::cli::evaluate;
+
+    #[test]
+    fn test_eval_prints_plain_result() {
+        assert_eq!(evaluate("2 + 3 * 4").unwrap(), "14");
+        assert_eq!(evaluate("sqrt(2.25)").unwrap(), "1.5");
+    }
+
+    #[test]
+    fn test_eval_reports_error() {
+        let error = evaluate("1 / 0").unwrap_err();
+        assert!(error.contains("ゼロ除算"));
+    }
+}
@@ -0,0 +1,302 @@
+use crate::bigint::BigUint;
+use crate::calculator::LIMIT_EXCEEDED;
+use serde::{Deserialize, Serialize};
+
+// n や k に指定できる上限（結果は数万桁になりうる）
+pub const MAX_N: u64 = 10_000;
+// スターリング数は n × k の表を作るため上限を小さくする
+pub const MAX_STIRLING_N: u64 = 1_000;
+// 指数表記で表示する有効数字
+const SCIENTIFIC_DIGITS: usize = 10;
+
+// 1回の計算で多倍長整数に使うメモリの上限（桁数で数える）
+#[derive(Debug, Clone, Copy)]
+pub struct DigitLimits {
+    // 結果と途中の値それぞれの桁数
+    pub max_digits: usize,
+    // 同時に保持する値の桁数の合計（スターリング数の表など）
+    pub max_working_digits: usize,
+}
+
+impl Default for DigitLimits {
+    fn default() -> Self {
+        Self {
+            max_digits: 50_000,
+            max_working_digits: 2_000_000,
+        }
+    }
+}
+
+impl DigitLimits {
+    // 値が大きくなるたびに呼び、上限を超えた時点で計算を打ち切る
+    fn check(&self, value: &BigUint) -> Result<(), String> {
+        if value.digits() > self.max_digits {
+            return Err(format!(
+                "{}: 値の桁数が上限（{} 桁）を超えます",
+                LIMIT_EXCEEDED, self.max_digits
+            ));
+        }
+        Ok(())
+    }
+
+    fn check_working(&self, values: &[BigUint]) -> Result<(), String> {
+        let digits: usize = values.iter().map(BigUint::digits).sum();
+        if digits > self.max_working_digits {
+            return Err(format!(
+                "{}: 計算途中の値の桁数の合計が上限（{} 桁）を超えます",
+                LIMIT_EXCEEDED, self.max_working_digits
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "server", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum CombinatoricsCalculation {
+    // n 個から k 個を選んで並べる P(n, k)
+    Permutations,
+    // 重複を許して並べる n^k
+    PermutationsWithRepetition,
+    // n 個から k 個を選ぶ C(n, k)
+    Combinations,
+    // 重複を許して選ぶ（重複組合せ）H(n, k) = C(n + k - 1, k)
+    CombinationsWithRepetition,
+    // 多項係数 (k1 + k2 + ...)! / (k1! k2! ...)
+    Multinomial,
+    // 完全順列（攪乱順列）の数 !n
+    Derangements,
+    // 第1種スターリング数（符号なし）: n 個を k 個の巡回に分ける方法の数
+    StirlingFirst,
+    // 第2種スターリング数: n 個を k 個の空でないグループに分ける方法の数
+    StirlingSecond,
+}
+
+impl CombinatoricsCalculation {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Permutations => "permutations",
+            Self::PermutationsWithRepetition => "permutations_with_repetition",
+            Self::Combinations => "combinations",
+            Self::CombinationsWithRepetition => "combinations_with_repetition",
+            Self::Multinomial => "multinomial",
+            Self::Derangements => "derangements",
+            Self::StirlingFirst => "stirling_first",
+            Self::StirlingSecond => "stirling_second",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Count {
+    pub calculation: &'static str,
+    pub expression: String,
+    // 正確な値（10進数の文字列）
+    pub value: String,
+    pub digits: usize,
+    // おおよその大きさ（例: 9.332621544e157）
+    pub scientific: String,
+}
+
+impl Count {
+    fn new(calculation: CombinatoricsCalculation, expression: String, value: BigUint) -> Self {
+        Self {
+            calculation: calculation.name(),
+            expression,
+            digits: value.digits(),
+            scientific: value.to_scientific(SCIENTIFIC_DIGITS),
+            value: value.to_string(),
+        }
+    }
+}
+
+pub fn permutations(n: u64, k: u64, limits: DigitLimits) -> Result<Count, String> {
+    check_n(n, "n")?;
+    check_k(n, k)?;
+    let mut value = BigUint::one();
+    for factor in (n - k + 1)..=n {
+        value = value.mul_small(factor as u32);
+        limits.check(&value)?;
+    }
+    Ok(Count::new(
+        CombinatoricsCalculation::Permutations,
+        format!("P({}, {})", n, k),
+        value,
+    ))
+}
+
+pub fn permutations_with_repetition(n: u64, k: u64, limits: DigitLimits) -> Result<Count, String> {
+    check_n(n, "n")?;
+    check_n(k, "k")?;
+    let mut value = BigUint::one();
+    for _ in 0..k {
+        value = value.mul_small(n as u32);
+        limits.check(&value)?;
+    }
+    Ok(Count::new(
+        CombinatoricsCalculation::PermutationsWithRepetition,
+        format!("{}^{}", n, k),
+        value,
+    ))
+}
+
+pub fn combinations(n: u64, k: u64, limits: DigitLimits) -> Result<Count, String> {
+    check_n(n, "n")?;
+    check_k(n, k)?;
+    Ok(Count::new(
+        CombinatoricsCalculation::Combinations,
+        format!("C({}, {})", n, k),
+        binomial(n, k, limits)?,
+    ))
+}
+
+pub fn combinations_with_repetition(n: u64, k: u64, limits: DigitLimits) -> Result<Count, String> {
+    check_n(n, "n")?;
+    check_n(k, "k")?;
+    // 0 種類から1個以上は選べない
+    let value = match (n, k) {
+        (_, 0) => BigUint::one(),
+        (0, _) => BigUint::zero(),
+        _ => {
+            check_n(n + k - 1, "n + k - 1")?;
+            binomial(n + k - 1, k, limits)?
+        }
+    };
+    Ok(Count::new(
+        CombinatoricsCalculation::CombinationsWithRepetition,
+        format!("H({}, {})", n, k),
+        value,
+    ))
+}
+
+pub fn multinomial(groups: &[u64], limits: DigitLimits) -> Result<Count, String> {
+    if groups.is_empty() {
+        return Err("multinomial には groups（各グループの個数）が必要です".to_string());
+    }
+    let total = groups
+        .iter()
+        .try_fold(0u64, |total, &group| total.checked_add(group))
+        .unwrap_or(u64::MAX);
+    check_n(total, "groups の合計")?;
+
+    // C(k1, k1) × C(k1 + k2, k2) × ... を1つずつ掛けて割る（各段階で割り切れる）
+    let mut value = BigUint::one();
+    let mut count = 0;
+    for &group in groups {
+        for j in 1..=group {
+            count += 1;
+            value = value.mul_small(count as u32).div_small(j as u32).0;
+            limits.check(&value)?;
+        }
+    }
+    let groups_text: Vec<String> = groups.iter().map(u64::to_string).collect();
+    Ok(Count::new(
+        CombinatoricsCalculation::Multinomial,
+        format!("{}! / ({}!)", total, groups_text.join("! ")),
+        value,
+    ))
+}
+
+pub fn derangements(n: u64, limits: DigitLimits) -> Result<Count, String> {
+    check_n(n, "n")?;
+    // !n = (n - 1)(!(n - 1) + !(n - 2))、!0 = 1、!1 = 0
+    let (mut previous, mut current) = (BigUint::one(), BigUint::zero());
+    if n == 0 {
+        current = BigUint::one();
+    }
+    for i in 2..=n {
+        let next = current.add(&previous).mul_small((i - 1) as u32);
+        limits.check(&next)?;
+        previous = current;
+        current = next;
+    }
+    Ok(Count::new(
+        CombinatoricsCalculation::Derangements,
+        format!("!{}", n),
+        current,
+    ))
+}
+
+pub fn stirling_first(n: u64, k: u64, limits: DigitLimits) -> Result<Count, String> {
+    // c(n, k) = (n - 1) c(n - 1, k) + c(n - 1, k - 1)
+    let value = stirling(n, k, limits, |i, _| i - 1)?;
+    Ok(Count::new(
+        CombinatoricsCalculation::StirlingFirst,
+        format!("c({}, {})", n, k),
+        value,
+    ))
+}
+
+pub fn stirling_second(n: u64, k: u64, limits: DigitLimits) -> Result<Count, String> {
+    // S(n, k) = k S(n - 1, k) + S(n - 1, k - 1)
+    let value = stirling(n, k, limits, |_, j| j)?;
+    Ok(Count::new(
+        CombinatoricsCalculation::StirlingSecond,
+        format!("S({}, {})", n, k),
+        value,
+    ))
+}
+
+// 表を1行ずつ更新する。factor(i, j) は i 行目の j 列の既存の値に掛ける係数
+fn stirling(
+    n: u64,
+    k: u64,
+    limits: DigitLimits,
+    factor: impl Fn(u64, u64) -> u64,
+) -> Result<BigUint, String> {
+    if n > MAX_STIRLING_N {
+        return Err(format!(
+            "スターリング数の n は {} 以下で指定してください: {}",
+            MAX_STIRLING_N, n
+        ));
+    }
+    if k > n {
+        return Ok(BigUint::zero());
+    }
+    let width = k as usize + 1;
+    let mut row = vec![BigUint::zero(); width];
+    row[0] = BigUint::one();
+    for i in 1..=n {
+        // 右から更新すると row[j - 1] は前の行の値のまま使える
+        for j in (1..width).rev() {
+            row[j] = row[j]
+                .mul_small(factor(i, j as u64) as u32)
+                .add(&row[j - 1]);
+            limits.check(&row[j])?;
+        }
+        row[0] = BigUint::zero();
+        limits.check_working(&row)?;
+    }
+    Ok(row.swap_remove(k as usize))
+}
+
+fn binomial(n: u64, k: u64, limits: DigitLimits) -> Result<BigUint, String> {
+    let k = k.min(n - k);
+    let mut value = BigUint::one();
+    for i in 1..=k {
+        value = value.mul_small((n - k + i) as u32).div_small(i as u32).0;
+        limits.check(&value)?;
+    }
+    Ok(value)
+}
+
+fn check_n(n: u64, name: &str) -> Result<(), String> {
+    if n > MAX_N {
+        return Err(format!(
+            "{} は {} 以下で指定してください: {}",
+            name, MAX_N, n
+        ));
+    }
+    Ok(())
+}
+
+fn check_k(n: u64, k: u64) -> Result<(), String> {
+    if k > n {
+        return Err(format!(
+            "k は n 以下で指定してください: n = {}, k = {}",
+            n, k
+        ));
+    }
+    Ok(())
+}
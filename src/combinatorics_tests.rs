@@ -0,0 +1,94 @@
+#[cfg(test)]
+mod tests {
+    use crate::combinatorics::{
+        combinations, combinations_with_repetition, derangements, multinomial, permutations,
+        permutations_with_repetition, stirling_first, stirling_second, DigitLimits,
+    };
+
+    const LIMITS: DigitLimits = DigitLimits {
+        max_digits: 50_000,
+        max_working_digits: 2_000_000,
+    };
+
+    #[test]
+    fn test_permutations_and_combinations() {
+        assert_eq!(permutations(10, 3, LIMITS).unwrap().value, "720");
+        assert_eq!(permutations(5, 0, LIMITS).unwrap().value, "1");
+        assert_eq!(
+            permutations_with_repetition(2, 100, LIMITS).unwrap().value,
+            "1267650600228229401496703205376"
+        );
+        assert_eq!(combinations(52, 5, LIMITS).unwrap().value, "2598960");
+
+        // 64ビットに収まらない値も正確に求める
+        let result = combinations(100, 50, LIMITS).unwrap();
+        assert_eq!(result.value, "100891344545564193334812497256");
+        assert_eq!(result.digits, 30);
+        assert_eq!(result.scientific, "1.008913445e29");
+        assert_eq!(result.expression, "C(100, 50)");
+
+        assert_eq!(
+            combinations_with_repetition(5, 3, LIMITS).unwrap().value,
+            "35"
+        );
+        assert_eq!(
+            combinations_with_repetition(0, 0, LIMITS).unwrap().value,
+            "1"
+        );
+        assert_eq!(
+            combinations_with_repetition(0, 2, LIMITS).unwrap().value,
+            "0"
+        );
+
+        assert!(permutations(3, 4, LIMITS).is_err());
+        assert!(combinations(10_001, 1, LIMITS).is_err());
+    }
+
+    #[test]
+    fn test_multinomial_and_derangements() {
+        let result = multinomial(&[2, 2, 1], LIMITS).unwrap();
+        assert_eq!(result.value, "30");
+        assert_eq!(result.expression, "5! / (2! 2! 1!)");
+        // MISSISSIPPI の並べ方
+        assert_eq!(multinomial(&[1, 4, 4, 2], LIMITS).unwrap().value, "34650");
+        assert!(multinomial(&[], LIMITS).is_err());
+
+        assert_eq!(derangements(0, LIMITS).unwrap().value, "1");
+        assert_eq!(derangements(1, LIMITS).unwrap().value, "0");
+        assert_eq!(derangements(10, LIMITS).unwrap().value, "1334961");
+    }
+
+    #[test]
+    fn test_stirling_numbers() {
+        assert_eq!(stirling_first(10, 3, LIMITS).unwrap().value, "1172700");
+        assert_eq!(stirling_first(0, 0, LIMITS).unwrap().value, "1");
+        assert_eq!(stirling_second(10, 3, LIMITS).unwrap().value, "9330");
+        assert_eq!(stirling_second(5, 5, LIMITS).unwrap().value, "1");
+        assert_eq!(stirling_second(5, 0, LIMITS).unwrap().value, "0");
+        assert_eq!(stirling_second(3, 5, LIMITS).unwrap().value, "0");
+        assert!(stirling_second(1001, 2, LIMITS).is_err());
+    }
+
+    #[test]
+    fn test_digit_limits() {
+        let limits = DigitLimits {
+            max_digits: 100,
+            max_working_digits: 1000,
+        };
+        assert_eq!(permutations(60, 60, limits).unwrap().digits, 82);
+        let error = permutations(70, 70, limits).unwrap_err();
+        assert!(error.starts_with("LIMIT_EXCEEDED:"), "{}", error);
+        assert!(permutations_with_repetition(9, 9999, limits).is_err());
+        assert!(combinations(10_000, 5000, limits).is_err());
+        assert!(derangements(100, limits).is_err());
+        assert!(multinomial(&[100, 100, 100], limits).is_err());
+
+        // 各値は上限内でも表全体の桁数が上限を超える
+        let limits = DigitLimits {
+            max_digits: 1000,
+            max_working_digits: 100,
+        };
+        let error = stirling_second(100, 50, limits).unwrap_err();
+        assert!(error.contains("合計"), "{}", error);
+    }
+}
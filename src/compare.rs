@@ -0,0 +1,43 @@
+use serde::Serialize;
+
+// 許容誤差を省略した場合の既定値
+pub const DEFAULT_ABS_TOLERANCE: f64 = 1e-9;
+pub const DEFAULT_REL_TOLERANCE: f64 = 1e-9;
+
+#[derive(Debug, Serialize)]
+pub struct Comparison {
+    // 許容誤差の範囲内で一致したか
+    #[serde(rename = "match")]
+    pub matches: bool,
+    pub actual: f64,
+    pub expected: f64,
+    // actual - expected
+    pub difference: f64,
+    // |actual - expected| / |expected|（expected が 0 の場合は None）
+    pub relative_difference: Option<f64>,
+    pub abs_tolerance: f64,
+    pub rel_tolerance: f64,
+}
+
+// |actual - expected| <= max(abs_tolerance, rel_tolerance * max(|actual|, |expected|)) なら一致とみなす
+pub fn compare(
+    actual: f64,
+    expected: f64,
+    abs_tolerance: f64,
+    rel_tolerance: f64,
+) -> Result<Comparison, String> {
+    if !(abs_tolerance >= 0.0 && rel_tolerance >= 0.0) {
+        return Err("許容誤差には 0 以上の値を指定してください".to_string());
+    }
+    let difference = actual - expected;
+    let allowed = abs_tolerance.max(rel_tolerance * actual.abs().max(expected.abs()));
+    Ok(Comparison {
+        matches: difference.abs() <= allowed,
+        actual,
+        expected,
+        difference,
+        relative_difference: (expected != 0.0).then(|| difference.abs() / expected.abs()),
+        abs_tolerance,
+        rel_tolerance,
+    })
+}
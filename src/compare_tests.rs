@@ -0,0 +1,29 @@
+#[cfg(test)]
+mod tests {
+    use crate::compare::{compare, DEFAULT_ABS_TOLERANCE, DEFAULT_REL_TOLERANCE};
+
+    #[test]
+    fn test_compare_with_default_tolerance() {
+        let result = compare(0.1 + 0.2, 0.3, DEFAULT_ABS_TOLERANCE, DEFAULT_REL_TOLERANCE).unwrap();
+        assert!(result.matches);
+        assert!(result.difference != 0.0);
+
+        let result = compare(1.0, 1.001, DEFAULT_ABS_TOLERANCE, DEFAULT_REL_TOLERANCE).unwrap();
+        assert!(!result.matches);
+    }
+
+    #[test]
+    fn test_compare_with_tolerances() {
+        // 相対誤差 1% 以内
+        let result = compare(101.0, 100.0, 0.0, 0.01).unwrap();
+        assert!(result.matches);
+        assert_eq!(result.relative_difference, Some(0.01));
+
+        // expected が 0 の場合は絶対誤差で判定する
+        let result = compare(0.004, 0.0, 0.005, 0.0).unwrap();
+        assert!(result.matches);
+        assert_eq!(result.relative_difference, None);
+
+        assert!(compare(1.0, 1.0, -1.0, 0.0).is_err());
+    }
+}
@@ -0,0 +1,326 @@
+use crate::calculator::{Calculator, ExpressionLimits, SafeProfile};
+#[cfg(feature = "units")]
+use crate::cooking;
+use crate::i18n::Language;
+use crate::rounding::RoundingMode;
+use crate::tax::TaxScope;
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+// 設定ファイルのパスを指定する環境変数
+pub const CONFIG_ENV: &str = "CALC_MCP_CONFIG";
+// 環境変数が未指定の場合に探す設定ファイル
+pub const DEFAULT_CONFIG_FILE: &str = "calc-mcp.toml";
+// [limits] の式の大きさの上限を上書きする環境変数
+pub const MAX_EXPRESSION_LENGTH_ENV: &str = "CALC_MCP_MAX_EXPRESSION_LENGTH";
+pub const MAX_TOKENS_ENV: &str = "CALC_MCP_MAX_TOKENS";
+pub const MAX_FUNCTION_ARGS_ENV: &str = "CALC_MCP_MAX_FUNCTION_ARGS";
+// tools.admin = true のときだけ公開される管理用ツール
+pub const ADMIN_TOOLS: &[&str] = &["set_tool_enabled"];
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub tools: ToolsConfig,
+    // 数式で定義する追加の関数（例: double = "2*x"、引数は x）
+    pub functions: BTreeMap<String, String>,
+    // すべての式から参照できる定数（例: tax_rate = 0.10）
+    pub constants: BTreeMap<String, f64>,
+    pub currency: CurrencyConfig,
+    pub tax: TaxConfig,
+    pub cooking: CookingConfig,
+    pub limits: LimitsConfig,
+    pub syntax: SyntaxConfig,
+}
+
+// calculate などの式の構文の拡張
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SyntaxConfig {
+    // 数値リテラルの SI 接頭辞（4.7k = 4700, 100m = 0.1）を受け付けるか
+    pub si_prefixes: bool,
+    // 暗黙の掛け算（2x）や × などの Unicode の記号を補正せずにエラーにするか
+    pub strict: bool,
+    // 1/2x や log(100) のようにあいまいな式を推測して計算せず、calculate で解釈を確認するか
+    pub clarify: bool,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ToolsConfig {
+    // 公開するツール名のリスト（未指定の場合はすべて公開）
+    pub enabled: Option<Vec<String>>,
+    // 管理用ツールを公開するか（既定では非公開）
+    pub admin: bool,
+    // ツールと引数の説明の言語（ja, en, both。未指定の場合はロケールから決める）
+    pub language: Option<Language>,
+    // true にすると histogram の image を返さず、ASCII の棒グラフだけにする（画像を受け付けないクライアント向け）
+    pub disable_images: bool,
+}
+
+// convert_currency のレートの取得元（url, file, rates の順に優先）
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CurrencyConfig {
+    // 基準通貨。rates の値は「1単位 = 基準通貨でいくらか」
+    pub base: String,
+    // rates の基準日（応答にそのまま出力する）
+    pub as_of: Option<String>,
+    pub rates: BTreeMap<String, f64>,
+    // レートを記述した JSON ファイル
+    pub file: Option<PathBuf>,
+    // レートを取得する URL（JSON）
+    pub url: Option<String>,
+    // URL から取得したレートを使い回す秒数
+    pub cache_seconds: u64,
+    // true にすると URL からの取得を止め、file または rates を使う（キルスイッチ）
+    pub disable_fetch: bool,
+}
+
+impl Default for CurrencyConfig {
+    fn default() -> Self {
+        Self {
+            base: "JPY".to_string(),
+            as_of: None,
+            rates: BTreeMap::new(),
+            file: None,
+            url: None,
+            cache_seconds: 3600,
+            disable_fetch: false,
+        }
+    }
+}
+
+// tax ツールの税率と端数処理の既定値
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TaxConfig {
+    // 名前付きの税率（例: standard = 0.10, reduced = 0.08）
+    pub rates: BTreeMap<String, f64>,
+    // 税率を指定しなかった場合に使う rates の名前
+    pub default_rate: String,
+    pub rounding: RoundingMode,
+    pub per: TaxScope,
+}
+
+impl Default for TaxConfig {
+    fn default() -> Self {
+        Self {
+            rates: BTreeMap::from([
+                ("standard".to_string(), 0.10),
+                ("reduced".to_string(), 0.08),
+            ]),
+            default_rate: "standard".to_string(),
+            rounding: RoundingMode::Floor,
+            per: TaxScope::Total,
+        }
+    }
+}
+
+// convert_ingredient の材料の密度
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CookingConfig {
+    // 材料名と密度（g/mL）。既定の材料（flour, sugar など）の密度を上書き・追加する
+    pub densities: BTreeMap<String, f64>,
+}
+
+// 大きな入力からサーバを守るための上限
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitsConfig {
+    // matrix ツールが受け付ける要素数（行数 × 列数）
+    pub max_matrix_elements: usize,
+    // upload_data で受け付ける1件あたりのバイト数
+    pub max_upload_bytes: usize,
+    // 保持するアップロードの数（超えると古いものから削除する）
+    pub max_uploads: usize,
+    // sheet ツールが受け付けるセルの数（行数 × 列数）
+    pub max_sheet_cells: usize,
+    // calc://history.csv などで公開する計算履歴の件数（超えると古いものから削除する）
+    pub max_history: usize,
+    // calculate_batch で1回に受け付ける式の数
+    pub max_batch_expressions: usize,
+    // calculate_batch で同時に評価する式の数
+    pub batch_concurrency: usize,
+    // calculate_batch の1式あたりの制限時間（ミリ秒。リクエストの timeout_ms の上限）
+    pub batch_timeout_ms: u64,
+    // combinatorics の多倍長整数の1つあたりの桁数と、同時に保持する桁数の合計
+    pub max_result_digits: usize,
+    pub max_working_digits: usize,
+    // calculate などで1つの式として受け付ける長さ（バイト数）・トークン数・関数の引数の数
+    pub max_expression_length: usize,
+    pub max_tokens: usize,
+    pub max_function_args: usize,
+    // 同時に実行するツールの呼び出しの数（0 で制限しない）と、空きを待たせる呼び出しの数・待つ時間（ミリ秒）
+    pub max_concurrent: usize,
+    pub max_queue: usize,
+    pub queue_timeout_ms: u64,
+    // 信頼できない利用者に公開するときの計算の量の上限（[limits.safe_profile] を書くと有効になる）
+    pub safe_profile: Option<SafeProfile>,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        let expression = ExpressionLimits::default();
+        Self {
+            max_matrix_elements: 2500,
+            max_upload_bytes: 4 * 1024 * 1024,
+            max_uploads: 16,
+            max_sheet_cells: 1000,
+            max_history: 1000,
+            max_batch_expressions: 500,
+            batch_concurrency: 4,
+            batch_timeout_ms: 2000,
+            max_result_digits: 50_000,
+            max_working_digits: 2_000_000,
+            max_expression_length: expression.max_length,
+            max_tokens: expression.max_tokens,
+            max_function_args: expression.max_function_args,
+            max_concurrent: 8,
+            max_queue: 64,
+            queue_timeout_ms: 10_000,
+            safe_profile: None,
+        }
+    }
+}
+
+impl LimitsConfig {
+    pub fn expression(&self) -> ExpressionLimits {
+        ExpressionLimits {
+            max_length: self.max_expression_length,
+            max_tokens: self.max_tokens,
+            max_function_args: self.max_function_args,
+        }
+    }
+}
+
+impl ToolsConfig {
+    pub fn language(&self) -> Language {
+        self.language.unwrap_or_else(Language::from_env)
+    }
+
+    pub fn is_enabled(&self, name: &str) -> bool {
+        if ADMIN_TOOLS.contains(&name) {
+            return self.admin;
+        }
+        match &self.enabled {
+            Some(enabled) => enabled.iter().any(|tool| tool == name),
+            None => true,
+        }
+    }
+
+    // 指定したツールの有効/無効を切り替える（未指定の場合は全ツールを起点にする）
+    pub fn set_enabled(&mut self, name: &str, enabled: bool, available: &[String]) {
+        let mut list = self.enabled.clone().unwrap_or_else(|| available.to_vec());
+        list.retain(|tool| tool != name);
+        if enabled {
+            list.push(name.to_string());
+        }
+        self.enabled = Some(list);
+    }
+
+    // 存在しないツール名の指定（タイプミスなど）を検出する
+    pub fn validate(&self, available: &[String]) -> Result<()> {
+        if let Some(enabled) = &self.enabled {
+            for name in enabled {
+                if !available.contains(name) {
+                    bail!(
+                        "未知のツール名が tools.enabled に指定されています: {}（利用可能: {}）",
+                        name,
+                        available.join(", ")
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Config {
+    pub fn parse(content: &str) -> Result<Self> {
+        toml::from_str(content).context("設定ファイルの解析に失敗しました")
+    }
+
+    // 起動時・再読み込み時の検証（ツール名、材料の密度と定数・関数の定義）
+    pub fn validate(&self, available_tools: &[String]) -> Result<()> {
+        self.tools.validate(available_tools)?;
+        #[cfg(feature = "units")]
+        cooking::validate_densities(&self.cooking.densities)
+            .map_err(|e| anyhow!("[cooking.densities] の指定が不正です: {}", e))?;
+        self.calculator()?;
+        Ok(())
+    }
+
+    // [constants] と [functions] の定義を登録した計算機を作る
+    pub fn calculator(&self) -> Result<Calculator> {
+        let mut calculator = Calculator::new();
+        calculator
+            .set_limits(self.limits.expression())
+            .map_err(|e| anyhow!("[limits] の指定が不正です: {}", e))?;
+        calculator.set_safe_profile(self.limits.safe_profile);
+        calculator.set_si_prefixes(self.syntax.si_prefixes);
+        calculator.set_strict(self.syntax.strict);
+        for (name, value) in &self.constants {
+            calculator
+                .define_constant(name, *value)
+                .map_err(|e| anyhow!("[constants] の定義が不正です: {}", e))?;
+        }
+        for (name, formula) in &self.functions {
+            calculator
+                .define_function(name, formula)
+                .map_err(|e| anyhow!("[functions] の定義が不正です: {}", e))?;
+        }
+        calculator
+            .validate_functions()
+            .map_err(|e| anyhow!("[functions] の定義が不正です: {}", e))?;
+        Ok(calculator)
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("設定ファイルを読み込めません: {}", path.display()))?;
+        Self::parse(&content)
+    }
+
+    // 設定ファイルがなければ既定値を使う。環境変数の指定はファイルより優先する
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let mut config = match path {
+            Some(path) => Self::from_file(path)?,
+            None => Self::default(),
+        };
+        config.apply_env(|name| std::env::var(name).ok())?;
+        Ok(config)
+    }
+
+    // var（環境変数の読み出し）で指定された [limits] の値で上書きする
+    pub fn apply_env(&mut self, var: impl Fn(&str) -> Option<String>) -> Result<()> {
+        for (name, limit) in [
+            (
+                MAX_EXPRESSION_LENGTH_ENV,
+                &mut self.limits.max_expression_length,
+            ),
+            (MAX_TOKENS_ENV, &mut self.limits.max_tokens),
+            (MAX_FUNCTION_ARGS_ENV, &mut self.limits.max_function_args),
+        ] {
+            if let Some(value) = var(name) {
+                *limit = value
+                    .trim()
+                    .parse()
+                    .with_context(|| format!("環境変数 {} の値が不正です: {}", name, value))?;
+            }
+        }
+        Ok(())
+    }
+
+    // 環境変数で指定されたファイル、またはカレントディレクトリの既定ファイルを探す
+    pub fn locate() -> Option<PathBuf> {
+        if let Some(path) = std::env::var_os(CONFIG_ENV) {
+            return Some(PathBuf::from(path));
+        }
+        let default_path = PathBuf::from(DEFAULT_CONFIG_FILE);
+        default_path.exists().then_some(default_path)
+    }
+}
@@ -0,0 +1,264 @@
+#[cfg(test)]
+mod tests {
+    use crate::config::{Config, ADMIN_TOOLS, MAX_EXPRESSION_LENGTH_ENV, MAX_TOKENS_ENV};
+    use crate::server::{CalculateRequest, CalculatorService, SetToolEnabledRequest};
+
+    #[test]
+    fn test_default_config_enables_all_tools() {
+        let config = Config::default();
+        assert!(config.tools.is_enabled("calculate"));
+
+        // 管理用ツールを除くすべてのツールが公開される
+        let service = CalculatorService::new(config);
+        assert_eq!(
+            service.enabled_tools().len(),
+            CalculatorService::tool_names().len() - ADMIN_TOOLS.len()
+        );
+    }
+
+    #[test]
+    fn test_admin_tools_hidden_by_default() {
+        let config = Config::default();
+        assert!(!config.tools.is_enabled("set_tool_enabled"));
+
+        let config = Config::parse("tools.admin = true").unwrap();
+        assert!(config.tools.is_enabled("set_tool_enabled"));
+    }
+
+    #[test]
+    fn test_replace_config_reports_tool_list_change() {
+        let service = CalculatorService::default();
+
+        // 既定の設定への差し替えでは一覧は変わらない
+        assert!(!service.replace_config(Config::default()));
+
+        let mut config = Config::default();
+        config.tools.enabled = Some(vec!["calculate".to_string()]);
+        assert!(service.replace_config(config));
+        assert_eq!(service.enabled_tools().len(), 1);
+
+        let mut config = Config::default();
+        config.tools.enabled = Some(vec![]);
+        assert!(service.replace_config(config));
+        assert!(service.enabled_tools().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_set_tool_enabled_at_runtime() {
+        let mut config = Config::default();
+        config.tools.admin = true;
+        let service = CalculatorService::new(config);
+
+        let request = SetToolEnabledRequest {
+            name: "calculate".to_string(),
+            enabled: false,
+        };
+        let result = service.set_tool_enabled(request).await.unwrap();
+        assert!(result.contains("無効化"));
+        assert!(!service.config().tools.is_enabled("calculate"));
+
+        let request = SetToolEnabledRequest {
+            name: "calculate".to_string(),
+            enabled: true,
+        };
+        service.set_tool_enabled(request).await.unwrap();
+        assert!(service.config().tools.is_enabled("calculate"));
+
+        // 管理用ツール自身や未知のツールは切り替えられない
+        let request = SetToolEnabledRequest {
+            name: "set_tool_enabled".to_string(),
+            enabled: false,
+        };
+        assert!(service.set_tool_enabled(request).await.is_err());
+        let request = SetToolEnabledRequest {
+            name: "unknown".to_string(),
+            enabled: true,
+        };
+        assert!(service.set_tool_enabled(request).await.is_err());
+    }
+
+    #[test]
+    fn test_tools_enabled_list() {
+        let config = Config::parse(
+            r#"
+            [tools]
+            enabled = ["calculate"]
+            "#,
+        )
+        .unwrap();
+
+        assert!(config.tools.is_enabled("calculate"));
+        assert!(!config.tools.is_enabled("convert_units"));
+    }
+
+    #[test]
+    fn test_disabled_tool_is_hidden() {
+        let config = Config::parse("tools.enabled = []").unwrap();
+        let service = CalculatorService::new(config);
+
+        assert!(service.enabled_tools().is_empty());
+    }
+
+    #[test]
+    fn test_validate_unknown_tool_name() {
+        let config = Config::parse(r#"tools.enabled = ["calcualte"]"#).unwrap();
+        let result = config.tools.validate(&CalculatorService::tool_names());
+
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("calcualte"));
+    }
+
+    #[test]
+    fn test_unknown_config_key_is_rejected() {
+        let result = Config::parse("[tool]\nenabled = []");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_custom_functions() {
+        let mut config = Config::default();
+        config
+            .functions
+            .insert("double".to_string(), "2 * x".to_string());
+        config
+            .functions
+            .insert("quad".to_string(), "double(double(x))".to_string());
+        let service = CalculatorService::new(config);
+
+        let request = CalculateRequest {
+            expression: "quad(3) + 1".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(service.calculate(request).unwrap(), "計算結果: 13");
+    }
+
+    #[test]
+    fn test_invalid_custom_functions() {
+        let tools = CalculatorService::tool_names();
+
+        // 組み込み関数の再定義
+        let mut config = Config::default();
+        config.functions.insert("sqrt".to_string(), "x".to_string());
+        assert!(config.validate(&tools).is_err());
+
+        // 式の中で未知の関数を呼び出している
+        let mut config = Config::default();
+        config.functions.insert("f".to_string(), "g(x)".to_string());
+        let error = config.validate(&tools).unwrap_err().to_string();
+        assert!(error.contains("未サポートの関数: g"));
+    }
+
+    #[test]
+    fn test_recursive_custom_function() {
+        let mut config = Config::default();
+        config
+            .functions
+            .insert("f".to_string(), "f(x) + 1".to_string());
+        let service = CalculatorService::new(config);
+
+        let request = CalculateRequest {
+            expression: "f(1)".to_string(),
+            ..Default::default()
+        };
+        assert!(service
+            .calculate(request)
+            .unwrap_err()
+            .contains("深すぎます"));
+    }
+
+    #[test]
+    fn test_custom_constants() {
+        let mut config = Config::default();
+        config.constants.insert("tax_rate".to_string(), 0.1);
+        config
+            .functions
+            .insert("with_tax".to_string(), "x * (1 + tax_rate)".to_string());
+        let service = CalculatorService::new(config);
+
+        let request = CalculateRequest {
+            expression: "with_tax(1000) - 1000 * tax_rate".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(service.calculate(request).unwrap(), "計算結果: 1000");
+
+        let listing = service.list_functions().unwrap();
+        assert!(listing.contains("tax_rate"));
+        assert!(listing.contains("with_tax"));
+        assert!(listing.contains("sqrt"));
+        assert!(listing.contains("domains"));
+    }
+
+    #[test]
+    fn test_constant_name_conflicts() {
+        let tools = CalculatorService::tool_names();
+
+        let mut config = Config::default();
+        config.constants.insert("sqrt".to_string(), 1.0);
+        assert!(config.validate(&tools).is_err());
+
+        let mut config = Config::default();
+        config.constants.insert("rate".to_string(), 1.0);
+        config.functions.insert("rate".to_string(), "x".to_string());
+        assert!(config.validate(&tools).is_err());
+    }
+
+    #[test]
+    fn test_expression_limits() {
+        let tools = CalculatorService::tool_names();
+        let mut config = Config::default();
+        config
+            .apply_env(|name| (name == MAX_EXPRESSION_LENGTH_ENV).then(|| "10".to_string()))
+            .unwrap();
+        assert_eq!(config.limits.max_expression_length, 10);
+        let calculator = config.calculator().unwrap();
+        assert_eq!(calculator.evaluate("1 + 2 + 3"), Ok(6.0));
+        assert!(calculator.evaluate("1 + 2 + 3 + 4").is_err());
+
+        config.limits.max_tokens = 3;
+        let calculator = config.calculator().unwrap();
+        assert_eq!(calculator.evaluate("1+2"), Ok(3.0));
+        let error = calculator.evaluate("1+2+3").unwrap_err();
+        assert!(error.contains("トークン"), "{}", error);
+
+        assert!(config
+            .apply_env(|name| (name == MAX_TOKENS_ENV).then(|| "many".to_string()))
+            .is_err());
+        config.limits.max_function_args = 0;
+        assert!(config.validate(&tools).is_err());
+    }
+
+    #[test]
+    fn test_safe_profile() {
+        let config = Config::parse("[limits.safe_profile]\nmax_exponent = 10").unwrap();
+        let profile = config.limits.safe_profile.unwrap();
+        assert_eq!(profile.max_exponent, 10.0);
+        assert_eq!(profile.max_list_length, 1000);
+        assert!(Config::default().limits.safe_profile.is_none());
+
+        let service = CalculatorService::new(config);
+        let request = CalculateRequest {
+            expression: "2^11".to_string(),
+            ..Default::default()
+        };
+        let error = service.calculate(request).unwrap_err();
+        assert!(error.contains("LIMIT_EXCEEDED:"), "{}", error);
+        let request = CalculateRequest {
+            expression: "2^10".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(service.calculate(request).unwrap(), "計算結果: 1024");
+    }
+
+    #[cfg(feature = "units")]
+    #[test]
+    fn test_cooking_densities() {
+        let tools = CalculatorService::tool_names();
+        let config = Config::parse("[cooking.densities]\nmatcha = 0.4\n").unwrap();
+        assert_eq!(config.cooking.densities.get("matcha"), Some(&0.4));
+        assert!(config.validate(&tools).is_ok());
+
+        let config = Config::parse("[cooking.densities]\nflour = -0.5\n").unwrap();
+        let error = config.validate(&tools).unwrap_err().to_string();
+        assert!(error.contains("flour"), "{}", error);
+    }
+}
@@ -0,0 +1,123 @@
+use crate::compare::{DEFAULT_ABS_TOLERANCE, DEFAULT_REL_TOLERANCE};
+use serde::Serialize;
+
+// 制約の比較演算子
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Relation {
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Equal,
+    NotEqual,
+}
+
+// 表記の長いものから探す（<= を < と = に分けない）
+const RELATIONS: &[(&str, Relation)] = &[
+    ("<=", Relation::LessEqual),
+    (">=", Relation::GreaterEqual),
+    ("==", Relation::Equal),
+    ("!=", Relation::NotEqual),
+    ("<>", Relation::NotEqual),
+    ("≤", Relation::LessEqual),
+    ("≥", Relation::GreaterEqual),
+    ("≠", Relation::NotEqual),
+    ("<", Relation::Less),
+    (">", Relation::Greater),
+    ("=", Relation::Equal),
+];
+
+impl Relation {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Self::Less => "<",
+            Self::LessEqual => "<=",
+            Self::Greater => ">",
+            Self::GreaterEqual => ">=",
+            Self::Equal => "=",
+            Self::NotEqual => "!=",
+        }
+    }
+}
+
+// check_constraints の1つの制約の結果
+#[derive(Debug, Clone, Serialize)]
+pub struct ConstraintCheck {
+    pub constraint: String,
+    pub holds: bool,
+    pub lhs: f64,
+    pub relation: &'static str,
+    pub rhs: f64,
+    // lhs - rhs
+    pub difference: f64,
+    // 満たすために lhs を動かす必要がある量の絶対値（満たしていれば 0）
+    pub violation: f64,
+}
+
+// text の先頭の比較演算子とその表記
+pub fn relation_prefix(text: &str) -> Option<(&'static str, Relation)> {
+    RELATIONS
+        .iter()
+        .find(|(symbol, _)| text.starts_with(symbol))
+        .copied()
+}
+
+// "x + y <= 10" を ("x + y", <=, "10") に分ける。比較演算子はちょうど1つ
+pub fn split(constraint: &str) -> Result<(&str, Relation, &str), String> {
+    let mut found: Option<(usize, &str, Relation)> = None;
+    let mut position = 0;
+    while position < constraint.len() {
+        let rest = &constraint[position..];
+        if let Some((symbol, relation)) = relation_prefix(rest) {
+            if found.is_some() {
+                return Err(format!(
+                    "比較演算子は1つにしてください（0 <= x <= 1 は2つの制約に分けます）: {}",
+                    constraint
+                ));
+            }
+            found = Some((position, symbol, relation));
+            position += symbol.len();
+        } else {
+            position += rest.chars().next().map_or(1, char::len_utf8);
+        }
+    }
+    let (position, symbol, relation) = found.ok_or_else(|| {
+        format!(
+            "比較演算子（<, <=, >, >=, =, !=）が含まれていません: {}",
+            constraint
+        )
+    })?;
+    let lhs = constraint[..position].trim();
+    let rhs = constraint[position + symbol.len()..].trim();
+    if lhs.is_empty() || rhs.is_empty() {
+        return Err(format!(
+            "比較演算子の両辺に式を書いてください: {}",
+            constraint
+        ));
+    }
+    Ok((lhs, relation, rhs))
+}
+
+// 等号と <=、>= は compare と同じ許容誤差で判定する（0.1 + 0.2 = 0.3 は成り立つ）。
+// < と > は許容誤差を使わず、等しい場合は成り立たない
+pub fn check(constraint: &str, lhs: f64, relation: Relation, rhs: f64) -> ConstraintCheck {
+    let difference = lhs - rhs;
+    let allowed = DEFAULT_ABS_TOLERANCE.max(DEFAULT_REL_TOLERANCE * lhs.abs().max(rhs.abs()));
+    let (holds, violation) = match relation {
+        Relation::Less => (difference < 0.0, difference.max(0.0)),
+        Relation::LessEqual => (difference <= allowed, difference.max(0.0)),
+        Relation::Greater => (difference > 0.0, (-difference).max(0.0)),
+        Relation::GreaterEqual => (-difference <= allowed, (-difference).max(0.0)),
+        Relation::Equal => (difference.abs() <= allowed, difference.abs()),
+        Relation::NotEqual => (difference.abs() > allowed, 0.0),
+    };
+    ConstraintCheck {
+        constraint: constraint.to_string(),
+        holds,
+        lhs,
+        relation: relation.symbol(),
+        rhs,
+        difference,
+        violation: if holds { 0.0 } else { violation },
+    }
+}
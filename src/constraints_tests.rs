@@ -0,0 +1,37 @@
+#[cfg(test)]
+mod tests {
+    use crate::constraints::{check, split, Relation};
+
+    #[test]
+    fn test_split_constraints() {
+        assert_eq!(
+            split("x + y <= 10").unwrap(),
+            ("x + y", Relation::LessEqual, "10")
+        );
+        assert_eq!(split("x>=-1").unwrap(), ("x", Relation::GreaterEqual, "-1"));
+        assert_eq!(split("a ≠ b").unwrap(), ("a", Relation::NotEqual, "b"));
+        assert_eq!(split("2 * r = d").unwrap(), ("2 * r", Relation::Equal, "d"));
+        assert!(split("x + 1").is_err());
+        assert!(split("0 <= x <= 1").is_err());
+        assert!(split("<= 3").is_err());
+    }
+
+    #[test]
+    fn test_check_constraints() {
+        let result = check("x <= 10", 12.5, Relation::LessEqual, 10.0);
+        assert!(!result.holds);
+        assert_eq!(result.violation, 2.5);
+        assert_eq!(result.difference, 2.5);
+
+        let result = check("x >= 0", 3.0, Relation::GreaterEqual, 0.0);
+        assert!(result.holds);
+        assert_eq!(result.violation, 0.0);
+
+        // 等号と <= は浮動小数点の誤差を許容し、< は許容しない
+        assert!(check("", 0.1 + 0.2, Relation::Equal, 0.3).holds);
+        assert!(check("", 0.1 + 0.2, Relation::LessEqual, 0.3).holds);
+        assert!(!check("", 1.0, Relation::Less, 1.0).holds);
+        assert_eq!(check("", 1.0, Relation::Greater, 3.0).violation, 2.0);
+        assert!(!check("", 2.0, Relation::NotEqual, 2.0).holds);
+    }
+}
@@ -0,0 +1,162 @@
+use crate::units::Unit;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+// 材料の密度（g/mL）の目安。粉類は計量カップにふんわり入れてすり切った場合の値で、
+// 詰め方や銘柄で 1 割程度は変わる
+pub const DEFAULT_DENSITIES: &[(&str, f64)] = &[
+    ("water", 1.0),
+    ("milk", 1.03),
+    ("flour", 0.53),
+    ("sugar", 0.845),
+    ("brown_sugar", 0.93),
+    ("powdered_sugar", 0.56),
+    ("salt", 1.217),
+    ("butter", 0.959),
+    ("oil", 0.92),
+    ("honey", 1.42),
+    ("rice", 0.78),
+    ("cocoa", 0.36),
+    ("oats", 0.38),
+];
+
+// 換算に使った密度の出典
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DensitySource {
+    // リクエストの density
+    Request,
+    // 設定ファイルの [cooking.densities]
+    Config,
+    // DEFAULT_DENSITIES
+    Default,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct IngredientConversion {
+    pub amount: f64,
+    pub from: String,
+    pub value: f64,
+    pub to: String,
+    // 体積と質量の換算をした場合だけ出力する
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ingredient: Option<String>,
+    // 換算に使った密度（g/mL）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub density: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub density_source: Option<DensitySource>,
+}
+
+// 材料名は大文字・小文字と空白・ハイフンの違いを区別しない（"Brown sugar" は brown_sugar）
+fn normalize(name: &str) -> String {
+    name.trim().to_lowercase().replace([' ', '-'], "_")
+}
+
+// 設定の密度を優先し、なければ既定の密度を使う
+pub fn density(ingredient: &str, custom: &BTreeMap<String, f64>) -> Option<(f64, DensitySource)> {
+    let name = normalize(ingredient);
+    if let Some((_, density)) = custom.iter().find(|(key, _)| normalize(key) == name) {
+        return Some((*density, DensitySource::Config));
+    }
+    DEFAULT_DENSITIES
+        .iter()
+        .find(|(key, _)| *key == name)
+        .map(|&(_, density)| (density, DensitySource::Default))
+}
+
+// 既定の密度に設定の密度を重ねた一覧
+pub fn densities(custom: &BTreeMap<String, f64>) -> BTreeMap<String, f64> {
+    let mut densities: BTreeMap<String, f64> = DEFAULT_DENSITIES
+        .iter()
+        .map(|&(name, density)| (name.to_string(), density))
+        .collect();
+    for (name, density) in custom {
+        densities.insert(normalize(name), *density);
+    }
+    densities
+}
+
+// 設定ファイルの [cooking.densities] の検証
+pub fn validate_densities(custom: &BTreeMap<String, f64>) -> Result<(), String> {
+    match custom
+        .iter()
+        .find(|(_, density)| !density.is_finite() || **density <= 0.0)
+    {
+        Some((name, density)) => Err(format!(
+            "{} の密度は正の数（g/mL）にしてください: {}",
+            name, density
+        )),
+        None => Ok(()),
+    }
+}
+
+fn lookup(name: &str) -> Result<Unit, String> {
+    Unit::lookup(name.trim()).ok_or_else(|| format!("未知の単位です: {}", name))
+}
+
+// amount（from の単位）を to の単位に換算する。体積と質量の換算には材料の密度を使い、
+// density（g/mL）の指定、custom（設定）、既定の密度の順に探す
+pub fn convert(
+    amount: f64,
+    from: &str,
+    to: &str,
+    ingredient: Option<&str>,
+    density: Option<f64>,
+    custom: &BTreeMap<String, f64>,
+) -> Result<IngredientConversion, String> {
+    if !amount.is_finite() {
+        return Err(format!("量が無効です: {}", amount));
+    }
+    let (from_unit, to_unit) = (lookup(from)?, lookup(to)?);
+    let mut conversion = IngredientConversion {
+        amount,
+        from: from_unit.name.clone(),
+        value: 0.0,
+        to: to_unit.name.clone(),
+        ingredient: None,
+        density: None,
+        density_source: None,
+    };
+    if from_unit.is_compatible(&to_unit) {
+        conversion.value = from_unit.convert(amount, &to_unit)?;
+        return Ok(conversion);
+    }
+    let (milliliter, gram) = (lookup("mL")?, lookup("g")?);
+    let volume_to_mass = from_unit.is_compatible(&milliliter) && to_unit.is_compatible(&gram);
+    let mass_to_volume = from_unit.is_compatible(&gram) && to_unit.is_compatible(&milliliter);
+    if !volume_to_mass && !mass_to_volume {
+        // 次元の違いを示すエラー
+        from_unit.convert(amount, &to_unit)?;
+    }
+    let (density, source) = match (density, ingredient) {
+        (Some(density), _) if !density.is_finite() || density <= 0.0 => {
+            return Err(format!("密度は正の数（g/mL）にしてください: {}", density))
+        }
+        (Some(density), _) => (density, DensitySource::Request),
+        (None, Some(ingredient)) => self::density(ingredient, custom).ok_or_else(|| {
+            format!(
+                "材料 {} の密度がわかりません（density で g/mL を指定してください。既知の材料: {}）",
+                ingredient,
+                densities(custom).into_keys().collect::<Vec<_>>().join(", ")
+            )
+        })?,
+        (None, None) => {
+            return Err(format!(
+                "{} と {} の換算には ingredient（材料名）か density（g/mL）が必要です",
+                from_unit.name, to_unit.name
+            ))
+        }
+    };
+    conversion.value = if volume_to_mass {
+        let grams = from_unit.convert(amount, &milliliter)? * density;
+        gram.convert(grams, &to_unit)?
+    } else {
+        let milliliters = from_unit.convert(amount, &gram)? / density;
+        milliliter.convert(milliliters, &to_unit)?
+    };
+    conversion.ingredient = ingredient.map(normalize);
+    conversion.density = Some(density);
+    conversion.density_source = Some(source);
+    Ok(conversion)
+}
@@ -0,0 +1,52 @@
+#[cfg(test)]
+mod tests {
+    use crate::cooking::{convert, DensitySource};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_volume_to_mass() {
+        let custom = BTreeMap::new();
+        // 1 カップ（約 236.6 mL）の小麦粉は約 125 g
+        let flour = convert(1.0, "cup", "g", Some("Flour"), None, &custom).unwrap();
+        assert!((flour.value - 125.391_765).abs() < 1e-5);
+        assert_eq!(flour.ingredient.as_deref(), Some("flour"));
+        assert_eq!(flour.density, Some(0.53));
+        assert_eq!(flour.density_source, Some(DensitySource::Default));
+
+        // 100 g のバターは約 104.3 mL（日本のカップで約 0.52 杯）
+        let butter = convert(100.0, "g", "cupJP", Some("butter"), None, &custom).unwrap();
+        assert!((butter.value - 100.0 / 0.959 / 200.0).abs() < 1e-12);
+
+        // 体積どうしは密度を使わない
+        let spoons = convert(1.0, "tbsp", "tsp", None, None, &custom).unwrap();
+        assert!((spoons.value - 3.0).abs() < 1e-12);
+        assert_eq!(spoons.density, None);
+    }
+
+    #[test]
+    fn test_density_sources() {
+        let custom = BTreeMap::from([("flour".to_string(), 0.6), ("matcha".to_string(), 0.4)]);
+        let flour = convert(100.0, "mL", "g", Some("flour"), None, &custom).unwrap();
+        assert!((flour.value - 60.0).abs() < 1e-9);
+        assert_eq!(flour.density_source, Some(DensitySource::Config));
+        let matcha = convert(10.0, "mL", "g", Some("matcha"), None, &custom).unwrap();
+        assert!((matcha.value - 4.0).abs() < 1e-9);
+        let request = convert(10.0, "mL", "g", Some("flour"), Some(0.5), &custom).unwrap();
+        assert_eq!(request.density_source, Some(DensitySource::Request));
+        assert!((request.value - 5.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_conversion_errors() {
+        let custom = BTreeMap::new();
+        assert!(convert(1.0, "cup", "g", None, None, &custom)
+            .unwrap_err()
+            .contains("ingredient"));
+        assert!(convert(1.0, "cup", "g", Some("unobtainium"), None, &custom)
+            .unwrap_err()
+            .contains("flour"));
+        assert!(convert(1.0, "cup", "g", None, Some(0.0), &custom).is_err());
+        assert!(convert(1.0, "cup", "m", Some("flour"), None, &custom).is_err());
+        assert!(convert(1.0, "pinch", "g", Some("salt"), None, &custom).is_err());
+    }
+}
@@ -0,0 +1,184 @@
+use crate::config::CurrencyConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// URL からの取得のタイムアウト
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+// 為替レートの一覧。rates の値は「1単位 = base 通貨でいくらか」
+#[derive(Debug, Clone, Deserialize)]
+pub struct RateTable {
+    pub base: String,
+    #[serde(default)]
+    pub as_of: Option<String>,
+    pub rates: BTreeMap<String, f64>,
+}
+
+impl RateTable {
+    // base 通貨で表した 1 単位の価値
+    fn rate(&self, code: &str) -> Result<f64, String> {
+        if code.eq_ignore_ascii_case(&self.base) {
+            return Ok(1.0);
+        }
+        self.rates
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(code))
+            .map(|(_, rate)| *rate)
+            .filter(|rate| rate.is_finite() && *rate > 0.0)
+            .ok_or_else(|| format!("為替レートがありません: {}", code))
+    }
+}
+
+// 為替レートの取得元。source は応答の出典として出力する
+pub trait RateProvider: Send + Sync {
+    fn source(&self) -> String;
+    fn rates(&self) -> Result<RateTable, String>;
+}
+
+// 設定ファイルの [currency.rates]
+pub struct ConfigRates(RateTable);
+
+impl RateProvider for ConfigRates {
+    fn source(&self) -> String {
+        "config".to_string()
+    }
+
+    fn rates(&self) -> Result<RateTable, String> {
+        Ok(self.0.clone())
+    }
+}
+
+// RateTable と同じ形の JSON ファイル（呼び出しのたびに読み直す）
+pub struct FileRates(PathBuf);
+
+impl RateProvider for FileRates {
+    fn source(&self) -> String {
+        format!("file:{}", self.0.display())
+    }
+
+    fn rates(&self) -> Result<RateTable, String> {
+        let content = std::fs::read_to_string(&self.0).map_err(|e| {
+            format!(
+                "為替レートのファイルを読み込めません: {}: {}",
+                self.0.display(),
+                e
+            )
+        })?;
+        serde_json::from_str(&content).map_err(|e| format!("為替レートのファイルが不正です: {}", e))
+    }
+}
+
+// URL から取得したレートのキャッシュ（URL ごと、サービス全体で共有）
+#[derive(Clone, Default)]
+pub struct RateCache(Arc<Mutex<HashMap<String, (Instant, RateTable)>>>);
+
+// RateTable と同じ形の JSON を返す URL。cache_seconds の間は取得し直さない
+pub struct UrlRates {
+    url: String,
+    ttl: Duration,
+    cache: RateCache,
+}
+
+impl RateProvider for UrlRates {
+    fn source(&self) -> String {
+        format!("url:{}", self.url)
+    }
+
+    fn rates(&self) -> Result<RateTable, String> {
+        if let Some((fetched_at, table)) = self
+            .cache
+            .0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&self.url)
+        {
+            if fetched_at.elapsed() < self.ttl {
+                return Ok(table.clone());
+            }
+        }
+
+        let body = ureq::get(&self.url)
+            .timeout(FETCH_TIMEOUT)
+            .call()
+            .map_err(|e| format!("為替レートを取得できません: {}", e))?
+            .into_string()
+            .map_err(|e| format!("為替レートを取得できません: {}", e))?;
+        let table: RateTable = serde_json::from_str(&body)
+            .map_err(|e| format!("為替レートの形式が不正です: {}", e))?;
+
+        self.cache
+            .0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(self.url.clone(), (Instant::now(), table.clone()));
+        Ok(table)
+    }
+}
+
+// 設定に応じた取得元を選ぶ（url, file, rates の順。disable_fetch のときは url を使わない）
+pub fn provider(
+    config: &CurrencyConfig,
+    cache: &RateCache,
+) -> Result<Box<dyn RateProvider>, String> {
+    if let Some(url) = config.url.as_ref().filter(|_| !config.disable_fetch) {
+        return Ok(Box::new(UrlRates {
+            url: url.clone(),
+            ttl: Duration::from_secs(config.cache_seconds),
+            cache: cache.clone(),
+        }));
+    }
+    if let Some(path) = &config.file {
+        return Ok(Box::new(FileRates(path.clone())));
+    }
+    if !config.rates.is_empty() {
+        return Ok(Box::new(ConfigRates(RateTable {
+            base: config.base.clone(),
+            as_of: config.as_of.clone(),
+            rates: config.rates.clone(),
+        })));
+    }
+    if config.url.is_some() {
+        return Err(
+            "URL からの為替レート取得は無効化されています（currency.disable_fetch）".to_string(),
+        );
+    }
+    Err("為替レートが設定されていません（設定ファイルの [currency]）".to_string())
+}
+
+#[derive(Debug, Serialize)]
+pub struct Conversion {
+    pub amount: f64,
+    pub from: String,
+    pub to: String,
+    pub result: f64,
+    // 1 from = rate to
+    pub rate: f64,
+    // レートの出典（config, file:<パス>, url:<URL>）と基準日
+    pub source: String,
+    pub as_of: Option<String>,
+}
+
+pub fn convert(
+    provider: &dyn RateProvider,
+    amount: f64,
+    from: &str,
+    to: &str,
+) -> Result<Conversion, String> {
+    if !amount.is_finite() {
+        return Err(format!("金額が無効です: {}", amount));
+    }
+    let table = provider.rates()?;
+    let rate = table.rate(from)? / table.rate(to)?;
+    Ok(Conversion {
+        amount,
+        from: from.to_ascii_uppercase(),
+        to: to.to_ascii_uppercase(),
+        result: amount * rate,
+        rate,
+        source: provider.source(),
+        as_of: table.as_of,
+    })
+}
@@ -0,0 +1,50 @@
+#[cfg(test)]
+mod tests {
+    use crate::config::CurrencyConfig;
+    use crate::currency::{convert, provider, RateCache};
+
+    fn config_with_rates() -> CurrencyConfig {
+        let mut config = CurrencyConfig {
+            as_of: Some("2026-10-01".to_string()),
+            ..CurrencyConfig::default()
+        };
+        config.rates.insert("USD".to_string(), 150.0);
+        config.rates.insert("EUR".to_string(), 160.0);
+        config
+    }
+
+    #[test]
+    fn test_convert_with_config_rates() {
+        let provider = provider(&config_with_rates(), &RateCache::default()).unwrap();
+
+        let conversion = convert(provider.as_ref(), 10.0, "usd", "JPY").unwrap();
+        assert_eq!(conversion.result, 1500.0);
+        assert_eq!(conversion.from, "USD");
+        assert_eq!(conversion.source, "config");
+        assert_eq!(conversion.as_of.as_deref(), Some("2026-10-01"));
+
+        // 基準通貨以外同士の換算
+        let conversion = convert(provider.as_ref(), 16.0, "EUR", "USD").unwrap();
+        assert_eq!(conversion.result, 16.0 * 160.0 / 150.0);
+
+        let result = convert(provider.as_ref(), 1.0, "GBP", "JPY");
+        assert!(result.unwrap_err().contains("GBP"));
+    }
+
+    #[test]
+    fn test_disable_fetch_falls_back_to_config_rates() {
+        let mut config = config_with_rates();
+        config.url = Some("https://rates.example.com/latest.json".to_string());
+        let source = provider(&config, &RateCache::default()).unwrap().source();
+        assert!(source.starts_with("url:"));
+
+        config.disable_fetch = true;
+        let source = provider(&config, &RateCache::default()).unwrap().source();
+        assert_eq!(source, "config");
+
+        // 代わりのレートがなければエラー
+        config.rates.clear();
+        let result = provider(&config, &RateCache::default());
+        assert!(result.is_err());
+    }
+}
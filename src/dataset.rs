@@ -0,0 +1,177 @@
+// CSV で渡されたデータから数値の列を取り出す
+#[derive(Debug)]
+pub struct Table {
+    // 1行目に数値ではない値があればヘッダーとみなす
+    pub headers: Option<Vec<String>>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut lines = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(split_line);
+        let first = lines.next().ok_or("CSV にデータがありません")?;
+        let is_header = first
+            .iter()
+            .any(|field| !field.is_empty() && field.parse::<f64>().is_err());
+
+        let (headers, mut rows) = if is_header {
+            (Some(first), Vec::new())
+        } else {
+            (None, vec![first])
+        };
+        rows.extend(lines);
+        Ok(Self { headers, rows })
+    }
+
+    // selector はヘッダー名か、1から数えた列番号。省略できるのは1列だけの場合
+    pub fn column_index(&self, selector: Option<&str>) -> Result<usize, String> {
+        let columns = self
+            .headers
+            .as_ref()
+            .map_or_else(|| self.rows.first().map_or(0, Vec::len), Vec::len);
+        let Some(selector) = selector else {
+            return if columns == 1 {
+                Ok(0)
+            } else {
+                Err(format!(
+                    "列を指定してください（{}）",
+                    self.describe_columns()
+                ))
+            };
+        };
+
+        if let Some(index) = self
+            .headers
+            .as_ref()
+            .and_then(|headers| headers.iter().position(|header| header == selector.trim()))
+        {
+            return Ok(index);
+        }
+        match selector.trim().parse::<usize>() {
+            Ok(number) if (1..=columns).contains(&number) => Ok(number - 1),
+            _ => Err(format!(
+                "列が見つかりません: {}（{}）",
+                selector,
+                self.describe_columns()
+            )),
+        }
+    }
+
+    // 空欄は読み飛ばす
+    pub fn column(&self, selector: Option<&str>) -> Result<Vec<f64>, String> {
+        let index = self.column_index(selector)?;
+        let mut values = Vec::new();
+        for (row, fields) in self.rows.iter().enumerate() {
+            if let Some(value) = self.cell(row, fields, index)? {
+                values.push(value);
+            }
+        }
+        Ok(values)
+    }
+
+    // どちらかが空欄の行は読み飛ばす
+    pub fn column_pair(
+        &self,
+        first: Option<&str>,
+        second: Option<&str>,
+    ) -> Result<(Vec<f64>, Vec<f64>), String> {
+        let (first, second) = (self.column_index(first)?, self.column_index(second)?);
+        let mut pairs = (Vec::new(), Vec::new());
+        for (row, fields) in self.rows.iter().enumerate() {
+            if let (Some(x), Some(y)) = (
+                self.cell(row, fields, first)?,
+                self.cell(row, fields, second)?,
+            ) {
+                pairs.0.push(x);
+                pairs.1.push(y);
+            }
+        }
+        Ok(pairs)
+    }
+
+    fn cell(&self, row: usize, fields: &[String], index: usize) -> Result<Option<f64>, String> {
+        match fields.get(index).map(|field| field.trim()) {
+            None | Some("") => Ok(None),
+            Some(field) => field.parse().map(Some).map_err(|_| {
+                let line = row + 1 + usize::from(self.headers.is_some());
+                format!("{}行目の値が数値ではありません: {}", line, field)
+            }),
+        }
+    }
+
+    fn describe_columns(&self) -> String {
+        match &self.headers {
+            Some(headers) => format!("列: {}", headers.join(", ")),
+            None => format!("列番号: 1〜{}", self.rows.first().map_or(0, Vec::len)),
+        }
+    }
+}
+
+// 1行に「名前,値」を並べた CSV（load_variables など）。1行目の値が数値でなければ見出しとみなす
+pub fn parse_pairs(text: &str) -> Result<Vec<(String, f64)>, String> {
+    let mut pairs = Vec::new();
+    let lines = text
+        .lines()
+        .map(str::trim)
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+    for (position, (line_index, line)) in lines.enumerate() {
+        let fields = split_line(line);
+        let [name, value] = fields.as_slice() else {
+            return Err(format!(
+                "{}行目は「名前,値」の2列にしてください: {}",
+                line_index + 1,
+                line
+            ));
+        };
+        match value.parse::<f64>() {
+            Ok(value) => pairs.push((name.clone(), value)),
+            Err(_) if position == 0 => {}
+            Err(_) => {
+                return Err(format!(
+                    "{}行目の値が数値ではありません: {}",
+                    line_index + 1,
+                    value
+                ))
+            }
+        }
+    }
+    if pairs.is_empty() {
+        return Err("CSV にデータがありません".to_string());
+    }
+    Ok(pairs)
+}
+
+// CSV に書き出すフィールド。カンマ・ダブルクォート・改行を含む場合は囲む
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+// ダブルクォートで囲んだフィールド（カンマや "" を含む）に対応する
+fn split_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field).trim().to_string()),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field.trim().to_string());
+    fields
+}
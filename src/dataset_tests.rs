@@ -0,0 +1,51 @@
+#[cfg(test)]
+mod tests {
+    use crate::dataset::{parse_pairs, Table};
+
+    const CSV: &str = "name,height,weight\n\"Sato, A\",170,65\nSuzuki,,58\nTanaka,165,60\n";
+
+    #[test]
+    fn test_column_by_header_and_number() {
+        let table = Table::parse(CSV).unwrap();
+
+        assert_eq!(table.column(Some("height")).unwrap(), vec![170.0, 165.0]);
+        assert_eq!(table.column(Some("3")).unwrap(), vec![65.0, 58.0, 60.0]);
+        assert!(table.column(Some("age")).is_err());
+        // 数値ではない列
+        assert!(table.column(Some("name")).is_err());
+        // 複数列のときは列の指定が必要
+        assert!(table.column(None).is_err());
+    }
+
+    #[test]
+    fn test_column_pair_skips_blank_cells() {
+        let table = Table::parse(CSV).unwrap();
+        let (x, y) = table.column_pair(Some("height"), Some("weight")).unwrap();
+
+        assert_eq!(x, vec![170.0, 165.0]);
+        assert_eq!(y, vec![65.0, 60.0]);
+    }
+
+    #[test]
+    fn test_headerless_single_column() {
+        let table = Table::parse("1\n2.5\n\n4\n").unwrap();
+
+        assert!(table.headers.is_none());
+        assert_eq!(table.column(None).unwrap(), vec![1.0, 2.5, 4.0]);
+    }
+
+    #[test]
+    fn test_parse_pairs() {
+        let pairs = parse_pairs("name,value\nrate,0.05\n\n# 期間\nyears, 10\n").unwrap();
+        assert_eq!(
+            pairs,
+            vec![("rate".to_string(), 0.05), ("years".to_string(), 10.0)]
+        );
+        // 見出しなし
+        assert_eq!(parse_pairs("x,2").unwrap(), vec![("x".to_string(), 2.0)]);
+
+        assert!(parse_pairs("rate,0.05\nyears,ten").is_err());
+        assert!(parse_pairs("rate,0.05,1").is_err());
+        assert!(parse_pairs("name,value\n").is_err());
+    }
+}
@@ -0,0 +1,246 @@
+use crate::duration;
+use chrono::{DateTime, Datelike, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::Serialize;
+
+// よく使われる略称。夏時間を反映するため、固定オフセットではなく地域のタイムゾーンに対応づける
+// （PST を指定しても夏の日付なら PDT（-07:00）として扱う）
+const ABBREVIATIONS: &[(&str, &str)] = &[
+    ("JST", "Asia/Tokyo"),
+    ("KST", "Asia/Seoul"),
+    ("UTC", "UTC"),
+    ("GMT", "Europe/London"),
+    ("BST", "Europe/London"),
+    ("CET", "Europe/Paris"),
+    ("CEST", "Europe/Paris"),
+    ("EST", "America/New_York"),
+    ("EDT", "America/New_York"),
+    ("ET", "America/New_York"),
+    ("CT", "America/Chicago"),
+    ("MST", "America/Denver"),
+    ("MDT", "America/Denver"),
+    ("PST", "America/Los_Angeles"),
+    ("PDT", "America/Los_Angeles"),
+    ("PT", "America/Los_Angeles"),
+];
+
+const DATETIME_FORMATS: &[&str] = &[
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%d %H:%M",
+    "%Y-%m-%dT%H:%M:%S",
+    "%Y-%m-%dT%H:%M",
+];
+const TIME_FORMATS: &[&str] = &["%H:%M:%S", "%H:%M", "%I:%M%p"];
+
+#[derive(Debug, Serialize)]
+pub struct ZonedTime {
+    pub zone: String,
+    // RFC3339（例: 2024-01-15T09:00:00+09:00）
+    pub time: String,
+    pub utc_offset: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TimezoneConversion {
+    pub source: ZonedTime,
+    pub converted: ZonedTime,
+    pub utc: String,
+    // 基準時刻からその時刻までの時間（過去なら負）
+    pub hours_from_now: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Interval {
+    pub start: ZonedTime,
+    pub end: ZonedTime,
+    pub seconds: i64,
+    pub hours: f64,
+    // h:mm:ss
+    pub hms: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Age {
+    pub birthdate: String,
+    pub reference: String,
+    pub years: i32,
+    pub months: i32,
+    pub days: i64,
+    pub total_days: i64,
+    // reference 以降で最初の記念日（reference 当日を含む）
+    pub next_anniversary: String,
+    pub days_until_next_anniversary: i64,
+    pub age_at_next_anniversary: i32,
+}
+
+// IANA のタイムゾーン名（Asia/Tokyo）または略称（JST, PST など）
+pub fn parse_zone(name: &str) -> Result<Tz, String> {
+    let name = name.trim();
+    let resolved = ABBREVIATIONS
+        .iter()
+        .find(|(abbreviation, _)| abbreviation.eq_ignore_ascii_case(name))
+        .map_or(name, |&(_, zone)| zone);
+    resolved
+        .parse::<Tz>()
+        .map_err(|_| format!("不明なタイムゾーン: {}", name))
+}
+
+// RFC3339、"2024-01-15 09:00"、"09:00" / "9am"（now のその地域での日付）のいずれか
+pub fn parse_time(text: &str, zone: Tz, now: DateTime<Utc>) -> Result<DateTime<Tz>, String> {
+    let text = text.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+        return Ok(time.with_timezone(&zone));
+    }
+
+    let naive = DATETIME_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+        .or_else(|| {
+            let date = now.with_timezone(&zone).date_naive();
+            let time = time_of_day(text);
+            TIME_FORMATS
+                .iter()
+                .find_map(|format| NaiveTime::parse_from_str(&time, format).ok())
+                .map(|time| date.and_time(time))
+        })
+        .ok_or_else(|| format!("日時として解釈できません: {}", text))?;
+
+    match zone.from_local_datetime(&naive) {
+        LocalResult::Single(time) => Ok(time),
+        // 夏時間の終わりで2回ある時刻は早い方とする
+        LocalResult::Ambiguous(earliest, _) => Ok(earliest),
+        LocalResult::None => Err(format!(
+            "夏時間の切り替えで {} には存在しない時刻です: {}",
+            zone.name(),
+            text
+        )),
+    }
+}
+
+pub fn convert(
+    time: &str,
+    from_zone: &str,
+    to_zone: &str,
+    now: DateTime<Utc>,
+) -> Result<TimezoneConversion, String> {
+    let source = parse_time(time, parse_zone(from_zone)?, now)?;
+    let converted = source.with_timezone(&parse_zone(to_zone)?);
+    let seconds = source.signed_duration_since(now).num_seconds();
+    Ok(TimezoneConversion {
+        source: zoned(&source),
+        converted: zoned(&converted),
+        utc: source.with_timezone(&Utc).to_rfc3339(),
+        hours_from_now: seconds as f64 / 3600.0,
+    })
+}
+
+// 異なるタイムゾーンの2つの時刻の間隔。夏時間の切り替えを含む場合も実際の経過時間を返す
+pub fn between(
+    start: &str,
+    start_zone: &str,
+    end: &str,
+    end_zone: &str,
+    now: DateTime<Utc>,
+) -> Result<Interval, String> {
+    let start = parse_time(start, parse_zone(start_zone)?, now)?;
+    let end = parse_time(end, parse_zone(end_zone)?, now)?;
+    let seconds = end.signed_duration_since(start).num_seconds();
+    Ok(Interval {
+        start: zoned(&start),
+        end: zoned(&end),
+        seconds,
+        hours: seconds as f64 / 3600.0,
+        hms: duration::normalize(seconds * 1000).hms,
+    })
+}
+
+// 満年齢を年・月・日で求める。応当日がない場合（1/31 の1か月後、2/29 生まれの平年）は月末とする
+pub fn age(birthdate: NaiveDate, reference: NaiveDate) -> Result<Age, String> {
+    if reference < birthdate {
+        return Err(format!(
+            "基準日 {} が生年月日 {} より前です",
+            reference, birthdate
+        ));
+    }
+
+    let mut total_months = (reference.year() - birthdate.year()) * 12
+        + (reference.month() as i32 - birthdate.month() as i32);
+    if add_months(birthdate, total_months)? > reference {
+        total_months -= 1;
+    }
+    let anchor = add_months(birthdate, total_months)?;
+    let (years, months, days) = (
+        total_months / 12,
+        total_months % 12,
+        (reference - anchor).num_days(),
+    );
+
+    let next_age = if months == 0 && days == 0 {
+        years
+    } else {
+        years + 1
+    };
+    let next_anniversary = add_months(birthdate, next_age * 12)?;
+    Ok(Age {
+        birthdate: birthdate.to_string(),
+        reference: reference.to_string(),
+        years,
+        months,
+        days,
+        total_days: (reference - birthdate).num_days(),
+        next_anniversary: next_anniversary.to_string(),
+        days_until_next_anniversary: (next_anniversary - reference).num_days(),
+        age_at_next_anniversary: next_age,
+    })
+}
+
+// YYYY-MM-DD
+pub fn parse_date(text: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(text.trim(), "%Y-%m-%d")
+        .map_err(|_| format!("日付は YYYY-MM-DD で指定してください: {}", text))
+}
+
+// zone（省略時は UTC）での今日の日付
+pub fn today(zone: Option<&str>) -> Result<NaiveDate, String> {
+    let zone = parse_zone(zone.unwrap_or("UTC"))?;
+    Ok(Utc::now().with_timezone(&zone).date_naive())
+}
+
+fn add_months(date: NaiveDate, months: i32) -> Result<NaiveDate, String> {
+    let index = date.year() * 12 + date.month0() as i32 + months;
+    let (year, month) = (index.div_euclid(12), index.rem_euclid(12) as u32 + 1);
+    (1..=date.day())
+        .rev()
+        .find_map(|day| NaiveDate::from_ymd_opt(year, month, day))
+        .ok_or_else(|| "日付が範囲外です".to_string())
+}
+
+pub fn parse_now(now: Option<&str>) -> Result<DateTime<Utc>, String> {
+    match now {
+        Some(now) => DateTime::parse_from_rfc3339(now.trim())
+            .map(|now| now.with_timezone(&Utc))
+            .map_err(|_| format!("基準時刻は RFC3339 で指定してください: {}", now)),
+        None => Ok(Utc::now()),
+    }
+}
+
+// "9am" は分がないと解析できないため "9:00AM" にそろえる
+fn time_of_day(text: &str) -> String {
+    let upper = text.replace(' ', "").to_uppercase();
+    for suffix in ["AM", "PM"] {
+        if let Some(hour) = upper.strip_suffix(suffix) {
+            if !hour.is_empty() && hour.chars().all(|c| c.is_ascii_digit()) {
+                return format!("{}:00{}", hour, suffix);
+            }
+        }
+    }
+    upper
+}
+
+fn zoned(time: &DateTime<Tz>) -> ZonedTime {
+    ZonedTime {
+        zone: time.timezone().name().to_string(),
+        time: time.to_rfc3339(),
+        utc_offset: time.format("%:z").to_string(),
+    }
+}
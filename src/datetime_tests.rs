@@ -0,0 +1,95 @@
+#[cfg(test)]
+mod tests {
+    use crate::datetime::{age, between, convert, parse_date, parse_now, parse_zone};
+
+    #[test]
+    fn test_parse_zone() {
+        assert_eq!(parse_zone("jst").unwrap().name(), "Asia/Tokyo");
+        assert_eq!(parse_zone("Europe/Berlin").unwrap().name(), "Europe/Berlin");
+        assert!(parse_zone("Mars/Olympus").is_err());
+    }
+
+    #[test]
+    fn test_convert_follows_dst() {
+        let now = parse_now(Some("2024-01-15T00:00:00Z")).unwrap();
+
+        // 冬は PST（-08:00）
+        let winter = convert("2024-01-15 09:00", "JST", "PST", now).unwrap();
+        assert_eq!(winter.converted.time, "2024-01-14T16:00:00-08:00");
+        assert_eq!(winter.utc, "2024-01-15T00:00:00+00:00");
+        assert_eq!(winter.hours_from_now, 0.0);
+
+        // 夏は PDT（-07:00）
+        let summer = convert("2024-07-15 09:00", "JST", "PST", now).unwrap();
+        assert_eq!(summer.converted.utc_offset, "-07:00");
+    }
+
+    #[test]
+    fn test_convert_time_of_day_uses_today() {
+        let now = parse_now(Some("2024-01-15T00:00:00Z")).unwrap();
+        let conversion = convert("9pm", "JST", "UTC", now).unwrap();
+
+        assert_eq!(conversion.source.time, "2024-01-15T21:00:00+09:00");
+        assert_eq!(conversion.hours_from_now, 12.0);
+    }
+
+    #[test]
+    fn test_between_across_dst() {
+        let now = parse_now(None).unwrap();
+        // 2024-03-10 は夏時間の開始日で、02:00〜03:00 が存在しない
+        let interval = between(
+            "2024-03-10 00:00",
+            "America/New_York",
+            "2024-03-10 04:00",
+            "America/New_York",
+            now,
+        )
+        .unwrap();
+        assert_eq!(interval.hours, 3.0);
+        assert_eq!(interval.hms, "3:00:00");
+
+        assert!(between("2024-03-10 02:30", "EST", "2024-03-10 04:00", "EST", now).is_err());
+    }
+
+    #[test]
+    fn test_age() {
+        let birthdate = parse_date("1990-08-20").unwrap();
+        let age = age(birthdate, parse_date("2024-03-05").unwrap()).unwrap();
+
+        assert_eq!((age.years, age.months, age.days), (33, 6, 14));
+        assert_eq!(age.next_anniversary, "2024-08-20");
+        assert_eq!(age.days_until_next_anniversary, 168);
+        assert_eq!(age.age_at_next_anniversary, 34);
+    }
+
+    #[test]
+    fn test_age_on_birthday_and_leap_day() {
+        let birthday = age(
+            parse_date("2000-05-01").unwrap(),
+            parse_date("2024-05-01").unwrap(),
+        )
+        .unwrap();
+        assert_eq!((birthday.years, birthday.months, birthday.days), (24, 0, 0));
+        assert_eq!(birthday.days_until_next_anniversary, 0);
+
+        // 2/29 生まれは平年では 2/28 を記念日とする
+        let leap = age(
+            parse_date("2000-02-29").unwrap(),
+            parse_date("2023-02-28").unwrap(),
+        )
+        .unwrap();
+        assert_eq!((leap.years, leap.months, leap.days), (23, 0, 0));
+        let leap = age(
+            parse_date("2000-02-29").unwrap(),
+            parse_date("2023-03-01").unwrap(),
+        )
+        .unwrap();
+        assert_eq!(leap.next_anniversary, "2024-02-29");
+
+        assert!(age(
+            parse_date("2024-01-01").unwrap(),
+            parse_date("2023-01-01").unwrap()
+        )
+        .is_err());
+    }
+}
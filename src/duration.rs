@@ -0,0 +1,259 @@
+use crate::calculator::{division_by_zero, domain_error};
+use serde::Serialize;
+
+// 時間はミリ秒の整数で扱い、浮動小数点の誤差で 1:29:59.999 のようにならないようにする
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Value {
+    Duration(i64),
+    Number(f64),
+}
+
+#[derive(Debug, Serialize)]
+pub struct Normalized {
+    // h:mm:ss（24時間を超えても時で表す）
+    pub hms: String,
+    pub total_hours: f64,
+    pub total_minutes: f64,
+    pub total_seconds: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DurationResult {
+    pub expression: String,
+    // 結果が時間の場合
+    pub duration: Option<Normalized>,
+    // 時間 ÷ 時間の場合の比（例: 6時間に45分の枠がいくつ入るか）
+    pub ratio: Option<f64>,
+    // ratio の整数部分と、その残りの時間
+    pub whole: Option<i64>,
+    pub remainder: Option<Normalized>,
+}
+
+const UNITS: &[(&str, i64)] = &[
+    ("時間", 3_600_000),
+    ("hours", 3_600_000),
+    ("hour", 3_600_000),
+    ("hr", 3_600_000),
+    ("h", 3_600_000),
+    ("minutes", 60_000),
+    ("minute", 60_000),
+    ("min", 60_000),
+    ("分", 60_000),
+    ("m", 60_000),
+    ("seconds", 1000),
+    ("second", 1000),
+    ("sec", 1000),
+    ("秒", 1000),
+    ("s", 1000),
+    ("days", 86_400_000),
+    ("day", 86_400_000),
+    ("日", 86_400_000),
+    ("d", 86_400_000),
+];
+
+// "2:45:30 + 1:30:00" や "6h / 45m" を計算する。
+// 時間どうしの加減算、時間と数値の乗除算、時間 ÷ 時間（比）に対応する
+pub fn calculate(expression: &str) -> Result<DurationResult, String> {
+    let tokens = tokenize(expression)?;
+    let mut position = 0;
+    let value = parse_sum(&tokens, &mut position)?;
+    if position != tokens.len() {
+        return Err(format!("式の解析に失敗しました: {}", expression));
+    }
+
+    let mut result = DurationResult {
+        expression: expression.to_string(),
+        duration: None,
+        ratio: None,
+        whole: None,
+        remainder: None,
+    };
+    match value {
+        Value::Duration(millis) => result.duration = Some(normalize(millis)),
+        Value::Number(ratio) => result.ratio = Some(ratio),
+    }
+    // 最上位が時間 ÷ 時間なら、整数個の枠と余りも返す
+    if let [Token::Operand(Value::Duration(a)), Token::Operator('/'), Token::Operand(Value::Duration(b))] =
+        tokens.as_slice()
+    {
+        result.whole = Some(a.div_euclid(*b));
+        result.remainder = Some(normalize(a.rem_euclid(*b)));
+    }
+    Ok(result)
+}
+
+pub fn normalize(millis: i64) -> Normalized {
+    let sign = if millis < 0 { "-" } else { "" };
+    let abs = millis.unsigned_abs();
+    let (hours, minutes, seconds, fraction) = (
+        abs / 3_600_000,
+        abs / 60_000 % 60,
+        abs / 1000 % 60,
+        abs % 1000,
+    );
+    let hms = if fraction == 0 {
+        format!("{}{}:{:02}:{:02}", sign, hours, minutes, seconds)
+    } else {
+        let fraction = format!("{:03}", fraction);
+        format!(
+            "{}{}:{:02}:{:02}.{}",
+            sign,
+            hours,
+            minutes,
+            seconds,
+            fraction.trim_end_matches('0')
+        )
+    };
+    Normalized {
+        hms,
+        total_hours: millis as f64 / 3_600_000.0,
+        total_minutes: millis as f64 / 60_000.0,
+        total_seconds: millis as f64 / 1000.0,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Token {
+    Operand(Value),
+    Operator(char),
+}
+
+fn tokenize(expression: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut operand = String::new();
+    for c in expression.chars() {
+        let operator = match c {
+            '×' => Some('*'),
+            '÷' => Some('/'),
+            '+' | '-' | '*' | '/' => Some(c),
+            _ => None,
+        };
+        match operator {
+            Some(operator) => {
+                if !operand.trim().is_empty() {
+                    tokens.push(Token::Operand(parse_operand(operand.trim())?));
+                }
+                operand.clear();
+                tokens.push(Token::Operator(operator));
+            }
+            None => operand.push(c),
+        }
+    }
+    if !operand.trim().is_empty() {
+        tokens.push(Token::Operand(parse_operand(operand.trim())?));
+    }
+    if tokens.is_empty() {
+        return Err("式が空です".to_string());
+    }
+    Ok(tokens)
+}
+
+// "2:45:30"（時:分:秒）, "1:30"（時:分）, "1h30m", "45分", "2.5" など
+fn parse_operand(text: &str) -> Result<Value, String> {
+    let invalid = || format!("時間として解釈できません: {}", text);
+
+    if text.contains(':') {
+        let parts: Vec<&str> = text.split(':').collect();
+        if parts.len() > 3 {
+            return Err(invalid());
+        }
+        let mut millis = 0.0;
+        for (part, millis_per_unit) in parts.iter().zip([3_600_000.0, 60_000.0, 1000.0]) {
+            let value: f64 = part.trim().parse().map_err(|_| invalid())?;
+            if value < 0.0 {
+                return Err(invalid());
+            }
+            millis += value * millis_per_unit;
+        }
+        return Ok(Value::Duration(millis.round() as i64));
+    }
+
+    if let Ok(number) = text.parse::<f64>() {
+        return Ok(Value::Number(number));
+    }
+
+    // 数値と単位の組を繰り返し読む
+    let mut millis = 0.0;
+    let mut rest = text.trim();
+    while !rest.is_empty() {
+        let number_end = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .ok_or_else(invalid)?;
+        let number: f64 = rest[..number_end].parse().map_err(|_| invalid())?;
+        rest = rest[number_end..].trim_start();
+        let &(unit, millis_per_unit) = UNITS
+            .iter()
+            .find(|(unit, _)| rest.starts_with(unit))
+            .ok_or_else(invalid)?;
+        millis += number * millis_per_unit as f64;
+        rest = rest[unit.len()..].trim_start();
+    }
+    Ok(Value::Duration(millis.round() as i64))
+}
+
+fn parse_sum(tokens: &[Token], position: &mut usize) -> Result<Value, String> {
+    let mut value = parse_product(tokens, position)?;
+    while let Some(&Token::Operator(operator @ ('+' | '-'))) = tokens.get(*position) {
+        *position += 1;
+        let right = parse_product(tokens, position)?;
+        value = apply(value, operator, right)?;
+    }
+    Ok(value)
+}
+
+fn parse_product(tokens: &[Token], position: &mut usize) -> Result<Value, String> {
+    let mut value = parse_unary(tokens, position)?;
+    while let Some(&Token::Operator(operator @ ('*' | '/'))) = tokens.get(*position) {
+        *position += 1;
+        let right = parse_unary(tokens, position)?;
+        value = apply(value, operator, right)?;
+    }
+    Ok(value)
+}
+
+fn parse_unary(tokens: &[Token], position: &mut usize) -> Result<Value, String> {
+    match tokens.get(*position) {
+        Some(Token::Operator('-')) => {
+            *position += 1;
+            apply(Value::Number(-1.0), '*', parse_unary(tokens, position)?)
+        }
+        Some(&Token::Operand(value)) => {
+            *position += 1;
+            Ok(value)
+        }
+        _ => Err("式の解析に失敗しました".to_string()),
+    }
+}
+
+fn apply(left: Value, operator: char, right: Value) -> Result<Value, String> {
+    use Value::{Duration, Number};
+
+    let scale = |millis: i64, factor: f64| {
+        let scaled = (millis as f64 * factor).round();
+        if scaled.is_finite() && scaled.abs() < i64::MAX as f64 {
+            Ok(Duration(scaled as i64))
+        } else {
+            Err(domain_error("計算結果が無効です（NaN または 無限大）"))
+        }
+    };
+    match (left, operator, right) {
+        (Duration(a), '+', Duration(b)) => a.checked_add(b).map(Duration),
+        (Duration(a), '-', Duration(b)) => a.checked_sub(b).map(Duration),
+        (Number(a), '+', Number(b)) => Some(Number(a + b)),
+        (Number(a), '-', Number(b)) => Some(Number(a - b)),
+        (Duration(_), '/', Duration(0)) | (Duration(_), '/', Number(0.0)) => {
+            return Err(division_by_zero())
+        }
+        (Duration(a), '*', Number(b)) | (Number(b), '*', Duration(a)) => return scale(a, b),
+        (Duration(a), '/', Number(b)) => return scale(a, 1.0 / b),
+        (Duration(a), '/', Duration(b)) => Some(Number(a as f64 / b as f64)),
+        (Number(a), '*', Number(b)) => Some(Number(a * b)),
+        (Number(_), '/', Number(0.0)) => return Err(division_by_zero()),
+        (Number(a), '/', Number(b)) => Some(Number(a / b)),
+        (Duration(_), '+' | '-', Number(_)) | (Number(_), '+' | '-', Duration(_)) => {
+            return Err("時間と単位のない数値は足し引きできません（例: 1:30 + 30m）".to_string())
+        }
+        _ => return Err("時間どうしの掛け算や、数値 ÷ 時間はできません".to_string()),
+    }
+    .ok_or_else(|| "計算結果が大きすぎます".to_string())
+}
@@ -0,0 +1,53 @@
+#[cfg(test)]
+mod tests {
+    use crate::duration::calculate;
+
+    fn hms(expression: &str) -> String {
+        calculate(expression).unwrap().duration.unwrap().hms
+    }
+
+    #[test]
+    fn test_add_and_subtract() {
+        assert_eq!(hms("2:45:30 + 1:30:00"), "4:15:30");
+        assert_eq!(hms("8:00 - 0:45 - 7:30"), "-0:15:00");
+        assert_eq!(hms("20:00:00 + 10:00:00"), "30:00:00");
+        assert_eq!(hms("1h30m + 45分"), "2:15:00");
+    }
+
+    #[test]
+    fn test_totals_in_units() {
+        let duration = calculate("1:30:00").unwrap().duration.unwrap();
+
+        assert_eq!(duration.total_hours, 1.5);
+        assert_eq!(duration.total_minutes, 90.0);
+        assert_eq!(duration.total_seconds, 5400.0);
+    }
+
+    #[test]
+    fn test_scale_by_number() {
+        assert_eq!(hms("7:30 * 5"), "37:30:00");
+        assert_eq!(hms("1:00:00 / 3"), "0:20:00");
+        assert_eq!(hms("0:00:01 / 8"), "0:00:00.125");
+    }
+
+    #[test]
+    fn test_slots() {
+        let result = calculate("6h / 45m").unwrap();
+
+        assert_eq!(result.ratio, Some(8.0));
+        assert_eq!(result.whole, Some(8));
+        assert_eq!(result.remainder.unwrap().hms, "0:00:00");
+
+        let result = calculate("6:10 / 45min").unwrap();
+        assert_eq!(result.whole, Some(8));
+        assert_eq!(result.remainder.unwrap().hms, "0:10:00");
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(calculate("1:30 + 5").is_err());
+        assert!(calculate("1:30 * 1:30").is_err());
+        assert!(calculate("6h / 0").is_err());
+        assert!(calculate("abc").is_err());
+    }
+}
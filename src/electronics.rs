@@ -0,0 +1,316 @@
+use crate::calculator::SI_PREFIXES;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "server", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ElectronicsCalculation {
+    // オームの法則 V = IR と電力 P = VI
+    OhmsLaw,
+    // 直列の合成値
+    Series,
+    // 並列の合成値
+    Parallel,
+    // 分圧回路の出力電圧
+    VoltageDivider,
+}
+
+impl ElectronicsCalculation {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::OhmsLaw => "ohms_law",
+            Self::Series => "series",
+            Self::Parallel => "parallel",
+            Self::VoltageDivider => "voltage_divider",
+        }
+    }
+}
+
+// 合成する素子。コンデンサは抵抗・コイルと直列・並列の式が逆になる
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "server", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum Component {
+    #[default]
+    Resistor,
+    Capacitor,
+    Inductor,
+}
+
+impl Component {
+    fn symbol(self) -> &'static str {
+        match self {
+            Self::Resistor => "Ω",
+            Self::Capacitor => "F",
+            Self::Inductor => "H",
+        }
+    }
+
+    // 値に付けてよい単位の記号
+    pub fn symbols(self) -> &'static [&'static str] {
+        match self {
+            Self::Resistor => OHM,
+            Self::Capacitor => &["F"],
+            Self::Inductor => &["H"],
+        }
+    }
+}
+
+// 数値、または SI 接頭辞つきの文字列（"4.7k", "100nF", "4k7"）で指定する値
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[cfg_attr(feature = "server", derive(schemars::JsonSchema))]
+#[serde(untagged)]
+pub enum Magnitude {
+    Number(f64),
+    Text(String),
+}
+
+// 値を読むときに付けてよい単位の記号
+pub const VOLT: &[&str] = &["V"];
+pub const AMPERE: &[&str] = &["A"];
+pub const OHM: &[&str] = &["Ω", "ohms", "ohm"];
+pub const WATT: &[&str] = &["W"];
+
+impl Magnitude {
+    // symbols は付けてよい単位（"4.7kΩ" の Ω）。抵抗値の "4R7" の R は小数点として読む
+    pub fn value(&self, symbols: &[&str]) -> Result<f64, String> {
+        let value = match self {
+            Self::Number(value) => *value,
+            Self::Text(text) => parse_magnitude(text, symbols)?,
+        };
+        if !value.is_finite() {
+            return Err(format!("値が無効です: {}", value));
+        }
+        Ok(value)
+    }
+}
+
+fn prefix_exponent(c: char) -> Option<i32> {
+    if c == 'R' {
+        return Some(0);
+    }
+    SI_PREFIXES
+        .iter()
+        .find(|(prefix, _)| *prefix == c)
+        .map(|&(_, exponent)| exponent)
+}
+
+pub fn parse_magnitude(text: &str, symbols: &[&str]) -> Result<f64, String> {
+    let invalid = || format!("値を読めません: {}（例: 4.7k, 100n, 4k7）", text);
+    let mut rest: String = text.split_whitespace().collect();
+    if let Some(symbol) = symbols.iter().find(|symbol| rest.ends_with(**symbol)) {
+        rest.truncate(rest.len() - symbol.len());
+    }
+    if let Ok(value) = rest.parse::<f64>() {
+        return Ok(value);
+    }
+    // 4.7k は 4.7 × 10^3、4k7 は 4.7k（接頭辞を小数点の位置に置く表記）
+    let (position, prefix) = rest
+        .char_indices()
+        .find(|(_, c)| prefix_exponent(*c).is_some())
+        .ok_or_else(invalid)?;
+    let (whole, fraction) = (&rest[..position], &rest[position + prefix.len_utf8()..]);
+    if whole.is_empty() || !fraction.chars().all(|c| c.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    if !fraction.is_empty() && whole.contains('.') {
+        return Err(invalid());
+    }
+    let number: f64 = if fraction.is_empty() {
+        whole.parse()
+    } else {
+        format!("{}.{}", whole, fraction).parse()
+    }
+    .map_err(|_| invalid())?;
+    let exponent = prefix_exponent(prefix).ok_or_else(invalid)?;
+    Ok(number * 10f64.powi(exponent))
+}
+
+// 値を SI 接頭辞つきで表す（4700 Ω は "4.7 kΩ"、0.0000001 F は "100 nF"）
+pub fn format_si(value: f64, symbol: &str) -> String {
+    if value == 0.0 || !value.is_finite() {
+        return format!("{} {}", value, symbol);
+    }
+    let exponent = ((value.abs().log10() / 3.0).floor() as i32 * 3).clamp(-15, 12);
+    let prefix = match exponent {
+        0 => String::new(),
+        -6 => "µ".to_string(),
+        _ => SI_PREFIXES
+            .iter()
+            .find(|(_, e)| *e == exponent)
+            .map_or(String::new(), |(prefix, _)| prefix.to_string()),
+    };
+    let scaled = format!("{:.4}", value / 10f64.powi(exponent));
+    let scaled = scaled.trim_end_matches('0').trim_end_matches('.');
+    format!("{} {}{}", scaled, prefix, symbol)
+}
+
+#[derive(Debug, Serialize)]
+pub struct OhmsLaw {
+    pub voltage: f64,
+    pub current: f64,
+    pub resistance: f64,
+    pub power: f64,
+    // 指定された値から求めた項目
+    pub solved: Vec<&'static str>,
+    pub formatted: BTreeMap<&'static str, String>,
+}
+
+// 電圧・電流・抵抗・電力のうち2つから残りの2つを求める
+pub fn ohms_law(
+    voltage: Option<f64>,
+    current: Option<f64>,
+    resistance: Option<f64>,
+    power: Option<f64>,
+) -> Result<OhmsLaw, String> {
+    let given = [voltage, current, resistance, power]
+        .iter()
+        .filter(|value| value.is_some())
+        .count();
+    if given != 2 {
+        return Err(format!(
+            "voltage, current, resistance, power のうち2つを指定してください（{} 個指定されています）",
+            given
+        ));
+    }
+    if resistance.is_some_and(|r| r <= 0.0) {
+        return Err("resistance は正の数にしてください".to_string());
+    }
+    let nonzero = |value: f64, name: &str| {
+        if value == 0.0 {
+            Err(format!("{} が 0 のため、残りの値を求められません", name))
+        } else {
+            Ok(value)
+        }
+    };
+    let (v, i, r, solved) = match (voltage, current, resistance, power) {
+        (Some(v), Some(i), None, None) => {
+            (v, i, v / nonzero(i, "current")?, ["resistance", "power"])
+        }
+        (Some(v), None, Some(r), None) => (v, v / r, r, ["current", "power"]),
+        (None, Some(i), Some(r), None) => (i * r, i, r, ["voltage", "power"]),
+        (Some(v), None, None, Some(p)) => {
+            let i = p / nonzero(v, "voltage")?;
+            (v, i, v / nonzero(i, "power")?, ["current", "resistance"])
+        }
+        (None, Some(i), None, Some(p)) => {
+            let v = p / nonzero(i, "current")?;
+            (v, i, v / i, ["voltage", "resistance"])
+        }
+        (None, None, Some(r), Some(p)) => {
+            if p < 0.0 {
+                return Err("抵抗で消費する power は 0 以上にしてください".to_string());
+            }
+            let i = (p / r).sqrt();
+            (i * r, i, r, ["voltage", "current"])
+        }
+        _ => unreachable!("指定された値は2つ"),
+    };
+    if r <= 0.0 || !r.is_finite() {
+        return Err(format!(
+            "抵抗が正の値になりません（{} Ω）。電圧と電流の符号を確認してください",
+            r
+        ));
+    }
+    let p = v * i;
+    Ok(OhmsLaw {
+        voltage: v,
+        current: i,
+        resistance: r,
+        power: p,
+        solved: solved.to_vec(),
+        formatted: BTreeMap::from([
+            ("voltage", format_si(v, "V")),
+            ("current", format_si(i, "A")),
+            ("resistance", format_si(r, "Ω")),
+            ("power", format_si(p, "W")),
+        ]),
+    })
+}
+
+#[derive(Debug, Serialize)]
+pub struct Combination {
+    pub component: Component,
+    pub connection: &'static str,
+    pub values: Vec<f64>,
+    pub total: f64,
+    pub formatted: String,
+}
+
+// 直列（parallel = false）または並列の合成値。抵抗とコイルは直列で和、並列で逆数の和の逆数、
+// コンデンサはその逆
+pub fn combine(
+    component: Component,
+    values: &[f64],
+    parallel: bool,
+) -> Result<Combination, String> {
+    if values.is_empty() {
+        return Err("values に1つ以上の値を指定してください".to_string());
+    }
+    if let Some(value) = values.iter().find(|value| **value <= 0.0) {
+        return Err(format!("素子の値は正の数にしてください: {}", value));
+    }
+    let reciprocal = parallel != (component == Component::Capacitor);
+    let total = if reciprocal {
+        1.0 / values.iter().map(|value| 1.0 / value).sum::<f64>()
+    } else {
+        values.iter().sum()
+    };
+    Ok(Combination {
+        component,
+        connection: if parallel { "parallel" } else { "series" },
+        values: values.to_vec(),
+        total,
+        formatted: format_si(total, component.symbol()),
+    })
+}
+
+#[derive(Debug, Serialize)]
+pub struct VoltageDivider {
+    pub input_voltage: f64,
+    pub r1: f64,
+    pub r2: f64,
+    // 負荷を指定した場合の、r2 と負荷の並列の合成抵抗
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub load: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub effective_r2: Option<f64>,
+    pub output_voltage: f64,
+    // 出力電圧 ÷ 入力電圧
+    pub ratio: f64,
+    // r1 を流れる電流
+    pub current: f64,
+    pub formatted: BTreeMap<&'static str, String>,
+}
+
+// 入力電圧を r1（入力側）と r2（接地側）で分けた r2 の両端の電圧
+pub fn voltage_divider(
+    input_voltage: f64,
+    r1: f64,
+    r2: f64,
+    load: Option<f64>,
+) -> Result<VoltageDivider, String> {
+    if r1 < 0.0 || r2 <= 0.0 || load.is_some_and(|load| load <= 0.0) || r1 + r2 <= 0.0 {
+        return Err("r1 は 0 以上、r2 と load は正の数にしてください".to_string());
+    }
+    let effective_r2 = load.map(|load| r2 * load / (r2 + load));
+    let bottom = effective_r2.unwrap_or(r2);
+    let ratio = bottom / (r1 + bottom);
+    let output_voltage = input_voltage * ratio;
+    let current = input_voltage / (r1 + bottom);
+    Ok(VoltageDivider {
+        input_voltage,
+        r1,
+        r2,
+        load,
+        effective_r2,
+        output_voltage,
+        ratio,
+        current,
+        formatted: BTreeMap::from([
+            ("output_voltage", format_si(output_voltage, "V")),
+            ("current", format_si(current, "A")),
+        ]),
+    })
+}
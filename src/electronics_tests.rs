@@ -0,0 +1,80 @@
+#[cfg(test)]
+mod tests {
+    use crate::electronics::{
+        combine, format_si, ohms_law, parse_magnitude, voltage_divider, Component, OHM,
+    };
+
+    #[test]
+    fn test_parse_magnitude() {
+        assert_eq!(parse_magnitude("4.7k", OHM), Ok(4700.0));
+        assert_eq!(parse_magnitude("4k7", OHM), Ok(4700.0));
+        assert_eq!(parse_magnitude("4R7", OHM), Ok(4.7));
+        assert_eq!(parse_magnitude("10 kΩ", OHM), Ok(10_000.0));
+        assert_eq!(parse_magnitude("2M", OHM), Ok(2_000_000.0));
+        assert!((parse_magnitude("100nF", &["F"]).unwrap() - 0.000_000_1).abs() < 1e-20);
+        assert!((parse_magnitude("20mA", &["A"]).unwrap() - 0.02).abs() < 1e-15);
+        assert_eq!(parse_magnitude("0.5", &["V"]), Ok(0.5));
+        // 付けてよい単位でなければエラー
+        assert!(parse_magnitude("5V", OHM).is_err());
+        assert!(parse_magnitude("4.7k7", OHM).is_err());
+        assert!(parse_magnitude("k", OHM).is_err());
+        assert_eq!(format_si(4700.0, "Ω"), "4.7 kΩ");
+        assert_eq!(format_si(0.000_000_47, "F"), "470 nF");
+        assert_eq!(format_si(0.02, "A"), "20 mA");
+        assert_eq!(format_si(12.0, "V"), "12 V");
+    }
+
+    #[test]
+    fn test_ohms_law() {
+        let result = ohms_law(Some(12.0), None, Some(4.0), None).unwrap();
+        assert_eq!(result.current, 3.0);
+        assert_eq!(result.power, 36.0);
+        assert_eq!(result.solved, vec!["current", "power"]);
+
+        let result = ohms_law(None, None, Some(100.0), Some(0.25)).unwrap();
+        assert!((result.current - 0.05).abs() < 1e-12);
+        assert!((result.voltage - 5.0).abs() < 1e-12);
+        assert_eq!(result.formatted["current"], "50 mA");
+
+        let result = ohms_law(Some(5.0), None, None, Some(0.5)).unwrap();
+        assert!((result.resistance - 50.0).abs() < 1e-12);
+
+        assert!(ohms_law(Some(12.0), None, None, None).is_err());
+        assert!(ohms_law(Some(12.0), Some(1.0), Some(12.0), None).is_err());
+        assert!(ohms_law(Some(12.0), Some(0.0), None, None).is_err());
+        assert!(ohms_law(None, Some(1.0), Some(-5.0), None).is_err());
+    }
+
+    #[test]
+    fn test_combinations() {
+        let series = combine(Component::Resistor, &[10_000.0, 4700.0], false).unwrap();
+        assert_eq!(series.total, 14_700.0);
+        assert_eq!(series.formatted, "14.7 kΩ");
+        let parallel = combine(Component::Resistor, &[100.0, 100.0, 50.0], true).unwrap();
+        assert!((parallel.total - 25.0).abs() < 1e-12);
+
+        // コンデンサは並列で和、直列で逆数の和の逆数
+        let parallel = combine(Component::Capacitor, &[0.000_001, 0.000_002], true).unwrap();
+        assert!((parallel.total - 0.000_003).abs() < 1e-18);
+        let series = combine(Component::Capacitor, &[0.000_001, 0.000_001], false).unwrap();
+        assert!((series.total - 0.000_000_5).abs() < 1e-18);
+        assert_eq!(series.formatted, "500 nF");
+
+        assert!(combine(Component::Inductor, &[], false).is_err());
+        assert!(combine(Component::Resistor, &[10.0, 0.0], true).is_err());
+    }
+
+    #[test]
+    fn test_voltage_divider() {
+        let divider = voltage_divider(12.0, 10_000.0, 5000.0, None).unwrap();
+        assert!((divider.output_voltage - 4.0).abs() < 1e-12);
+        assert!((divider.current - 0.0008).abs() < 1e-15);
+
+        // 負荷 5 kΩ は r2 と並列で 2.5 kΩ になる
+        let loaded = voltage_divider(12.0, 10_000.0, 5000.0, Some(5000.0)).unwrap();
+        assert_eq!(loaded.effective_r2, Some(2500.0));
+        assert!((loaded.output_voltage - 2.4).abs() < 1e-12);
+
+        assert!(voltage_divider(12.0, 10_000.0, 0.0, None).is_err());
+    }
+}
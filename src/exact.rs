@@ -0,0 +1,138 @@
+use std::f64::consts::PI;
+
+// 分数として認識する分母の上限
+const MAX_DENOMINATOR: i64 = 1000;
+// 平方根として認識する根号の中の整数の上限
+const MAX_RADICAND: i64 = 1_000_000;
+// 分数とみなす誤差（相対）。f64 の丸め誤差（約 1e-16）より大きく、偶然の一致は起きにくい
+const TOLERANCE: f64 = 1e-13;
+// これ以上の値は整数の精度が失われているため、厳密な値を推定しない（2^53）
+const MAX_MAGNITUDE: f64 = 9_007_199_254_740_992.0;
+// 最短の10進表記がこれより短い値（1.5 や 0.125）は、10進数のままで正確なので分数にしない
+const MIN_SIGNIFICANT_DIGITS: usize = 12;
+
+// f64 の計算結果が整数・分数・π の有理数倍・平方根のいずれかに一致すれば、その厳密な表記を返す。
+// 例: sqrt(2)^2 = 2.0000000000000004 は "2"、1/3 は "1/3"、π/2 は "pi/2"、1/sqrt(2) は "sqrt(2)/2"
+pub fn recognize(value: f64) -> Option<String> {
+    if !value.is_finite()
+        || value == 0.0
+        || value.abs() >= MAX_MAGNITUDE
+        || significant_digits(value) < MIN_SIGNIFICANT_DIGITS
+    {
+        return None;
+    }
+    if let Some((numerator, denominator)) = rational(value) {
+        return Some(fraction(numerator, denominator));
+    }
+    if let Some((numerator, denominator)) = rational(value / PI) {
+        return Some(multiple_of("pi", numerator, denominator));
+    }
+    square_root(value)
+}
+
+// 最短の往復変換での有効数字の桁数
+fn significant_digits(value: f64) -> usize {
+    let formatted = format!("{:e}", value.abs());
+    let mantissa = formatted.split('e').next().unwrap_or_default();
+    mantissa.bytes().filter(u8::is_ascii_digit).count()
+}
+
+// 連分数展開で、誤差 TOLERANCE 以内の分母が最小の分数を求める
+fn rational(value: f64) -> Option<(i64, i64)> {
+    let target = value.abs();
+    let (mut p0, mut q0, mut p1, mut q1) = (0i64, 1i64, 1i64, 0i64);
+    let mut remainder = target;
+    loop {
+        if remainder >= i64::MAX as f64 {
+            return None;
+        }
+        let term = remainder.floor() as i64;
+        let p2 = term.checked_mul(p1)?.checked_add(p0)?;
+        let q2 = term.checked_mul(q1)?.checked_add(q0)?;
+        if q2 > MAX_DENOMINATOR {
+            return None;
+        }
+        if (p2 as f64 / q2 as f64 - target).abs() <= target * TOLERANCE {
+            return Some((if value < 0.0 { -p2 } else { p2 }, q2));
+        }
+        let fractional = remainder - remainder.floor();
+        if fractional == 0.0 {
+            return None;
+        }
+        remainder = 1.0 / fractional;
+        (p0, q0, p1, q1) = (p1, q1, p2, q2);
+    }
+}
+
+// 2 乗が分数 p/q になる値を a*sqrt(b)/c の形で表す（b は平方因子を含まない）
+fn square_root(value: f64) -> Option<String> {
+    let (numerator, denominator) = rational(value * value)?;
+    // √(p/q) = √(pq)/q
+    let radicand = numerator.checked_mul(denominator)?;
+    if radicand > MAX_RADICAND {
+        return None;
+    }
+    let (outside, inside) = split_square(radicand);
+    if inside == 1 {
+        // 2 乗が有理数の平方なら、値自体が分数として認識されている
+        return None;
+    }
+    let divisor = gcd(outside, denominator);
+    let (outside, denominator) = (outside / divisor, denominator / divisor);
+    let sign = if value < 0.0 { "-" } else { "" };
+    let root = format!("sqrt({})", inside);
+    let scaled = if outside == 1 {
+        root
+    } else {
+        format!("{}*{}", outside, root)
+    };
+    Some(if denominator == 1 {
+        format!("{}{}", sign, scaled)
+    } else {
+        format!("{}{}/{}", sign, scaled, denominator)
+    })
+}
+
+// n = a² × b（b は平方因子を含まない）となる (a, b)
+fn split_square(n: i64) -> (i64, i64) {
+    let (mut outside, mut inside) = (1, n);
+    let mut factor = 2;
+    while factor * factor <= inside {
+        while inside % (factor * factor) == 0 {
+            inside /= factor * factor;
+            outside *= factor;
+        }
+        factor += 1;
+    }
+    (outside, inside)
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+fn fraction(numerator: i64, denominator: i64) -> String {
+    if denominator == 1 {
+        numerator.to_string()
+    } else {
+        format!("{}/{}", numerator, denominator)
+    }
+}
+
+// p/q × name（"pi"、"-pi"、"3*pi/4" など）
+fn multiple_of(name: &str, numerator: i64, denominator: i64) -> String {
+    let sign = if numerator < 0 { "-" } else { "" };
+    let scaled = match numerator.abs() {
+        1 => name.to_string(),
+        n => format!("{}*{}", n, name),
+    };
+    if denominator == 1 {
+        format!("{}{}", sign, scaled)
+    } else {
+        format!("{}{}/{}", sign, scaled, denominator)
+    }
+}
@@ -0,0 +1,31 @@
+#[cfg(test)]
+mod tests {
+    use crate::exact::recognize;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn test_recognize_exact_forms() {
+        assert_eq!(recognize(2f64.sqrt().powi(2)).as_deref(), Some("2"));
+        assert_eq!(recognize(1.0 / 3.0).as_deref(), Some("1/3"));
+        assert_eq!(recognize(-22.0 / 7.0).as_deref(), Some("-22/7"));
+        assert_eq!(recognize(0.1 + 0.2).as_deref(), Some("3/10"));
+        assert_eq!(recognize(PI).as_deref(), Some("pi"));
+        assert_eq!(recognize(PI / 2.0).as_deref(), Some("pi/2"));
+        assert_eq!(recognize(-3.0 * PI / 4.0).as_deref(), Some("-3*pi/4"));
+        assert_eq!(recognize(2f64.sqrt()).as_deref(), Some("sqrt(2)"));
+        assert_eq!(recognize(12f64.sqrt()).as_deref(), Some("2*sqrt(3)"));
+        assert_eq!(recognize(1.0 / 2f64.sqrt()).as_deref(), Some("sqrt(2)/2"));
+    }
+
+    #[test]
+    fn test_recognize_leaves_plain_decimals() {
+        // 10進数のままで正確な値や、認識できない値
+        assert_eq!(recognize(1.5), None);
+        assert_eq!(recognize(0.0), None);
+        assert_eq!(recognize(1234.5678), None);
+        assert_eq!(recognize(std::f64::consts::E), None);
+        assert_eq!(recognize(2f64.ln()), None);
+        assert_eq!(recognize(f64::NAN), None);
+        assert_eq!(recognize(2f64.powi(60) + 1.0), None);
+    }
+}
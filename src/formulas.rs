@@ -0,0 +1,198 @@
+use crate::calculator::{Calculator, CompiledExpr};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+// recalculate で値が変わった数式
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Change {
+    // 再計算する前の値（新しく定義した数式やエラーだった場合は None）
+    pub previous: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+struct Formula {
+    expression: String,
+    compiled: CompiledExpr,
+    // 式が参照する変数と他の数式の名前
+    dependencies: Vec<String>,
+    value: Result<f64, String>,
+}
+
+// 名前を付けて保存した数式と、その依存関係。変数が変わったときは、その変数に
+// （他の数式を通して）依存する数式だけを依存関係の順に再計算する
+#[derive(Debug, Clone, Default)]
+pub struct FormulaStore {
+    formulas: BTreeMap<String, Formula>,
+}
+
+impl FormulaStore {
+    pub fn len(&self) -> usize {
+        self.formulas.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.formulas.is_empty()
+    }
+
+    pub fn clear(&mut self) -> usize {
+        std::mem::take(&mut self.formulas).len()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.formulas.contains_key(name)
+    }
+
+    // 数式を定義（同じ名前があれば置き換え）する。値は recalculate で求める
+    pub fn define(
+        &mut self,
+        calculator: &Calculator,
+        name: &str,
+        expression: &str,
+    ) -> Result<(), String> {
+        let compiled = calculator
+            .compile(expression)
+            .map_err(|e| format!("数式 {} の計算エラー: {}", name, e))?;
+        let dependencies: Vec<String> = compiled
+            .variables()
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+        if dependencies.iter().any(|dependency| dependency == name)
+            || dependencies
+                .iter()
+                .any(|dependency| self.depends_on(dependency, name))
+        {
+            return Err(format!("数式 {} が循環参照しています", name));
+        }
+        self.formulas.insert(
+            name.to_string(),
+            Formula {
+                expression: expression.to_string(),
+                compiled,
+                dependencies,
+                value: Err("まだ計算していません".to_string()),
+            },
+        );
+        Ok(())
+    }
+
+    // formula が（他の数式を通して）target を参照しているか
+    fn depends_on(&self, formula: &str, target: &str) -> bool {
+        let mut stack = vec![formula];
+        let mut seen = BTreeSet::new();
+        while let Some(name) = stack.pop() {
+            let Some(formula) = self.formulas.get(name) else {
+                continue;
+            };
+            if !seen.insert(name) {
+                continue;
+            }
+            for dependency in &formula.dependencies {
+                if dependency == target {
+                    return true;
+                }
+                stack.push(dependency);
+            }
+        }
+        false
+    }
+
+    // changed（変数または数式の名前）に依存する数式を、依存関係の順に並べる
+    pub fn affected(&self, changed: &BTreeSet<String>) -> Vec<String> {
+        let mut order = Vec::new();
+        let mut visited = BTreeSet::new();
+        for name in self.formulas.keys() {
+            self.visit(name, &mut visited, &mut order);
+        }
+        order
+            .into_iter()
+            .filter(|name| {
+                changed.contains(name)
+                    || changed.iter().any(|changed| self.depends_on(name, changed))
+            })
+            .collect()
+    }
+
+    // 帰りがけ順に並べると、数式は参照する数式より後になる（define で循環を防いでいる）
+    fn visit(&self, name: &str, visited: &mut BTreeSet<String>, order: &mut Vec<String>) {
+        let Some(formula) = self.formulas.get(name) else {
+            return;
+        };
+        if !visited.insert(name.to_string()) {
+            return;
+        }
+        for dependency in &formula.dependencies {
+            self.visit(dependency, visited, order);
+        }
+        order.push(name.to_string());
+    }
+
+    // changed に依存する数式を再計算し、再計算した数式の名前と、値が変わった数式を返す
+    pub fn recalculate(
+        &mut self,
+        variables: &BTreeMap<String, f64>,
+        changed: &BTreeSet<String>,
+    ) -> (Vec<String>, BTreeMap<String, Change>) {
+        let affected = self.affected(changed);
+        let mut changes = BTreeMap::new();
+        for name in &affected {
+            let mut vars: HashMap<String, f64> = variables
+                .iter()
+                .map(|(name, value)| (name.clone(), *value))
+                .collect();
+            let formula = &self.formulas[name];
+            let mut value = Ok(());
+            for dependency in &formula.dependencies {
+                if let Some(other) = self.formulas.get(dependency) {
+                    match &other.value {
+                        Ok(other_value) => {
+                            vars.insert(dependency.clone(), *other_value);
+                        }
+                        Err(_) => {
+                            value = Err(format!("参照している数式 {} がエラーです", dependency))
+                        }
+                    }
+                }
+            }
+            let value = value.and_then(|()| formula.compiled.eval(&vars));
+            if formula.value != value {
+                changes.insert(
+                    name.clone(),
+                    Change {
+                        previous: formula.value.as_ref().ok().copied(),
+                        value: value.as_ref().ok().copied(),
+                        error: value.as_ref().err().cloned(),
+                    },
+                );
+            }
+            if let Some(formula) = self.formulas.get_mut(name) {
+                formula.value = value;
+            }
+        }
+        (affected, changes)
+    }
+
+    // すべての数式の式と現在の値
+    pub fn summary(&self) -> BTreeMap<String, serde_json::Value> {
+        self.formulas
+            .iter()
+            .map(|(name, formula)| {
+                let value = match &formula.value {
+                    Ok(value) => serde_json::json!({
+                        "expression": formula.expression,
+                        "value": value,
+                    }),
+                    Err(e) => serde_json::json!({
+                        "expression": formula.expression,
+                        "error": e,
+                    }),
+                };
+                (name.clone(), value)
+            })
+            .collect()
+    }
+}
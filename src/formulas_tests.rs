@@ -0,0 +1,65 @@
+#[cfg(test)]
+mod tests {
+    use crate::calculator::Calculator;
+    use crate::formulas::FormulaStore;
+    use std::collections::{BTreeMap, BTreeSet};
+
+    fn names(names: &[&str]) -> BTreeSet<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_recalculate_only_dependents() {
+        let calculator = Calculator::new();
+        let mut store = FormulaStore::default();
+        store
+            .define(&calculator, "with_tax", "subtotal * (1 + tax)")
+            .unwrap();
+        store
+            .define(&calculator, "subtotal", "price * quantity")
+            .unwrap();
+        store.define(&calculator, "shipping", "weight * 2").unwrap();
+        let mut variables = BTreeMap::from([
+            ("price".to_string(), 100.0),
+            ("quantity".to_string(), 3.0),
+            ("tax".to_string(), 0.1),
+            ("weight".to_string(), 5.0),
+        ]);
+        let (recalculated, changes) =
+            store.recalculate(&variables, &names(&["with_tax", "subtotal", "shipping"]));
+        // 参照される数式が先
+        assert_eq!(recalculated, vec!["shipping", "subtotal", "with_tax"]);
+        assert_eq!(changes["with_tax"].value, Some(330.0));
+        assert_eq!(changes["with_tax"].previous, None);
+
+        variables.insert("price".to_string(), 200.0);
+        let (recalculated, changes) = store.recalculate(&variables, &names(&["price"]));
+        assert_eq!(recalculated, vec!["subtotal", "with_tax"]);
+        assert_eq!(changes["subtotal"].previous, Some(300.0));
+        assert_eq!(changes["with_tax"].value, Some(660.0));
+        assert!(!changes.contains_key("shipping"));
+
+        // 値が変わらなければ changes には含めない
+        variables.insert("quantity".to_string(), 3.0);
+        let (recalculated, changes) = store.recalculate(&variables, &names(&["quantity"]));
+        assert_eq!(recalculated.len(), 2);
+        assert!(changes.is_empty());
+    }
+
+    #[test]
+    fn test_formula_errors() {
+        let calculator = Calculator::new();
+        let mut store = FormulaStore::default();
+        store.define(&calculator, "a", "b + 1").unwrap();
+        store.define(&calculator, "b", "c * 2").unwrap();
+        assert!(store.define(&calculator, "c", "a - 1").is_err());
+        assert!(store.define(&calculator, "d", "d + 1").is_err());
+        assert!(store.define(&calculator, "e", "1 +").is_err());
+        assert_eq!(store.len(), 2);
+
+        // 未定義の変数を参照する数式と、それに依存する数式はエラーになる
+        let (_, changes) = store.recalculate(&BTreeMap::new(), &names(&["a", "b"]));
+        assert!(changes["b"].error.is_some());
+        assert!(changes["a"].error.as_deref().unwrap().contains("b"));
+    }
+}
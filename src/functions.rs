@@ -0,0 +1,409 @@
+use crate::calculator::domain_error;
+use std::f64::consts::PI;
+
+// 引数の数や定義域を検査する組み込み関数（sqrt などの1引数の関数は Calculator::new で登録する）
+
+// Lanczos 近似（g = 7, n = 9）の係数。相対誤差はおよそ 1e-15
+const LANCZOS_G: f64 = 7.0;
+const LANCZOS_COEFFICIENTS: [f64; 9] = [
+    0.999_999_999_999_809_9,
+    676.520_368_121_885_1,
+    -1_259.139_216_722_402_8,
+    771.323_428_777_653_1,
+    -176.615_029_162_140_6,
+    12.507_343_278_686_905,
+    -0.138_571_095_265_720_12,
+    9.984_369_578_019_572e-6,
+    1.505_632_735_149_311_6e-7,
+];
+// これより大きい整数の gamma は f64 の範囲を超える（171! は約 1.2e309）
+const MAX_FACTORIAL_GAMMA: f64 = 171.0;
+// erf を級数、erfc を連分数で求める境目
+const ERFC_CONTINUED_FRACTION_FROM: f64 = 2.0;
+const MAX_SERIES_TERMS: usize = 500;
+
+// 関数の（最初の）引数 x の定義域。呼び出す前に検査し、NaN の代わりに定義域を示すエラーにする
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Domain {
+    Real,
+    Positive,
+    NonNegative,
+    GreaterThanMinusOne,
+    // ガンマ関数の極（0 と負の整数）を除く
+    NotPole,
+}
+
+impl Domain {
+    pub fn contains(self, x: f64) -> bool {
+        match self {
+            Domain::Real => true,
+            Domain::Positive => x > 0.0,
+            Domain::NonNegative => x >= 0.0,
+            Domain::GreaterThanMinusOne => x > -1.0,
+            Domain::NotPole => !(x <= 0.0 && x.fract() == 0.0),
+        }
+    }
+
+    pub fn describe(self) -> &'static str {
+        match self {
+            Domain::Real => "すべての実数",
+            Domain::Positive => "x > 0",
+            Domain::NonNegative => "x ≥ 0",
+            Domain::GreaterThanMinusOne => "x > -1",
+            Domain::NotPole => "0 と負の整数を除く実数",
+        }
+    }
+
+    pub fn check(self, name: &str, x: f64) -> Result<f64, String> {
+        if !self.contains(x) {
+            return Err(domain_error(format!(
+                "{} は x = {} では定義されません（定義域: {}）",
+                name,
+                x,
+                self.describe()
+            )));
+        }
+        Ok(x)
+    }
+}
+
+// sqrt などの1引数の関数
+#[derive(Debug, Clone, Copy)]
+pub struct UnaryFunction {
+    pub function: fn(f64) -> f64,
+    pub domain: Domain,
+}
+
+impl UnaryFunction {
+    pub const fn new(function: fn(f64) -> f64, domain: Domain) -> Self {
+        Self { function, domain }
+    }
+
+    pub fn call(&self, name: &str, x: f64) -> Result<f64, String> {
+        Ok((self.function)(self.domain.check(name, x)?))
+    }
+}
+
+// 呼び出しごとに引数の数を検査し、定義域の外ならエラーにする関数
+#[derive(Debug, Clone, Copy)]
+pub struct MultiFunction {
+    pub min_args: usize,
+    // None なら上限なし（可変長引数）
+    pub max_args: Option<usize>,
+    // 最初の引数の定義域（残りの引数は function の中で検査する）
+    pub domain: Domain,
+    pub function: fn(&[f64]) -> Result<f64, String>,
+}
+
+impl MultiFunction {
+    const fn fixed(args: usize, function: fn(&[f64]) -> Result<f64, String>) -> Self {
+        Self {
+            min_args: args,
+            max_args: Some(args),
+            domain: Domain::Real,
+            function,
+        }
+    }
+
+    const fn variadic(min_args: usize, function: fn(&[f64]) -> Result<f64, String>) -> Self {
+        Self {
+            min_args,
+            max_args: None,
+            domain: Domain::Real,
+            function,
+        }
+    }
+
+    const fn with_domain(self, domain: Domain) -> Self {
+        Self { domain, ..self }
+    }
+
+    // 引数の数は呼び出す側（構文解析やコンパイル）で検査済みとする
+    pub fn call(&self, name: &str, args: &[f64]) -> Result<f64, String> {
+        if let Some(&x) = args.first() {
+            self.domain.check(name, x)?;
+        }
+        (self.function)(args)
+    }
+
+    pub fn check_arity(&self, name: &str, count: usize) -> Result<(), String> {
+        let expected = match self.max_args {
+            Some(max) if max == self.min_args => format!("{} 個", max),
+            Some(max) => format!("{}〜{} 個", self.min_args, max),
+            None => format!("{} 個以上", self.min_args),
+        };
+        if count < self.min_args || self.max_args.is_some_and(|max| count > max) {
+            return Err(format!(
+                "{} の引数は {}です（{} 個指定されています）",
+                name, expected, count
+            ));
+        }
+        Ok(())
+    }
+}
+
+// Calculator に登録する関数の一覧
+pub fn builtins() -> Vec<(&'static str, MultiFunction)> {
+    #[cfg_attr(not(feature = "trig"), allow(unused_mut))]
+    let mut functions = vec![
+        ("root", MultiFunction::fixed(2, root)),
+        (
+            "ln",
+            MultiFunction::fixed(1, ln).with_domain(Domain::Positive),
+        ),
+        (
+            "log10",
+            MultiFunction::fixed(1, log10).with_domain(Domain::Positive),
+        ),
+        (
+            "log2",
+            MultiFunction::fixed(1, log2).with_domain(Domain::Positive),
+        ),
+        (
+            "log",
+            MultiFunction::fixed(2, log).with_domain(Domain::Positive),
+        ),
+        (
+            "ln1p",
+            MultiFunction::fixed(1, ln1p).with_domain(Domain::GreaterThanMinusOne),
+        ),
+        (
+            "gamma",
+            MultiFunction::fixed(1, gamma).with_domain(Domain::NotPole),
+        ),
+        (
+            "lgamma",
+            MultiFunction::fixed(1, lgamma).with_domain(Domain::NotPole),
+        ),
+        ("hypot", MultiFunction::fixed(2, hypot)),
+        ("clamp", MultiFunction::fixed(3, clamp)),
+        ("min", MultiFunction::variadic(2, min)),
+        ("max", MultiFunction::variadic(2, max)),
+        ("avg", MultiFunction::variadic(2, avg)),
+        ("lerp", MultiFunction::fixed(3, lerp)),
+        ("maprange", MultiFunction::fixed(5, maprange)),
+    ];
+    #[cfg(feature = "trig")]
+    functions.push(("atan2", MultiFunction::fixed(2, atan2)));
+    functions
+}
+
+// n 乗根。奇数乗根は負の数にも使える（root(-8, 3) = -2）
+fn root(args: &[f64]) -> Result<f64, String> {
+    let (x, n) = (args[0], args[1]);
+    if n == 0.0 {
+        return Err("root の次数 n は 0 以外にしてください".to_string());
+    }
+    let is_integer = n.fract() == 0.0;
+    if x < 0.0 && !(is_integer && n % 2.0 != 0.0) {
+        return Err(domain_error(format!(
+            "負の数の {} 乗根は実数になりません: root({}, {})",
+            n, x, n
+        )));
+    }
+    let magnitude = match n {
+        2.0 => x.abs().sqrt(),
+        3.0 => x.abs().cbrt(),
+        _ => x.abs().powf(n.recip()),
+    };
+    // 64^(1/3) = 3.9999999999999996 のような誤差を、整数の根なら正確な値にする
+    let rounded = magnitude.round();
+    let magnitude = if is_integer && rounded.powf(n) == x.abs() {
+        rounded
+    } else {
+        magnitude
+    };
+    Ok(magnitude.copysign(x))
+}
+
+// 対数の引数 x の定義域（x > 0）は MultiFunction::call で検査済み
+fn ln(args: &[f64]) -> Result<f64, String> {
+    Ok(args[0].ln())
+}
+
+fn log10(args: &[f64]) -> Result<f64, String> {
+    Ok(args[0].log10())
+}
+
+fn log2(args: &[f64]) -> Result<f64, String> {
+    Ok(args[0].log2())
+}
+
+// 任意の底の対数 log(x, base)
+fn log(args: &[f64]) -> Result<f64, String> {
+    let (x, base) = (args[0], args[1]);
+    if base <= 0.0 || base == 1.0 {
+        return Err(format!(
+            "log の底は 1 以外の正の数にしてください: log({}, {})",
+            x, base
+        ));
+    }
+    // 底が 10 と 2 のときは専用の関数の方が正確（log(1000, 10) = 3）
+    Ok(match base {
+        10.0 => x.log10(),
+        2.0 => x.log2(),
+        _ => x.ln() / base.ln(),
+    })
+}
+
+// ln(1 + x)。0 に近い x（小さな利率や確率）でも 1 + x の丸めで桁を失わない
+fn ln1p(args: &[f64]) -> Result<f64, String> {
+    Ok(args[0].ln_1p())
+}
+
+// √(x² + y²)。途中の x² が f64 の範囲を超える値（hypot(1e200, 1e200)）でも求められる
+fn hypot(args: &[f64]) -> Result<f64, String> {
+    Ok(args[0].hypot(args[1]))
+}
+
+// x を lo 以上 hi 以下に収める
+fn clamp(args: &[f64]) -> Result<f64, String> {
+    let (x, lo, hi) = (args[0], args[1], args[2]);
+    if lo > hi {
+        return Err(format!(
+            "clamp の下限は上限以下にしてください: clamp({}, {}, {})",
+            x, lo, hi
+        ));
+    }
+    Ok(x.clamp(lo, hi))
+}
+
+fn min(args: &[f64]) -> Result<f64, String> {
+    Ok(args.iter().copied().fold(f64::INFINITY, f64::min))
+}
+
+fn max(args: &[f64]) -> Result<f64, String> {
+    Ok(args.iter().copied().fold(f64::NEG_INFINITY, f64::max))
+}
+
+// 相加平均。合計が f64 の範囲を超えないよう、各値を個数で割ってから足す
+fn avg(args: &[f64]) -> Result<f64, String> {
+    let count = args.len() as f64;
+    Ok(args.iter().map(|x| x / count).sum())
+}
+
+// a と b の間の線形補間。t = 0 で a、t = 1 で b をちょうど返す
+fn lerp(args: &[f64]) -> Result<f64, String> {
+    Ok(interpolate(args[0], args[1], args[2]))
+}
+
+// x を [in_lo, in_hi] から [out_lo, out_hi] に線形に写す（範囲の外の x は外挿する）
+fn maprange(args: &[f64]) -> Result<f64, String> {
+    let (x, in_lo, in_hi, out_lo, out_hi) = (args[0], args[1], args[2], args[3], args[4]);
+    if in_lo == in_hi {
+        return Err(format!(
+            "maprange の入力の範囲の幅が 0 です: maprange({}, {}, {}, {}, {})",
+            x, in_lo, in_hi, out_lo, out_hi
+        ));
+    }
+    Ok(interpolate(out_lo, out_hi, (x - in_lo) / (in_hi - in_lo)))
+}
+
+fn interpolate(a: f64, b: f64, t: f64) -> f64 {
+    (1.0 - t) * a + t * b
+}
+
+// 点 (x, y) の偏角（-π〜π のラジアン）。度数法では Calculator が度に変換する
+#[cfg(feature = "trig")]
+fn atan2(args: &[f64]) -> Result<f64, String> {
+    let (y, x) = (args[0], args[1]);
+    if y == 0.0 && x == 0.0 {
+        return Err("atan2(0, 0) の角度は定まりません".to_string());
+    }
+    Ok(y.atan2(x))
+}
+
+// ガンマ関数。正の整数 n では (n - 1)! を正確に返す（gamma(5) = 24）
+fn gamma(args: &[f64]) -> Result<f64, String> {
+    let x = args[0];
+    if x.fract() == 0.0 && x <= MAX_FACTORIAL_GAMMA {
+        return Ok((1..x as u32).map(f64::from).product());
+    }
+    if x < 0.5 {
+        // 相反公式 Γ(x)Γ(1 - x) = π / sin(πx)
+        return Ok(PI / ((PI * x).sin() * lanczos_gamma(1.0 - x)));
+    }
+    Ok(lanczos_gamma(x))
+}
+
+// ln|Γ(x)|。gamma が f64 の範囲を超える大きな x でも求められる
+fn lgamma(args: &[f64]) -> Result<f64, String> {
+    Ok(ln_abs_gamma(args[0]))
+}
+
+fn ln_abs_gamma(x: f64) -> f64 {
+    if x < 0.5 {
+        return (PI / (PI * x).sin().abs()).ln() - ln_abs_gamma(1.0 - x);
+    }
+    let (sum, t) = lanczos_sum(x);
+    0.5 * (2.0 * PI).ln() + (x - 0.5) * t.ln() - t + sum.ln()
+}
+
+// x >= 0.5 の Γ(x)
+fn lanczos_gamma(x: f64) -> f64 {
+    let (sum, t) = lanczos_sum(x);
+    (2.0 * PI).sqrt() * t.powf(x - 0.5) * (-t).exp() * sum
+}
+
+fn lanczos_sum(x: f64) -> (f64, f64) {
+    let x = x - 1.0;
+    let sum = LANCZOS_COEFFICIENTS[1..]
+        .iter()
+        .enumerate()
+        .fold(LANCZOS_COEFFICIENTS[0], |sum, (i, c)| {
+            sum + c / (x + i as f64 + 1.0)
+        });
+    (sum, x + LANCZOS_G + 0.5)
+}
+
+// 誤差関数
+pub fn erf(x: f64) -> f64 {
+    if x.abs() >= ERFC_CONTINUED_FRACTION_FROM {
+        return (1.0 - erfc_continued_fraction(x.abs())).copysign(x);
+    }
+    erf_series(x)
+}
+
+// 相補誤差関数 1 - erf(x)。大きな x でも桁落ちしない（erfc(10) ≈ 2.09e-45）
+pub fn erfc(x: f64) -> f64 {
+    if x >= ERFC_CONTINUED_FRACTION_FROM {
+        erfc_continued_fraction(x)
+    } else if x <= -ERFC_CONTINUED_FRACTION_FROM {
+        2.0 - erfc_continued_fraction(-x)
+    } else {
+        1.0 - erf_series(x)
+    }
+}
+
+// erf(x) = 2/√π · e^(-x²) · Σ (2x²)^n x / (1·3·…·(2n+1))。項はすべて同符号なので桁落ちしない
+fn erf_series(x: f64) -> f64 {
+    let mut term = x;
+    let mut sum = x;
+    for n in 1..MAX_SERIES_TERMS {
+        term *= 2.0 * x * x / (2 * n + 1) as f64;
+        sum += term;
+        if term.abs() <= sum.abs() * f64::EPSILON {
+            break;
+        }
+    }
+    2.0 / PI.sqrt() * (-x * x).exp() * sum
+}
+
+// x >= 2 の erfc(x) = e^(-x²)/√π · 1/(x + (1/2)/(x + 1/(x + (3/2)/(x + …))))。
+// 連分数は修正 Lentz 法で評価する
+fn erfc_continued_fraction(x: f64) -> f64 {
+    let mut f = x;
+    let mut c = x;
+    let mut d = 0.0;
+    for n in 1..MAX_SERIES_TERMS {
+        let a = n as f64 / 2.0;
+        d = 1.0 / (x + a * d);
+        c = x + a / c;
+        let delta = c * d;
+        f *= delta;
+        if (delta - 1.0).abs() <= f64::EPSILON {
+            break;
+        }
+    }
+    (-x * x).exp() / PI.sqrt() / f
+}
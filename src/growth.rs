@@ -0,0 +1,121 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "server", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum GrowthCalculation {
+    // 年平均成長率（CAGR）
+    Cagr,
+    // インフレ率による将来価値・現在価値
+    Inflation,
+    // 元の値が2倍になるまでの期間
+    DoublingTime,
+}
+
+impl GrowthCalculation {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Cagr => "cagr",
+            Self::Inflation => "inflation",
+            Self::DoublingTime => "doubling_time",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Cagr {
+    pub start_value: f64,
+    pub end_value: f64,
+    pub periods: f64,
+    // (end / start)^(1 / periods) - 1
+    pub cagr_percent: f64,
+    // 総成長率を期間数で割っただけの単純平均（複利を無視するため CAGR より大きくなる）
+    pub simple_average_percent: f64,
+    pub total_growth_percent: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Inflation {
+    pub amount: f64,
+    pub rate_percent: f64,
+    pub periods: f64,
+    // 今の amount と同じ購買力を保つのに periods 後に必要な額
+    pub future_value: f64,
+    // periods 後の amount が今のいくらに相当するか
+    pub present_value: f64,
+    pub cumulative_inflation_percent: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DoublingTime {
+    pub rate_percent: f64,
+    // ln 2 / ln(1 + r)
+    pub exact_periods: f64,
+    // 72 / r と 70 / r（目安）
+    pub rule_of_72: f64,
+    pub rule_of_70: f64,
+}
+
+pub fn cagr(start_value: f64, end_value: f64, periods: f64) -> Result<Cagr, String> {
+    if !(start_value > 0.0 && end_value > 0.0) {
+        return Err("CAGR の開始値と終了値には正の値を指定してください".to_string());
+    }
+    check_periods(periods)?;
+    let total_growth = end_value / start_value - 1.0;
+    Ok(Cagr {
+        start_value,
+        end_value,
+        periods,
+        cagr_percent: ((end_value / start_value).powf(1.0 / periods) - 1.0) * 100.0,
+        simple_average_percent: total_growth / periods * 100.0,
+        total_growth_percent: total_growth * 100.0,
+    })
+}
+
+pub fn inflation(amount: f64, rate_percent: f64, periods: f64) -> Result<Inflation, String> {
+    check_rate(rate_percent)?;
+    check_periods(periods)?;
+    let factor = (1.0 + rate_percent / 100.0).powf(periods);
+    if !amount.is_finite() || !factor.is_finite() {
+        return Err("計算結果が無効です（NaN または 無限大）".to_string());
+    }
+    Ok(Inflation {
+        amount,
+        rate_percent,
+        periods,
+        future_value: amount * factor,
+        present_value: amount / factor,
+        cumulative_inflation_percent: (factor - 1.0) * 100.0,
+    })
+}
+
+pub fn doubling_time(rate_percent: f64) -> Result<DoublingTime, String> {
+    if !(rate_percent > 0.0 && rate_percent.is_finite()) {
+        return Err("2倍になる期間は成長率が正の場合のみ求められます".to_string());
+    }
+    Ok(DoublingTime {
+        rate_percent,
+        exact_periods: 2f64.ln() / (1.0 + rate_percent / 100.0).ln(),
+        rule_of_72: 72.0 / rate_percent,
+        rule_of_70: 70.0 / rate_percent,
+    })
+}
+
+fn check_periods(periods: f64) -> Result<(), String> {
+    if periods > 0.0 && periods.is_finite() {
+        Ok(())
+    } else {
+        Err(format!("期間数には正の値を指定してください: {}", periods))
+    }
+}
+
+fn check_rate(rate_percent: f64) -> Result<(), String> {
+    if rate_percent > -100.0 && rate_percent.is_finite() {
+        Ok(())
+    } else {
+        Err(format!(
+            "率は -100% より大きい値で指定してください: {}",
+            rate_percent
+        ))
+    }
+}
@@ -0,0 +1,39 @@
+#[cfg(test)]
+mod tests {
+    use crate::growth::{cagr, doubling_time, inflation};
+
+    fn approx(actual: f64, expected: f64) -> bool {
+        (actual - expected).abs() < 1e-9
+    }
+
+    #[test]
+    fn test_cagr() {
+        // 100 → 121 は2年で年10%
+        let result = cagr(100.0, 121.0, 2.0).unwrap();
+
+        assert!(approx(result.cagr_percent, 10.0));
+        assert!(approx(result.simple_average_percent, 10.5));
+        assert!(approx(result.total_growth_percent, 21.0));
+        assert!(cagr(0.0, 121.0, 2.0).is_err());
+        assert!(cagr(100.0, 121.0, 0.0).is_err());
+    }
+
+    #[test]
+    fn test_inflation() {
+        let result = inflation(10000.0, 2.0, 2.0).unwrap();
+
+        assert!(approx(result.future_value, 10404.0));
+        assert!(approx(result.present_value, 10000.0 / 1.0404));
+        assert!(approx(result.cumulative_inflation_percent, 4.04));
+        assert!(inflation(10000.0, -100.0, 2.0).is_err());
+    }
+
+    #[test]
+    fn test_doubling_time() {
+        let result = doubling_time(8.0).unwrap();
+
+        assert_eq!(result.rule_of_72, 9.0);
+        assert!((result.exact_periods - 9.006).abs() < 1e-3);
+        assert!(doubling_time(0.0).is_err());
+    }
+}
@@ -0,0 +1,104 @@
+use crate::build_info;
+use crate::calculator::Calculator;
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub struct HealthCheck {
+    pub name: &'static str,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HealthReport {
+    pub status: &'static str,
+    pub version: &'static str,
+    pub uptime_seconds: u64,
+    pub active_sessions: usize,
+    pub checks: Vec<HealthCheck>,
+}
+
+impl HealthReport {
+    pub fn new(uptime_seconds: u64, active_sessions: usize) -> Self {
+        let checks = run_checks();
+        let status = if checks.iter().all(|check| check.ok) {
+            "ok"
+        } else {
+            "fail"
+        };
+        Self {
+            status,
+            version: build_info::VERSION,
+            uptime_seconds,
+            active_sessions,
+            checks,
+        }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.status == "ok"
+    }
+}
+
+// 計算エンジンの簡単な自己診断（解析・評価・ホワイトリスト）
+pub fn run_checks() -> Vec<HealthCheck> {
+    let calculator = Calculator::new();
+    vec![
+        expect_value(&calculator, "parse", "(1 + 2) * -3", -9.0),
+        expect_value(
+            &calculator,
+            "evaluate",
+            "2 + 3 * 4 - sqrt(16) / 2 ^ 2",
+            13.0,
+        ),
+        expect_error(&calculator, "whitelist", "exec(rm)"),
+        expect_error(&calculator, "dangerous_characters", "1; rm -rf /"),
+    ]
+}
+
+fn expect_value(
+    calculator: &Calculator,
+    name: &'static str,
+    expression: &str,
+    expected: f64,
+) -> HealthCheck {
+    match calculator.evaluate(expression) {
+        Ok(value) if value == expected => HealthCheck {
+            name,
+            ok: true,
+            detail: None,
+        },
+        Ok(value) => HealthCheck {
+            name,
+            ok: false,
+            detail: Some(format!(
+                "{} = {}（期待値: {}）",
+                expression, value, expected
+            )),
+        },
+        Err(e) => HealthCheck {
+            name,
+            ok: false,
+            detail: Some(format!("{}: {}", expression, e)),
+        },
+    }
+}
+
+fn expect_error(calculator: &Calculator, name: &'static str, expression: &str) -> HealthCheck {
+    match calculator.evaluate(expression) {
+        Err(_) => HealthCheck {
+            name,
+            ok: true,
+            detail: None,
+        },
+        Ok(value) => HealthCheck {
+            name,
+            ok: false,
+            detail: Some(format!(
+                "{} が拒否されずに {} と評価されました",
+                expression, value
+            )),
+        },
+    }
+}
@@ -0,0 +1,31 @@
+#[cfg(test)]
+mod tests {
+    use crate::health::{run_checks, HealthReport};
+    use crate::server::CalculatorService;
+
+    #[test]
+    fn test_health_checks_pass() {
+        let checks = run_checks();
+        assert!(!checks.is_empty());
+        for check in &checks {
+            assert!(check.ok, "{}: {:?}", check.name, check.detail);
+        }
+    }
+
+    #[test]
+    fn test_health_report() {
+        let report = HealthReport::new(42, 1);
+        assert!(report.is_ok());
+        assert_eq!(report.version, "0.1.0");
+        assert_eq!(report.uptime_seconds, 42);
+        assert_eq!(report.active_sessions, 1);
+    }
+
+    #[test]
+    fn test_health_tool() {
+        let service = CalculatorService::default();
+        let result = service.health().unwrap();
+        assert!(result.contains("\"status\": \"ok\""));
+        assert!(result.contains("\"active_sessions\": 0"));
+    }
+}
@@ -0,0 +1,122 @@
+use crate::dataset::csv_field;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// 計算履歴を CSV と JSON で公開するリソース
+pub const CSV_URI: &str = "calc://history.csv";
+pub const JSON_URI: &str = "calc://history.json";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HistoryEntry {
+    // セッション内の通し番号（古い履歴を削除しても振り直さない）
+    pub index: u64,
+    // 記録した時刻（UNIX 時間の秒）
+    pub timestamp: u64,
+    pub tool: &'static str,
+    pub expression: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct History {
+    next_index: u64,
+    entries: VecDeque<HistoryEntry>,
+}
+
+// calculate と calculate_batch で評価した式の履歴。max_entries を超えると古いものから削除する
+#[derive(Debug, Clone, Default)]
+pub struct HistoryStore {
+    inner: Arc<Mutex<History>>,
+}
+
+impl HistoryStore {
+    pub fn record(
+        &self,
+        tool: &'static str,
+        expression: &str,
+        outcome: Result<String, String>,
+        max_entries: usize,
+    ) {
+        if max_entries == 0 {
+            return;
+        }
+        let mut history = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        history.next_index += 1;
+        let (result, error) = match outcome {
+            Ok(result) => (Some(result), None),
+            Err(error) => (None, Some(error)),
+        };
+        let entry = HistoryEntry {
+            index: history.next_index,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs()),
+            tool,
+            expression: expression.to_string(),
+            result,
+            error,
+        };
+        history.entries.push_back(entry);
+        while history.entries.len() > max_entries {
+            history.entries.pop_front();
+        }
+    }
+
+    pub fn entries(&self) -> Vec<HistoryEntry> {
+        let history = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        history.entries.iter().cloned().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        let history = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        history.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // すべての履歴を削除し、削除した件数を返す
+    // ロックを持ったままパニックした（記録の更新が途中かもしれない）場合は空に戻し、戻したかを返す
+    pub fn recover(&self) -> bool {
+        if !self.inner.is_poisoned() {
+            return false;
+        }
+        self.clear();
+        self.inner.clear_poison();
+        true
+    }
+
+    pub fn clear(&self) -> usize {
+        let mut history = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let count = history.entries.len();
+        history.entries.clear();
+        count
+    }
+
+    // 表計算ソフトで開ける CSV（見出し行つき）
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("index,timestamp,tool,expression,result,error\n");
+        for entry in self.entries() {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{}\n",
+                entry.index,
+                entry.timestamp,
+                entry.tool,
+                csv_field(&entry.expression),
+                csv_field(entry.result.as_deref().unwrap_or_default()),
+                csv_field(entry.error.as_deref().unwrap_or_default())
+            ));
+        }
+        csv
+    }
+
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(&self.entries()).map_err(|e| e.to_string())
+    }
+}
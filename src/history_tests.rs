@@ -0,0 +1,41 @@
+#[cfg(test)]
+mod tests {
+    use crate::history::HistoryStore;
+
+    #[test]
+    fn test_history_csv() {
+        let history = HistoryStore::default();
+        history.record("calculate", "1 + 2", Ok("3".to_string()), 10);
+        history.record(
+            "calculate",
+            "max(1, 2",
+            Err("閉じ括弧がありません".to_string()),
+            10,
+        );
+        let csv = history.to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "index,timestamp,tool,expression,result,error");
+        assert!(lines[1].starts_with("1,"));
+        assert!(lines[1].ends_with(",calculate,1 + 2,3,"));
+        // カンマを含む式は囲む
+        assert!(lines[2].ends_with(",calculate,\"max(1, 2\",,閉じ括弧がありません"));
+    }
+
+    #[test]
+    fn test_history_limit() {
+        let history = HistoryStore::default();
+        for i in 0..5 {
+            history.record("calculate", &i.to_string(), Ok(i.to_string()), 3);
+        }
+        let entries = history.entries();
+        assert_eq!(entries.len(), 3);
+        // 通し番号は削除しても振り直さない
+        assert_eq!(entries[0].index, 3);
+        assert_eq!(entries[0].expression, "2");
+
+        history.record("calculate", "1", Ok("1".to_string()), 0);
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.clear(), 3);
+        assert!(history.is_empty());
+    }
+}
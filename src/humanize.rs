@@ -0,0 +1,199 @@
+use serde::{Deserialize, Serialize};
+
+// 数値を短く表すときの単位系
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "server", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum Locale {
+    // K, M, B, T（3桁ごと）
+    #[default]
+    En,
+    // 万, 億, 兆, 京（4桁ごと）
+    Ja,
+}
+
+// 小数点以下の最大桁数の上限（f64 の有効数字は約 15〜17 桁）
+pub const MAX_DECIMALS: u32 = 15;
+
+const EN_UNITS: &[(&str, f64)] = &[("T", 1e12), ("B", 1e9), ("M", 1e6), ("K", 1e3)];
+const JA_UNITS: &[(&str, f64)] = &[("京", 1e16), ("兆", 1e12), ("億", 1e8), ("万", 1e4)];
+// 2進接頭辞（1024倍ごと）
+const BYTE_UNITS: &[(&str, f64)] = &[
+    ("PiB", 1125899906842624.0),
+    ("TiB", 1099511627776.0),
+    ("GiB", 1073741824.0),
+    ("MiB", 1048576.0),
+    ("KiB", 1024.0),
+];
+// 解析のときだけ受け付ける SI 接頭辞（1000倍ごと）
+const SI_BYTE_UNITS: &[(&str, f64)] = &[
+    ("PB", 1e15),
+    ("TB", 1e12),
+    ("GB", 1e9),
+    ("MB", 1e6),
+    ("KB", 1e3),
+];
+
+#[derive(Debug, Serialize)]
+pub struct Humanized {
+    pub value: f64,
+    pub text: String,
+}
+
+// 1234567 → "1.23M"（en）/ "123.46万"（ja）。decimals は小数点以下の最大桁数
+pub fn humanize(value: f64, locale: Locale, decimals: u32) -> Result<Humanized, String> {
+    let units = match locale {
+        Locale::En => EN_UNITS,
+        Locale::Ja => JA_UNITS,
+    };
+    let text = format_with_units(value, units, "", decimals)?;
+    Ok(Humanized { value, text })
+}
+
+// 1610612736 → "1.5 GiB"
+pub fn humanize_bytes(value: f64, decimals: u32) -> Result<Humanized, String> {
+    let text = format_with_units(value, BYTE_UNITS, " B", decimals)?;
+    Ok(Humanized { value, text })
+}
+
+// "1.23M", "1億2345万", "1,234" などを数値に戻す
+pub fn parse(text: &str, locale: Locale) -> Result<Humanized, String> {
+    let normalized: String = text
+        .chars()
+        .filter(|c| !c.is_whitespace() && !matches!(c, ',' | '_'))
+        .collect();
+
+    let value = match locale {
+        Locale::En => parse_suffixed(&normalized, EN_UNITS, true),
+        Locale::Ja => parse_japanese(&normalized),
+    }
+    .ok_or_else(|| format!("数値として解釈できません: {}", text))?;
+    Ok(Humanized {
+        value,
+        text: text.to_string(),
+    })
+}
+
+// "1536 MiB", "1.5GB", "512 B" などをバイト数に戻す
+pub fn parse_bytes(text: &str) -> Result<Humanized, String> {
+    let normalized: String = text
+        .chars()
+        .filter(|c| !c.is_whitespace() && !matches!(c, ',' | '_'))
+        .collect();
+
+    let value = parse_suffixed(&normalized, BYTE_UNITS, false)
+        .or_else(|| parse_suffixed(&normalized, SI_BYTE_UNITS, false))
+        .or_else(|| parse_number(normalized.strip_suffix('B').unwrap_or(&normalized)))
+        .ok_or_else(|| format!("バイト数として解釈できません: {}", text))?;
+    Ok(Humanized {
+        value,
+        text: text.to_string(),
+    })
+}
+
+fn format_with_units(
+    value: f64,
+    units: &[(&str, f64)],
+    plain_suffix: &str,
+    decimals: u32,
+) -> Result<String, String> {
+    if !value.is_finite() {
+        return Err("計算結果が無効です（NaN または 無限大）".to_string());
+    }
+
+    let rounded = |scaled: f64| {
+        let factor = 10f64.powi(decimals as i32);
+        (scaled * factor).round() / factor
+    };
+    let mut selected = units.iter().position(|&(_, size)| value.abs() >= size);
+    // 丸めた結果が次の単位に届く場合（999999 → 1000K、999.9996 → 1000）は上の単位を使う
+    let size = selected.map_or(1.0, |index| units[index].1);
+    let next = match selected {
+        Some(index) => index.checked_sub(1),
+        None => units.len().checked_sub(1),
+    };
+    if let Some(next) = next {
+        if (rounded(value / size) * size).abs() >= units[next].1 {
+            selected = Some(next);
+        }
+    }
+
+    Ok(match selected {
+        Some(index) => {
+            let (name, size) = units[index];
+            let separator = if plain_suffix.is_empty() { "" } else { " " };
+            format!(
+                "{}{}{}",
+                trim_decimals(rounded(value / size), decimals),
+                separator,
+                name
+            )
+        }
+        None => format!(
+            "{}{}",
+            trim_decimals(rounded(value), decimals),
+            plain_suffix
+        ),
+    })
+}
+
+fn trim_decimals(value: f64, decimals: u32) -> String {
+    let text = format!("{:.*}", decimals as usize, value);
+    if text.contains('.') {
+        text.trim_end_matches('0').trim_end_matches('.').to_string()
+    } else {
+        text
+    }
+}
+
+fn parse_suffixed(text: &str, units: &[(&str, f64)], ignore_case: bool) -> Option<f64> {
+    for &(name, size) in units {
+        let split = text.len().checked_sub(name.len())?;
+        let (number, suffix) = (text.get(..split)?, text.get(split..)?);
+        let matched = if ignore_case {
+            suffix.eq_ignore_ascii_case(name)
+        } else {
+            suffix == name
+        };
+        if matched {
+            return parse_number(number).map(|number| number * size);
+        }
+    }
+    parse_number(text)
+}
+
+// "1億2345万6789" のように単位ごとに区切られた表記も受け付ける
+fn parse_japanese(text: &str) -> Option<f64> {
+    let (sign, text) = match text.strip_prefix('-') {
+        Some(rest) => (-1.0, rest),
+        None => (1.0, text),
+    };
+
+    let mut total = 0.0;
+    let mut rest = text;
+    let mut previous_size = f64::INFINITY;
+    while let Some(position) = rest.find(['万', '億', '兆', '京']) {
+        let unit = rest[position..].chars().next()?;
+        let size = JA_UNITS
+            .iter()
+            .find(|(name, _)| name.starts_with(unit))
+            .map(|&(_, size)| size)?;
+        // 単位は大きい順に並んでいなければならない
+        if size >= previous_size {
+            return None;
+        }
+        total += parse_number(&rest[..position])? * size;
+        previous_size = size;
+        rest = &rest[position + unit.len_utf8()..];
+    }
+    if !rest.is_empty() {
+        total += parse_number(rest)?;
+    } else if previous_size.is_infinite() {
+        return None;
+    }
+    Some(sign * total)
+}
+
+fn parse_number(text: &str) -> Option<f64> {
+    text.parse::<f64>().ok().filter(|value| value.is_finite())
+}
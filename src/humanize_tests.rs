@@ -0,0 +1,48 @@
+#[cfg(test)]
+mod tests {
+    use crate::humanize::{humanize, humanize_bytes, parse, parse_bytes, Locale};
+
+    #[test]
+    fn test_humanize_en() {
+        assert_eq!(humanize(1234567.0, Locale::En, 2).unwrap().text, "1.23M");
+        assert_eq!(humanize(-2500.0, Locale::En, 2).unwrap().text, "-2.5K");
+        assert_eq!(humanize(999.0, Locale::En, 2).unwrap().text, "999");
+        // 丸めで 1000K になる場合は M に繰り上げる
+        assert_eq!(humanize(999999.0, Locale::En, 2).unwrap().text, "1M");
+        assert_eq!(humanize(-999999.0, Locale::En, 2).unwrap().text, "-1M");
+        assert_eq!(humanize(999.9996, Locale::En, 2).unwrap().text, "1K");
+    }
+
+    #[test]
+    fn test_humanize_ja() {
+        assert_eq!(humanize(1234567.0, Locale::Ja, 1).unwrap().text, "123.5万");
+        assert_eq!(humanize(350000000.0, Locale::Ja, 2).unwrap().text, "3.5億");
+    }
+
+    #[test]
+    fn test_humanize_bytes() {
+        assert_eq!(humanize_bytes(1610612736.0, 2).unwrap().text, "1.5 GiB");
+        assert_eq!(humanize_bytes(512.0, 2).unwrap().text, "512 B");
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("1.23M", Locale::En).unwrap().value, 1230000.0);
+        assert_eq!(parse("2.5k", Locale::En).unwrap().value, 2500.0);
+        assert_eq!(parse("1,234", Locale::En).unwrap().value, 1234.0);
+        assert_eq!(parse("123.4万", Locale::Ja).unwrap().value, 1234000.0);
+        assert_eq!(
+            parse("1億2345万6789", Locale::Ja).unwrap().value,
+            123456789.0
+        );
+        assert!(parse("万1億", Locale::Ja).is_err());
+        assert!(parse("abc", Locale::En).is_err());
+    }
+
+    #[test]
+    fn test_parse_bytes() {
+        assert_eq!(parse_bytes("1536 MiB").unwrap().value, 1610612736.0);
+        assert_eq!(parse_bytes("1.5GB").unwrap().value, 1.5e9);
+        assert_eq!(parse_bytes("512 B").unwrap().value, 512.0);
+    }
+}
@@ -0,0 +1,361 @@
+use rmcp::model::Tool;
+use serde::Deserialize;
+use serde_json::Value;
+use std::sync::Arc;
+
+// tools/list のツールと引数の説明の言語
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Language {
+    // 日本語のみ（ツールの定義に書かれた説明）
+    Ja,
+    // 英語のみ（英語の説明がない項目は日本語）
+    En,
+    // 英語の後に日本語
+    Both,
+}
+
+impl Language {
+    // 設定で指定されていない場合はロケール（LC_ALL, LC_MESSAGES, LANG の順）から決める。
+    // ja なら日本語、en なら英語、それ以外（未設定や C を含む）は両方
+    pub fn from_locale(locale: Option<&str>) -> Self {
+        match locale.map(str::to_ascii_lowercase) {
+            Some(locale) if locale.starts_with("ja") => Self::Ja,
+            Some(locale) if locale.starts_with("en") => Self::En,
+            _ => Self::Both,
+        }
+    }
+
+    pub fn from_env() -> Self {
+        let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|value| !value.is_empty());
+        Self::from_locale(locale.as_deref())
+    }
+
+    pub fn combine(self, ja: &str, en: &str) -> String {
+        match self {
+            Self::Ja => ja.to_string(),
+            Self::En => en.to_string(),
+            Self::Both => format!("{}\n\n{}", en, ja),
+        }
+    }
+}
+
+// サーバの説明（initialize の instructions）
+pub const INSTRUCTIONS_EN: &str =
+    "An MCP server that provides calculation tools. It takes math expressions and returns the results.";
+
+// ツールの英語の説明
+const TOOLS: &[(&str, &str)] = &[
+    ("calculate", "Evaluates a math expression safely. Supports arithmetic, powers, parentheses and math functions (square root, absolute value, trigonometry, natural logarithm), and is protected against malicious input."),
+    ("calculate_batch", "Evaluates many expressions at once. Expressions are evaluated in parallel and the result of each (including the value type, unit and whether it is exact) or its error is returned as JSON in input order. An error or timeout in one expression does not affect the others."),
+    ("compare", "Evaluates two expressions (or an expression and an expected value) and returns as JSON whether they match within absolute and relative tolerances, along with the difference. Useful for double-checking your own results."),
+    ("duration_calc", "Calculates with durations: adding and subtracting durations, multiplying and dividing by numbers, and duration ÷ duration (how many slots fit). Returns the result as h:mm:ss and as total hours, minutes and seconds in JSON."),
+    ("convert_timezone", "Converts a date and time to another time zone and returns as JSON the RFC3339 time, the UTC offset (reflecting daylight saving time) and the hours from the reference time (hours_from_now)."),
+    ("time_between", "Returns as JSON the interval between two date-times in different time zones as the actual elapsed time including daylight saving transitions (seconds, hours, h:mm:ss)."),
+    ("age", "Calculates the age in years, months and days on a reference date from a birth date, and returns it as JSON with the number of days until the next birthday. People born on February 29 celebrate on February 28 in common years."),
+    ("humanize", "Converts between numbers and human-readable notation and returns value and text as JSON. With value it produces notation such as 1.23M or 123.46万; with text it parses back to a number. With bytes: true values are treated as byte counts (1.5 GiB, etc.)."),
+    ("sheet", "Computes a table like a spreadsheet, with numbers and formulas referring to other cells (=A1 + B1). Cells are evaluated in dependency order and the computed table and the cells that failed (circular references, etc.) with reasons are returned as JSON."),
+    ("matrix", "Computes matrix eigenvalues and eigenvectors (QR algorithm), LU decomposition (PA = LU), QR decomposition, Cholesky decomposition and condition numbers, returning result matrices as arrays of rows in JSON."),
+    ("upload_data", "Sends large data to the server in chunks. Omit upload_id in the first chunk, pass the returned upload_id with the following chunks and send done: true with the last one. The completed data can be referenced by the returned uri (upload://<id>) from tools such as matrix, and can also be read as an MCP resource."),
+    ("statistics", "Returns summary statistics of numeric data as JSON (count, sum, mean, median, min/max, quartiles, sample and population variance and standard deviation). Pass the data as a values array, CSV text (csv) or a CSV sent with upload_data (resource_uri); for CSV, choose the column with column."),
+    ("histogram", "Returns the frequency distribution of numeric data as JSON (frequency, relative frequency and cumulative relative frequency per bin). Choose the number of equal-width bins with bins or the boundaries with edges; by default Sturges' rule is used. chart: true also returns an ASCII bar chart, and image: true a PNG bar chart (size and colors set with width, height and theme). Data is passed as for statistics."),
+    ("combinatorics", "Computes permutations and combinations (with and without repetition), multinomial coefficients, derangements and Stirling numbers exactly with arbitrary-precision integers, returning the value (decimal string), digit count and an approximate scientific notation as JSON."),
+    ("number_properties", "Returns properties of an integer as JSON: parity, primality, whether it is a perfect square, prime factorization, divisors with their count and sum, perfect/abundant/deficient classification, digit count and digit sum, binary/octal/hex notation and the neighbouring primes."),
+    ("generate_samples", "Generates count samples from a uniform, normal, binomial, Poisson or exponential distribution and returns as JSON the samples, their summary statistics (as in statistics) and the theoretical mean and standard deviation. Specify seed to reproduce the same samples."),
+    ("fit_distribution", "Fits normal, exponential and uniform distributions to numeric data by maximum likelihood and returns as JSON the parameters, log-likelihood, AIC, Kolmogorov–Smirnov statistic and p-value. Fits are sorted by AIC and the best fit is returned in best. Data is passed as for statistics."),
+    ("smooth", "Smooths a numeric series and returns a series of the same length as JSON. simple is a simple moving average over the last window values, exponential is an exponential moving average (alpha or window), and rolling also returns the moving standard deviation, min, max and sum. Leading positions without a full window are null. Data is passed as for statistics."),
+    ("regression", "Fits the least-squares line y = slope * x + intercept and returns it as JSON with the correlation coefficient and the coefficient of determination. Pass the data as x and y arrays, CSV text (csv) or a CSV sent with upload_data (resource_uri); for CSV, choose the columns with x_column and y_column."),
+    ("weighted_average", "Computes the weighted average of value/weight pairs and returns it as JSON with the total weight and the share of each weight (grading, portfolio averages, etc.)."),
+    ("percentage", "Returns percentage calculations as JSON. With from and to it returns the difference and the relative change (relative_change_percent), and with values_are_percent: true also the change in percentage points (percentage_point_change). With percent and of it returns percent% of of."),
+    ("proportion", "Solves the unknown term of the proportion a / b = c / d, or scales quantities proportionally with scale_from, scale_to and quantities (e.g. a recipe for 4 people to 7 people). Returns the result as JSON."),
+    ("convert_ingredient", "Converts cooking quantities. Conversions between volume and mass (how many grams is 1 cup of flour) use the ingredient density (built-in table, [cooking.densities] in the config file, or density), and the density used and its source are returned as JSON. Densities are approximate and vary with packing and brand."),
+    ("electronics", "Returns electronics calculations as JSON. ohms_law finds the remaining values from two of voltage, current, resistance and power, series / parallel combine resistors, capacitors or inductors, and voltage_divider finds the output voltage of a divider (optionally loaded). Values can be numbers or strings with SI prefixes such as \"4.7k\" or \"100n\", and results include prefixed notation (formatted)."),
+    ("kinematics", "Solves uniformly accelerated motion: from three of initial velocity u, final velocity v, acceleration a, time t and displacement s it finds the other two, returning them as JSON with the SUVAT equations used. When a quadratic arises, all solutions with t ≥ 0 are returned."),
+    ("growth", "Returns growth calculations as JSON. cagr finds the compound annual growth rate from two values and a number of periods (compared with the simple average), inflation finds future and present values at an inflation rate, and doubling_time finds the exact doubling period together with the rule of 72 and rule of 70 estimates."),
+    ("list_functions", "Returns as JSON the functions (built-in and defined in the config file) and constants that can be used in calculate."),
+    ("convert_currency", "Converts an amount to another currency. Rates come from the config file, a rate file or a URL, and the rate used and its source (source, as_of) are returned as JSON with the converted amount."),
+    ("tax", "Calculates consumption tax / VAT. From tax-exclusive or tax-inclusive amounts (one or more line items) it finds the tax, the tax-exclusive and the tax-inclusive totals with the chosen rounding (half up, floor, ceil; per line or once on the total) and returns them as JSON."),
+    ("business_math", "Calculates the break-even point (break_even), converts between markup and margin (markup_margin) and computes the contribution margin (contribution_margin), returning labelled JSON. Markup is profit ÷ cost and margin is profit ÷ price."),
+    ("set_tool_enabled", "Enables or disables a tool at runtime and notifies connected clients that the tool list changed (administrative; exposed only when tools.admin = true in the config)."),
+    ("set_options", "Sets the defaults of calculate and calculate_batch for this session (angle unit, rounding, notation, decimal places, digit grouping, locale). Only the given items are changed and the current settings are returned as JSON (pass nothing to just check them). Values given in each request take precedence."),
+    ("load_variables", "Loads name/value pairs (JSON object, CSV or an upload_data URI) so that expressions in calculate, calculate_batch and compare in this session can refer to them. Variables with the same name are overwritten, and nothing is loaded if any pair is invalid. Returns the number loaded and all variables as JSON."),
+    ("rearrange", "Solves an equation (e.g. v = d / t) for the given variable. If the variable appears once and the inverse operations are known, the equation is rearranged symbolically (t = d / v); otherwise it is solved numerically (Newton's method, etc.) using the values in variables. Returns the rearranged equation, the value (when all variables are known) and the method as JSON."),
+    ("solve_system", "Solves a system of equations (each expression = 0) with the Newton–Raphson method (numerical Jacobian) starting from initial values of the unknowns. Returns the solution, the residual of each equation, whether it converged and the number of iterations as JSON. If it does not converge, try other initial values."),
+    ("check_constraints", "Evaluates inequality and equality constraints (such as x + y <= 10) with the given variable values and returns as JSON whether each holds, the values of both sides, the difference, the shortfall (violation) and how many held or failed. Useful for verifying optimization results and answers to word problems."),
+    ("recalculate", "Stores named formulas and, when variables change, recalculates in dependency order only the formulas that depend on them (directly or through other formulas). Add or replace formulas with formulas and change variables with variables; returns as JSON the recalculated formulas, the before/after values of formulas that changed and the current values of all formulas. Formulas can refer to variables and other formulas by name."),
+    ("reset_session", "Resets the state of this session (set_options settings, variables from load_variables and recalculate, formulas stored by recalculate, calculation history and data received by upload_data). Use it when starting a new conversation so that no state carries over. It also runs automatically when the session ends. The state is shared by the whole server rather than kept per client, so with several clients connected it resets the state of every client (the automatic reset only happens when the last session ends)."),
+    ("server_info", "Returns build information about this server as JSON (version, git commit, build time, enabled features, supported MCP protocol version). Please attach it to bug reports."),
+    ("health", "Checks the server status. Runs a self-test of the calculation engine (parsing, evaluation, whitelist) and returns the version, uptime and number of connected sessions as JSON."),
+];
+
+// 引数の英語の説明。"ツール名.引数名" を先に探し、なければ引数名だけで探す
+const PARAMETERS: &[(&str, &str)] = &[
+    // データを受け取るツールに共通の引数
+    ("values", "Array of numbers"),
+    ("csv", "CSV text passed instead of values"),
+    ("resource_uri", "URI of a CSV sent with upload_data (upload://<id>), instead of values"),
+    ("column", "CSV column (header name or 1-based column number; may be omitted for a single column)"),
+    ("decimals", "Number of decimal places to round the result to (at most 100; no rounding when omitted)"),
+    ("calculate.expression", "Expression to evaluate (e.g. \"2 + 3 * 4\", \"sqrt(25)\", \"sin(1.57)\"). Supports arithmetic (+, -, *, /), powers (^), parentheses, absolute value (|x|), square root (√x), math functions (sqrt, cbrt, root(x, n), abs, sin, cos, tan, exp, expm1, ln, ln1p, log10, log2, log(x, base), gamma, lgamma, erf, erfc, hypot(x, y), clamp(x, lo, hi), atan2(y, x), min, max, avg (2 or more arguments), lerp(a, b, t), maprange(x, in_lo, in_hi, out_lo, out_hi), round), vector and matrix literals ([[1, 2], [3, 4]] * [5, 6]; functions det, inv, transpose, trace, dot, cross, norm) and functions and constants defined in the config file"),
+    ("calculate.rounding_mode", "Rounding (half_up: round half up (default), half_even: banker's rounding, floor, ceil, truncate). Applies to rounding the result with decimals and to round()"),
+    ("calculate.sig_figs", "When true, takes the significant figures of numeric literals into account (12.0 has 3, 1200 has 2) and returns the result rounded to the correct number of digits with an explanation"),
+    ("calculate.notation", "Result notation (auto: plain when the absolute value is at least 1e-6 and less than 1e21, scientific otherwise (default); plain: always plain; scientific: always scientific)"),
+    ("calculate.precision", "Number of decimal places to display (mantissa digits in scientific notation), at most 100, padded with zeros. By default the shortest digits that reproduce the value"),
+    ("calculate.si_prefixes", "Whether to accept SI prefixes on numeric literals (4.7k = 4700, 100m = 0.1, 2.2M, 5u, etc.). Defaults to [syntax] si_prefixes in the config file (disabled by default)"),
+    ("calculate.clarify", "When true, ambiguous expressions (the precedence of 1/2x, the base of log(100), milli or meters for 100m when SI prefixes are enabled) are not evaluated by guessing; a NEEDS_CLARIFICATION error returns the expression rewritten for each interpretation. Defaults to [syntax] clarify in the config file (false)"),
+    ("calculate.strict", "When true, implicit multiplication (2x, 2(3 + 4)), ambiguous precedence (1/2x) and Unicode symbols such as × and ÷ are errors. Defaults to [syntax] strict in the config file (false); when false they are corrected and the corrections are returned in \"- 警告:\" lines"),
+    ("calculate.elementwise", "When true, numeric functions such as sqrt and abs are applied element by element to vectors and matrices (sqrt([4, 9]) is [2, 3]). Defaults to false, which is an error"),
+    ("calculate.output_bases", "Bases (2–36) in which to also show an integer result (e.g. [2, 16] also returns 0b100000000 and 0x100)"),
+    ("calculate.group_digits", "When true, groups the integer part of the result in threes (1,234,567.89) and also returns the ungrouped value in a \"- 値:\" line"),
+    ("calculate.locale", "Digit grouping and decimal mark convention for group_digits (en: 1,234,567.89 (default), ja: same as en, de: 1.234.567,89, fr: 1 234 567,89)"),
+    ("calculate.audit", "When true, returns the operations performed (add, multiply, function, ...) in evaluation order with their operands and intermediate results as a JSON array in a \"- 監査:\" line (for audit records; real-valued expressions only)"),
+    ("calculate.dry_run", "When true, parses the expression without evaluating it and returns the estimated cost as JSON (operation count operations, function call count function_calls, tree depth depth, complexity class complexity, and repetitions such as matrix powers in loops), so you can decide whether to run it under a bigger budget"),
+    ("calculate_batch.expressions", "Array of expressions to evaluate (same syntax as calculate; each is evaluated independently)"),
+    ("calculate_batch.rounding_mode", "Rounding (half_up: round half up (default), half_even: banker's rounding, floor, ceil, truncate)"),
+    ("calculate_batch.timeout_ms", "Time limit per expression in milliseconds (default and maximum: batch_timeout_ms in the config)"),
+    ("calculate_batch.output", "Output format (json (default), markdown / csv: also returns the results as a Markdown table / CSV)"),
+    ("compare.expression", "Expression to check (e.g. \"0.1 + 0.2\")"),
+    ("compare.expected", "Expected value or an expression to compare with (e.g. \"0.3\", \"3 / 10\")"),
+    ("compare.abs_tolerance", "Absolute tolerance (default: 1e-9)"),
+    ("compare.rel_tolerance", "Relative tolerance (default: 1e-9)"),
+    ("duration_calc.expression", "Duration expression (e.g. \"2:45:30 + 1:30:00\", \"7:30 * 5\", \"6h / 45m\"). Notations such as h:mm:ss, h:mm, 1h30m and 45分 are accepted"),
+    ("convert_timezone.time", "Date and time to convert (RFC3339, \"2024-01-15 09:00\", or a time of day only such as \"09:00\" / \"9am\")"),
+    ("convert_timezone.from_zone", "Time zone of time (IANA name such as Asia/Tokyo or an abbreviation such as JST or PST; ignored when time has an offset)"),
+    ("convert_timezone.to_zone", "Target time zone"),
+    ("convert_timezone.now", "Reference time (RFC3339; current time when omitted). Used for the date of time-only input and for hours_from_now"),
+    ("time_between.start", "Start date and time (same formats as time in convert_timezone)"),
+    ("time_between.start_zone", "Time zone of the start"),
+    ("time_between.end", "End date and time (reference time when omitted)"),
+    ("time_between.end_zone", "Time zone of the end (start_zone when omitted)"),
+    ("time_between.now", "Reference time (RFC3339; current time when omitted)"),
+    ("age.birthdate", "Date of birth (YYYY-MM-DD)"),
+    ("age.reference", "Reference date (YYYY-MM-DD; today when omitted)"),
+    ("age.zone", "Time zone that determines \"today\" (default: UTC)"),
+    ("humanize.value", "Number to convert to human-readable notation (e.g. 1234567)"),
+    ("humanize.text", "Notation to convert back to a number (e.g. \"1.23M\", \"123.4万\", \"1536 MiB\")"),
+    ("humanize.locale", "Unit system (en: K/M/B/T (default), ja: 万/億/兆/京)"),
+    ("humanize.bytes", "When true, treats the value as a byte count (KiB, MiB, ...; KB, MB, etc. are also accepted when parsing)"),
+    ("humanize.decimals", "Maximum number of decimal places (default: 2, at most 15)"),
+    ("sheet.cells", "Array of rows of cells. Each cell is a number, a formula starting with = (e.g. \"=A1 * B1\") or empty (\"\" or null). Formulas can refer to other cells in A1 style (columns A, B, ..., rows from 1); empty cells count as 0"),
+    ("matrix.operation", "Operation (eigen: eigenvalues and eigenvectors, lu: LU decomposition, qr: QR decomposition, cholesky: Cholesky decomposition, condition_number: condition number)"),
+    ("matrix.matrix", "Matrix as an array of rows (e.g. [[2, 1], [1, 2]]). At most 2500 elements by default"),
+    ("matrix.matrix_uri", "URI of a matrix sent with upload_data (upload://<id>), instead of matrix. The content is a JSON array of rows or one row per line of comma- or space-separated numbers"),
+    ("upload_data.upload_id", "ID of the upload to continue (omit in the first chunk)"),
+    ("upload_data.chunk", "Data to append (text)"),
+    ("upload_data.done", "true for the last chunk (default); when false more chunks are accepted"),
+    ("statistics.csv", "CSV text passed instead of values (the first line is a header unless it is numeric)"),
+    ("statistics.output", "Output format (json (default), markdown / csv: also returns the statistics as a Markdown table / CSV)"),
+    ("histogram.bins", "Number of equal-width bins (by default Sturges' rule; cannot be combined with edges)"),
+    ("histogram.edges", "Bin boundaries in ascending order (e.g. [0, 60, 80, 100])"),
+    ("histogram.chart", "When true, also returns an ASCII bar chart (chart)"),
+    ("histogram.image", "When true, also returns the bar chart as a PNG image (MCP image content), along with the ASCII bar chart (chart) for clients that cannot display images"),
+    ("histogram.width", "Image width in pixels (64 to 2000, default 640)"),
+    ("histogram.height", "Image height in pixels (64 to 2000, default 400)"),
+    ("histogram.theme", "Image color theme (light (default), dark)"),
+    ("histogram.output", "Output format (json (default), markdown / csv: also returns the frequency distribution as a Markdown table / CSV)"),
+    ("fit_distribution.distributions", "Distributions to fit (normal, exponential, uniform; all when omitted)"),
+    ("smooth.values", "Array of numbers (in time order)"),
+    ("smooth.method", "simple: simple moving average, exponential: exponential moving average, rolling: moving average, standard deviation, min, max and sum"),
+    ("smooth.window", "Number of values in the moving window (for exponential it can be given instead of alpha, with alpha = 2 / (window + 1))"),
+    ("smooth.alpha", "Smoothing factor for exponential (0 < alpha <= 1; larger values weight recent values more)"),
+    ("regression.x", "Array of explanatory values"),
+    ("regression.y", "Array of response values (same length as x)"),
+    ("regression.csv", "CSV text passed instead of x and y"),
+    ("regression.resource_uri", "URI of a CSV sent with upload_data (upload://<id>), instead of x and y"),
+    ("regression.x_column", "CSV column to use as x (header name or column number)"),
+    ("regression.y_column", "CSV column to use as y (header name or column number)"),
+    ("weighted_average.items", "Value/weight pairs (e.g. [{\"value\": 80, \"weight\": 30}, ...]). Weights must be 0 or more"),
+    ("weighted_average.normalize", "When true, divides by the total weight. When false (default), the weights must sum to 1 or 100"),
+    ("percentage.from", "Value before the change (used with to)"),
+    ("percentage.to", "Value after the change"),
+    ("percentage.values_are_percent", "true when from and to are rates (5 = 5%); the difference is also returned in percentage points"),
+    ("percentage.percent", "Percentage (%); with of, finds percent% of of"),
+    ("percentage.of", "Base value the percentage is applied to"),
+    ("proportion.a", "a in the proportion a / b = c / d (omit the unknown term)"),
+    ("proportion.b", "b in the proportion a / b = c / d (omit the unknown term)"),
+    ("proportion.c", "c in the proportion a / b = c / d (omit the unknown term)"),
+    ("proportion.d", "d in the proportion a / b = c / d (omit the unknown term)"),
+    ("proportion.scale_from", "Base of the scaling (e.g. 4 for a recipe for 4 people); given instead of a–d"),
+    ("proportion.scale_to", "Target of the scaling (e.g. 7 to make it for 7 people)"),
+    ("proportion.quantities", "Quantities relative to scale_from (e.g. {\"flour_g\": 200, \"eggs\": 2})"),
+    ("convert_ingredient.amount", "Amount to convert"),
+    ("convert_ingredient.from", "Source unit (e.g. \"cup\", \"tbsp\", \"tsp\", \"cupJP\", \"mL\", \"g\", \"oz\")"),
+    ("convert_ingredient.to", "Target unit (e.g. \"g\")"),
+    ("convert_ingredient.ingredient", "Ingredient name (e.g. \"flour\", \"sugar\", \"butter\"), used for volume/mass conversion"),
+    ("convert_ingredient.density", "Ingredient density (g/mL); takes precedence over the density of ingredient"),
+    ("convert_currency.amount", "Amount of money to convert"),
+    ("convert_currency.from", "Source currency code (e.g. \"USD\")"),
+    ("convert_currency.to", "Target currency code (e.g. \"JPY\")"),
+    ("tax.amounts", "Amounts of the line items (one is fine)"),
+    ("tax.mode", "Whether amounts exclude tax (exclusive, default) or include it (inclusive)"),
+    ("tax.rate", "Tax rate (e.g. 0.1). Defaults to rate_name or the default rate in the config"),
+    ("tax.rate_name", "Name of a tax rate defined in the config file (e.g. \"standard\", \"reduced\")"),
+    ("tax.rounding", "Rounding (half_up, half_even, floor, ceil, truncate: toward zero). Defaults to the config value (floor)"),
+    ("tax.per", "Where rounding applies (line: per line item, total: once on the total). Defaults to the config value (total)"),
+    ("tax.decimals", "Number of decimal places to round to (default: 0, whole yen; at most 100)"),
+    ("business_math.calculation", "Calculation (break_even: break-even point, markup_margin: markup/margin conversion, contribution_margin: contribution margin)"),
+    ("business_math.fixed_costs", "Fixed costs (required for break_even; for contribution_margin, give it to get the operating profit)"),
+    ("business_math.price", "Selling price per unit"),
+    ("business_math.variable_cost", "Variable cost per unit"),
+    ("business_math.quantity", "Quantity sold (contribution_margin; default 1)"),
+    ("business_math.cost", "Cost (markup_margin)"),
+    ("business_math.markup_percent", "Markup: profit ÷ cost × 100"),
+    ("business_math.margin_percent", "Margin: profit ÷ price × 100"),
+    ("electronics.calculation", "Calculation (ohms_law: V = IR and P = VI, series: series combination, parallel: parallel combination, voltage_divider: voltage divider)"),
+    ("electronics.voltage", "Voltage (V). For ohms_law give two of the four values; for voltage_divider the input voltage. Values are numbers or strings with SI prefixes (\"4.7k\", \"100n\", \"4k7\", \"3.3V\")"),
+    ("electronics.current", "Current (A; e.g. \"20mA\")"),
+    ("electronics.resistance", "Resistance (Ω; e.g. \"4.7k\", \"4R7\")"),
+    ("electronics.power", "Power (W)"),
+    ("electronics.component", "Component combined by series / parallel (resistor (default), capacitor, inductor)"),
+    ("electronics.values", "Values of the components combined by series / parallel (e.g. [\"10k\", \"4.7k\"])"),
+    ("electronics.r1", "Input-side resistor of voltage_divider"),
+    ("electronics.r2", "Ground-side resistor of voltage_divider (the output is across it)"),
+    ("electronics.load", "Load resistance connected to the voltage_divider output (in parallel with r2)"),
+    ("kinematics.u", "Initial velocity (give three of u, v, a, t, s in consistent units, e.g. m/s)"),
+    ("kinematics.v", "Final velocity"),
+    ("kinematics.a", "Acceleration (e.g. m/s^2; for gravity use a sign matching the direction, such as -9.80665)"),
+    ("kinematics.t", "Time (0 or more)"),
+    ("kinematics.s", "Displacement"),
+    ("growth.calculation", "Calculation (cagr: compound annual growth rate, inflation: inflation adjustment, doubling_time: time to double)"),
+    ("growth.start_value", "Value at the start (cagr)"),
+    ("growth.end_value", "Value at the end (cagr)"),
+    ("growth.periods", "Number of periods (cagr, inflation)"),
+    ("growth.amount", "Amount (inflation)"),
+    ("growth.rate_percent", "Rate per period (%): the inflation rate for inflation, the growth rate for doubling_time"),
+    ("generate_samples.distribution", "Distribution (uniform, normal, binomial, poisson, exponential)"),
+    ("generate_samples.count", "Number of samples (1–10000)"),
+    ("generate_samples.seed", "Random seed; the same value reproduces the same samples (by default derived from the current time and returned as seed)"),
+    ("generate_samples.min", "Lower bound (uniform; default 0)"),
+    ("generate_samples.max", "Upper bound (uniform; default 1, exclusive)"),
+    ("generate_samples.mean", "Mean (default 0 for normal; required for poisson)"),
+    ("generate_samples.std_dev", "Standard deviation (normal; default 1)"),
+    ("generate_samples.trials", "Number of trials (binomial)"),
+    ("generate_samples.probability", "Success probability 0–1 (binomial)"),
+    ("generate_samples.rate", "Events per unit time (exponential; default 1)"),
+    ("combinatorics.calculation", "Calculation (permutations: P(n, k), permutations_with_repetition: n^k, combinations: C(n, k), combinations_with_repetition: H(n, k), multinomial: multinomial coefficient, derangements: !n, stirling_first: unsigned Stirling numbers of the first kind, stirling_second: Stirling numbers of the second kind)"),
+    ("combinatorics.n", "Total number of items (except multinomial; at most 10000, or 1000 for Stirling numbers)"),
+    ("combinatorics.k", "Number chosen, or number of groups or cycles (except derangements and multinomial)"),
+    ("combinatorics.groups", "Size of each group (multinomial; e.g. [2, 2, 1])"),
+    ("number_properties.number", "Integer to examine (64-bit range)"),
+    ("set_options.angle_mode", "Angle unit for trigonometric functions (radians (default), degrees)"),
+    ("set_options.rounding_mode", "Rounding (half_up (default), half_even, floor, ceil, truncate)"),
+    ("set_options.notation", "Result notation (auto (default), plain, scientific)"),
+    ("set_options.precision", "Number of decimal places to display (at most 100; a negative value clears it)"),
+    ("set_options.group_digits", "Whether to group the integer part of results in threes"),
+    ("set_options.locale", "Digit grouping and decimal mark convention (en (default), ja, de, fr)"),
+    ("load_variables.variables", "Object of variable names and values (e.g. {\"rate\": 0.05, \"years\": 10})"),
+    ("load_variables.csv", "CSV with \"name,value\" on each line (the first line may be a header)"),
+    ("load_variables.resource_uri", "URI of a JSON object or CSV sent with upload_data (e.g. \"upload://1\")"),
+    ("load_variables.replace", "When true, removes all existing variables before loading"),
+    ("rearrange.equation", "Equation containing one = (e.g. \"v = d / t\")"),
+    ("rearrange.symbol", "Name of the variable to solve for (e.g. \"t\")"),
+    ("rearrange.variables", "Values of the other variables (e.g. {\"v\": 20, \"d\": 100}). All of them are needed when the equation cannot be solved symbolically"),
+    ("rearrange.guess", "Initial value for numerical solving (default: 1)"),
+    ("solve_system.equations", "Array of expressions set to 0 (e.g. [\"x^2 + y^2 - 4\", \"x - y\"]). An expression with = such as \"x + y = 3\" is treated as left - right = 0"),
+    ("solve_system.unknowns", "Names and initial values of the unknowns (e.g. {\"x\": 1, \"y\": 1}); as many as there are equations"),
+    ("solve_system.variables", "Values of the variables other than the unknowns"),
+    ("solve_system.tolerance", "Largest residual regarded as converged (default: 1e-10)"),
+    ("solve_system.max_iterations", "Maximum number of iterations (default: 100, at most 1000)"),
+    ("check_constraints.constraints", "Array of constraints, each with one comparison operator (<, <=, >, >=, =, !=) (e.g. [\"x + y <= 10\", \"x >= 0\"])"),
+    ("check_constraints.variables", "Values of the variables (e.g. {\"x\": 3, \"y\": 4})"),
+    ("recalculate.formulas", "Formulas to store (name and expression, e.g. {\"total\": \"price * quantity\", \"with_tax\": \"total * 1.1\"}); formulas with the same name are replaced"),
+    ("recalculate.variables", "Names and new values of the variables to change (e.g. {\"price\": 120})"),
+    ("set_tool_enabled.name", "Name of the tool to switch (e.g. \"calculate\")"),
+    ("set_tool_enabled.enabled", "true to enable, false to disable"),
+];
+
+// JSON Schema の examples に載せる値（JSON で書く）
+const EXAMPLES: &[(&str, &str)] = &[
+    (
+        "calculate.expression",
+        r#"["2 + 3 * 4", "sqrt(25)", "200 + 10%", "5 km in mi"]"#,
+    ),
+    (
+        "calculate_batch.expressions",
+        r#"[["1 + 1", "2 ^ 10", "1 / 3"]]"#,
+    ),
+    ("compare.expression", r#"["0.1 + 0.2"]"#),
+    ("compare.expected", r#"["0.3", "3 / 10"]"#),
+    (
+        "duration_calc.expression",
+        r#"["2:45:30 + 1:30:00", "7:30 * 5", "6h / 45m"]"#,
+    ),
+    ("convert_timezone.time", r#"["2024-01-15 09:00", "9am"]"#),
+    ("convert_timezone.to_zone", r#"["America/New_York", "JST"]"#),
+    ("humanize.text", r#"["1.23M", "123.4万", "1536 MiB"]"#),
+    ("sheet.cells", r#"[[[100, 2, "=A1 * B1"]]]"#),
+    ("matrix.matrix", "[[[2, 1], [1, 2]]]"),
+    ("histogram.edges", "[[0, 60, 80, 100]]"),
+    (
+        "weighted_average.items",
+        r#"[[{"value": 80, "weight": 30}, {"value": 90, "weight": 70}]]"#,
+    ),
+    ("convert_ingredient.from", r#"["cup", "tbsp"]"#),
+    ("electronics.resistance", r#"["4.7k", "4R7"]"#),
+    ("electronics.values", r#"[["10k", "4.7k"]]"#),
+    (
+        "load_variables.variables",
+        r#"[{"rate": 0.05, "years": 10}]"#,
+    ),
+    ("rearrange.equation", r#"["v = d / t"]"#),
+    ("solve_system.equations", r#"[["x^2 + y^2 - 4", "x - y"]]"#),
+    (
+        "check_constraints.constraints",
+        r#"[["x + y <= 10", "x >= 0"]]"#,
+    ),
+    (
+        "recalculate.formulas",
+        r#"[{"total": "price * quantity", "with_tax": "total * 1.1"}]"#,
+    ),
+];
+
+fn lookup<'a>(table: &'a [(&str, &str)], key: &str) -> Option<&'a str> {
+    table
+        .iter()
+        .find(|(name, _)| *name == key)
+        .map(|&(_, text)| text)
+}
+
+pub fn tool_description(tool: &str) -> Option<&'static str> {
+    lookup(TOOLS, tool)
+}
+
+pub fn parameter_description(tool: &str, parameter: &str) -> Option<&'static str> {
+    lookup(PARAMETERS, &format!("{}.{}", tool, parameter)).or_else(|| lookup(PARAMETERS, parameter))
+}
+
+pub fn examples(tool: &str, parameter: &str) -> Option<Value> {
+    lookup(EXAMPLES, &format!("{}.{}", tool, parameter))
+        .and_then(|json| serde_json::from_str(json).ok())
+}
+
+// ツールと引数の説明を language にし、引数に examples を加える
+pub fn localize(mut tool: Tool, language: Language) -> Tool {
+    let name = tool.name.to_string();
+    if let Some(en) = tool_description(&name) {
+        tool.description = language.combine(&tool.description, en).into();
+    }
+    let mut schema = (*tool.input_schema).clone();
+    if let Some(Value::Object(properties)) = schema.get_mut("properties") {
+        for (parameter, property) in properties.iter_mut() {
+            let Value::Object(property) = property else {
+                continue;
+            };
+            if let (Some(Value::String(ja)), Some(en)) = (
+                property.get("description"),
+                parameter_description(&name, parameter),
+            ) {
+                let description = language.combine(ja, en);
+                property.insert("description".to_string(), Value::String(description));
+            }
+            if let Some(examples) = examples(&name, parameter) {
+                property.insert("examples".to_string(), examples);
+            }
+        }
+    }
+    tool.input_schema = Arc::new(schema);
+    tool
+}
@@ -0,0 +1,67 @@
+#[cfg(test)]
+mod tests {
+    use crate::config::Config;
+    use crate::i18n::{self, Language};
+    use crate::server::CalculatorService;
+    use serde_json::Value;
+
+    fn properties(tool: &rmcp::model::Tool) -> serde_json::Map<String, Value> {
+        match tool.input_schema.get("properties") {
+            Some(Value::Object(properties)) => properties.clone(),
+            _ => serde_json::Map::new(),
+        }
+    }
+
+    #[test]
+    fn test_every_tool_and_parameter_has_english_description() {
+        let service = CalculatorService::new(Config::parse("tools.admin = true").unwrap());
+        for tool in service.enabled_tools() {
+            assert!(
+                i18n::tool_description(&tool.name).is_some(),
+                "{}",
+                tool.name
+            );
+            for (parameter, property) in properties(&tool) {
+                if property.get("description").is_some() {
+                    assert!(
+                        i18n::parameter_description(&tool.name, &parameter).is_some(),
+                        "{}.{}",
+                        tool.name,
+                        parameter
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_language_selection() {
+        assert_eq!(Language::from_locale(Some("ja_JP.UTF-8")), Language::Ja);
+        assert_eq!(Language::from_locale(Some("en_US.UTF-8")), Language::En);
+        assert_eq!(Language::from_locale(Some("C")), Language::Both);
+        assert_eq!(Language::from_locale(None), Language::Both);
+
+        let config =
+            Config::parse("tools.enabled = [\"calculate\"]\ntools.language = \"en\"").unwrap();
+        assert_eq!(config.tools.language(), Language::En);
+        let service = CalculatorService::new(config);
+        let tool = &service.localized_tools()[0];
+        assert!(tool.description.starts_with("Evaluates a math expression"));
+        let expression = &properties(tool)["expression"];
+        assert!(expression["description"]
+            .as_str()
+            .unwrap()
+            .starts_with("Expression to evaluate"));
+        assert!(expression["examples"]
+            .as_array()
+            .is_some_and(|examples| !examples.is_empty()));
+
+        // both は英語の後に日本語
+        let original = service.enabled_tools().remove(0);
+        let both = i18n::localize(original.clone(), Language::Both);
+        assert!(both.description.starts_with("Evaluates"));
+        assert!(both.description.ends_with(original.description.as_ref()));
+        let ja = i18n::localize(original.clone(), Language::Ja);
+        assert_eq!(ja.description, original.description);
+    }
+}
@@ -0,0 +1,336 @@
+use serde::{Deserialize, Serialize};
+
+// 画像の幅と高さ（ピクセル）
+pub const DEFAULT_WIDTH: u32 = 640;
+pub const DEFAULT_HEIGHT: u32 = 400;
+pub const MIN_DIMENSION: u32 = 64;
+pub const MAX_DIMENSION: u32 = 2000;
+
+pub type Color = [u8; 3];
+
+// グラフの配色
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "server", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum Theme {
+    #[default]
+    Light,
+    Dark,
+}
+
+impl Theme {
+    fn background(self) -> Color {
+        match self {
+            Theme::Light => [255, 255, 255],
+            Theme::Dark => [30, 30, 30],
+        }
+    }
+
+    fn foreground(self) -> Color {
+        match self {
+            Theme::Light => [51, 51, 51],
+            Theme::Dark => [210, 210, 210],
+        }
+    }
+
+    fn accent(self) -> Color {
+        match self {
+            Theme::Light => [66, 133, 244],
+            Theme::Dark => [110, 170, 255],
+        }
+    }
+}
+
+pub fn check_dimensions(width: u32, height: u32) -> Result<(), String> {
+    for (name, value) in [("width", width), ("height", height)] {
+        if !(MIN_DIMENSION..=MAX_DIMENSION).contains(&value) {
+            return Err(format!(
+                "{} は {} 以上 {} 以下で指定してください: {}",
+                name, MIN_DIMENSION, MAX_DIMENSION, value
+            ));
+        }
+    }
+    Ok(())
+}
+
+// RGB の画像
+#[derive(Debug, Clone, PartialEq)]
+pub struct Canvas {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    pub fn new(width: u32, height: u32, color: Color) -> Self {
+        Self {
+            width,
+            height,
+            pixels: color.repeat((width * height) as usize),
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> Color {
+        let offset = ((y * self.width + x) * 3) as usize;
+        [
+            self.pixels[offset],
+            self.pixels[offset + 1],
+            self.pixels[offset + 2],
+        ]
+    }
+
+    // 画像からはみ出す部分は描かない
+    pub fn fill_rect(&mut self, x: u32, y: u32, width: u32, height: u32, color: Color) {
+        let right = x.saturating_add(width).min(self.width);
+        let bottom = y.saturating_add(height).min(self.height);
+        for row in y..bottom {
+            for column in x..right {
+                let offset = ((row * self.width + column) * 3) as usize;
+                self.pixels[offset..offset + 3].copy_from_slice(&color);
+            }
+        }
+    }
+
+    // 8ビットの RGB（カラータイプ 2）の PNG
+    pub fn to_png(&self) -> Vec<u8> {
+        let stride = self.width as usize * 3;
+        let mut raw = Vec::with_capacity((stride + 1) * self.height as usize);
+        for row in self.pixels.chunks(stride) {
+            // 各行のフィルタは None
+            raw.push(0);
+            raw.extend_from_slice(row);
+        }
+
+        let mut header = Vec::with_capacity(13);
+        header.extend_from_slice(&self.width.to_be_bytes());
+        header.extend_from_slice(&self.height.to_be_bytes());
+        header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+        let mut png = vec![0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+        chunk(&mut png, b"IHDR", &header);
+        chunk(&mut png, b"IDAT", &zlib(&raw, stride + 1));
+        chunk(&mut png, b"IEND", &[]);
+        png
+    }
+}
+
+// 度数の棒グラフ。棒の高さは最大の度数を基準にし、下と左に軸を描く
+pub fn bar_chart(counts: &[usize], width: u32, height: u32, theme: Theme) -> Canvas {
+    let mut canvas = Canvas::new(width, height, theme.background());
+    let margin = (width.min(height) / 10).max(4);
+    let (left, top) = (margin, margin);
+    let plot_width = width - 2 * margin;
+    let plot_height = height - 2 * margin;
+    let max_count = counts.iter().copied().max().unwrap_or(0).max(1);
+
+    let bins = counts.len().max(1) as u64;
+    for (index, &count) in counts.iter().enumerate() {
+        let start = (plot_width as u64 * index as u64 / bins) as u32;
+        let end = (plot_width as u64 * (index as u64 + 1) / bins) as u32;
+        // 棒が2ピクセル以上あれば、隣の棒との間に1ピクセルの隙間を空ける
+        let bar_width = if end - start >= 2 {
+            end - start - 1
+        } else {
+            end - start
+        };
+        let bar_height = (plot_height as u64 * count as u64 / max_count as u64) as u32;
+        canvas.fill_rect(
+            left + start,
+            top + plot_height - bar_height,
+            bar_width,
+            bar_height,
+            theme.accent(),
+        );
+    }
+    canvas.fill_rect(left - 1, top, 1, plot_height + 1, theme.foreground());
+    canvas.fill_rect(
+        left - 1,
+        top + plot_height,
+        plot_width + 1,
+        1,
+        theme.foreground(),
+    );
+    canvas
+}
+
+pub fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for group in bytes.chunks(3) {
+        let value = group.iter().enumerate().fold(0u32, |value, (i, &byte)| {
+            value | (byte as u32) << (16 - 8 * i)
+        });
+        for i in 0..4 {
+            if i <= group.len() {
+                encoded.push(ALPHABET[(value >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+fn chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in bytes {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+// zlib 形式（固定ハフマン符号の deflate）。グラフは同じ色が続くため、
+// 直前のピクセル（距離 3）と1つ上の行（距離 row）との一致だけを探す
+fn zlib(data: &[u8], row: usize) -> Vec<u8> {
+    let mut bits = BitWriter::default();
+    // BFINAL = 1, BTYPE = 01（固定ハフマン符号）
+    bits.write(1, 1);
+    bits.write(1, 2);
+    let mut position = 0;
+    while position < data.len() {
+        let (length, distance) = [3, row]
+            .into_iter()
+            .filter(|&distance| distance <= position && distance <= 32768)
+            .map(|distance| (match_length(data, position, distance), distance))
+            .max_by_key(|&(length, _)| length)
+            .unwrap_or((0, 0));
+        if length >= 3 {
+            bits.length(length);
+            bits.distance(distance);
+            position += length;
+        } else {
+            bits.literal(data[position] as usize);
+            position += 1;
+        }
+    }
+    bits.literal(256);
+
+    let mut zlib = vec![0x78, 0x01];
+    zlib.extend(bits.finish());
+    zlib.extend_from_slice(&adler32(data).to_be_bytes());
+    zlib
+}
+
+fn match_length(data: &[u8], position: usize, distance: usize) -> usize {
+    (0..258.min(data.len() - position))
+        .take_while(|&i| data[position + i] == data[position + i - distance])
+        .count()
+}
+
+const LENGTH_BASES: [usize; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA_BITS: [u32; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASES: [usize; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA_BITS: [u32; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    count: u32,
+}
+
+impl BitWriter {
+    // 下位ビットから順に書く
+    fn write(&mut self, value: u32, count: u32) {
+        for i in 0..count {
+            self.buffer |= ((value >> i) & 1) << self.count;
+            self.count += 1;
+            if self.count == 8 {
+                self.bytes.push(self.buffer as u8);
+                self.buffer = 0;
+                self.count = 0;
+            }
+        }
+    }
+
+    // ハフマン符号は上位ビットから順に書く
+    fn code(&mut self, code: u32, count: u32) {
+        self.write(code.reverse_bits() >> (32 - count), count);
+    }
+
+    // 固定ハフマン符号のリテラル・長さの符号（0〜287）
+    fn literal(&mut self, symbol: usize) {
+        let symbol = symbol as u32;
+        match symbol {
+            0..=143 => self.code(0x30 + symbol, 8),
+            144..=255 => self.code(0x190 + symbol - 144, 9),
+            256..=279 => self.code(symbol - 256, 7),
+            _ => self.code(0xc0 + symbol - 280, 8),
+        }
+    }
+
+    fn length(&mut self, length: usize) {
+        let index = LENGTH_BASES
+            .iter()
+            .rposition(|&base| base <= length)
+            .unwrap();
+        self.literal(257 + index);
+        self.write(
+            (length - LENGTH_BASES[index]) as u32,
+            LENGTH_EXTRA_BITS[index],
+        );
+    }
+
+    fn distance(&mut self, distance: usize) {
+        let index = DISTANCE_BASES
+            .iter()
+            .rposition(|&base| base <= distance)
+            .unwrap();
+        self.code(index as u32, 5);
+        self.write(
+            (distance - DISTANCE_BASES[index]) as u32,
+            DISTANCE_EXTRA_BITS[index],
+        );
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
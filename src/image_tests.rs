@@ -0,0 +1,47 @@
+#[cfg(test)]
+mod tests {
+    use crate::image::{bar_chart, base64, check_dimensions, Canvas, Theme};
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+        assert_eq!(base64(&[0xff, 0xfe]), "//4=");
+    }
+
+    #[test]
+    fn test_png_header() {
+        let png = Canvas::new(100, 80, [255, 0, 0]).to_png();
+
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!(&png[16..20], &100u32.to_be_bytes());
+        assert_eq!(&png[20..24], &80u32.to_be_bytes());
+        // IHDR の CRC
+        assert_eq!(&png[29..33], &[0x60, 0x6b, 0x47, 0xb8]);
+        assert!(png.ends_with(b"IEND\xae\x42\x60\x82"));
+        // 同じ色が続くため、生のデータ（約24KB）よりずっと小さい
+        assert!(png.len() < 1000);
+    }
+
+    #[test]
+    fn test_bar_chart() {
+        let canvas = bar_chart(&[1, 4, 2], 200, 100, Theme::Light);
+        assert_eq!((canvas.width(), canvas.height()), (200, 100));
+        // 左上は背景、最も高い棒の中央は棒の色
+        assert_eq!(canvas.pixel(0, 0), [255, 255, 255]);
+        assert_eq!(canvas.pixel(100, 15), [66, 133, 244]);
+        assert_eq!(canvas.pixel(40, 15), [255, 255, 255]);
+        assert_eq!(canvas.pixel(40, 85), [66, 133, 244]);
+
+        let dark = bar_chart(&[0, 0], 64, 64, Theme::Dark);
+        assert_eq!(dark.pixel(0, 0), [30, 30, 30]);
+
+        assert!(check_dimensions(640, 400).is_ok());
+        assert!(check_dimensions(10, 400).is_err());
+        assert!(check_dimensions(640, 5000).is_err());
+    }
+}
@@ -0,0 +1,190 @@
+use serde::Serialize;
+
+// 等加速度運動の式（SUVAT）。それぞれ u, v, a, t, s のうち1つを含まない
+pub const VELOCITY_TIME: &str = "v = u + a·t";
+pub const AVERAGE_VELOCITY: &str = "s = (u + v)·t / 2";
+pub const DISPLACEMENT_FROM_START: &str = "s = u·t + a·t²/2";
+pub const VELOCITY_DISPLACEMENT: &str = "v² = u² + 2·a·s";
+pub const DISPLACEMENT_FROM_END: &str = "s = v·t - a·t²/2";
+
+const NAMES: [&str; 5] = ["u", "v", "a", "t", "s"];
+
+// 初速度 u、終速度 v、加速度 a、時間 t、変位 s（単位は揃っていればよい）
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Motion {
+    pub u: f64,
+    pub v: f64,
+    pub a: f64,
+    pub t: f64,
+    pub s: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Kinematics {
+    pub given: Vec<&'static str>,
+    pub solved: Vec<&'static str>,
+    // 使った式（求めた値の順）
+    pub equations: Vec<&'static str>,
+    // 2次方程式になる場合は t ≥ 0 の解がすべて入る
+    pub solutions: Vec<Motion>,
+}
+
+fn nonzero(value: f64, name: &str) -> Result<f64, String> {
+    if value == 0.0 {
+        Err(format!("{} が 0 のため、残りの値が1つに決まりません", name))
+    } else {
+        Ok(value)
+    }
+}
+
+// v² = u² + 2as から求めた速度の2乗の、正と負の平方根（0 なら1つ）
+fn roots(square: f64) -> Result<Vec<f64>, String> {
+    if square < 0.0 {
+        return Err(format!(
+            "速度の2乗が負になる（{}）ため、この組み合わせの運動はありません",
+            square
+        ));
+    }
+    let root = square.sqrt();
+    Ok(if root == 0.0 {
+        vec![0.0]
+    } else {
+        vec![root, -root]
+    })
+}
+
+// u, v, a, t, s のうち3つから残りの2つを求める
+pub fn solve(
+    u: Option<f64>,
+    v: Option<f64>,
+    a: Option<f64>,
+    t: Option<f64>,
+    s: Option<f64>,
+) -> Result<Kinematics, String> {
+    let values = [u, v, a, t, s];
+    if let Some(value) = values.iter().flatten().find(|value| !value.is_finite()) {
+        return Err(format!("値が無効です: {}", value));
+    }
+    let given: Vec<&'static str> = NAMES
+        .iter()
+        .zip(&values)
+        .filter(|(_, value)| value.is_some())
+        .map(|(name, _)| *name)
+        .collect();
+    if given.len() != 3 {
+        return Err(format!(
+            "u, v, a, t, s のうち3つを指定してください（{} 個指定されています）",
+            given.len()
+        ));
+    }
+    if t.is_some_and(|t| t < 0.0) {
+        return Err("t は 0 以上にしてください".to_string());
+    }
+    let motion = |u, v, a, t, s| Motion { u, v, a, t, s };
+    let (equations, solutions) = match values {
+        [Some(u), None, Some(a), Some(t), None] => (
+            vec![VELOCITY_TIME, DISPLACEMENT_FROM_START],
+            vec![motion(u, u + a * t, a, t, u * t + a * t * t / 2.0)],
+        ),
+        [Some(u), Some(v), None, Some(t), None] => {
+            let a = (v - u) / nonzero(t, "t")?;
+            (
+                vec![VELOCITY_TIME, AVERAGE_VELOCITY],
+                vec![motion(u, v, a, t, (u + v) * t / 2.0)],
+            )
+        }
+        [Some(u), Some(v), Some(a), None, None] => {
+            let t = (v - u) / nonzero(a, "a")?;
+            (
+                vec![VELOCITY_TIME, VELOCITY_DISPLACEMENT],
+                vec![motion(u, v, a, t, (v * v - u * u) / (2.0 * a))],
+            )
+        }
+        [Some(u), Some(v), None, None, Some(s)] => {
+            let t = 2.0 * s / nonzero(u + v, "u + v")?;
+            let a = (v * v - u * u) / (2.0 * nonzero(s, "s")?);
+            (
+                vec![AVERAGE_VELOCITY, VELOCITY_DISPLACEMENT],
+                vec![motion(u, v, a, t, s)],
+            )
+        }
+        [Some(u), None, Some(a), None, Some(s)] => {
+            let solutions = if a == 0.0 {
+                vec![motion(u, u, a, s / nonzero(u, "u")?, s)]
+            } else {
+                roots(u * u + 2.0 * a * s)?
+                    .into_iter()
+                    .map(|v| motion(u, v, a, (v - u) / a, s))
+                    .collect()
+            };
+            (vec![VELOCITY_DISPLACEMENT, VELOCITY_TIME], solutions)
+        }
+        [Some(u), None, None, Some(t), Some(s)] => {
+            let t = nonzero(t, "t")?;
+            (
+                vec![AVERAGE_VELOCITY, DISPLACEMENT_FROM_START],
+                vec![motion(
+                    u,
+                    2.0 * s / t - u,
+                    2.0 * (s - u * t) / (t * t),
+                    t,
+                    s,
+                )],
+            )
+        }
+        [None, Some(v), Some(a), Some(t), None] => (
+            vec![VELOCITY_TIME, DISPLACEMENT_FROM_END],
+            vec![motion(v - a * t, v, a, t, v * t - a * t * t / 2.0)],
+        ),
+        [None, Some(v), Some(a), None, Some(s)] => {
+            let solutions = if a == 0.0 {
+                vec![motion(v, v, a, s / nonzero(v, "v")?, s)]
+            } else {
+                roots(v * v - 2.0 * a * s)?
+                    .into_iter()
+                    .map(|u| motion(u, v, a, (v - u) / a, s))
+                    .collect()
+            };
+            (vec![VELOCITY_DISPLACEMENT, VELOCITY_TIME], solutions)
+        }
+        [None, Some(v), None, Some(t), Some(s)] => {
+            let t = nonzero(t, "t")?;
+            (
+                vec![AVERAGE_VELOCITY, DISPLACEMENT_FROM_END],
+                vec![motion(
+                    2.0 * s / t - v,
+                    v,
+                    2.0 * (v * t - s) / (t * t),
+                    t,
+                    s,
+                )],
+            )
+        }
+        [None, None, Some(a), Some(t), Some(s)] => {
+            let u = (s - a * t * t / 2.0) / nonzero(t, "t")?;
+            (
+                vec![DISPLACEMENT_FROM_START, VELOCITY_TIME],
+                vec![motion(u, u + a * t, a, t, s)],
+            )
+        }
+        _ => unreachable!("指定された値は3つ"),
+    };
+    // 時間が負になる解（運動を逆にたどったもの）は除く
+    let solutions: Vec<Motion> = solutions
+        .into_iter()
+        .filter(|motion| motion.t >= 0.0 && motion.t.is_finite())
+        .collect();
+    if solutions.is_empty() {
+        return Err("t ≥ 0 の解がありません（値の符号を確認してください）".to_string());
+    }
+    Ok(Kinematics {
+        solved: NAMES
+            .iter()
+            .filter(|name| !given.contains(name))
+            .copied()
+            .collect(),
+        given,
+        equations,
+        solutions,
+    })
+}
@@ -0,0 +1,63 @@
+#[cfg(test)]
+mod tests {
+    use crate::kinematics::{solve, AVERAGE_VELOCITY, VELOCITY_DISPLACEMENT, VELOCITY_TIME};
+
+    #[test]
+    fn test_solve_each_subset() {
+        // u = 2, v = 8, a = 3, t = 2, s = 10 はすべての式を満たす
+        let expected = [2.0, 8.0, 3.0, 2.0, 10.0];
+        for mask in 0..32u32 {
+            if mask.count_ones() != 3 {
+                continue;
+            }
+            let pick = |index: usize| (mask & (1 << index) != 0).then_some(expected[index]);
+            let result = solve(pick(0), pick(1), pick(2), pick(3), pick(4)).unwrap();
+            assert_eq!(result.given.len(), 3);
+            assert_eq!(result.solved.len(), 2);
+            let motion = result
+                .solutions
+                .iter()
+                .find(|motion| (motion.v - 8.0).abs() < 1e-9 && (motion.u - 2.0).abs() < 1e-9)
+                .unwrap_or_else(|| panic!("{:b}: {:?}", mask, result.solutions));
+            for (value, expected) in [motion.a, motion.t, motion.s].iter().zip(&expected[2..]) {
+                assert!((value - expected).abs() < 1e-9, "{:b}: {:?}", mask, motion);
+            }
+        }
+    }
+
+    #[test]
+    fn test_equations_used() {
+        let result = solve(Some(0.0), None, Some(2.0), Some(3.0), None).unwrap();
+        assert_eq!(result.solved, vec!["v", "s"]);
+        assert_eq!(result.solutions[0].v, 6.0);
+        assert_eq!(result.solutions[0].s, 9.0);
+        assert_eq!(result.equations[0], VELOCITY_TIME);
+
+        let result = solve(Some(4.0), Some(8.0), None, None, Some(12.0)).unwrap();
+        assert_eq!(
+            result.equations,
+            vec![AVERAGE_VELOCITY, VELOCITY_DISPLACEMENT]
+        );
+    }
+
+    #[test]
+    fn test_two_solutions() {
+        // 10 m/s で投げ上げて 3.2 m の高さを通るのは上昇中と下降中の2回
+        let result = solve(Some(10.0), None, Some(-10.0), None, Some(3.2)).unwrap();
+        assert_eq!(result.solutions.len(), 2);
+        assert!(result.solutions[0].t < result.solutions[1].t);
+        assert!(result.solutions[0].v > 0.0 && result.solutions[1].v < 0.0);
+        // 届かない高さ
+        assert!(solve(Some(10.0), None, Some(-10.0), None, Some(6.0)).is_err());
+    }
+
+    #[test]
+    fn test_invalid_inputs() {
+        assert!(solve(Some(1.0), Some(2.0), None, None, None).is_err());
+        assert!(solve(Some(1.0), Some(2.0), Some(1.0), Some(1.0), None).is_err());
+        assert!(solve(Some(1.0), Some(2.0), None, Some(-1.0), None).is_err());
+        assert!(solve(Some(1.0), Some(1.0), Some(0.0), None, None).is_err());
+        // 減速中の物体が速くなることはない
+        assert!(solve(Some(10.0), Some(20.0), Some(-1.0), None, None).is_err());
+    }
+}
@@ -0,0 +1,158 @@
+#[cfg(feature = "server")]
+pub mod admission;
+#[cfg(all(test, feature = "server"))]
+mod admission_tests;
+pub mod average;
+#[cfg(test)]
+mod average_tests;
+#[cfg(feature = "bignum")]
+pub mod bigint;
+#[cfg(all(test, feature = "bignum"))]
+mod bigint_tests;
+pub mod build_info;
+#[cfg(all(test, feature = "server"))]
+mod build_info_tests;
+pub mod business;
+#[cfg(test)]
+mod business_tests;
+pub mod calculator;
+#[cfg(all(test, feature = "server"))]
+mod calculator_tests;
+#[cfg(feature = "bignum")]
+pub mod combinatorics;
+#[cfg(all(test, feature = "bignum"))]
+mod combinatorics_tests;
+pub mod compare;
+#[cfg(test)]
+mod compare_tests;
+#[cfg(feature = "server")]
+pub mod config;
+#[cfg(all(test, feature = "server"))]
+mod config_tests;
+pub mod constraints;
+#[cfg(test)]
+mod constraints_tests;
+#[cfg(feature = "units")]
+pub mod cooking;
+#[cfg(all(test, feature = "units"))]
+mod cooking_tests;
+#[cfg(feature = "finance")]
+pub mod currency;
+#[cfg(all(test, feature = "finance"))]
+mod currency_tests;
+pub mod dataset;
+#[cfg(test)]
+mod dataset_tests;
+#[cfg(feature = "datetime")]
+pub mod datetime;
+#[cfg(all(test, feature = "datetime"))]
+mod datetime_tests;
+pub mod duration;
+#[cfg(test)]
+mod duration_tests;
+pub mod electronics;
+#[cfg(test)]
+mod electronics_tests;
+pub mod exact;
+#[cfg(test)]
+mod exact_tests;
+#[cfg(feature = "server")]
+pub mod formulas;
+#[cfg(all(test, feature = "server"))]
+mod formulas_tests;
+pub mod functions;
+pub mod growth;
+#[cfg(test)]
+mod growth_tests;
+pub mod health;
+#[cfg(all(test, feature = "server"))]
+mod health_tests;
+#[cfg(feature = "server")]
+pub mod history;
+#[cfg(all(test, feature = "server"))]
+mod history_tests;
+pub mod humanize;
+#[cfg(test)]
+mod humanize_tests;
+#[cfg(feature = "server")]
+pub mod i18n;
+#[cfg(all(test, feature = "server"))]
+mod i18n_tests;
+pub mod image;
+#[cfg(test)]
+mod image_tests;
+pub mod kinematics;
+#[cfg(test)]
+mod kinematics_tests;
+pub mod markdown;
+#[cfg(test)]
+mod markdown_tests;
+pub mod matrix;
+#[cfg(test)]
+mod matrix_tests;
+pub mod number_format;
+#[cfg(test)]
+mod number_format_tests;
+pub mod number_theory;
+#[cfg(test)]
+mod number_theory_tests;
+pub mod percentage;
+#[cfg(test)]
+mod percentage_tests;
+pub mod proportion;
+#[cfg(test)]
+mod proportion_tests;
+pub mod rng;
+#[cfg(test)]
+mod rng_tests;
+pub mod rounding;
+#[cfg(test)]
+mod rounding_tests;
+#[cfg(feature = "stats")]
+pub mod sampling;
+#[cfg(all(test, feature = "stats"))]
+mod sampling_tests;
+#[cfg(feature = "server")]
+pub mod server;
+#[cfg(feature = "server")]
+pub mod sheet;
+#[cfg(all(test, feature = "server"))]
+mod sheet_tests;
+pub mod sigfig;
+#[cfg(test)]
+mod sigfig_tests;
+#[cfg(feature = "stats")]
+pub mod smoothing;
+#[cfg(all(test, feature = "stats"))]
+mod smoothing_tests;
+#[cfg(feature = "symbolic")]
+pub mod solver;
+#[cfg(all(test, feature = "symbolic"))]
+mod solver_tests;
+#[cfg(feature = "stats")]
+pub mod statistics;
+#[cfg(all(test, feature = "stats"))]
+mod statistics_tests;
+pub mod stress;
+#[cfg(test)]
+mod stress_tests;
+#[cfg(feature = "server")]
+pub mod tax;
+#[cfg(all(test, feature = "server"))]
+mod tax_tests;
+#[cfg(feature = "server")]
+pub mod tool_error;
+#[cfg(all(test, feature = "server"))]
+mod tool_error_tests;
+#[cfg(feature = "server")]
+pub mod uploads;
+#[cfg(all(test, feature = "server"))]
+mod uploads_tests;
+pub mod units;
+#[cfg(all(test, feature = "units"))]
+mod units_tests;
+pub mod value;
+#[cfg(test)]
+mod value_tests;
+#[cfg(feature = "wasm")]
+pub mod wasm;
@@ -1,14 +1,60 @@
 use anyhow::Result;
+use calc_mcp::build_info;
+use calc_mcp::config::Config;
+use calc_mcp::server::CalculatorService;
 use rmcp::{transport::stdio, ServiceExt};
+use std::process::ExitCode;
 
-mod calculator;
+mod batch;
 #[cfg(test)]
-mod calculator_tests;
-use calculator::CalculatorService;
+mod batch_tests;
+mod cli;
+#[cfg(test)]
+mod cli_tests;
+mod reload;
+#[cfg(test)]
+mod reload_tests;
+mod repl;
+#[cfg(test)]
+mod repl_tests;
 
 #[tokio::main]
-async fn main() -> Result<()> {
-    let service = CalculatorService.serve(stdio()).await?;
+async fn main() -> Result<ExitCode> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        None | Some("serve") => {
+            serve().await?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Some("eval") => Ok(cli::eval(&args[1..])),
+        Some("repl") => repl::run(),
+        Some("run") => batch::run(&args[1..]),
+        Some("stress-test") => Ok(cli::stress_test(&args[1..])),
+        Some("-V" | "--version" | "version") => {
+            println!("{}", build_info::summary());
+            Ok(ExitCode::SUCCESS)
+        }
+        Some("-h" | "--help" | "help") => {
+            println!("{}", cli::USAGE);
+            Ok(ExitCode::SUCCESS)
+        }
+        Some(other) => Ok(cli::usage_error(&format!("未知のサブコマンド: {}", other))),
+    }
+}
+
+async fn serve() -> Result<()> {
+    let config_path = Config::locate();
+    let config = Config::load(config_path.as_deref())?;
+    config.validate(&CalculatorService::tool_names())?;
+
+    let service = CalculatorService::new(config);
+    if let Some(path) = config_path {
+        reload::spawn(service.clone(), path);
+    }
+
+    let handle = service.clone();
+    let service = service.serve(stdio()).await?;
     service.waiting().await?;
+    handle.end_session();
     Ok(())
 }
@@ -0,0 +1,84 @@
+use crate::dataset::csv_field;
+use serde::{Deserialize, Serialize};
+
+// 表を返すツールの出力形式。markdown ではそのまま表示できる Markdown の表を、
+// csv では表計算ソフトに貼り付けられる CSV を JSON に加えて返す
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "server", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum OutputFormat {
+    #[default]
+    Json,
+    Markdown,
+    Csv,
+}
+
+impl OutputFormat {
+    // 表を加える JSON の項目の名前（json では加えない）
+    pub fn field(self) -> Option<&'static str> {
+        match self {
+            OutputFormat::Json => None,
+            OutputFormat::Markdown => Some("markdown"),
+            OutputFormat::Csv => Some("csv"),
+        }
+    }
+}
+
+// 見出しつきの表。Markdown（GitHub Flavored Markdown）か CSV に書き出す
+#[derive(Debug, Clone, PartialEq)]
+pub struct Table {
+    header: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new(header: &[&str]) -> Self {
+        Self {
+            header: header.iter().map(|name| name.to_string()).collect(),
+            rows: Vec::new(),
+        }
+    }
+
+    // 列が足りない行は空のセルで埋め、多い列は捨てる
+    pub fn push(&mut self, mut row: Vec<String>) {
+        row.resize(self.header.len(), String::new());
+        self.rows.push(row);
+    }
+
+    pub fn render(&self, format: OutputFormat) -> Option<String> {
+        match format {
+            OutputFormat::Json => None,
+            OutputFormat::Markdown => Some(self.to_markdown()),
+            OutputFormat::Csv => Some(self.to_csv()),
+        }
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let line = |cells: &[String]| {
+            let cells: Vec<String> = cells.iter().map(|text| cell(text)).collect();
+            format!("| {} |", cells.join(" | "))
+        };
+        let mut lines = vec![
+            line(&self.header),
+            format!("|{}", " --- |".repeat(self.header.len())),
+        ];
+        lines.extend(self.rows.iter().map(|row| line(row)));
+        lines.join("\n")
+    }
+
+    // 見出し行つき。各行は改行で終わる
+    pub fn to_csv(&self) -> String {
+        std::iter::once(&self.header)
+            .chain(&self.rows)
+            .map(|row| {
+                let fields: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+                format!("{}\n", fields.join(","))
+            })
+            .collect()
+    }
+}
+
+// 表が崩れないよう、セルの中の | と改行を置き換える
+fn cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', "<br>")
+}
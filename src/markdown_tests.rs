@@ -0,0 +1,41 @@
+#[cfg(test)]
+mod tests {
+    use crate::markdown::{OutputFormat, Table};
+
+    #[test]
+    fn test_render_table() {
+        let mut table = Table::new(&["x", "y"]);
+        table.push(vec!["1".to_string(), "2".to_string()]);
+        table.push(vec!["3".to_string()]);
+
+        assert_eq!(
+            table.to_markdown(),
+            "| x | y |\n| --- | --- |\n| 1 | 2 |\n| 3 |  |"
+        );
+    }
+
+    #[test]
+    fn test_escape_cells() {
+        let mut table = Table::new(&["expression"]);
+        table.push(vec!["|x|\n+ 1".to_string()]);
+
+        assert_eq!(
+            table.to_markdown(),
+            "| expression |\n| --- |\n| \\|x\\|<br>+ 1 |"
+        );
+    }
+
+    #[test]
+    fn test_csv() {
+        let mut table = Table::new(&["index", "expression"]);
+        table.push(vec!["0".to_string(), "max(1, 2)".to_string()]);
+        table.push(vec!["1".to_string(), "\"a\"".to_string()]);
+
+        assert_eq!(
+            table.to_csv(),
+            "index,expression\n0,\"max(1, 2)\"\n1,\"\"\"a\"\"\"\n"
+        );
+        assert_eq!(table.render(OutputFormat::Json), None);
+        assert_eq!(OutputFormat::Csv.field(), Some("csv"));
+    }
+}
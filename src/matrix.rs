@@ -0,0 +1,564 @@
+use serde::{Deserialize, Serialize};
+
+// 行列は行の配列で表す（[[1, 2], [3, 4]]）
+pub type Matrix = Vec<Vec<f64>>;
+
+#[cfg(feature = "matrix")]
+const MAX_QR_ITERATIONS: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "server", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum MatrixOperation {
+    // 固有値・固有ベクトル（QR法）
+    Eigen,
+    // PA = LU（部分ピボット選択）
+    Lu,
+    // A = QR（ハウスホルダー変換）
+    Qr,
+    // A = LLᵀ（対称正定値行列のみ）
+    Cholesky,
+    // 条件数
+    ConditionNumber,
+}
+
+#[cfg(feature = "matrix")]
+#[derive(Debug, Serialize)]
+pub struct Eigenpair {
+    pub value: f64,
+    // 複素数の固有値の虚部（実数の固有値では 0）
+    pub imaginary: f64,
+    // 長さ 1 の固有ベクトル（複素数の固有値では None）
+    pub vector: Option<Vec<f64>>,
+}
+
+#[cfg(feature = "matrix")]
+#[derive(Debug, Serialize)]
+pub struct Eigen {
+    pub symmetric: bool,
+    pub eigenpairs: Vec<Eigenpair>,
+    pub iterations: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Lu {
+    pub l: Matrix,
+    pub u: Matrix,
+    // 置換行列（PA = LU）
+    pub p: Matrix,
+    pub determinant: f64,
+}
+
+#[cfg(feature = "matrix")]
+#[derive(Debug, Serialize)]
+pub struct Qr {
+    pub q: Matrix,
+    pub r: Matrix,
+}
+
+#[cfg(feature = "matrix")]
+#[derive(Debug, Serialize)]
+pub struct Cholesky {
+    pub l: Matrix,
+}
+
+#[cfg(feature = "matrix")]
+#[derive(Debug, Serialize)]
+pub struct ConditionNumber {
+    // 2ノルムの条件数 σmax / σmin（特異な場合は None）
+    pub condition_number_2: Option<f64>,
+    // 1ノルムの条件数 ‖A‖₁‖A⁻¹‖₁
+    pub condition_number_1: Option<f64>,
+    pub singular_values: Vec<f64>,
+    pub singular: bool,
+    // 条件数が 1e12 を超えると、連立方程式の解などはほとんど信頼できない
+    pub ill_conditioned: bool,
+}
+
+// 要素数（行数 × 列数）が上限以内か。固有値の計算などは O(n^3) を繰り返すため、計算の前に確かめる
+pub fn check_size(matrix: &Matrix, max_elements: usize) -> Result<(), String> {
+    let elements: usize = matrix.iter().map(Vec::len).sum();
+    if elements > max_elements {
+        return Err(format!(
+            "行列が大きすぎます: 要素数 {}（上限は {}）",
+            elements, max_elements
+        ));
+    }
+    Ok(())
+}
+
+// 行の長さがそろっていて、有限な値だけからなるか
+pub fn validate(matrix: &Matrix) -> Result<(usize, usize), String> {
+    let rows = matrix.len();
+    let cols = matrix.first().map_or(0, Vec::len);
+    if rows == 0 || cols == 0 {
+        return Err("行列が空です".to_string());
+    }
+    if let Some(index) = matrix.iter().position(|row| row.len() != cols) {
+        return Err(format!(
+            "{}行目の要素数が {} ではありません",
+            index + 1,
+            cols
+        ));
+    }
+    if matrix.iter().flatten().any(|value| !value.is_finite()) {
+        return Err("行列に無効な値（NaN または 無限大）が含まれています".to_string());
+    }
+    Ok((rows, cols))
+}
+
+#[cfg(feature = "matrix")]
+pub fn eigen(matrix: &Matrix) -> Result<Eigen, String> {
+    let n = validate_square(matrix)?;
+    let symmetric = is_symmetric(matrix);
+    let (values, schur_vectors, iterations) = qr_algorithm(matrix, symmetric)?;
+
+    let mut eigenpairs: Vec<Eigenpair> = values
+        .into_iter()
+        .enumerate()
+        .map(|(index, (value, imaginary))| {
+            let vector = if imaginary != 0.0 {
+                None
+            } else if symmetric {
+                // 対称行列では QR 法で累積した直交行列の列がそのまま固有ベクトルになる
+                Some(normalize((0..n).map(|i| schur_vectors[i][index]).collect()))
+            } else {
+                inverse_iteration(matrix, value)
+            };
+            Eigenpair {
+                value,
+                imaginary,
+                vector,
+            }
+        })
+        .collect();
+    eigenpairs.sort_by(|a, b| {
+        b.value
+            .total_cmp(&a.value)
+            .then(b.imaginary.total_cmp(&a.imaginary))
+    });
+
+    Ok(Eigen {
+        symmetric,
+        eigenpairs,
+        iterations,
+    })
+}
+
+pub fn lu(matrix: &Matrix) -> Result<Lu, String> {
+    let n = validate_square(matrix)?;
+    let (l, u, permutation, sign) = lu_decompose(matrix);
+    let p = permutation
+        .iter()
+        .map(|&column| {
+            (0..n)
+                .map(|j| if j == column { 1.0 } else { 0.0 })
+                .collect()
+        })
+        .collect();
+    let determinant = sign * (0..n).map(|i| u[i][i]).product::<f64>();
+    Ok(Lu {
+        l,
+        u,
+        p,
+        determinant,
+    })
+}
+
+#[cfg(feature = "matrix")]
+pub fn qr(matrix: &Matrix) -> Result<Qr, String> {
+    validate(matrix)?;
+    let (q, r) = householder_qr(matrix);
+    Ok(Qr { q, r })
+}
+
+#[cfg(feature = "matrix")]
+pub fn cholesky(matrix: &Matrix) -> Result<Cholesky, String> {
+    let n = validate_square(matrix)?;
+    if !is_symmetric(matrix) {
+        return Err("コレスキー分解は対称行列にのみ適用できます".to_string());
+    }
+    let mut l = vec![vec![0.0; n]; n];
+    for j in 0..n {
+        let diagonal = matrix[j][j] - (0..j).map(|k| l[j][k] * l[j][k]).sum::<f64>();
+        if diagonal <= 0.0 {
+            return Err("正定値ではない行列はコレスキー分解できません".to_string());
+        }
+        l[j][j] = diagonal.sqrt();
+        for i in j + 1..n {
+            let sum: f64 = (0..j).map(|k| l[i][k] * l[j][k]).sum();
+            l[i][j] = (matrix[i][j] - sum) / l[j][j];
+        }
+    }
+    Ok(Cholesky { l })
+}
+
+#[cfg(feature = "matrix")]
+pub fn condition_number(matrix: &Matrix) -> Result<ConditionNumber, String> {
+    let n = validate_square(matrix)?;
+
+    let singular_values = singular_values(matrix)?;
+    let (largest, smallest) = (singular_values[0], singular_values[n - 1]);
+    let singular = largest == 0.0 || smallest <= largest * f64::EPSILON * n as f64;
+
+    let condition_number_2 = (!singular).then(|| largest / smallest);
+    let condition_number_1 = if singular {
+        None
+    } else {
+        inverse(matrix).map(|inverse| norm_1(matrix) * norm_1(&inverse))
+    };
+    Ok(ConditionNumber {
+        condition_number_2,
+        condition_number_1,
+        singular_values,
+        singular,
+        ill_conditioned: condition_number_2.is_none_or(|condition| condition > 1e12),
+    })
+}
+
+pub fn identity(n: usize) -> Matrix {
+    (0..n)
+        .map(|i| (0..n).map(|j| if i == j { 1.0 } else { 0.0 }).collect())
+        .collect()
+}
+
+pub fn transpose(matrix: &Matrix) -> Matrix {
+    let cols = matrix.first().map_or(0, Vec::len);
+    (0..cols)
+        .map(|j| matrix.iter().map(|row| row[j]).collect())
+        .collect()
+}
+
+pub fn multiply(a: &Matrix, b: &Matrix) -> Matrix {
+    let cols = b.first().map_or(0, Vec::len);
+    a.iter()
+        .map(|row| {
+            (0..cols)
+                .map(|j| row.iter().zip(b).map(|(x, b_row)| x * b_row[j]).sum())
+                .collect()
+        })
+        .collect()
+}
+
+// 連立一次方程式 Ax = b の解（A が特異なら None）
+pub fn solve_linear(matrix: &Matrix, b: &[f64]) -> Option<Vec<f64>> {
+    let (l, u, permutation, _) = lu_decompose(matrix);
+    solve(&l, &u, &permutation, b).filter(|x| x.iter().all(|value| value.is_finite()))
+}
+
+// 片側ヤコビ法で特異値を大きい順に求める。
+// AᵀA の固有値から求めると条件数が2乗されて小さい特異値の精度が失われるため、A を直接回転する
+
+#[cfg(feature = "matrix")]
+fn singular_values(matrix: &Matrix) -> Result<Vec<f64>, String> {
+    let mut columns = transpose(matrix);
+    let n = columns.len();
+    let mut converged = false;
+    for _ in 0..100 {
+        converged = true;
+        for p in 0..n {
+            for q in p + 1..n {
+                let dot = |a: &[f64], b: &[f64]| a.iter().zip(b).map(|(x, y)| x * y).sum::<f64>();
+                let alpha = dot(&columns[p], &columns[p]);
+                let beta = dot(&columns[q], &columns[q]);
+                let gamma = dot(&columns[p], &columns[q]);
+                if gamma.abs() <= f64::EPSILON * (alpha * beta).sqrt() {
+                    continue;
+                }
+                converged = false;
+                let zeta = (beta - alpha) / (2.0 * gamma);
+                let t = zeta.signum() / (zeta.abs() + (1.0 + zeta * zeta).sqrt());
+                let c = 1.0 / (1.0 + t * t).sqrt();
+                let s = c * t;
+                let (left, right) = columns.split_at_mut(q);
+                for (x, y) in left[p].iter_mut().zip(right[0].iter_mut()) {
+                    let (u, v) = (*x, *y);
+                    *x = c * u - s * v;
+                    *y = s * u + c * v;
+                }
+            }
+        }
+        if converged {
+            break;
+        }
+    }
+    if !converged {
+        return Err("特異値の計算が収束しませんでした".to_string());
+    }
+    let mut values: Vec<f64> = columns
+        .iter()
+        .map(|column| column.iter().map(|x| x * x).sum::<f64>().sqrt())
+        .collect();
+    values.sort_by(|a, b| b.total_cmp(a));
+    Ok(values)
+}
+
+pub fn validate_square(matrix: &Matrix) -> Result<usize, String> {
+    let (rows, cols) = validate(matrix)?;
+    if rows != cols {
+        return Err(format!("正方行列を指定してください: {}×{}", rows, cols));
+    }
+    Ok(rows)
+}
+
+#[cfg(feature = "matrix")]
+fn is_symmetric(matrix: &Matrix) -> bool {
+    let scale = matrix
+        .iter()
+        .flatten()
+        .fold(0.0f64, |max, x| max.max(x.abs()));
+    let n = matrix.len();
+    (0..n).all(|i| (0..i).all(|j| (matrix[i][j] - matrix[j][i]).abs() <= 1e-12 * scale))
+}
+
+// シフト付き QR 法。収束した末尾の行を順に切り離し（デフレーション）、
+// 実数の固有値と、2×2 のブロックに残る複素共役の組を求める
+#[cfg(feature = "matrix")]
+#[allow(clippy::type_complexity)]
+fn qr_algorithm(
+    matrix: &Matrix,
+    symmetric: bool,
+) -> Result<(Vec<(f64, f64)>, Matrix, usize), String> {
+    let n = matrix.len();
+    let mut a = matrix.clone();
+    let mut vectors = identity(n);
+    let mut values = vec![(0.0, 0.0); n];
+    let scale = matrix
+        .iter()
+        .flatten()
+        .fold(0.0f64, |max, x| max.max(x.abs()))
+        .max(f64::MIN_POSITIVE);
+    let negligible = |row: &[f64], end: usize| {
+        row[..end].iter().map(|x| x.abs()).sum::<f64>() <= f64::EPSILON * scale
+    };
+
+    let mut m = n;
+    let mut iterations = 0;
+    while m > 0 {
+        if m == 1 || negligible(&a[m - 1], m - 1) {
+            values[m - 1] = (a[m - 1][m - 1], 0.0);
+            m -= 1;
+            continue;
+        }
+        if !symmetric && (m == 2 || negligible(&a[m - 2], m - 2)) {
+            let (p, q, r, s) = (
+                a[m - 2][m - 2],
+                a[m - 2][m - 1],
+                a[m - 1][m - 2],
+                a[m - 1][m - 1],
+            );
+            let half_trace = (p + s) / 2.0;
+            let discriminant = ((p - s) / 2.0).powi(2) + q * r;
+            if discriminant < 0.0 {
+                let imaginary = (-discriminant).sqrt();
+                values[m - 2] = (half_trace, imaginary);
+                values[m - 1] = (half_trace, -imaginary);
+                m -= 2;
+                continue;
+            }
+        }
+
+        iterations += 1;
+        if iterations > MAX_QR_ITERATIONS {
+            return Err("固有値の計算が収束しませんでした".to_string());
+        }
+
+        let shift = wilkinson_shift(&a, m);
+        let block: Matrix = (0..m)
+            .map(|i| {
+                (0..m)
+                    .map(|j| a[i][j] - if i == j { shift } else { 0.0 })
+                    .collect()
+            })
+            .collect();
+        let (q, r) = householder_qr(&block);
+        let next = multiply(&r, &q);
+        for i in 0..m {
+            for j in 0..m {
+                a[i][j] = next[i][j] + if i == j { shift } else { 0.0 };
+            }
+        }
+        if symmetric {
+            for row in vectors.iter_mut() {
+                let updated: Vec<f64> = (0..m)
+                    .map(|j| (0..m).map(|k| row[k] * q[k][j]).sum())
+                    .collect();
+                row[..m].copy_from_slice(&updated);
+            }
+        }
+    }
+    Ok((values, vectors, iterations))
+}
+
+// 末尾の 2×2 の固有値のうち右下の要素に近い方（複素数になる場合は右下の要素）
+#[cfg(feature = "matrix")]
+fn wilkinson_shift(a: &Matrix, m: usize) -> f64 {
+    let (p, q, r, s) = (
+        a[m - 2][m - 2],
+        a[m - 2][m - 1],
+        a[m - 1][m - 2],
+        a[m - 1][m - 1],
+    );
+    let delta = (p - s) / 2.0;
+    let discriminant = delta * delta + q * r;
+    if discriminant < 0.0 {
+        return s;
+    }
+    let denominator = delta.abs() + discriminant.sqrt();
+    if denominator == 0.0 {
+        return s;
+    }
+    let sign = if delta < 0.0 { -1.0 } else { 1.0 };
+    s - sign * q * r / denominator
+}
+
+#[cfg(feature = "matrix")]
+fn householder_qr(matrix: &Matrix) -> (Matrix, Matrix) {
+    let rows = matrix.len();
+    let cols = matrix[0].len();
+    let mut r = matrix.clone();
+    let mut q = identity(rows);
+
+    for k in 0..cols.min(rows.saturating_sub(1)) {
+        let mut v: Vec<f64> = (k..rows).map(|i| r[i][k]).collect();
+        let norm = v.iter().map(|x| x * x).sum::<f64>().sqrt();
+        if norm == 0.0 {
+            continue;
+        }
+        v[0] += if v[0] < 0.0 { -norm } else { norm };
+        let v_norm = v.iter().map(|x| x * x).sum::<f64>().sqrt();
+        v.iter_mut().for_each(|x| *x /= v_norm);
+
+        for j in 0..cols {
+            let dot: f64 = v.iter().zip(&r[k..]).map(|(x, row)| x * row[j]).sum();
+            for (x, row) in v.iter().zip(&mut r[k..]) {
+                row[j] -= 2.0 * x * dot;
+            }
+        }
+        for row in q.iter_mut() {
+            let dot: f64 = (0..v.len()).map(|i| row[k + i] * v[i]).sum();
+            for i in 0..v.len() {
+                row[k + i] -= 2.0 * dot * v[i];
+            }
+        }
+    }
+
+    // R の対角成分を 0 以上にそろえる
+    for k in 0..cols.min(rows) {
+        if r[k][k] < 0.0 {
+            r[k].iter_mut().for_each(|x| *x = -*x);
+            q.iter_mut().for_each(|row| row[k] = -row[k]);
+        }
+        for row in r.iter_mut().skip(k + 1) {
+            row[k] = 0.0;
+        }
+    }
+    (q, r)
+}
+
+// (L, U, 各行の元の行番号, 置換の符号)
+fn lu_decompose(matrix: &Matrix) -> (Matrix, Matrix, Vec<usize>, f64) {
+    let n = matrix.len();
+    let mut u = matrix.clone();
+    let mut l = identity(n);
+    let mut permutation: Vec<usize> = (0..n).collect();
+    let mut sign = 1.0;
+
+    for k in 0..n {
+        let pivot = (k..n)
+            .max_by(|&a, &b| u[a][k].abs().total_cmp(&u[b][k].abs()))
+            .unwrap_or(k);
+        if pivot != k {
+            u.swap(k, pivot);
+            permutation.swap(k, pivot);
+            let (upper, lower) = l.split_at_mut(pivot);
+            upper[k][..k].swap_with_slice(&mut lower[0][..k]);
+            sign = -sign;
+        }
+        if u[k][k] == 0.0 {
+            continue;
+        }
+        for i in k + 1..n {
+            let factor = u[i][k] / u[k][k];
+            l[i][k] = factor;
+            let (upper, lower) = u.split_at_mut(i);
+            for (x, pivot_value) in lower[0][k..].iter_mut().zip(&upper[k][k..]) {
+                *x -= factor * pivot_value;
+            }
+        }
+    }
+    (l, u, permutation, sign)
+}
+
+fn solve(l: &Matrix, u: &Matrix, permutation: &[usize], b: &[f64]) -> Option<Vec<f64>> {
+    let n = l.len();
+    let mut y = vec![0.0; n];
+    for i in 0..n {
+        y[i] = b[permutation[i]] - (0..i).map(|j| l[i][j] * y[j]).sum::<f64>();
+    }
+    let mut x = vec![0.0; n];
+    for i in (0..n).rev() {
+        if u[i][i] == 0.0 {
+            return None;
+        }
+        x[i] = (y[i] - (i + 1..n).map(|j| u[i][j] * x[j]).sum::<f64>()) / u[i][i];
+    }
+    Some(x)
+}
+
+// 正方行列の逆行列（特異なら None）
+pub fn inverse(matrix: &Matrix) -> Option<Matrix> {
+    let n = matrix.len();
+    let (l, u, permutation, _) = lu_decompose(matrix);
+    let columns = identity(n)
+        .iter()
+        .map(|e| solve(&l, &u, &permutation, e))
+        .collect::<Option<Vec<_>>>()?;
+    Some(transpose(&columns))
+}
+
+// 逆反復法で実数の固有値 λ に対応する固有ベクトルを求める
+#[cfg(feature = "matrix")]
+fn inverse_iteration(matrix: &Matrix, value: f64) -> Option<Vec<f64>> {
+    let n = matrix.len();
+    // A - λI がちょうど特異にならないよう、わずかにずらす
+    let shift = value + 1e-10 * value.abs().max(1.0);
+    let shifted: Matrix = (0..n)
+        .map(|i| {
+            (0..n)
+                .map(|j| matrix[i][j] - if i == j { shift } else { 0.0 })
+                .collect()
+        })
+        .collect();
+    let (l, u, permutation, _) = lu_decompose(&shifted);
+
+    let mut vector = normalize(vec![1.0; n]);
+    for _ in 0..3 {
+        vector = normalize(solve(&l, &u, &permutation, &vector)?);
+    }
+    vector.iter().all(|x| x.is_finite()).then_some(vector)
+}
+
+// 長さ 1 にし、絶対値が最大の成分が正になる向きにそろえる
+#[cfg(feature = "matrix")]
+fn normalize(mut vector: Vec<f64>) -> Vec<f64> {
+    let norm = vector.iter().map(|x| x * x).sum::<f64>().sqrt();
+    let largest = vector
+        .iter()
+        .copied()
+        .max_by(|a, b| a.abs().total_cmp(&b.abs()))
+        .unwrap_or(0.0);
+    let factor = if largest < 0.0 { -norm } else { norm };
+    if factor != 0.0 {
+        vector.iter_mut().for_each(|x| *x /= factor);
+    }
+    vector
+}
+
+#[cfg(feature = "matrix")]
+fn norm_1(matrix: &Matrix) -> f64 {
+    transpose(matrix)
+        .iter()
+        .map(|column| column.iter().map(|x| x.abs()).sum::<f64>())
+        .fold(0.0, f64::max)
+}
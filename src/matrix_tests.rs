@@ -0,0 +1,121 @@
+#[cfg(test)]
+mod tests {
+    use crate::matrix::{check_size, lu, multiply, Matrix};
+    #[cfg(feature = "matrix")]
+    use crate::matrix::{cholesky, condition_number, eigen, qr, transpose};
+
+    fn assert_close(actual: &Matrix, expected: &Matrix) {
+        for (actual_row, expected_row) in actual.iter().zip(expected) {
+            for (a, e) in actual_row.iter().zip(expected_row) {
+                assert!((a - e).abs() < 1e-9, "{:?} != {:?}", actual, expected);
+            }
+        }
+    }
+
+    #[cfg(feature = "matrix")]
+    #[test]
+    fn test_eigen_symmetric() {
+        let result = eigen(&vec![vec![2.0, 1.0], vec![1.0, 2.0]]).unwrap();
+
+        assert!(result.symmetric);
+        assert!((result.eigenpairs[0].value - 3.0).abs() < 1e-9);
+        assert!((result.eigenpairs[1].value - 1.0).abs() < 1e-9);
+        let vector = result.eigenpairs[0].vector.as_ref().unwrap();
+        let half = 0.5f64.sqrt();
+        assert!((vector[0] - half).abs() < 1e-9 && (vector[1] - half).abs() < 1e-9);
+    }
+
+    #[cfg(feature = "matrix")]
+    #[test]
+    fn test_eigen_nonsymmetric() {
+        let matrix = vec![
+            vec![4.0, 1.0, 2.0],
+            vec![0.0, 3.0, 1.0],
+            vec![1.0, 0.0, 2.0],
+        ];
+        let result = eigen(&matrix).unwrap();
+
+        assert!(!result.symmetric);
+        for pair in result
+            .eigenpairs
+            .iter()
+            .filter(|pair| pair.imaginary == 0.0)
+        {
+            // Av = λv を確かめる
+            let vector = pair.vector.as_ref().unwrap();
+            let product = multiply(&matrix, &transpose(&vec![vector.clone()]));
+            for (row, x) in product.iter().zip(vector) {
+                assert!((row[0] - pair.value * x).abs() < 1e-6);
+            }
+        }
+    }
+
+    #[cfg(feature = "matrix")]
+    #[test]
+    fn test_eigen_complex() {
+        // 90度回転は固有値 ±i
+        let result = eigen(&vec![vec![0.0, -1.0], vec![1.0, 0.0]]).unwrap();
+
+        assert_eq!(result.eigenpairs.len(), 2);
+        assert!((result.eigenpairs[0].imaginary - 1.0).abs() < 1e-9);
+        assert!(result.eigenpairs[0].vector.is_none());
+    }
+
+    #[test]
+    fn test_lu() {
+        let matrix = vec![vec![4.0, 3.0], vec![6.0, 3.0]];
+        let result = lu(&matrix).unwrap();
+
+        assert_close(
+            &multiply(&result.p, &matrix),
+            &multiply(&result.l, &result.u),
+        );
+        assert!((result.determinant + 6.0).abs() < 1e-9);
+    }
+
+    #[cfg(feature = "matrix")]
+    #[test]
+    fn test_qr() {
+        let matrix = vec![vec![12.0, -51.0], vec![6.0, 167.0], vec![-4.0, 24.0]];
+        let result = qr(&matrix).unwrap();
+
+        assert_close(&multiply(&result.q, &result.r), &matrix);
+        assert_close(
+            &multiply(&transpose(&result.q), &result.q),
+            &crate::matrix::identity(3),
+        );
+        assert!((result.r[0][0] - 14.0).abs() < 1e-9);
+    }
+
+    #[cfg(feature = "matrix")]
+    #[test]
+    fn test_cholesky() {
+        let result = cholesky(&vec![vec![4.0, 2.0], vec![2.0, 3.0]]).unwrap();
+        assert_close(&result.l, &vec![vec![2.0, 0.0], vec![1.0, 2f64.sqrt()]]);
+
+        assert!(cholesky(&vec![vec![1.0, 2.0], vec![2.0, 1.0]]).is_err());
+        assert!(cholesky(&vec![vec![1.0, 2.0], vec![0.0, 1.0]]).is_err());
+    }
+
+    #[cfg(feature = "matrix")]
+    #[test]
+    fn test_condition_number() {
+        let well = condition_number(&vec![vec![2.0, 0.0], vec![0.0, 1.0]]).unwrap();
+        assert!((well.condition_number_2.unwrap() - 2.0).abs() < 1e-9);
+        assert!((well.condition_number_1.unwrap() - 2.0).abs() < 1e-9);
+
+        let singular = condition_number(&vec![vec![1.0, 2.0], vec![2.0, 4.0]]).unwrap();
+        assert!(singular.singular && singular.ill_conditioned);
+        assert_eq!(singular.condition_number_2, None);
+    }
+
+    #[test]
+    fn test_validation() {
+        assert!(check_size(&vec![vec![0.0; 50]; 50], 2500).is_ok());
+        assert!(check_size(&vec![vec![0.0; 51]; 50], 2500).is_err());
+        assert!(lu(&vec![vec![1.0, 2.0], vec![3.0]]).is_err());
+        assert!(lu(&vec![vec![1.0, 2.0]]).is_err());
+        #[cfg(feature = "matrix")]
+        assert!(qr(&vec![]).is_err());
+    }
+}
@@ -0,0 +1,217 @@
+use serde::{Deserialize, Serialize};
+
+// これ以上の整数は f64 で隣の整数と区別できないことがある（2^53）
+const MAX_EXACT_INTEGER: f64 = 9_007_199_254_740_992.0;
+// 絶対値がこれ以上（以下）の結果は、続けて計算するとあふれる（0 になる）おそれがある
+const HUGE_MAGNITUDE: f64 = 1e300;
+const TINY_MAGNITUDE: f64 = 1e-300;
+// auto で通常の表記にする範囲（10^-6 以上 10^21 未満。JavaScript の Number#toString と同じ）
+const PLAIN_MIN_EXPONENT: i32 = -6;
+const PLAIN_MAX_EXPONENT: i32 = 21;
+// 表示する小数点以下の桁数の上限（これを超える桁数は Rust の書式指定で扱えないことがある）
+pub const MAX_PRECISION: usize = 100;
+
+// 数値の表記
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "server", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum Notation {
+    // 絶対値が 1e-6 以上 1e21 未満なら通常の表記、それ以外は指数表記
+    #[default]
+    Auto,
+    // 常に通常の表記（1e300 も 301 桁で表示する）
+    Plain,
+    // 常に指数表記（例: 1.5e3）
+    Scientific,
+}
+
+impl Notation {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::Plain => "plain",
+            Self::Scientific => "scientific",
+        }
+    }
+}
+
+// 桁区切りと小数点の記号の慣習
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "server", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum NumberLocale {
+    // 1,234,567.89
+    #[default]
+    En,
+    // 1,234,567.89（英語と同じ）
+    Ja,
+    // 1.234.567,89
+    De,
+    // 1 234 567,89
+    Fr,
+}
+
+impl NumberLocale {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::En => "en",
+            Self::Ja => "ja",
+            Self::De => "de",
+            Self::Fr => "fr",
+        }
+    }
+
+    // (桁区切り, 小数点)
+    fn separators(self) -> (char, char) {
+        match self {
+            Self::En | Self::Ja => (',', '.'),
+            Self::De => ('.', ','),
+            Self::Fr => (' ', ','),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FormatOptions {
+    pub notation: Notation,
+    // 小数点以下の桁数（指数表記では仮数の桁数）。足りない桁は 0 で埋める。
+    // None なら元の値に戻せる最短の桁数で表示する
+    pub precision: Option<usize>,
+    // 整数部を3桁ごとに区切る場合の慣習（None なら区切らず、小数点は .）
+    pub grouping: Option<NumberLocale>,
+}
+
+// 計算結果の表示。プラットフォームやロケールによらず、同じ値は常に同じ文字列になる。
+// 桁の決定は Rust 標準の最短往復変換（{} と {:e}）と、桁数指定の正確な10進変換だけを使う
+pub fn format_number(value: f64, options: FormatOptions) -> String {
+    if !value.is_finite() {
+        return value.to_string();
+    }
+    // -0 は 0 と表示する
+    let value = if value == 0.0 { 0.0 } else { value };
+    let scientific = match options.notation {
+        Notation::Auto => {
+            let exponent = exponent(value);
+            value != 0.0 && !(PLAIN_MIN_EXPONENT..PLAIN_MAX_EXPONENT).contains(&exponent)
+        }
+        Notation::Plain => false,
+        Notation::Scientific => true,
+    };
+    let formatted = match (scientific, options.precision.map(|p| p.min(MAX_PRECISION))) {
+        (false, None) => value.to_string(),
+        (false, Some(precision)) => format!("{:.*}", precision, value),
+        (true, None) => format!("{:e}", value),
+        (true, Some(precision)) => format!("{:.*e}", precision, value),
+    };
+    // 丸めて 0 になった負の数（-0.001 を小数第2位まで表示など）に符号を付けない
+    let formatted = match formatted.strip_prefix('-') {
+        Some(rest) if rest.bytes().all(|b| matches!(b, b'0' | b'.')) => rest.to_string(),
+        _ => formatted,
+    };
+    match options.grouping {
+        Some(locale) => group_digits(&formatted, locale),
+        None => formatted,
+    }
+}
+
+pub fn check_precision(precision: usize) -> Result<(), String> {
+    if precision > MAX_PRECISION {
+        return Err(format!(
+            "precision は {} 以下で指定してください: {}",
+            MAX_PRECISION, precision
+        ));
+    }
+    Ok(())
+}
+
+// "-1234567.89" → "-1,234,567.89"。指数表記の仮数の整数部は1桁なので、小数点だけが変わる
+fn group_digits(formatted: &str, locale: NumberLocale) -> String {
+    let (group_separator, decimal_separator) = locale.separators();
+    let (sign, unsigned) = match formatted.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", formatted),
+    };
+    let split = unsigned
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(unsigned.len());
+    let (integer, rest) = unsigned.split_at(split);
+    let mut grouped = String::from(sign);
+    for (i, digit) in integer.chars().enumerate() {
+        if i > 0 && (integer.len() - i) % 3 == 0 {
+            grouped.push(group_separator);
+        }
+        grouped.push(digit);
+    }
+    grouped.push_str(&rest.replacen('.', &decimal_separator.to_string(), 1));
+    grouped
+}
+
+// 指数表記にしたときの 10 の指数
+fn exponent(value: f64) -> i32 {
+    let formatted = format!("{:e}", value);
+    formatted
+        .split_once('e')
+        .and_then(|(_, exponent)| exponent.parse().ok())
+        .unwrap_or(0)
+}
+
+// 計算結果が誤差を含む、またはあふれる可能性がある場合の注意（エラーにはしない）
+pub fn value_warnings(value: f64) -> Vec<String> {
+    let magnitude = value.abs();
+    let mut warnings = Vec::new();
+    if magnitude >= HUGE_MAGNITUDE {
+        warnings.push(format!(
+            "極端に大きい値です（{:e}）。f64 の上限（約 1.8e308）に近く、続けて計算するとあふれるおそれがあります",
+            value
+        ));
+    } else if magnitude >= MAX_EXACT_INTEGER && magnitude < 10f64.powi(PLAIN_MAX_EXPONENT) {
+        // auto の通常の表記では 1 の位まで表示され、正確な整数に見えてしまう
+        warnings.push(format!(
+            "2^53（{}）以上の値のため、1 の位以下の精度が失われています",
+            MAX_EXACT_INTEGER
+        ));
+    }
+    if magnitude != 0.0 && magnitude < TINY_MAGNITUDE {
+        warnings.push(format!(
+            "極端に小さい値です（{:e}）。有効数字が減っているか、続けて計算すると 0 になるおそれがあります",
+            value
+        ));
+    }
+    warnings
+}
+
+// 整数の値を base 進数で表す（2, 8, 16 進数は 0b, 0o, 0x を付ける）。
+// 2^53 以上や整数でない値は、正確な桁を求められないためエラー
+pub fn format_in_base(value: f64, base: u32) -> Result<String, String> {
+    if !(2..=36).contains(&base) {
+        return Err(format!("基数は 2〜36 にしてください: {}", base));
+    }
+    if value.fract() != 0.0 || value.abs() >= MAX_EXACT_INTEGER {
+        return Err(format!(
+            "{} 進数で表せるのは 2^53 未満の整数だけです: {}",
+            base, value
+        ));
+    }
+    let mut magnitude = value.abs() as u64;
+    let mut digits = Vec::new();
+    loop {
+        digits.push(char::from_digit((magnitude % u64::from(base)) as u32, base).unwrap_or('?'));
+        magnitude /= u64::from(base);
+        if magnitude == 0 {
+            break;
+        }
+    }
+    let prefix = match base {
+        2 => "0b",
+        8 => "0o",
+        16 => "0x",
+        _ => "",
+    };
+    let sign = if value < 0.0 { "-" } else { "" };
+    Ok(format!(
+        "{}{}{}",
+        sign,
+        prefix,
+        digits.iter().rev().collect::<String>()
+    ))
+}
@@ -0,0 +1,92 @@
+#[cfg(test)]
+mod tests {
+    use crate::number_format::{
+        format_in_base, format_number, value_warnings, FormatOptions, Notation, NumberLocale,
+    };
+
+    fn format(value: f64, notation: Notation, precision: Option<usize>) -> String {
+        format_number(
+            value,
+            FormatOptions {
+                notation,
+                precision,
+                grouping: None,
+            },
+        )
+    }
+
+    #[test]
+    fn test_format_shortest_round_trip() {
+        let auto = FormatOptions::default();
+        assert_eq!(format_number(14.0, auto), "14");
+        assert_eq!(format_number(0.1 + 0.2, auto), "0.30000000000000004");
+        assert_eq!(format_number(-0.0, auto), "0");
+        assert_eq!(format_number(1e20, auto), "100000000000000000000");
+        assert_eq!(format_number(1e21, auto), "1e21");
+        assert_eq!(format_number(1.5e-7, auto), "1.5e-7");
+        assert_eq!(format_number(0.000001, auto), "0.000001");
+        assert_eq!(format_number(f64::NAN, auto), "NaN");
+
+        for value in [1.0 / 3.0, 2.0f64.sqrt(), 6.02214076e23, -1.23456789e-12] {
+            assert_eq!(format_number(value, auto).parse::<f64>().unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn test_format_notation_and_precision() {
+        assert_eq!(
+            format(1e21, Notation::Plain, None),
+            "1000000000000000000000"
+        );
+        assert_eq!(format(1500.0, Notation::Scientific, None), "1.5e3");
+        assert_eq!(format(1500.0, Notation::Scientific, Some(3)), "1.500e3");
+        assert_eq!(format(2.5, Notation::Auto, Some(2)), "2.50");
+        assert_eq!(format(1.0 / 3.0, Notation::Plain, Some(4)), "0.3333");
+        assert_eq!(format(-0.001, Notation::Plain, Some(2)), "0.00");
+        assert_eq!(format(2.5e-9, Notation::Auto, Some(1)), "2.5e-9");
+        // 上限を超える桁数は上限に切り詰める
+        assert_eq!(format(2.5, Notation::Plain, Some(70000)).len(), 102);
+    }
+
+    #[test]
+    fn test_value_warnings() {
+        assert!(value_warnings(0.0).is_empty());
+        assert!(value_warnings(123.456).is_empty());
+        assert!(value_warnings(9_007_199_254_740_991.0).is_empty());
+        assert!(value_warnings(2f64.powi(60))[0].contains("2^53"));
+        // 指数表記で表示する大きさでは 1 の位の精度を期待しない
+        assert!(value_warnings(2.5e24).is_empty());
+        assert!(value_warnings(-1e305)[0].contains("大きい"));
+        assert!(value_warnings(5e-310)[0].contains("小さい"));
+    }
+
+    #[test]
+    fn test_format_in_base() {
+        assert_eq!(format_in_base(256.0, 2).unwrap(), "0b100000000");
+        assert_eq!(format_in_base(256.0, 16).unwrap(), "0x100");
+        assert_eq!(format_in_base(-8.0, 8).unwrap(), "-0o10");
+        assert_eq!(format_in_base(0.0, 2).unwrap(), "0b0");
+        assert_eq!(format_in_base(71.0, 36).unwrap(), "1z");
+        assert_eq!(format_in_base(255.0, 10).unwrap(), "255");
+        assert!(format_in_base(2.5, 2).is_err());
+        assert!(format_in_base(2f64.powi(53), 16).is_err());
+        assert!(format_in_base(10.0, 37).is_err());
+    }
+
+    #[test]
+    fn test_format_group_digits() {
+        let grouped = |value: f64, locale: NumberLocale| {
+            let options = FormatOptions {
+                grouping: Some(locale),
+                ..FormatOptions::default()
+            };
+            format_number(value, options)
+        };
+        assert_eq!(grouped(1234567.89, NumberLocale::En), "1,234,567.89");
+        assert_eq!(grouped(1234567.89, NumberLocale::Fr), "1 234 567,89");
+        assert_eq!(grouped(-1234567.89, NumberLocale::De), "-1.234.567,89");
+        assert_eq!(grouped(999.5, NumberLocale::En), "999.5");
+        assert_eq!(grouped(100000.0, NumberLocale::Ja), "100,000");
+        assert_eq!(grouped(2.5e24, NumberLocale::De), "2,5e24");
+    }
+}
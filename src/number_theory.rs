@@ -0,0 +1,257 @@
+use serde::Serialize;
+
+// divisors に列挙する約数の数の上限（個数と総和は上限を超えても正確に求める）
+pub const MAX_LISTED_DIVISORS: usize = 1000;
+// 64ビット整数の素数判定で決定的な結果になるミラー–ラビン法の底
+const WITNESSES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+
+#[derive(Debug, Serialize)]
+pub struct PrimeFactor {
+    pub prime: u64,
+    pub exponent: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NumberProperties {
+    pub number: i64,
+    pub parity: &'static str,
+    pub is_prime: bool,
+    pub is_perfect_square: bool,
+    // 以下の約数に関する項目は絶対値について求める（0 では空）
+    pub prime_factors: Vec<PrimeFactor>,
+    // 例: 2^3 × 3 × 5
+    pub factorization: String,
+    pub divisor_count: u64,
+    pub divisors: Vec<u64>,
+    pub divisors_truncated: bool,
+    pub divisor_sum: u128,
+    // 自身を除く約数の和と、それによる分類（perfect, abundant, deficient。正の数のみ）
+    pub aliquot_sum: Option<u128>,
+    pub classification: Option<&'static str>,
+    pub digit_count: usize,
+    pub digit_sum: u32,
+    pub binary: String,
+    pub octal: String,
+    pub hex: String,
+    // number より小さい最大の素数と、大きい最小の素数
+    pub previous_prime: Option<u64>,
+    pub next_prime: Option<u64>,
+}
+
+pub fn properties(number: i64) -> NumberProperties {
+    let magnitude = number.unsigned_abs();
+    let sign = if number < 0 { "-" } else { "" };
+    let factors = if magnitude == 0 {
+        Vec::new()
+    } else {
+        factorize(magnitude)
+    };
+
+    let divisor_count = factors.iter().map(|f| u64::from(f.exponent) + 1).product();
+    let divisor_sum: u128 = if magnitude == 0 {
+        0
+    } else {
+        factors
+            .iter()
+            .map(|f| {
+                // 1 + p + p^2 + ... + p^e
+                let mut power = 1u128;
+                let mut sum = 1u128;
+                for _ in 0..f.exponent {
+                    power *= u128::from(f.prime);
+                    sum += power;
+                }
+                sum
+            })
+            .product()
+    };
+    let (aliquot_sum, classification) = if number > 0 {
+        let aliquot = divisor_sum - u128::from(magnitude);
+        let classification = match aliquot.cmp(&u128::from(magnitude)) {
+            std::cmp::Ordering::Equal => "perfect",
+            std::cmp::Ordering::Greater => "abundant",
+            std::cmp::Ordering::Less => "deficient",
+        };
+        (Some(aliquot), Some(classification))
+    } else {
+        (None, None)
+    };
+    let (divisors, divisors_truncated) = divisors(magnitude, &factors);
+    let digits = magnitude.to_string();
+    let root = magnitude.isqrt();
+
+    NumberProperties {
+        number,
+        parity: if magnitude.is_multiple_of(2) {
+            "even"
+        } else {
+            "odd"
+        },
+        is_prime: number > 0 && is_prime(magnitude),
+        is_perfect_square: number >= 0 && root * root == magnitude,
+        factorization: match magnitude {
+            0 | 1 => magnitude.to_string(),
+            _ => factors
+                .iter()
+                .map(|f| match f.exponent {
+                    1 => f.prime.to_string(),
+                    exponent => format!("{}^{}", f.prime, exponent),
+                })
+                .collect::<Vec<_>>()
+                .join(" × "),
+        },
+        prime_factors: factors,
+        divisor_count: if magnitude == 0 { 0 } else { divisor_count },
+        divisors,
+        divisors_truncated,
+        divisor_sum,
+        aliquot_sum,
+        classification,
+        digit_count: digits.len(),
+        digit_sum: digits.bytes().map(|digit| u32::from(digit - b'0')).sum(),
+        binary: format!("{}0b{:b}", sign, magnitude),
+        octal: format!("{}0o{:o}", sign, magnitude),
+        hex: format!("{}0x{:x}", sign, magnitude),
+        previous_prime: previous_prime(number),
+        next_prime: next_prime(number),
+    }
+}
+
+pub fn is_prime(n: u64) -> bool {
+    if n < 2 {
+        return false;
+    }
+    for &p in &WITNESSES {
+        if n.is_multiple_of(p) {
+            return n == p;
+        }
+    }
+    let (mut d, mut s) = (n - 1, 0);
+    while d.is_multiple_of(2) {
+        d /= 2;
+        s += 1;
+    }
+    'witness: for &a in &WITNESSES {
+        let mut x = pow_mod(a, d, n);
+        if x == 1 || x == n - 1 {
+            continue;
+        }
+        for _ in 1..s {
+            x = mul_mod(x, x, n);
+            if x == n - 1 {
+                continue 'witness;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+// 素因数分解（小さな素数は試し割り、残りはポラードのロー法）
+pub fn factorize(n: u64) -> Vec<PrimeFactor> {
+    let mut primes = Vec::new();
+    let mut rest = n;
+    for p in 2..1000u64 {
+        while rest.is_multiple_of(p) {
+            primes.push(p);
+            rest /= p;
+        }
+    }
+    let mut stack = vec![rest];
+    while let Some(m) = stack.pop() {
+        if m == 1 {
+            continue;
+        }
+        if is_prime(m) {
+            primes.push(m);
+            continue;
+        }
+        let divisor = pollard_rho(m);
+        stack.push(divisor);
+        stack.push(m / divisor);
+    }
+    primes.sort_unstable();
+
+    let mut factors: Vec<PrimeFactor> = Vec::new();
+    for prime in primes {
+        match factors.last_mut() {
+            Some(last) if last.prime == prime => last.exponent += 1,
+            _ => factors.push(PrimeFactor { prime, exponent: 1 }),
+        }
+    }
+    factors
+}
+
+fn divisors(n: u64, factors: &[PrimeFactor]) -> (Vec<u64>, bool) {
+    if n == 0 {
+        return (Vec::new(), false);
+    }
+    let mut divisors = vec![1u64];
+    for factor in factors {
+        let current = divisors.clone();
+        let mut power = 1u64;
+        for _ in 0..factor.exponent {
+            power *= factor.prime;
+            divisors.extend(current.iter().map(|d| d * power));
+        }
+    }
+    divisors.sort_unstable();
+    let truncated = divisors.len() > MAX_LISTED_DIVISORS;
+    divisors.truncate(MAX_LISTED_DIVISORS);
+    (divisors, truncated)
+}
+
+fn previous_prime(number: i64) -> Option<u64> {
+    if number <= 2 {
+        return None;
+    }
+    (2..number as u64).rev().find(|&n| is_prime(n))
+}
+
+fn next_prime(number: i64) -> Option<u64> {
+    if number < 2 {
+        return Some(2);
+    }
+    (number as u64 + 1..=u64::MAX).find(|&n| is_prime(n))
+}
+
+// フロイドの循環検出による。n は奇数の合成数
+fn pollard_rho(n: u64) -> u64 {
+    for c in 1u64.. {
+        let f = |x: u64| ((u128::from(x) * u128::from(x) + u128::from(c)) % u128::from(n)) as u64;
+        let (mut x, mut y, mut divisor) = (2, 2, 1);
+        while divisor == 1 {
+            x = f(x);
+            y = f(f(y));
+            divisor = gcd(x.abs_diff(y), n);
+        }
+        if divisor != n {
+            return divisor;
+        }
+    }
+    unreachable!()
+}
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+fn mul_mod(a: u64, b: u64, m: u64) -> u64 {
+    (u128::from(a) * u128::from(b) % u128::from(m)) as u64
+}
+
+fn pow_mod(mut base: u64, mut exponent: u64, m: u64) -> u64 {
+    let mut result = 1;
+    base %= m;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = mul_mod(result, base, m);
+        }
+        base = mul_mod(base, base, m);
+        exponent >>= 1;
+    }
+    result
+}
@@ -0,0 +1,75 @@
+#[cfg(test)]
+mod tests {
+    use crate::number_theory::{factorize, is_prime, properties};
+
+    #[test]
+    fn test_properties() {
+        let result = properties(28);
+        assert_eq!(result.parity, "even");
+        assert!(!result.is_prime);
+        assert_eq!(result.factorization, "2^2 × 7");
+        assert_eq!(result.divisors, vec![1, 2, 4, 7, 14, 28]);
+        assert_eq!(result.divisor_count, 6);
+        assert_eq!(result.divisor_sum, 56);
+        assert_eq!(result.classification, Some("perfect"));
+        assert_eq!(result.digit_sum, 10);
+        assert_eq!(result.binary, "0b11100");
+        assert_eq!(result.hex, "0x1c");
+        assert_eq!(
+            (result.previous_prime, result.next_prime),
+            (Some(23), Some(29))
+        );
+
+        let result = properties(12);
+        assert_eq!(result.classification, Some("abundant"));
+        assert_eq!(result.aliquot_sum, Some(16));
+
+        let result = properties(97);
+        assert!(result.is_prime);
+        assert_eq!(result.classification, Some("deficient"));
+        assert_eq!(result.divisors, vec![1, 97]);
+
+        let result = properties(1);
+        assert_eq!(result.divisors, vec![1]);
+        assert_eq!(result.factorization, "1");
+        assert_eq!(result.previous_prime, None);
+    }
+
+    #[test]
+    fn test_properties_zero_and_negative() {
+        let result = properties(0);
+        assert!(result.divisors.is_empty());
+        assert_eq!(result.divisor_count, 0);
+        assert_eq!(result.classification, None);
+        assert!(result.is_perfect_square);
+
+        let result = properties(-12);
+        assert!(!result.is_prime);
+        assert_eq!(result.classification, None);
+        assert_eq!(result.divisor_count, 6);
+        assert_eq!(result.binary, "-0b1100");
+        assert_eq!(result.next_prime, Some(2));
+    }
+
+    #[test]
+    fn test_large_numbers() {
+        // 2^61 - 1 はメルセンヌ素数
+        assert!(is_prime(2_305_843_009_213_693_951));
+        assert!(!is_prime(3_215_031_751));
+
+        let factors = factorize(1_000_000_007 * 998_244_353);
+        let primes: Vec<u64> = factors.iter().map(|f| f.prime).collect();
+        assert_eq!(primes, vec![998_244_353, 1_000_000_007]);
+
+        let result = properties(i64::MAX);
+        assert_eq!(
+            result.factorization,
+            "7^2 × 73 × 127 × 337 × 92737 × 649657"
+        );
+        assert_eq!(result.next_prime, Some(9_223_372_036_854_775_837));
+        assert_eq!(properties(i64::MIN).factorization, "2^63");
+
+        // 720720 の約数は 240 個
+        assert_eq!(properties(720_720).divisors.len(), 240);
+    }
+}
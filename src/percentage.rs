@@ -0,0 +1,65 @@
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub struct PercentOf {
+    pub percent: f64,
+    pub of: f64,
+    // of * percent / 100
+    pub value: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PercentChange {
+    pub from: f64,
+    pub to: f64,
+    // to - from（入力と同じ単位。値が率のときはパーセントポイントと同じ）
+    pub difference: f64,
+    // (to - from) / |from| * 100（from が 0 の場合は None）
+    pub relative_change_percent: Option<f64>,
+    // 値が率（%）のときだけ設定する。相対変化率と混同しないよう別の項目にする
+    pub percentage_point_change: Option<f64>,
+    pub summary: String,
+}
+
+pub fn percent_of(percent: f64, of: f64) -> Result<PercentOf, String> {
+    let value = of * percent / 100.0;
+    if !value.is_finite() {
+        return Err("計算結果が無効です（NaN または 無限大）".to_string());
+    }
+    Ok(PercentOf { percent, of, value })
+}
+
+// from から to への変化。values_are_percent なら from, to 自体を率（5 = 5%）として扱い、
+// パーセントポイントの差と相対的な変化率を両方返す
+pub fn change(from: f64, to: f64, values_are_percent: bool) -> Result<PercentChange, String> {
+    if !from.is_finite() || !to.is_finite() {
+        return Err("計算結果が無効です（NaN または 無限大）".to_string());
+    }
+    let difference = to - from;
+    let relative_change_percent = (from != 0.0).then(|| difference / from.abs() * 100.0);
+    let relative = match relative_change_percent {
+        Some(percent) => format!("{:+}%", percent),
+        None => "基準が 0 のため算出できません".to_string(),
+    };
+
+    let summary = if values_are_percent {
+        format!(
+            "{}% → {}% は {:+} パーセントポイントの変化です（相対的な変化率: {}）",
+            from, to, difference, relative
+        )
+    } else {
+        format!(
+            "{} → {} の変化は {:+}（変化率: {}）です",
+            from, to, difference, relative
+        )
+    };
+
+    Ok(PercentChange {
+        from,
+        to,
+        difference,
+        relative_change_percent,
+        percentage_point_change: values_are_percent.then_some(difference),
+        summary,
+    })
+}
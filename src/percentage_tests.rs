@@ -0,0 +1,36 @@
+#[cfg(test)]
+mod tests {
+    use crate::percentage::{change, percent_of};
+
+    #[test]
+    fn test_percent_of() {
+        assert_eq!(percent_of(15.0, 200.0).unwrap().value, 30.0);
+    }
+
+    #[test]
+    fn test_change_of_plain_values() {
+        let result = change(200.0, 250.0, false).unwrap();
+
+        assert_eq!(result.difference, 50.0);
+        assert_eq!(result.relative_change_percent, Some(25.0));
+        assert_eq!(result.percentage_point_change, None);
+    }
+
+    #[test]
+    fn test_change_of_rates_distinguishes_points() {
+        // 5% → 7% は 2 ポイントの上昇で、相対的には 40% の上昇
+        let result = change(5.0, 7.0, true).unwrap();
+
+        assert_eq!(result.percentage_point_change, Some(2.0));
+        assert!((result.relative_change_percent.unwrap() - 40.0).abs() < 1e-9);
+        assert!(result.summary.contains("+2 パーセントポイント"));
+    }
+
+    #[test]
+    fn test_change_from_zero() {
+        let result = change(0.0, 3.0, true).unwrap();
+
+        assert_eq!(result.relative_change_percent, None);
+        assert_eq!(result.percentage_point_change, Some(3.0));
+    }
+}
@@ -0,0 +1,76 @@
+use crate::calculator::{division_by_zero, domain_error};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Serialize)]
+pub struct Solution {
+    // 求めた項の名前（a, b, c, d のいずれか）
+    pub unknown: &'static str,
+    pub value: f64,
+    // 解いた比例式（例: "3 / 4 = 6 / 8"）
+    pub equation: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Scaled {
+    // to / from
+    pub factor: f64,
+    pub quantities: BTreeMap<String, f64>,
+}
+
+// a / b = c / d のうち、None の1項を求める
+pub fn solve(
+    a: Option<f64>,
+    b: Option<f64>,
+    c: Option<f64>,
+    d: Option<f64>,
+) -> Result<Solution, String> {
+    let (unknown, value) = match (a, b, c, d) {
+        (None, Some(b), Some(c), Some(d)) => ("a", divide(b * c, d)?),
+        (Some(a), None, Some(c), Some(d)) => ("b", divide(a * d, c)?),
+        (Some(a), Some(b), None, Some(d)) => ("c", divide(a * d, b)?),
+        (Some(a), Some(b), Some(c), None) => ("d", divide(b * c, a)?),
+        _ => return Err("a, b, c, d のうち求めたい1つだけを省略してください".to_string()),
+    };
+    // 省略された項は1つだけなので、求めた値で埋めれば比例式が揃う
+    let (a, b, c, d) = (
+        a.unwrap_or(value),
+        b.unwrap_or(value),
+        c.unwrap_or(value),
+        d.unwrap_or(value),
+    );
+    if b == 0.0 || d == 0.0 {
+        return Err("分母が 0 の比例式は解けません".to_string());
+    }
+    Ok(Solution {
+        unknown,
+        value,
+        equation: format!("{} / {} = {} / {}", a, b, c, d),
+    })
+}
+
+// from を基準にした分量を to に合わせて拡大・縮小する（例: 4人分のレシピを7人分に）
+pub fn scale(from: f64, to: f64, quantities: &BTreeMap<String, f64>) -> Result<Scaled, String> {
+    if !from.is_finite() || !to.is_finite() || from == 0.0 {
+        return Err(format!("拡大・縮小の基準が無効です: {} → {}", from, to));
+    }
+    let factor = to / from;
+    Ok(Scaled {
+        factor,
+        quantities: quantities
+            .iter()
+            .map(|(name, quantity)| (name.clone(), quantity * factor))
+            .collect(),
+    })
+}
+
+fn divide(numerator: f64, denominator: f64) -> Result<f64, String> {
+    if denominator == 0.0 {
+        return Err(division_by_zero());
+    }
+    let value = numerator / denominator;
+    if !value.is_finite() {
+        return Err(domain_error("計算結果が無効です（NaN または 無限大）"));
+    }
+    Ok(value)
+}
@@ -0,0 +1,47 @@
+#[cfg(test)]
+mod tests {
+    use crate::proportion::{scale, solve};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_solve_each_unknown() {
+        // 3 / 4 = 6 / 8
+        assert_eq!(
+            solve(None, Some(4.0), Some(6.0), Some(8.0)).unwrap().value,
+            3.0
+        );
+        assert_eq!(
+            solve(Some(3.0), None, Some(6.0), Some(8.0)).unwrap().value,
+            4.0
+        );
+        assert_eq!(
+            solve(Some(3.0), Some(4.0), None, Some(8.0)).unwrap().value,
+            6.0
+        );
+
+        let solution = solve(Some(3.0), Some(4.0), Some(6.0), None).unwrap();
+        assert_eq!(solution.unknown, "d");
+        assert_eq!(solution.value, 8.0);
+        assert_eq!(solution.equation, "3 / 4 = 6 / 8");
+    }
+
+    #[test]
+    fn test_solve_invalid() {
+        assert!(solve(Some(3.0), Some(4.0), Some(6.0), Some(8.0)).is_err());
+        assert!(solve(None, None, Some(6.0), Some(8.0)).is_err());
+        assert!(solve(Some(0.0), Some(4.0), Some(6.0), None).is_err());
+        assert!(solve(Some(3.0), Some(0.0), None, Some(8.0)).is_err());
+    }
+
+    #[test]
+    fn test_scale_recipe() {
+        let quantities =
+            BTreeMap::from([("flour_g".to_string(), 200.0), ("eggs".to_string(), 2.0)]);
+        let scaled = scale(4.0, 7.0, &quantities).unwrap();
+
+        assert_eq!(scaled.factor, 1.75);
+        assert_eq!(scaled.quantities["flour_g"], 350.0);
+        assert_eq!(scaled.quantities["eggs"], 3.5);
+        assert!(scale(0.0, 7.0, &quantities).is_err());
+    }
+}
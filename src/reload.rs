@@ -0,0 +1,69 @@
+use anyhow::Result;
+use calc_mcp::config::Config;
+use calc_mcp::server::CalculatorService;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tokio::time::{interval, Duration};
+
+// 設定ファイルの更新を確認する間隔
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+// 設定ファイルの監視（更新時刻のポーリングと SIGHUP）を開始する
+pub fn spawn(service: CalculatorService, path: PathBuf) {
+    #[cfg(unix)]
+    tokio::spawn(watch_hangup(service.clone(), path.clone()));
+    tokio::spawn(watch_file(service, path));
+}
+
+// 設定を読み直して反映する。失敗した場合は現在の設定を維持する
+pub async fn reload(service: &CalculatorService, path: &Path) -> Result<()> {
+    let config = Config::load(Some(path))?;
+    config.validate(&CalculatorService::tool_names())?;
+    service.apply_config(config).await;
+    Ok(())
+}
+
+async fn reload_and_report(service: &CalculatorService, path: &Path) {
+    match reload(service, path).await {
+        Ok(()) => eprintln!("設定を再読み込みしました: {}", path.display()),
+        Err(e) => eprintln!(
+            "設定の再読み込みに失敗しました（現在の設定を維持します）: {:#}",
+            e
+        ),
+    }
+}
+
+async fn watch_file(service: CalculatorService, path: PathBuf) {
+    let mut last_modified = modified_time(&path);
+    let mut ticker = interval(POLL_INTERVAL);
+    loop {
+        ticker.tick().await;
+        let modified = modified_time(&path);
+        if modified != last_modified {
+            last_modified = modified;
+            reload_and_report(&service, &path).await;
+        }
+    }
+}
+
+#[cfg(unix)]
+async fn watch_hangup(service: CalculatorService, path: PathBuf) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangup = match signal(SignalKind::hangup()) {
+        Ok(hangup) => hangup,
+        Err(e) => {
+            eprintln!("SIGHUP の監視を開始できません: {}", e);
+            return;
+        }
+    };
+    while hangup.recv().await.is_some() {
+        reload_and_report(&service, &path).await;
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
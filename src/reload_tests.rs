@@ -0,0 +1,47 @@
+#[cfg(test)]
+mod tests {
+    use crate::reload::reload;
+    use calc_mcp::server::CalculatorService;
+    use std::path::PathBuf;
+
+    fn temp_config(name: &str, content: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "calc-mcp-{}-{}.toml",
+            name,
+            std::process::id()
+        ));
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[tokio::test]
+    async fn test_reload_applies_new_config() {
+        let service = CalculatorService::default();
+        let path = temp_config("reload", "tools.enabled = []");
+
+        reload(&service, &path).await.unwrap();
+        assert!(service.enabled_tools().is_empty());
+
+        std::fs::write(&path, r#"tools.enabled = ["calculate"]"#).unwrap();
+        reload(&service, &path).await.unwrap();
+        assert!(service.config().tools.is_enabled("calculate"));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_reload_keeps_config_on_error() {
+        let service = CalculatorService::default();
+        let path = temp_config("reload-invalid", "tools.enabled = [");
+
+        assert!(reload(&service, &path).await.is_err());
+        assert!(service.config().tools.is_enabled("calculate"));
+
+        // 未知のツール名も現在の設定を壊さない
+        std::fs::write(&path, r#"tools.enabled = ["calcualte"]"#).unwrap();
+        assert!(reload(&service, &path).await.is_err());
+        assert!(service.config().tools.is_enabled("calculate"));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
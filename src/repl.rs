@@ -0,0 +1,177 @@
+use anyhow::Result;
+use calc_mcp::calculator::{format_significant, AngleMode, Calculator};
+use calc_mcp::number_format::format_number;
+use calc_mcp::rounding::RoundingMode;
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+use std::process::ExitCode;
+
+const PROMPT: &str = "calc> ";
+
+const HELP: &str =
+    "式を入力すると評価します。`名前 = 式` で変数に代入し、直前の結果は ans で参照できます。
+  :vars            変数の一覧
+  :history         入力履歴
+  :precision <n>   有効数字 n 桁で表示（:precision off で解除）
+  :mode deg|rad    三角関数（atan2 の結果を含む）の角度の単位
+  :rounding <mode> round() の端数処理（half_up, half_even, floor, ceil, truncate）
+  :help            このヘルプ
+  :quit            終了";
+
+#[derive(Debug)]
+pub enum Outcome {
+    Output(String),
+    Quit,
+}
+
+// REPLのセッション状態。評価には MCP の calculate と同じ Calculator を使う
+pub struct Session {
+    calculator: Calculator,
+    precision: Option<usize>,
+    history: Vec<String>,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Self {
+            calculator: Calculator::new(),
+            precision: None,
+            history: Vec::new(),
+        }
+    }
+
+    pub fn handle_line(&mut self, line: &str) -> Result<Outcome, String> {
+        let line = line.trim();
+        if line.is_empty() {
+            return Ok(Outcome::Output(String::new()));
+        }
+        if let Some(command) = line.strip_prefix(':') {
+            return self.handle_command(command);
+        }
+
+        self.history.push(line.to_string());
+        let (name, expression) = match line.split_once('=') {
+            Some((name, expression)) => (Some(name.trim()), expression),
+            None => (None, line),
+        };
+
+        let result = self.calculator.evaluate(expression)?;
+        if let Some(name) = name {
+            self.calculator.set_variable(name, result)?;
+        }
+        self.calculator.set_variable("ans", result)?;
+
+        let formatted = self.format(result);
+        match name {
+            Some(name) => Ok(Outcome::Output(format!("{} = {}", name, formatted))),
+            None => Ok(Outcome::Output(formatted)),
+        }
+    }
+
+    fn handle_command(&mut self, command: &str) -> Result<Outcome, String> {
+        let mut parts = command.split_whitespace();
+        let name = parts.next().unwrap_or("");
+        let arg = parts.next();
+
+        let output = match (name, arg) {
+            ("q" | "quit" | "exit", None) => return Ok(Outcome::Quit),
+            ("help", None) => HELP.to_string(),
+            ("vars", None) if self.calculator.variables().is_empty() => {
+                "変数はありません".to_string()
+            }
+            ("vars", None) => {
+                let mut variables: Vec<_> = self.calculator.variables().iter().collect();
+                variables.sort_by(|a, b| a.0.cmp(b.0));
+                variables
+                    .into_iter()
+                    .map(|(name, value)| format!("{} = {}", name, self.format(*value)))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            }
+            ("history", None) => self
+                .history
+                .iter()
+                .enumerate()
+                .map(|(i, line)| format!("{:>4}  {}", i + 1, line))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            ("precision", Some("off")) => {
+                self.precision = None;
+                "有効数字の指定を解除しました".to_string()
+            }
+            ("precision", Some(digits)) => {
+                let digits = digits
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|digits| (1..=17).contains(digits))
+                    .ok_or_else(|| format!("有効数字は 1〜17 で指定してください: {}", digits))?;
+                self.precision = Some(digits);
+                format!("有効数字 {} 桁で表示します", digits)
+            }
+            ("mode", None) => match self.calculator.angle_mode() {
+                AngleMode::Degrees => "角度の単位: 度".to_string(),
+                AngleMode::Radians => "角度の単位: ラジアン".to_string(),
+            },
+            ("mode", Some("deg")) => {
+                self.calculator.set_angle_mode(AngleMode::Degrees);
+                "角度の単位: 度".to_string()
+            }
+            ("mode", Some("rad")) => {
+                self.calculator.set_angle_mode(AngleMode::Radians);
+                "角度の単位: ラジアン".to_string()
+            }
+            ("rounding", None) => format!("端数処理: {}", self.calculator.rounding_mode().name()),
+            ("rounding", Some(mode)) => {
+                let rounding_mode = RoundingMode::parse(mode).ok_or_else(|| {
+                    format!(
+                        "端数処理は half_up, half_even, floor, ceil, truncate のいずれかです: {}",
+                        mode
+                    )
+                })?;
+                self.calculator.set_rounding_mode(rounding_mode);
+                format!("端数処理: {}", rounding_mode.name())
+            }
+            _ => return Err(format!("不明なコマンド: :{}（:help で一覧）", command)),
+        };
+        Ok(Outcome::Output(output))
+    }
+
+    fn format(&self, value: f64) -> String {
+        match self.precision {
+            Some(digits) => format_significant(value, digits),
+            None => format_number(value, Default::default()),
+        }
+    }
+}
+
+pub fn run() -> Result<ExitCode> {
+    let mut editor = DefaultEditor::new()?;
+    let mut session = Session::new();
+    println!("calc-mcp REPL（:help でヘルプ、:quit で終了）");
+
+    loop {
+        match editor.readline(PROMPT) {
+            Ok(line) => {
+                if !line.trim().is_empty() {
+                    let _ = editor.add_history_entry(line.as_str());
+                }
+                match session.handle_line(&line) {
+                    Ok(Outcome::Output(output)) if output.is_empty() => {}
+                    Ok(Outcome::Output(output)) => println!("{}", output),
+                    Ok(Outcome::Quit) => break,
+                    Err(e) => eprintln!("エラー: {}", e),
+                }
+            }
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
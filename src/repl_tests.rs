@@ -0,0 +1,77 @@
+#[cfg(test)]
+mod tests {
+    use crate::repl::{Outcome, Session};
+
+    fn output(session: &mut Session, line: &str) -> String {
+        match session.handle_line(line).unwrap() {
+            Outcome::Output(output) => output,
+            Outcome::Quit => panic!("予期しない終了: {}", line),
+        }
+    }
+
+    #[test]
+    fn test_variables_and_ans() {
+        let mut session = Session::new();
+
+        assert_eq!(output(&mut session, "x = 3"), "x = 3");
+        assert_eq!(output(&mut session, "x * 2 + 1"), "7");
+        assert_eq!(output(&mut session, "ans * 2"), "14");
+        assert_eq!(output(&mut session, ":vars"), "ans = 14\nx = 3");
+    }
+
+    #[test]
+    fn test_undefined_variable_and_bad_assignment() {
+        let mut session = Session::new();
+
+        assert!(session.handle_line("y + 1").unwrap_err().contains("未定義の変数"));
+        assert!(session.handle_line("sqrt = 2").unwrap_err().contains("関数名"));
+        assert!(session.handle_line("2x = 1").unwrap_err().contains("不正な変数名"));
+    }
+
+    #[test]
+    fn test_precision_command() {
+        let mut session = Session::new();
+
+        output(&mut session, ":precision 3");
+        assert_eq!(output(&mut session, "2 / 3"), "0.667");
+        assert_eq!(output(&mut session, "123456"), "123000");
+
+        output(&mut session, ":precision off");
+        assert_eq!(output(&mut session, "1 / 4"), "0.25");
+        assert!(session.handle_line(":precision 0").is_err());
+    }
+
+    #[cfg(feature = "trig")]
+    #[test]
+    fn test_angle_mode_command() {
+        let mut session = Session::new();
+
+        output(&mut session, ":mode deg");
+        assert_eq!(output(&mut session, "sin(90)"), "1");
+        assert_eq!(output(&mut session, "cos(180)"), "-1");
+
+        output(&mut session, ":mode rad");
+        assert_eq!(output(&mut session, "cos(0)"), "1");
+    }
+
+    #[test]
+    fn test_history_and_quit() {
+        let mut session = Session::new();
+
+        output(&mut session, "1 + 1");
+        output(&mut session, "a = 2");
+        assert_eq!(output(&mut session, ":history"), "   1  1 + 1\n   2  a = 2");
+        assert!(matches!(session.handle_line(":quit"), Ok(Outcome::Quit)));
+        assert!(session.handle_line(":unknown").is_err());
+    }
+
+    #[test]
+    fn test_rounding_command() {
+        let mut session = Session::new();
+
+        assert_eq!(output(&mut session, "round(2.5)"), "3");
+        assert_eq!(output(&mut session, ":rounding half_even"), "端数処理: half_even");
+        assert_eq!(output(&mut session, "round(2.5)"), "2");
+        assert!(session.handle_line(":rounding bankers").is_err());
+    }
+}
@@ -0,0 +1,78 @@
+use serde::{Deserialize, Serialize};
+
+// 端数処理の方法
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "server", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum RoundingMode {
+    // 四捨五入（0.5 は 0 から遠い方へ）
+    #[default]
+    #[serde(alias = "round")]
+    HalfUp,
+    // 偶数丸め（銀行型丸め。0.5 は偶数の方へ）
+    HalfEven,
+    // 負の無限大方向へ
+    Floor,
+    // 正の無限大方向へ
+    Ceil,
+    // 0 方向へ（切り捨て）
+    Truncate,
+}
+
+impl RoundingMode {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::HalfUp => "half_up",
+            Self::HalfEven => "half_even",
+            Self::Floor => "floor",
+            Self::Ceil => "ceil",
+            Self::Truncate => "truncate",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "half_up" | "round" => Some(Self::HalfUp),
+            "half_even" => Some(Self::HalfEven),
+            "floor" => Some(Self::Floor),
+            "ceil" => Some(Self::Ceil),
+            "truncate" => Some(Self::Truncate),
+            _ => None,
+        }
+    }
+}
+
+// リクエストで指定できる丸めの桁数の上限
+pub const MAX_DECIMALS: u32 = 100;
+
+pub fn check_decimals(decimals: u32) -> Result<(), String> {
+    if decimals > MAX_DECIMALS {
+        return Err(format!(
+            "decimals は {} 以下で指定してください: {}",
+            MAX_DECIMALS, decimals
+        ));
+    }
+    Ok(())
+}
+
+// value を小数点以下 decimals 桁に丸める。
+// 0.29 * 100 = 28.999… や 2.675 = 2.67499… のような誤差で結果が変わらないよう、先に 1e-9 単位に揃える
+pub fn round_to(value: f64, decimals: u32, mode: RoundingMode) -> f64 {
+    let scale = i32::try_from(decimals).map_or(f64::INFINITY, |decimals| 10f64.powi(decimals));
+    // 桁が f64 で表せない（あふれる）ほど細かい場合は、丸めても値は変わらない
+    if !(value * scale).is_finite() {
+        return value;
+    }
+    let scaled = match (value * scale * 1e9).round() / 1e9 {
+        normalized if normalized.is_finite() => normalized,
+        _ => value * scale,
+    };
+    let rounded = match mode {
+        RoundingMode::HalfUp => scaled.round(),
+        RoundingMode::HalfEven => scaled.round_ties_even(),
+        RoundingMode::Floor => scaled.floor(),
+        RoundingMode::Ceil => scaled.ceil(),
+        RoundingMode::Truncate => scaled.trunc(),
+    };
+    rounded / scale
+}
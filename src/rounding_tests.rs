@@ -0,0 +1,47 @@
+#[cfg(test)]
+mod tests {
+    use crate::rounding::{check_decimals, round_to, RoundingMode};
+
+    #[test]
+    fn test_rounding_modes() {
+        let cases = [
+            (RoundingMode::HalfUp, [3.0, 2.0, -3.0]),
+            (RoundingMode::HalfEven, [2.0, 2.0, -2.0]),
+            (RoundingMode::Floor, [2.0, 1.0, -3.0]),
+            (RoundingMode::Ceil, [3.0, 2.0, -2.0]),
+            (RoundingMode::Truncate, [2.0, 1.0, -2.0]),
+        ];
+        for (mode, expected) in cases {
+            let actual = [2.5, 1.5, -2.5].map(|value| round_to(value, 0, mode));
+            assert_eq!(actual, expected, "{:?}", mode);
+        }
+    }
+
+    #[test]
+    fn test_half_even_with_decimals() {
+        // 2.675 は f64 では 2.67499… だが、10進数としての値で丸める
+        assert_eq!(round_to(2.675, 2, RoundingMode::HalfEven), 2.68);
+        assert_eq!(round_to(2.665, 2, RoundingMode::HalfEven), 2.66);
+        assert_eq!(round_to(2.675, 2, RoundingMode::HalfUp), 2.68);
+    }
+
+    #[test]
+    fn test_round_to_overflow() {
+        // 1e-9 単位に揃えるとあふれる値や、桁数が f64 で表せない場合はそのまま返す
+        assert_eq!(round_to(1e300, 10, RoundingMode::HalfUp), 1e300);
+        assert_eq!(round_to(1.5, 400, RoundingMode::HalfUp), 1.5);
+        assert_eq!(round_to(1.5, u32::MAX, RoundingMode::Floor), 1.5);
+        assert_eq!(round_to(1e-300, 400, RoundingMode::HalfUp), 1e-300);
+        assert!(check_decimals(100).is_ok());
+        assert!(check_decimals(101).is_err());
+    }
+
+    #[test]
+    fn test_parse_names() {
+        for name in ["half_up", "half_even", "floor", "ceil", "truncate"] {
+            assert_eq!(RoundingMode::parse(name).unwrap().name(), name);
+        }
+        assert_eq!(RoundingMode::parse("round"), Some(RoundingMode::HalfUp));
+        assert_eq!(RoundingMode::parse("bankers"), None);
+    }
+}
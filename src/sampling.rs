@@ -0,0 +1,185 @@
+use crate::rng::Rng;
+use crate::statistics::{self, Summary};
+use serde::{Deserialize, Serialize};
+
+// 1回に生成できる標本の数
+pub const MAX_SAMPLES: usize = 10_000;
+// 二項分布の試行回数・ポアソン分布の平均 × 標本数の上限（1標本あたりの計算量が比例するため）
+const MAX_WORK: f64 = 10_000_000.0;
+// ポアソン分布を分割して生成する単位（exp(-mean) のアンダーフローを避ける）
+const POISSON_CHUNK: f64 = 500.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "server", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum Distribution {
+    Uniform,
+    Normal,
+    Binomial,
+    Poisson,
+    Exponential,
+}
+
+impl Distribution {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Uniform => "uniform",
+            Self::Normal => "normal",
+            Self::Binomial => "binomial",
+            Self::Poisson => "poisson",
+            Self::Exponential => "exponential",
+        }
+    }
+}
+
+// 分布とそのパラメータ
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Sampler {
+    // [min, max) の一様分布
+    Uniform { min: f64, max: f64 },
+    Normal { mean: f64, std_dev: f64 },
+    // 成功確率 probability の試行を trials 回行ったときの成功回数
+    Binomial { trials: u64, probability: f64 },
+    Poisson { mean: f64 },
+    // rate は単位時間あたりの発生回数（平均は 1 / rate）
+    Exponential { rate: f64 },
+}
+
+#[derive(Debug, Serialize)]
+pub struct Samples {
+    pub distribution: &'static str,
+    // 同じ seed を指定すると同じ標本を再現できる
+    pub seed: u64,
+    // 分布の理論上の平均と標準偏差（summary と比べるため）
+    pub expected_mean: f64,
+    pub expected_std_dev: f64,
+    pub samples: Vec<f64>,
+    pub summary: Summary,
+}
+
+impl Sampler {
+    pub fn distribution(self) -> Distribution {
+        match self {
+            Self::Uniform { .. } => Distribution::Uniform,
+            Self::Normal { .. } => Distribution::Normal,
+            Self::Binomial { .. } => Distribution::Binomial,
+            Self::Poisson { .. } => Distribution::Poisson,
+            Self::Exponential { .. } => Distribution::Exponential,
+        }
+    }
+
+    fn validate(self) -> Result<(), String> {
+        let valid = match self {
+            Self::Uniform { min, max } => min.is_finite() && max.is_finite() && min < max,
+            Self::Normal { mean, std_dev } => {
+                mean.is_finite() && std_dev.is_finite() && std_dev > 0.0
+            }
+            Self::Binomial { probability, .. } => (0.0..=1.0).contains(&probability),
+            Self::Poisson { mean } => mean.is_finite() && mean > 0.0,
+            Self::Exponential { rate } => rate.is_finite() && rate > 0.0,
+        };
+        if valid {
+            return Ok(());
+        }
+        Err(match self {
+            Self::Uniform { .. } => "uniform には min < max の有限の値を指定してください",
+            Self::Normal { .. } => "normal の std_dev には正の値を指定してください",
+            Self::Binomial { .. } => "binomial の probability には 0〜1 を指定してください",
+            Self::Poisson { .. } => "poisson の mean には正の値を指定してください",
+            Self::Exponential { .. } => "exponential の rate には正の値を指定してください",
+        }
+        .to_string())
+    }
+
+    // 1標本あたりの乱数の消費量の目安
+    fn work(self) -> f64 {
+        match self {
+            Self::Binomial { trials, .. } => trials as f64,
+            Self::Poisson { mean } => mean,
+            _ => 1.0,
+        }
+    }
+
+    fn moments(self) -> (f64, f64) {
+        match self {
+            Self::Uniform { min, max } => ((min + max) / 2.0, (max - min) / 12f64.sqrt()),
+            Self::Normal { mean, std_dev } => (mean, std_dev),
+            Self::Binomial {
+                trials,
+                probability,
+            } => {
+                let n = trials as f64;
+                (
+                    n * probability,
+                    (n * probability * (1.0 - probability)).sqrt(),
+                )
+            }
+            Self::Poisson { mean } => (mean, mean.sqrt()),
+            Self::Exponential { rate } => (1.0 / rate, 1.0 / rate),
+        }
+    }
+
+    pub fn sample(self, rng: &mut Rng) -> f64 {
+        match self {
+            Self::Uniform { min, max } => min + (max - min) * rng.next_f64(),
+            Self::Normal { mean, std_dev } => {
+                // ボックス＝ミュラー法（ln(0) を避けるため 1 - U を使う）
+                let u1 = 1.0 - rng.next_f64();
+                let u2 = rng.next_f64();
+                let z = (-2.0 * u1.ln()).sqrt() * (std::f64::consts::TAU * u2).cos();
+                mean + std_dev * z
+            }
+            Self::Binomial {
+                trials,
+                probability,
+            } => (0..trials).filter(|_| rng.next_f64() < probability).count() as f64,
+            Self::Poisson { mean } => {
+                // 独立なポアソン分布の和もポアソン分布になるため、平均を分割して足し合わせる
+                let mut remaining = mean;
+                let mut total = 0u64;
+                while remaining > 0.0 {
+                    let chunk = remaining.min(POISSON_CHUNK);
+                    remaining -= chunk;
+                    // Knuth の方法: 一様乱数の積が exp(-chunk) を下回るまでの回数
+                    let limit = (-chunk).exp();
+                    let mut product = rng.next_f64();
+                    while product > limit {
+                        total += 1;
+                        product *= rng.next_f64();
+                    }
+                }
+                total as f64
+            }
+            // 逆関数法
+            Self::Exponential { rate } => -(1.0 - rng.next_f64()).ln() / rate,
+        }
+    }
+}
+
+pub fn generate(sampler: Sampler, count: usize, seed: u64) -> Result<Samples, String> {
+    if count == 0 || count > MAX_SAMPLES {
+        return Err(format!(
+            "標本の数は 1〜{} で指定してください: {}",
+            MAX_SAMPLES, count
+        ));
+    }
+    sampler.validate()?;
+    if sampler.work() * count as f64 > MAX_WORK {
+        return Err(format!(
+            "計算量が多すぎます: {} の標本数とパラメータを小さくしてください",
+            sampler.distribution().name()
+        ));
+    }
+
+    let mut rng = Rng::new(seed);
+    let samples: Vec<f64> = (0..count).map(|_| sampler.sample(&mut rng)).collect();
+    let (expected_mean, expected_std_dev) = sampler.moments();
+    Ok(Samples {
+        distribution: sampler.distribution().name(),
+        seed,
+        expected_mean,
+        expected_std_dev,
+        summary: statistics::summarize(&samples)?,
+        samples,
+    })
+}
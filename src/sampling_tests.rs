@@ -0,0 +1,84 @@
+#[cfg(test)]
+mod tests {
+    use crate::sampling::{generate, Sampler};
+
+    #[test]
+    fn test_generate_distributions() {
+        let uniform = generate(
+            Sampler::Uniform {
+                min: 5.0,
+                max: 10.0,
+            },
+            1000,
+            1,
+        )
+        .unwrap();
+        assert_eq!(uniform.distribution, "uniform");
+        assert_eq!(uniform.summary.count, 1000);
+        assert!(uniform.samples.iter().all(|x| (5.0..10.0).contains(x)));
+        assert_eq!(uniform.expected_mean, 7.5);
+
+        let normal = Sampler::Normal {
+            mean: 100.0,
+            std_dev: 15.0,
+        };
+        let result = generate(normal, 10_000, 7).unwrap();
+        assert!((result.summary.mean - 100.0).abs() < 1.0);
+        assert!((result.summary.sample_std_dev.unwrap() - 15.0).abs() < 1.0);
+        assert_eq!(result.samples, generate(normal, 10_000, 7).unwrap().samples);
+
+        let binomial = Sampler::Binomial {
+            trials: 10,
+            probability: 0.3,
+        };
+        let result = generate(binomial, 2000, 3).unwrap();
+        assert!(result
+            .samples
+            .iter()
+            .all(|x| x.fract() == 0.0 && (0.0..=10.0).contains(x)));
+        assert!((result.summary.mean - 3.0).abs() < 0.2);
+
+        // 分割して生成する大きな平均
+        let poisson = generate(Sampler::Poisson { mean: 1200.0 }, 500, 5).unwrap();
+        assert!((poisson.summary.mean - 1200.0).abs() < 10.0);
+
+        let exponential = generate(Sampler::Exponential { rate: 0.5 }, 5000, 9).unwrap();
+        assert!(exponential.samples.iter().all(|x| *x >= 0.0));
+        assert!((exponential.summary.mean - 2.0).abs() < 0.15);
+    }
+
+    #[test]
+    fn test_generate_invalid() {
+        assert!(generate(Sampler::Uniform { min: 1.0, max: 1.0 }, 10, 0).is_err());
+        assert!(generate(
+            Sampler::Normal {
+                mean: 0.0,
+                std_dev: -1.0
+            },
+            10,
+            0
+        )
+        .is_err());
+        assert!(generate(
+            Sampler::Binomial {
+                trials: 10,
+                probability: 1.5
+            },
+            10,
+            0
+        )
+        .is_err());
+        assert!(generate(Sampler::Poisson { mean: 0.0 }, 10, 0).is_err());
+        assert!(generate(Sampler::Exponential { rate: 1.0 }, 0, 0).is_err());
+        assert!(generate(Sampler::Exponential { rate: 1.0 }, 10_001, 0).is_err());
+        assert!(generate(
+            Sampler::Binomial {
+                trials: 1_000_000,
+                probability: 0.5
+            },
+            100,
+            0
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,2998 @@
+use crate::admission::Admission;
+use crate::average::{self, WeightedValue};
+use crate::build_info;
+use crate::business::{self, Calculation};
+#[cfg(any(feature = "stats", feature = "matrix", feature = "symbolic"))]
+use crate::calculator::SafeProfile;
+use crate::calculator::{AngleMode, Calculator, CompiledExpr, SI_PREFIXES};
+#[cfg(feature = "bignum")]
+use crate::combinatorics::{self, CombinatoricsCalculation, DigitLimits};
+use crate::compare::{self, DEFAULT_ABS_TOLERANCE, DEFAULT_REL_TOLERANCE};
+use crate::config::{Config, ADMIN_TOOLS};
+use crate::constraints;
+#[cfg(feature = "units")]
+use crate::cooking;
+#[cfg(feature = "finance")]
+use crate::currency::{self, RateCache};
+use crate::dataset;
+#[cfg(feature = "stats")]
+use crate::dataset::Table;
+#[cfg(feature = "datetime")]
+use crate::datetime;
+use crate::duration;
+use crate::electronics::{self, Component, ElectronicsCalculation, Magnitude};
+use crate::exact;
+use crate::formulas::FormulaStore;
+use crate::growth::{self, GrowthCalculation};
+use crate::health::HealthReport;
+use crate::history::{self, HistoryStore};
+use crate::humanize::{self, Locale};
+use crate::i18n;
+#[cfg(feature = "stats")]
+use crate::image;
+use crate::image::Theme;
+use crate::kinematics;
+use crate::markdown::{self, OutputFormat};
+#[cfg(feature = "matrix")]
+use crate::matrix;
+use crate::matrix::{Matrix, MatrixOperation};
+use crate::number_format::{
+    check_precision, format_in_base, format_number, value_warnings, FormatOptions, Notation,
+    NumberLocale,
+};
+use crate::number_theory;
+use crate::percentage;
+use crate::proportion;
+use crate::rounding::{check_decimals, round_to, RoundingMode};
+#[cfg(feature = "stats")]
+use crate::sampling::{self, Distribution, Sampler};
+use crate::sheet;
+#[cfg(feature = "stats")]
+use crate::smoothing::{self, SmoothingMethod};
+#[cfg(feature = "symbolic")]
+use crate::solver;
+#[cfg(feature = "stats")]
+use crate::statistics;
+use crate::tax::{self, TaxMode, TaxOptions, TaxScope};
+use crate::tool_error::{self, ToolError};
+use crate::units;
+#[cfg(feature = "matrix")]
+use crate::uploads;
+use crate::uploads::UploadStore;
+use crate::value::{self, Value};
+use rmcp::{
+    handler::server::tool::ToolCallContext,
+    model::{
+        AnnotateAble, CallToolRequestParam, CallToolResult, Content, Implementation,
+        InitializeResult, ListResourcesResult, ListToolsResult, PaginatedRequestParam,
+        ProtocolVersion, RawResource, ReadResourceRequestParam, ReadResourceResult,
+        ResourceContents, ServerCapabilities, Tool,
+    },
+    service::RequestContext,
+    tool, Error as McpError, Peer, RoleServer, ServerHandler,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::any::Any;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock, RwLockReadGuard};
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+
+// feature が無効なビルドでは公開しないツール。メソッドと引数の型は残して本体がエラーを返すようにし、
+// 引数のうち無効な feature のモジュールの型を使う項目は省く
+const FEATURE_TOOLS: &[(&str, bool)] = &[
+    ("statistics", cfg!(feature = "stats")),
+    ("histogram", cfg!(feature = "stats")),
+    ("generate_samples", cfg!(feature = "stats")),
+    ("fit_distribution", cfg!(feature = "stats")),
+    ("smooth", cfg!(feature = "stats")),
+    ("regression", cfg!(feature = "stats")),
+    ("convert_ingredient", cfg!(feature = "units")),
+    ("combinatorics", cfg!(feature = "bignum")),
+    ("matrix", cfg!(feature = "matrix")),
+    ("rearrange", cfg!(feature = "symbolic")),
+    ("solve_system", cfg!(feature = "symbolic")),
+    ("convert_currency", cfg!(feature = "finance")),
+    ("tax", cfg!(feature = "finance")),
+    ("business_math", cfg!(feature = "finance")),
+    ("growth", cfg!(feature = "finance")),
+    ("convert_timezone", cfg!(feature = "datetime")),
+    ("time_between", cfg!(feature = "datetime")),
+    ("age", cfg!(feature = "datetime")),
+];
+
+// calculate の構文と式の大きさの上限を公開するリソース
+pub const SYNTAX_URI: &str = "calc://syntax";
+
+#[cfg(feature = "stats")]
+const DATA_SOURCE_ERROR: &str = "データは配列、csv、resource_uri のいずれか1つで指定してください";
+
+// 式をどう解釈したかを表す中置記法（200 + 10% は 200 + 200 * 0.1、|x| は abs(x)、
+// 1/2*x は 1 / 2 * x）。空白を除いて入力と同じなら None
+fn normalized_expression(calculator: &Calculator, expression: &str) -> Option<String> {
+    let normalized = calculator.compile(expression).ok()?.to_infix();
+    let strip = |text: &str| text.split_whitespace().collect::<String>();
+    (strip(&normalized) != strip(expression)).then_some(normalized)
+}
+
+// calculate の audit で返す「- 監査:」の行。ベクトル・行列や単位を含む式はコンパイルできないため記録しない
+fn audit_line(calculator: &Calculator, expression: &str) -> Result<String, String> {
+    let (_, steps) = calculator
+        .compile(expression)
+        .and_then(|compiled| compiled.audit(&HashMap::new()))?;
+    let steps = serde_json::to_string(&steps).map_err(|e| e.to_string())?;
+    Ok(format!("- 監査: {}", steps))
+}
+
+// ツールの実行中のパニックを変換したエラーの接頭辞
+pub const INTERNAL_ERROR: &str = "INTERNAL_ERROR";
+
+// パニックを呼び出し元へのエラーにする（ほかのセッションの処理は続ける）
+fn internal_error(tool: &str, message: &str) -> McpError {
+    eprintln!(
+        "ツール {} の実行中に内部エラーが発生しました: {}",
+        tool, message
+    );
+    McpError::internal_error(
+        format!(
+            "{}: ツール {} の実行中に内部エラーが発生しました: {}",
+            INTERNAL_ERROR, tool, message
+        ),
+        Some(json!({ "code": INTERNAL_ERROR, "tool": tool, "message": message })),
+    )
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "不明なパニック".to_string()
+    }
+}
+
+// ツールが返したエラーに、コード・メッセージ・ヒントを JSON にした2つめの内容を加える
+// （1つめの内容はツールが返した文言のまま）
+fn with_error_details(mut result: CallToolResult, calculator: &Calculator) -> CallToolResult {
+    if result.is_error != Some(true) {
+        return result;
+    }
+    let Some(message) = result
+        .content
+        .first()
+        .and_then(|content| content.as_text())
+        .map(|text| text.text.clone())
+    else {
+        return result;
+    };
+    let error = ToolError::classify(&message, calculator);
+    if let Ok(json) = serde_json::to_string_pretty(&json!({ "error": error })) {
+        result.content.push(Content::text(json));
+    }
+    result
+}
+
+// 成功した結果の JSON に markdown か csv があれば、Markdown を表示できるクライアントや
+// 表計算ソフトにそのまま渡せるよう、その表を2つめの内容として加える
+fn with_table(mut result: CallToolResult) -> CallToolResult {
+    if result.is_error == Some(true) {
+        return result;
+    }
+    let table = result
+        .content
+        .first()
+        .and_then(|content| content.as_text())
+        .and_then(|text| serde_json::from_str::<serde_json::Value>(&text.text).ok())
+        .and_then(|json| {
+            [OutputFormat::Markdown, OutputFormat::Csv]
+                .into_iter()
+                .find_map(|format| json.get(format.field()?)?.as_str().map(str::to_string))
+        });
+    if let Some(table) = table {
+        result.content.push(Content::text(table));
+    }
+    result
+}
+
+// 成功した結果の JSON の image に画像のデータ（base64）があれば、MCP の画像の内容として加え、
+// 1つめの内容からはデータを取り除く（幅・高さなどはそのまま残す）
+fn with_image(mut result: CallToolResult) -> CallToolResult {
+    if result.is_error == Some(true) {
+        return result;
+    }
+    let Some(mut json) = result
+        .content
+        .first()
+        .and_then(|content| content.as_text())
+        .and_then(|text| serde_json::from_str::<serde_json::Value>(&text.text).ok())
+    else {
+        return result;
+    };
+    let Some(image) = json
+        .get_mut("image")
+        .and_then(|image| image.as_object_mut())
+    else {
+        return result;
+    };
+    let (Some(serde_json::Value::String(data)), Some(mime_type)) = (
+        image.remove("data"),
+        image.get("mime_type").and_then(|mime| mime.as_str()),
+    ) else {
+        return result;
+    };
+    let image = Content::image(data, mime_type);
+    if let Ok(text) = serde_json::to_string_pretty(&json) {
+        result.content[0] = Content::text(text);
+        result.content.push(image);
+    }
+    result
+}
+
+// output が markdown / csv なら、JSON にその形式の表（markdown / csv）を加える
+fn add_table(
+    json: &mut serde_json::Value,
+    output: Option<OutputFormat>,
+    table: impl FnOnce() -> markdown::Table,
+) {
+    let output = output.unwrap_or_default();
+    if let Some(field) = output.field() {
+        json[field] = json!(table().render(output));
+    }
+}
+
+// 表を返すツールの出力
+fn table_json(
+    value: &impl Serialize,
+    output: Option<OutputFormat>,
+    table: impl FnOnce() -> markdown::Table,
+) -> Result<String, String> {
+    if output.unwrap_or_default().field().is_none() {
+        return serde_json::to_string_pretty(value).map_err(|e| e.to_string());
+    }
+    let mut json = serde_json::to_value(value).map_err(|e| e.to_string())?;
+    add_table(&mut json, output, table);
+    serde_json::to_string_pretty(&json).map_err(|e| e.to_string())
+}
+
+const AUDIT_UNSUPPORTED: &str = "audit は実数の式だけに対応しているため、演算の記録は省略しました";
+
+// calculate_batch などの構造化した出力での値。実数は数値、ベクトル・行列は配列、
+// 複素数は {"re", "im"}、量は数値（単位は別の項目）、真偽値は true / false
+fn value_json(value: &Value) -> serde_json::Value {
+    match value {
+        Value::Complex(re, im) => json!({ "re": re, "im": im }),
+        Value::List(list) => json!(list),
+        Value::Matrix(matrix) => json!(matrix),
+        Value::Quantity(value, _) => json!(value),
+        Value::Boolean(value) => json!(value),
+        real => json!(real.real()),
+    }
+}
+
+#[derive(Clone)]
+pub struct CalculatorService {
+    config: Arc<RwLock<Config>>,
+    // 接続中のクライアント（ツール一覧の変更通知先。通知に失敗したものは取り除く）
+    // 番号は接続の順の通し番号
+    peers: Arc<Mutex<BTreeMap<usize, Peer<RoleServer>>>>,
+    next_peer: Arc<AtomicUsize>,
+    started_at: Instant,
+    // 初期化が完了したセッション数
+    sessions: Arc<AtomicUsize>,
+    // convert_currency が URL から取得したレート
+    #[cfg(feature = "finance")]
+    rate_cache: RateCache,
+    // upload_data で受け取ったデータ（MCP のリソースとしても公開する）
+    uploads: UploadStore,
+    // set_options で設定したこのセッションの既定値
+    options: Arc<RwLock<SessionOptions>>,
+    // calculate と calculate_batch の計算履歴（calc://history.csv などで公開する）
+    history: HistoryStore,
+    // load_variables で読み込んだ変数（calculate などの式から参照できる）
+    variables: Arc<RwLock<BTreeMap<String, f64>>>,
+    // recalculate で保存した数式
+    formulas: Arc<Mutex<FormulaStore>>,
+    // ツールの呼び出しの同時実行数の制限（設定の再読み込みで上限が変わると作り直す）
+    admission: Arc<RwLock<Arc<Admission>>>,
+}
+
+// calculate などの指定を省略したときに使う既定値（リクエストの指定が優先する）
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct SessionOptions {
+    pub angle_mode: AngleMode,
+    pub rounding_mode: RoundingMode,
+    pub notation: Notation,
+    pub precision: Option<usize>,
+    pub group_digits: bool,
+    pub locale: NumberLocale,
+}
+
+impl Default for CalculatorService {
+    fn default() -> Self {
+        Self::new(Config::default())
+    }
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct CalculateRequest {
+    #[schemars(
+        description = "計算する数式（例: \"2 + 3 * 4\", \"sqrt(25)\", \"sin(1.57)\"）。サポート: 四則演算(+, -, *, /)、べき乗(^)、括弧、絶対値(|x|)、平方根(√x)、数学関数(sqrt, cbrt, root(x, n), abs, sin, cos, tan, exp, expm1, ln, ln1p, log10, log2, log(x, base), gamma, lgamma, erf, erfc, hypot(x, y), clamp(x, lo, hi), atan2(y, x), min, max, avg（引数2個以上）, lerp(a, b, t), maprange(x, in_lo, in_hi, out_lo, out_hi), round)、ベクトル・行列のリテラル（[[1, 2], [3, 4]] * [5, 6]、関数 det, inv, transpose, trace, dot, cross, norm）、設定ファイルで定義された関数と定数"
+    )]
+    pub expression: String,
+    #[schemars(description = "結果を丸める小数点以下の桁数（100 以下。省略時は丸めない）")]
+    pub decimals: Option<u32>,
+    #[schemars(
+        description = "端数処理（half_up: 四捨五入（既定）, half_even: 偶数丸め, floor, ceil, truncate）。decimals による結果の丸めと round() に適用"
+    )]
+    pub rounding_mode: Option<RoundingMode>,
+    #[schemars(
+        description = "true にすると数値リテラルの有効数字（12.0 は3桁、1200 は2桁）を考慮し、結果を正しい桁数に丸めて根拠とあわせて返します"
+    )]
+    #[serde(default)]
+    pub sig_figs: bool,
+    #[schemars(
+        description = "結果の表記（auto: 絶対値が 1e-6 以上 1e21 未満なら通常の表記で、それ以外は指数表記（既定）, plain: 常に通常の表記, scientific: 常に指数表記）"
+    )]
+    pub notation: Option<Notation>,
+    #[schemars(
+        description = "表示する小数点以下の桁数（指数表記では仮数の桁数）。100 以下で、足りない桁は 0 で埋めます。省略時は値を再現できる最短の桁数です"
+    )]
+    pub precision: Option<usize>,
+    #[schemars(
+        description = "数値リテラルの SI 接頭辞（4.7k = 4700, 100m = 0.1, 2.2M, 5u など）を受け付けるか（省略時は設定ファイルの [syntax] si_prefixes、既定は無効）"
+    )]
+    pub si_prefixes: Option<bool>,
+    #[schemars(
+        description = "true にすると暗黙の掛け算（2x, 2(3 + 4)）、優先順位があいまいな式（1/2x）、× や ÷ などの Unicode の記号をエラーにします。省略時は設定ファイルの [syntax] strict（既定は false）で、false では補正して計算し、加えた補正を「- 警告:」の行で返します"
+    )]
+    pub strict: Option<bool>,
+    #[schemars(
+        description = "true にするとあいまいな式（1/2x の優先順位、log(100) の底、SI 接頭辞が有効なときの 100m のミリとメートル）を推測して計算せず、NEEDS_CLARIFICATION のエラーで解釈ごとに書き直した式を返します。省略時は設定ファイルの [syntax] clarify（既定は false）"
+    )]
+    pub clarify: Option<bool>,
+    #[schemars(
+        description = "true にすると sqrt や abs などの数値の関数にベクトル・行列を渡したとき要素ごとに計算します（sqrt([4, 9]) は [2, 3]）。省略時は false で、エラーになります"
+    )]
+    pub elementwise: Option<bool>,
+    #[schemars(
+        description = "結果が整数の場合に、あわせて表示する基数のリスト（2〜36。例: [2, 16] で 0b100000000 と 0x100 も返します）"
+    )]
+    pub output_bases: Option<Vec<u32>>,
+    #[schemars(
+        description = "true にすると結果の整数部を3桁ごとに区切って表示し（1,234,567.89）、区切らない値も「- 値:」の行で返します"
+    )]
+    pub group_digits: Option<bool>,
+    #[schemars(
+        description = "group_digits の桁区切りと小数点の慣習（en: 1,234,567.89（既定）, ja: en と同じ, de: 1.234.567,89, fr: 1 234 567,89）"
+    )]
+    pub locale: Option<NumberLocale>,
+    #[schemars(
+        description = "true にすると、行った演算（add, multiply, function など）を評価した順に、被演算子と途中の結果とあわせて「- 監査:」の行に JSON の配列で返します（監査記録用。実数の式のみ）"
+    )]
+    #[serde(default)]
+    pub audit: bool,
+    #[schemars(
+        description = "true にすると式を評価せず、解析した結果から見積もった計算の量（演算の回数 operations、関数の呼び出しの回数 function_calls、式の木の深さ depth、計算量のクラス complexity、行列のべき乗などの繰り返しの回数 loops）をJSONで返します。大きな予算で実行するかを決めるのに使えます"
+    )]
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CalculateBatchRequest {
+    #[schemars(description = "計算する数式の配列（calculate と同じ構文。互いに独立に評価します）")]
+    pub expressions: Vec<String>,
+    #[schemars(description = "結果を丸める小数点以下の桁数（100 以下。省略時は丸めない）")]
+    pub decimals: Option<u32>,
+    #[schemars(
+        description = "端数処理（half_up: 四捨五入（既定）, half_even: 偶数丸め, floor, ceil, truncate）"
+    )]
+    pub rounding_mode: Option<RoundingMode>,
+    #[schemars(description = "1式あたりの制限時間（ミリ秒。既定と上限は設定の batch_timeout_ms）")]
+    pub timeout_ms: Option<u64>,
+    #[schemars(
+        description = "出力形式（json（既定）, markdown / csv: 各式の結果を Markdown の表 / CSV にしたものも返します）"
+    )]
+    pub output: Option<OutputFormat>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CompareRequest {
+    #[schemars(description = "検証する数式（例: \"0.1 + 0.2\"）")]
+    pub expression: String,
+    #[schemars(description = "期待する値、または比較する数式（例: \"0.3\", \"3 / 10\"）")]
+    pub expected: String,
+    #[schemars(description = "絶対誤差の許容値（既定: 1e-9）")]
+    pub abs_tolerance: Option<f64>,
+    #[schemars(description = "相対誤差の許容値（既定: 1e-9）")]
+    pub rel_tolerance: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct DurationCalcRequest {
+    #[schemars(
+        description = "時間の式（例: \"2:45:30 + 1:30:00\", \"7:30 * 5\", \"6h / 45m\"）。h:mm:ss, h:mm, 1h30m, 45分 などの表記が使えます"
+    )]
+    pub expression: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ConvertTimezoneRequest {
+    #[schemars(
+        description = "変換する日時（RFC3339, \"2024-01-15 09:00\", または \"09:00\" / \"9am\" のような時刻のみ）"
+    )]
+    pub time: String,
+    #[schemars(
+        description = "time のタイムゾーン（IANA名 Asia/Tokyo または略称 JST, PST など。time がオフセット付きなら無視されます）"
+    )]
+    pub from_zone: String,
+    #[schemars(description = "変換先のタイムゾーン")]
+    pub to_zone: String,
+    #[schemars(
+        description = "基準時刻（RFC3339。省略時は現在時刻）。時刻のみの日付と hours_from_now に使います"
+    )]
+    pub now: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct TimeBetweenRequest {
+    #[schemars(description = "開始日時（convert_timezone の time と同じ形式）")]
+    pub start: String,
+    #[schemars(description = "開始日時のタイムゾーン")]
+    pub start_zone: String,
+    #[schemars(description = "終了日時（省略時は基準時刻）")]
+    pub end: Option<String>,
+    #[schemars(description = "終了日時のタイムゾーン（省略時は start_zone）")]
+    pub end_zone: Option<String>,
+    #[schemars(description = "基準時刻（RFC3339。省略時は現在時刻）")]
+    pub now: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct AgeRequest {
+    #[schemars(description = "生年月日（YYYY-MM-DD）")]
+    pub birthdate: String,
+    #[schemars(description = "基準日（YYYY-MM-DD。省略時は今日）")]
+    pub reference: Option<String>,
+    #[schemars(description = "「今日」を決めるタイムゾーン（既定は UTC）")]
+    pub zone: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct HumanizeRequest {
+    #[schemars(description = "人が読みやすい表記に変換する数値（例: 1234567）")]
+    pub value: Option<f64>,
+    #[schemars(description = "数値に戻す表記（例: \"1.23M\", \"123.4万\", \"1536 MiB\"）")]
+    pub text: Option<String>,
+    #[schemars(description = "単位系（en: K/M/B/T（既定）, ja: 万/億/兆/京）")]
+    #[serde(default)]
+    pub locale: Locale,
+    #[schemars(
+        description = "true ならバイト数として扱います（KiB, MiB, ...。解析時は KB, MB なども可）"
+    )]
+    #[serde(default)]
+    pub bytes: bool,
+    #[schemars(description = "小数点以下の最大桁数（既定は 2、15 以下）")]
+    pub decimals: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SheetRequest {
+    #[schemars(
+        description = "セルの行の配列。各セルは数値、= で始まる数式（例: \"=A1 * B1\"）、または空（\"\" か null）。数式からは A1 形式（列は A, B, ...、行は 1 から）で他のセルを参照でき、空のセルは 0 として扱います"
+    )]
+    pub cells: Vec<Vec<serde_json::Value>>,
+    #[schemars(description = "結果を丸める小数点以下の桁数（100 以下。省略時は丸めない）")]
+    pub decimals: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct MatrixRequest {
+    #[schemars(
+        description = "計算の種類（eigen: 固有値と固有ベクトル, lu: LU分解, qr: QR分解, cholesky: コレスキー分解, condition_number: 条件数）"
+    )]
+    pub operation: MatrixOperation,
+    #[schemars(
+        description = "行の配列で表した行列（例: [[2, 1], [1, 2]]）。要素数の上限は既定で 2500"
+    )]
+    pub matrix: Option<Matrix>,
+    #[schemars(
+        description = "matrix の代わりに upload_data で送った行列の URI（upload://<id>）。内容は JSON の行の配列か、1行ずつカンマ・空白区切りの数値"
+    )]
+    pub matrix_uri: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct UploadDataRequest {
+    #[schemars(description = "続きを送るアップロードのID（最初のチャンクでは省略）")]
+    pub upload_id: Option<String>,
+    #[schemars(description = "追加するデータ（テキスト）")]
+    pub chunk: String,
+    #[schemars(description = "最後のチャンクなら true（既定）。false の場合は続きを受け付けます")]
+    pub done: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct StatisticsRequest {
+    #[schemars(description = "数値の配列")]
+    pub values: Option<Vec<f64>>,
+    #[schemars(
+        description = "values の代わりに渡す CSV テキスト（1行目が数値でなければヘッダー）"
+    )]
+    pub csv: Option<String>,
+    #[schemars(
+        description = "values の代わりに、upload_data で送った CSV の URI（upload://<id>）"
+    )]
+    pub resource_uri: Option<String>,
+    #[schemars(description = "CSV の列（ヘッダー名または1から数えた列番号。1列だけなら省略可）")]
+    pub column: Option<String>,
+    #[schemars(
+        description = "出力形式（json（既定）, markdown / csv: 統計量を Markdown の表 / CSV にしたものも返します）"
+    )]
+    pub output: Option<OutputFormat>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct HistogramRequest {
+    #[schemars(description = "数値の配列")]
+    pub values: Option<Vec<f64>>,
+    #[schemars(description = "values の代わりに渡す CSV テキスト")]
+    pub csv: Option<String>,
+    #[schemars(
+        description = "values の代わりに、upload_data で送った CSV の URI（upload://<id>）"
+    )]
+    pub resource_uri: Option<String>,
+    #[schemars(description = "CSV の列（ヘッダー名または1から数えた列番号。1列だけなら省略可）")]
+    pub column: Option<String>,
+    #[schemars(
+        description = "等幅の区間の数（省略時はスタージェスの公式で決めます。edges と同時には指定できません）"
+    )]
+    pub bins: Option<usize>,
+    #[schemars(description = "区間の境界の昇順の配列（例: [0, 60, 80, 100]）")]
+    pub edges: Option<Vec<f64>>,
+    #[serde(default)]
+    #[schemars(description = "true なら ASCII の棒グラフ（chart）も返します")]
+    pub chart: bool,
+    #[schemars(
+        description = "出力形式（json（既定）, markdown / csv: 度数分布を Markdown の表 / CSV にしたものも返します）"
+    )]
+    pub output: Option<OutputFormat>,
+    #[serde(default)]
+    #[schemars(
+        description = "true なら棒グラフを PNG 画像（MCP の画像の内容）でも返します。画像を表示できないクライアント向けに ASCII の棒グラフ（chart）も返します"
+    )]
+    pub image: bool,
+    #[schemars(description = "画像の幅（ピクセル。64〜2000、既定は 640）")]
+    pub width: Option<u32>,
+    #[schemars(description = "画像の高さ（ピクセル。64〜2000、既定は 400）")]
+    pub height: Option<u32>,
+    #[schemars(description = "画像の配色（light（既定）, dark）")]
+    pub theme: Option<Theme>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct FitDistributionRequest {
+    #[schemars(description = "数値の配列")]
+    pub values: Option<Vec<f64>>,
+    #[schemars(description = "values の代わりに渡す CSV テキスト")]
+    pub csv: Option<String>,
+    #[schemars(
+        description = "values の代わりに、upload_data で送った CSV の URI（upload://<id>）"
+    )]
+    pub resource_uri: Option<String>,
+    #[schemars(description = "CSV の列（ヘッダー名または1から数えた列番号。1列だけなら省略可）")]
+    pub column: Option<String>,
+    #[cfg(feature = "stats")]
+    #[schemars(description = "当てはめる分布（normal, exponential, uniform。省略時はすべて）")]
+    pub distributions: Option<Vec<Distribution>>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SmoothRequest {
+    #[schemars(description = "数値の配列（時系列の順）")]
+    pub values: Option<Vec<f64>>,
+    #[schemars(description = "values の代わりに渡す CSV テキスト")]
+    pub csv: Option<String>,
+    #[schemars(
+        description = "values の代わりに、upload_data で送った CSV の URI（upload://<id>）"
+    )]
+    pub resource_uri: Option<String>,
+    #[schemars(description = "CSV の列（ヘッダー名または1から数えた列番号。1列だけなら省略可）")]
+    pub column: Option<String>,
+    #[cfg(feature = "stats")]
+    #[schemars(
+        description = "simple: 単純移動平均, exponential: 指数移動平均, rolling: 移動平均・標準偏差・最小・最大・合計"
+    )]
+    pub method: SmoothingMethod,
+    #[schemars(
+        description = "移動平均をとる個数（exponential では alpha の代わりに指定でき、alpha = 2 / (window + 1)）"
+    )]
+    pub window: Option<usize>,
+    #[schemars(
+        description = "exponential の平滑化係数（0 < alpha <= 1。大きいほど直近の値を重視）"
+    )]
+    pub alpha: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct RegressionRequest {
+    #[schemars(description = "説明変数の配列")]
+    pub x: Option<Vec<f64>>,
+    #[schemars(description = "目的変数の配列（x と同じ個数）")]
+    pub y: Option<Vec<f64>>,
+    #[schemars(description = "x, y の代わりに渡す CSV テキスト")]
+    pub csv: Option<String>,
+    #[schemars(description = "x, y の代わりに、upload_data で送った CSV の URI（upload://<id>）")]
+    pub resource_uri: Option<String>,
+    #[schemars(description = "x にする CSV の列（ヘッダー名または列番号）")]
+    pub x_column: Option<String>,
+    #[schemars(description = "y にする CSV の列（ヘッダー名または列番号）")]
+    pub y_column: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct WeightedAverageRequest {
+    #[schemars(
+        description = "値と重みの組（例: [{\"value\": 80, \"weight\": 30}, ...]）。重みは 0 以上"
+    )]
+    pub items: Vec<WeightedValue>,
+    #[schemars(
+        description = "true なら重みの合計で割ります。false（既定）の場合、重みの合計は 1 または 100 でなければなりません"
+    )]
+    #[serde(default)]
+    pub normalize: bool,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct PercentageRequest {
+    #[schemars(description = "変化前の値（to とあわせて変化を求める）")]
+    pub from: Option<f64>,
+    #[schemars(description = "変化後の値")]
+    pub to: Option<f64>,
+    #[schemars(
+        description = "from, to が率（5 = 5%）の場合は true。差をパーセントポイントとして返します"
+    )]
+    #[serde(default)]
+    pub values_are_percent: bool,
+    #[schemars(description = "割合（%）。of とあわせて of の percent% を求める")]
+    pub percent: Option<f64>,
+    #[schemars(description = "割合を掛ける元の値")]
+    pub of: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ProportionRequest {
+    #[schemars(description = "比例式 a / b = c / d の a（求める項は省略）")]
+    pub a: Option<f64>,
+    #[schemars(description = "比例式 a / b = c / d の b（求める項は省略）")]
+    pub b: Option<f64>,
+    #[schemars(description = "比例式 a / b = c / d の c（求める項は省略）")]
+    pub c: Option<f64>,
+    #[schemars(description = "比例式 a / b = c / d の d（求める項は省略）")]
+    pub d: Option<f64>,
+    #[schemars(description = "拡大・縮小の基準（例: 4人分のレシピなら 4）。a〜d の代わりに指定")]
+    pub scale_from: Option<f64>,
+    #[schemars(description = "拡大・縮小後の量（例: 7人分にするなら 7）")]
+    pub scale_to: Option<f64>,
+    #[schemars(description = "scale_from を基準にした分量（例: {\"flour_g\": 200, \"eggs\": 2}）")]
+    pub quantities: Option<BTreeMap<String, f64>>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ConvertIngredientRequest {
+    #[schemars(description = "換算する量")]
+    pub amount: f64,
+    #[schemars(
+        description = "換算元の単位（例: \"cup\", \"tbsp\", \"tsp\", \"cupJP\", \"mL\", \"g\", \"oz\"）"
+    )]
+    pub from: String,
+    #[schemars(description = "換算先の単位（例: \"g\"）")]
+    pub to: String,
+    #[schemars(
+        description = "材料名（例: \"flour\", \"sugar\", \"butter\"）。体積と質量の換算に使う"
+    )]
+    pub ingredient: Option<String>,
+    #[schemars(description = "材料の密度（g/mL）。指定すると ingredient の密度より優先する")]
+    pub density: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ConvertCurrencyRequest {
+    #[schemars(description = "換算する金額")]
+    pub amount: f64,
+    #[schemars(description = "換算元の通貨コード（例: \"USD\"）")]
+    pub from: String,
+    #[schemars(description = "換算先の通貨コード（例: \"JPY\"）")]
+    pub to: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct TaxRequest {
+    #[schemars(description = "明細ごとの金額（1件でも可）")]
+    pub amounts: Vec<f64>,
+    #[schemars(description = "amounts が税抜（exclusive、既定）か税込（inclusive）か")]
+    #[serde(default)]
+    pub mode: TaxMode,
+    #[schemars(description = "税率（例: 0.1）。省略時は rate_name または設定の既定税率")]
+    pub rate: Option<f64>,
+    #[schemars(description = "設定ファイルで定義した税率の名前（例: \"standard\", \"reduced\"）")]
+    pub rate_name: Option<String>,
+    #[schemars(
+        description = "端数処理（half_up: 四捨五入, half_even: 偶数丸め, floor: 切り捨て, ceil: 切り上げ, truncate: 0方向）。省略時は設定の値（既定: floor）"
+    )]
+    pub rounding: Option<RoundingMode>,
+    #[schemars(
+        description = "端数処理の単位（line: 明細ごと, total: 合計に対して1回）。省略時は設定の値（既定: total）"
+    )]
+    pub per: Option<TaxScope>,
+    #[schemars(description = "丸める小数点以下の桁数（既定: 0、円単位。100 以下）")]
+    pub decimals: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct BusinessMathRequest {
+    #[schemars(
+        description = "計算の種類（break_even: 損益分岐点, markup_margin: マークアップとマージンの変換, contribution_margin: 貢献利益）"
+    )]
+    pub calculation: Calculation,
+    #[schemars(
+        description = "固定費（break_even では必須、contribution_margin では営業利益を求める場合に指定）"
+    )]
+    pub fixed_costs: Option<f64>,
+    #[schemars(description = "1個あたりの売価")]
+    pub price: Option<f64>,
+    #[schemars(description = "1個あたりの変動費")]
+    pub variable_cost: Option<f64>,
+    #[schemars(description = "販売数量（contribution_margin。既定は 1）")]
+    pub quantity: Option<f64>,
+    #[schemars(description = "原価（markup_margin）")]
+    pub cost: Option<f64>,
+    #[schemars(description = "マークアップ（値入率）。利益 ÷ 原価 × 100")]
+    pub markup_percent: Option<f64>,
+    #[schemars(description = "マージン（利益率）。利益 ÷ 売価 × 100")]
+    pub margin_percent: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ElectronicsRequest {
+    #[schemars(
+        description = "計算の種類（ohms_law: V = IR と P = VI, series: 直列の合成値, parallel: 並列の合成値, voltage_divider: 分圧回路）"
+    )]
+    pub calculation: ElectronicsCalculation,
+    #[schemars(
+        description = "電圧（V）。ohms_law では4つのうち2つを指定し、voltage_divider では入力電圧。値は数値か SI 接頭辞つきの文字列（\"4.7k\", \"100n\", \"4k7\", \"3.3V\"）"
+    )]
+    pub voltage: Option<Magnitude>,
+    #[schemars(description = "電流（A。例: \"20mA\"）")]
+    pub current: Option<Magnitude>,
+    #[schemars(description = "抵抗（Ω。例: \"4.7k\", \"4R7\"）")]
+    pub resistance: Option<Magnitude>,
+    #[schemars(description = "電力（W）")]
+    pub power: Option<Magnitude>,
+    #[schemars(
+        description = "series / parallel で合成する素子（resistor: 抵抗（既定）, capacitor: コンデンサ, inductor: コイル）"
+    )]
+    #[serde(default)]
+    pub component: Component,
+    #[schemars(description = "series / parallel で合成する素子の値（例: [\"10k\", \"4.7k\"]）")]
+    pub values: Option<Vec<Magnitude>>,
+    #[schemars(description = "voltage_divider の入力側の抵抗")]
+    pub r1: Option<Magnitude>,
+    #[schemars(description = "voltage_divider の接地側の抵抗（出力電圧はこの両端）")]
+    pub r2: Option<Magnitude>,
+    #[schemars(description = "voltage_divider の出力につなぐ負荷の抵抗（r2 と並列になる）")]
+    pub load: Option<Magnitude>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct KinematicsRequest {
+    #[schemars(description = "初速度（u, v, a, t, s のうち3つを指定。単位は揃えること、例: m/s）")]
+    pub u: Option<f64>,
+    #[schemars(description = "終速度")]
+    pub v: Option<f64>,
+    #[schemars(
+        description = "加速度（例: m/s^2。重力加速度なら -9.80665 など向きに合わせた符号）"
+    )]
+    pub a: Option<f64>,
+    #[schemars(description = "時間（0 以上）")]
+    pub t: Option<f64>,
+    #[schemars(description = "変位")]
+    pub s: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GrowthRequest {
+    #[schemars(
+        description = "計算の種類（cagr: 年平均成長率, inflation: インフレ調整, doubling_time: 2倍になる期間）"
+    )]
+    pub calculation: GrowthCalculation,
+    #[schemars(description = "開始時点の値（cagr）")]
+    pub start_value: Option<f64>,
+    #[schemars(description = "終了時点の値（cagr）")]
+    pub end_value: Option<f64>,
+    #[schemars(description = "期間数（cagr, inflation）")]
+    pub periods: Option<f64>,
+    #[schemars(description = "金額（inflation）")]
+    pub amount: Option<f64>,
+    #[schemars(
+        description = "1期間あたりの率（%）。inflation ではインフレ率、doubling_time では成長率"
+    )]
+    pub rate_percent: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GenerateSamplesRequest {
+    #[cfg(feature = "stats")]
+    #[schemars(description = "分布（uniform, normal, binomial, poisson, exponential）")]
+    pub distribution: Distribution,
+    #[schemars(description = "標本の数（1〜10000）")]
+    pub count: usize,
+    #[schemars(
+        description = "乱数のシード。同じ値を指定すると同じ標本を再現できます（省略時は現在時刻から決め、応答の seed に出力します）"
+    )]
+    pub seed: Option<u64>,
+    #[schemars(description = "下限（uniform、既定は 0）")]
+    pub min: Option<f64>,
+    #[schemars(description = "上限（uniform、既定は 1。この値は含みません）")]
+    pub max: Option<f64>,
+    #[schemars(description = "平均（normal では既定 0、poisson では必須）")]
+    pub mean: Option<f64>,
+    #[schemars(description = "標準偏差（normal、既定は 1）")]
+    pub std_dev: Option<f64>,
+    #[schemars(description = "試行回数（binomial）")]
+    pub trials: Option<u64>,
+    #[schemars(description = "成功確率 0〜1（binomial）")]
+    pub probability: Option<f64>,
+    #[schemars(description = "単位時間あたりの発生回数（exponential、既定は 1）")]
+    pub rate: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CombinatoricsRequest {
+    #[cfg(feature = "bignum")]
+    #[schemars(
+        description = "計算の種類（permutations: 順列 P(n, k), permutations_with_repetition: 重複順列 n^k, combinations: 組合せ C(n, k), combinations_with_repetition: 重複組合せ H(n, k), multinomial: 多項係数, derangements: 完全順列の数 !n, stirling_first: 第1種スターリング数（符号なし）, stirling_second: 第2種スターリング数）"
+    )]
+    pub calculation: CombinatoricsCalculation,
+    #[schemars(
+        description = "全体の個数（multinomial 以外。10000 以下、スターリング数では 1000 以下）"
+    )]
+    pub n: Option<u64>,
+    #[schemars(
+        description = "選ぶ個数またはグループ・巡回の数（derangements と multinomial 以外）"
+    )]
+    pub k: Option<u64>,
+    #[schemars(description = "各グループの個数（multinomial。例: [2, 2, 1]）")]
+    pub groups: Option<Vec<u64>>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct NumberPropertiesRequest {
+    #[schemars(description = "調べる整数（64ビットの範囲）")]
+    pub number: i64,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct SetOptionsRequest {
+    #[schemars(description = "三角関数の角度の単位（radians（既定）, degrees）")]
+    pub angle_mode: Option<AngleMode>,
+    #[schemars(description = "端数処理（half_up（既定）, half_even, floor, ceil, truncate）")]
+    pub rounding_mode: Option<RoundingMode>,
+    #[schemars(description = "結果の表記（auto（既定）, plain, scientific）")]
+    pub notation: Option<Notation>,
+    #[schemars(description = "表示する小数点以下の桁数（100 以下。負の値で解除）")]
+    pub precision: Option<i64>,
+    #[schemars(description = "結果の整数部を3桁ごとに区切るか")]
+    pub group_digits: Option<bool>,
+    #[schemars(description = "桁区切りと小数点の慣習（en（既定）, ja, de, fr）")]
+    pub locale: Option<NumberLocale>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct LoadVariablesRequest {
+    #[schemars(description = "変数名と値のオブジェクト（例: {\"rate\": 0.05, \"years\": 10}）")]
+    pub variables: Option<BTreeMap<String, f64>>,
+    #[schemars(description = "1行に「変数名,値」を並べた CSV（1行目は見出しでもよい）")]
+    pub csv: Option<String>,
+    #[schemars(
+        description = "upload_data で送った JSON オブジェクトまたは CSV の URI（例: \"upload://1\"）"
+    )]
+    pub resource_uri: Option<String>,
+    #[schemars(description = "true にすると読み込む前に既存の変数をすべて削除します")]
+    #[serde(default)]
+    pub replace: bool,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct RearrangeRequest {
+    #[schemars(description = "= を1つ含む等式（例: \"v = d / t\"）")]
+    pub equation: String,
+    #[schemars(description = "解く変数の名前（例: \"t\"）")]
+    pub symbol: String,
+    #[schemars(
+        description = "他の変数の値（例: {\"v\": 20, \"d\": 100}）。記号的に解けない場合はすべての変数の値が必要です"
+    )]
+    pub variables: Option<BTreeMap<String, f64>>,
+    #[schemars(description = "数値的に解くときの初期値（既定: 1）")]
+    pub guess: Option<f64>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct SolveSystemRequest {
+    #[schemars(
+        description = "= 0 とする式の配列（例: [\"x^2 + y^2 - 4\", \"x - y\"]）。\"x + y = 3\" のように = を含む式は 左辺 - 右辺 = 0 とみなします"
+    )]
+    pub equations: Vec<String>,
+    #[schemars(
+        description = "未知数の名前と初期値（例: {\"x\": 1, \"y\": 1}）。式の数と同じ数にしてください"
+    )]
+    pub unknowns: BTreeMap<String, f64>,
+    #[schemars(description = "未知数以外の変数の値")]
+    pub variables: Option<BTreeMap<String, f64>>,
+    #[schemars(description = "収束とみなす残差の最大値（既定: 1e-10）")]
+    pub tolerance: Option<f64>,
+    #[schemars(description = "反復回数の上限（既定: 100、最大 1000）")]
+    pub max_iterations: Option<usize>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct CheckConstraintsRequest {
+    #[schemars(
+        description = "比較演算子（<, <=, >, >=, =, !=）を1つ含む制約の配列（例: [\"x + y <= 10\", \"x >= 0\"]）"
+    )]
+    pub constraints: Vec<String>,
+    #[schemars(description = "変数の値（例: {\"x\": 3, \"y\": 4}）")]
+    pub variables: Option<BTreeMap<String, f64>>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct RecalculateRequest {
+    #[schemars(
+        description = "保存する数式（名前と式。例: {\"total\": \"price * quantity\", \"with_tax\": \"total * 1.1\"}）。同じ名前の数式は置き換えます"
+    )]
+    pub formulas: Option<BTreeMap<String, String>>,
+    #[schemars(description = "変更する変数の名前と値（例: {\"price\": 120}）")]
+    pub variables: Option<BTreeMap<String, f64>>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SetToolEnabledRequest {
+    #[schemars(description = "切り替えるツール名（例: \"calculate\"）")]
+    pub name: String,
+    #[schemars(description = "true で有効化、false で無効化")]
+    pub enabled: bool,
+}
+
+impl CalculatorService {
+    pub fn new(config: Config) -> Self {
+        let admission = Arc::new(Admission::new(&config.limits));
+        Self {
+            config: Arc::new(RwLock::new(config)),
+            peers: Arc::default(),
+            next_peer: Arc::default(),
+            started_at: Instant::now(),
+            sessions: Arc::default(),
+            #[cfg(feature = "finance")]
+            rate_cache: RateCache::default(),
+            uploads: UploadStore::default(),
+            options: Arc::default(),
+            history: HistoryStore::default(),
+            variables: Arc::default(),
+            formulas: Arc::default(),
+            admission: Arc::new(RwLock::new(admission)),
+        }
+    }
+
+    // セッション終了時に呼ぶ（初期化時の加算と対になる）。
+    // セッションの状態も破棄し、次の会話に持ち越さない。状態はクライアントごとでなくサーバで1つ
+    // （stdio では接続は1つ）のため、ほかのセッションが残っている間は破棄しない
+    pub fn end_session(&self) {
+        let previous = self
+            .sessions
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+            .unwrap_or(0);
+        if previous <= 1 {
+            self.clear_session();
+        }
+    }
+
+    pub fn config(&self) -> RwLockReadGuard<'_, Config> {
+        self.config.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    // 設定の定数・関数と load_variables で読み込んだ変数を登録した計算機
+    fn calculator(&self) -> Result<Calculator, String> {
+        let mut calculator = self.config().calculator().map_err(|e| e.to_string())?;
+        for (name, value) in self.variables().iter() {
+            calculator.set_variable(name, *value)?;
+        }
+        Ok(calculator)
+    }
+
+    fn variables(&self) -> RwLockReadGuard<'_, BTreeMap<String, f64>> {
+        self.variables.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    // set_options の設定・変数・数式・計算履歴・アップロードしたデータを既定の状態に戻し、
+    // 削除した種類ごとの件数を返す
+    fn clear_session(&self) -> Vec<(&'static str, usize)> {
+        *self.options.write().unwrap_or_else(|e| e.into_inner()) = SessionOptions::default();
+        let variables =
+            std::mem::take(&mut *self.variables.write().unwrap_or_else(|e| e.into_inner()));
+        let formulas = self
+            .formulas
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+        vec![
+            ("変数", variables.len()),
+            ("数式", formulas),
+            ("計算履歴", self.history.clear()),
+            ("アップロード", self.uploads.clear()),
+        ]
+    }
+
+    // パニックしたツールが書き込み中だった（ロックがポイズニングされた）状態だけを既定に戻し、
+    // 戻した状態の名前を返す。ほかの状態はそのまま使い続ける
+    pub fn recover_poisoned(&self) -> Vec<&'static str> {
+        let mut recovered = Vec::new();
+        if self.options.is_poisoned() {
+            *self.options.write().unwrap_or_else(|e| e.into_inner()) = SessionOptions::default();
+            self.options.clear_poison();
+            recovered.push("set_options の設定");
+        }
+        if self.variables.is_poisoned() {
+            self.variables
+                .write()
+                .unwrap_or_else(|e| e.into_inner())
+                .clear();
+            self.variables.clear_poison();
+            recovered.push("変数");
+        }
+        if self.formulas.is_poisoned() {
+            self.formulas
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .clear();
+            self.formulas.clear_poison();
+            recovered.push("数式");
+        }
+        if self.history.recover() {
+            recovered.push("計算履歴");
+        }
+        if self.uploads.recover() {
+            recovered.push("アップロード");
+        }
+        recovered
+    }
+
+    pub fn history(&self) -> &HistoryStore {
+        &self.history
+    }
+
+    pub fn options(&self) -> SessionOptions {
+        *self.options.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    // calculate の引数とセッションのオプションを反映した計算機
+    fn request_calculator(&self, request: &CalculateRequest) -> Result<Calculator, String> {
+        let mut calculator = self
+            .calculator()
+            .map_err(|e| format!("計算エラー: {}", e))?;
+        let options = self.options();
+        calculator.set_rounding_mode(request.rounding_mode.unwrap_or(options.rounding_mode));
+        calculator.set_angle_mode(options.angle_mode);
+        if let Some(si_prefixes) = request.si_prefixes {
+            calculator.set_si_prefixes(si_prefixes);
+        }
+        if let Some(strict) = request.strict {
+            calculator.set_strict(strict);
+        }
+        if let Some(elementwise) = request.elementwise {
+            calculator.set_elementwise(elementwise);
+        }
+        Ok(calculator)
+    }
+
+    // dry_run: 式を解析し、評価せずに計算の量の見積もりを返す
+    fn estimate_request(&self, request: &CalculateRequest) -> Result<String, String> {
+        let estimate = self
+            .request_calculator(request)?
+            .estimate(&request.expression)
+            .map_err(|e| format!("計算エラー: {}", e))?;
+        let response = json!({
+            "dry_run": true,
+            "expression": request.expression,
+            "estimate": estimate,
+        });
+        serde_json::to_string_pretty(&response).map_err(|e| e.to_string())
+    }
+
+    // calculate の本体（履歴の記録は calculate で行う）
+    fn evaluate_request(&self, request: &CalculateRequest) -> Result<String, String> {
+        let calculator = self.request_calculator(request)?;
+        if request.clarify.unwrap_or(self.config().syntax.clarify) {
+            let clarifications = calculator.clarifications(&request.expression);
+            if !clarifications.is_empty() {
+                return Err(tool_error::needs_clarification(&clarifications));
+            }
+        }
+        let options = self.options();
+        let rounding_mode = calculator.rounding_mode();
+        let output_bases = request.output_bases.clone().unwrap_or_default();
+        if let Some(base) = output_bases.iter().find(|base| !(2..=36).contains(*base)) {
+            return Err(format!(
+                "計算エラー: output_bases の基数は 2〜36 にしてください: {}",
+                base
+            ));
+        }
+        if let Some(precision) = request.precision {
+            check_precision(precision).map_err(|e| format!("計算エラー: {}", e))?;
+        }
+        if let Some(decimals) = request.decimals {
+            check_decimals(decimals).map_err(|e| format!("計算エラー: {}", e))?;
+        }
+        let mut warnings = calculator
+            .corrections(&request.expression)
+            .unwrap_or_default();
+        warnings.extend(calculator.unit_notes(&request.expression));
+        if request.sig_figs {
+            return calculator
+                .evaluate_sig_figs(&request.expression)
+                .map(|result| {
+                    warnings.extend(value_warnings(result.value));
+                    let mut lines = vec![match result.digits {
+                        Some(digits) => {
+                            format!("計算結果: {}（有効数字{}桁）", result.display, digits)
+                        }
+                        None => format!(
+                            "計算結果: {}（厳密な値のみのため丸めていません）",
+                            result.display
+                        ),
+                    }];
+                    lines.extend(result.explanation.iter().map(|note| format!("- {}", note)));
+                    if request.audit {
+                        match audit_line(&calculator, &request.expression) {
+                            Ok(line) => lines.push(line),
+                            Err(_) => warnings.push(AUDIT_UNSUPPORTED.to_string()),
+                        }
+                    }
+                    lines.extend(
+                        warnings
+                            .iter()
+                            .map(|warning| format!("- 警告: {}", warning)),
+                    );
+                    lines.join("\n")
+                })
+                .map_err(|e| format!("計算エラー: {}", e));
+        }
+        let options = FormatOptions {
+            notation: request.notation.unwrap_or(options.notation),
+            precision: request.precision.or(options.precision),
+            grouping: request
+                .group_digits
+                .unwrap_or(options.group_digits)
+                .then(|| request.locale.unwrap_or(options.locale)),
+        };
+        // 整数・分数の結果も、これまでどおり数値として表示する
+        let evaluated = calculator
+            .evaluate_value(&request.expression)
+            .map(|value| value.real().map_or(value, Value::Scalar));
+        match evaluated {
+            Ok(Value::Scalar(value)) => {
+                warnings.extend(value_warnings(value));
+                let result = match request.decimals {
+                    Some(decimals) => round_to(value, decimals, rounding_mode),
+                    None => value,
+                };
+                if result == 0.0 && value != 0.0 {
+                    warnings.push(format!(
+                        "0 でない結果（{}）を小数点以下 {} 桁に丸めたため 0 になりました",
+                        value,
+                        request.decimals.unwrap_or_default()
+                    ));
+                }
+                let mut output = format!("計算結果: {}", format_number(result, options));
+                // 桁区切りはそのまま数値として読めないため、区切らない値も返す
+                if options.grouping.is_some() {
+                    let raw = FormatOptions {
+                        grouping: None,
+                        ..options
+                    };
+                    output.push_str(&format!("\n- 値: {}", format_number(result, raw)));
+                }
+                // 入力と表記が異なる場合は、読み違いに気づけるよう解釈した式も返す
+                if let Some(normalized) = normalized_expression(&calculator, &request.expression) {
+                    output.push_str(&format!("\n- 解釈: {}", normalized));
+                }
+                // 浮動小数点の誤差を含む結果が既知の厳密な値に一致すれば、その表記も返す
+                // （decimals で丸めを指定した場合を除く）
+                if let Some(exact) = exact::recognize(value).filter(|_| request.decimals.is_none())
+                {
+                    output.push_str(&format!("\n- 厳密な値: {}", exact));
+                }
+                for base in output_bases {
+                    match format_in_base(result, base) {
+                        Ok(digits) => output.push_str(&format!("\n- {}進数: {}", base, digits)),
+                        Err(e) => warnings.push(format!(
+                            "output_bases の {} 進数は省略しました（{}）",
+                            base, e
+                        )),
+                    }
+                }
+                if request.audit {
+                    match audit_line(&calculator, &request.expression) {
+                        Ok(line) => output.push_str(&format!("\n{}", line)),
+                        Err(_) => warnings.push(AUDIT_UNSUPPORTED.to_string()),
+                    }
+                }
+                for warning in warnings {
+                    output.push_str(&format!("\n- 警告: {}", warning));
+                }
+                Ok(output)
+            }
+            Ok(value) => {
+                let value = match request.decimals {
+                    Some(decimals) => value.map(|x| round_to(x, decimals, rounding_mode)),
+                    None => value,
+                };
+                // 要素の区切りの , と紛らわしいため、桁区切りは付けない
+                let options = FormatOptions {
+                    grouping: None,
+                    ..options
+                };
+                let mut output = format!(
+                    "計算結果: {}\n- 型: {}",
+                    value.format(|x| format_number(x, options)),
+                    value.describe()
+                );
+                if request.audit {
+                    warnings.push(AUDIT_UNSUPPORTED.to_string());
+                }
+                for warning in warnings {
+                    output.push_str(&format!("\n- 警告: {}", warning));
+                }
+                Ok(output)
+            }
+            Err(e) => Err(format!("計算エラー: {}", e)),
+        }
+    }
+
+    // calc://syntax の内容。クライアントが式を組み立てる前に上限を確認できるようにする
+    pub fn syntax(&self) -> Result<String, String> {
+        let config = self.config();
+        let calculator = config.calculator().map_err(|e| e.to_string())?;
+        let syntax = json!({
+            "operators": ["+", "-", "*", "/", "^"],
+            "grouping": ["(", ")"],
+            "unary_operators": "単項の - は値の始まる位置ならどこでも使えます（3 * -2, 2 ^ -1, 2 - -3, --5 は 5）。単項の + は式・括弧・引数の先頭だけで、演算子の直後の + と * / ^（2 ++ 3, 2 * +3, 2 ** 3）はエラーです",
+            "arrays": "[1, 2] はベクトル、[[1, 2], [3, 4]] は行列（calculate のみ）。+ - は同じ形どうし、* は数値倍と行列の積（ベクトルは左からは行、右からは列とみなす）、/ は数値で割る場合、^ は正方行列の整数乗（負の指数は逆行列）",
+            "array_functions": value::FUNCTIONS,
+            "types": "整数どうしの + - * / ^ は分数で正確に計算し（1/3 * 3 は 1）、小数を含むと浮動小数点数で計算します。i は虚数単位です（同じ名前の変数・定数があればそちら）。複素数の ^ の指数は整数です",
+            "units": units::names(),
+            "quantities": "5 km のように数値に単位を続けると単位つきの量になります（strict モードでは 5 * km）。+ - は同じ次元どうしで、結果は左辺の単位です。量どうしの * / と量の整数乗は単位も掛け合わせ、無次元になれば数値です。abs と sqrt は複素数と量も受け付けます",
+            "data_sizes": "kB（KB）, MB, GB, TB, PB は 1000 倍ごと、KiB, MiB, GiB, TiB は 1024 倍ごとのバイトで、bps, kbps, Mbps, Gbps はビット毎秒です。1024 倍の意味でも使われる単位を使った場合は警告で知らせます",
+            "fuel_economy": "mpg, mpgUK（距離/体積）と L/100km（体積/距離）は互いに逆数として換算します（6 L/100km in mpg）",
+            "temperatures": "degC, degF（C, F）は温度の目盛り、deltaC, deltaF は温度差です。温度どうしの差は温度差、温度 + 温度差は温度で、温度と温度差の換算、温度どうしの和、温度の数値倍はエラーです。K はどちらにも使えます",
+            "conversion": "式 in 単位 で量を換算します（5 km in mi、36 km/h in m/s、1 ha in m^2。calculate のみ）。in は + - より弱く、比較より強く結合します",
+            "comparisons": "<, <=, >, >=, ==, !=（≤ ≥ ≠ も可）は真偽値を返します（calculate のみ、1つの式に1つ）。== と <= >= は compare と同じ許容誤差で判定します",
+            "argument_separator": ",",
+            "square_root": "√x（sqrt(x) と同じ。単項のマイナスと同じく ^ より強く結合し、√4^2 は (√4)^2）",
+            "absolute_value": "|x|（abs(x) と同じ）",
+            "percent": "10% は 0.1。a + 10% と a - 10% のように百分率のリテラルだけが + / - の右辺にある場合は a の 10%（a * 0.1）を加減します。10% * a や a * 10% は 0.1 を掛けます",
+            "whitespace": "空白・タブ・改行は無視します",
+            "implicit_multiplication": "2x, 2(3 + 4), (a + b)(a - b), 2sqrt(2) は * を補って計算します（1/2x は (1/2)*x）。strict モードではエラーです",
+            "unicode_operators": "× · ÷ − などは * / - として扱います。strict モードではエラーです",
+            "strict": calculator.strict(),
+            "clarify": {
+                "enabled": config.syntax.clarify,
+                "description": "calculate の clarify: true では 1/2x、log(x)、（SI 接頭辞が有効なとき）100m を推測して計算せず、NEEDS_CLARIFICATION のエラーで解釈の候補を返します",
+            },
+            "comment": "# から行末まで",
+            "functions": calculator.function_names(),
+            "custom_functions": config.functions.keys().collect::<Vec<_>>(),
+            "constants": config.constants.keys().collect::<Vec<_>>(),
+            "si_prefixes": {
+                "enabled": calculator.si_prefixes(),
+                "prefixes": SI_PREFIXES
+                    .iter()
+                    .map(|(prefix, exponent)| format!("{} = 1e{}", prefix, exponent))
+                    .collect::<Vec<_>>(),
+            },
+            "limits": calculator.limits(),
+            "safe_profile": calculator.safe_profile(),
+        });
+        serde_json::to_string_pretty(&syntax).map_err(|e| e.to_string())
+    }
+
+    // 統計系のツールに CSV テキストまたはアップロードの URI で渡されたデータ
+    #[cfg(feature = "stats")]
+    fn table(&self, csv: Option<String>, resource_uri: Option<String>) -> Result<Table, String> {
+        let text = match (csv, resource_uri) {
+            (Some(csv), None) => csv,
+            (None, Some(uri)) => self.uploads.get(&uri)?,
+            _ => return Err(DATA_SOURCE_ERROR.to_string()),
+        };
+        Table::parse(&text)
+    }
+
+    // values の配列か、CSV テキスト・アップロードの1列
+    #[cfg(feature = "stats")]
+    fn values(
+        &self,
+        values: Option<Vec<f64>>,
+        csv: Option<String>,
+        resource_uri: Option<String>,
+        column: Option<&str>,
+    ) -> Result<Vec<f64>, String> {
+        let values = match values {
+            Some(values) if csv.is_none() && resource_uri.is_none() => values,
+            Some(_) => return Err(DATA_SOURCE_ERROR.to_string()),
+            None => self.table(csv, resource_uri)?.column(column)?,
+        };
+        if let Some(profile) = self.safe_profile() {
+            profile.check_list_length(values.len())?;
+        }
+        Ok(values)
+    }
+
+    #[cfg(any(feature = "stats", feature = "matrix", feature = "symbolic"))]
+    fn safe_profile(&self) -> Option<SafeProfile> {
+        self.config().limits.safe_profile
+    }
+
+    // このビルドで使えるツール（必要な feature が無効なものを除く）
+    fn compiled_tools() -> Vec<Tool> {
+        Self::tool_box()
+            .list()
+            .into_iter()
+            .filter(|tool| {
+                FEATURE_TOOLS
+                    .iter()
+                    .all(|(name, compiled)| *compiled || tool.name != *name)
+            })
+            .collect()
+    }
+
+    // 実装されているすべてのツール名
+    pub fn tool_names() -> Vec<String> {
+        Self::compiled_tools()
+            .into_iter()
+            .map(|tool| tool.name.to_string())
+            .collect()
+    }
+
+    // 設定で有効化されているツールのみを返す
+    pub fn enabled_tools(&self) -> Vec<Tool> {
+        let config = self.config();
+        Self::compiled_tools()
+            .into_iter()
+            .filter(|tool| config.tools.is_enabled(&tool.name))
+            .collect()
+    }
+
+    // tools/list で返す、説明を設定の言語にしたツール
+    pub fn localized_tools(&self) -> Vec<Tool> {
+        let language = self.config().tools.language();
+        self.enabled_tools()
+            .into_iter()
+            .map(|tool| i18n::localize(tool, language))
+            .collect()
+    }
+
+    fn enabled_tool_names(&self) -> Vec<String> {
+        self.enabled_tools()
+            .into_iter()
+            .map(|tool| tool.name.to_string())
+            .collect()
+    }
+
+    // 設定を差し替え、公開ツールの集合が変わったかどうかを返す
+    pub fn replace_config(&self, config: Config) -> bool {
+        let before = self.enabled_tool_names();
+        {
+            // 実行中・待機中の呼び出しは古い制限のまま終わらせる
+            let mut admission = self.admission.write().unwrap_or_else(|e| e.into_inner());
+            if !admission.matches(&config.limits) {
+                *admission = Arc::new(Admission::new(&config.limits));
+            }
+        }
+        *self.config.write().unwrap_or_else(|e| e.into_inner()) = config;
+        before != self.enabled_tool_names()
+    }
+
+    // 実行中に設定を反映し、ツール一覧が変わった場合はクライアントへ通知する
+    pub async fn apply_config(&self, config: Config) {
+        if self.replace_config(config) {
+            self.notify_tool_list_changed().await;
+        }
+    }
+
+    async fn notify_tool_list_changed(&self) {
+        let peers = self.peers.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        for (id, peer) in peers {
+            if let Err(e) = peer.notify_tool_list_changed().await {
+                eprintln!("ツール一覧の変更通知に失敗しました: {}", e);
+                self.peers
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .remove(&id);
+            }
+        }
+    }
+}
+
+#[tool(tool_box)]
+impl CalculatorService {
+    #[tool(
+        description = "セキュアな数式計算を実行します。四則演算、べき乗、括弧、数学関数（平方根、絶対値、三角関数、自然対数）をサポートし、悪意のある入力から保護されています。"
+    )]
+    pub fn calculate(&self, #[tool(aggr)] request: CalculateRequest) -> Result<String, String> {
+        // 評価しないため履歴にも残さない
+        if request.dry_run {
+            return self.estimate_request(&request);
+        }
+        let output = self.evaluate_request(&request);
+        // 履歴には出力の1行目（計算結果）だけを残す
+        let outcome = match &output {
+            Ok(output) => Ok(output
+                .lines()
+                .next()
+                .unwrap_or_default()
+                .trim_start_matches("計算結果: ")
+                .to_string()),
+            Err(e) => Err(e.trim_start_matches("計算エラー: ").to_string()),
+        };
+        let max_history = self.config().limits.max_history;
+        self.history
+            .record("calculate", &request.expression, outcome, max_history);
+        output
+    }
+
+    #[tool(
+        description = "複数の数式をまとめて計算します。式は並列に評価し、入力と同じ順序で各式の結果（値の型・単位・厳密な値かどうかを含む）またはエラーをJSONで返します。1式がエラーやタイムアウトになっても他の式の結果は返します。"
+    )]
+    pub async fn calculate_batch(
+        &self,
+        #[tool(aggr)] request: CalculateBatchRequest,
+    ) -> Result<String, String> {
+        if let Some(decimals) = request.decimals {
+            check_decimals(decimals)?;
+        }
+        let options = self.options();
+        let rounding_mode = request.rounding_mode.unwrap_or(options.rounding_mode);
+        let mut calculator = self
+            .calculator()
+            .map_err(|e| format!("計算エラー: {}", e))?;
+        calculator.set_rounding_mode(rounding_mode);
+        calculator.set_angle_mode(options.angle_mode);
+        let calculator = Arc::new(calculator);
+        let limits = self.config().limits.clone();
+        if request.expressions.len() > limits.max_batch_expressions {
+            return Err(format!(
+                "式が多すぎます: {} 個（上限は {} 個）",
+                request.expressions.len(),
+                limits.max_batch_expressions
+            ));
+        }
+        let timeout = Duration::from_millis(
+            request
+                .timeout_ms
+                .unwrap_or(limits.batch_timeout_ms)
+                .min(limits.batch_timeout_ms),
+        );
+        let semaphore = Arc::new(Semaphore::new(limits.batch_concurrency.max(1)));
+
+        let handles: Vec<_> = request
+            .expressions
+            .iter()
+            .map(|expression| {
+                let expression = expression.clone();
+                let calculator = calculator.clone();
+                let semaphore = semaphore.clone();
+                tokio::spawn(async move {
+                    let permit = semaphore.acquire_owned().await.map_err(|e| e.to_string())?;
+                    let evaluation = tokio::task::spawn_blocking(move || {
+                        // タイムアウトした後も評価が終わるまで枠を占有し、同時に評価する数を守る
+                        let _permit = permit;
+                        let value = calculator.evaluate_value(&expression)?;
+                        // ベクトル・行列や比較を含む式はコンパイルできないため、ハッシュと解釈した式は省略する
+                        let compiled = calculator.compile(&expression).ok();
+                        let mut warnings = calculator.corrections(&expression)?;
+                        warnings.extend(calculator.unit_notes(&expression));
+                        if let Some(real) = value.real() {
+                            warnings.extend(value_warnings(real));
+                        }
+                        Ok((
+                            value,
+                            compiled.as_ref().map(CompiledExpr::canonical_hash),
+                            compiled.as_ref().map(CompiledExpr::to_infix),
+                            warnings,
+                        ))
+                    });
+                    match tokio::time::timeout(timeout, evaluation).await {
+                        Ok(joined) => joined.map_err(|e| e.to_string())?,
+                        Err(_) => Err(format!(
+                            "制限時間（{} ミリ秒）内に評価できませんでした",
+                            timeout.as_millis()
+                        )),
+                    }
+                })
+            })
+            .collect();
+
+        let mut results = Vec::with_capacity(handles.len());
+        let mut table = markdown::Table::new(&["index", "expression", "result"]);
+        let mut failed = 0;
+        for (index, (expression, handle)) in request.expressions.iter().zip(handles).enumerate() {
+            match handle
+                .await
+                .map_err(|e| e.to_string())
+                .and_then(|result| result)
+            {
+                Ok((value, hash, normalized, warnings)) => {
+                    // 分数は既知の値として認識できなくても n/d で正確に表せる
+                    let exact = match value {
+                        Value::Rational(..) => Some(value.format(|x| x.to_string())),
+                        _ => value.real().and_then(exact::recognize),
+                    }
+                    .filter(|_| request.decimals.is_none());
+                    let mut result = json!({
+                        "index": index,
+                        "expression": expression,
+                        "type": value.kind(),
+                        "is_exact": value.is_exact(),
+                    });
+                    if let Some(unit) = value.unit() {
+                        result["unit"] = json!(unit.name);
+                    }
+                    if let Some(shape) = value.shape() {
+                        result["shape"] = json!(shape);
+                    }
+                    let value = match request.decimals {
+                        Some(decimals) => value.map(|x| round_to(x, decimals, rounding_mode)),
+                        None => value,
+                    };
+                    result["result"] = value_json(&value);
+                    if let (Some(normalized), Some(hash)) = (normalized, hash) {
+                        result["normalized_expression"] = json!(normalized);
+                        result["canonical_hash"] = json!(hash);
+                    }
+                    if let Some(exact) = exact {
+                        result["exact"] = json!(exact);
+                    }
+                    if !warnings.is_empty() {
+                        result["warnings"] = json!(warnings);
+                    }
+                    results.push(result);
+                    let display = match value.real() {
+                        Some(real) => format_number(real, Default::default()),
+                        None => value.format(|x| format_number(x, Default::default())),
+                    };
+                    table.push(vec![index.to_string(), expression.clone(), display.clone()]);
+                    self.history.record(
+                        "calculate_batch",
+                        expression,
+                        Ok(display),
+                        limits.max_history,
+                    );
+                }
+                Err(e) => {
+                    failed += 1;
+                    table.push(vec![
+                        index.to_string(),
+                        expression.clone(),
+                        format!("エラー: {}", e),
+                    ]);
+                    self.history.record(
+                        "calculate_batch",
+                        expression,
+                        Err(e.clone()),
+                        limits.max_history,
+                    );
+                    results.push(json!({ "index": index, "expression": expression, "error": e }));
+                }
+            }
+        }
+        let response = json!({
+            "results": results,
+            "succeeded": results.len() - failed,
+            "failed": failed,
+        });
+        table_json(&response, request.output, || table)
+    }
+
+    #[tool(
+        description = "2つの数式（または数式と期待値）を評価し、許容誤差（絶対・相対）の範囲内で一致するかを差分とあわせてJSONで返します。自分の計算結果の検算に使えます。"
+    )]
+    pub fn compare(&self, #[tool(aggr)] request: CompareRequest) -> Result<String, String> {
+        let calculator = self
+            .calculator()
+            .map_err(|e| format!("計算エラー: {}", e))?;
+        let actual = calculator
+            .evaluate(&request.expression)
+            .map_err(|e| format!("expression の計算エラー: {}", e))?;
+        let expected = calculator
+            .evaluate(&request.expected)
+            .map_err(|e| format!("expected の計算エラー: {}", e))?;
+        let comparison = compare::compare(
+            actual,
+            expected,
+            request.abs_tolerance.unwrap_or(DEFAULT_ABS_TOLERANCE),
+            request.rel_tolerance.unwrap_or(DEFAULT_REL_TOLERANCE),
+        )?;
+        serde_json::to_string_pretty(&comparison).map_err(|e| e.to_string())
+    }
+
+    #[tool(
+        description = "時間の計算をします。時間どうしの加減算、時間と数値の乗除算、時間 ÷ 時間（枠がいくつ入るか）に対応し、結果を h:mm:ss と時・分・秒それぞれの合計でJSONで返します。"
+    )]
+    pub fn duration_calc(
+        &self,
+        #[tool(aggr)] request: DurationCalcRequest,
+    ) -> Result<String, String> {
+        let result = duration::calculate(&request.expression)?;
+        serde_json::to_string_pretty(&result).map_err(|e| e.to_string())
+    }
+
+    #[tool(
+        description = "日時を別のタイムゾーンに変換し、RFC3339 の時刻・UTCオフセット（夏時間を反映）と、基準時刻からの時間（hours_from_now）をJSONで返します。"
+    )]
+    pub fn convert_timezone(
+        &self,
+        #[tool(aggr)] request: ConvertTimezoneRequest,
+    ) -> Result<String, String> {
+        #[cfg(feature = "datetime")]
+        {
+            let now = datetime::parse_now(request.now.as_deref())?;
+            let conversion =
+                datetime::convert(&request.time, &request.from_zone, &request.to_zone, now)?;
+            serde_json::to_string_pretty(&conversion).map_err(|e| e.to_string())
+        }
+        #[cfg(not(feature = "datetime"))]
+        {
+            let _ = request;
+            Err("このビルドでは convert_timezone は使えません（datetime feature）".to_string())
+        }
+    }
+
+    #[tool(
+        description = "タイムゾーンの異なる2つの日時の間隔を、夏時間の切り替えを含めた実際の経過時間（秒・時間・h:mm:ss）でJSONで返します。"
+    )]
+    pub fn time_between(
+        &self,
+        #[tool(aggr)] request: TimeBetweenRequest,
+    ) -> Result<String, String> {
+        #[cfg(feature = "datetime")]
+        {
+            let now = datetime::parse_now(request.now.as_deref())?;
+            let end = request.end.unwrap_or_else(|| now.to_rfc3339());
+            let end_zone = request.end_zone.as_deref().unwrap_or(&request.start_zone);
+            let interval =
+                datetime::between(&request.start, &request.start_zone, &end, end_zone, now)?;
+            serde_json::to_string_pretty(&interval).map_err(|e| e.to_string())
+        }
+        #[cfg(not(feature = "datetime"))]
+        {
+            let _ = request;
+            Err("このビルドでは time_between は使えません（datetime feature）".to_string())
+        }
+    }
+
+    #[tool(
+        description = "生年月日から基準日時点の満年齢を年・月・日で求め、次の誕生日（記念日）までの日数とあわせてJSONで返します。うるう年の2月29日生まれは平年では2月28日を記念日とします。"
+    )]
+    pub fn age(&self, #[tool(aggr)] request: AgeRequest) -> Result<String, String> {
+        #[cfg(feature = "datetime")]
+        {
+            let birthdate = datetime::parse_date(&request.birthdate)?;
+            let reference = match request.reference.as_deref() {
+                Some(reference) => datetime::parse_date(reference)?,
+                None => datetime::today(request.zone.as_deref())?,
+            };
+            let age = datetime::age(birthdate, reference)?;
+            serde_json::to_string_pretty(&age).map_err(|e| e.to_string())
+        }
+        #[cfg(not(feature = "datetime"))]
+        {
+            let _ = request;
+            Err("このビルドでは age は使えません（datetime feature）".to_string())
+        }
+    }
+
+    #[tool(
+        description = "数値と人が読みやすい表記を相互に変換し、value と text をJSONで返します。value を指定すると 1.23M や 123.46万 のような表記に、text を指定すると数値に変換します。bytes: true ならバイト数（1.5 GiB など）として扱います。"
+    )]
+    pub fn humanize(&self, #[tool(aggr)] request: HumanizeRequest) -> Result<String, String> {
+        let decimals = request.decimals.unwrap_or(2);
+        if decimals > humanize::MAX_DECIMALS {
+            return Err(format!(
+                "decimals は {} 以下で指定してください: {}",
+                humanize::MAX_DECIMALS,
+                decimals
+            ));
+        }
+        let humanized = match (request.value, request.text.as_deref()) {
+            (Some(value), None) if request.bytes => humanize::humanize_bytes(value, decimals)?,
+            (Some(value), None) => humanize::humanize(value, request.locale, decimals)?,
+            (None, Some(text)) if request.bytes => humanize::parse_bytes(text)?,
+            (None, Some(text)) => humanize::parse(text, request.locale)?,
+            _ => return Err("value と text のどちらか一方を指定してください".to_string()),
+        };
+        serde_json::to_string_pretty(&humanized).map_err(|e| e.to_string())
+    }
+
+    #[tool(
+        description = "表計算のように、数値と他のセルを参照する数式（=A1 + B1）を並べた表を計算します。参照の依存関係の順に評価し、計算した表と、エラー（循環参照など）になったセルとその理由をJSONで返します。"
+    )]
+    pub fn sheet(&self, #[tool(aggr)] request: SheetRequest) -> Result<String, String> {
+        if let Some(decimals) = request.decimals {
+            check_decimals(decimals)?;
+        }
+        let max_cells = self.config().limits.max_sheet_cells;
+        let count: usize = request.cells.iter().map(Vec::len).sum();
+        if count > max_cells {
+            return Err(format!(
+                "セルが多すぎます: {} 個（上限は {} 個）",
+                count, max_cells
+            ));
+        }
+        let grid = request
+            .cells
+            .iter()
+            .enumerate()
+            .map(|(row, cells)| {
+                cells
+                    .iter()
+                    .enumerate()
+                    .map(|(column, cell)| match cell {
+                        serde_json::Value::Null => Ok(String::new()),
+                        serde_json::Value::Number(number) => Ok(number.to_string()),
+                        serde_json::Value::String(text) => Ok(text.clone()),
+                        _ => Err(format!(
+                            "{} のセルは数値か文字列にしてください",
+                            sheet::cell_name(row, column)
+                        )),
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut calculator = self
+            .calculator()
+            .map_err(|e| format!("計算エラー: {}", e))?;
+        let options = self.options();
+        calculator.set_rounding_mode(options.rounding_mode);
+        calculator.set_angle_mode(options.angle_mode);
+        let mut result = sheet::evaluate(&calculator, &grid);
+        if let Some(decimals) = request.decimals {
+            for value in result.values.iter_mut().flatten().flatten() {
+                *value = round_to(*value, decimals, options.rounding_mode);
+            }
+        }
+        serde_json::to_string_pretty(&result).map_err(|e| e.to_string())
+    }
+
+    #[tool(
+        description = "行列の固有値・固有ベクトル（QR法）、LU分解（PA = LU）、QR分解、コレスキー分解、条件数を計算し、結果の行列を行の配列としてJSONで返します。"
+    )]
+    pub fn matrix(&self, #[tool(aggr)] request: MatrixRequest) -> Result<String, String> {
+        #[cfg(feature = "matrix")]
+        {
+            let max_elements = self.config().limits.max_matrix_elements;
+            let matrix = match (request.matrix, request.matrix_uri) {
+                (Some(matrix), None) => matrix,
+                (None, Some(uri)) => uploads::parse_matrix(&self.uploads.get(&uri)?, max_elements)?,
+                _ => {
+                    return Err("matrix と matrix_uri のどちらか一方を指定してください".to_string())
+                }
+            };
+            matrix::check_size(&matrix, max_elements)?;
+            if let Some(profile) = self.safe_profile() {
+                let cols = matrix.iter().map(Vec::len).max().unwrap_or(0);
+                profile.check_matrix(matrix.len(), cols)?;
+            }
+            let matrix = &matrix;
+            let json = match request.operation {
+                MatrixOperation::Eigen => serde_json::to_string_pretty(&matrix::eigen(matrix)?),
+                MatrixOperation::Lu => serde_json::to_string_pretty(&matrix::lu(matrix)?),
+                MatrixOperation::Qr => serde_json::to_string_pretty(&matrix::qr(matrix)?),
+                MatrixOperation::Cholesky => {
+                    serde_json::to_string_pretty(&matrix::cholesky(matrix)?)
+                }
+                MatrixOperation::ConditionNumber => {
+                    serde_json::to_string_pretty(&matrix::condition_number(matrix)?)
+                }
+            };
+            json.map_err(|e| e.to_string())
+        }
+        #[cfg(not(feature = "matrix"))]
+        {
+            let _ = request;
+            Err("このビルドでは matrix は使えません（matrix feature）".to_string())
+        }
+    }
+
+    #[tool(
+        description = "大きなデータをチャンクに分けてサーバに送ります。最初のチャンクで upload_id を省略し、返された upload_id を続きのチャンクに指定して、最後に done: true を送ります。完了したデータは返された uri（upload://<id>）で matrix ツールなどから参照でき、MCP のリソースとしても読めます。"
+    )]
+    pub fn upload_data(&self, #[tool(aggr)] request: UploadDataRequest) -> Result<String, String> {
+        let status = self.uploads.append(
+            request.upload_id.as_deref(),
+            &request.chunk,
+            request.done.unwrap_or(true),
+            &self.config().limits,
+        )?;
+        serde_json::to_string_pretty(&status).map_err(|e| e.to_string())
+    }
+
+    #[tool(
+        description = "数値データの要約統計量（件数、合計、平均、中央値、最小・最大、四分位数、標本・母集団の分散と標準偏差）をJSONで返します。データは values の配列、CSV テキスト（csv）、upload_data で送った CSV（resource_uri）のいずれかで渡し、CSV では column で列を選びます。"
+    )]
+    pub fn statistics(&self, #[tool(aggr)] request: StatisticsRequest) -> Result<String, String> {
+        #[cfg(feature = "stats")]
+        {
+            let values = self.values(
+                request.values,
+                request.csv,
+                request.resource_uri,
+                request.column.as_deref(),
+            )?;
+            let summary = statistics::summarize(&values)?;
+            table_json(&summary, request.output, || summary.table())
+        }
+        #[cfg(not(feature = "stats"))]
+        {
+            let _ = request;
+            Err("このビルドでは statistics は使えません（stats feature）".to_string())
+        }
+    }
+
+    #[tool(
+        description = "数値データを区間に分けた度数分布（各区間の度数、相対度数、累積相対度数）をJSONで返します。区間は bins で等幅の個数を、edges で境界を指定し、省略時はスタージェスの公式で決めます。chart: true で ASCII の棒グラフも、image: true で PNG 画像の棒グラフ（width, height, theme で大きさと配色を指定）も返します。データの渡し方は statistics と同じです。"
+    )]
+    pub fn histogram(&self, #[tool(aggr)] request: HistogramRequest) -> Result<String, String> {
+        #[cfg(feature = "stats")]
+        {
+            if request.bins.is_some() && request.edges.is_some() {
+                return Err("bins と edges はどちらか一方だけ指定してください".to_string());
+            }
+            let values = self.values(
+                request.values,
+                request.csv,
+                request.resource_uri,
+                request.column.as_deref(),
+            )?;
+            let width = request.width.unwrap_or(image::DEFAULT_WIDTH);
+            let height = request.height.unwrap_or(image::DEFAULT_HEIGHT);
+            if request.image {
+                image::check_dimensions(width, height)?;
+            }
+            let histogram = statistics::histogram(
+                &values,
+                request.bins,
+                request.edges.as_deref(),
+                request.chart || request.image,
+            )?;
+            if !request.image {
+                return table_json(&histogram, request.output, || histogram.table());
+            }
+            let mut json = serde_json::to_value(&histogram).map_err(|e| e.to_string())?;
+            add_table(&mut json, request.output, || histogram.table());
+            json["image"] = if self.config().tools.disable_images {
+                json!({ "omitted": "画像の出力は設定（tools.disable_images）で無効になっているため、ASCII の棒グラフ（chart）だけを返します" })
+            } else {
+                let theme = request.theme.unwrap_or_default();
+                let counts: Vec<usize> = histogram.bins.iter().map(|bin| bin.count).collect();
+                let png = image::bar_chart(&counts, width, height, theme).to_png();
+                json!({
+                    "mime_type": "image/png",
+                    "width": width,
+                    "height": height,
+                    "theme": theme,
+                    "data": image::base64(&png),
+                })
+            };
+            serde_json::to_string_pretty(&json).map_err(|e| e.to_string())
+        }
+        #[cfg(not(feature = "stats"))]
+        {
+            let _ = request;
+            Err("このビルドでは histogram は使えません（stats feature）".to_string())
+        }
+    }
+
+    #[tool(
+        description = "順列・組合せ（重複あり・なし）、多項係数、完全順列の数、スターリング数を任意精度の整数で正確に求め、値（10進数の文字列）と桁数、指数表記の概算をJSONで返します。"
+    )]
+    pub fn combinatorics(
+        &self,
+        #[tool(aggr)] request: CombinatoricsRequest,
+    ) -> Result<String, String> {
+        #[cfg(feature = "bignum")]
+        {
+            let required = |value: Option<u64>, name: &str| {
+                value.ok_or_else(|| {
+                    format!("{} には {} が必要です", request.calculation.name(), name)
+                })
+            };
+            let limits = &self.config().limits;
+            if let Some(profile) = &limits.safe_profile {
+                let n = match request.calculation {
+                    CombinatoricsCalculation::Multinomial => request
+                        .groups
+                        .iter()
+                        .flatten()
+                        .fold(0, |sum: u64, group| sum.saturating_add(*group)),
+                    _ => request.n.unwrap_or_default(),
+                };
+                profile.check_factorial(n as f64)?;
+            }
+            let limits = DigitLimits {
+                max_digits: limits.max_result_digits,
+                max_working_digits: limits.max_working_digits,
+            };
+            let count = match request.calculation {
+                CombinatoricsCalculation::Permutations => combinatorics::permutations(
+                    required(request.n, "n")?,
+                    required(request.k, "k")?,
+                    limits,
+                ),
+                CombinatoricsCalculation::PermutationsWithRepetition => {
+                    combinatorics::permutations_with_repetition(
+                        required(request.n, "n")?,
+                        required(request.k, "k")?,
+                        limits,
+                    )
+                }
+                CombinatoricsCalculation::Combinations => combinatorics::combinations(
+                    required(request.n, "n")?,
+                    required(request.k, "k")?,
+                    limits,
+                ),
+                CombinatoricsCalculation::CombinationsWithRepetition => {
+                    combinatorics::combinations_with_repetition(
+                        required(request.n, "n")?,
+                        required(request.k, "k")?,
+                        limits,
+                    )
+                }
+                CombinatoricsCalculation::Multinomial => combinatorics::multinomial(
+                    request.groups.as_deref().unwrap_or_default(),
+                    limits,
+                ),
+                CombinatoricsCalculation::Derangements => {
+                    combinatorics::derangements(required(request.n, "n")?, limits)
+                }
+                CombinatoricsCalculation::StirlingFirst => combinatorics::stirling_first(
+                    required(request.n, "n")?,
+                    required(request.k, "k")?,
+                    limits,
+                ),
+                CombinatoricsCalculation::StirlingSecond => combinatorics::stirling_second(
+                    required(request.n, "n")?,
+                    required(request.k, "k")?,
+                    limits,
+                ),
+            }?;
+            serde_json::to_string_pretty(&count).map_err(|e| e.to_string())
+        }
+        #[cfg(not(feature = "bignum"))]
+        {
+            let _ = request;
+            Err("このビルドでは combinatorics は使えません（bignum feature）".to_string())
+        }
+    }
+
+    #[tool(
+        description = "整数の性質（偶奇、素数か、平方数か、素因数分解、約数とその個数・総和、完全数・過剰数・不足数の分類、桁数と各桁の和、2進・8進・16進表記、前後の素数）をまとめてJSONで返します。"
+    )]
+    pub fn number_properties(
+        &self,
+        #[tool(aggr)] request: NumberPropertiesRequest,
+    ) -> Result<String, String> {
+        let properties = number_theory::properties(request.number);
+        serde_json::to_string_pretty(&properties).map_err(|e| e.to_string())
+    }
+
+    #[tool(
+        description = "一様分布・正規分布・二項分布・ポアソン分布・指数分布から count 個の標本を生成し、標本と要約統計量（statistics と同じ項目）、分布の理論上の平均と標準偏差をJSONで返します。seed を指定すると同じ標本を再現できます。"
+    )]
+    pub fn generate_samples(
+        &self,
+        #[tool(aggr)] request: GenerateSamplesRequest,
+    ) -> Result<String, String> {
+        #[cfg(feature = "stats")]
+        {
+            let name = request.distribution.name();
+            let required = |value: Option<f64>, parameter: &str| {
+                value.ok_or_else(|| format!("{} には {} が必要です", name, parameter))
+            };
+            let sampler = match request.distribution {
+                Distribution::Uniform => Sampler::Uniform {
+                    min: request.min.unwrap_or(0.0),
+                    max: request.max.unwrap_or(1.0),
+                },
+                Distribution::Normal => Sampler::Normal {
+                    mean: request.mean.unwrap_or(0.0),
+                    std_dev: request.std_dev.unwrap_or(1.0),
+                },
+                Distribution::Binomial => Sampler::Binomial {
+                    trials: request
+                        .trials
+                        .ok_or_else(|| format!("{} には trials が必要です", name))?,
+                    probability: required(request.probability, "probability")?,
+                },
+                Distribution::Poisson => Sampler::Poisson {
+                    mean: required(request.mean, "mean")?,
+                },
+                Distribution::Exponential => Sampler::Exponential {
+                    rate: request.rate.unwrap_or(1.0),
+                },
+            };
+            let seed = request.seed.unwrap_or_else(|| {
+                std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|elapsed| elapsed.as_nanos() as u64)
+                    .unwrap_or_default()
+            });
+            let samples = sampling::generate(sampler, request.count, seed)?;
+            serde_json::to_string_pretty(&samples).map_err(|e| e.to_string())
+        }
+        #[cfg(not(feature = "stats"))]
+        {
+            let _ = request;
+            Err("このビルドでは generate_samples は使えません（stats feature）".to_string())
+        }
+    }
+
+    #[tool(
+        description = "数値データに正規分布（normal）・指数分布（exponential）・一様分布（uniform）を最尤推定で当てはめ、パラメータ、対数尤度、AIC、コルモゴロフ–スミルノフ統計量と p 値をJSONで返します。AIC の小さい順に並べ、最もよく当てはまる分布を best に返します。データの渡し方は statistics と同じです。"
+    )]
+    pub fn fit_distribution(
+        &self,
+        #[tool(aggr)] request: FitDistributionRequest,
+    ) -> Result<String, String> {
+        #[cfg(feature = "stats")]
+        {
+            let values = self.values(
+                request.values,
+                request.csv,
+                request.resource_uri,
+                request.column.as_deref(),
+            )?;
+            let distributions = request
+                .distributions
+                .unwrap_or_else(|| statistics::FIT_DISTRIBUTIONS.to_vec());
+            let report = statistics::fit_distributions(&values, &distributions)?;
+            serde_json::to_string_pretty(&report).map_err(|e| e.to_string())
+        }
+        #[cfg(not(feature = "stats"))]
+        {
+            let _ = request;
+            Err("このビルドでは fit_distribution は使えません（stats feature）".to_string())
+        }
+    }
+
+    #[tool(
+        description = "数値の系列を平滑化し、入力と同じ長さの系列をJSONで返します。simple は直近 window 個の単純移動平均、exponential は指数移動平均（alpha または window で指定）、rolling は移動平均に加えて移動標準偏差・最小・最大・合計を返します。window 個そろわない先頭の位置は null です。データの渡し方は statistics と同じです。"
+    )]
+    pub fn smooth(&self, #[tool(aggr)] request: SmoothRequest) -> Result<String, String> {
+        #[cfg(feature = "stats")]
+        {
+            let values = self.values(
+                request.values,
+                request.csv,
+                request.resource_uri,
+                request.column.as_deref(),
+            )?;
+            let smoothed =
+                smoothing::smooth(&values, request.method, request.window, request.alpha)?;
+            serde_json::to_string_pretty(&smoothed).map_err(|e| e.to_string())
+        }
+        #[cfg(not(feature = "stats"))]
+        {
+            let _ = request;
+            Err("このビルドでは smooth は使えません（stats feature）".to_string())
+        }
+    }
+
+    #[tool(
+        description = "最小二乗法で単回帰直線 y = slope * x + intercept を求め、相関係数と決定係数とあわせてJSONで返します。データは x, y の配列、CSV テキスト（csv）、upload_data で送った CSV（resource_uri）のいずれかで渡し、CSV では x_column, y_column で列を選びます。"
+    )]
+    pub fn regression(&self, #[tool(aggr)] request: RegressionRequest) -> Result<String, String> {
+        #[cfg(feature = "stats")]
+        {
+            let (x, y) = match (request.x, request.y) {
+                (Some(x), Some(y)) if request.csv.is_none() && request.resource_uri.is_none() => {
+                    (x, y)
+                }
+                (None, None) => self
+                    .table(request.csv, request.resource_uri)?
+                    .column_pair(request.x_column.as_deref(), request.y_column.as_deref())?,
+                _ => return Err(DATA_SOURCE_ERROR.to_string()),
+            };
+            if let Some(profile) = self.safe_profile() {
+                profile.check_list_length(x.len().max(y.len()))?;
+            }
+            let regression = statistics::linear_regression(&x, &y)?;
+            serde_json::to_string_pretty(&regression).map_err(|e| e.to_string())
+        }
+        #[cfg(not(feature = "stats"))]
+        {
+            let _ = request;
+            Err("このビルドでは regression は使えません（stats feature）".to_string())
+        }
+    }
+
+    #[tool(
+        description = "値と重みの組から加重平均を求め、重みの合計と各重みの割合とあわせてJSONで返します（成績の評価やポートフォリオの平均など）。"
+    )]
+    pub fn weighted_average(
+        &self,
+        #[tool(aggr)] request: WeightedAverageRequest,
+    ) -> Result<String, String> {
+        let result = average::weighted_average(&request.items, request.normalize)?;
+        serde_json::to_string_pretty(&result).map_err(|e| e.to_string())
+    }
+
+    #[tool(
+        description = "パーセントの計算をJSONで返します。from, to を指定すると差（difference）と相対的な変化率（relative_change_percent）を、values_are_percent: true なら差をパーセントポイント（percentage_point_change）としても返します。percent, of を指定すると of の percent% を求めます。"
+    )]
+    pub fn percentage(&self, #[tool(aggr)] request: PercentageRequest) -> Result<String, String> {
+        let json = match (request.from, request.to, request.percent, request.of) {
+            (Some(from), Some(to), None, None) => serde_json::to_string_pretty(
+                &percentage::change(from, to, request.values_are_percent)?,
+            ),
+            (None, None, Some(percent), Some(of)) => {
+                serde_json::to_string_pretty(&percentage::percent_of(percent, of)?)
+            }
+            _ => {
+                return Err(
+                    "from と to、または percent と of のどちらかの組を指定してください".to_string(),
+                )
+            }
+        };
+        json.map_err(|e| e.to_string())
+    }
+
+    #[tool(
+        description = "比例式 a / b = c / d の未知の1項を求めます。または scale_from, scale_to, quantities を指定して分量を比例配分します（例: 4人分のレシピを7人分に）。結果はJSONで返します。"
+    )]
+    pub fn proportion(&self, #[tool(aggr)] request: ProportionRequest) -> Result<String, String> {
+        let json = match (request.scale_from, request.scale_to, &request.quantities) {
+            (Some(from), Some(to), Some(quantities)) => {
+                serde_json::to_string_pretty(&proportion::scale(from, to, quantities)?)
+            }
+            (None, None, None) => serde_json::to_string_pretty(&proportion::solve(
+                request.a, request.b, request.c, request.d,
+            )?),
+            _ => {
+                return Err(
+                    "拡大・縮小には scale_from, scale_to, quantities をすべて指定してください"
+                        .to_string(),
+                )
+            }
+        };
+        json.map_err(|e| e.to_string())
+    }
+
+    #[tool(
+        description = "料理の分量を換算します。体積と質量の換算（1 cup の flour は何 g か）には材料の密度（既定の表、設定ファイルの [cooking.densities]、または density の指定）を使い、使った密度と出典をあわせてJSONで返します。密度は目安で、詰め方や銘柄で変わります。"
+    )]
+    pub fn convert_ingredient(
+        &self,
+        #[tool(aggr)] request: ConvertIngredientRequest,
+    ) -> Result<String, String> {
+        #[cfg(feature = "units")]
+        {
+            let config = self.config();
+            let conversion = cooking::convert(
+                request.amount,
+                &request.from,
+                &request.to,
+                request.ingredient.as_deref(),
+                request.density,
+                &config.cooking.densities,
+            )?;
+            let mut json = json!(conversion);
+            let notes: Vec<&str> = [&request.from, &request.to]
+                .into_iter()
+                .filter_map(|name| units::ambiguity(name.trim()))
+                .collect();
+            if !notes.is_empty() {
+                json["notes"] = json!(notes);
+            }
+            serde_json::to_string_pretty(&json).map_err(|e| e.to_string())
+        }
+        #[cfg(not(feature = "units"))]
+        {
+            let _ = request;
+            Err("このビルドでは convert_ingredient は使えません（units feature）".to_string())
+        }
+    }
+
+    #[tool(
+        description = "電子回路の計算をJSONで返します。ohms_law は電圧・電流・抵抗・電力のうち2つから残りを、series / parallel は抵抗・コンデンサ・コイルの合成値を、voltage_divider は分圧回路の出力電圧（負荷つきも可）を求めます。値は数値か、\"4.7k\" や \"100n\" のように SI 接頭辞をつけた文字列で指定でき、結果にも接頭辞つきの表記（formatted）を添えます。"
+    )]
+    pub fn electronics(&self, #[tool(aggr)] request: ElectronicsRequest) -> Result<String, String> {
+        let name = request.calculation.name();
+        let optional = |value: &Option<Magnitude>, symbols: &[&str], field: &str| {
+            value
+                .as_ref()
+                .map(|value| value.value(symbols))
+                .transpose()
+                .map_err(|e| format!("{}: {}", field, e))
+        };
+        let required = |value: &Option<Magnitude>, symbols: &[&str], field: &str| {
+            optional(value, symbols, field)?
+                .ok_or_else(|| format!("{} には {} が必要です", name, field))
+        };
+        let json = match request.calculation {
+            ElectronicsCalculation::OhmsLaw => {
+                serde_json::to_string_pretty(&electronics::ohms_law(
+                    optional(&request.voltage, electronics::VOLT, "voltage")?,
+                    optional(&request.current, electronics::AMPERE, "current")?,
+                    optional(&request.resistance, electronics::OHM, "resistance")?,
+                    optional(&request.power, electronics::WATT, "power")?,
+                )?)
+            }
+            ElectronicsCalculation::Series | ElectronicsCalculation::Parallel => {
+                let values = request
+                    .values
+                    .as_ref()
+                    .ok_or_else(|| format!("{} には values が必要です", name))?
+                    .iter()
+                    .map(|value| value.value(request.component.symbols()))
+                    .collect::<Result<Vec<f64>, String>>()
+                    .map_err(|e| format!("values: {}", e))?;
+                serde_json::to_string_pretty(&electronics::combine(
+                    request.component,
+                    &values,
+                    request.calculation == ElectronicsCalculation::Parallel,
+                )?)
+            }
+            ElectronicsCalculation::VoltageDivider => {
+                serde_json::to_string_pretty(&electronics::voltage_divider(
+                    required(&request.voltage, electronics::VOLT, "voltage")?,
+                    required(&request.r1, electronics::OHM, "r1")?,
+                    required(&request.r2, electronics::OHM, "r2")?,
+                    optional(&request.load, electronics::OHM, "load")?,
+                )?)
+            }
+        };
+        json.map_err(|e| e.to_string())
+    }
+
+    #[tool(
+        description = "等加速度運動の初速度 u・終速度 v・加速度 a・時間 t・変位 s のうち3つから残りの2つを求め、使った式（SUVAT の式）とあわせてJSONで返します。2次方程式になる場合は t ≥ 0 の解をすべて返します。"
+    )]
+    pub fn kinematics(&self, #[tool(aggr)] request: KinematicsRequest) -> Result<String, String> {
+        let result = kinematics::solve(request.u, request.v, request.a, request.t, request.s)?;
+        serde_json::to_string_pretty(&result).map_err(|e| e.to_string())
+    }
+
+    #[tool(
+        description = "calculate で使える関数（組み込み関数と設定ファイルで定義された関数）と定数の一覧をJSONで返します。"
+    )]
+    pub fn list_functions(&self) -> Result<String, String> {
+        let config = self.config();
+        let listing = json!({
+            "functions": Calculator::new().function_names(),
+            "domains": Calculator::new().function_domains(),
+            "custom_functions": &config.functions,
+            "constants": &config.constants,
+            "variables": &*self.variables(),
+        });
+        serde_json::to_string_pretty(&listing).map_err(|e| e.to_string())
+    }
+
+    #[tool(
+        description = "金額を別の通貨に換算します。レートは設定ファイル・レートファイル・URL のいずれかから取得し、使用したレートと出典（source, as_of）を換算結果とあわせてJSONで返します。"
+    )]
+    pub async fn convert_currency(
+        &self,
+        #[tool(aggr)] request: ConvertCurrencyRequest,
+    ) -> Result<String, String> {
+        #[cfg(feature = "finance")]
+        {
+            let provider = currency::provider(&self.config().currency, &self.rate_cache)?;
+            // URL からの取得はブロッキングのため専用スレッドで行う
+            let conversion = tokio::task::spawn_blocking(move || {
+                currency::convert(
+                    provider.as_ref(),
+                    request.amount,
+                    &request.from,
+                    &request.to,
+                )
+            })
+            .await
+            .map_err(|e| e.to_string())??;
+            serde_json::to_string_pretty(&conversion).map_err(|e| e.to_string())
+        }
+        #[cfg(not(feature = "finance"))]
+        {
+            let _ = request;
+            Err("このビルドでは convert_currency は使えません（finance feature）".to_string())
+        }
+    }
+
+    #[tool(
+        description = "消費税・VATを計算します。税抜または税込の金額（複数の明細も可）から、税額・税抜額・税込額を端数処理（四捨五入・切り捨て・切り上げ、明細ごと/合計で1回）を指定して求め、JSONで返します。"
+    )]
+    pub fn tax(&self, #[tool(aggr)] request: TaxRequest) -> Result<String, String> {
+        if !cfg!(feature = "finance") {
+            return Err("このビルドでは tax は使えません（finance feature）".to_string());
+        }
+        let decimals = request.decimals.unwrap_or(0);
+        check_decimals(decimals)?;
+        let config = &self.config().tax;
+        let options = TaxOptions {
+            mode: request.mode,
+            rate: tax::resolve_rate(config, request.rate, request.rate_name.as_deref())?,
+            rounding: request.rounding.unwrap_or(config.rounding),
+            per: request.per.unwrap_or(config.per),
+            decimals,
+        };
+        let result = tax::calculate_tax(&request.amounts, &options)?;
+        serde_json::to_string_pretty(&result).map_err(|e| e.to_string())
+    }
+
+    #[tool(
+        description = "損益分岐点（break_even）、マークアップとマージンの相互変換（markup_margin）、貢献利益（contribution_margin）を計算し、項目名をつけたJSONで返します。マークアップは利益 ÷ 原価、マージンは利益 ÷ 売価です。"
+    )]
+    pub fn business_math(
+        &self,
+        #[tool(aggr)] request: BusinessMathRequest,
+    ) -> Result<String, String> {
+        if !cfg!(feature = "finance") {
+            return Err("このビルドでは business_math は使えません（finance feature）".to_string());
+        }
+        let required = |value: Option<f64>, name: &str| {
+            value.ok_or_else(|| format!("{} には {} が必要です", request.calculation.name(), name))
+        };
+        let json = match request.calculation {
+            Calculation::BreakEven => serde_json::to_string_pretty(&business::break_even(
+                required(request.fixed_costs, "fixed_costs")?,
+                required(request.price, "price")?,
+                required(request.variable_cost, "variable_cost")?,
+            )?),
+            Calculation::MarkupMargin => serde_json::to_string_pretty(&business::markup_margin(
+                request.cost,
+                request.price,
+                request.markup_percent,
+                request.margin_percent,
+            )?),
+            Calculation::ContributionMargin => {
+                serde_json::to_string_pretty(&business::contribution_margin(
+                    required(request.price, "price")?,
+                    required(request.variable_cost, "variable_cost")?,
+                    request.quantity.unwrap_or(1.0),
+                    request.fixed_costs,
+                )?)
+            }
+        };
+        json.map_err(|e| e.to_string())
+    }
+
+    #[tool(
+        description = "成長率の計算をJSONで返します。cagr は2つの値と期間数から年平均成長率（単純平均との比較つき）を、inflation はインフレ率による将来価値と現在価値を、doubling_time は2倍になる期間を正確な値と72の法則・70の法則の目安で求めます。"
+    )]
+    pub fn growth(&self, #[tool(aggr)] request: GrowthRequest) -> Result<String, String> {
+        if !cfg!(feature = "finance") {
+            return Err("このビルドでは growth は使えません（finance feature）".to_string());
+        }
+        let required = |value: Option<f64>, name: &str| {
+            value.ok_or_else(|| format!("{} には {} が必要です", request.calculation.name(), name))
+        };
+        let json = match request.calculation {
+            GrowthCalculation::Cagr => serde_json::to_string_pretty(&growth::cagr(
+                required(request.start_value, "start_value")?,
+                required(request.end_value, "end_value")?,
+                required(request.periods, "periods")?,
+            )?),
+            GrowthCalculation::Inflation => serde_json::to_string_pretty(&growth::inflation(
+                required(request.amount, "amount")?,
+                required(request.rate_percent, "rate_percent")?,
+                required(request.periods, "periods")?,
+            )?),
+            GrowthCalculation::DoublingTime => serde_json::to_string_pretty(
+                &growth::doubling_time(required(request.rate_percent, "rate_percent")?)?,
+            ),
+        };
+        json.map_err(|e| e.to_string())
+    }
+
+    #[tool(
+        description = "実行中にツールの有効/無効を切り替え、接続中のクライアントへツール一覧の変更を通知します（管理用。設定で tools.admin = true の場合のみ公開されます）。"
+    )]
+    pub async fn set_tool_enabled(
+        &self,
+        #[tool(aggr)] request: SetToolEnabledRequest,
+    ) -> Result<String, String> {
+        if ADMIN_TOOLS.contains(&request.name.as_str()) {
+            return Err(format!(
+                "管理用ツールは切り替えられません: {}",
+                request.name
+            ));
+        }
+        let names = Self::tool_names();
+        if !names.contains(&request.name) {
+            return Err(format!("未知のツール: {}", request.name));
+        }
+
+        let mut config = self.config().clone();
+        config
+            .tools
+            .set_enabled(&request.name, request.enabled, &names);
+        self.apply_config(config).await;
+
+        let action = if request.enabled {
+            "有効化"
+        } else {
+            "無効化"
+        };
+        Ok(format!("{} を{}しました", request.name, action))
+    }
+
+    #[tool(
+        description = "このセッションの calculate と calculate_batch の既定値（角度の単位、端数処理、表記、小数点以下の桁数、桁区切り、ロケール）を設定します。指定した項目だけを変更し、現在の設定をJSONで返します（何も指定しなければ確認のみ）。各リクエストで指定した値がこの設定より優先します。"
+    )]
+    pub fn set_options(&self, #[tool(aggr)] request: SetOptionsRequest) -> Result<String, String> {
+        let precision = request
+            .precision
+            .map(|precision| usize::try_from(precision).ok());
+        if let Some(Some(precision)) = precision {
+            check_precision(precision)?;
+        }
+        let mut options = self.options.write().unwrap_or_else(|e| e.into_inner());
+        if let Some(angle_mode) = request.angle_mode {
+            options.angle_mode = angle_mode;
+        }
+        if let Some(rounding_mode) = request.rounding_mode {
+            options.rounding_mode = rounding_mode;
+        }
+        if let Some(notation) = request.notation {
+            options.notation = notation;
+        }
+        if let Some(precision) = precision {
+            options.precision = precision;
+        }
+        if let Some(group_digits) = request.group_digits {
+            options.group_digits = group_digits;
+        }
+        if let Some(locale) = request.locale {
+            options.locale = locale;
+        }
+        serde_json::to_string_pretty(&*options).map_err(|e| e.to_string())
+    }
+
+    #[tool(
+        description = "変数名と値の組（JSON オブジェクト、CSV、または upload_data の URI）をまとめて読み込み、このセッションの calculate・calculate_batch・compare の式から参照できるようにします。同じ名前の変数は上書きし、1つでも不正な組があれば何も読み込みません。読み込んだ件数とすべての変数をJSONで返します。"
+    )]
+    pub fn load_variables(
+        &self,
+        #[tool(aggr)] request: LoadVariablesRequest,
+    ) -> Result<String, String> {
+        let pairs = match (request.variables, request.csv, request.resource_uri) {
+            (Some(variables), None, None) => variables.into_iter().collect(),
+            (None, Some(csv), None) => dataset::parse_pairs(&csv)?,
+            (None, None, Some(uri)) => {
+                let text = self.uploads.get(&uri)?;
+                if text.trim_start().starts_with('{') {
+                    serde_json::from_str::<BTreeMap<String, f64>>(&text)
+                        .map_err(|e| format!("JSON オブジェクトを読み込めません: {}", e))?
+                        .into_iter()
+                        .collect()
+                } else {
+                    dataset::parse_pairs(&text)?
+                }
+            }
+            _ => {
+                return Err(
+                    "変数は variables、csv、resource_uri のいずれか1つで指定してください"
+                        .to_string(),
+                )
+            }
+        };
+        // 名前が定数や関数と重ならないか、すべて確かめてから反映する
+        let mut calculator = self.config().calculator().map_err(|e| e.to_string())?;
+        for (name, value) in &pairs {
+            if !value.is_finite() {
+                return Err(format!("変数の値が無効です: {} = {}", name, value));
+            }
+            calculator.set_variable(name, *value)?;
+        }
+        let mut variables = self.variables.write().unwrap_or_else(|e| e.into_inner());
+        if request.replace {
+            variables.clear();
+        }
+        let loaded = pairs.len();
+        variables.extend(pairs);
+        let response = json!({ "loaded": loaded, "variables": &*variables });
+        serde_json::to_string_pretty(&response).map_err(|e| e.to_string())
+    }
+
+    #[tool(
+        description = "等式（例: v = d / t）を指定した変数について解きます。変数が1回だけ現れ逆の演算がわかる場合は記号的に式を変形し（t = d / v）、それ以外は variables の値を使って数値的に（ニュートン法など）解きます。変形した式、値（変数の値がすべてわかる場合）、解き方をJSONで返します。"
+    )]
+    pub fn rearrange(&self, #[tool(aggr)] request: RearrangeRequest) -> Result<String, String> {
+        #[cfg(feature = "symbolic")]
+        {
+            let (lhs, rhs) = match request.equation.split('=').collect::<Vec<_>>()[..] {
+                [lhs, rhs] => (lhs.trim(), rhs.trim()),
+                _ => {
+                    return Err(
+                        "equation には = を1つだけ含めてください（例: v = d / t）".to_string()
+                    )
+                }
+            };
+            let symbol = request.symbol.trim();
+            let calculator = self
+                .calculator()
+                .map_err(|e| format!("計算エラー: {}", e))?;
+            let compile = |side: &str| {
+                calculator
+                    .compile(side)
+                    .map_err(|e| format!("計算エラー: {}: {}", side, e))
+            };
+            let (lhs, rhs) = (compile(lhs)?, compile(rhs)?);
+            if !lhs.variables().contains(&symbol) && !rhs.variables().contains(&symbol) {
+                return Err(format!("等式に {} が含まれていません", symbol));
+            }
+            let variables: HashMap<String, f64> =
+                request.variables.unwrap_or_default().into_iter().collect();
+
+            let mut notes = Vec::new();
+            let solved = lhs
+                .isolate(symbol, &rhs, &mut notes)
+                .or_else(|| rhs.isolate(symbol, &lhs, &mut notes))
+                // 組み立てた式を解析し直し、表記を揃える
+                .and_then(|solved| calculator.compile(&solved).ok());
+            if let Some(solved) = solved {
+                let mut response = json!({
+                    "equation": format!("{} = {}", symbol, solved.to_infix()),
+                    "method": "symbolic",
+                });
+                if let Ok(value) = solved.eval(&variables) {
+                    response["value"] = json!(value);
+                }
+                if !notes.is_empty() {
+                    response["notes"] = json!(notes);
+                }
+                return serde_json::to_string_pretty(&response).map_err(|e| e.to_string());
+            }
+
+            let mut missing: Vec<&str> = lhs
+                .free_variables()
+                .into_iter()
+                .chain(rhs.free_variables())
+                .filter(|name| *name != symbol && !variables.contains_key(*name))
+                .collect();
+            missing.sort_unstable();
+            missing.dedup();
+            if !missing.is_empty() {
+                return Err(format!(
+                    "{} について記号的に解けませんでした。数値的に解くには variables で {} の値を指定してください",
+                    symbol,
+                    missing.join(", ")
+                ));
+            }
+            let guess = request.guess.unwrap_or(1.0);
+            let residual = |x: f64| {
+                let mut variables = variables.clone();
+                variables.insert(symbol.to_string(), x);
+                Ok(lhs.eval(&variables)? - rhs.eval(&variables)?)
+            };
+            let root = solver::find_root(residual, guess)?;
+            // 二分法が不連続な点（1/x の 0 など）に収束した場合は解ではない
+            let scale = residual(guess).map_or(1.0, |value: f64| value.abs().max(1.0));
+            if !root.residual.is_finite() || root.residual.abs() > 1e-9 * scale {
+                return Err(format!(
+                    "初期値 {} の近くで解が見つかりませんでした（別の初期値を試してください）",
+                    guess
+                ));
+            }
+            let response = json!({
+                "symbol": symbol,
+                "value": root.value,
+                "residual": root.residual,
+                "iterations": root.iterations,
+                "method": "numeric",
+            });
+            serde_json::to_string_pretty(&response).map_err(|e| e.to_string())
+        }
+        #[cfg(not(feature = "symbolic"))]
+        {
+            let _ = request;
+            Err("このビルドでは rearrange は使えません（symbolic feature）".to_string())
+        }
+    }
+
+    #[tool(
+        description = "連立方程式（各式 = 0）を、未知数の初期値からニュートン・ラフソン法（ヤコビ行列は数値微分）で解きます。解、各式の残差、収束したか（converged）、反復回数をJSONで返します。収束しない場合は初期値を変えてください。"
+    )]
+    pub fn solve_system(
+        &self,
+        #[tool(aggr)] request: SolveSystemRequest,
+    ) -> Result<String, String> {
+        #[cfg(feature = "symbolic")]
+        {
+            let calculator = self
+                .calculator()
+                .map_err(|e| format!("計算エラー: {}", e))?;
+            let equations = request
+                .equations
+                .iter()
+                .map(|equation| {
+                    let expression = match equation.split('=').collect::<Vec<_>>()[..] {
+                        [expression] => expression.to_string(),
+                        [lhs, rhs] => format!("({}) - ({})", lhs, rhs),
+                        _ => return Err(format!("= は1つまでにしてください: {}", equation)),
+                    };
+                    calculator
+                        .compile(&expression)
+                        .map_err(|e| format!("計算エラー: {}: {}", equation, e))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let (names, guess): (Vec<String>, Vec<f64>) = request.unknowns.into_iter().unzip();
+            let mut max_iterations = request.max_iterations.unwrap_or(100).min(1000);
+            if let Some(profile) = self.safe_profile() {
+                profile.check_list_length(guess.len())?;
+                max_iterations = max_iterations.min(profile.max_iterations);
+            }
+            let variables: HashMap<String, f64> =
+                request.variables.unwrap_or_default().into_iter().collect();
+            let tolerance = request.tolerance.unwrap_or(1e-10);
+            if tolerance <= 0.0 || !tolerance.is_finite() {
+                return Err(format!("tolerance は正の数にしてください: {}", tolerance));
+            }
+            let residuals = |x: &[f64]| {
+                let mut variables = variables.clone();
+                variables.extend(names.iter().cloned().zip(x.iter().copied()));
+                equations
+                    .iter()
+                    .map(|equation| equation.eval(&variables))
+                    .collect::<Result<Vec<f64>, String>>()
+            };
+            let solution = solver::solve_system(residuals, &guess, tolerance, max_iterations)?;
+            let response = json!({
+                "solution": names.iter().zip(&solution.values).collect::<BTreeMap<_, _>>(),
+                "residuals": solution.residuals,
+                "converged": solution.converged,
+                "iterations": solution.iterations,
+            });
+            serde_json::to_string_pretty(&response).map_err(|e| e.to_string())
+        }
+        #[cfg(not(feature = "symbolic"))]
+        {
+            let _ = request;
+            Err("このビルドでは solve_system は使えません（symbolic feature）".to_string())
+        }
+    }
+
+    #[tool(
+        description = "不等式・等式の制約（x + y <= 10 など）を変数の値で評価し、各制約が成り立つか、両辺の値、差、満たすのに足りない量（violation）と、成り立った数・成り立たなかった数をJSONで返します。最適化や文章題の答えの検証に使えます。"
+    )]
+    pub fn check_constraints(
+        &self,
+        #[tool(aggr)] request: CheckConstraintsRequest,
+    ) -> Result<String, String> {
+        let calculator = self
+            .calculator()
+            .map_err(|e| format!("計算エラー: {}", e))?;
+        let variables: HashMap<String, f64> =
+            request.variables.unwrap_or_default().into_iter().collect();
+        let max_expressions = self.config().limits.max_batch_expressions;
+        if request.constraints.len() > max_expressions {
+            return Err(format!(
+                "制約が多すぎます: {} 個（上限は {} 個）",
+                request.constraints.len(),
+                max_expressions
+            ));
+        }
+        let evaluate = |side: &str| {
+            calculator
+                .compile(side)
+                .and_then(|compiled| compiled.eval(&variables))
+        };
+        let mut results = Vec::with_capacity(request.constraints.len());
+        let (mut held, mut failed) = (0, 0);
+        for constraint in &request.constraints {
+            let check = constraints::split(constraint).and_then(|(lhs, relation, rhs)| {
+                Ok(constraints::check(
+                    constraint,
+                    evaluate(lhs)?,
+                    relation,
+                    evaluate(rhs)?,
+                ))
+            });
+            match check {
+                Ok(check) => {
+                    if check.holds {
+                        held += 1;
+                    } else {
+                        failed += 1;
+                    }
+                    results.push(json!(check));
+                }
+                Err(e) => {
+                    failed += 1;
+                    results.push(json!({ "constraint": constraint, "holds": false, "error": e }));
+                }
+            }
+        }
+        let response = json!({
+            "all_hold": failed == 0,
+            "held": held,
+            "failed": failed,
+            "results": results,
+        });
+        serde_json::to_string_pretty(&response).map_err(|e| e.to_string())
+    }
+
+    #[tool(
+        description = "名前を付けた数式を保存し、変数を変えたときに、その変数に（他の数式を通して）依存する数式だけを依存関係の順に再計算します。formulas で数式を追加・置き換え、variables で変数を変更すると、再計算した数式の名前、値が変わった数式の前後の値、すべての数式の現在の値をJSONで返します。数式からは変数と他の数式を名前で参照できます。"
+    )]
+    pub fn recalculate(&self, #[tool(aggr)] request: RecalculateRequest) -> Result<String, String> {
+        let updates = request.variables.unwrap_or_default();
+        let definitions = request.formulas.unwrap_or_default();
+        let calculator = self
+            .calculator()
+            .map_err(|e| format!("計算エラー: {}", e))?;
+        let mut formulas = self.formulas.lock().unwrap_or_else(|e| e.into_inner());
+        // すべての変更を確かめてから反映する
+        let mut updated = formulas.clone();
+        let mut names = self.config().calculator().map_err(|e| e.to_string())?;
+        for (name, value) in &updates {
+            if !value.is_finite() {
+                return Err(format!("変数の値が無効です: {} = {}", name, value));
+            }
+            if definitions.contains_key(name) || formulas.contains(name) {
+                return Err(format!("数式と同じ名前の変数は使えません: {}", name));
+            }
+            names.set_variable(name, *value)?;
+        }
+        for (name, expression) in &definitions {
+            if self.variables().contains_key(name) {
+                return Err(format!("変数と同じ名前の数式は使えません: {}", name));
+            }
+            names.set_variable(name, 0.0)?;
+            updated.define(&calculator, name, expression)?;
+        }
+
+        let mut variables = self.variables.write().unwrap_or_else(|e| e.into_inner());
+        let mut changed: BTreeSet<String> = updates
+            .iter()
+            .filter(|(name, value)| variables.get(*name) != Some(*value))
+            .map(|(name, _)| name.clone())
+            .collect();
+        changed.extend(definitions.into_keys());
+        variables.extend(updates);
+        let (recalculated, changes) = updated.recalculate(&variables, &changed);
+        *formulas = updated;
+        let response = json!({
+            "recalculated": recalculated,
+            "changed": changes,
+            "formulas": formulas.summary(),
+        });
+        serde_json::to_string_pretty(&response).map_err(|e| e.to_string())
+    }
+
+    #[tool(
+        description = "このセッションの状態（set_options の設定、load_variables と recalculate の変数、recalculate で保存した数式、計算履歴、upload_data で受け取ったデータ）を既定の状態に戻します。新しい会話を始めるときに前の状態を持ち越さないために使います。セッションの終了時にも自動で行います。状態はクライアントごとではなくサーバで1つのため、複数のクライアントが接続している場合はすべてのクライアントの状態が戻ります（自動で戻すのは最後のセッションが終了したときだけです）。"
+    )]
+    pub fn reset_session(&self) -> Result<String, String> {
+        let cleared: Vec<String> = self
+            .clear_session()
+            .into_iter()
+            .map(|(kind, count)| format!("{} {} 件", kind, count))
+            .collect();
+        Ok(format!(
+            "セッションをリセットしました（設定を既定値に戻し、{}を削除しました）",
+            cleared.join("、")
+        ))
+    }
+
+    #[tool(
+        description = "このサーバのビルド情報（バージョン、gitコミット、ビルド日時、有効なfeature、対応するMCPプロトコルバージョン）をJSONで返します。不具合報告に添付してください。"
+    )]
+    pub fn server_info(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(&build_info::build_info()).map_err(|e| e.to_string())
+    }
+
+    #[tool(
+        description = "サーバの稼働状態を確認します。計算エンジンの自己診断（解析・評価・ホワイトリスト）を実行し、バージョン、稼働時間、接続中のセッション数をJSONで返します。"
+    )]
+    pub fn health(&self) -> Result<String, String> {
+        let report = HealthReport::new(
+            self.started_at.elapsed().as_secs(),
+            self.sessions.load(Ordering::SeqCst),
+        );
+        let json = serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?;
+        if report.is_ok() {
+            Ok(json)
+        } else {
+            Err(json)
+        }
+    }
+}
+
+impl ServerHandler for CalculatorService {
+    fn get_info(&self) -> InitializeResult {
+        InitializeResult {
+            protocol_version: ProtocolVersion::V_2024_11_05,
+            capabilities: ServerCapabilities::builder()
+                .enable_tools()
+                .enable_tool_list_changed()
+                .enable_resources()
+                .build(),
+            server_info: Implementation {
+                name: "calc-mcp".into(),
+                version: build_info::VERSION.into(),
+            },
+            instructions: Some(format!(
+                "{}\n\nビルド情報: {}",
+                self.config().tools.language().combine(
+                    "計算機能を提供するMCPサーバです。数式を受け取って計算結果を返します。",
+                    i18n::INSTRUCTIONS_EN
+                ),
+                build_info::summary()
+            )),
+        }
+    }
+
+    // 接続ごとに1回呼ばれる。すべてのクライアントにツール一覧の変更を通知できるよう保持する
+    fn set_peer(&mut self, peer: Peer<RoleServer>) {
+        let id = self.next_peer.fetch_add(1, Ordering::SeqCst);
+        self.peers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(id, peer);
+    }
+
+    async fn on_initialized(&self) {
+        self.sessions.fetch_add(1, Ordering::SeqCst);
+    }
+
+    async fn list_tools(
+        &self,
+        _request: PaginatedRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, McpError> {
+        Ok(ListToolsResult {
+            next_cursor: None,
+            tools: self.localized_tools(),
+        })
+    }
+
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        // 無効化されたツールは tools/list に出ないだけでなく、呼び出しも拒否する
+        if !self.config().tools.is_enabled(&request.name) {
+            return Err(McpError::invalid_params(
+                format!("このツールは無効化されています: {}", request.name),
+                None,
+            ));
+        }
+        // 高負荷でも稼働状態は確認できるよう、health は制限しない
+        let permit = if request.name == "health" {
+            None
+        } else {
+            let admission = self
+                .admission
+                .read()
+                .unwrap_or_else(|e| e.into_inner())
+                .clone();
+            admission
+                .admit()
+                .await
+                .map_err(|e| McpError::internal_error(e, None))?
+        };
+        // 重い計算（多倍長整数のべき乗、モンテカルロ法、行列の分解など）で非同期ランタイムの
+        // スレッドを塞ぐと、stdio の keepalive やキャンセル通知の処理が止まるため、
+        // ブロッキング用のスレッドで実行する。スレッドの数は上の制限で max_concurrent までになる
+        // （max_concurrent が 0 なら制限せず、tokio のブロッキング用のスレッドの上限（既定 512）までになる）
+        let service = self.clone();
+        let runtime = tokio::runtime::Handle::current();
+        let name = request.name.to_string();
+        let evaluation = tokio::task::spawn_blocking(move || {
+            // キャンセルされた後も評価が終わるまで枠を占有し、同時に実行する数を守る
+            let _permit = permit;
+            let context = ToolCallContext::new(&service, request, context);
+            // 状態のロックはポイズニングを無視して使い続ける。ツールが状態を更新する途中で
+            // パニックすると更新が途中のまま残りうるため、パニックの後はそのときロックを持っていた
+            // 状態だけを既定に戻す（設定は1回の代入で差し替えるため、途中の状態にはならない）
+            let outcome = panic::catch_unwind(AssertUnwindSafe(|| {
+                let result = runtime.block_on(Self::tool_box().call(context))?;
+                // 設定が不正で計算機を作れない場合も、ヒントのない詳細は付ける
+                let calculator = service.calculator().unwrap_or_default();
+                Ok(with_image(with_table(with_error_details(
+                    result,
+                    &calculator,
+                ))))
+            }));
+            outcome.map_err(|payload| (payload, service.recover_poisoned()))
+        });
+        match evaluation.await {
+            Ok(Ok(result)) => result,
+            Ok(Err((payload, recovered))) => {
+                let message = panic_message(payload.as_ref());
+                let message = if recovered.is_empty() {
+                    message
+                } else {
+                    format!(
+                        "{}（更新の途中だった{}を既定に戻しました）",
+                        message,
+                        recovered.join("・")
+                    )
+                };
+                Err(internal_error(&name, &message))
+            }
+            Err(e) => Err(internal_error(&name, &e.to_string())),
+        }
+    }
+
+    async fn list_resources(
+        &self,
+        _request: PaginatedRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, McpError> {
+        let mut syntax = RawResource::new(SYNTAX_URI, "calculate の構文と上限");
+        syntax.mime_type = Some("application/json".to_string());
+        let history = [
+            (history::CSV_URI, "text/csv"),
+            (history::JSON_URI, "application/json"),
+        ]
+        .into_iter()
+        .map(|(uri, mime_type)| {
+            let mut resource = RawResource::new(uri, "calculate と calculate_batch の計算履歴");
+            resource.mime_type = Some(mime_type.to_string());
+            resource.no_annotation()
+        });
+        let resources = std::iter::once(syntax.no_annotation())
+            .chain(history)
+            .chain(self.uploads.list().into_iter().map(|(uri, bytes)| {
+                let mut resource = RawResource::new(uri.clone(), uri);
+                resource.mime_type = Some("text/plain".to_string());
+                resource.size = u32::try_from(bytes).ok();
+                resource.no_annotation()
+            }))
+            .collect();
+        Ok(ListResourcesResult {
+            resources,
+            next_cursor: None,
+        })
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, McpError> {
+        let text = if request.uri == SYNTAX_URI {
+            self.syntax()
+                .map_err(|e| McpError::internal_error(e, None))?
+        } else if request.uri == history::CSV_URI {
+            self.history.to_csv()
+        } else if request.uri == history::JSON_URI {
+            self.history
+                .to_json()
+                .map_err(|e| McpError::internal_error(e, None))?
+        } else {
+            self.uploads
+                .get(&request.uri)
+                .map_err(|e| McpError::resource_not_found(e, None))?
+        };
+        Ok(ReadResourceResult {
+            contents: vec![ResourceContents::text(text, request.uri)],
+        })
+    }
+}
@@ -0,0 +1,211 @@
+use crate::calculator::{Calculator, CompiledExpr};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+// sheet ツールの計算結果
+#[derive(Debug, Serialize)]
+pub struct Sheet {
+    // 入力と同じ形の表。空のセルとエラーになったセルは null
+    pub values: Vec<Vec<Option<f64>>>,
+    // エラーになったセル（A1 形式）とその理由
+    pub errors: BTreeMap<String, String>,
+}
+
+// セルの内容
+enum Cell {
+    Empty,
+    Number(f64),
+    Formula(CompiledExpr),
+    Invalid(String),
+}
+
+// 評価の状態（深さ優先探索で循環参照を見つける）
+#[derive(Clone, Copy, PartialEq)]
+enum State {
+    Pending,
+    Visiting,
+    Done,
+}
+
+// 列番号（0 始まり）を A, B, ..., Z, AA, AB, ... の形にする
+pub fn column_name(mut column: usize) -> String {
+    let mut name = Vec::new();
+    loop {
+        name.push(b'A' + (column % 26) as u8);
+        if column < 26 {
+            break;
+        }
+        column = column / 26 - 1;
+    }
+    name.iter().rev().map(|&byte| byte as char).collect()
+}
+
+pub fn cell_name(row: usize, column: usize) -> String {
+    format!("{}{}", column_name(column), row + 1)
+}
+
+// A1 形式の名前を (行, 列)（0 始まり）にする。セルの名前でなければ None
+pub fn parse_cell_name(name: &str) -> Option<(usize, usize)> {
+    let digits = name.find(|c: char| c.is_ascii_digit())?;
+    let (letters, number) = name.split_at(digits);
+    if letters.is_empty() || !letters.bytes().all(|b| b.is_ascii_uppercase()) {
+        return None;
+    }
+    if number.starts_with('0') || !number.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let column = letters.bytes().try_fold(0usize, |acc, b| {
+        acc.checked_mul(26)?.checked_add(usize::from(b - b'A') + 1)
+    })? - 1;
+    let row = number.parse::<usize>().ok()? - 1;
+    Some((row, column))
+}
+
+// 各セルは数値、= で始まる数式、または空。数式からは A1 形式で他のセルを参照でき、
+// 空のセルは 0 として扱う。参照の依存関係の順に評価し、循環参照はエラーにする
+pub fn evaluate(calculator: &Calculator, grid: &[Vec<String>]) -> Sheet {
+    let cells: Vec<Vec<Cell>> = grid
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|text| parse_cell(calculator, text))
+                .collect()
+        })
+        .collect();
+    let mut evaluator = Evaluator {
+        cells: &cells,
+        states: cells
+            .iter()
+            .map(|row| vec![State::Pending; row.len()])
+            .collect(),
+        results: cells.iter().map(|row| vec![Ok(None); row.len()]).collect(),
+    };
+    for (row, cells_in_row) in cells.iter().enumerate() {
+        for column in 0..cells_in_row.len() {
+            evaluator.visit(row, column, &mut Vec::new());
+        }
+    }
+
+    let mut errors = BTreeMap::new();
+    let values = evaluator
+        .results
+        .into_iter()
+        .enumerate()
+        .map(|(row, results)| {
+            results
+                .into_iter()
+                .enumerate()
+                .map(|(column, result)| match result {
+                    Ok(value) => value,
+                    Err(e) => {
+                        errors.insert(cell_name(row, column), e);
+                        None
+                    }
+                })
+                .collect()
+        })
+        .collect();
+    Sheet { values, errors }
+}
+
+fn parse_cell(calculator: &Calculator, text: &str) -> Cell {
+    let text = text.trim();
+    if text.is_empty() {
+        return Cell::Empty;
+    }
+    if let Some(formula) = text.strip_prefix('=') {
+        return match calculator.compile(formula) {
+            Ok(compiled) => Cell::Formula(compiled),
+            Err(e) => Cell::Invalid(e),
+        };
+    }
+    match text.parse::<f64>() {
+        Ok(value) => Cell::Number(value),
+        Err(_) => Cell::Invalid(format!("数値か = で始まる数式を指定してください: {}", text)),
+    }
+}
+
+struct Evaluator<'a> {
+    cells: &'a [Vec<Cell>],
+    states: Vec<Vec<State>>,
+    results: Vec<Vec<Result<Option<f64>, String>>>,
+}
+
+impl Evaluator<'_> {
+    // path は評価中のセルの並び（循環参照の報告に使う）
+    fn visit(&mut self, row: usize, column: usize, path: &mut Vec<(usize, usize)>) {
+        match self.states[row][column] {
+            State::Done => return,
+            State::Visiting => {
+                // path の中で自分から始まる部分が循環している
+                let start = path
+                    .iter()
+                    .position(|&cell| cell == (row, column))
+                    .unwrap_or_default();
+                let mut names: Vec<String> = path[start..]
+                    .iter()
+                    .map(|&(row, column)| cell_name(row, column))
+                    .collect();
+                names.push(cell_name(row, column));
+                let message = format!("循環参照しています: {}", names.join(" → "));
+                for &(row, column) in &path[start..] {
+                    self.states[row][column] = State::Done;
+                    self.results[row][column] = Err(message.clone());
+                }
+                return;
+            }
+            State::Pending => {}
+        }
+        let result = match &self.cells[row][column] {
+            Cell::Empty => Ok(None),
+            Cell::Number(value) => Ok(Some(*value)),
+            Cell::Invalid(e) => Err(e.clone()),
+            Cell::Formula(compiled) => {
+                self.states[row][column] = State::Visiting;
+                path.push((row, column));
+                let result = self.formula(compiled, path);
+                path.pop();
+                result.map(Some)
+            }
+        };
+        // 循環の途中で結果が決まったセルはそのエラーを残す
+        if self.states[row][column] != State::Done {
+            self.states[row][column] = State::Done;
+            self.results[row][column] = result;
+        }
+    }
+
+    fn formula(
+        &mut self,
+        compiled: &CompiledExpr,
+        path: &mut Vec<(usize, usize)>,
+    ) -> Result<f64, String> {
+        let mut vars = HashMap::new();
+        for name in compiled.variables() {
+            let Some((row, column)) = parse_cell_name(name) else {
+                continue;
+            };
+            if self
+                .cells
+                .get(row)
+                .is_none_or(|cells| column >= cells.len())
+            {
+                return Err(format!("表の範囲外のセルを参照しています: {}", name));
+            }
+            self.visit(row, column, path);
+            let (current_row, current_column) = *path.last().expect("評価中のセル");
+            if self.states[current_row][current_column] == State::Done {
+                // 自分も循環参照に含まれていた
+                let result = &self.results[current_row][current_column];
+                return Err(result.clone().err().unwrap_or_default());
+            }
+            match &self.results[row][column] {
+                Ok(value) => {
+                    vars.insert(name.to_string(), value.unwrap_or(0.0));
+                }
+                Err(_) => return Err(format!("参照しているセル {} がエラーです", name)),
+            }
+        }
+        compiled.eval(&vars)
+    }
+}
@@ -0,0 +1,63 @@
+#[cfg(test)]
+mod tests {
+    use crate::calculator::Calculator;
+    use crate::sheet::{cell_name, column_name, evaluate, parse_cell_name};
+
+    fn grid(rows: &[&[&str]]) -> Vec<Vec<String>> {
+        rows.iter()
+            .map(|row| row.iter().map(|cell| cell.to_string()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_cell_names() {
+        assert_eq!(column_name(0), "A");
+        assert_eq!(column_name(25), "Z");
+        assert_eq!(column_name(26), "AA");
+        assert_eq!(column_name(701), "ZZ");
+        assert_eq!(cell_name(9, 27), "AB10");
+        assert_eq!(parse_cell_name("AB10"), Some((9, 27)));
+        assert_eq!(parse_cell_name("A1"), Some((0, 0)));
+        assert_eq!(parse_cell_name("A0"), None);
+        assert_eq!(parse_cell_name("a1"), None);
+        assert_eq!(parse_cell_name("rate"), None);
+        assert_eq!(parse_cell_name("A1B"), None);
+    }
+
+    #[test]
+    fn test_evaluate_sheet() {
+        let calculator = Calculator::new();
+        // 依存関係の順に評価する（C1 は後ろの行を参照する）
+        let cells = grid(&[
+            &["120", "3", "=A1 * B1"],
+            &["80", "", "=A2 * B2"],
+            &["", "", "=C1 + C2"],
+        ]);
+        let sheet = evaluate(&calculator, &cells);
+        assert!(sheet.errors.is_empty(), "{:?}", sheet.errors);
+        assert_eq!(sheet.values[0][2], Some(360.0));
+        assert_eq!(sheet.values[1][2], Some(0.0));
+        assert_eq!(sheet.values[2][2], Some(360.0));
+        assert_eq!(sheet.values[2][0], None);
+    }
+
+    #[test]
+    fn test_sheet_errors() {
+        let calculator = Calculator::new();
+        let cells = grid(&[
+            &["=B1 + 1", "=A1 * 2", "=A1"],
+            &["=Z9", "abc", "=B2 + 1"],
+            &["=1 / 0", "=B3", "5"],
+        ]);
+        let sheet = evaluate(&calculator, &cells);
+        assert_eq!(sheet.errors["A1"], "循環参照しています: A1 → B1 → A1");
+        assert_eq!(sheet.errors["B1"], sheet.errors["A1"]);
+        assert!(sheet.errors["C1"].contains("A1"));
+        assert!(sheet.errors["A2"].contains("範囲外"));
+        assert!(sheet.errors["B2"].contains("= で始まる数式"));
+        assert!(sheet.errors["C2"].contains("B2"));
+        assert!(sheet.errors.contains_key("A3"));
+        assert!(sheet.errors["B3"].contains("循環参照"));
+        assert_eq!(sheet.values[2][2], Some(5.0));
+    }
+}
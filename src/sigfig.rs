@@ -0,0 +1,180 @@
+use crate::rounding::{round_to, RoundingMode};
+
+// 有効数字の情報（sig_figs モード）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Figures {
+    // 有効数字の桁数
+    pub digits: u32,
+    // 最後の有効桁の位（10^place の位。0 は一の位、-2 は小数第2位）
+    pub place: i32,
+}
+
+impl Figures {
+    // 数値リテラルの有効数字。整数の末尾の 0 は有効とみなさない（1200 は2桁、1200. は4桁）
+    pub fn of_literal(literal: &str) -> Self {
+        let (integer, fraction) = match literal.split_once('.') {
+            Some((integer, fraction)) => (integer, Some(fraction)),
+            None => (literal, None),
+        };
+        let all_digits = format!("{}{}", integer, fraction.unwrap_or(""));
+        let significant = all_digits.trim_start_matches('0');
+        let (digits, place) = match fraction {
+            Some(fraction) => (significant.len(), -(fraction.len() as i32)),
+            None => {
+                let trimmed = significant.trim_end_matches('0');
+                (trimmed.len(), (significant.len() - trimmed.len()) as i32)
+            }
+        };
+        Self {
+            // 0 や 0.00 は1桁として扱う
+            digits: digits.max(1) as u32,
+            place,
+        }
+    }
+
+    fn with_place(value: f64, place: i32) -> Self {
+        Self {
+            digits: (magnitude(value) - place + 1).max(1) as u32,
+            place,
+        }
+    }
+
+    fn with_digits(value: f64, digits: u32) -> Self {
+        Self {
+            digits,
+            place: magnitude(value) - digits as i32 + 1,
+        }
+    }
+}
+
+// 評価中の値。定数や変数など厳密な値は figures が None
+#[derive(Debug, Clone)]
+pub struct Measured {
+    pub value: f64,
+    pub figures: Option<Figures>,
+    // 有効数字を決めた規則の説明（評価した順）
+    pub notes: Vec<String>,
+}
+
+impl Measured {
+    pub fn exact(value: f64) -> Self {
+        Self {
+            value,
+            figures: None,
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn literal(value: f64, figures: Figures) -> Self {
+        Self {
+            value,
+            figures: Some(figures),
+            notes: Vec::new(),
+        }
+    }
+
+    // 値だけを変える演算（単項演算・関数）。有効数字の桁数は引き継ぐ
+    pub fn map(self, value: f64) -> Self {
+        Self {
+            value,
+            figures: self
+                .figures
+                .map(|figures| Figures::with_digits(value, figures.digits)),
+            notes: self.notes,
+        }
+    }
+
+    // 二項演算の結果。加減算は最後の有効桁の位、乗除算・べき乗は有効数字の桁数で決める
+    pub fn combine(self, operator: char, right: Self, value: f64) -> Self {
+        let mut notes = self.notes;
+        notes.extend(right.notes);
+        let figures = match (operator, self.figures, right.figures) {
+            (_, None, None) => None,
+            ('+' | '-', left_figures, right_figures) => {
+                let place = left_figures
+                    .into_iter()
+                    .chain(right_figures)
+                    .map(|figures| figures.place)
+                    .max()
+                    .unwrap_or(0);
+                let figures = Figures::with_place(value, place);
+                notes.push(format!(
+                    "{} {} {} = {}: 加減算は最後の有効桁が最も高い位（10^{} の位）に合わせます",
+                    format_measured(self.value, self.figures),
+                    operator,
+                    format_measured(right.value, right.figures),
+                    format_figures(value, figures),
+                    place
+                ));
+                Some(figures)
+            }
+            // べき乗の指数は厳密な値として扱う
+            ('^', None, _) => None,
+            ('^', Some(base), _) => Some(Figures::with_digits(value, base.digits)),
+            (_, left_figures, right_figures) => {
+                let digits = left_figures
+                    .into_iter()
+                    .chain(right_figures)
+                    .map(|figures| figures.digits)
+                    .min()
+                    .unwrap_or(1);
+                let figures = Figures::with_digits(value, digits);
+                notes.push(format!(
+                    "{} {} {} = {}: 乗除算は有効数字の桁数が最も少ない値（{}桁）に合わせます",
+                    format_measured(self.value, self.figures),
+                    operator,
+                    format_measured(right.value, right.figures),
+                    format_figures(value, figures),
+                    digits
+                ));
+                Some(figures)
+            }
+        };
+        Self {
+            value,
+            figures,
+            notes,
+        }
+    }
+}
+
+// 有効数字に合わせた表示（2.50, 0.0045, 1.2e3 など）
+pub fn format_figures(value: f64, figures: Figures) -> String {
+    if figures.place < 0 {
+        let decimals = figures.place.unsigned_abs();
+        return format!(
+            "{:.*}",
+            decimals as usize,
+            round_to(value, decimals, RoundingMode::HalfUp)
+        );
+    }
+    let scale = 10f64.powi(figures.place);
+    let rounded = round_to(value / scale, 0, RoundingMode::HalfUp) * scale;
+    if figures.place == 0 {
+        return format!("{}", rounded);
+    }
+    // 末尾の 0 が有効数字でないことが分かるよう指数表記にする
+    let exponent = magnitude(rounded);
+    let mantissa = rounded / 10f64.powi(exponent);
+    format!(
+        "{:.*}e{}",
+        figures.digits.saturating_sub(1) as usize,
+        mantissa,
+        exponent
+    )
+}
+
+fn format_measured(value: f64, figures: Option<Figures>) -> String {
+    match figures {
+        Some(figures) => format_figures(value, figures),
+        None => value.to_string(),
+    }
+}
+
+fn magnitude(value: f64) -> i32 {
+    if value == 0.0 || !value.is_finite() {
+        0
+    } else {
+        value.abs().log10().floor() as i32
+    }
+}
@@ -0,0 +1,67 @@
+#[cfg(test)]
+mod tests {
+    use crate::calculator::Calculator;
+    use crate::sigfig::{format_figures, Figures};
+
+    fn figures(literal: &str) -> (u32, i32) {
+        let figures = Figures::of_literal(literal);
+        (figures.digits, figures.place)
+    }
+
+    #[test]
+    fn test_literal_figures() {
+        assert_eq!(figures("12.0"), (3, -1));
+        assert_eq!(figures("0.0045"), (2, -4));
+        assert_eq!(figures("1200"), (2, 2));
+        assert_eq!(figures("1200."), (4, 0));
+        assert_eq!(figures("250"), (2, 1));
+        assert_eq!(figures("7"), (1, 0));
+        assert_eq!(figures("0.00"), (1, -2));
+    }
+
+    #[test]
+    fn test_multiplication_uses_fewest_digits() {
+        let result = Calculator::new().evaluate_sig_figs("12.0 * 2.5").unwrap();
+        assert_eq!(result.display, "30");
+        assert_eq!(result.digits, Some(2));
+        assert_eq!(result.explanation.len(), 1);
+        assert!(result.explanation[0].contains("乗除算"));
+
+        let result = Calculator::new().evaluate_sig_figs("2.50 * 4.000").unwrap();
+        assert_eq!(result.display, "10.0");
+    }
+
+    #[test]
+    fn test_addition_uses_coarsest_place() {
+        let result = Calculator::new().evaluate_sig_figs("12.11 + 0.3").unwrap();
+        assert_eq!(result.display, "12.4");
+        assert_eq!(result.digits, Some(3));
+        assert!(result.explanation[0].contains("加減算"));
+    }
+
+    #[test]
+    fn test_mixed_operations() {
+        // (1.25 + 3.1) = 4.4（小数第1位）、4.4 * 2.000 = 8.7（2桁）
+        let result = Calculator::new()
+            .evaluate_sig_figs("(1.25 + 3.1) * 2.000")
+            .unwrap();
+        assert_eq!(result.display, "8.7");
+        assert_eq!(result.explanation.len(), 2);
+    }
+
+    #[test]
+    fn test_exact_values_are_not_rounded() {
+        let mut calculator = Calculator::new();
+        calculator.set_variable("n", 3.0).unwrap();
+        let result = calculator.evaluate_sig_figs("n * n").unwrap();
+        assert_eq!(result.digits, None);
+        assert_eq!(result.display, "9");
+    }
+
+    #[test]
+    fn test_format_figures() {
+        let figures = Figures::of_literal("1200");
+        assert_eq!(format_figures(1234.0, figures), "1.2e3");
+        assert_eq!(format_figures(0.5, Figures::of_literal("0.500")), "0.500");
+    }
+}
@@ -0,0 +1,158 @@
+use crate::statistics;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "server", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum SmoothingMethod {
+    // 単純移動平均（直近 window 個の平均）
+    Simple,
+    // 指数移動平均（平滑化係数 alpha。window を指定すると alpha = 2 / (window + 1)）
+    Exponential,
+    // 直近 window 個の平均・標準偏差・最小・最大・合計
+    Rolling,
+}
+
+// 系列の各位置の値。window 個そろわない先頭の位置は None（JSON では null）
+pub type Series = Vec<Option<f64>>;
+
+#[derive(Debug, Serialize)]
+pub struct Smoothed {
+    pub method: SmoothingMethod,
+    pub count: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub window: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alpha: Option<f64>,
+    // 平滑化した系列（入力と同じ長さ）。rolling では移動平均
+    pub values: Series,
+    // rolling の場合だけ出力する。標準偏差は n - 1 で割る標本標準偏差
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub std_dev: Option<Series>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min: Option<Series>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<Series>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sum: Option<Series>,
+}
+
+fn check_window(window: Option<usize>, count: usize) -> Result<usize, String> {
+    match window {
+        None => Err("window（移動平均をとる個数）を指定してください".to_string()),
+        Some(0) => Err("window は 1 以上にしてください".to_string()),
+        Some(window) if window > count => Err(format!(
+            "window（{}）がデータの個数（{}）より大きくなっています",
+            window, count
+        )),
+        Some(window) => Ok(window),
+    }
+}
+
+// window 個そろった位置だけ値を入れた系列
+fn windowed(count: usize, window: usize, mut value: impl FnMut(usize) -> f64) -> Series {
+    (0..count)
+        .map(|end| (end + 1 >= window).then(|| value(end)))
+        .collect()
+}
+
+pub fn smooth(
+    values: &[f64],
+    method: SmoothingMethod,
+    window: Option<usize>,
+    alpha: Option<f64>,
+) -> Result<Smoothed, String> {
+    statistics::check_values(values)?;
+    let count = values.len();
+    let mut smoothed = Smoothed {
+        method,
+        count,
+        window: None,
+        alpha: None,
+        values: Vec::new(),
+        std_dev: None,
+        min: None,
+        max: None,
+        sum: None,
+    };
+    if method == SmoothingMethod::Exponential {
+        let alpha = match (alpha, window) {
+            (Some(alpha), _) if alpha > 0.0 && alpha <= 1.0 => alpha,
+            (Some(alpha), _) => {
+                return Err(format!(
+                    "alpha は 0 より大きく 1 以下にしてください: {}",
+                    alpha
+                ))
+            }
+            (None, window) => 2.0 / (check_window(window, count)? as f64 + 1.0),
+        };
+        // 最初の値から始める（pandas の ewm(adjust=False) と同じ）
+        let mut average = values[0];
+        smoothed.values = values
+            .iter()
+            .map(|value| {
+                average += alpha * (value - average);
+                Some(average)
+            })
+            .collect();
+        smoothed.window = window;
+        smoothed.alpha = Some(alpha);
+        return Ok(smoothed);
+    }
+    if alpha.is_some() {
+        return Err("alpha は method が exponential の場合だけ指定できます".to_string());
+    }
+    let window = check_window(window, count)?;
+    // 累積和の差で窓の合計を求める。全体の平均を引いてから2乗和をとり、桁落ちを抑える
+    let mean = values.iter().sum::<f64>() / count as f64;
+    let mut sums = vec![0.0; count + 1];
+    let mut squares = vec![0.0; count + 1];
+    for (i, value) in values.iter().enumerate() {
+        let deviation = value - mean;
+        sums[i + 1] = sums[i] + deviation;
+        squares[i + 1] = squares[i] + deviation * deviation;
+    }
+    let n = window as f64;
+    let window_sum = |end: usize| sums[end + 1] - sums[end + 1 - window];
+    smoothed.window = Some(window);
+    smoothed.values = windowed(count, window, |end| mean + window_sum(end) / n);
+    if method == SmoothingMethod::Rolling {
+        smoothed.sum = Some(windowed(count, window, |end| mean * n + window_sum(end)));
+        smoothed.std_dev = Some(if window < 2 {
+            vec![None; count]
+        } else {
+            windowed(count, window, |end| {
+                let sum = window_sum(end);
+                let square = squares[end + 1] - squares[end + 1 - window];
+                ((square - sum * sum / n) / (n - 1.0)).max(0.0).sqrt()
+            })
+        });
+        smoothed.min = Some(extremes(values, window, |a, b| a <= b));
+        smoothed.max = Some(extremes(values, window, |a, b| a >= b));
+    }
+    Ok(smoothed)
+}
+
+// 窓の最小値（keep が <=）または最大値（>=）。単調な両端キューで O(n) にする
+fn extremes(values: &[f64], window: usize, keep: impl Fn(f64, f64) -> bool) -> Series {
+    let mut candidates: VecDeque<usize> = VecDeque::new();
+    let mut series = Vec::with_capacity(values.len());
+    for (end, &value) in values.iter().enumerate() {
+        while candidates
+            .back()
+            .is_some_and(|&last| keep(value, values[last]))
+        {
+            candidates.pop_back();
+        }
+        candidates.push_back(end);
+        if candidates
+            .front()
+            .is_some_and(|&first| first + window <= end)
+        {
+            candidates.pop_front();
+        }
+        series.push((end + 1 >= window).then(|| values[candidates[0]]));
+    }
+    series
+}
@@ -0,0 +1,83 @@
+#[cfg(test)]
+mod tests {
+    use crate::smoothing::{smooth, SmoothingMethod};
+
+    fn assert_series(actual: &[Option<f64>], expected: &[Option<f64>]) {
+        assert_eq!(actual.len(), expected.len());
+        for (actual, expected) in actual.iter().zip(expected) {
+            match (actual, expected) {
+                (Some(a), Some(e)) => assert!((a - e).abs() < 1e-9, "{:?}", actual),
+                _ => assert_eq!(actual, expected),
+            }
+        }
+    }
+
+    #[test]
+    fn test_simple_moving_average() {
+        let values = [1.0, 2.0, 3.0, 4.0, 5.0, 9.0];
+        let smoothed = smooth(&values, SmoothingMethod::Simple, Some(3), None).unwrap();
+        assert_series(
+            &smoothed.values,
+            &[None, None, Some(2.0), Some(3.0), Some(4.0), Some(6.0)],
+        );
+        assert!(smoothed.std_dev.is_none());
+        // window = 1 はそのまま
+        let smoothed = smooth(&values, SmoothingMethod::Simple, Some(1), None).unwrap();
+        assert_series(&smoothed.values, &values.map(Some));
+    }
+
+    #[test]
+    fn test_exponential_moving_average() {
+        let values = [10.0, 20.0, 20.0];
+        let smoothed = smooth(&values, SmoothingMethod::Exponential, None, Some(0.5)).unwrap();
+        assert_series(&smoothed.values, &[Some(10.0), Some(15.0), Some(17.5)]);
+        // window = 3 は alpha = 0.5
+        let smoothed = smooth(&values, SmoothingMethod::Exponential, Some(3), None).unwrap();
+        assert_eq!(smoothed.alpha, Some(0.5));
+        assert!(smooth(&values, SmoothingMethod::Exponential, None, Some(1.5)).is_err());
+        assert!(smooth(&values, SmoothingMethod::Exponential, None, None).is_err());
+    }
+
+    #[test]
+    fn test_rolling_statistics() {
+        let values = [4.0, 8.0, 6.0, -2.0, 6.0];
+        let smoothed = smooth(&values, SmoothingMethod::Rolling, Some(2), None).unwrap();
+        assert_series(
+            &smoothed.values,
+            &[None, Some(6.0), Some(7.0), Some(2.0), Some(2.0)],
+        );
+        assert_series(
+            smoothed.min.as_ref().unwrap(),
+            &[None, Some(4.0), Some(6.0), Some(-2.0), Some(-2.0)],
+        );
+        assert_series(
+            smoothed.max.as_ref().unwrap(),
+            &[None, Some(8.0), Some(8.0), Some(6.0), Some(6.0)],
+        );
+        assert_series(
+            smoothed.sum.as_ref().unwrap(),
+            &[None, Some(12.0), Some(14.0), Some(4.0), Some(4.0)],
+        );
+        let two = std::f64::consts::SQRT_2;
+        assert_series(
+            smoothed.std_dev.as_ref().unwrap(),
+            &[
+                None,
+                Some(2.0 * two),
+                Some(two),
+                Some(4.0 * two),
+                Some(4.0 * two),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_invalid_window() {
+        let values = [1.0, 2.0];
+        assert!(smooth(&values, SmoothingMethod::Simple, None, None).is_err());
+        assert!(smooth(&values, SmoothingMethod::Simple, Some(0), None).is_err());
+        assert!(smooth(&values, SmoothingMethod::Rolling, Some(3), None).is_err());
+        assert!(smooth(&values, SmoothingMethod::Simple, Some(2), Some(0.5)).is_err());
+        assert!(smooth(&[], SmoothingMethod::Simple, Some(1), None).is_err());
+    }
+}
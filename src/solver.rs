@@ -0,0 +1,256 @@
+use crate::matrix::{self, Matrix};
+
+// ニュートン法の反復回数の上限
+const MAX_NEWTON_ITERATIONS: usize = 100;
+// 符号が変わる区間を探すときに幅を倍にする回数の上限（guess ± 2^60 程度まで）
+const MAX_BRACKET_STEPS: usize = 60;
+const MAX_BISECTION_ITERATIONS: usize = 200;
+// solve_system で1回の反復ごとにステップを半分にする回数の上限
+const MAX_LINE_SEARCH_STEPS: usize = 30;
+// solve_system が受け付ける未知数の数
+pub const MAX_SYSTEM_SIZE: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Root {
+    pub value: f64,
+    // 解での f の値
+    pub residual: f64,
+    pub iterations: usize,
+}
+
+// 中心差分による数値微分の刻み幅
+fn step_size(x: f64) -> f64 {
+    1e-6 * x.abs().max(1.0)
+}
+
+// f(x) = 0 の解を guess の近くで探す。ニュートン法（導関数は数値微分）で収束しなければ、
+// guess の周りに f の符号が変わる区間を広げながら探し、二分法で求める
+pub fn find_root<F>(f: F, guess: f64) -> Result<Root, String>
+where
+    F: Fn(f64) -> Result<f64, String>,
+{
+    if !guess.is_finite() {
+        return Err(format!("初期値が無効です: {}", guess));
+    }
+    if let Some(root) = newton(&f, guess)? {
+        return Ok(root);
+    }
+    bracket_and_bisect(&f, guess)
+}
+
+fn is_converged(residual: f64, step: f64, x: f64) -> bool {
+    residual == 0.0 || (residual.abs() < 1e-10 && step.abs() <= 1e-12 * x.abs().max(1.0))
+}
+
+fn newton<F>(f: &F, guess: f64) -> Result<Option<Root>, String>
+where
+    F: Fn(f64) -> Result<f64, String>,
+{
+    let mut x = guess;
+    for iteration in 1..=MAX_NEWTON_ITERATIONS {
+        let Ok(fx) = f(x) else {
+            return Ok(None);
+        };
+        if fx == 0.0 {
+            return Ok(Some(Root {
+                value: x,
+                residual: fx,
+                iterations: iteration,
+            }));
+        }
+        let h = step_size(x);
+        let (Ok(forward), Ok(backward)) = (f(x + h), f(x - h)) else {
+            return Ok(None);
+        };
+        let derivative = (forward - backward) / (2.0 * h);
+        if derivative == 0.0 || !derivative.is_finite() {
+            return Ok(None);
+        }
+        let step = fx / derivative;
+        x -= step;
+        if !x.is_finite() {
+            return Ok(None);
+        }
+        if let Ok(residual) = f(x) {
+            if is_converged(residual, step, x) {
+                return Ok(Some(Root {
+                    value: x,
+                    residual,
+                    iterations: iteration,
+                }));
+            }
+        }
+    }
+    Ok(None)
+}
+
+fn bracket_and_bisect<F>(f: &F, guess: f64) -> Result<Root, String>
+where
+    F: Fn(f64) -> Result<f64, String>,
+{
+    let sign_change = |a: f64, b: f64| match (f(a), f(b)) {
+        (Ok(fa), Ok(fb)) if fa.is_finite() && fb.is_finite() => {
+            (fa.signum() != fb.signum()).then_some((a, b))
+        }
+        _ => None,
+    };
+    let mut width = 0.01 * guess.abs().max(1.0);
+    for _ in 0..MAX_BRACKET_STEPS {
+        if let Some((low, high)) =
+            sign_change(guess, guess + width).or_else(|| sign_change(guess - width, guess))
+        {
+            return bisect(f, low, high);
+        }
+        width *= 2.0;
+    }
+    Err(format!(
+        "初期値 {} の近くで解が見つかりませんでした（別の初期値を試してください）",
+        guess
+    ))
+}
+
+fn bisect<F>(f: &F, mut low: f64, mut high: f64) -> Result<Root, String>
+where
+    F: Fn(f64) -> Result<f64, String>,
+{
+    let mut f_low = f(low)?;
+    for iteration in 1..=MAX_BISECTION_ITERATIONS {
+        let middle = low + (high - low) / 2.0;
+        let f_middle = f(middle)?;
+        if f_middle == 0.0 || middle == low || middle == high {
+            return Ok(Root {
+                value: middle,
+                residual: f_middle,
+                iterations: iteration,
+            });
+        }
+        if f_middle.signum() == f_low.signum() {
+            low = middle;
+            f_low = f_middle;
+        } else {
+            high = middle;
+        }
+    }
+    let value = low + (high - low) / 2.0;
+    Ok(Root {
+        value,
+        residual: f(value)?,
+        iterations: MAX_BISECTION_ITERATIONS,
+    })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SystemSolution {
+    pub values: Vec<f64>,
+    // 解での各式の値
+    pub residuals: Vec<f64>,
+    pub converged: bool,
+    pub iterations: usize,
+}
+
+// 最大値ノルム。NaN を含む場合は無限大とみなす（f64::max は NaN を無視するため）
+fn max_norm(values: &[f64]) -> f64 {
+    values.iter().fold(0.0, |norm, value| {
+        if value.is_nan() {
+            f64::INFINITY
+        } else {
+            norm.max(value.abs())
+        }
+    })
+}
+
+// 連立方程式 f(x) = 0（式の数と未知数の数は同じ）をニュートン・ラフソン法で解く。
+// ヤコビ行列は中心差分で求め、残差が減らないときはステップを半分にする。
+// 収束しなかった場合もエラーにはせず、converged を false にして最後の値を返す
+pub fn solve_system<F>(
+    f: F,
+    guess: &[f64],
+    tolerance: f64,
+    max_iterations: usize,
+) -> Result<SystemSolution, String>
+where
+    F: Fn(&[f64]) -> Result<Vec<f64>, String>,
+{
+    let n = guess.len();
+    if n == 0 || n > MAX_SYSTEM_SIZE {
+        return Err(format!(
+            "未知数の数は 1〜{} 個にしてください（{} 個指定されています）",
+            MAX_SYSTEM_SIZE, n
+        ));
+    }
+    if let Some(value) = guess.iter().find(|value| !value.is_finite()) {
+        return Err(format!("初期値が無効です: {}", value));
+    }
+    let mut x = guess.to_vec();
+    let mut residuals = f(&x)?;
+    if residuals.len() != n {
+        return Err(format!(
+            "式の数（{} 個）と未知数の数（{} 個）を同じにしてください",
+            residuals.len(),
+            n
+        ));
+    }
+    let mut iterations = 0;
+    while max_norm(&residuals) > tolerance && iterations < max_iterations {
+        iterations += 1;
+        let jacobian = jacobian(&f, &x)?;
+        let negated: Vec<f64> = residuals.iter().map(|value| -value).collect();
+        let Some(step) = matrix::solve_linear(&jacobian, &negated) else {
+            // ヤコビ行列が特異で、これ以上進めない
+            break;
+        };
+        let norm = max_norm(&residuals);
+        let mut scale = 1.0;
+        let mut improved = None;
+        for _ in 0..MAX_LINE_SEARCH_STEPS {
+            let candidate: Vec<f64> = x
+                .iter()
+                .zip(&step)
+                .map(|(value, delta)| value + scale * delta)
+                .collect();
+            if let Ok(candidate_residuals) = f(&candidate) {
+                if max_norm(&candidate_residuals) < norm {
+                    improved = Some((candidate, candidate_residuals));
+                    break;
+                }
+            }
+            scale /= 2.0;
+        }
+        let Some((candidate, candidate_residuals)) = improved else {
+            break;
+        };
+        x = candidate;
+        residuals = candidate_residuals;
+    }
+    Ok(SystemSolution {
+        converged: max_norm(&residuals) <= tolerance,
+        values: x,
+        residuals,
+        iterations,
+    })
+}
+
+// 中心差分によるヤコビ行列（行が式、列が未知数）
+fn jacobian<F>(f: &F, x: &[f64]) -> Result<Matrix, String>
+where
+    F: Fn(&[f64]) -> Result<Vec<f64>, String>,
+{
+    let n = x.len();
+    let mut columns = Vec::with_capacity(n);
+    for j in 0..n {
+        let h = step_size(x[j]);
+        let mut forward = x.to_vec();
+        forward[j] += h;
+        let mut backward = x.to_vec();
+        backward[j] -= h;
+        let (forward, backward) = (f(&forward)?, f(&backward)?);
+        columns.push(
+            forward
+                .iter()
+                .zip(&backward)
+                .map(|(a, b)| (a - b) / (2.0 * h))
+                .collect::<Vec<f64>>(),
+        );
+    }
+    Ok(matrix::transpose(&columns))
+}
@@ -0,0 +1,45 @@
+#[cfg(test)]
+mod tests {
+    use crate::solver::{find_root, solve_system};
+
+    #[test]
+    fn test_find_root() {
+        let root = find_root(|x| Ok(x * x - 2.0), 1.0).unwrap();
+        assert!((root.value - 2f64.sqrt()).abs() < 1e-12);
+        assert!(root.residual.abs() < 1e-12);
+
+        // 初期値によって別の解に収束する
+        let root = find_root(|x| Ok(x * x - 2.0), -3.0).unwrap();
+        assert!((root.value + 2f64.sqrt()).abs() < 1e-12);
+
+        // 導関数が 0 の点からは区間を広げて探す
+        let root = find_root(|x| Ok(x * x * x - 8.0), 0.0).unwrap();
+        assert!((root.value - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_find_root_failures() {
+        assert!(find_root(|x| Ok(x * x + 1.0), 1.0).is_err());
+        assert!(find_root(|x| Ok(x - 1.0), f64::NAN).is_err());
+    }
+
+    #[test]
+    fn test_solve_system() {
+        // x^2 + y^2 = 4, x = y
+        let equations = |v: &[f64]| Ok(vec![v[0] * v[0] + v[1] * v[1] - 4.0, v[0] - v[1]]);
+        let solution = solve_system(equations, &[1.0, 0.5], 1e-12, 100).unwrap();
+        assert!(solution.converged);
+        assert!((solution.values[0] - 2f64.sqrt()).abs() < 1e-9);
+        assert!((solution.values[1] - 2f64.sqrt()).abs() < 1e-9);
+        assert!(solution.residuals.iter().all(|r| r.abs() <= 1e-12));
+
+        // 解がなければ収束しないことを返す
+        let equations = |v: &[f64]| Ok(vec![v[0] * v[0] + 1.0]);
+        let solution = solve_system(equations, &[1.0], 1e-12, 50).unwrap();
+        assert!(!solution.converged);
+
+        let equations = |v: &[f64]| Ok(vec![v[0], v[0]]);
+        assert!(solve_system(equations, &[1.0], 1e-12, 50).is_err());
+        assert!(solve_system(|_| Ok(vec![]), &[], 1e-12, 50).is_err());
+    }
+}
@@ -0,0 +1,491 @@
+use crate::functions::erfc;
+use crate::markdown::Table;
+use crate::sampling::Distribution;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Serialize)]
+pub struct Summary {
+    pub count: usize,
+    pub sum: f64,
+    pub mean: f64,
+    pub median: f64,
+    pub min: f64,
+    pub max: f64,
+    pub range: f64,
+    // 四分位数（線形補間。Excel の QUARTILE.INC と同じ）
+    pub q1: f64,
+    pub q3: f64,
+    // 標本分散・標本標準偏差（n - 1 で割る。値が1つの場合は None）
+    pub sample_variance: Option<f64>,
+    pub sample_std_dev: Option<f64>,
+    // 母分散・母標準偏差（n で割る）
+    pub population_variance: f64,
+    pub population_std_dev: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Regression {
+    pub count: usize,
+    // y = slope * x + intercept
+    pub slope: f64,
+    pub intercept: f64,
+    // 相関係数と決定係数
+    pub r: f64,
+    pub r_squared: f64,
+    pub equation: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Bin {
+    // 区間は [lower, upper)。最後の区間だけ upper を含む
+    pub lower: f64,
+    pub upper: f64,
+    pub count: usize,
+    pub relative_frequency: f64,
+    pub cumulative_frequency: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Histogram {
+    pub count: usize,
+    pub bins: Vec<Bin>,
+    // 指定した区間の外にある値の個数
+    pub below: usize,
+    pub above: usize,
+    pub chart: Option<String>,
+}
+
+impl Summary {
+    // 項目と値の2列の表（標本分散など値がない項目は -）
+    pub fn table(&self) -> Table {
+        let optional = |value: Option<f64>| value.map_or("-".to_string(), |x| x.to_string());
+        let mut table = Table::new(&["item", "value"]);
+        for (item, value) in [
+            ("count", self.count.to_string()),
+            ("sum", self.sum.to_string()),
+            ("mean", self.mean.to_string()),
+            ("median", self.median.to_string()),
+            ("min", self.min.to_string()),
+            ("max", self.max.to_string()),
+            ("range", self.range.to_string()),
+            ("q1", self.q1.to_string()),
+            ("q3", self.q3.to_string()),
+            ("sample_variance", optional(self.sample_variance)),
+            ("sample_std_dev", optional(self.sample_std_dev)),
+            ("population_variance", self.population_variance.to_string()),
+            ("population_std_dev", self.population_std_dev.to_string()),
+        ] {
+            table.push(vec![item.to_string(), value]);
+        }
+        table
+    }
+}
+
+impl Histogram {
+    // 1行が1つの区間
+    pub fn table(&self) -> Table {
+        let mut table = Table::new(&[
+            "lower",
+            "upper",
+            "count",
+            "relative_frequency",
+            "cumulative_frequency",
+        ]);
+        for bin in &self.bins {
+            table.push(vec![
+                bin.lower.to_string(),
+                bin.upper.to_string(),
+                bin.count.to_string(),
+                bin.relative_frequency.to_string(),
+                bin.cumulative_frequency.to_string(),
+            ]);
+        }
+        table
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct DistributionFit {
+    pub distribution: Distribution,
+    // 最尤推定したパラメータ（normal: mean, std_dev / exponential: rate / uniform: min, max）
+    pub parameters: BTreeMap<&'static str, f64>,
+    pub log_likelihood: f64,
+    // 赤池情報量規準 2k - 2 ln L（小さいほどよく当てはまる）
+    pub aic: f64,
+    // コルモゴロフ–スミルノフ統計量（経験分布関数と当てはめた分布関数の差の最大値）と、その漸近的な p 値
+    pub ks_statistic: f64,
+    pub p_value: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SkippedFit {
+    pub distribution: Distribution,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FitReport {
+    pub count: usize,
+    // aic の小さい順
+    pub fits: Vec<DistributionFit>,
+    // データに当てはめられなかった分布（負の値を含むデータの exponential など）
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<SkippedFit>,
+    pub best: Distribution,
+    pub note: &'static str,
+}
+
+// 分布の当てはめの既定の候補
+pub const FIT_DISTRIBUTIONS: &[Distribution] = &[
+    Distribution::Normal,
+    Distribution::Exponential,
+    Distribution::Uniform,
+];
+
+const FIT_NOTE: &str = "パラメータをデータから推定しているため、p 値は実際より大きめになります（目安として使ってください）。p 値が 0.05 未満なら、その分布とはみなしにくいと言えます";
+
+// 棒グラフの最も長い棒の文字数
+const CHART_WIDTH: usize = 40;
+const MAX_BINS: usize = 1000;
+
+pub fn summarize(values: &[f64]) -> Result<Summary, String> {
+    check_values(values)?;
+    let count = values.len();
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+
+    let sum: f64 = values.iter().sum();
+    let mean = sum / count as f64;
+    let squares: f64 = values.iter().map(|x| (x - mean).powi(2)).sum();
+    let sample_variance = (count > 1).then(|| squares / (count - 1) as f64);
+    let population_variance = squares / count as f64;
+    let (min, max) = (sorted[0], sorted[count - 1]);
+
+    Ok(Summary {
+        count,
+        sum,
+        mean,
+        median: quantile(&sorted, 0.5),
+        min,
+        max,
+        range: max - min,
+        q1: quantile(&sorted, 0.25),
+        q3: quantile(&sorted, 0.75),
+        sample_variance,
+        sample_std_dev: sample_variance.map(f64::sqrt),
+        population_variance,
+        population_std_dev: population_variance.sqrt(),
+    })
+}
+
+// edges を省略すると、bins（省略時はスタージェスの公式）個の等幅の区間に分ける
+pub fn histogram(
+    values: &[f64],
+    bins: Option<usize>,
+    edges: Option<&[f64]>,
+    chart: bool,
+) -> Result<Histogram, String> {
+    check_values(values)?;
+    let edges = match edges {
+        Some(edges) => {
+            if edges.len() < 2
+                || edges.iter().any(|edge| !edge.is_finite())
+                || edges.windows(2).any(|pair| pair[0] >= pair[1])
+            {
+                return Err("区間の境界は2つ以上の昇順の値で指定してください".to_string());
+            }
+            edges.to_vec()
+        }
+        None => equal_width_edges(values, bins)?,
+    };
+    if edges.len() - 1 > MAX_BINS {
+        return Err(format!("区間の数が多すぎます（上限は {}）", MAX_BINS));
+    }
+
+    let (first, last) = (edges[0], edges[edges.len() - 1]);
+    let mut counts = vec![0; edges.len() - 1];
+    let (mut below, mut above) = (0, 0);
+    for &value in values {
+        if value < first {
+            below += 1;
+        } else if value > last {
+            above += 1;
+        } else if value == last {
+            counts[edges.len() - 2] += 1;
+        } else {
+            counts[edges.partition_point(|&edge| edge <= value) - 1] += 1;
+        }
+    }
+
+    let total = values.len() as f64;
+    let mut cumulative = 0;
+    let bins: Vec<Bin> = counts
+        .iter()
+        .enumerate()
+        .map(|(index, &count)| {
+            cumulative += count;
+            Bin {
+                lower: edges[index],
+                upper: edges[index + 1],
+                count,
+                relative_frequency: count as f64 / total,
+                cumulative_frequency: cumulative as f64 / total,
+            }
+        })
+        .collect();
+    Ok(Histogram {
+        count: values.len(),
+        chart: chart.then(|| bar_chart(&bins)),
+        bins,
+        below,
+        above,
+    })
+}
+
+fn equal_width_edges(values: &[f64], bins: Option<usize>) -> Result<Vec<f64>, String> {
+    let bins = match bins {
+        Some(0) => return Err("区間の数には 1 以上を指定してください".to_string()),
+        Some(bins) if bins > MAX_BINS => {
+            return Err(format!("区間の数が多すぎます（上限は {}）", MAX_BINS))
+        }
+        Some(bins) => bins,
+        None => (values.len() as f64).log2().ceil() as usize + 1,
+    };
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    if min == max {
+        return Ok(vec![min, max + 1.0]);
+    }
+    let width = (max - min) / bins as f64;
+    if !width.is_finite() {
+        return Err("データの範囲が広すぎて等幅の区間に分けられません".to_string());
+    }
+    // 範囲が狭いと境界が丸めで重なるため、幅が 0 になる区間はまとめる
+    let mut edges: Vec<f64> = (0..bins)
+        .map(|i| min + width * i as f64)
+        .filter(|&edge| edge < max)
+        .collect();
+    edges.dedup();
+    edges.push(max);
+    Ok(edges)
+}
+
+fn bar_chart(bins: &[Bin]) -> String {
+    let labels: Vec<String> = bins
+        .iter()
+        .enumerate()
+        .map(|(index, bin)| {
+            let close = if index + 1 == bins.len() { ']' } else { ')' };
+            format!("[{}, {}{}", bin.lower, bin.upper, close)
+        })
+        .collect();
+    let label_width = labels.iter().map(String::len).max().unwrap_or(0);
+    let max_count = bins.iter().map(|bin| bin.count).max().unwrap_or(0).max(1);
+    bins.iter()
+        .zip(&labels)
+        .map(|(bin, label)| {
+            let bar = "#".repeat((bin.count * CHART_WIDTH).div_ceil(max_count));
+            format!(
+                "{:<width$} | {} {}",
+                label,
+                bar,
+                bin.count,
+                width = label_width
+            )
+            .trim_end()
+            .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// 最小二乗法による単回帰
+pub fn linear_regression(x: &[f64], y: &[f64]) -> Result<Regression, String> {
+    if x.len() != y.len() {
+        return Err(format!(
+            "x と y の個数が一致しません: {} と {}",
+            x.len(),
+            y.len()
+        ));
+    }
+    if x.len() < 2 {
+        return Err("回帰には2組以上のデータが必要です".to_string());
+    }
+    check_values(x)?;
+    check_values(y)?;
+
+    let n = x.len() as f64;
+    let (mean_x, mean_y) = (x.iter().sum::<f64>() / n, y.iter().sum::<f64>() / n);
+    let sxx: f64 = x.iter().map(|x| (x - mean_x).powi(2)).sum();
+    let syy: f64 = y.iter().map(|y| (y - mean_y).powi(2)).sum();
+    let sxy: f64 = x
+        .iter()
+        .zip(y)
+        .map(|(x, y)| (x - mean_x) * (y - mean_y))
+        .sum();
+    if sxx == 0.0 {
+        return Err("x がすべて同じ値のため回帰直線を求められません".to_string());
+    }
+
+    let slope = sxy / sxx;
+    let intercept = mean_y - slope * mean_x;
+    // y がすべて同じ値なら、直線で完全に説明できるものとする
+    let r = if syy == 0.0 {
+        1.0
+    } else {
+        sxy / (sxx * syy).sqrt()
+    };
+    Ok(Regression {
+        count: x.len(),
+        slope,
+        intercept,
+        r,
+        r_squared: r * r,
+        equation: format!("y = {} * x + {}", slope, intercept),
+    })
+}
+
+// 最尤推定で values に distributions の各分布を当てはめ、コルモゴロフ–スミルノフ検定で適合度を求める
+pub fn fit_distributions(
+    values: &[f64],
+    distributions: &[Distribution],
+) -> Result<FitReport, String> {
+    check_values(values)?;
+    if values.len() < 2 {
+        return Err("分布の当てはめには2つ以上のデータが必要です".to_string());
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let mut fits = Vec::new();
+    let mut skipped = Vec::new();
+    for &distribution in distributions {
+        match fit(&sorted, distribution) {
+            Ok(fit) => fits.push(fit),
+            Err(reason) => skipped.push(SkippedFit {
+                distribution,
+                reason,
+            }),
+        }
+    }
+    fits.sort_by(|a, b| a.aic.total_cmp(&b.aic));
+    let best = fits.first().map(|fit| fit.distribution).ok_or_else(|| {
+        let reasons: Vec<String> = skipped
+            .iter()
+            .map(|skipped| format!("{}: {}", skipped.distribution.name(), skipped.reason))
+            .collect();
+        format!("当てはめられる分布がありません（{}）", reasons.join("、"))
+    })?;
+    Ok(FitReport {
+        count: values.len(),
+        fits,
+        skipped,
+        best,
+        note: FIT_NOTE,
+    })
+}
+
+// sorted は昇順に並んだ2つ以上の値
+fn fit(sorted: &[f64], distribution: Distribution) -> Result<DistributionFit, String> {
+    let n = sorted.len() as f64;
+    let (min, max) = (sorted[0], sorted[sorted.len() - 1]);
+    let mean = sorted.iter().sum::<f64>() / n;
+    let (parameters, log_likelihood, cdf): (_, _, Box<dyn Fn(f64) -> f64>) = match distribution {
+        Distribution::Normal => {
+            // 最尤推定の標準偏差は n で割る（母標準偏差）
+            let std_dev = (sorted.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n).sqrt();
+            if std_dev == 0.0 {
+                return Err("すべての値が同じです".to_string());
+            }
+            let log_likelihood =
+                -n / 2.0 * (2.0 * std::f64::consts::PI * std_dev * std_dev).ln() - n / 2.0;
+            (
+                vec![("mean", mean), ("std_dev", std_dev)],
+                log_likelihood,
+                Box::new(move |x| 0.5 * erfc(-(x - mean) / (std_dev * std::f64::consts::SQRT_2))),
+            )
+        }
+        Distribution::Exponential => {
+            if min < 0.0 {
+                return Err("負の値が含まれています".to_string());
+            }
+            if mean == 0.0 {
+                return Err("すべての値が 0 です".to_string());
+            }
+            let rate = 1.0 / mean;
+            (
+                vec![("rate", rate)],
+                n * rate.ln() - n,
+                Box::new(move |x: f64| if x < 0.0 { 0.0 } else { -(-rate * x).exp_m1() }),
+            )
+        }
+        Distribution::Uniform => {
+            if max == min {
+                return Err("すべての値が同じです".to_string());
+            }
+            (
+                vec![("min", min), ("max", max)],
+                -n * (max - min).ln(),
+                Box::new(move |x: f64| ((x - min) / (max - min)).clamp(0.0, 1.0)),
+            )
+        }
+        other => {
+            return Err(format!(
+                "{} の当てはめには対応していません（normal, exponential, uniform のみ）",
+                other.name()
+            ))
+        }
+    };
+    let ks_statistic = sorted
+        .iter()
+        .enumerate()
+        .map(|(i, &x)| {
+            let f = cdf(x);
+            ((i + 1) as f64 / n - f).max(f - i as f64 / n)
+        })
+        .fold(0.0, f64::max);
+    let k = parameters.len() as f64;
+    Ok(DistributionFit {
+        distribution,
+        parameters: parameters.into_iter().collect(),
+        log_likelihood,
+        aic: 2.0 * k - 2.0 * log_likelihood,
+        ks_statistic,
+        p_value: kolmogorov_p_value(ks_statistic, n),
+    })
+}
+
+// D が ks_statistic 以上になる確率の近似（Stephens の補正をしたコルモゴロフ分布）
+fn kolmogorov_p_value(ks_statistic: f64, n: f64) -> f64 {
+    let lambda = (n.sqrt() + 0.12 + 0.11 / n.sqrt()) * ks_statistic;
+    if lambda < 0.2 {
+        return 1.0;
+    }
+    let mut sum = 0.0;
+    for j in 1..=100 {
+        let j = j as f64;
+        let term = (-2.0 * j * j * lambda * lambda).exp();
+        sum += if j % 2.0 == 1.0 { term } else { -term };
+        if term < 1e-16 {
+            break;
+        }
+    }
+    (2.0 * sum).clamp(0.0, 1.0)
+}
+
+// sorted は昇順に並んだ空でない配列
+pub fn quantile(sorted: &[f64], p: f64) -> f64 {
+    let position = p * (sorted.len() - 1) as f64;
+    let (lower, upper) = (position.floor() as usize, position.ceil() as usize);
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (position - lower as f64)
+}
+
+pub fn check_values(values: &[f64]) -> Result<(), String> {
+    if values.is_empty() {
+        return Err("データが空です".to_string());
+    }
+    if values.iter().any(|value| !value.is_finite()) {
+        return Err("データに無効な値（NaN または 無限大）が含まれています".to_string());
+    }
+    Ok(())
+}
@@ -0,0 +1,139 @@
+#[cfg(test)]
+mod tests {
+    use crate::sampling::{self, Distribution, Sampler};
+    use crate::statistics::{fit_distributions, histogram, linear_regression, summarize};
+
+    #[test]
+    fn test_summarize() {
+        let summary = summarize(&[2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]).unwrap();
+
+        assert_eq!(summary.count, 8);
+        assert_eq!(summary.mean, 5.0);
+        assert_eq!(summary.median, 4.5);
+        assert_eq!(summary.population_std_dev, 2.0);
+        assert!((summary.sample_variance.unwrap() - 32.0 / 7.0).abs() < 1e-12);
+        assert_eq!((summary.q1, summary.q3), (4.0, 5.5));
+        assert_eq!(summary.range, 7.0);
+    }
+
+    #[test]
+    fn test_summarize_single_value() {
+        let summary = summarize(&[3.0]).unwrap();
+
+        assert_eq!(summary.sample_variance, None);
+        assert_eq!(summary.population_variance, 0.0);
+        assert!(summarize(&[]).is_err());
+
+        let markdown = summary.table().to_markdown();
+        assert!(markdown.starts_with("| item | value |\n| --- | --- |\n| count | 1 |"));
+        assert!(markdown.contains("| sample_variance | - |"));
+    }
+
+    #[test]
+    fn test_linear_regression() {
+        let result = linear_regression(&[1.0, 2.0, 3.0, 4.0], &[3.0, 5.0, 7.0, 9.0]).unwrap();
+
+        assert_eq!((result.slope, result.intercept), (2.0, 1.0));
+        assert_eq!(result.r_squared, 1.0);
+        assert!(linear_regression(&[1.0, 1.0], &[2.0, 3.0]).is_err());
+        assert!(linear_regression(&[1.0, 2.0], &[2.0]).is_err());
+    }
+
+    #[test]
+    fn test_histogram_auto_bins() {
+        let values: Vec<f64> = (1..=8).map(f64::from).collect();
+        // スタージェスの公式: ceil(log2(8)) + 1 = 4
+        let result = histogram(&values, None, None, false).unwrap();
+
+        assert_eq!(result.bins.len(), 4);
+        let counts: Vec<usize> = result.bins.iter().map(|bin| bin.count).collect();
+        assert_eq!(counts, vec![2, 2, 2, 2]);
+        assert_eq!(result.bins[3].upper, 8.0);
+        assert_eq!(result.bins[1].cumulative_frequency, 0.5);
+
+        let table = result.table();
+        let markdown = table.to_markdown();
+        assert_eq!(markdown.lines().count(), 6);
+        assert!(markdown.contains("| 2.75 | 4.5 | 2 | 0.25 | 0.5 |"));
+        assert!(table.to_csv().starts_with(
+            "lower,upper,count,relative_frequency,cumulative_frequency\n1,2.75,2,0.25,0.25\n"
+        ));
+    }
+
+    #[test]
+    fn test_histogram_explicit_edges() {
+        let values = [55.0, 62.0, 70.0, 71.0, 88.0, 100.0, 40.0, 101.0];
+        let result = histogram(&values, None, Some(&[50.0, 70.0, 90.0, 100.0]), true).unwrap();
+
+        let counts: Vec<usize> = result.bins.iter().map(|bin| bin.count).collect();
+        assert_eq!(counts, vec![2, 3, 1]);
+        assert_eq!((result.below, result.above), (1, 1));
+        assert_eq!(result.bins[0].relative_frequency, 0.25);
+        let chart = result.chart.unwrap();
+        let lines: Vec<&str> = chart.lines().collect();
+        assert_eq!(lines[0], format!("[50, 70)  | {} 2", "#".repeat(27)));
+        assert_eq!(lines[1], format!("[70, 90)  | {} 3", "#".repeat(40)));
+        assert_eq!(lines[2], format!("[90, 100] | {} 1", "#".repeat(14)));
+
+        assert!(histogram(&values, None, Some(&[1.0, 1.0]), false).is_err());
+        assert!(histogram(&values, Some(0), None, false).is_err());
+    }
+
+    #[test]
+    fn test_histogram_extreme_ranges() {
+        assert!(histogram(&[-1e308, 1e308], None, None, false)
+            .unwrap_err()
+            .contains("範囲が広すぎて"));
+
+        let result = histogram(&[1.0, 1.0000000000000002], Some(1000), None, false).unwrap();
+        assert!(result.bins.iter().all(|bin| bin.lower < bin.upper));
+        let counts: Vec<usize> = result.bins.iter().map(|bin| bin.count).collect();
+        assert_eq!(counts.iter().sum::<usize>(), 2);
+    }
+
+    #[test]
+    fn test_fit_distributions() {
+        let normal = sampling::generate(
+            Sampler::Normal {
+                mean: 10.0,
+                std_dev: 2.0,
+            },
+            2000,
+            42,
+        )
+        .unwrap()
+        .samples;
+        let report =
+            fit_distributions(&normal, &[Distribution::Normal, Distribution::Uniform]).unwrap();
+        assert_eq!(report.best, Distribution::Normal);
+        let fit = &report.fits[0];
+        assert!((fit.parameters["mean"] - 10.0).abs() < 0.2);
+        assert!((fit.parameters["std_dev"] - 2.0).abs() < 0.2);
+        assert!(fit.ks_statistic < 0.05);
+        assert!(fit.p_value > 0.01);
+        // 一様分布からは明らかに外れる
+        assert!(report.fits[1].p_value < 0.001);
+
+        let exponential = sampling::generate(Sampler::Exponential { rate: 0.5 }, 2000, 7)
+            .unwrap()
+            .samples;
+        let report = fit_distributions(&exponential, crate::statistics::FIT_DISTRIBUTIONS).unwrap();
+        assert_eq!(report.best, Distribution::Exponential);
+        assert!((report.fits[0].parameters["rate"] - 0.5).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_fit_distributions_skipped() {
+        let report = fit_distributions(
+            &[-1.0, 0.5, 2.0, 3.5],
+            &[Distribution::Normal, Distribution::Exponential],
+        )
+        .unwrap();
+        assert_eq!(report.fits.len(), 1);
+        assert_eq!(report.skipped[0].distribution, Distribution::Exponential);
+
+        assert!(fit_distributions(&[1.0, 1.0, 1.0], &[Distribution::Normal]).is_err());
+        assert!(fit_distributions(&[1.0], &[Distribution::Normal]).is_err());
+        assert!(fit_distributions(&[1.0, 2.0], &[Distribution::Poisson]).is_err());
+    }
+}
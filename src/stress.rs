@@ -0,0 +1,296 @@
+use crate::calculator::Calculator;
+use crate::compare::{self, DEFAULT_ABS_TOLERANCE, DEFAULT_REL_TOLERANCE};
+use crate::rng::Rng;
+use serde::Serialize;
+use std::collections::HashMap;
+
+// 不一致の種類
+// 参照評価器（生成した木を f64 でそのまま計算したもの）と evaluate の値が違う
+pub const REFERENCE: &str = "reference";
+// evaluate と compile した式の eval の値が違う
+pub const COMPILED: &str = "compiled";
+// to_infix で書き戻した式の値か正規化ハッシュが元の式と違う
+pub const ROUND_TRIP: &str = "round_trip";
+// 空白や冗長な括弧だけが違う書き方で正規化ハッシュが違う
+pub const CANONICAL_HASH: &str = "canonical_hash";
+
+// 生成する関数。参照評価器では同じ意味の f64 の計算で評価する
+type UnaryFunction = fn(f64) -> f64;
+const UNARY_FUNCTIONS: &[(&str, UnaryFunction)] = &[
+    ("sqrt", f64::sqrt),
+    ("abs", f64::abs),
+    ("cbrt", f64::cbrt),
+    ("exp", f64::exp),
+];
+const VARIADIC_FUNCTIONS: &[&str] = &["min", "max", "avg"];
+const OPERATORS: [char; 5] = ['+', '-', '*', '/', '^'];
+
+#[derive(Debug, Clone)]
+enum Node {
+    Number(String),
+    Negate(Box<Node>),
+    Binary(char, Box<Node>, Box<Node>),
+    Call(&'static str, Vec<Node>),
+}
+
+fn precedence(node: &Node) -> u8 {
+    match node {
+        Node::Binary('+' | '-', ..) => 1,
+        Node::Binary('*' | '/', ..) => 2,
+        Node::Binary(..) => 3,
+        _ => 4,
+    }
+}
+
+impl Node {
+    // 演算子の優先順位と結合性から必要な括弧だけを付けた式。
+    // redundant が Some なら、その乱数で冗長な括弧と空白をランダムに加える
+    fn render(&self, redundant: &mut Option<&mut Rng>) -> String {
+        let text = match self {
+            Node::Number(text) => text.clone(),
+            // 単項マイナスと ^ の優先順位の違いに依存しないよう、常に括弧で囲む
+            Node::Negate(operand) => format!("(-{})", operand.render_operand(4, redundant)),
+            Node::Binary(op, left, right) => {
+                // この計算機では ^ も含めてすべて左結合（2 ^ 3 ^ 2 は (2 ^ 3) ^ 2）
+                let own = precedence(self);
+                let space = spacing(redundant);
+                format!(
+                    "{}{}{}{}{}",
+                    left.render_operand(own, redundant),
+                    space,
+                    op,
+                    space,
+                    right.render_operand(own + 1, redundant)
+                )
+            }
+            Node::Call(name, args) => {
+                let args: Vec<String> = args.iter().map(|arg| arg.render(redundant)).collect();
+                format!("{}({})", name, args.join(", "))
+            }
+        };
+        let wrap = match redundant {
+            Some(rng) => rng.next_u64() % 6 == 0,
+            None => false,
+        };
+        if wrap {
+            format!("({})", text)
+        } else {
+            text
+        }
+    }
+
+    fn render_operand(&self, min_precedence: u8, redundant: &mut Option<&mut Rng>) -> String {
+        let text = self.render(redundant);
+        if precedence(self) < min_precedence {
+            format!("({})", text)
+        } else {
+            text
+        }
+    }
+
+    fn is_integer_literal(&self) -> bool {
+        matches!(self, Node::Number(text) if !text.contains('.'))
+    }
+
+    // 条件の悪い計算（桁落ち、極端な大きさ、定義域外）では None を返し、その式は使わない。
+    // 計算機は整数・分数を正確に扱うため、丸め誤差が増幅される式では値が一致しないことがある
+    fn reference(&self) -> Option<f64> {
+        let value = match self {
+            Node::Number(text) => text.parse().ok()?,
+            Node::Negate(operand) => -operand.reference()?,
+            Node::Binary(op, left, right) => {
+                let (a, b) = (left.reference()?, right.reference()?);
+                let value = match op {
+                    '+' => a + b,
+                    '-' => a - b,
+                    '*' => a * b,
+                    '/' if b == 0.0 => return None,
+                    '/' => a / b,
+                    // 負の数のべき乗は、指数が整数のリテラルの場合だけにする（計算した指数は
+                    // 誤差でわずかに整数からずれることがある）
+                    _ if a < 0.0 && !right.is_integer_literal() => return None,
+                    _ => a.powf(b),
+                };
+                if matches!(op, '+' | '-') && value.abs() < 1e-6 * a.abs().max(b.abs()) {
+                    return None;
+                }
+                value
+            }
+            Node::Call(name, args) => {
+                let args = args
+                    .iter()
+                    .map(Node::reference)
+                    .collect::<Option<Vec<f64>>>()?;
+                match *name {
+                    "min" => args.iter().copied().fold(f64::INFINITY, f64::min),
+                    "max" => args.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+                    "avg" => args.iter().sum::<f64>() / args.len() as f64,
+                    name => {
+                        let (_, function) = UNARY_FUNCTIONS.iter().find(|(n, _)| *n == name)?;
+                        function(args[0])
+                    }
+                }
+            }
+        };
+        let magnitude = value.abs();
+        if !value.is_finite() || magnitude > 1e9 || (magnitude != 0.0 && magnitude < 1e-6) {
+            return None;
+        }
+        Some(value)
+    }
+}
+
+fn spacing(redundant: &mut Option<&mut Rng>) -> &'static str {
+    match redundant {
+        Some(rng) => ["", " ", "  "][(rng.next_u64() % 3) as usize],
+        None => " ",
+    }
+}
+
+fn generate(rng: &mut Rng, depth: usize) -> Node {
+    let pick = |rng: &mut Rng, n: usize| (rng.next_u64() % n as u64) as usize;
+    if depth == 0 || pick(rng, 4) == 0 {
+        return match pick(rng, 10) {
+            // 2進数で正確に表せる小数（0.25, 1.5 など）と整数
+            0..=2 => Node::Number(format!("{}", pick(rng, 40) as f64 / 4.0)),
+            _ => Node::Number(pick(rng, 21).to_string()),
+        };
+    }
+    match pick(rng, 10) {
+        0 => Node::Negate(Box::new(generate(rng, depth - 1))),
+        1 => {
+            let (name, _) = UNARY_FUNCTIONS[pick(rng, UNARY_FUNCTIONS.len())];
+            Node::Call(name, vec![generate(rng, depth - 1)])
+        }
+        2 => {
+            let name = VARIADIC_FUNCTIONS[pick(rng, VARIADIC_FUNCTIONS.len())];
+            let count = 2 + pick(rng, 2);
+            Node::Call(name, (0..count).map(|_| generate(rng, depth - 1)).collect())
+        }
+        _ => Node::Binary(
+            OPERATORS[pick(rng, OPERATORS.len())],
+            Box::new(generate(rng, depth - 1)),
+            Box::new(generate(rng, depth - 1)),
+        ),
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Mismatch {
+    pub kind: &'static str,
+    pub expression: String,
+    pub expected: Option<f64>,
+    pub actual: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StressReport {
+    pub seed: u64,
+    // 検証した式の数
+    pub count: usize,
+    // 条件が悪いため生成し直した式の数
+    pub discarded: usize,
+    pub mismatches: Vec<Mismatch>,
+}
+
+impl StressReport {
+    pub fn is_ok(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+fn close(actual: f64, expected: f64) -> bool {
+    compare::compare(
+        actual,
+        expected,
+        DEFAULT_ABS_TOLERANCE,
+        DEFAULT_REL_TOLERANCE,
+    )
+    .is_ok_and(|comparison| comparison.matches)
+}
+
+// 乱数で生成した式 count 個を、計算機の評価・コンパイル・正規化の各経路と参照評価器で
+// 突き合わせる。同じ seed からは同じ式が生成される
+pub fn run(calculator: &Calculator, count: usize, seed: u64, max_depth: usize) -> StressReport {
+    let mut rng = Rng::new(seed);
+    let mut report = StressReport {
+        seed,
+        count: 0,
+        discarded: 0,
+        mismatches: Vec::new(),
+    };
+    let vars = HashMap::new();
+    // トークンは1バイト以上なので、この長さ以下の式は式の大きさの上限に収まる
+    let limits = calculator.limits();
+    let max_length = limits.max_length.min(limits.max_tokens);
+    // 条件のよい式がほとんど生成できない場合でも終わるようにする
+    while report.count < count && report.discarded < count.saturating_mul(100).max(100) {
+        let node = generate(&mut rng, max_depth);
+        let expression = node.render(&mut None);
+        let Some(expected) = node.reference().filter(|_| expression.len() <= max_length) else {
+            report.discarded += 1;
+            continue;
+        };
+        report.count += 1;
+        let mut mismatch = |kind, expected: Option<f64>, actual: Option<f64>, detail| {
+            report.mismatches.push(Mismatch {
+                kind,
+                expression: expression.clone(),
+                expected,
+                actual,
+                detail,
+            });
+        };
+        let actual = match calculator.evaluate(&expression) {
+            Ok(actual) => actual,
+            Err(e) => {
+                mismatch(REFERENCE, Some(expected), None, Some(e));
+                continue;
+            }
+        };
+        if !close(actual, expected) {
+            mismatch(REFERENCE, Some(expected), Some(actual), None);
+        }
+        let compiled = match calculator.compile(&expression) {
+            Ok(compiled) => compiled,
+            Err(e) => {
+                mismatch(COMPILED, Some(actual), None, Some(e));
+                continue;
+            }
+        };
+        match compiled.eval(&vars) {
+            Ok(value) if close(value, actual) => {}
+            Ok(value) => mismatch(COMPILED, Some(actual), Some(value), None),
+            Err(e) => mismatch(COMPILED, Some(actual), None, Some(e)),
+        }
+        let hash = compiled.canonical_hash();
+        let infix = compiled.to_infix();
+        match calculator.evaluate(&infix) {
+            Ok(value) if close(value, actual) => {}
+            Ok(value) => mismatch(ROUND_TRIP, Some(actual), Some(value), Some(infix.clone())),
+            Err(e) => mismatch(
+                ROUND_TRIP,
+                Some(actual),
+                None,
+                Some(format!("{}: {}", infix, e)),
+            ),
+        }
+        if calculator.canonical_hash(&infix).ok().as_ref() != Some(&hash) {
+            mismatch(
+                ROUND_TRIP,
+                None,
+                None,
+                Some(format!("正規化ハッシュが違います: {}", infix)),
+            );
+        }
+        let variant = node.render(&mut Some(&mut rng));
+        if variant.len() <= max_length
+            && calculator.canonical_hash(&variant).ok().as_ref() != Some(&hash)
+        {
+            mismatch(CANONICAL_HASH, None, None, Some(variant));
+        }
+    }
+    report
+}
@@ -0,0 +1,17 @@
+#[cfg(test)]
+mod tests {
+    use crate::calculator::Calculator;
+    use crate::stress;
+
+    #[test]
+    fn test_stress_run_finds_no_mismatches() {
+        let calculator = Calculator::new();
+        let report = stress::run(&calculator, 500, 42, 4);
+        assert_eq!(report.count, 500);
+        assert!(report.is_ok(), "{:?}", report.mismatches);
+
+        // 同じ seed からは同じ式が生成される
+        let again = stress::run(&calculator, 500, 42, 4);
+        assert_eq!(again.discarded, report.discarded);
+    }
+}
@@ -0,0 +1,134 @@
+use crate::config::TaxConfig;
+use crate::rounding::{round_to, RoundingMode};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+// 入力した金額が税抜か税込か
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TaxMode {
+    #[default]
+    Exclusive,
+    Inclusive,
+}
+
+// 端数処理を明細ごとに行うか、合計に対して1回だけ行うか
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TaxScope {
+    Line,
+    #[default]
+    Total,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TaxOptions {
+    pub mode: TaxMode,
+    pub rate: f64,
+    pub rounding: RoundingMode,
+    pub per: TaxScope,
+    pub decimals: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TaxLine {
+    pub amount: f64,
+    pub net: f64,
+    pub tax: f64,
+    pub gross: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TaxResult {
+    pub mode: TaxMode,
+    pub rate: f64,
+    pub rounding: RoundingMode,
+    pub per: TaxScope,
+    // per = line の場合の明細ごとの内訳
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub lines: Vec<TaxLine>,
+    pub net: f64,
+    pub tax: f64,
+    pub gross: f64,
+}
+
+// 明示された税率、設定の名前付き税率、既定の税率の順に決める
+pub fn resolve_rate(
+    config: &TaxConfig,
+    rate: Option<f64>,
+    name: Option<&str>,
+) -> Result<f64, String> {
+    let rate = match (rate, name) {
+        (Some(rate), _) => rate,
+        (None, name) => {
+            let name = name.unwrap_or(&config.default_rate);
+            *config
+                .rates
+                .get(name)
+                .ok_or_else(|| format!("未定義の税率: {}", name))?
+        }
+    };
+    if !(0.0..=1.0).contains(&rate) {
+        return Err(format!("税率は 0〜1 で指定してください: {}", rate));
+    }
+    Ok(rate)
+}
+
+pub fn calculate_tax(amounts: &[f64], options: &TaxOptions) -> Result<TaxResult, String> {
+    if amounts.is_empty() {
+        return Err("金額を1つ以上指定してください".to_string());
+    }
+    if let Some(amount) = amounts.iter().find(|amount| !amount.is_finite()) {
+        return Err(format!("金額が無効です: {}", amount));
+    }
+
+    let lines: Vec<TaxLine> = match options.per {
+        TaxScope::Line => amounts
+            .iter()
+            .map(|amount| tax_line(*amount, options))
+            .collect(),
+        TaxScope::Total => vec![tax_line(amounts.iter().sum(), options)],
+    };
+    // 合計は丸め済みの値の和なので、加算の誤差だけを取り除く
+    let total = |f: fn(&TaxLine) -> f64| {
+        round_to(
+            lines.iter().map(f).sum(),
+            options.decimals,
+            RoundingMode::HalfUp,
+        )
+    };
+    let (net, tax, gross) = (total(|l| l.net), total(|l| l.tax), total(|l| l.gross));
+
+    Ok(TaxResult {
+        mode: options.mode,
+        rate: options.rate,
+        rounding: options.rounding,
+        per: options.per,
+        lines: if options.per == TaxScope::Line {
+            lines
+        } else {
+            Vec::new()
+        },
+        net,
+        tax,
+        gross,
+    })
+}
+
+fn tax_line(amount: f64, options: &TaxOptions) -> TaxLine {
+    let exact = match options.mode {
+        TaxMode::Exclusive => amount * options.rate,
+        TaxMode::Inclusive => amount * options.rate / (1.0 + options.rate),
+    };
+    let tax = round_to(exact, options.decimals, options.rounding);
+    let (net, gross) = match options.mode {
+        TaxMode::Exclusive => (amount, amount + tax),
+        TaxMode::Inclusive => (amount - tax, amount),
+    };
+    TaxLine {
+        amount,
+        net,
+        tax,
+        gross,
+    }
+}
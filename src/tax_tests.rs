@@ -0,0 +1,70 @@
+#[cfg(test)]
+mod tests {
+    use crate::config::TaxConfig;
+    use crate::rounding::RoundingMode;
+    use crate::tax::{calculate_tax, resolve_rate, TaxMode, TaxOptions, TaxScope};
+
+    fn tax_options(mode: TaxMode, rounding: RoundingMode, per: TaxScope) -> TaxOptions {
+        TaxOptions {
+            mode,
+            rate: 0.1,
+            rounding,
+            per,
+            decimals: 0,
+        }
+    }
+
+    #[test]
+    fn test_exclusive_tax() {
+        let options = tax_options(TaxMode::Exclusive, RoundingMode::Floor, TaxScope::Total);
+        let result = calculate_tax(&[1980.0], &options).unwrap();
+        assert_eq!(result.tax, 198.0);
+        assert_eq!(result.gross, 2178.0);
+    }
+
+    #[test]
+    fn test_inclusive_tax() {
+        let options = tax_options(TaxMode::Inclusive, RoundingMode::Floor, TaxScope::Total);
+        let result = calculate_tax(&[1100.0], &options).unwrap();
+        assert_eq!(result.tax, 100.0);
+        assert_eq!(result.net, 1000.0);
+    }
+
+    #[test]
+    fn test_rounding_per_line_and_per_total() {
+        let amounts = [98.0, 98.0, 98.0];
+
+        // 明細ごとに切り捨て: 9 + 9 + 9
+        let options = tax_options(TaxMode::Exclusive, RoundingMode::Floor, TaxScope::Line);
+        let result = calculate_tax(&amounts, &options).unwrap();
+        assert_eq!(result.tax, 27.0);
+        assert_eq!(result.lines.len(), 3);
+
+        // 合計 294 に対して切り捨て: 29
+        let options = tax_options(TaxMode::Exclusive, RoundingMode::Floor, TaxScope::Total);
+        let result = calculate_tax(&amounts, &options).unwrap();
+        assert_eq!(result.tax, 29.0);
+        assert!(result.lines.is_empty());
+
+        let options = tax_options(TaxMode::Exclusive, RoundingMode::Ceil, TaxScope::Line);
+        assert_eq!(calculate_tax(&amounts, &options).unwrap().tax, 30.0);
+    }
+
+    #[test]
+    fn test_floor_is_not_affected_by_float_error() {
+        // 0.29 * 100 は浮動小数点では 28.999… になる
+        let mut options = tax_options(TaxMode::Exclusive, RoundingMode::Floor, TaxScope::Total);
+        options.rate = 0.29;
+        assert_eq!(calculate_tax(&[100.0], &options).unwrap().tax, 29.0);
+    }
+
+    #[test]
+    fn test_resolve_rate() {
+        let config = TaxConfig::default();
+        assert_eq!(resolve_rate(&config, None, None).unwrap(), 0.10);
+        assert_eq!(resolve_rate(&config, None, Some("reduced")).unwrap(), 0.08);
+        assert_eq!(resolve_rate(&config, Some(0.2), None).unwrap(), 0.2);
+        assert!(resolve_rate(&config, None, Some("unknown")).is_err());
+        assert!(resolve_rate(&config, Some(10.0), None).is_err());
+    }
+}
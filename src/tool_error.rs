@@ -0,0 +1,160 @@
+use crate::calculator::{Calculator, Clarification, LIMIT_EXCEEDED};
+pub use crate::calculator::{DIVISION_BY_ZERO, DOMAIN_ERROR, SYNTAX_ERROR};
+use serde::Serialize;
+
+// エラーのコード。SYNTAX_ERROR: のように接頭辞で明示されたコードはそのまま使う
+pub const UNKNOWN_FUNCTION: &str = "UNKNOWN_FUNCTION";
+pub const UNDEFINED_VARIABLE: &str = "UNDEFINED_VARIABLE";
+pub const UNKNOWN_UNIT: &str = "UNKNOWN_UNIT";
+pub const INVALID_ARGUMENT: &str = "INVALID_ARGUMENT";
+pub const TOOL_ERROR: &str = "TOOL_ERROR";
+pub const NEEDS_CLARIFICATION: &str = "NEEDS_CLARIFICATION";
+
+// コードの接頭辞がないエラーの文言からコードを決める規則（上から順に調べる）。
+// 計算機の構文・定義域・ゼロ除算のエラーは接頭辞を付けるため、ここは接頭辞のないエラーの予備
+const RULES: &[(&str, &[&str])] = &[
+    (UNKNOWN_FUNCTION, &["未知の関数", "未サポートの関数"]),
+    (UNDEFINED_VARIABLE, &["未定義の変数"]),
+    (UNKNOWN_UNIT, &["未知の単位"]),
+    (DIVISION_BY_ZERO, &["ゼロ除算"]),
+    (
+        SYNTAX_ERROR,
+        &[
+            "括弧",
+            "予期しない",
+            "不正な文字",
+            "空の式",
+            "空の []",
+            "絶対値の",
+            "比較演算子は",
+            "演算子が連続",
+            "の左辺がありません",
+            "から後を解釈できません",
+            "番目の引数が空です",
+        ],
+    ),
+    (
+        DOMAIN_ERROR,
+        &[
+            "計算結果が無効",
+            "実数になりません",
+            "実数ではありません",
+            "数値リテラルが",
+            "では定義されません",
+        ],
+    ),
+    (
+        INVALID_ARGUMENT,
+        &[
+            "が必要です",
+            "指定してください",
+            "にしてください",
+            "読めません",
+        ],
+    ),
+];
+
+// ツールが返したエラーを、クライアントが対処を判断できる形にしたもの
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ToolError {
+    pub code: String,
+    pub message: String,
+    // 次に試せること（使える関数の一覧など）
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub hints: Vec<String>,
+}
+
+// calculate の clarify で、あいまいな式を計算せずに解釈を確認するエラー。
+// 2行目からは、解釈ごとに書き直した式を - で始まる行に1つずつ並べる
+pub fn needs_clarification(clarifications: &[Clarification]) -> String {
+    let questions: Vec<&str> = clarifications
+        .iter()
+        .map(|clarification| clarification.question.as_str())
+        .collect();
+    let mut lines = vec![format!(
+        "{}: 式の解釈があいまいなため計算していません（{}）。意図に合う次の式で計算し直してください",
+        NEEDS_CLARIFICATION,
+        questions.join("。")
+    )];
+    for clarification in clarifications {
+        lines.extend(clarification.interpretations.iter().map(|interpretation| {
+            format!(
+                "- {}（{}）",
+                interpretation.expression, interpretation.meaning
+            )
+        }));
+    }
+    lines.join("\n")
+}
+
+// "LIMIT_EXCEEDED: ..." のような大文字の接頭辞。"計算エラー: SYNTAX_ERROR: ..." のように
+// ツールの説明の後に続くコードも読む（": " で区切った最後の部分は本文なのでコードとしない）
+fn explicit_code(message: &str) -> Option<&str> {
+    let (head, _) = message.rsplit_once(": ").unwrap_or((message, ""));
+    let is_code =
+        |part: &str| !part.is_empty() && part.chars().all(|c| c.is_ascii_uppercase() || c == '_');
+    if let Some((code, _)) = message.split_once(':').filter(|(code, _)| is_code(code)) {
+        return Some(code);
+    }
+    head.split(": ").find(|part| is_code(part))
+}
+
+impl ToolError {
+    // calculator はヒント（使える関数や変数の一覧）に使う
+    pub fn classify(message: &str, calculator: &Calculator) -> Self {
+        let code = explicit_code(message).unwrap_or_else(|| {
+            RULES
+                .iter()
+                .find(|(_, patterns)| patterns.iter().any(|pattern| message.contains(pattern)))
+                .map_or(TOOL_ERROR, |(code, _)| code)
+        });
+        let hints = match code {
+            UNKNOWN_FUNCTION => {
+                let mut functions = calculator.function_names();
+                functions.sort_unstable();
+                vec![format!("使える関数: {}", functions.join(", "))]
+            }
+            UNDEFINED_VARIABLE => {
+                let mut variables: Vec<&str> =
+                    calculator.variables().keys().map(String::as_str).collect();
+                variables.sort_unstable();
+                let mut hints = vec![
+                    "値を式に直接書くか、load_variables で変数を読み込んでください".to_string(),
+                ];
+                if !variables.is_empty() {
+                    hints.push(format!("定義済みの変数: {}", variables.join(", ")));
+                }
+                hints
+            }
+            UNKNOWN_UNIT => {
+                vec!["単位の表記（大文字・小文字、略記）を確認してください".to_string()]
+            }
+            DIVISION_BY_ZERO => vec!["割る数が 0 にならないよう式を確認してください".to_string()],
+            SYNTAX_ERROR => vec![
+                "演算子と括弧の対応を確認してください。構文は calc://syntax で確認できます"
+                    .to_string(),
+            ],
+            INVALID_ARGUMENT => {
+                vec![
+                    "引数の名前と値の範囲を tools/list の inputSchema で確認してください"
+                        .to_string(),
+                ]
+            }
+            // 解釈ごとに書き直した式（- で始まる行）
+            NEEDS_CLARIFICATION => message
+                .lines()
+                .filter_map(|line| line.strip_prefix("- "))
+                .map(str::to_string)
+                .collect(),
+            LIMIT_EXCEEDED => {
+                vec!["入力を小さくするか、[limits] の上限を見直してください".to_string()]
+            }
+            _ => Vec::new(),
+        };
+        Self {
+            code: code.to_string(),
+            message: message.to_string(),
+            hints,
+        }
+    }
+}
@@ -0,0 +1,78 @@
+#[cfg(test)]
+mod tests {
+    use crate::calculator::Calculator;
+    use crate::tool_error::{
+        needs_clarification, ToolError, DIVISION_BY_ZERO, DOMAIN_ERROR, NEEDS_CLARIFICATION,
+        SYNTAX_ERROR, TOOL_ERROR, UNDEFINED_VARIABLE, UNKNOWN_FUNCTION,
+    };
+
+    fn classify(expression: &str, calculator: &Calculator) -> ToolError {
+        ToolError::classify(&calculator.evaluate(expression).unwrap_err(), calculator)
+    }
+
+    #[test]
+    fn test_classify_calculator_errors() {
+        let mut calculator = Calculator::new();
+        assert_eq!(classify("1 / 0", &calculator).code, DIVISION_BY_ZERO);
+        assert_eq!(classify("(1 + 2", &calculator).code, SYNTAX_ERROR);
+        assert_eq!(classify("ln(0)", &calculator).code, DOMAIN_ERROR);
+
+        // 使える関数の一覧をヒントに付ける
+        let error = classify("sqr(4)", &calculator);
+        assert_eq!(error.code, UNKNOWN_FUNCTION);
+        assert!(error.hints[0].contains("sqrt"), "{:?}", error.hints);
+
+        calculator.set_variable("rate", 0.1).unwrap();
+        let error = classify("rat * 2", &calculator);
+        assert_eq!(error.code, UNDEFINED_VARIABLE);
+        assert!(error.hints.iter().any(|hint| hint.contains("rate")));
+    }
+
+    #[test]
+    fn test_classify_explicit_code() {
+        let calculator = Calculator::new();
+        let error = ToolError::classify("LIMIT_EXCEEDED: 桁数が多すぎます", &calculator);
+        assert_eq!(error.code, "LIMIT_EXCEEDED");
+        assert!(!error.hints.is_empty());
+        let error = ToolError::classify(
+            "計算エラー: LIMIT_EXCEEDED: ^ の指数の絶対値が safe プロファイルの上限（1024）を超えます: 2000",
+            &calculator,
+        );
+        assert_eq!(error.code, "LIMIT_EXCEEDED");
+        // 計算機のエラーは文言によらずコードで分類する
+        let message = format!("計算エラー: {}", calculator.evaluate("2 +* 3").unwrap_err());
+        assert!(
+            message.starts_with("計算エラー: SYNTAX_ERROR: "),
+            "{}",
+            message
+        );
+        assert_eq!(
+            ToolError::classify(&message, &calculator).code,
+            SYNTAX_ERROR
+        );
+        let error = ToolError::classify("計算エラー: DOMAIN_ERROR: 括弧を含む文言", &calculator);
+        assert_eq!(error.code, DOMAIN_ERROR);
+        // 本文の大文字の名前はコードとしない
+        let error = ToolError::classify("未定義の変数: ABC", &calculator);
+        assert_eq!(error.code, UNDEFINED_VARIABLE);
+
+        let error = ToolError::classify("想定外のエラー", &calculator);
+        assert_eq!(error.code, TOOL_ERROR);
+        assert!(error.hints.is_empty());
+    }
+
+    #[test]
+    fn test_needs_clarification() {
+        let calculator = Calculator::new();
+        let message = needs_clarification(&calculator.clarifications("1/2x"));
+        assert!(message.starts_with("NEEDS_CLARIFICATION: "));
+
+        // 解釈ごとに書き直した式をヒントにする
+        let error = ToolError::classify(&message, &calculator);
+        assert_eq!(error.code, NEEDS_CLARIFICATION);
+        assert_eq!(
+            error.hints,
+            vec!["1/2 * x（(1/2) * x）", "1/(2x)（1 / (2x)）"]
+        );
+    }
+}
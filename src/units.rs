@@ -0,0 +1,437 @@
+// 量の単位。値は単位ごとに保持し、演算や変換のときに基本単位への倍率で換算する
+// units feature が無効なときは型（Unit）だけを残し、単位の名前を引けなくする（式の中で単位が使えなくなる）
+
+// 基本次元（SI の7つとデータ量）の数と、表示に使う基本単位
+pub const DIMENSIONS: usize = 8;
+const BASE_UNITS: [&str; DIMENSIONS] = ["m", "kg", "s", "A", "K", "mol", "cd", "bit"];
+
+// 基本次元の指数
+pub type Dimension = [i8; DIMENSIONS];
+
+const LENGTH: Dimension = [1, 0, 0, 0, 0, 0, 0, 0];
+const AREA: Dimension = [2, 0, 0, 0, 0, 0, 0, 0];
+const VOLUME: Dimension = [3, 0, 0, 0, 0, 0, 0, 0];
+const MASS: Dimension = [0, 1, 0, 0, 0, 0, 0, 0];
+const TIME: Dimension = [0, 0, 1, 0, 0, 0, 0, 0];
+const CURRENT: Dimension = [0, 0, 0, 1, 0, 0, 0, 0];
+const TEMPERATURE: Dimension = [0, 0, 0, 0, 1, 0, 0, 0];
+const AMOUNT: Dimension = [0, 0, 0, 0, 0, 1, 0, 0];
+const FREQUENCY: Dimension = [0, 0, -1, 0, 0, 0, 0, 0];
+const FORCE: Dimension = [1, 1, -2, 0, 0, 0, 0, 0];
+const PRESSURE: Dimension = [-1, 1, -2, 0, 0, 0, 0, 0];
+const ENERGY: Dimension = [2, 1, -2, 0, 0, 0, 0, 0];
+const POWER: Dimension = [2, 1, -3, 0, 0, 0, 0, 0];
+const VOLTAGE: Dimension = [2, 1, -3, -1, 0, 0, 0, 0];
+const RESISTANCE: Dimension = [2, 1, -3, -2, 0, 0, 0, 0];
+const INFORMATION: Dimension = [0, 0, 0, 0, 0, 0, 0, 1];
+// 燃料消費率（L/100km などの 体積/距離）と燃費（mpg などの 距離/体積）
+const FUEL_CONSUMPTION: Dimension = [2, 0, 0, 0, 0, 0, 0, 0];
+const FUEL_ECONOMY: Dimension = [-2, 0, 0, 0, 0, 0, 0, 0];
+const DATA_RATE: Dimension = [0, 0, -1, 0, 0, 0, 0, 1];
+
+// 式の中で使える単位の名前、基本単位への倍率、次元。
+// 名前は大文字・小文字を区別し、同じ名前の変数・定数があればそちらを優先する
+const UNITS: &[(&str, f64, Dimension)] = &[
+    ("m", 1.0, LENGTH),
+    ("km", 1000.0, LENGTH),
+    ("cm", 0.01, LENGTH),
+    ("mm", 0.001, LENGTH),
+    ("mi", 1609.344, LENGTH),
+    ("yd", 0.9144, LENGTH),
+    ("ft", 0.3048, LENGTH),
+    ("inch", 0.0254, LENGTH),
+    ("nmi", 1852.0, LENGTH),
+    ("ha", 10_000.0, AREA),
+    ("L", 0.001, VOLUME),
+    ("mL", 0.000_001, VOLUME),
+    // 米ガロンと英ガロン
+    ("gal", 0.003_785_411_784, VOLUME),
+    ("galUK", 0.004_546_09, VOLUME),
+    // 料理の計量単位（米国の慣用単位と、日本の 1 カップ = 200 mL）
+    ("cup", 0.000_236_588_236_5, VOLUME),
+    ("cupJP", 0.000_2, VOLUME),
+    ("tbsp", 0.000_014_786_764_781_25, VOLUME),
+    ("tsp", 0.000_004_928_921_593_75, VOLUME),
+    ("floz", 0.000_029_573_529_562_5, VOLUME),
+    ("L/100km", 0.001 / 100_000.0, FUEL_CONSUMPTION),
+    ("mpg", 1609.344 / 0.003_785_411_784, FUEL_ECONOMY),
+    ("mpgUK", 1609.344 / 0.004_546_09, FUEL_ECONOMY),
+    ("kg", 1.0, MASS),
+    ("g", 0.001, MASS),
+    ("mg", 0.000_001, MASS),
+    ("t", 1000.0, MASS),
+    ("lb", 0.453_592_37, MASS),
+    ("oz", 0.028_349_523_125, MASS),
+    ("s", 1.0, TIME),
+    ("ms", 0.001, TIME),
+    ("min", 60.0, TIME),
+    ("h", 3600.0, TIME),
+    ("day", 86_400.0, TIME),
+    ("week", 604_800.0, TIME),
+    ("A", 1.0, CURRENT),
+    ("mA", 0.001, CURRENT),
+    ("K", 1.0, TEMPERATURE),
+    ("deltaC", 1.0, TEMPERATURE),
+    ("deltaF", 5.0 / 9.0, TEMPERATURE),
+    ("mol", 1.0, AMOUNT),
+    ("Hz", 1.0, FREQUENCY),
+    ("kHz", 1000.0, FREQUENCY),
+    ("N", 1.0, FORCE),
+    ("Pa", 1.0, PRESSURE),
+    ("kPa", 1000.0, PRESSURE),
+    ("J", 1.0, ENERGY),
+    ("kJ", 1000.0, ENERGY),
+    ("kWh", 3_600_000.0, ENERGY),
+    ("W", 1.0, POWER),
+    ("kW", 1000.0, POWER),
+    ("V", 1.0, VOLTAGE),
+    ("ohm", 1.0, RESISTANCE),
+    ("bit", 1.0, INFORMATION),
+    ("kbit", 1000.0, INFORMATION),
+    ("Mbit", 1_000_000.0, INFORMATION),
+    ("Gbit", 1_000_000_000.0, INFORMATION),
+    ("B", 8.0, INFORMATION),
+    // SI 接頭辞（1000 倍ごと）のバイト。KB は kB と同じ
+    ("kB", 8_000.0, INFORMATION),
+    ("KB", 8_000.0, INFORMATION),
+    ("MB", 8_000_000.0, INFORMATION),
+    ("GB", 8_000_000_000.0, INFORMATION),
+    ("TB", 8_000_000_000_000.0, INFORMATION),
+    ("PB", 8_000_000_000_000_000.0, INFORMATION),
+    // IEC の2進接頭辞（1024 倍ごと）のバイト
+    ("KiB", 8.0 * 1024.0, INFORMATION),
+    ("MiB", 8.0 * 1_048_576.0, INFORMATION),
+    ("GiB", 8.0 * 1_073_741_824.0, INFORMATION),
+    ("TiB", 8.0 * 1_099_511_627_776.0, INFORMATION),
+    ("bps", 1.0, DATA_RATE),
+    ("kbps", 1000.0, DATA_RATE),
+    ("Mbps", 1_000_000.0, DATA_RATE),
+    ("Gbps", 1_000_000_000.0, DATA_RATE),
+];
+
+// 複数の意味で使われる単位（1000 倍と 1024 倍、国ごとに違う計量カップなど）と、計算に使った意味の説明
+const AMBIGUOUS_UNITS: &[(&str, &str)] = &[
+    ("kB", "kB は 1000 バイトとして計算しました（1024 バイトの場合は KiB）"),
+    ("KB", "KB は 1000 バイトとして計算しました（1024 バイトの場合は KiB）"),
+    ("MB", "MB は 1000^2 バイトとして計算しました（1024^2 バイトの場合は MiB）"),
+    ("GB", "GB は 1000^3 バイトとして計算しました（1024^3 バイトの場合は GiB。OS の表示では GB が GiB の意味のことがあります）"),
+    ("TB", "TB は 1000^4 バイトとして計算しました（1024^4 バイトの場合は TiB）"),
+    ("PB", "PB は 1000^5 バイトとして計算しました"),
+    ("cup", "cup は米国の 1 カップ（約 236.6 mL）として計算しました（日本の 1 カップ 200 mL は cupJP）"),
+    ("tbsp", "tbsp は米国の大さじ（約 14.8 mL）として計算しました（日本の大さじは 15 mL）"),
+    ("tsp", "tsp は米国の小さじ（約 4.93 mL）として計算しました（日本の小さじは 5 mL）"),
+];
+
+// / や数字を含むため、式の中で1つの名前として読む単位
+pub const COMPOUND_NAMES: &[&str] = if cfg!(feature = "units") {
+    &["L/100km"]
+} else {
+    &[]
+};
+
+// 零点が 0 K でない温度の目盛り（名前、倍率、水の凝固点（0 °C）の目盛りの値、温度差の単位）。
+// 20 degC は 293.15 K の温度で、温度の差（20 degC - 10 degC = 10 deltaC）は温度差の単位で表す
+const TEMPERATURE_SCALES: &[(&str, f64, f64, &str)] = &[
+    ("degC", 1.0, 0.0, "deltaC"),
+    ("C", 1.0, 0.0, "deltaC"),
+    ("degF", 5.0 / 9.0, 32.0, "deltaF"),
+    ("F", 5.0 / 9.0, 32.0, "deltaF"),
+];
+// 水の凝固点（0 °C）を K で表した値
+const FREEZING_POINT: f64 = 273.15;
+// UNITS のうち温度の差だけを表す単位（K は温度と温度差のどちらにも使える）
+const TEMPERATURE_DIFFERENCES: &[&str] = &["deltaC", "deltaF"];
+
+// 値と基本単位の関係。温度の目盛り（degC, degF）は倍率だけでは換算できず、
+// 温度差の単位（deltaC, deltaF）とは換算も足し算もできない
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Scale {
+    // 倍率だけで換算できる単位（km、K など）
+    Ratio,
+    // 温度の目盛り。水の凝固点は freezing 度で、差は difference の単位で表す
+    Absolute {
+        freezing: f64,
+        difference: &'static str,
+    },
+    // 温度の差
+    Difference,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Unit {
+    // 表示に使う名前（km など）
+    pub name: String,
+    // 1 単位を基本単位で表した値（km は 1000 m）
+    pub factor: f64,
+    pub dimension: Dimension,
+    pub scale: Scale,
+}
+
+impl Unit {
+    // UNITS と TEMPERATURE_SCALES にある単位。units feature が無効なら単位は使えない
+    pub fn lookup(name: &str) -> Option<Self> {
+        if !cfg!(feature = "units") {
+            return None;
+        }
+        if let Some(&(name, factor, freezing, difference)) = TEMPERATURE_SCALES
+            .iter()
+            .find(|(scale, _, _, _)| *scale == name)
+        {
+            return Some(Self {
+                name: name.to_string(),
+                factor,
+                dimension: TEMPERATURE,
+                scale: Scale::Absolute {
+                    freezing,
+                    difference,
+                },
+            });
+        }
+        UNITS
+            .iter()
+            .find(|(unit, _, _)| *unit == name)
+            .map(|&(name, factor, dimension)| Self {
+                name: name.to_string(),
+                factor,
+                dimension,
+                scale: if TEMPERATURE_DIFFERENCES.contains(&name) {
+                    Scale::Difference
+                } else {
+                    Scale::Ratio
+                },
+            })
+    }
+
+    // 次元が dimension の基本単位の組み合わせ（m·s^-2 など。倍率は 1）
+    pub fn base(dimension: Dimension) -> Self {
+        Self {
+            name: dimension_text(&dimension),
+            factor: 1.0,
+            dimension,
+            scale: Scale::Ratio,
+        }
+    }
+
+    // 温度の目盛り（degC, degF）か
+    pub fn is_absolute(&self) -> bool {
+        matches!(self.scale, Scale::Absolute { .. })
+    }
+
+    // 温度の目盛りの差を表す単位（degC なら deltaC）。それ以外はそのまま
+    pub fn difference(&self) -> Self {
+        match self.scale {
+            Scale::Absolute { difference, .. } => {
+                Self::lookup(difference).expect("TEMPERATURE_SCALES の温度差の単位は UNITS にある")
+            }
+            _ => self.clone(),
+        }
+    }
+
+    // この単位で表した value を基本単位（温度の目盛りは K）で表す
+    fn base_value(&self, value: f64) -> f64 {
+        match self.scale {
+            Scale::Absolute { freezing, .. } => (value - freezing) * self.factor + FREEZING_POINT,
+            _ => value * self.factor,
+        }
+    }
+
+    // 基本単位で表した base をこの単位で表す
+    fn unit_value(&self, base: f64) -> f64 {
+        match self.scale {
+            Scale::Absolute { freezing, .. } => (base - FREEZING_POINT) / self.factor + freezing,
+            _ => base / self.factor,
+        }
+    }
+
+    // 掛け算・割り算・べき乗に使えるか（温度の目盛りは零点がずれているため使えない）
+    fn check_ratio(&self) -> Result<(), String> {
+        if self.is_absolute() {
+            return Err(format!(
+                "温度 {} は掛け算・割り算・べき乗に使えません（温度の差は {} で表してください）",
+                self.name,
+                self.difference().name
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn is_dimensionless(&self) -> bool {
+        self.dimension == [0; DIMENSIONS]
+    }
+
+    // 単位の積（m·s）と商（km/h）。同じ単位どうしの積は m^2 とする
+    pub fn multiply(&self, other: &Self) -> Result<Self, String> {
+        if self.name == other.name {
+            return self.power(2);
+        }
+        self.combine(other, 1, format!("{}·{}", self.name, wrap(&other.name)))
+    }
+
+    pub fn divide(&self, other: &Self) -> Result<Self, String> {
+        self.combine(other, -1, format!("{}/{}", self.name, wrap(&other.name)))
+    }
+
+    fn combine(&self, other: &Self, sign: i8, name: String) -> Result<Self, String> {
+        self.check_ratio()?;
+        other.check_ratio()?;
+        let mut dimension = self.dimension;
+        for (exponent, other) in dimension.iter_mut().zip(other.dimension) {
+            *exponent = other
+                .checked_mul(sign)
+                .and_then(|other| exponent.checked_add(other))
+                .ok_or_else(|| RANGE_ERROR.to_string())?;
+        }
+        Ok(Self {
+            name,
+            factor: if sign > 0 {
+                self.factor * other.factor
+            } else {
+                self.factor / other.factor
+            },
+            dimension,
+            scale: Scale::Ratio,
+        })
+    }
+
+    // 単位の逆数（1/s）
+    pub fn reciprocal(&self) -> Result<Self, String> {
+        self.check_ratio()?;
+        let mut dimension = self.dimension;
+        for value in dimension.iter_mut() {
+            *value = value.checked_neg().ok_or_else(|| RANGE_ERROR.to_string())?;
+        }
+        Ok(Self {
+            name: format!("1/{}", wrap(&self.name)),
+            factor: 1.0 / self.factor,
+            dimension,
+            scale: Scale::Ratio,
+        })
+    }
+
+    pub fn power(&self, exponent: i32) -> Result<Self, String> {
+        self.check_ratio()?;
+        let mut dimension = self.dimension;
+        for value in dimension.iter_mut() {
+            *value = i32::from(*value)
+                .checked_mul(exponent)
+                .and_then(|value| i8::try_from(value).ok())
+                .ok_or_else(|| RANGE_ERROR.to_string())?;
+        }
+        Ok(Self {
+            name: format!("{}^{}", wrap(&self.name), exponent),
+            factor: self.factor.powi(exponent),
+            dimension,
+            scale: Scale::Ratio,
+        })
+    }
+
+    pub fn is_compatible(&self, other: &Self) -> bool {
+        self.dimension == other.dimension
+    }
+
+    // 燃料消費率と燃費のように、互いに逆数の関係で換算する単位か
+    fn is_reciprocal(&self, other: &Self) -> bool {
+        matches!(
+            (self.dimension, other.dimension),
+            (FUEL_CONSUMPTION, FUEL_ECONOMY) | (FUEL_ECONOMY, FUEL_CONSUMPTION)
+        )
+    }
+
+    // この単位で表した value を to の単位で表す
+    pub fn convert(&self, value: f64, to: &Self) -> Result<f64, String> {
+        // 6 L/100km は 100 km あたり 6 L で、燃費はその逆数（約 39.2 mpg）
+        if self.is_reciprocal(to) {
+            if value == 0.0 {
+                return Err(format!(
+                    "0 {} は {} に換算できません（逆数になるため）",
+                    self.name, to.name
+                ));
+            }
+            return Ok(1.0 / (value * self.factor) / to.factor);
+        }
+        if !self.is_compatible(to) {
+            return Err(format!(
+                "{} と {} は次元が違うため換算できません（{} と {}）",
+                self.name,
+                to.name,
+                dimension_text(&self.dimension),
+                dimension_text(&to.dimension)
+            ));
+        }
+        if let (Scale::Absolute { .. }, Scale::Difference)
+        | (Scale::Difference, Scale::Absolute { .. }) = (self.scale, to.scale)
+        {
+            return Err(format!(
+                "温度と温度差は換算できません（{} と {}。20 deltaC は 36 deltaF で、68 degF ではありません）",
+                self.name, to.name
+            ));
+        }
+        // 温度の目盛りどうしは K を経由せず凝固点からの差で換算する（F = C × 9/5 + 32）。
+        // K の値 273.15 や 459.67 × 5/9 の丸め誤差で 20 degC が 67.99999999999999 degF にならないようにする
+        if let (
+            Scale::Absolute {
+                freezing: from_freezing,
+                ..
+            },
+            Scale::Absolute {
+                freezing: to_freezing,
+                ..
+            },
+        ) = (self.scale, to.scale)
+        {
+            return Ok((value - from_freezing) * self.factor / to.factor + to_freezing);
+        }
+        Ok(to.unit_value(self.base_value(value)))
+    }
+}
+
+const RANGE_ERROR: &str = "単位の次元の指数が大きすぎます";
+
+// 組み立てた単位の名前を、別の単位と組み合わせるときに括弧で囲む（m/(s·s)）
+fn wrap(name: &str) -> String {
+    if name.contains(['·', '/', '^']) {
+        format!("({})", name)
+    } else {
+        name.to_string()
+    }
+}
+
+// 次元を基本単位の積で表す（m·s^-2 など）
+pub fn dimension_text(dimension: &Dimension) -> String {
+    let parts: Vec<String> = BASE_UNITS
+        .iter()
+        .zip(dimension)
+        .filter(|(_, &exponent)| exponent != 0)
+        .map(|(unit, &exponent)| match exponent {
+            1 => unit.to_string(),
+            _ => format!("{}^{}", unit, exponent),
+        })
+        .collect();
+    if parts.is_empty() {
+        "無次元".to_string()
+    } else {
+        parts.join("·")
+    }
+}
+
+// 1000 倍と 1024 倍のどちらの意味でも使われる単位なら、計算に使った意味の説明
+pub fn ambiguity(name: &str) -> Option<&'static str> {
+    if !cfg!(feature = "units") {
+        return None;
+    }
+    AMBIGUOUS_UNITS
+        .iter()
+        .find(|(unit, _)| *unit == name)
+        .map(|(_, note)| *note)
+}
+
+pub fn names() -> Vec<&'static str> {
+    if !cfg!(feature = "units") {
+        return Vec::new();
+    }
+    UNITS
+        .iter()
+        .map(|(name, _, _)| *name)
+        .chain(TEMPERATURE_SCALES.iter().map(|(name, _, _, _)| *name))
+        .collect()
+}
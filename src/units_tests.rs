@@ -0,0 +1,75 @@
+#[cfg(test)]
+mod tests {
+    use crate::units::{self, Unit};
+
+    #[test]
+    fn test_unit_conversion() {
+        let km = Unit::lookup("km").unwrap();
+        let mile = Unit::lookup("mi").unwrap();
+        assert_eq!(km.convert(3.0, &Unit::lookup("m").unwrap()), Ok(3000.0));
+        assert!((mile.convert(1.0, &km).unwrap() - 1.609344).abs() < 1e-12);
+        assert_eq!(
+            Unit::lookup("kWh")
+                .unwrap()
+                .convert(1.0, &Unit::lookup("kJ").unwrap()),
+            Ok(3600.0)
+        );
+        assert_eq!(
+            Unit::lookup("B")
+                .unwrap()
+                .convert(2.0, &Unit::lookup("bit").unwrap()),
+            Ok(16.0)
+        );
+        // 名前は大文字・小文字を区別する
+        assert!(Unit::lookup("KM").is_none());
+        assert!(units::names().contains(&"ohm"));
+    }
+
+    #[test]
+    fn test_temperature_scales() {
+        let unit = |name: &str| Unit::lookup(name).unwrap();
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-9;
+        assert!(close(
+            unit("degC").convert(20.0, &unit("degF")).unwrap(),
+            68.0
+        ));
+        assert!(close(unit("F").convert(-40.0, &unit("C")).unwrap(), -40.0));
+        assert!(close(
+            unit("degC").convert(0.0, &unit("K")).unwrap(),
+            273.15
+        ));
+        assert!(close(
+            unit("K").convert(0.0, &unit("degF")).unwrap(),
+            -459.67
+        ));
+        // 温度差は倍率だけで換算する（20 deltaC は 36 deltaF）
+        assert!(close(
+            unit("deltaC").convert(20.0, &unit("deltaF")).unwrap(),
+            36.0
+        ));
+        assert!(close(unit("deltaF").convert(9.0, &unit("K")).unwrap(), 5.0));
+        assert!(unit("deltaC")
+            .convert(20.0, &unit("degF"))
+            .unwrap_err()
+            .contains("温度と温度差は換算できません"));
+        assert_eq!(unit("degF").difference(), unit("deltaF"));
+        assert!(unit("degC").multiply(&unit("m")).is_err());
+        assert!(unit("deltaC").multiply(&unit("m")).is_ok());
+    }
+
+    #[test]
+    fn test_dimension_mismatch() {
+        let km = Unit::lookup("km").unwrap();
+        let second = Unit::lookup("s").unwrap();
+        assert!(!km.is_compatible(&second));
+        assert_eq!(
+            km.convert(1.0, &second),
+            Err("km と s は次元が違うため換算できません（m と s）".to_string())
+        );
+        assert_eq!(
+            units::dimension_text(&Unit::lookup("N").unwrap().dimension),
+            "m·kg·s^-2"
+        );
+        assert_eq!(units::dimension_text(&[0; units::DIMENSIONS]), "無次元");
+    }
+}
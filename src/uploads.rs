@@ -0,0 +1,180 @@
+use crate::config::LimitsConfig;
+use crate::matrix::{self, Matrix};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+// upload_data で受け取ったデータを参照する URI の接頭辞
+pub const URI_PREFIX: &str = "upload://";
+
+#[derive(Debug, Serialize)]
+pub struct UploadStatus {
+    pub upload_id: String,
+    pub uri: String,
+    pub bytes: usize,
+    // false の間は続きのチャンクを受け付け、まだ計算には使えない
+    pub complete: bool,
+}
+
+#[derive(Debug)]
+struct Upload {
+    data: String,
+    complete: bool,
+}
+
+#[derive(Debug, Default)]
+struct Uploads {
+    next_id: u64,
+    // ID は連番のため、BTreeMap の先頭が最も古い
+    entries: BTreeMap<u64, Upload>,
+}
+
+// 大きなデータを1回のツール引数で送らずに済むよう、チャンクに分けて受け取って保持する
+#[derive(Debug, Clone, Default)]
+pub struct UploadStore {
+    inner: Arc<Mutex<Uploads>>,
+}
+
+impl UploadStore {
+    // upload_id を省略すると新しいアップロードを始める
+    pub fn append(
+        &self,
+        upload_id: Option<&str>,
+        chunk: &str,
+        done: bool,
+        limits: &LimitsConfig,
+    ) -> Result<UploadStatus, String> {
+        let mut uploads = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let id = match upload_id {
+            Some(upload_id) => parse_id(upload_id)?,
+            None => {
+                uploads.next_id += 1;
+                let id = uploads.next_id;
+                uploads.entries.insert(
+                    id,
+                    Upload {
+                        data: String::new(),
+                        complete: false,
+                    },
+                );
+                while uploads.entries.len() > limits.max_uploads.max(1) {
+                    uploads.entries.pop_first();
+                }
+                id
+            }
+        };
+
+        let upload = uploads
+            .entries
+            .get_mut(&id)
+            .ok_or_else(|| format!("アップロードが見つかりません: {}", id))?;
+        if upload.complete {
+            return Err(format!("アップロード {} は完了しています", id));
+        }
+        if upload.data.len() + chunk.len() > limits.max_upload_bytes {
+            let bytes = upload.data.len() + chunk.len();
+            uploads.entries.remove(&id);
+            return Err(format!(
+                "アップロードが大きすぎます: {} バイト（上限は {} バイト）",
+                bytes, limits.max_upload_bytes
+            ));
+        }
+        upload.data.push_str(chunk);
+        upload.complete = done;
+        Ok(UploadStatus {
+            upload_id: id.to_string(),
+            uri: format!("{}{}", URI_PREFIX, id),
+            bytes: upload.data.len(),
+            complete: upload.complete,
+        })
+    }
+
+    // 完了したアップロードの内容（uri は upload://<id>）
+    pub fn get(&self, uri: &str) -> Result<String, String> {
+        let id = uri
+            .strip_prefix(URI_PREFIX)
+            .ok_or_else(|| format!("{} で始まる URI を指定してください: {}", URI_PREFIX, uri))
+            .and_then(parse_id)?;
+        let uploads = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        match uploads.entries.get(&id) {
+            Some(upload) if upload.complete => Ok(upload.data.clone()),
+            Some(_) => Err(format!(
+                "アップロード {} はまだ完了していません（最後のチャンクで done: true を指定してください）",
+                id
+            )),
+            None => Err(format!("アップロードが見つかりません: {}", uri)),
+        }
+    }
+
+    // 完了したアップロードの URI とバイト数
+    pub fn list(&self) -> Vec<(String, usize)> {
+        let uploads = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        uploads
+            .entries
+            .iter()
+            .filter(|(_, upload)| upload.complete)
+            .map(|(id, upload)| (format!("{}{}", URI_PREFIX, id), upload.data.len()))
+            .collect()
+    }
+
+    // すべてのアップロードを削除し、削除した件数を返す。
+    // ID の連番は戻さないので、古い URI が新しいデータを指すことはない
+    // ロックを持ったままパニックした（データの更新が途中かもしれない）場合は空に戻し、戻したかを返す
+    pub fn recover(&self) -> bool {
+        if !self.inner.is_poisoned() {
+            return false;
+        }
+        self.clear();
+        self.inner.clear_poison();
+        true
+    }
+
+    pub fn clear(&self) -> usize {
+        let mut uploads = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let count = uploads.entries.len();
+        uploads.entries.clear();
+        count
+    }
+}
+
+// JSON の行の配列（[[1, 2], [3, 4]]）か、1行に1行ずつカンマまたは空白で区切った数値
+pub fn parse_matrix(text: &str, max_elements: usize) -> Result<Matrix, String> {
+    let text = text.trim();
+    let matrix: Matrix = if text.starts_with('[') {
+        serde_json::from_str(text).map_err(|e| format!("行列の JSON が不正です: {}", e))?
+    } else {
+        let mut rows = Vec::new();
+        let mut elements = 0;
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let row = line
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|value| !value.is_empty())
+                .map(|value| value.parse::<f64>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| format!("{}行目に数値ではない値があります", index + 1))?;
+            // 巨大な入力は読み終える前に打ち切る
+            elements += row.len();
+            if elements > max_elements {
+                return Err(format!(
+                    "行列が大きすぎます: 要素数が上限 {} を超えています",
+                    max_elements
+                ));
+            }
+            rows.push(row);
+        }
+        rows
+    };
+    matrix::check_size(&matrix, max_elements)?;
+    Ok(matrix)
+}
+
+fn parse_id(upload_id: &str) -> Result<u64, String> {
+    upload_id
+        .trim()
+        .parse()
+        .map_err(|_| format!("アップロードIDが不正です: {}", upload_id))
+}
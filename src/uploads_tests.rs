@@ -0,0 +1,71 @@
+#[cfg(test)]
+mod tests {
+    use crate::config::LimitsConfig;
+    use crate::uploads::{parse_matrix, UploadStore};
+
+    #[test]
+    fn test_chunked_upload() {
+        let store = UploadStore::default();
+        let limits = LimitsConfig::default();
+
+        let first = store.append(None, "1,2\n", false, &limits).unwrap();
+        assert!(!first.complete);
+        assert!(store.get(&first.uri).is_err());
+
+        let last = store
+            .append(Some(&first.upload_id), "3,4\n", true, &limits)
+            .unwrap();
+        assert_eq!(last.uri, first.uri);
+        assert_eq!(store.get(&last.uri).unwrap(), "1,2\n3,4\n");
+        assert_eq!(store.list(), vec![(last.uri.clone(), 8)]);
+        assert!(store
+            .append(Some(&first.upload_id), "5,6\n", true, &limits)
+            .is_err());
+    }
+
+    #[test]
+    fn test_upload_limits() {
+        let store = UploadStore::default();
+        let limits = LimitsConfig {
+            max_upload_bytes: 8,
+            max_uploads: 2,
+            ..LimitsConfig::default()
+        };
+
+        let too_large = store.append(None, "123456789", true, &limits);
+        assert!(too_large.is_err());
+
+        let oldest = store.append(None, "1", true, &limits).unwrap();
+        store.append(None, "2", true, &limits).unwrap();
+        store.append(None, "3", true, &limits).unwrap();
+        assert!(store.get(&oldest.uri).is_err());
+        assert_eq!(store.list().len(), 2);
+        assert!(store.get("file:///etc/passwd").is_err());
+    }
+
+    #[test]
+    fn test_clear_uploads() {
+        let store = UploadStore::default();
+        let limits = LimitsConfig::default();
+
+        let old = store.append(None, "1", true, &limits).unwrap();
+        store.append(None, "2", false, &limits).unwrap();
+        assert_eq!(store.clear(), 2);
+        assert!(store.list().is_empty());
+
+        // 削除前の URI が新しいアップロードを指さない
+        let new = store.append(None, "3", true, &limits).unwrap();
+        assert_ne!(new.uri, old.uri);
+        assert!(store.get(&old.uri).is_err());
+    }
+
+    #[test]
+    fn test_parse_matrix_text() {
+        assert_eq!(
+            parse_matrix("# 2x2\n1, 2\n3 4\n", 2500).unwrap(),
+            vec![vec![1.0, 2.0], vec![3.0, 4.0]]
+        );
+        assert!(parse_matrix("1,2\n3,x\n", 2500).is_err());
+        assert!(parse_matrix("1,2,3\n4,5,6\n", 5).is_err());
+    }
+}
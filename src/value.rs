@@ -0,0 +1,858 @@
+use crate::calculator::{division_by_zero, domain_error, syntax_error};
+use crate::constraints::{self, Relation};
+use crate::matrix::{self, Matrix};
+use crate::units::{self, Unit};
+
+// 行列・ベクトルを引数にとる関数。変数名と衝突しないよう、直後に ( がある場合だけ関数として扱う
+pub const FUNCTIONS: &[&str] = &["det", "inv", "transpose", "trace", "dot", "cross", "norm"];
+// 変数・定数として定義されていなければ虚数単位として扱う名前
+pub const IMAGINARY_UNIT: &str = "i";
+
+// 式の値。数値の型は 整数 ⊂ 分数 ⊂ 浮動小数点数 ⊂ 複素数 の順に広く、
+// 2つの数値の演算は両方を表せる最も狭い型で行う（1/3 は分数、1/3 + 0.5 は浮動小数点数）。
+// 数値以外との演算の型は次のとおりで、ここにない組み合わせは型のエラーにする
+//   数値 と ベクトル・行列     * は要素ごと（右が数値なら / も）
+//   ベクトル・行列 どうし      + - は同じ形で要素ごと、* は行列の積
+//   量 と 実数                 * / は単位をそのままにした量（実数 / 量 は単位の逆数）
+//   量 と 量                   + - と比較は同じ次元どうしで左辺の単位、* / は単位の積と商
+//   量 ^ 整数                  単位のべき乗
+//   真偽値                     == と != だけ
+// 単位の積や商が無次元になった場合は数値にする（1 km / m は 1000）
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    // 浮動小数点数（小数のリテラルや関数の結果）
+    Scalar(f64),
+    // 整数のリテラルと、整数どうしの + - * の結果（i64 を超えると浮動小数点数になる）
+    Integer(i64),
+    // 既約分数（分母は 2 以上）。整数どうしの割り算の結果
+    Rational(i64, i64),
+    // 実部と虚部（虚部が 0 になった結果は実数にする）
+    Complex(f64, f64),
+    // [1, 2] のベクトル
+    List(Vec<f64>),
+    // [[1, 2], [3, 4]] の行列（行の配列）
+    Matrix(Matrix),
+    // 単位つきの量（5 km。値はその単位で表す）
+    Quantity(f64, Unit),
+    // 比較の結果
+    Boolean(bool),
+}
+
+impl Value {
+    // 分数を約分して値にする（分母が 1 なら整数）。i64 に収まらなければ None
+    fn rational(numerator: i128, denominator: i128) -> Option<Self> {
+        if denominator == 0 {
+            return None;
+        }
+        let divisor = gcd(numerator.unsigned_abs(), denominator.unsigned_abs());
+        let divisor = i128::try_from(divisor).ok()? * denominator.signum();
+        let numerator = i64::try_from(numerator / divisor).ok()?;
+        let denominator = i64::try_from(denominator / divisor).ok()?;
+        Some(if denominator == 1 {
+            Self::Integer(numerator)
+        } else {
+            Self::Rational(numerator, denominator)
+        })
+    }
+
+    // 数値リテラルの値。f64 で正確に表せる整数は整数として扱う
+    pub fn from_literal(value: f64) -> Self {
+        if value.fract() == 0.0 && value.abs() <= MAX_INTEGER_EXPONENT {
+            Self::Integer(value as i64)
+        } else {
+            Self::Scalar(value)
+        }
+    }
+
+    pub fn quantity(value: f64, unit: Unit) -> Self {
+        if unit.is_dimensionless() {
+            Self::Scalar(value * unit.factor)
+        } else {
+            Self::Quantity(value, unit)
+        }
+    }
+
+    pub fn complex(re: f64, im: f64) -> Self {
+        if im == 0.0 {
+            Self::Scalar(re)
+        } else {
+            Self::Complex(re, im)
+        }
+    }
+
+    // 実数（整数・分数・浮動小数点数）なら f64 の値
+    pub fn real(&self) -> Option<f64> {
+        match self {
+            Self::Scalar(value) => Some(*value),
+            Self::Integer(value) => Some(*value as f64),
+            Self::Rational(numerator, denominator) => Some(*numerator as f64 / *denominator as f64),
+            _ => None,
+        }
+    }
+
+    // 整数・分数なら（分子, 分母）
+    fn exact(&self) -> Option<(i128, i128)> {
+        match self {
+            Self::Integer(value) => Some((i128::from(*value), 1)),
+            Self::Rational(numerator, denominator) => {
+                Some((i128::from(*numerator), i128::from(*denominator)))
+            }
+            _ => None,
+        }
+    }
+
+    fn complex_parts(&self) -> Option<(f64, f64)> {
+        match self {
+            Self::Complex(re, im) => Some((*re, *im)),
+            other => other.real().map(|value| (value, 0.0)),
+        }
+    }
+
+    // エラーの説明に使う値の型（「2×3 の行列」など）
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Scalar(_) => "数値".to_string(),
+            Self::Integer(_) => "整数".to_string(),
+            Self::Rational(..) => "分数".to_string(),
+            Self::Complex(..) => "複素数".to_string(),
+            Self::List(list) => format!("長さ {} のベクトル", list.len()),
+            Self::Matrix(matrix) => format!("{}×{} の行列", matrix.len(), cols(matrix)),
+            Self::Quantity(_, unit) => format!("単位 {} の量", unit.name),
+            Self::Boolean(_) => "真偽値".to_string(),
+        }
+    }
+
+    // 構造化した出力（calculate_batch など）での型の名前
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Scalar(_) => "number",
+            Self::Integer(_) => "integer",
+            Self::Rational(..) => "rational",
+            Self::Complex(..) => "complex",
+            Self::List(_) => "vector",
+            Self::Matrix(_) => "matrix",
+            Self::Quantity(..) => "quantity",
+            Self::Boolean(_) => "boolean",
+        }
+    }
+
+    // 浮動小数点数の誤差を含まない値か（整数・分数・真偽値）
+    pub fn is_exact(&self) -> bool {
+        matches!(
+            self,
+            Self::Integer(_) | Self::Rational(..) | Self::Boolean(_)
+        )
+    }
+
+    pub fn unit(&self) -> Option<&Unit> {
+        match self {
+            Self::Quantity(_, unit) => Some(unit),
+            _ => None,
+        }
+    }
+
+    // ベクトルは [長さ]、行列は [行数, 列数]。それ以外は None
+    pub fn shape(&self) -> Option<Vec<usize>> {
+        match self {
+            Self::List(list) => Some(vec![list.len()]),
+            Self::Matrix(matrix) => Some(vec![matrix.len(), cols(matrix)]),
+            _ => None,
+        }
+    }
+
+    // 実数しか受け付けない演算や関数の引数として取り出す
+    pub fn as_scalar(&self, context: &str) -> Result<f64, String> {
+        self.real().ok_or_else(|| {
+            format!(
+                "{}には数値を指定してください（{}が指定されています）",
+                context,
+                self.describe()
+            )
+        })
+    }
+
+    // [a, b, ...] の値。要素がすべて実数ならベクトル、同じ長さのベクトルなら行列
+    pub fn from_elements(elements: Vec<Value>) -> Result<Self, String> {
+        if elements.is_empty() {
+            return Err(syntax_error("空の [] は使えません"));
+        }
+        if elements.iter().all(|element| element.real().is_some()) {
+            return Ok(Self::List(
+                elements
+                    .iter()
+                    .map(|element| element.as_scalar("[]"))
+                    .collect::<Result<_, _>>()?,
+            ));
+        }
+        let mut rows = Vec::with_capacity(elements.len());
+        for element in elements {
+            match element {
+                Self::List(row) => rows.push(row),
+                Self::Matrix(_) => return Err("3次元以上の配列は使えません".to_string()),
+                other if other.real().is_some() => {
+                    return Err("[] の中に数値とベクトルを混ぜることはできません".to_string())
+                }
+                other => {
+                    return Err(format!(
+                        "ベクトル・行列の要素には実数を指定してください（{}が指定されています）",
+                        other.describe()
+                    ))
+                }
+            }
+        }
+        let cols = rows[0].len();
+        if let Some(index) = rows.iter().position(|row| row.len() != cols) {
+            return Err(format!(
+                "行列の行の長さがそろっていません: 1 行目は {} 個、{} 行目は {} 個",
+                cols,
+                index + 1,
+                rows[index].len()
+            ));
+        }
+        Ok(Self::Matrix(rows))
+    }
+
+    // 数値の要素すべてに f を適用する（整数・分数は浮動小数点数になる）
+    pub fn map(self, f: impl Fn(f64) -> f64) -> Self {
+        match self {
+            Self::Complex(re, im) => Self::complex(f(re), f(im)),
+            Self::List(list) => Self::List(list.into_iter().map(f).collect()),
+            Self::Matrix(matrix) => Self::Matrix(
+                matrix
+                    .into_iter()
+                    .map(|row| row.into_iter().map(&f).collect())
+                    .collect(),
+            ),
+            Self::Quantity(value, unit) => Self::Quantity(f(value), unit),
+            Self::Boolean(value) => Self::Boolean(value),
+            real => Self::Scalar(f(real.real().unwrap_or_default())),
+        }
+    }
+
+    // ベクトル・行列の要素ごとに f を適用する。それ以外の値なら None
+    pub fn try_map_elements(
+        &self,
+        f: impl Fn(f64) -> Result<f64, String>,
+    ) -> Option<Result<Self, String>> {
+        match self {
+            Self::List(list) => Some(
+                list.iter()
+                    .map(|&x| f(x))
+                    .collect::<Result<_, _>>()
+                    .map(Self::List),
+            ),
+            Self::Matrix(matrix) => Some(
+                matrix
+                    .iter()
+                    .map(|row| row.iter().map(|&x| f(x)).collect::<Result<_, _>>())
+                    .collect::<Result<_, _>>()
+                    .map(Self::Matrix),
+            ),
+            _ => None,
+        }
+    }
+
+    // 値を表記する（浮動小数点数の表記は number に任せる）
+    pub fn format(&self, number: impl Fn(f64) -> String) -> String {
+        let list = |values: &[f64]| {
+            let items: Vec<String> = values.iter().map(|&value| number(value)).collect();
+            format!("[{}]", items.join(", "))
+        };
+        match self {
+            Self::Scalar(value) => number(*value),
+            Self::Integer(value) => value.to_string(),
+            Self::Rational(numerator, denominator) => format!("{}/{}", numerator, denominator),
+            Self::Complex(re, im) => {
+                let imaginary = match im.abs() {
+                    1.0 => IMAGINARY_UNIT.to_string(),
+                    magnitude => format!("{}{}", number(magnitude), IMAGINARY_UNIT),
+                };
+                match (*re == 0.0, im.is_sign_negative()) {
+                    (true, false) => imaginary,
+                    (true, true) => format!("-{}", imaginary),
+                    (false, false) => format!("{} + {}", number(*re), imaginary),
+                    (false, true) => format!("{} - {}", number(*re), imaginary),
+                }
+            }
+            Self::List(values) => list(values),
+            Self::Matrix(matrix) => {
+                let rows: Vec<String> = matrix.iter().map(|row| list(row)).collect();
+                format!("[{}]", rows.join(", "))
+            }
+            Self::Quantity(value, unit) => format!("{} {}", number(*value), unit.name),
+            Self::Boolean(value) => value.to_string(),
+        }
+    }
+
+    // 量を unit で表す（5 km in mi）
+    pub fn convert_to(self, unit: Unit) -> Result<Self, String> {
+        match self {
+            Self::Quantity(value, from) => Ok(Self::Quantity(from.convert(value, &unit)?, unit)),
+            other => Err(format!(
+                "{}は {} に換算できません（in の左辺には 5 km のような単位つきの量を指定してください）",
+                other.describe(),
+                unit.name
+            )),
+        }
+    }
+
+    pub fn checked_add(self, other: Self) -> Result<Self, String> {
+        self.additive(other, '+', "足す")
+    }
+
+    pub fn checked_sub(self, other: Self) -> Result<Self, String> {
+        self.additive(other, '-', "引く")
+    }
+
+    // + と - は数値どうし、同じ形のベクトル・行列どうし（要素ごと）、同じ次元の量どうし
+    fn additive(self, other: Self, operator: char, verb: &str) -> Result<Self, String> {
+        if let Some(result) = numeric(operator, &self, &other) {
+            return result;
+        }
+        let f = |a: f64, b: f64| if operator == '+' { a + b } else { a - b };
+        match (self, other) {
+            (Self::List(a), Self::List(b)) if a.len() == b.len() => Ok(Self::List(
+                a.iter().zip(&b).map(|(&x, &y)| f(x, y)).collect(),
+            )),
+            (Self::Matrix(a), Self::Matrix(b)) if a.len() == b.len() && cols(&a) == cols(&b) => {
+                Ok(Self::Matrix(
+                    a.iter()
+                        .zip(&b)
+                        .map(|(x, y)| x.iter().zip(y).map(|(&x, &y)| f(x, y)).collect())
+                        .collect(),
+                ))
+            }
+            (Self::Quantity(a, u), Self::Quantity(b, v)) if u.is_compatible(&v) => {
+                temperature_additive(a, u, b, v, operator, f)
+            }
+            (a, b) => Err(mismatch(&a, &b, verb)),
+        }
+    }
+
+    // 数値との積は要素ごと、行列同士・行列とベクトルは行列の積（ベクトルは左からは行、右からは列とみなす）
+    pub fn checked_mul(self, other: Self) -> Result<Self, String> {
+        if let Some(result) = numeric('*', &self, &other) {
+            return result;
+        }
+        let shapes = (self.describe(), other.describe());
+        match (self, other) {
+            (Self::Boolean(_), _) | (_, Self::Boolean(_)) => Err(BOOLEAN_ARITHMETIC.to_string()),
+            (Self::Quantity(a, u), Self::Quantity(b, v)) => {
+                Ok(Self::quantity(a * b, u.multiply(&v)?))
+            }
+            (Self::Quantity(_, unit), _) | (_, Self::Quantity(_, unit)) if unit.is_absolute() => {
+                Err(absolute_scaling(&unit))
+            }
+            (a, b) if a.real().is_some() => {
+                let a = a.real().unwrap_or_default();
+                Ok(b.map(|x| a * x))
+            }
+            (a, b) if b.real().is_some() => {
+                let b = b.real().unwrap_or_default();
+                Ok(a.map(|x| x * b))
+            }
+            (Self::Matrix(a), Self::Matrix(b)) => {
+                check_product(&shapes, cols(&a), b.len())?;
+                Ok(Self::Matrix(matrix::multiply(&a, &b)))
+            }
+            (Self::Matrix(a), Self::List(b)) => {
+                check_product(&shapes, cols(&a), b.len())?;
+                Ok(Self::List(
+                    a.iter()
+                        .map(|row| row.iter().zip(&b).map(|(x, y)| x * y).sum())
+                        .collect(),
+                ))
+            }
+            (Self::List(a), Self::Matrix(b)) => {
+                check_product(&shapes, a.len(), b.len())?;
+                Ok(Self::List(matrix::multiply(&vec![a], &b).remove(0)))
+            }
+            (Self::List(_), Self::List(_)) => Err(
+                "ベクトル同士の * は使えません（内積は dot、外積は cross を使ってください）"
+                    .to_string(),
+            ),
+            (a, b) => Err(mismatch(&a, &b, "掛ける")),
+        }
+    }
+
+    // ベクトル・行列・量は実数で割る場合だけ使える（行列で割る場合は inv を掛ける）
+    pub fn checked_div(self, other: Self) -> Result<Self, String> {
+        if let Some(result) = numeric('/', &self, &other) {
+            return result;
+        }
+        match (self, other) {
+            (Self::Boolean(_), _) | (_, Self::Boolean(_)) => Err(BOOLEAN_ARITHMETIC.to_string()),
+            (_, Self::Quantity(0.0, _)) => Err(division_by_zero()),
+            (Self::Quantity(a, u), Self::Quantity(b, v)) => {
+                Ok(Self::quantity(a / b, u.divide(&v)?))
+            }
+            (Self::Quantity(_, unit), _) if unit.is_absolute() => Err(absolute_scaling(&unit)),
+            (a, Self::Quantity(b, v)) if a.real().is_some() => Ok(Self::Quantity(
+                a.real().unwrap_or_default() / b,
+                v.reciprocal()?,
+            )),
+            (a, b) if b.real().is_some() => {
+                let divisor = b.real().unwrap_or_default();
+                if divisor == 0.0 {
+                    return Err(division_by_zero());
+                }
+                Ok(a.map(|x| x / divisor))
+            }
+            (_, other @ (Self::List(_) | Self::Matrix(_))) => Err(format!(
+                "{}で割ることはできません（逆行列を掛ける場合は inv を使ってください）",
+                other.describe()
+            )),
+            (a, b) => Err(mismatch(&a, &b, "割る")),
+        }
+    }
+
+    // 数値のべき乗と、正方行列の整数乗（負の指数は逆行列のべき乗）
+    pub fn checked_pow(self, exponent: Self) -> Result<Self, String> {
+        if let Some(result) = numeric('^', &self, &exponent) {
+            return result;
+        }
+        match self {
+            Self::Matrix(base) => {
+                let exponent = exponent.as_scalar("^ の指数")?;
+                let n = matrix::validate_square(&base)?;
+                if exponent.fract() != 0.0 || exponent.abs() > MAX_INTEGER_EXPONENT {
+                    return Err(format!(
+                        "行列のべき乗の指数は整数にしてください（絶対値は {} まで）: {}",
+                        MAX_INTEGER_EXPONENT, exponent
+                    ));
+                }
+                let base = if exponent < 0.0 { invert(&base)? } else { base };
+                Ok(Self::Matrix(repeated_product(
+                    matrix::identity(n),
+                    base,
+                    exponent.abs() as u64,
+                    matrix::multiply,
+                )))
+            }
+            Self::Quantity(base, unit) => {
+                let exponent = exponent.as_scalar("量の ^ の指数")?;
+                if exponent.fract() != 0.0 || exponent.abs() > f64::from(i8::MAX) {
+                    return Err(format!(
+                        "量のべき乗の指数は絶対値 {} までの整数にしてください: {}",
+                        i8::MAX,
+                        exponent
+                    ));
+                }
+                let exponent = exponent as i32;
+                Ok(Self::quantity(base.powi(exponent), unit.power(exponent)?))
+            }
+            Self::Boolean(_) => Err(BOOLEAN_ARITHMETIC.to_string()),
+            other @ Self::List(_) => Err(format!(
+                "{}のべき乗はできません（要素ごとに計算する場合は * で書いてください）",
+                other.describe()
+            )),
+            other => Err(mismatch(&other, &exponent, "べき乗する")),
+        }
+    }
+
+    pub fn negate(self) -> Result<Self, String> {
+        match self {
+            Self::Integer(value) => Ok(value
+                .checked_neg()
+                .map_or(Self::Scalar(-(value as f64)), Self::Integer)),
+            Self::Rational(numerator, denominator) => Ok(numerator.checked_neg().map_or(
+                Self::Scalar(-(numerator as f64) / denominator as f64),
+                |numerator| Self::Rational(numerator, denominator),
+            )),
+            Self::Boolean(_) => Err(BOOLEAN_ARITHMETIC.to_string()),
+            other => Ok(other.map(|x| -x)),
+        }
+    }
+
+    // 比較の結果の真偽値。実数（と同じ次元の量）は check_constraints と同じ許容誤差で比べる
+    pub fn compare(self, relation: Relation, other: Self) -> Result<Self, String> {
+        let equality = matches!(relation, Relation::Equal | Relation::NotEqual);
+        let holds = |lhs: f64, rhs: f64| constraints::check("", lhs, relation, rhs).holds;
+        match (self, other) {
+            (a, b) if a.real().is_some() && b.real().is_some() => Ok(Self::Boolean(holds(
+                a.real().unwrap_or_default(),
+                b.real().unwrap_or_default(),
+            ))),
+            (Self::Quantity(a, u), Self::Quantity(b, v)) if u.is_compatible(&v) => {
+                Ok(Self::Boolean(holds(a, v.convert(b, &u)?)))
+            }
+            (Self::Boolean(a), Self::Boolean(b)) if equality => {
+                Ok(Self::Boolean((a == b) == (relation == Relation::Equal)))
+            }
+            // 複素数には大小がないため、等しいかどうかだけ比べる
+            (a, b) if equality && a.complex_parts().is_some() && b.complex_parts().is_some() => {
+                let ((a_re, a_im), (b_re, b_im)) = (
+                    a.complex_parts().unwrap_or_default(),
+                    b.complex_parts().unwrap_or_default(),
+                );
+                let equal = constraints::check("", a_re, Relation::Equal, b_re).holds
+                    && constraints::check("", a_im, Relation::Equal, b_im).holds;
+                Ok(Self::Boolean(equal == (relation == Relation::Equal)))
+            }
+            (a, b) => Err(format!(
+                "{}と{}は {} で比較できません",
+                a.describe(),
+                b.describe(),
+                relation.symbol()
+            )),
+        }
+    }
+}
+
+pub fn absolute_scaling(unit: &Unit) -> String {
+    format!(
+        "温度 {} は数値倍できません（温度の差は {} で表してください）",
+        unit.name,
+        unit.difference().name
+    )
+}
+
+const BOOLEAN_ARITHMETIC: &str = "真偽値（比較の結果）は計算に使えません";
+// 2^53。整数のリテラルとして扱う値と、整数乗を2乗の繰り返しで計算する指数の絶対値の上限
+// （ここまでは f64 で整数を正確に表せる）
+const MAX_INTEGER_EXPONENT: f64 = 9_007_199_254_740_992.0;
+
+// 同じ次元の量の + -。結果は左辺の単位で表す。温度の目盛り（degC など）どうしの差は温度差、
+// 温度と温度差の和・差は温度で、温度どうしの和と温度差から温度を引く計算はできない
+fn temperature_additive(
+    a: f64,
+    u: Unit,
+    b: f64,
+    v: Unit,
+    operator: char,
+    f: impl Fn(f64, f64) -> f64,
+) -> Result<Value, String> {
+    match (u.is_absolute(), v.is_absolute()) {
+        (true, true) if operator == '-' => {
+            Ok(Value::Quantity(a - v.convert(b, &u)?, u.difference()))
+        }
+        (true, true) => Err(format!(
+            "温度どうしは足せません（{} + {}。温度差は {} で表してください）",
+            u.name,
+            v.name,
+            u.difference().name
+        )),
+        (true, false) => {
+            let difference = u.difference();
+            Ok(Value::Quantity(f(a, v.convert(b, &difference)?), u))
+        }
+        (false, true) if operator == '+' => {
+            let difference = v.difference();
+            Ok(Value::Quantity(u.convert(a, &difference)? + b, v))
+        }
+        (false, true) => Err(format!(
+            "温度差 {} から温度 {} は引けません",
+            u.name, v.name
+        )),
+        (false, false) => Ok(Value::Quantity(f(a, v.convert(b, &u)?), u)),
+    }
+}
+
+fn mismatch(a: &Value, b: &Value, verb: &str) -> String {
+    if matches!(a, Value::Boolean(_)) || matches!(b, Value::Boolean(_)) {
+        return BOOLEAN_ARITHMETIC.to_string();
+    }
+    format!(
+        "{}と{}は{}ことができません（型か形が合いません）",
+        a.describe(),
+        b.describe(),
+        verb
+    )
+}
+
+// 2つの数値の演算。どちらかが数値でなければ None
+fn numeric(operator: char, a: &Value, b: &Value) -> Option<Result<Value, String>> {
+    if let (Some(a), Some(b)) = (a.exact(), b.exact()) {
+        match exact_operation(operator, a, b) {
+            Ok(Some(value)) => return Some(Ok(value)),
+            Err(e) => return Some(Err(e)),
+            // i64 に収まらないか、整数でない指数のべき乗は浮動小数点数で計算する
+            Ok(None) => {}
+        }
+    }
+    if let (Some(a), Some(b)) = (a.real(), b.real()) {
+        return Some(real_operation(operator, a, b).map(Value::Scalar));
+    }
+    let (a, b) = (a.complex_parts()?, b.complex_parts()?);
+    Some(complex_operation(operator, a, b))
+}
+
+fn real_operation(operator: char, a: f64, b: f64) -> Result<f64, String> {
+    match operator {
+        '+' => Ok(a + b),
+        '-' => Ok(a - b),
+        '*' => Ok(a * b),
+        '/' if b == 0.0 => Err(division_by_zero()),
+        '/' => Ok(a / b),
+        _ => {
+            let value = a.powf(b);
+            if !value.is_finite() {
+                return Err(domain_error("べき乗の計算結果が無効です"));
+            }
+            Ok(value)
+        }
+    }
+}
+
+// 分数どうしの演算
+fn exact_operation(
+    operator: char,
+    (a, b): (i128, i128),
+    (c, d): (i128, i128),
+) -> Result<Option<Value>, String> {
+    let fraction = |numerator: Option<i128>, denominator: Option<i128>| {
+        numerator
+            .zip(denominator)
+            .and_then(|(numerator, denominator)| Value::rational(numerator, denominator))
+    };
+    Ok(match operator {
+        '+' | '-' => {
+            let right = if operator == '+' { c * b } else { -(c * b) };
+            fraction((a * d).checked_add(right), Some(b * d))
+        }
+        '*' => fraction(Some(a * c), Some(b * d)),
+        '/' if c == 0 => return Err(division_by_zero()),
+        '/' => fraction(Some(a * d), Some(b * c)),
+        // 0 の負のべき乗は浮動小数点数の計算でエラーにする
+        _ if d != 1 || c.unsigned_abs() > u128::from(u32::MAX) || (a == 0 && c < 0) => None,
+        _ => {
+            let exponent = c.unsigned_abs() as u32;
+            let (numerator, denominator) = (a.checked_pow(exponent), b.checked_pow(exponent));
+            if c < 0 {
+                fraction(denominator, numerator)
+            } else {
+                fraction(numerator, denominator)
+            }
+        }
+    })
+}
+
+fn complex_operation(
+    operator: char,
+    (a, b): (f64, f64),
+    (c, d): (f64, f64),
+) -> Result<Value, String> {
+    let (re, im) = match operator {
+        '+' => (a + c, b + d),
+        '-' => (a - c, b - d),
+        '*' => (a * c - b * d, a * d + b * c),
+        '/' => complex_divide((a, b), (c, d))?,
+        _ => {
+            if d != 0.0 || c.fract() != 0.0 || c.abs() > MAX_INTEGER_EXPONENT {
+                return Err(format!(
+                    "複素数のべき乗の指数は整数にしてください: {}",
+                    Value::complex(c, d).format(|x| x.to_string())
+                ));
+            }
+            let power = repeated_product((1.0, 0.0), (a, b), c.abs() as u64, |&(a, b), &(c, d)| {
+                (a * c - b * d, a * d + b * c)
+            });
+            if c < 0.0 {
+                complex_divide((1.0, 0.0), power)?
+            } else {
+                power
+            }
+        }
+    };
+    Ok(Value::complex(re, im))
+}
+
+fn complex_divide((a, b): (f64, f64), (c, d): (f64, f64)) -> Result<(f64, f64), String> {
+    let denominator = c * c + d * d;
+    if denominator == 0.0 {
+        return Err(division_by_zero());
+    }
+    Ok(((a * c + b * d) / denominator, (b * c - a * d) / denominator))
+}
+
+// base の exponent 乗を2乗の繰り返しで求める（one は単位元）
+fn repeated_product<T>(one: T, base: T, mut exponent: u64, multiply: impl Fn(&T, &T) -> T) -> T {
+    let mut result = one;
+    let mut square = base;
+    while exponent > 0 {
+        if exponent % 2 == 1 {
+            result = multiply(&result, &square);
+        }
+        exponent /= 2;
+        if exponent > 0 {
+            square = multiply(&square, &square);
+        }
+    }
+    result
+}
+
+fn gcd(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+fn cols(matrix: &Matrix) -> usize {
+    matrix.first().map_or(0, Vec::len)
+}
+
+fn check_product(
+    (left, right): &(String, String),
+    left_cols: usize,
+    right_rows: usize,
+) -> Result<(), String> {
+    if left_cols != right_rows {
+        return Err(format!(
+            "{}と{}の積は計算できません（左の列数 {} と右の行数 {} を同じにしてください）",
+            left, right, left_cols, right_rows
+        ));
+    }
+    Ok(())
+}
+
+fn invert(matrix: &Matrix) -> Result<Matrix, String> {
+    matrix::validate_square(matrix)?;
+    matrix::inverse(matrix)
+        .filter(|inverse| inverse.iter().flatten().all(|value| value.is_finite()))
+        .ok_or_else(|| "特異行列のため逆行列がありません".to_string())
+}
+
+fn square(name: &str, value: &Value) -> Result<Matrix, String> {
+    match value {
+        Value::Matrix(matrix) => {
+            matrix::validate_square(matrix)?;
+            Ok(matrix.clone())
+        }
+        other => Err(format!(
+            "{} の引数には正方行列を指定してください（{}が指定されています）",
+            name,
+            other.describe()
+        )),
+    }
+}
+
+fn list<'a>(name: &str, value: &'a Value) -> Result<&'a [f64], String> {
+    match value {
+        Value::List(list) => Ok(list),
+        other => Err(format!(
+            "{} の引数にはベクトルを指定してください（{}が指定されています）",
+            name,
+            other.describe()
+        )),
+    }
+}
+
+// 実数でない値も受け付ける数値の関数（abs, sqrt）。対応しない型なら None
+pub fn overload(name: &str, arg: &Value) -> Option<Result<Value, String>> {
+    match (name, arg) {
+        ("abs" | "sqrt", Value::Quantity(_, unit)) if unit.is_absolute() => Some(Err(format!(
+            "{} は温度 {} には使えません（温度の差は {} で表してください）",
+            name,
+            unit.name,
+            unit.difference().name
+        ))),
+        ("abs", Value::Complex(re, im)) => Some(Ok(Value::Scalar(re.hypot(*im)))),
+        ("abs", Value::Quantity(value, unit)) => {
+            Some(Ok(Value::Quantity(value.abs(), unit.clone())))
+        }
+        // 主値（実部が 0 以上）の平方根
+        ("sqrt", Value::Complex(re, im)) => {
+            let modulus = re.hypot(*im);
+            let real = ((modulus + re) / 2.0).sqrt();
+            let imaginary = ((modulus - re) / 2.0).sqrt().copysign(*im);
+            Some(Ok(Value::complex(real, imaginary)))
+        }
+        // 次元の指数がすべて偶数なら、基本単位で表した量の平方根（sqrt(9 m^2) は 3 m）
+        ("sqrt", Value::Quantity(value, unit)) => Some(if *value < 0.0 {
+            Err(domain_error(format!(
+                "負の量の平方根は実数になりません: sqrt({} {})",
+                value, unit.name
+            )))
+        } else if unit.dimension.iter().any(|exponent| exponent % 2 != 0) {
+            Err(format!(
+                "単位 {} の量の平方根は単位を表せません（次元 {}）",
+                unit.name,
+                units::dimension_text(&unit.dimension)
+            ))
+        } else {
+            Ok(Value::quantity(
+                (value * unit.factor).sqrt(),
+                Unit::base(unit.dimension.map(|exponent| exponent / 2)),
+            ))
+        }),
+        _ => None,
+    }
+}
+
+// FUNCTIONS の関数を呼び出す
+pub fn call(name: &str, args: &[Value]) -> Result<Value, String> {
+    let expected = if matches!(name, "dot" | "cross") {
+        2
+    } else {
+        1
+    };
+    if args.len() != expected {
+        return Err(format!(
+            "{} の引数は {} 個です（{} 個指定されています）",
+            name,
+            expected,
+            args.len()
+        ));
+    }
+    match name {
+        "det" => Ok(Value::Scalar(
+            matrix::lu(&square(name, &args[0])?)?.determinant,
+        )),
+        "inv" => Ok(Value::Matrix(invert(&square(name, &args[0])?)?)),
+        "trace" => {
+            let matrix = square(name, &args[0])?;
+            Ok(Value::Scalar((0..matrix.len()).map(|i| matrix[i][i]).sum()))
+        }
+        "transpose" => match &args[0] {
+            Value::Matrix(matrix) => Ok(Value::Matrix(matrix::transpose(matrix))),
+            other => Err(format!(
+                "transpose の引数には行列を指定してください（{}が指定されています）",
+                other.describe()
+            )),
+        },
+        "dot" => {
+            let (a, b) = (list(name, &args[0])?, list(name, &args[1])?);
+            if a.len() != b.len() {
+                return Err(format!(
+                    "dot のベクトルの長さが違います: {} と {}",
+                    a.len(),
+                    b.len()
+                ));
+            }
+            Ok(Value::Scalar(a.iter().zip(b).map(|(x, y)| x * y).sum()))
+        }
+        "cross" => {
+            let (a, b) = (list(name, &args[0])?, list(name, &args[1])?);
+            if a.len() != 3 || b.len() != 3 {
+                return Err(format!(
+                    "cross の引数は長さ 3 のベクトルにしてください: {} と {}",
+                    a.len(),
+                    b.len()
+                ));
+            }
+            Ok(Value::List(vec![
+                a[1] * b[2] - a[2] * b[1],
+                a[2] * b[0] - a[0] * b[2],
+                a[0] * b[1] - a[1] * b[0],
+            ]))
+        }
+        // ベクトルはユークリッドノルム、行列はフロベニウスノルム
+        "norm" => {
+            let values: Vec<f64> = match &args[0] {
+                Value::List(list) => list.clone(),
+                Value::Matrix(matrix) => matrix.iter().flatten().copied().collect(),
+                other => {
+                    return Err(format!(
+                        "norm の引数にはベクトルか行列を指定してください（{}が指定されています）",
+                        other.describe()
+                    ))
+                }
+            };
+            Ok(Value::Scalar(
+                values.iter().map(|x| x * x).sum::<f64>().sqrt(),
+            ))
+        }
+        _ => Err(format!("未知の関数: {}", name)),
+    }
+}
@@ -0,0 +1,412 @@
+#[cfg(test)]
+mod tests {
+    use crate::calculator::Calculator;
+    use crate::value::Value;
+
+    #[test]
+    fn test_array_literals() {
+        let calculator = Calculator::new();
+        assert_eq!(
+            calculator.evaluate_value("[[1, 2], [3, 4]] * [5, 6]"),
+            Ok(Value::List(vec![17.0, 39.0]))
+        );
+        assert_eq!(
+            calculator.evaluate_value("[1, 2] + [3, 4] * 2"),
+            Ok(Value::List(vec![7.0, 10.0]))
+        );
+        assert_eq!(
+            calculator.evaluate_value("[[1, 2], [3, 4]] * [[0, 1], [1, 0]]"),
+            Ok(Value::Matrix(vec![vec![2.0, 1.0], vec![4.0, 3.0]]))
+        );
+        assert_eq!(
+            calculator.evaluate_value("[1, 2] * [[1, 0], [0, 2]]"),
+            Ok(Value::List(vec![1.0, 4.0]))
+        );
+        assert_eq!(
+            calculator.evaluate_value("[[1, 1], [1, 0]] ^ 5"),
+            Ok(Value::Matrix(vec![vec![8.0, 5.0], vec![5.0, 3.0]]))
+        );
+        assert_eq!(
+            calculator.evaluate_value("[[2, 0], [0, 4]] ^ -1"),
+            Ok(Value::Matrix(vec![vec![0.5, 0.0], vec![0.0, 0.25]]))
+        );
+        assert_eq!(
+            calculator.evaluate_value("[sqrt(4), 2 ^ 3, -1]"),
+            Ok(Value::List(vec![2.0, 8.0, -1.0]))
+        );
+        // 数値だけの式は evaluate と同じ値になる（整数どうしの計算は整数）
+        assert_eq!(
+            calculator.evaluate_value("2 + 3 * 4"),
+            Ok(Value::Integer(14))
+        );
+        assert_eq!(calculator.evaluate("2 + 3 * 4"), Ok(14.0));
+    }
+
+    #[test]
+    fn test_array_functions() {
+        let calculator = Calculator::new();
+        let scalar = |expression: &str| calculator.evaluate_value(expression);
+        assert_eq!(scalar("det([[1, 2], [3, 4]])"), Ok(Value::Scalar(-2.0)));
+        assert_eq!(scalar("trace([[1, 2], [3, 4]])"), Ok(Value::Scalar(5.0)));
+        assert_eq!(scalar("dot([1, 2, 3], [4, 5, 6])"), Ok(Value::Scalar(32.0)));
+        assert_eq!(scalar("norm([3, 4])"), Ok(Value::Scalar(5.0)));
+        assert_eq!(
+            scalar("cross([1, 0, 0], [0, 1, 0])"),
+            Ok(Value::List(vec![0.0, 0.0, 1.0]))
+        );
+        assert_eq!(
+            scalar("transpose([[1, 2, 3]])"),
+            Ok(Value::Matrix(vec![vec![1.0], vec![2.0], vec![3.0]]))
+        );
+        assert_eq!(
+            scalar("inv([[1, 1], [0, 1]]) * [3, 5]"),
+            Ok(Value::List(vec![-2.0, 5.0]))
+        );
+        assert!(scalar("inv([[1, 2], [2, 4]])").is_err());
+        // 行列の関数の名前は変数名にも使える
+        let mut calculator = Calculator::new();
+        calculator.set_variable("norm", 2.0).unwrap();
+        assert_eq!(calculator.evaluate("norm * 3"), Ok(6.0));
+    }
+
+    #[test]
+    fn test_array_shape_errors() {
+        let calculator = Calculator::new();
+        let error = |expression: &str| calculator.evaluate_value(expression).unwrap_err();
+        assert!(error("[[1, 2], [3]]").contains("行の長さがそろっていません"));
+        assert!(error("[[1, 2, 3]] * [[1, 2, 3]]").contains("積は計算できません"));
+        assert!(error("[1, 2] + [1, 2, 3]").contains("足すことができません"));
+        assert!(error("[1, 2] * [3, 4]").contains("dot"));
+        assert!(error("1 / [1, 2]").contains("割ることはできません"));
+        assert!(error("sqrt([4, 9])").contains("sqrt の引数には数値を指定してください"));
+        assert!(error("[[1, 2]] ^ 2").contains("正方行列"));
+        assert!(error("[]").contains("空の []"));
+        assert!(error("[1, [2]]").contains("混ぜる"));
+        assert!(error("[1, 2").contains("]"));
+        // 数値の計算しかできない evaluate では使えない
+        assert!(calculator.evaluate("[1, 2]").is_err());
+        assert!(calculator.compile("[1, 2]").is_err());
+    }
+
+    #[test]
+    fn test_exact_numbers() {
+        let calculator = Calculator::new();
+        let value = |expression: &str| calculator.evaluate_value(expression);
+        assert_eq!(value("1 / 3 * 3"), Ok(Value::Integer(1)));
+        assert_eq!(value("1 / 3 + 1 / 6"), Ok(Value::Rational(1, 2)));
+        assert_eq!(value("(2 / 3) ^ -2"), Ok(Value::Rational(9, 4)));
+        assert_eq!(value("-6 / 4"), Ok(Value::Rational(-3, 2)));
+        // 小数を含むと浮動小数点数で計算する
+        assert_eq!(value("0.5 + 1 / 2"), Ok(Value::Scalar(1.0)));
+        assert_eq!(value("2 ^ 0.5"), Ok(Value::Scalar(2.0_f64.sqrt())));
+        // i64 に収まらない場合も浮動小数点数で計算する
+        assert_eq!(
+            value("4294967296 * 4294967296 * 4"),
+            Ok(Value::Scalar(73786976294838206464.0))
+        );
+        assert_eq!(calculator.evaluate("1 / 3"), Ok(1.0 / 3.0));
+        assert_eq!(
+            value("1 / 0"),
+            Err("DIVISION_BY_ZERO: ゼロ除算エラー".to_string())
+        );
+    }
+
+    #[test]
+    fn test_complex_numbers() {
+        let mut calculator = Calculator::new();
+        let value =
+            |calculator: &Calculator, expression: &str| calculator.evaluate_value(expression);
+        assert_eq!(value(&calculator, "i * i"), Ok(Value::Scalar(-1.0)));
+        assert_eq!(
+            value(&calculator, "(1 + 2i) * (3 - i)"),
+            Ok(Value::Complex(5.0, 5.0))
+        );
+        assert_eq!(
+            value(&calculator, "1 / (1 + i)"),
+            Ok(Value::Complex(0.5, -0.5))
+        );
+        assert_eq!(
+            value(&calculator, "(1 + i) ^ 2"),
+            Ok(Value::Complex(0.0, 2.0))
+        );
+        assert!(value(&calculator, "i ^ 0.5")
+            .unwrap_err()
+            .contains("指数は整数"));
+        assert!(calculator
+            .evaluate("2 + i")
+            .unwrap_err()
+            .contains("実数ではありません（複素数）"));
+        // 同じ名前の変数があればそちらを使う
+        calculator.set_variable("i", 3.0).unwrap();
+        assert_eq!(value(&calculator, "2i"), Ok(Value::Scalar(6.0)));
+        assert_eq!(
+            Value::Complex(1.0, -2.0).format(|x| x.to_string()),
+            "1 - 2i"
+        );
+    }
+
+    #[cfg(feature = "units")]
+    #[test]
+    fn test_quantities() {
+        let calculator = Calculator::new();
+        let value = |expression: &str| calculator.evaluate_value(expression).unwrap();
+        let format = |expression: &str| value(expression).format(|x| x.to_string());
+        assert_eq!(format("5 km + 300 m"), "5.3 km");
+        assert_eq!(format("2 * 3 kg"), "6 kg");
+        assert_eq!(format("90 min / 2"), "45 min");
+        assert_eq!(format("1 h - 30 min"), "0.5 h");
+        assert_eq!(value("5 km").describe(), "単位 km の量");
+        // 関数と同じ名前の単位は ( が続かなければ単位
+        assert_eq!(format("min(5, 3) min"), "3 min");
+    }
+
+    #[cfg(feature = "units")]
+    #[test]
+    fn test_quantity_products() {
+        let calculator = Calculator::new();
+        let value = |expression: &str| calculator.evaluate_value(expression).unwrap();
+        let format = |expression: &str| value(expression).format(|x| x.to_string());
+        assert_eq!(format("3 m * 4 m"), "12 m^2");
+        assert_eq!(format("100 km / (2 h)"), "50 km/h");
+        assert_eq!(format("2 N * 3 m"), "6 N·m");
+        assert_eq!(format("(2 m) ^ 3"), "8 m^3");
+        assert_eq!(format("10 / (4 s)"), "2.5 1/s");
+        // 無次元になれば数値
+        assert_eq!(value("1 km / m"), Value::Scalar(1000.0));
+        // 同じ次元なら組み立てた単位の量どうしも足せる
+        assert_eq!(format("1 km/h + 0 m/s"), "1 km/h");
+        assert!(value("1 N * 1 m == 1 J") == Value::Boolean(true));
+        assert!(calculator
+            .evaluate_value("1 m / 0 s")
+            .unwrap_err()
+            .contains("ゼロ除算"));
+    }
+
+    #[cfg(feature = "units")]
+    #[test]
+    fn test_unit_conversion() {
+        let mut calculator = Calculator::new();
+        let value = |calculator: &Calculator, expression: &str| {
+            calculator.evaluate_value(expression).unwrap()
+        };
+        let format = |calculator: &Calculator, expression: &str| {
+            value(calculator, expression).format(|x| x.to_string())
+        };
+        assert_eq!(format(&calculator, "90 min in h"), "1.5 h");
+        assert_eq!(format(&calculator, "1 ha in m^2"), "10000 m^2");
+        assert_eq!(format(&calculator, "2 kWh in kJ"), "7200 kJ");
+        let Value::Quantity(miles, unit) = value(&calculator, "5 km in mi") else {
+            panic!("量になりません");
+        };
+        assert!((miles - 3.106_855_961).abs() < 1e-9);
+        assert_eq!(unit.name, "mi");
+        let Value::Quantity(speed, unit) = value(&calculator, "36 km/h in m/s") else {
+            panic!("量になりません");
+        };
+        assert!((speed - 10.0).abs() < 1e-12);
+        assert_eq!(unit.name, "m/s");
+        // 換算は + - より弱く、比較より強く結合する
+        assert_eq!(format(&calculator, "1 h + 30 min in min"), "90 min");
+        assert_eq!(
+            value(&calculator, "1 mi in km > 1.6 km"),
+            Value::Boolean(true)
+        );
+        assert!(calculator
+            .evaluate_value("5 km in s")
+            .unwrap_err()
+            .contains("次元が違う"));
+        assert!(calculator
+            .evaluate_value("5 in mi")
+            .unwrap_err()
+            .contains("整数は mi に換算できません"));
+        assert!(calculator
+            .evaluate_value("5 km in parsec")
+            .unwrap_err()
+            .contains("未知の単位: parsec"));
+        assert!(calculator.compile("5 km in mi").is_err());
+        // 同じ名前の変数があればそちらを使う
+        calculator.set_variable("in", 2.0).unwrap();
+        assert_eq!(calculator.evaluate("3 in"), Ok(6.0));
+    }
+
+    #[cfg(feature = "units")]
+    #[test]
+    fn test_temperatures() {
+        let calculator = Calculator::new();
+        let format = |expression: &str| {
+            let value = calculator.evaluate_value(expression).unwrap();
+            value
+                .map(|x| (x * 1e9).round() / 1e9)
+                .format(|x| x.to_string())
+        };
+        let error = |expression: &str| calculator.evaluate_value(expression).unwrap_err();
+        // 丸めずに比べても 68 degF になる
+        assert_eq!(
+            calculator
+                .evaluate_value("20 degC in degF")
+                .unwrap()
+                .format(|x| x.to_string()),
+            "68 degF"
+        );
+        assert_eq!(format("72 F in C"), "22.222222222 C");
+        assert_eq!(format("-40 degC in degF"), "-40 degF");
+        assert_eq!(format("20 degC in K"), "293.15 K");
+        // 温度どうしの差は温度差、温度に温度差を足すと温度
+        assert_eq!(format("30 degC - 10 degC"), "20 deltaC");
+        assert_eq!(format("86 degF - 20 degC"), "18 deltaF");
+        assert_eq!(format("20 degC + 9 deltaF"), "25 degC");
+        assert_eq!(format("10 deltaC + 20 degC"), "30 degC");
+        assert_eq!(format("20 degC + 5 K"), "25 degC");
+        assert_eq!(format("20 deltaC in deltaF"), "36 deltaF");
+        assert_eq!(format("2 * 20 deltaC"), "40 deltaC");
+        assert!(error("20 deltaC in degF").contains("温度と温度差は換算できません"));
+        assert!(error("20 degC + 10 degC").contains("温度どうしは足せません"));
+        assert!(error("10 deltaC - 20 degC").contains("引けません"));
+        assert!(error("2 * (20 degC)").contains("数値倍できません"));
+        assert!(error("2 * 20 degC").contains("数値倍できません"));
+        assert!(error("20 degC / 2").contains("数値倍できません"));
+        assert!(error("1 W / degC").contains("掛け算・割り算"));
+        assert_eq!(
+            calculator.evaluate_value("20 degC == 68 degF"),
+            Ok(Value::Boolean(true))
+        );
+    }
+
+    #[cfg(feature = "units")]
+    #[test]
+    fn test_data_sizes() {
+        let calculator = Calculator::new();
+        let quantity = |expression: &str| match calculator.evaluate_value(expression) {
+            Ok(Value::Quantity(value, unit)) => (value, unit.name),
+            other => panic!("量になりません: {:?}", other),
+        };
+        // 4.7 GB を 35 Mbps で転送する時間
+        let (minutes, unit) = quantity("4.7 GB / (35 Mbps) in min");
+        assert!((minutes - 4.7 * 8000.0 / 35.0 / 60.0).abs() < 1e-9);
+        assert_eq!(unit, "min");
+        assert_eq!(quantity("1 GiB in MB"), (1073.741824, "MB".to_string()));
+        assert_eq!(quantity("1 MiB in KiB"), (1024.0, "KiB".to_string()));
+        assert_eq!(quantity("100 Mbps in MB/s"), (12.5, "MB/s".to_string()));
+        assert_eq!(
+            calculator.evaluate_value("1 KiB / KB"),
+            Ok(Value::Scalar(1.024))
+        );
+        // 1000 倍と 1024 倍のどちらの意味でも使われる単位は、使った意味を返す
+        let notes = calculator.unit_notes("4.7 GB / (35 Mbps) + 1 GB / (35 Mbps)");
+        assert_eq!(notes.len(), 1);
+        assert!(notes[0].contains("GiB"));
+        assert!(calculator.unit_notes("1 GiB in MiB").is_empty());
+    }
+
+    #[cfg(feature = "units")]
+    #[test]
+    fn test_fuel_economy() {
+        let calculator = Calculator::new();
+        let convert = |expression: &str| match calculator.evaluate_value(expression) {
+            Ok(Value::Quantity(value, _)) => value,
+            other => panic!("量になりません: {:?}", other),
+        };
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-6;
+        // 燃料消費率と燃費は逆数の関係で換算する
+        assert!(close(convert("6 L/100km in mpg"), 39.202_430_6));
+        assert!(close(convert("30 mpg in L/100km"), 7.840_486_1));
+        assert!(close(convert("30 mpg in mpgUK"), 36.028_497_8));
+        assert!(close(convert("20 km/L in L/100km"), 5.0));
+        assert!(close(convert("1 galUK in gal"), 1.200_949_9));
+        assert!(calculator
+            .evaluate_value("0 L/100km in mpg")
+            .unwrap_err()
+            .contains("逆数"));
+        // 逆数の換算は燃料消費率と燃費だけ
+        assert!(calculator.evaluate_value("2 s in Hz").is_err());
+    }
+
+    #[test]
+    fn test_overloaded_functions() {
+        let mut calculator = Calculator::new();
+        assert_eq!(
+            calculator.evaluate_value("abs(3 + 4i)"),
+            Ok(Value::Scalar(5.0))
+        );
+        assert_eq!(
+            calculator.evaluate_value("|-3 - 4i|"),
+            Ok(Value::Scalar(5.0))
+        );
+        assert_eq!(
+            calculator.evaluate_value("sqrt(-3 - 4i)"),
+            Ok(Value::Complex(1.0, -2.0))
+        );
+        #[cfg(feature = "units")]
+        assert_eq!(
+            calculator
+                .evaluate_value("sqrt(9 m * 4 m)")
+                .unwrap()
+                .format(|x| x.to_string()),
+            "6 m"
+        );
+        #[cfg(feature = "units")]
+        assert!(calculator
+            .evaluate_value("sqrt(2 m)")
+            .unwrap_err()
+            .contains("単位を表せません"));
+        #[cfg(feature = "units")]
+        assert!(calculator
+            .evaluate_value("exp(1 m)")
+            .unwrap_err()
+            .contains("単位 m の量が指定されています"));
+        // ベクトル・行列は elementwise が有効な場合だけ要素ごとに計算する
+        assert!(calculator
+            .evaluate_value("sqrt([[4, 9], [16, 25]])")
+            .unwrap_err()
+            .contains("elementwise"));
+        calculator.set_elementwise(true);
+        assert_eq!(
+            calculator.evaluate_value("sqrt([[4, 9], [16, 25]])"),
+            Ok(Value::Matrix(vec![vec![2.0, 3.0], vec![4.0, 5.0]]))
+        );
+        assert_eq!(
+            calculator.evaluate_value("√[4, 9] + abs([-1, 1])"),
+            Ok(Value::List(vec![3.0, 4.0]))
+        );
+        assert!(calculator.evaluate_value("ln([1, 0])").is_err());
+    }
+
+    #[test]
+    fn test_comparisons() {
+        let calculator = Calculator::new();
+        let value = |expression: &str| calculator.evaluate_value(expression);
+        assert_eq!(value("1 < 2"), Ok(Value::Boolean(true)));
+        assert_eq!(value("2 + 2 != 4"), Ok(Value::Boolean(false)));
+        assert_eq!(value("0.1 + 0.2 == 0.3"), Ok(Value::Boolean(true)));
+        #[cfg(feature = "units")]
+        assert_eq!(value("1 km > 999 m"), Ok(Value::Boolean(true)));
+        assert_eq!(value("(1 ≤ 2) == (3 ≥ 4)"), Ok(Value::Boolean(false)));
+        assert!(value("0 < 1 < 2").unwrap_err().contains("比較演算子は1つ"));
+        assert!(value("1 = 1").unwrap_err().contains("=="));
+        assert!(calculator
+            .evaluate("1 < 2")
+            .unwrap_err()
+            .contains("実数ではありません（真偽値）"));
+    }
+
+    #[test]
+    fn test_type_mismatch_errors() {
+        let calculator = Calculator::new();
+        let error = |expression: &str| calculator.evaluate_value(expression).unwrap_err();
+        #[cfg(feature = "units")]
+        assert_eq!(
+            error("5 km + 3 s"),
+            "単位 km の量と単位 s の量は足すことができません（型か形が合いません）"
+        );
+        #[cfg(feature = "units")]
+        assert!(error("5 km + 3").contains("単位 km の量と整数は足すことができません"));
+        assert!(error("(1 < 2) + 1").contains("真偽値"));
+        assert!(error("-(1 < 2)").contains("真偽値"));
+        assert!(error("i < 1").contains("複素数"));
+        #[cfg(feature = "units")]
+        assert!(error("[1, 2] * 1 km").contains("ベクトル"));
+        #[cfg(feature = "units")]
+        assert!(error("ln(4 m)")
+            .contains("ln の引数には数値を指定してください（単位 m の量が指定されています）"));
+    }
+}
@@ -0,0 +1,28 @@
+use crate::calculator::Calculator;
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+// 成功時は result、失敗時は error のみを持つ
+#[derive(Serialize)]
+struct Evaluation {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+// JavaScript から呼び出す評価関数: evaluate("2 + 3") => { result: 5 }
+#[wasm_bindgen]
+pub fn evaluate(expression: &str) -> JsValue {
+    let evaluation = match Calculator::new().evaluate(expression) {
+        Ok(value) => Evaluation {
+            result: Some(value),
+            error: None,
+        },
+        Err(e) => Evaluation {
+            result: None,
+            error: Some(e),
+        },
+    };
+    serde_wasm_bindgen::to_value(&evaluation).unwrap_or(JsValue::NULL)
+}
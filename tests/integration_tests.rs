@@ -128,11 +128,13 @@ async fn test_mcp_server_tools_list() -> Result<()> {
             assert!(response["result"]["tools"].is_array());
             
             let tools = response["result"]["tools"].as_array().unwrap();
-            assert_eq!(tools.len(), 1);
-            
-            let calculate_tool = &tools[0];
-            assert_eq!(calculate_tool["name"], "calculate");
+            let find_tool = |name: &str| tools.iter().find(|tool| tool["name"] == name);
+
+            let calculate_tool = find_tool("calculate").expect("calculate ツールがありません");
             assert!(calculate_tool["description"].as_str().unwrap().contains("セキュアな数式計算"));
+            assert!(find_tool("health").is_some());
+            // 管理用ツールは既定では公開されない
+            assert!(find_tool("set_tool_enabled").is_none());
         }
         Err(_) => {
             panic!("タイムアウト: ツールリストレスポンスが得られませんでした");
@@ -233,4 +235,28 @@ async fn test_mcp_server_calculate_tool() -> Result<()> {
     // プロセスを終了
     child.kill().await?;
     Ok(())
-}
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn test_cli_eval() -> Result<()> {
+    let output = Command::new("cargo")
+        .args(&["run", "--quiet", "--bin", "calc-mcp", "--", "eval", "2 + 3 * 4"])
+        .output()
+        .await?;
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout)?, "14\n");
+
+    // 計算エラーは終了コード 1 と構造化エラー（JSON）で報告される
+    let output = Command::new("cargo")
+        .args(&["run", "--quiet", "--bin", "calc-mcp", "--", "eval", "1 / 0"])
+        .output()
+        .await?;
+
+    assert_eq!(output.status.code(), Some(1));
+    let error: Value = serde_json::from_slice(&output.stderr)?;
+    assert!(error["error"].as_str().unwrap().contains("ゼロ除算"));
+    assert_eq!(error["expression"], "1 / 0");
+
+    Ok(())
+}